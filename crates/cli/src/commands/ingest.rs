@@ -52,6 +52,7 @@ pub async fn run(limit: Option<u64>, source: &str) -> anyhow::Result<()> {
         info!("   ✅ Success: {}", result.successful);
         info!("   ❌ Failed: {}", result.failed);
         info!("   🔄 Duplicates: {}", result.duplicates);
+        info!("   🔗 Linked to existing: {}", result.linked);
         info!("   ⚠️  Validation errors: {}", result.validation_errors);
         info!("   ⏱️  Time: {:.2}s", result.elapsed_seconds);
         info!("   📈 Rate: {:.0} records/s\n",
@@ -77,6 +78,7 @@ pub async fn run(limit: Option<u64>, source: &str) -> anyhow::Result<()> {
         info!("   ✅ Success: {}", result.successful);
         info!("   ❌ Failed: {}", result.failed);
         info!("   🔄 Duplicates: {}", result.duplicates);
+        info!("   🔗 Linked to existing: {}", result.linked);
         info!("   ⚠️  Validation errors: {}", result.validation_errors);
         info!("   ⏱️  Time: {:.2}s", result.elapsed_seconds);
         info!("   📈 Rate: {:.0} records/s\n",
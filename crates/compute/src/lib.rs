@@ -10,6 +10,7 @@
 pub mod ml_engine;
 pub mod md_engine;
 pub mod dft_bridge;
+pub mod oracle;
 pub mod properties;
 pub mod error;
 
@@ -736,7 +736,6 @@ mod tests {
 
     #[test]
     fn test_md_engine() {
-        let engine = MDEngine::new();
         let atoms = vec![
             Atom::new(0, "Ar".to_string(), Vec3::new(0.0, 0.0, 0.0)),
             Atom::new(1, "Ar".to_string(), Vec3::new(3.5, 0.0, 0.0)),
@@ -747,7 +746,7 @@ mod tests {
         config.output_freq = 5;
 
         let state = MDState::new(atoms, config.clone());
-        let result = engine.with_config(config).run_simulation(state);
+        let result = MDEngine::with_config(config).run_simulation(state);
 
         assert!(result.is_ok());
         let trajectory = result.unwrap();
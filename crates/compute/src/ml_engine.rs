@@ -6,16 +6,22 @@
 //! - Elastic moduli
 //! - Magnetic properties
 //!
-//! Models can be loaded from:
-//! - Candle safetensors format
-//! - ONNX Runtime
-//! - PyTorch checkpoints (via tch-rs)
+//! Models are feed-forward networks trained in-process with Adam
+//! (see [`MLModel::fit`]) and evaluated with k-fold cross-validation.
+//! Trained models are saved as JSON (weights, normalization and the
+//! trained flag) and loaded back with [`MLModel::load`].
 
 use crate::{ComputationMethod, Error, Result};
+use materials_core::elements::{self, Element};
+use materials_core::featurizer::MagpieFeaturizer;
 use materials_core::Material;
 use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use tracing::{debug, info, warn};
 
@@ -26,7 +32,7 @@ use tracing::{debug, info, warn};
 /// Material feature vector for ML models
 #[derive(Debug, Clone)]
 pub struct MaterialFeatures {
-    /// Composition features (element fractions, stoichiometry)
+    /// Composition features (Magpie weighted elemental statistics)
     pub composition: Vec<f64>,
 
     /// Structural features (volume, density, packing)
@@ -40,6 +46,9 @@ pub struct MaterialFeatures {
 }
 
 impl MaterialFeatures {
+    /// Length of the combined feature vector
+    pub const DIM: usize = MagpieFeaturizer::NUM_FEATURES + 10 + 3;
+
     /// Extract features from material
     pub fn from_material(material: &Material) -> Self {
         let composition = Self::extract_composition_features(material);
@@ -61,35 +70,20 @@ impl MaterialFeatures {
     }
 
     fn extract_composition_features(material: &Material) -> Vec<f64> {
-        let mut features = vec![0.0; 100]; // 100-dim composition vector
-
-        // Get element counts
-        let elements = material.elements();
-
-        // Simple encoding: element occurrence and fraction
-        for (i, element) in elements.iter().enumerate().take(10) {
-            // Atomic number (normalized)
-            let atomic_num = Self::atomic_number(element) as f64 / 118.0;
-            features[i * 10] = atomic_num;
-
-            // Element fraction
-            features[i * 10 + 1] = 1.0 / elements.len() as f64;
-
-            // Electronegativity (Pauling scale, normalized)
-            features[i * 10 + 2] = Self::electronegativity(element) / 4.0;
-
-            // Atomic radius (normalized)
-            features[i * 10 + 3] = Self::atomic_radius(element) / 300.0;
-        }
-
-        features
+        // Magpie-style weighted elemental statistics shared with materials-core
+        MagpieFeaturizer::new()
+            .featurize_material(material)
+            .unwrap_or_else(|e| {
+                warn!("Composition featurization failed for {}: {}", material.formula, e);
+                vec![0.0; MagpieFeaturizer::NUM_FEATURES]
+            })
     }
 
     fn extract_structural_features(material: &Material) -> Vec<f64> {
         let mut features = Vec::new();
 
         // Number of atoms (normalized)
-        features.push((material.num_atoms() as f64).ln() / 10.0);
+        features.push((material.num_atoms().max(1) as f64).ln() / 10.0);
 
         // Average atomic mass
         let avg_mass = material.average_atomic_mass();
@@ -114,199 +108,794 @@ impl MaterialFeatures {
     fn extract_electronic_features(material: &Material) -> Vec<f64> {
         let mut features = Vec::new();
 
-        let elements = material.elements();
+        let known: Vec<&Element> = material
+            .structure
+            .sites
+            .iter()
+            .filter_map(|site| elements::element(&site.element))
+            .collect();
 
         // Total valence electrons
-        let total_valence: f64 = elements
+        let total_valence: f64 = known
             .iter()
-            .map(|e| Self::valence_electrons(e) as f64)
+            .map(|e| e.valence_shells().total() as f64)
             .sum();
         features.push(total_valence / 100.0); // Normalized
 
-        // Average electronegativity
-        let avg_en: f64 = elements
-            .iter()
-            .map(|e| Self::electronegativity(e))
-            .sum::<f64>()
-            / elements.len() as f64;
-        features.push(avg_en / 4.0);
-
-        // Electronegativity difference (max - min)
-        let en_values: Vec<f64> = elements.iter().map(|e| Self::electronegativity(e)).collect();
-        let en_diff = en_values.iter().cloned().fold(f64::NEG_INFINITY, f64::max)
-            - en_values.iter().cloned().fold(f64::INFINITY, f64::min);
-        features.push(en_diff / 4.0);
+        // Average electronegativity and spread (max - min)
+        let en_values: Vec<f64> = known.iter().filter_map(|e| e.electronegativity).collect();
+        if en_values.is_empty() {
+            features.extend([0.0, 0.0]);
+        } else {
+            let avg_en = en_values.iter().sum::<f64>() / en_values.len() as f64;
+            let en_diff = en_values.iter().cloned().fold(f64::NEG_INFINITY, f64::max)
+                - en_values.iter().cloned().fold(f64::INFINITY, f64::min);
+            features.push(avg_en / 4.0);
+            features.push(en_diff / 4.0);
+        }
 
         features
     }
+}
+
+// ============================================================================
+// ML MODEL
+// ============================================================================
+
+/// Hyperparameters for building and training an [`MLModel`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MLModelConfig {
+    /// Width of each hidden layer (depth = number of entries)
+    pub hidden_layers: Vec<usize>,
+
+    /// Adam learning rate
+    pub learning_rate: f64,
+
+    /// Adam first-moment decay
+    pub beta1: f64,
+
+    /// Adam second-moment decay
+    pub beta2: f64,
+
+    /// Adam numerical stability term
+    pub epsilon: f64,
+
+    /// L2 penalty applied to weights (not biases)
+    pub weight_decay: f64,
 
-    // Atomic property lookup tables (simplified)
+    /// Probability of dropping a hidden unit during training
+    pub dropout: f64,
 
-    fn atomic_number(element: &str) -> u8 {
-        match element {
-            "H" => 1, "He" => 2, "Li" => 3, "Be" => 4, "B" => 5,
-            "C" => 6, "N" => 7, "O" => 8, "F" => 9, "Ne" => 10,
-            "Na" => 11, "Mg" => 12, "Al" => 13, "Si" => 14, "P" => 15,
-            "S" => 16, "Cl" => 17, "Ar" => 18, "K" => 19, "Ca" => 20,
-            "Ti" => 22, "V" => 23, "Cr" => 24, "Mn" => 25, "Fe" => 26,
-            "Co" => 27, "Ni" => 28, "Cu" => 29, "Zn" => 30, "Ga" => 31,
-            "Sr" => 38, "Y" => 39, "Zr" => 40, "Nb" => 41, "Mo" => 42,
-            "Ba" => 56, "La" => 57, "Ce" => 58, "Pb" => 82, "Bi" => 83,
-            _ => 1,
+    /// Number of passes over the training set
+    pub epochs: usize,
+
+    /// Mini-batch size
+    pub batch_size: usize,
+
+    /// Seed for weight initialization, shuffling and dropout masks
+    pub seed: u64,
+}
+
+impl Default for MLModelConfig {
+    fn default() -> Self {
+        Self {
+            hidden_layers: vec![128],
+            learning_rate: 1e-3,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            weight_decay: 0.0,
+            dropout: 0.0,
+            epochs: 200,
+            batch_size: 32,
+            seed: 42,
         }
     }
+}
+
+impl MLModelConfig {
+    pub fn with_hidden_layers(mut self, layers: Vec<usize>) -> Self {
+        self.hidden_layers = layers;
+        self
+    }
 
-    fn electronegativity(element: &str) -> f64 {
-        match element {
-            "H" => 2.20, "Li" => 0.98, "C" => 2.55, "N" => 3.04,
-            "O" => 3.44, "F" => 3.98, "Na" => 0.93, "Mg" => 1.31,
-            "Al" => 1.61, "Si" => 1.90, "P" => 2.19, "S" => 2.58,
-            "Cl" => 3.16, "K" => 0.82, "Ca" => 1.00, "Ti" => 1.54,
-            "Fe" => 1.83, "Ni" => 1.91, "Cu" => 1.90, "Zn" => 1.65,
-            "Sr" => 0.95, "Ba" => 0.89, "Pb" => 2.33,
-            _ => 1.5,
+    pub fn with_learning_rate(mut self, lr: f64) -> Self {
+        self.learning_rate = lr;
+        self
+    }
+
+    pub fn with_dropout(mut self, dropout: f64) -> Self {
+        self.dropout = dropout;
+        self
+    }
+
+    pub fn with_epochs(mut self, epochs: usize) -> Self {
+        self.epochs = epochs;
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.hidden_layers.contains(&0) {
+            return Err(Error::InvalidInput("Hidden layers must have non-zero width".to_string()));
+        }
+        if !(0.0..1.0).contains(&self.dropout) {
+            return Err(Error::InvalidInput(format!("Dropout must be in [0, 1), got {}", self.dropout)));
         }
+        if self.batch_size == 0 || self.epochs == 0 {
+            return Err(Error::InvalidInput("Batch size and epochs must be positive".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Regression quality metrics
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RegressionMetrics {
+    pub mae: f64,
+    pub rmse: f64,
+    pub r2: f64,
+}
+
+impl RegressionMetrics {
+    /// Compute MAE, RMSE and R² between predictions and targets
+    pub fn compute(predictions: &[f64], targets: &[f64]) -> Self {
+        let n = predictions.len().min(targets.len());
+        if n == 0 {
+            return Self { mae: 0.0, rmse: 0.0, r2: 0.0 };
+        }
+
+        let mean = targets[..n].iter().sum::<f64>() / n as f64;
+        let mut abs_err = 0.0;
+        let mut ss_res = 0.0;
+        let mut ss_tot = 0.0;
+
+        for (p, t) in predictions.iter().zip(targets).take(n) {
+            abs_err += (p - t).abs();
+            ss_res += (p - t).powi(2);
+            ss_tot += (t - mean).powi(2);
+        }
+
+        Self {
+            mae: abs_err / n as f64,
+            rmse: (ss_res / n as f64).sqrt(),
+            r2: if ss_tot > 1e-12 { 1.0 - ss_res / ss_tot } else { 0.0 },
+        }
+    }
+}
+
+/// Summary of a training run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainingReport {
+    pub samples: usize,
+    pub epochs: usize,
+    /// Mean squared error (normalized target scale) per epoch
+    pub loss_history: Vec<f64>,
+    /// Metrics on the training set in original units
+    pub train_metrics: RegressionMetrics,
+}
+
+/// K-fold cross-validation results
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossValidationReport {
+    pub folds: Vec<RegressionMetrics>,
+    pub mean: RegressionMetrics,
+    pub std: RegressionMetrics,
+}
+
+impl CrossValidationReport {
+    fn from_folds(folds: Vec<RegressionMetrics>) -> Self {
+        let k = folds.len().max(1) as f64;
+        let mean = RegressionMetrics {
+            mae: folds.iter().map(|m| m.mae).sum::<f64>() / k,
+            rmse: folds.iter().map(|m| m.rmse).sum::<f64>() / k,
+            r2: folds.iter().map(|m| m.r2).sum::<f64>() / k,
+        };
+        let std = RegressionMetrics {
+            mae: (folds.iter().map(|m| (m.mae - mean.mae).powi(2)).sum::<f64>() / k).sqrt(),
+            rmse: (folds.iter().map(|m| (m.rmse - mean.rmse).powi(2)).sum::<f64>() / k).sqrt(),
+            r2: (folds.iter().map(|m| (m.r2 - mean.r2).powi(2)).sum::<f64>() / k).sqrt(),
+        };
+
+        Self { folds, mean, std }
     }
+}
 
-    fn atomic_radius(element: &str) -> f64 {
-        match element {
-            "H" => 37.0, "Li" => 152.0, "C" => 77.0, "N" => 71.0,
-            "O" => 66.0, "F" => 64.0, "Na" => 186.0, "Mg" => 160.0,
-            "Al" => 143.0, "Si" => 117.0, "P" => 110.0, "S" => 104.0,
-            "Cl" => 99.0, "K" => 227.0, "Ca" => 197.0, "Ti" => 147.0,
-            "Fe" => 126.0, "Ni" => 124.0, "Cu" => 128.0, "Zn" => 134.0,
-            "Sr" => 215.0, "Ba" => 222.0, "Pb" => 180.0,
-            _ => 100.0,
+/// Fully connected layer with Adam optimizer state
+#[derive(Debug, Clone)]
+struct DenseLayer {
+    weights: Vec<Vec<f64>>, // [input][output]
+    biases: Vec<f64>,
+
+    m_w: Vec<Vec<f64>>,
+    v_w: Vec<Vec<f64>>,
+    m_b: Vec<f64>,
+    v_b: Vec<f64>,
+}
+
+impl DenseLayer {
+    fn new(nin: usize, nout: usize, rng: &mut StdRng) -> Self {
+        // Xavier/Glorot uniform initialization
+        let limit = (6.0 / (nin + nout) as f64).sqrt();
+        let weights = (0..nin)
+            .map(|_| (0..nout).map(|_| rng.gen_range(-limit..limit)).collect())
+            .collect();
+
+        Self {
+            weights,
+            biases: vec![0.0; nout],
+            m_w: vec![vec![0.0; nout]; nin],
+            v_w: vec![vec![0.0; nout]; nin],
+            m_b: vec![0.0; nout],
+            v_b: vec![0.0; nout],
         }
     }
 
-    fn valence_electrons(element: &str) -> u8 {
-        match element {
-            "H" => 1, "He" => 2, "Li" => 1, "Be" => 2, "B" => 3,
-            "C" => 4, "N" => 5, "O" => 6, "F" => 7, "Ne" => 8,
-            "Na" => 1, "Mg" => 2, "Al" => 3, "Si" => 4, "P" => 5,
-            "S" => 6, "Cl" => 7, "Ti" => 4, "Fe" => 8, "Ni" => 10,
-            "Cu" => 11, "Zn" => 12,
-            _ => 4,
+    fn nout(&self) -> usize {
+        self.biases.len()
+    }
+
+    fn forward(&self, input: &[f64]) -> Vec<f64> {
+        let mut out = self.biases.clone();
+        for (x, row) in input.iter().zip(&self.weights) {
+            if *x == 0.0 {
+                continue;
+            }
+            for (o, w) in out.iter_mut().zip(row) {
+                *o += x * w;
+            }
         }
+        out
     }
 }
 
-// ============================================================================
-// ML MODEL
-// ============================================================================
+/// Weights of a [`DenseLayer`] as written to disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SavedLayer {
+    weights: Vec<Vec<f64>>,
+    biases: Vec<f64>,
+}
+
+/// On-disk form of an [`MLModel`]; optimizer state is not kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SavedModel {
+    name: String,
+    input_dim: usize,
+    config: MLModelConfig,
+    layers: Vec<SavedLayer>,
+    feature_mean: Vec<f64>,
+    feature_std: Vec<f64>,
+    target_mean: f64,
+    target_std: f64,
+    trained: bool,
+}
+
+/// Gradient accumulator matching the shape of a [`DenseLayer`]
+struct LayerGrad {
+    weights: Vec<Vec<f64>>,
+    biases: Vec<f64>,
+}
 
-/// Simple neural network model for property prediction
-#[derive(Debug)]
+/// Feed-forward neural network for property prediction
+///
+/// ReLU hidden layers with a linear output, trained with Adam on MSE loss.
+/// Feature and target normalization are learned from the training data.
+#[derive(Debug, Clone)]
 pub struct MLModel {
     name: String,
     input_dim: usize,
-    hidden_dim: usize,
-    output_dim: usize,
+    config: MLModelConfig,
 
-    // Weights (in production, would use Candle Tensors)
-    w1: Vec<Vec<f64>>,  // input -> hidden
-    b1: Vec<f64>,        // hidden bias
-    w2: Vec<Vec<f64>>,  // hidden -> output
-    b2: Vec<f64>,        // output bias
+    layers: Vec<DenseLayer>,
+    adam_step: u64,
 
     // Normalization parameters
     feature_mean: Vec<f64>,
     feature_std: Vec<f64>,
     target_mean: f64,
     target_std: f64,
+
+    trained: bool,
 }
 
 impl MLModel {
     pub fn new(name: String, input_dim: usize) -> Self {
-        let hidden_dim = 128;
-        let output_dim = 1;
+        Self::with_config(name, input_dim, MLModelConfig::default())
+    }
 
-        // Initialize random weights (Xavier initialization)
-        let w1 = Self::init_weights(input_dim, hidden_dim);
-        let b1 = vec![0.0; hidden_dim];
-        let w2 = Self::init_weights(hidden_dim, output_dim);
-        let b2 = vec![0.0; output_dim];
+    pub fn with_config(name: String, input_dim: usize, config: MLModelConfig) -> Self {
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let layers = Self::init_layers(input_dim, &config.hidden_layers, &mut rng);
 
         Self {
             name,
             input_dim,
-            hidden_dim,
-            output_dim,
-            w1,
-            b1,
-            w2,
-            b2,
+            config,
+            layers,
+            adam_step: 0,
             feature_mean: vec![0.0; input_dim],
             feature_std: vec![1.0; input_dim],
             target_mean: 0.0,
             target_std: 1.0,
+            trained: false,
         }
     }
 
-    fn init_weights(nin: usize, nout: usize) -> Vec<Vec<f64>> {
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-        let scale = (2.0 / nin as f64).sqrt();
+    fn init_layers(input_dim: usize, hidden: &[usize], rng: &mut StdRng) -> Vec<DenseLayer> {
+        let mut dims = vec![input_dim];
+        dims.extend_from_slice(hidden);
+        dims.push(1);
 
-        (0..nin)
-            .map(|_| {
-                (0..nout)
-                    .map(|_| rng.gen::<f64>() * scale - scale / 2.0)
-                    .collect()
-            })
+        dims.windows(2)
+            .map(|w| DenseLayer::new(w[0], w[1], rng))
             .collect()
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn input_dim(&self) -> usize {
+        self.input_dim
+    }
+
+    pub fn config(&self) -> &MLModelConfig {
+        &self.config
+    }
+
+    /// Whether the model has been fitted to data
+    pub fn is_trained(&self) -> bool {
+        self.trained
+    }
+
     /// Forward pass through the network
-    pub fn predict(&self, features: &[f64]) -> f64 {
-        assert_eq!(features.len(), self.input_dim);
+    pub fn predict(&self, features: &[f64]) -> Result<f64> {
+        if features.len() != self.input_dim {
+            return Err(Error::InvalidInput(format!(
+                "Model {} expects {} features, got {}",
+                self.name,
+                self.input_dim,
+                features.len()
+            )));
+        }
+
+        let normalized = self.normalize(features);
+        let output = self.forward(&normalized);
+
+        // Denormalize output
+        Ok(output * self.target_std + self.target_mean)
+    }
+
+    /// Predict a batch of feature vectors
+    pub fn predict_batch(&self, features: &[Vec<f64>]) -> Result<Vec<f64>> {
+        features.iter().map(|f| self.predict(f)).collect()
+    }
 
-        // Normalize input
-        let normalized: Vec<f64> = features
+    fn normalize(&self, features: &[f64]) -> Vec<f64> {
+        features
             .iter()
             .zip(&self.feature_mean)
             .zip(&self.feature_std)
             .map(|((x, mean), std)| (x - mean) / std)
+            .collect()
+    }
+
+    fn forward(&self, input: &[f64]) -> f64 {
+        let last = self.layers.len() - 1;
+        let mut activation = input.to_vec();
+
+        for (l, layer) in self.layers.iter().enumerate() {
+            activation = layer.forward(&activation);
+            if l < last {
+                activation.iter_mut().for_each(|a| *a = Self::relu(*a));
+            }
+        }
+
+        activation[0]
+    }
+
+    fn relu(x: f64) -> f64 {
+        if x > 0.0 { x } else { 0.0 }
+    }
+
+    /// Fit the network to feature vectors and targets
+    ///
+    /// Re-initializes the weights, learns feature/target normalization from
+    /// the data and runs mini-batch Adam for `config.epochs` epochs.
+    pub fn fit(&mut self, features: &[Vec<f64>], targets: &[f64]) -> Result<TrainingReport> {
+        self.config.validate()?;
+
+        if features.is_empty() {
+            return Err(Error::ML("No training data".to_string()));
+        }
+        if features.len() != targets.len() {
+            return Err(Error::InvalidInput(format!(
+                "{} feature vectors but {} targets",
+                features.len(),
+                targets.len()
+            )));
+        }
+        if let Some(bad) = features.iter().find(|f| f.len() != self.input_dim) {
+            return Err(Error::InvalidInput(format!(
+                "Expected {} features, got {}",
+                self.input_dim,
+                bad.len()
+            )));
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.config.seed);
+        self.layers = Self::init_layers(self.input_dim, &self.config.hidden_layers, &mut rng);
+        self.adam_step = 0;
+        self.fit_normalization(features, targets);
+
+        let x: Vec<Vec<f64>> = features.iter().map(|f| self.normalize(f)).collect();
+        let y: Vec<f64> = targets
+            .iter()
+            .map(|t| (t - self.target_mean) / self.target_std)
             .collect();
 
-        // Layer 1: input -> hidden
-        let mut hidden = vec![0.0; self.hidden_dim];
-        for (i, h) in hidden.iter_mut().enumerate() {
-            let mut sum = self.b1[i];
-            for (j, &x) in normalized.iter().enumerate() {
-                sum += x * self.w1[j][i];
+        let n = x.len();
+        let mut order: Vec<usize> = (0..n).collect();
+        let mut loss_history = Vec::with_capacity(self.config.epochs);
+
+        for epoch in 0..self.config.epochs {
+            order.shuffle(&mut rng);
+            let mut epoch_loss = 0.0;
+
+            for batch in order.chunks(self.config.batch_size) {
+                epoch_loss += self.train_batch(&x, &y, batch, &mut rng);
+            }
+
+            let mse = epoch_loss / n as f64;
+            if !mse.is_finite() {
+                return Err(Error::ML(format!("Training diverged at epoch {}", epoch)));
             }
-            *h = Self::relu(sum);
+            loss_history.push(mse);
         }
 
-        // Layer 2: hidden -> output
-        let mut output = self.b2[0];
-        for (i, &h) in hidden.iter().enumerate() {
-            output += h * self.w2[i][0];
+        self.trained = true;
+
+        let predictions = self.predict_batch(features)?;
+        let train_metrics = RegressionMetrics::compute(&predictions, targets);
+
+        info!(
+            "Trained {} on {} samples: MAE={:.4}, RMSE={:.4}, R²={:.4}",
+            self.name, n, train_metrics.mae, train_metrics.rmse, train_metrics.r2
+        );
+
+        Ok(TrainingReport {
+            samples: n,
+            epochs: self.config.epochs,
+            loss_history,
+            train_metrics,
+        })
+    }
+
+    /// Fit the network on materials, extracting features with [`MaterialFeatures`]
+    pub fn fit_materials(&mut self, data: &[(Material, f64)]) -> Result<TrainingReport> {
+        let (features, targets) = Self::featurize(data);
+        self.fit(&features, &targets)
+    }
+
+    /// K-fold cross-validation of a model configuration
+    ///
+    /// Each fold trains a fresh model on the remaining folds and reports
+    /// MAE/RMSE/R² on the held-out fold in original target units.
+    pub fn cross_validate(
+        config: &MLModelConfig,
+        features: &[Vec<f64>],
+        targets: &[f64],
+        k: usize,
+    ) -> Result<CrossValidationReport> {
+        if k < 2 || k > features.len() {
+            return Err(Error::InvalidInput(format!(
+                "Cannot run {}-fold cross-validation on {} samples",
+                k,
+                features.len()
+            )));
+        }
+        if features.len() != targets.len() {
+            return Err(Error::InvalidInput(format!(
+                "{} feature vectors but {} targets",
+                features.len(),
+                targets.len()
+            )));
         }
 
-        // Denormalize output
-        output * self.target_std + self.target_mean
+        let input_dim = features[0].len();
+        let mut order: Vec<usize> = (0..features.len()).collect();
+        order.shuffle(&mut StdRng::seed_from_u64(config.seed));
+
+        let mut folds = Vec::with_capacity(k);
+        for fold in 0..k {
+            let (mut train_x, mut train_y) = (Vec::new(), Vec::new());
+            let (mut test_x, mut test_y) = (Vec::new(), Vec::new());
+
+            for (pos, &idx) in order.iter().enumerate() {
+                if pos % k == fold {
+                    test_x.push(features[idx].clone());
+                    test_y.push(targets[idx]);
+                } else {
+                    train_x.push(features[idx].clone());
+                    train_y.push(targets[idx]);
+                }
+            }
+
+            let mut model = Self::with_config(format!("cv_fold_{}", fold), input_dim, config.clone());
+            model.fit(&train_x, &train_y)?;
+
+            let predictions = model.predict_batch(&test_x)?;
+            let metrics = RegressionMetrics::compute(&predictions, &test_y);
+            debug!(
+                "Fold {}/{}: MAE={:.4}, RMSE={:.4}, R²={:.4}",
+                fold + 1, k, metrics.mae, metrics.rmse, metrics.r2
+            );
+            folds.push(metrics);
+        }
+
+        Ok(CrossValidationReport::from_folds(folds))
     }
 
-    fn relu(x: f64) -> f64 {
-        if x > 0.0 { x } else { 0.0 }
+    /// K-fold cross-validation on materials
+    pub fn cross_validate_materials(
+        config: &MLModelConfig,
+        data: &[(Material, f64)],
+        k: usize,
+    ) -> Result<CrossValidationReport> {
+        let (features, targets) = Self::featurize(data);
+        Self::cross_validate(config, &features, &targets, k)
+    }
+
+    fn featurize(data: &[(Material, f64)]) -> (Vec<Vec<f64>>, Vec<f64>) {
+        data.iter()
+            .map(|(material, target)| (MaterialFeatures::from_material(material).features, *target))
+            .unzip()
     }
 
-    /// Load pre-trained model (mock implementation)
-    pub fn load_from_file(&mut self, _path: &PathBuf) -> Result<()> {
-        info!("Loading model from file (mock mode)");
+    fn fit_normalization(&mut self, features: &[Vec<f64>], targets: &[f64]) {
+        let n = features.len() as f64;
+
+        for j in 0..self.input_dim {
+            let mean = features.iter().map(|f| f[j]).sum::<f64>() / n;
+            let var = features.iter().map(|f| (f[j] - mean).powi(2)).sum::<f64>() / n;
+            self.feature_mean[j] = mean;
+            // Constant features are centered but not scaled
+            self.feature_std[j] = if var.sqrt() > 1e-10 { var.sqrt() } else { 1.0 };
+        }
+
+        let mean = targets.iter().sum::<f64>() / n;
+        let var = targets.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / n;
+        self.target_mean = mean;
+        self.target_std = if var.sqrt() > 1e-10 { var.sqrt() } else { 1.0 };
+    }
+
+    /// Backpropagate one mini-batch and apply an Adam update.
+    /// Returns the summed squared error of the batch.
+    fn train_batch(&mut self, x: &[Vec<f64>], y: &[f64], batch: &[usize], rng: &mut StdRng) -> f64 {
+        let num_layers = self.layers.len();
+        let keep = 1.0 - self.config.dropout;
+
+        let mut grads: Vec<LayerGrad> = self
+            .layers
+            .iter()
+            .map(|l| LayerGrad {
+                weights: vec![vec![0.0; l.nout()]; l.weights.len()],
+                biases: vec![0.0; l.nout()],
+            })
+            .collect();
+
+        let mut batch_loss = 0.0;
+
+        for &idx in batch {
+            // Forward pass, keeping activations and dropout masks
+            let mut activations = vec![x[idx].clone()];
+            let mut pre_activations = Vec::with_capacity(num_layers);
+            let mut masks = Vec::with_capacity(num_layers);
+
+            for (l, layer) in self.layers.iter().enumerate() {
+                let z = layer.forward(&activations[l]);
+                let (a, mask) = if l < num_layers - 1 {
+                    let mask: Vec<f64> = (0..z.len())
+                        .map(|_| {
+                            if self.config.dropout > 0.0 && rng.gen::<f64>() >= keep {
+                                0.0
+                            } else {
+                                1.0 / keep
+                            }
+                        })
+                        .collect();
+                    let a = z.iter().zip(&mask).map(|(v, m)| Self::relu(*v) * m).collect();
+                    (a, mask)
+                } else {
+                    (z.clone(), vec![1.0; z.len()])
+                };
+                pre_activations.push(z);
+                masks.push(mask);
+                activations.push(a);
+            }
 
-        // In real implementation, would load weights from safetensors/ONNX
-        // For now, we use pre-initialized weights
+            let error = activations[num_layers][0] - y[idx];
+            batch_loss += error * error;
+
+            // Backward pass (d MSE / d output, averaged over the batch)
+            let mut delta = vec![2.0 * error / batch.len() as f64];
+            for l in (0..num_layers).rev() {
+                let input = &activations[l];
+                for (i, &a_in) in input.iter().enumerate() {
+                    if a_in == 0.0 {
+                        continue;
+                    }
+                    for (g, d) in grads[l].weights[i].iter_mut().zip(&delta) {
+                        *g += a_in * d;
+                    }
+                }
+                for (g, d) in grads[l].biases.iter_mut().zip(&delta) {
+                    *g += d;
+                }
 
-        // Set some reasonable normalization parameters
-        self.feature_mean = vec![0.5; self.input_dim];
-        self.feature_std = vec![0.2; self.input_dim];
-        self.target_mean = -3.0; // Typical formation energy
-        self.target_std = 1.5;
+                if l > 0 {
+                    let prev_z = &pre_activations[l - 1];
+                    let prev_mask = &masks[l - 1];
+                    delta = self.layers[l]
+                        .weights
+                        .iter()
+                        .enumerate()
+                        .map(|(i, row)| {
+                            if prev_z[i] <= 0.0 {
+                                return 0.0;
+                            }
+                            let back: f64 = row.iter().zip(&delta).map(|(w, d)| w * d).sum();
+                            back * prev_mask[i]
+                        })
+                        .collect();
+                }
+            }
+        }
 
+        self.apply_adam(&grads);
+        batch_loss
+    }
+
+    fn apply_adam(&mut self, grads: &[LayerGrad]) {
+        self.adam_step += 1;
+        let cfg = &self.config;
+        let t = self.adam_step as i32;
+        let bias1 = 1.0 - cfg.beta1.powi(t);
+        let bias2 = 1.0 - cfg.beta2.powi(t);
+
+        let update = |param: &mut f64, m: &mut f64, v: &mut f64, g: f64| {
+            *m = cfg.beta1 * *m + (1.0 - cfg.beta1) * g;
+            *v = cfg.beta2 * *v + (1.0 - cfg.beta2) * g * g;
+            let m_hat = *m / bias1;
+            let v_hat = *v / bias2;
+            *param -= cfg.learning_rate * m_hat / (v_hat.sqrt() + cfg.epsilon);
+        };
+
+        for (layer, grad) in self.layers.iter_mut().zip(grads) {
+            for i in 0..layer.weights.len() {
+                for j in 0..layer.biases.len() {
+                    let g = grad.weights[i][j] + cfg.weight_decay * layer.weights[i][j];
+                    update(&mut layer.weights[i][j], &mut layer.m_w[i][j], &mut layer.v_w[i][j], g);
+                }
+            }
+            for j in 0..layer.biases.len() {
+                update(&mut layer.biases[j], &mut layer.m_b[j], &mut layer.v_b[j], grad.biases[j]);
+            }
+        }
+    }
+
+    /// Write the weights, normalization and trained flag as JSON
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let saved = SavedModel {
+            name: self.name.clone(),
+            input_dim: self.input_dim,
+            config: self.config.clone(),
+            layers: self.layers.iter()
+                .map(|l| SavedLayer { weights: l.weights.clone(), biases: l.biases.clone() })
+                .collect(),
+            feature_mean: self.feature_mean.clone(),
+            feature_std: self.feature_std.clone(),
+            target_mean: self.target_mean,
+            target_std: self.target_std,
+            trained: self.trained,
+        };
+        let json = serde_json::to_string(&saved)
+            .map_err(|e| Error::ML(format!("Failed to serialize model {}: {}", self.name, e)))?;
+        std::fs::write(path, json)
+            .map_err(|e| Error::ML(format!("Failed to write model to {:?}: {}", path, e)))
+    }
+
+    /// Read a model written by [`MLModel::save`]
+    ///
+    /// Fails for a missing or unreadable file and for weights whose shapes
+    /// do not chain from `input_dim` to a single output.
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| Error::ML(format!("Failed to read model from {:?}: {}", path, e)))?;
+        let saved: SavedModel = serde_json::from_str(&json)
+            .map_err(|e| Error::ML(format!("Invalid model file {:?}: {}", path, e)))?;
+        let invalid = |what: &str| Error::ML(format!("Invalid model file {:?}: {}", path, what));
+
+        if saved.layers.is_empty() {
+            return Err(invalid("no layers"));
+        }
+        let mut nin = saved.input_dim;
+        for layer in &saved.layers {
+            let nout = layer.biases.len();
+            if nout == 0 || layer.weights.len() != nin || layer.weights.iter().any(|row| row.len() != nout) {
+                return Err(invalid("layer shapes do not match"));
+            }
+            nin = nout;
+        }
+        if nin != 1 {
+            return Err(invalid("output layer must have one unit"));
+        }
+        if saved.feature_mean.len() != saved.input_dim || saved.feature_std.len() != saved.input_dim {
+            return Err(invalid("normalization does not match the input dimension"));
+        }
+        let finite = saved.layers.iter()
+            .flat_map(|l| l.weights.iter().flatten().chain(&l.biases))
+            .chain(&saved.feature_mean)
+            .chain(&saved.feature_std)
+            .chain([&saved.target_mean, &saved.target_std])
+            .all(|x| x.is_finite());
+        if !finite {
+            return Err(invalid("non-finite parameters"));
+        }
+        if saved.feature_std.iter().chain([&saved.target_std]).any(|&s| s <= 0.0) {
+            return Err(invalid("non-positive standard deviation"));
+        }
+
+        let layers = saved.layers.into_iter()
+            .map(|l| {
+                let (nin, nout) = (l.weights.len(), l.biases.len());
+                DenseLayer {
+                    weights: l.weights,
+                    biases: l.biases,
+                    m_w: vec![vec![0.0; nout]; nin],
+                    v_w: vec![vec![0.0; nout]; nin],
+                    m_b: vec![0.0; nout],
+                    v_b: vec![0.0; nout],
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            name: saved.name,
+            input_dim: saved.input_dim,
+            config: saved.config,
+            layers,
+            adam_step: 0,
+            feature_mean: saved.feature_mean,
+            feature_std: saved.feature_std,
+            target_mean: saved.target_mean,
+            target_std: saved.target_std,
+            trained: saved.trained,
+        })
+    }
+
+    /// Replace this model with one saved by [`MLModel::save`]
+    ///
+    /// The saved model must have the same input dimension.
+    pub fn load_from_file(&mut self, path: &PathBuf) -> Result<()> {
+        info!("Loading model {} from {:?}", self.name, path);
+        let loaded = Self::load(path)?;
+        if loaded.input_dim != self.input_dim {
+            return Err(Error::ML(format!(
+                "Model in {:?} expects {} features, not {}",
+                path, loaded.input_dim, self.input_dim
+            )));
+        }
+        *self = loaded;
         Ok(())
     }
 }
@@ -335,32 +924,110 @@ impl MLEngine {
         self
     }
 
+    /// File a property's model is saved to and loaded from
+    pub fn model_path(&self, property: &str) -> PathBuf {
+        self.model_dir.join(format!("{}.json", property))
+    }
+
     /// Load ML models for different properties
+    ///
+    /// Properties that already have a trained model are left untouched.
+    /// Fails if a file does not yield trained weights; untrained networks
+    /// are never registered.
     pub fn load_models(&self) -> Result<()> {
         info!("Loading ML models from {:?}", self.model_dir);
 
+        for property in ["formation_energy", "band_gap", "elastic_modulus"] {
+            let trained = self.models.read()
+                .map_err(|e| Error::Other(format!("Lock error: {}", e)))?
+                .get(property)
+                .is_some_and(|m| m.is_trained());
+            if !trained {
+                self.load_model(property)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Load a property's model from the model directory if none is registered
+    fn ensure_model_loaded(&self, property: &str) -> Result<()> {
+        let registered = self.models.read()
+            .map_err(|e| Error::Other(format!("Lock error: {}", e)))?
+            .contains_key(property);
+        if registered {
+            return Ok(());
+        }
+        self.load_model(property)
+    }
+
+    /// Load and register a property's trained model from the model directory
+    fn load_model(&self, property: &str) -> Result<()> {
+        let model_path = self.model_path(property);
+        let mut model = MLModel::new(property.to_string(), MaterialFeatures::DIM);
+        model.load_from_file(&model_path)?;
+        if !model.is_trained() {
+            return Err(Error::ML(format!(
+                "No trained weights for {} in {:?}",
+                property, model_path
+            )));
+        }
+
+        let mut models = self.models.write()
+            .map_err(|e| Error::Other(format!("Lock error: {}", e)))?;
+        models.insert(property.to_string(), model);
+        info!("Loaded {} model from {:?}", property, model_path);
+        Ok(())
+    }
+
+    /// Save every trained model to the model directory
+    ///
+    /// Returns the number of models written.
+    pub fn save_models(&self) -> Result<usize> {
+        std::fs::create_dir_all(&self.model_dir)
+            .map_err(|e| Error::ML(format!("Failed to create {:?}: {}", self.model_dir, e)))?;
+
+        let models = self.models.read()
+            .map_err(|e| Error::Other(format!("Lock error: {}", e)))?;
+        let mut saved = 0;
+        for (property, model) in models.iter().filter(|(_, m)| m.is_trained()) {
+            model.save(&self.model_path(property))?;
+            saved += 1;
+        }
+        Ok(saved)
+    }
+
+    /// Train a model for a property from labelled materials and register it
+    pub fn train_property(
+        &self,
+        property: &str,
+        data: &[(Material, f64)],
+        config: MLModelConfig,
+    ) -> Result<TrainingReport> {
+        let mut model = MLModel::with_config(property.to_string(), MaterialFeatures::DIM, config);
+        let report = model.fit_materials(data)?;
+
         let mut models = self.models.write()
             .map_err(|e| Error::Other(format!("Lock error: {}", e)))?;
+        models.insert(property.to_string(), model);
+
+        Ok(report)
+    }
 
-        // Formation energy model
-        let mut formation_model = MLModel::new("formation_energy".to_string(), 120);
-        let model_path = self.model_dir.join("formation_energy.safetensors");
-        formation_model.load_from_file(&model_path)?;
-        models.insert("formation_energy".to_string(), formation_model);
-
-        // Band gap model
-        let mut bandgap_model = MLModel::new("band_gap".to_string(), 120);
-        let model_path = self.model_dir.join("band_gap.safetensors");
-        bandgap_model.load_from_file(&model_path)?;
-        models.insert("band_gap".to_string(), bandgap_model);
-
-        // Elastic modulus model
-        let mut elastic_model = MLModel::new("elastic_modulus".to_string(), 120);
-        let model_path = self.model_dir.join("elastic_modulus.safetensors");
-        elastic_model.load_from_file(&model_path)?;
-        models.insert("elastic_modulus".to_string(), elastic_model);
-
-        info!("Loaded {} ML models", models.len());
+    /// Cross-validate a model configuration for a property without registering it
+    pub fn cross_validate_property(
+        &self,
+        data: &[(Material, f64)],
+        config: &MLModelConfig,
+        k: usize,
+    ) -> Result<CrossValidationReport> {
+        MLModel::cross_validate_materials(config, data, k)
+    }
+
+    /// Register an already trained model under a property name
+    pub fn insert_model(&self, property: impl Into<String>, model: MLModel) -> Result<()> {
+        let mut models = self.models.write()
+            .map_err(|e| Error::Other(format!("Lock error: {}", e)))?;
+        models.insert(property.into(), model);
         Ok(())
     }
 
@@ -378,8 +1045,12 @@ impl MLEngine {
         let model = models.get(property)
             .ok_or_else(|| Error::Other(format!("Model not found: {}", property)))?;
 
+        if !model.is_trained() {
+            return Err(Error::ML(format!("Model {} is not trained", property)));
+        }
+
         // Predict
-        let prediction = model.predict(&features.features);
+        let prediction = model.predict(&features.features)?;
 
         debug!("Predicted {} = {:.4} for {}", property, prediction, material.formula);
 
@@ -406,18 +1077,8 @@ impl MLEngine {
 #[async_trait]
 impl ComputationMethod for MLEngine {
     async fn calculate_energy(&self, material: &Material) -> Result<f64> {
-        // Try to load models if not already loaded
-        {
-            let models = self.models.read()
-                .map_err(|e| Error::Other(format!("Lock error: {}", e)))?;
-
-            if models.is_empty() {
-                drop(models);
-                if let Err(e) = self.load_models() {
-                    warn!("Failed to load models: {}, using fallback", e);
-                }
-            }
-        }
+        // Try to load the model if not already loaded
+        self.ensure_model_loaded("formation_energy")?;
 
         // Predict formation energy
         self.predict_property(material, "formation_energy")
@@ -462,9 +1123,29 @@ impl BandGapPredictor {
         }
     }
 
+    /// Load and save the model in `dir` instead of the default directory
+    pub fn with_model_dir(mut self, dir: PathBuf) -> Self {
+        self.engine = self.engine.with_model_dir(dir);
+        self
+    }
+
     pub async fn predict(&self, material: &Material) -> Result<f64> {
+        self.engine.ensure_model_loaded("band_gap")?;
         self.engine.predict_property(material, "band_gap")
     }
+
+    /// Save the trained model so later processes can load it
+    pub fn save(&self) -> Result<()> {
+        match self.engine.save_models()? {
+            0 => Err(Error::ML("No trained model to save".to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Train the underlying model on (material, band_gap) pairs
+    pub fn train(&self, data: &[(Material, f64)], config: MLModelConfig) -> Result<TrainingReport> {
+        self.engine.train_property("band_gap", data, config)
+    }
 }
 
 /// Formation energy predictor
@@ -479,9 +1160,29 @@ impl FormationEnergyPredictor {
         }
     }
 
+    /// Load and save the model in `dir` instead of the default directory
+    pub fn with_model_dir(mut self, dir: PathBuf) -> Self {
+        self.engine = self.engine.with_model_dir(dir);
+        self
+    }
+
     pub async fn predict(&self, material: &Material) -> Result<f64> {
+        self.engine.ensure_model_loaded("formation_energy")?;
         self.engine.predict_property(material, "formation_energy")
     }
+
+    /// Save the trained model so later processes can load it
+    pub fn save(&self) -> Result<()> {
+        match self.engine.save_models()? {
+            0 => Err(Error::ML("No trained model to save".to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Train the underlying model on (material, formation_energy) pairs
+    pub fn train(&self, data: &[(Material, f64)], config: MLModelConfig) -> Result<TrainingReport> {
+        self.engine.train_property("formation_energy", data, config)
+    }
 }
 
 #[cfg(test)]
@@ -501,15 +1202,30 @@ mod tests {
         assert!(features.features.len() > 100);
     }
 
+    #[test]
+    fn test_feature_extraction_full_periodic_table() {
+        for formula in ["PtAu", "Nd2Fe14B", "UO2"] {
+            let material = MaterialBuilder::new(formula).unwrap().build();
+            let features = MaterialFeatures::from_material(&material);
+
+            assert_eq!(features.features.len(), MaterialFeatures::DIM);
+            assert!(features.features.iter().all(|f| f.is_finite()));
+            assert!(features.composition.iter().any(|f| *f != 0.0));
+        }
+    }
+
     #[test]
     fn test_ml_model_prediction() {
         let model = MLModel::new("test".to_string(), 120);
 
         let features = vec![0.5; 120];
-        let prediction = model.predict(&features);
+        let prediction = model.predict(&features).unwrap();
 
         // Should return some value
         assert!(prediction.is_finite());
+
+        // Wrong feature dimension is an error, not a panic
+        assert!(model.predict(&features[..100]).is_err());
     }
 
     #[tokio::test]
@@ -520,11 +1236,15 @@ mod tests {
             .unwrap()
             .build();
 
+        // No trained weights on disk: no mock fallback
         let result = engine.calculate_energy(&material).await;
-        assert!(result.is_ok());
+        assert!(result.is_err());
 
-        let energy = result.unwrap();
-        assert!(energy.is_finite());
+        let mut untrained = MLModel::new("formation_energy".to_string(), MaterialFeatures::DIM);
+        assert!(untrained.load_from_file(&PathBuf::from("missing.json")).is_err());
+        engine.insert_model("formation_energy", untrained).unwrap();
+        let error = engine.calculate_energy(&material).await.unwrap_err();
+        assert!(error.to_string().contains("not trained"), "{}", error);
     }
 
     #[tokio::test]
@@ -536,6 +1256,111 @@ mod tests {
             .build();
 
         let result = predictor.predict(&material).await;
-        assert!(result.is_ok());
+        assert!(result.is_err());
+    }
+
+    fn synthetic_data(n: usize) -> (Vec<Vec<f64>>, Vec<f64>) {
+        let features: Vec<Vec<f64>> = (0..n)
+            .map(|i| {
+                let a = (i % 10) as f64 / 10.0;
+                let b = (i / 10) as f64 / (n / 10) as f64;
+                vec![a, b, a * b]
+            })
+            .collect();
+        let targets = features.iter().map(|f| 3.0 * f[0] - 2.0 * f[1] + 1.0).collect();
+        (features, targets)
+    }
+
+    #[test]
+    fn test_ml_model_training_reduces_loss() {
+        let (features, targets) = synthetic_data(100);
+        let config = MLModelConfig::default()
+            .with_hidden_layers(vec![16, 8])
+            .with_learning_rate(0.01)
+            .with_epochs(100);
+
+        let mut model = MLModel::with_config("linear".to_string(), 3, config);
+        let report = model.fit(&features, &targets).unwrap();
+
+        assert!(model.is_trained());
+        assert_eq!(report.loss_history.len(), 100);
+        assert!(report.loss_history.last().unwrap() < &report.loss_history[0]);
+        assert!(report.train_metrics.r2 > 0.9);
+        assert!((model.predict(&[0.5, 0.5, 0.25]).unwrap() - 1.5).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_ml_model_dropout_and_invalid_input() {
+        let (features, targets) = synthetic_data(50);
+        let config = MLModelConfig::default().with_dropout(0.2).with_epochs(20);
+
+        let mut model = MLModel::with_config("dropout".to_string(), 3, config);
+        assert!(model.fit(&features, &targets).is_ok());
+        assert!(model.fit(&features, &targets[..10]).is_err());
+
+        let mut bad = MLModel::with_config(
+            "bad".to_string(),
+            3,
+            MLModelConfig::default().with_dropout(1.0),
+        );
+        assert!(bad.fit(&features, &targets).is_err());
+    }
+
+    #[test]
+    fn test_cross_validation() {
+        let (features, targets) = synthetic_data(60);
+        let config = MLModelConfig::default()
+            .with_hidden_layers(vec![8])
+            .with_learning_rate(0.01)
+            .with_epochs(50);
+
+        let report = MLModel::cross_validate(&config, &features, &targets, 5).unwrap();
+
+        assert_eq!(report.folds.len(), 5);
+        assert!(report.mean.mae.is_finite());
+        assert!(report.mean.rmse >= report.mean.mae);
+        assert!(MLModel::cross_validate(&config, &features, &targets, 1).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_train_predictor_on_materials() {
+        let data: Vec<(Material, f64)> = [
+            ("NaCl", 8.5), ("KCl", 8.4), ("LiF", 13.6), ("MgO", 7.8),
+            ("Si", 1.1), ("GaAs", 1.4), ("ZnO", 3.4), ("TiO2", 3.0),
+            ("Fe2O3", 2.2), ("Cu2O", 2.1), ("Al2O3", 8.8), ("SiO2", 9.0),
+        ]
+        .iter()
+        .map(|(formula, gap)| (MaterialBuilder::new(*formula).unwrap().build(), *gap))
+        .collect();
+
+        let dir = std::env::temp_dir().join(format!("ml_engine_models_{}", std::process::id()));
+        let predictor = BandGapPredictor::new().with_model_dir(dir.clone());
+        assert!(predictor.save().is_err());
+        let report = predictor
+            .train(&data, MLModelConfig::default().with_epochs(50))
+            .unwrap();
+        assert_eq!(report.samples, data.len());
+
+        let prediction = predictor.predict(&data[0].0).await.unwrap();
+        assert!(prediction.is_finite());
+
+        // A fresh predictor loads the saved weights and normalization
+        predictor.save().unwrap();
+        let reloaded = BandGapPredictor::new().with_model_dir(dir.clone());
+        for (material, _) in &data {
+            let expected = predictor.predict(material).await.unwrap();
+            assert!((reloaded.predict(material).await.unwrap() - expected).abs() < 1e-12);
+        }
+
+        // Truncated or mismatched files are rejected
+        let path = dir.join("band_gap.json");
+        let json = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, &json[..json.len() / 2]).unwrap();
+        assert!(MLModel::load(&path).is_err());
+        let mut wide = MLModel::new("band_gap".to_string(), MaterialFeatures::DIM);
+        let narrow_path = dir.join("narrow.json");
+        MLModel::new("narrow".to_string(), 4).save(&narrow_path).unwrap();
+        assert!(wide.load_from_file(&narrow_path).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
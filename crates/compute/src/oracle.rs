@@ -0,0 +1,102 @@
+//! Active-learning oracles backed by computation methods
+//!
+//! Adapts any [`ComputationMethod`] into a
+//! [`materials_core::active_learning::LabelOracle`], so the discovery
+//! engine's active learning loop can label candidates with MD, ML or DFT
+//! bridges from this crate.
+
+use crate::ComputationMethod;
+use async_trait::async_trait;
+use materials_core::active_learning::LabelOracle;
+use materials_core::Material;
+
+/// Oracle that labels energies with a computation method
+///
+/// Supports `energy`/`total_energy` (eV) and `energy_per_atom` (eV/atom);
+/// any other property is reported as a per-candidate failure.
+pub struct ComputationOracle<M: ComputationMethod> {
+    method: M,
+}
+
+impl<M: ComputationMethod> ComputationOracle<M> {
+    pub fn new(method: M) -> Self {
+        Self { method }
+    }
+
+    /// Total estimated cost (seconds) of labelling a batch
+    pub fn batch_cost(&self, materials: &[Material]) -> f64 {
+        materials.iter().map(|m| self.method.cost_estimate(m)).sum()
+    }
+
+    async fn label_one(&self, property: &str, material: &Material) -> Result<f64, String> {
+        match property {
+            "energy" | "total_energy" => self.method.calculate_energy(material).await.map_err(|e| e.to_string()),
+            "energy_per_atom" => {
+                let atoms = material.num_atoms();
+                if atoms == 0 {
+                    return Err(format!("{} has no sites", material.formula));
+                }
+                let energy = self.method.calculate_energy(material).await.map_err(|e| e.to_string())?;
+                Ok(energy / atoms as f64)
+            }
+            _ => Err(format!("{} cannot compute {}", self.method.name(), property)),
+        }
+    }
+}
+
+#[async_trait]
+impl<M: ComputationMethod> LabelOracle for ComputationOracle<M> {
+    fn name(&self) -> &str {
+        self.method.name()
+    }
+
+    async fn label(&self, property: &str, materials: &[Material]) -> Vec<Result<f64, String>> {
+        let mut labels = Vec::with_capacity(materials.len());
+        for material in materials {
+            labels.push(self.label_one(property, material).await);
+        }
+        labels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Result;
+    use materials_core::material::MaterialBuilder;
+
+    struct PairEnergy;
+
+    #[async_trait]
+    impl ComputationMethod for PairEnergy {
+        async fn calculate_energy(&self, material: &Material) -> Result<f64> {
+            Ok(-1.5 * material.num_atoms() as f64)
+        }
+
+        async fn calculate_forces(&self, material: &Material) -> Result<Vec<[f64; 3]>> {
+            Ok(vec![[0.0; 3]; material.num_atoms()])
+        }
+
+        fn cost_estimate(&self, _material: &Material) -> f64 {
+            1.0
+        }
+
+        fn name(&self) -> &str {
+            "pair_energy"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_computation_oracle() {
+        let oracle = ComputationOracle::new(PairEnergy);
+        let materials = vec![MaterialBuilder::new("Fe2O3").unwrap().build(), Material::new("NiO")];
+
+        let labels = oracle.label("energy_per_atom", &materials).await;
+        assert_eq!(labels[0], Ok(-1.5));
+        assert!(labels[1].is_err());
+
+        assert_eq!(oracle.label("total_energy", &materials[..1]).await[0], Ok(-7.5));
+        assert!(oracle.label("band_gap", &materials[..1]).await[0].is_err());
+        assert_eq!(oracle.batch_cost(&materials), 2.0);
+    }
+}
@@ -0,0 +1,581 @@
+//! Active Learning
+//!
+//! Building blocks for the uncertainty-driven discovery loop in
+//! [`crate::discovery::DiscoveryEngine::run_active_learning`]:
+//! - Acquisition functions (UCB, expected improvement, max-variance)
+//! - [`LabelOracle`], the source of ground-truth labels, with a lookup-table
+//!   oracle for offline runs and a queued-DFT oracle that hands jobs to an
+//!   external worker pool and awaits their [`DFTResult`]s
+//! - Learning-curve records produced per iteration
+
+use crate::material::Material;
+use crate::quantum::{CalculationType, DFTResult};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+// ============================================================================
+// ACQUISITION
+// ============================================================================
+
+/// Direction of the search
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LearningGoal {
+    Maximize,
+    Minimize,
+    /// Get as close as possible to a target value
+    Target(f64),
+}
+
+impl LearningGoal {
+    /// Map a property value to a utility where larger is better
+    pub fn utility(&self, value: f64) -> f64 {
+        match self {
+            LearningGoal::Maximize => value,
+            LearningGoal::Minimize => -value,
+            LearningGoal::Target(target) => -(value - target).abs(),
+        }
+    }
+}
+
+/// Acquisition function used to rank unlabelled candidates
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AcquisitionFunction {
+    /// μ + κσ (in utility space)
+    UpperConfidenceBound { kappa: f64 },
+    /// E[max(u - u_best - ξ, 0)] under a Gaussian predictive distribution
+    ExpectedImprovement { xi: f64 },
+    /// Pure exploration: largest predictive standard deviation
+    MaxVariance,
+}
+
+impl Default for AcquisitionFunction {
+    fn default() -> Self {
+        AcquisitionFunction::UpperConfidenceBound { kappa: 2.0 }
+    }
+}
+
+impl AcquisitionFunction {
+    /// Score a candidate from its predictive mean and standard deviation
+    pub fn score(&self, goal: LearningGoal, mean: f64, std: f64, best_utility: f64) -> f64 {
+        let mu = goal.utility(mean);
+        let sigma = std.max(0.0);
+
+        match *self {
+            AcquisitionFunction::UpperConfidenceBound { kappa } => mu + kappa * sigma,
+            AcquisitionFunction::ExpectedImprovement { xi } => {
+                let improvement = mu - best_utility - xi;
+                if sigma < 1e-12 {
+                    return improvement.max(0.0);
+                }
+                let z = improvement / sigma;
+                improvement * normal_cdf(z) + sigma * normal_pdf(z)
+            }
+            AcquisitionFunction::MaxVariance => sigma * sigma,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AcquisitionFunction::UpperConfidenceBound { .. } => "ucb",
+            AcquisitionFunction::ExpectedImprovement { .. } => "expected_improvement",
+            AcquisitionFunction::MaxVariance => "max_variance",
+        }
+    }
+}
+
+/// Standard normal density
+pub fn normal_pdf(z: f64) -> f64 {
+    (-0.5 * z * z).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+/// Standard normal CDF (Abramowitz & Stegun 7.1.26, |error| < 1.5e-7)
+pub fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let poly = t * (0.254_829_592
+        + t * (-0.284_496_736 + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let erf = 1.0 - poly * (-x * x).exp();
+    if z >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+// ============================================================================
+// ORACLES
+// ============================================================================
+
+/// Source of ground-truth labels for selected candidates
+///
+/// Implementations may be slow (DFT) or fail per candidate; a failed label
+/// removes the candidate from the pool without aborting the loop.
+#[async_trait]
+pub trait LabelOracle: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Label a batch, returning one result per material in order
+    async fn label(&self, property: &str, materials: &[Material]) -> Vec<Result<f64, String>>;
+}
+
+/// Oracle backed by a precomputed table, for offline benchmarks and tests
+#[derive(Debug, Clone, Default)]
+pub struct LookupOracle {
+    by_id: HashMap<(Uuid, String), f64>,
+    by_formula: HashMap<(String, String), f64>,
+}
+
+impl LookupOracle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a value for a specific material
+    pub fn insert(&mut self, material_id: Uuid, property: &str, value: f64) {
+        self.by_id.insert((material_id, property.to_string()), value);
+    }
+
+    /// Register a value for any material with this formula
+    pub fn insert_formula(&mut self, formula: &str, property: &str, value: f64) {
+        self.by_formula.insert((formula.to_string(), property.to_string()), value);
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_id.len() + self.by_formula.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl LabelOracle for LookupOracle {
+    fn name(&self) -> &str {
+        "lookup_table"
+    }
+
+    async fn label(&self, property: &str, materials: &[Material]) -> Vec<Result<f64, String>> {
+        materials
+            .iter()
+            .map(|m| {
+                self.by_id
+                    .get(&(m.id, property.to_string()))
+                    .or_else(|| self.by_formula.get(&(m.formula.clone(), property.to_string())))
+                    .copied()
+                    .ok_or_else(|| format!("No {} entry for {}", property, m.formula))
+            })
+            .collect()
+    }
+}
+
+/// A DFT job handed to an external worker
+#[derive(Debug)]
+pub struct DftJob {
+    pub id: Uuid,
+    pub material: Material,
+    pub calc_type: CalculationType,
+    /// Properties the submitter needs from the result
+    pub properties: Vec<String>,
+    reply: oneshot::Sender<Result<DFTResult, String>>,
+}
+
+impl DftJob {
+    /// Report the outcome of the calculation back to the oracle
+    pub fn complete(self, result: Result<DFTResult, String>) {
+        // The oracle may have given up on the job; nothing to do then
+        let _ = self.reply.send(result);
+    }
+}
+
+/// Property names [`QueuedDftOracle::extract_property`] understands
+pub const DFT_PROPERTIES: &[&str] = &[
+    "total_energy",
+    "energy_per_atom",
+    "formation_energy",
+    "fermi_energy",
+    "band_gap",
+    "magnetic_moment",
+    "pressure",
+];
+
+/// Oracle that queues DFT jobs and waits for workers to complete them
+///
+/// Created together with a [`DftJobQueue`]; whatever drives the DFT codes
+/// (a local `QuantumEngine`, a cluster scheduler) pulls jobs from the
+/// queue and calls [`DftJob::complete`]. Jobs not completed within the
+/// oracle's timeout are reported as failed labels.
+pub struct QueuedDftOracle {
+    sender: mpsc::Sender<DftJob>,
+    timeout: Duration,
+}
+
+/// How long [`QueuedDftOracle`] waits for a submission or batch by default
+pub const DEFAULT_DFT_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Receiving end of a [`QueuedDftOracle`]
+pub struct DftJobQueue {
+    receiver: Mutex<mpsc::Receiver<DftJob>>,
+}
+
+impl QueuedDftOracle {
+    /// Create an oracle and its job queue, holding at most `capacity`
+    /// pending jobs before submission waits
+    pub fn new(capacity: usize) -> (Self, DftJobQueue) {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        (
+            Self { sender, timeout: DEFAULT_DFT_TIMEOUT },
+            DftJobQueue { receiver: Mutex::new(receiver) },
+        )
+    }
+
+    /// Give up on jobs (queueing included) that take longer than `timeout`
+    ///
+    /// For [`LabelOracle::label`] the timeout covers the whole batch.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Submit one calculation and wait for its result
+    ///
+    /// Unconverged results and jobs exceeding the timeout are reported as
+    /// errors.
+    pub async fn submit(
+        &self,
+        material: Material,
+        calc_type: CalculationType,
+        properties: Vec<String>,
+    ) -> Result<DFTResult, String> {
+        let deadline = Instant::now() + self.timeout;
+        let receiver = self.before(deadline, self.enqueue(material, calc_type, properties)).await?;
+        self.before(deadline, Self::await_result(receiver)).await
+    }
+
+    async fn before<T>(
+        &self,
+        deadline: Instant,
+        fut: impl Future<Output = Result<T, String>>,
+    ) -> Result<T, String> {
+        tokio::time::timeout_at(deadline, fut)
+            .await
+            .unwrap_or_else(|_| Err(format!("DFT job timed out after {:?}", self.timeout)))
+    }
+
+    async fn enqueue(
+        &self,
+        material: Material,
+        calc_type: CalculationType,
+        properties: Vec<String>,
+    ) -> Result<oneshot::Receiver<Result<DFTResult, String>>, String> {
+        let (reply, receiver) = oneshot::channel();
+        let job = DftJob { id: Uuid::new_v4(), material, calc_type, properties, reply };
+        self.sender.send(job).await.map_err(|_| "DFT job queue is closed".to_string())?;
+        Ok(receiver)
+    }
+
+    async fn await_result(receiver: oneshot::Receiver<Result<DFTResult, String>>) -> Result<DFTResult, String> {
+        match receiver.await {
+            Ok(Ok(dft)) if !dft.converged => Err("DFT calculation did not converge".to_string()),
+            Ok(result) => result,
+            Err(_) => Err("DFT worker dropped the job".to_string()),
+        }
+    }
+
+    /// All properties present in a DFT result, keyed by their canonical names
+    pub fn result_properties(result: &DFTResult) -> HashMap<String, f64> {
+        DFT_PROPERTIES
+            .iter()
+            .filter_map(|name| Self::extract_property(result, name).map(|v| (name.to_string(), v)))
+            .collect()
+    }
+
+    /// Extract a named property from a DFT result
+    pub fn extract_property(result: &DFTResult, property: &str) -> Option<f64> {
+        match property {
+            "total_energy" | "energy" => result.total_energy,
+            "energy_per_atom" => result.energy_per_atom,
+            "formation_energy" => result.formation_energy,
+            "fermi_energy" => result.fermi_energy,
+            "band_gap" => result.band_gap,
+            "magnetic_moment" => result.magnetic_moment,
+            "pressure" => result.pressure,
+            _ => None,
+        }
+    }
+}
+
+impl DftJobQueue {
+    /// Next pending job, or None once the oracle has been dropped
+    pub async fn next_job(&self) -> Option<DftJob> {
+        self.receiver.lock().await.recv().await
+    }
+}
+
+#[async_trait]
+impl LabelOracle for QueuedDftOracle {
+    fn name(&self) -> &str {
+        "queued_dft"
+    }
+
+    async fn label(&self, property: &str, materials: &[Material]) -> Vec<Result<f64, String>> {
+        // Submit the whole batch first so workers can run it concurrently
+        let deadline = Instant::now() + self.timeout;
+        let mut pending = Vec::with_capacity(materials.len());
+        for material in materials {
+            let job = self.enqueue(material.clone(), CalculationType::SinglePoint, vec![property.to_string()]);
+            pending.push(self.before(deadline, job).await);
+        }
+
+        let mut results = Vec::with_capacity(pending.len());
+        for entry in pending {
+            let result = match entry {
+                Ok(receiver) => self.before(deadline, Self::await_result(receiver)).await.and_then(|dft| {
+                    Self::extract_property(&dft, property)
+                        .ok_or_else(|| format!("DFT result has no {}", property))
+                }),
+                Err(e) => Err(e),
+            };
+            results.push(result);
+        }
+        results
+    }
+}
+
+// ============================================================================
+// LOOP CONFIGURATION AND REPORTING
+// ============================================================================
+
+/// Active learning loop settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveLearningConfig {
+    pub property: String,
+    pub goal: LearningGoal,
+    pub acquisition: AcquisitionFunction,
+    /// Randomly labelled seed set (skipped if the predictor already has data)
+    pub initial_samples: usize,
+    /// Candidates labelled per iteration
+    pub batch_size: usize,
+    pub iterations: usize,
+    pub seed: u64,
+}
+
+impl ActiveLearningConfig {
+    pub fn new(property: impl Into<String>, goal: LearningGoal) -> Self {
+        Self {
+            property: property.into(),
+            goal,
+            acquisition: AcquisitionFunction::default(),
+            initial_samples: 5,
+            batch_size: 5,
+            iterations: 10,
+            seed: 42,
+        }
+    }
+
+    pub fn with_acquisition(mut self, acquisition: AcquisitionFunction) -> Self {
+        self.acquisition = acquisition;
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    pub fn with_initial_samples(mut self, initial_samples: usize) -> Self {
+        self.initial_samples = initial_samples;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.batch_size == 0 {
+            return Err("batch_size must be positive".to_string());
+        }
+        if let AcquisitionFunction::UpperConfidenceBound { kappa } = self.acquisition {
+            if kappa < 0.0 {
+                return Err("UCB kappa must be non-negative".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A candidate labelled by the oracle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelledSample {
+    pub material_id: Uuid,
+    pub formula: String,
+    pub value: f64,
+    /// Iteration in which it was acquired (0 = seed set)
+    pub iteration: usize,
+    /// Model prediction at acquisition time (None for seed samples)
+    pub predicted: Option<f64>,
+    pub predicted_std: Option<f64>,
+}
+
+/// One point of the learning curve
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearningCurvePoint {
+    pub iteration: usize,
+    pub labelled: usize,
+    pub remaining_pool: usize,
+    /// Best property value labelled so far
+    pub best_value: Option<f64>,
+    pub best_formula: Option<String>,
+    /// Mean predictive standard deviation over the unlabelled pool
+    pub mean_pool_uncertainty: f64,
+    /// RMSE of the model on the batch it selected, measured after labelling
+    pub batch_rmse: Option<f64>,
+    pub acquired: usize,
+    pub failed: usize,
+}
+
+/// Outcome of an active learning run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveLearningReport {
+    pub property: String,
+    pub acquisition: String,
+    pub oracle: String,
+    pub learning_curve: Vec<LearningCurvePoint>,
+    pub labelled: Vec<LabelledSample>,
+    pub best: Option<LabelledSample>,
+    /// Candidates the oracle could not label
+    pub failures: Vec<(Uuid, String)>,
+}
+
+impl ActiveLearningReport {
+    pub(crate) fn new(config: &ActiveLearningConfig, oracle: &str) -> Self {
+        Self {
+            property: config.property.clone(),
+            acquisition: config.acquisition.name().to_string(),
+            oracle: oracle.to_string(),
+            learning_curve: Vec::new(),
+            labelled: Vec::new(),
+            best: None,
+            failures: Vec::new(),
+        }
+    }
+
+    /// Record a labelled sample, updating the best one under `goal`
+    pub(crate) fn record(&mut self, sample: LabelledSample, goal: LearningGoal) {
+        let improves = self
+            .best
+            .as_ref()
+            .map_or(true, |best| goal.utility(sample.value) > goal.utility(best.value));
+        if improves {
+            self.best = Some(sample.clone());
+        }
+        self.labelled.push(sample);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acquisition_scores() {
+        let ucb = AcquisitionFunction::UpperConfidenceBound { kappa: 2.0 };
+        assert_eq!(ucb.score(LearningGoal::Maximize, 1.0, 0.5, 0.0), 2.0);
+        assert_eq!(ucb.score(LearningGoal::Minimize, 1.0, 0.5, 0.0), 0.0);
+
+        // EI prefers uncertainty when means tie, and is never negative
+        let ei = AcquisitionFunction::ExpectedImprovement { xi: 0.0 };
+        let confident = ei.score(LearningGoal::Maximize, 1.0, 0.1, 1.0);
+        let uncertain = ei.score(LearningGoal::Maximize, 1.0, 1.0, 1.0);
+        assert!(uncertain > confident);
+        assert!(ei.score(LearningGoal::Maximize, -5.0, 0.0, 1.0) >= 0.0);
+
+        assert!((normal_cdf(0.0) - 0.5).abs() < 1e-7);
+        assert!((normal_cdf(1.96) - 0.975).abs() < 1e-3);
+
+        let target = LearningGoal::Target(2.0);
+        assert!(target.utility(2.1) > target.utility(3.0));
+    }
+
+    #[tokio::test]
+    async fn test_lookup_oracle() {
+        let mut oracle = LookupOracle::new();
+        oracle.insert_formula("Fe2O3", "band_gap", 2.1);
+
+        let labels = oracle
+            .label("band_gap", &[Material::new("Fe2O3"), Material::new("NiO")])
+            .await;
+        assert_eq!(labels[0], Ok(2.1));
+        assert!(labels[1].is_err());
+    }
+
+    #[tokio::test]
+    async fn test_queued_dft_oracle() {
+        let (oracle, queue) = QueuedDftOracle::new(4);
+        let worker = tokio::spawn(async move {
+            while let Some(job) = queue.next_job().await {
+                let mut result = DFTResult::new(job.material.id, CalculationType::SinglePoint);
+                result.converged = job.material.formula != "Unstable";
+                result.band_gap = Some(job.material.formula.len() as f64);
+                job.complete(Ok(result));
+            }
+        });
+
+        let labels = oracle
+            .label("band_gap", &[Material::new("GaAs"), Material::new("Unstable")])
+            .await;
+        assert_eq!(labels[0], Ok(4.0));
+        assert!(labels[1].is_err());
+
+        drop(oracle);
+        worker.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_queued_dft_oracle_timeout() {
+        let (oracle, queue) = QueuedDftOracle::new(1);
+        let oracle = oracle.with_timeout(Duration::from_secs(60));
+        // Completes GaAs, holds on to anything else without answering
+        let worker = tokio::spawn(async move {
+            let mut stalled = Vec::new();
+            while let Some(job) = queue.next_job().await {
+                if job.material.formula == "GaAs" {
+                    let mut result = DFTResult::new(job.material.id, CalculationType::SinglePoint);
+                    result.converged = true;
+                    result.band_gap = Some(1.4);
+                    job.complete(Ok(result));
+                } else {
+                    stalled.push(job);
+                }
+            }
+        });
+
+        let labels = oracle
+            .label("band_gap", &[Material::new("GaAs"), Material::new("Stuck"), Material::new("AlAs")])
+            .await;
+        assert_eq!(labels[0], Ok(1.4));
+        assert!(labels[1].as_ref().unwrap_err().contains("timed out"));
+        assert!(labels[2].as_ref().unwrap_err().contains("timed out"));
+
+        let result = oracle
+            .submit(Material::new("Stuck"), CalculationType::SinglePoint, vec!["band_gap".to_string()])
+            .await;
+        assert!(result.unwrap_err().contains("timed out"));
+
+        drop(oracle);
+        worker.await.unwrap();
+    }
+}
@@ -0,0 +1,624 @@
+//! Bayesian Optimization over Composition Simplexes
+//!
+//! Ask/tell optimizer for continuous compositions (element fractions that
+//! sum to one), using a [`GaussianProcess`] surrogate for the objective and
+//! one per outcome constraint. Proposals maximize constrained expected
+//! improvement (EI × probability of feasibility); batches are built either
+//! with the Kriging believer heuristic or greedy Monte Carlo q-EI.
+//!
+//! Constraints from a [`DiscoveryTarget`] map onto the search: forbidden
+//! elements are removed from the simplex, required elements get a minimum
+//! fraction, and property constraints become outcome constraints.
+
+use crate::active_learning::{normal_cdf, normal_pdf};
+use crate::discovery::{DiscoveryTarget, PropertyConstraint};
+use crate::gaussian_process::{GaussianProcess, GaussianProcessConfig};
+use crate::{Error, Result};
+use nalgebra::{DMatrix, DVector};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// SEARCH SPACE
+// ============================================================================
+
+/// Compositions over a fixed element set with per-element minimum fractions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompositionSpace {
+    elements: Vec<String>,
+    min_fractions: Vec<f64>,
+}
+
+impl CompositionSpace {
+    pub fn new(elements: Vec<String>) -> Result<Self> {
+        let mut unique: Vec<String> = Vec::new();
+        for element in elements {
+            if !unique.contains(&element) {
+                unique.push(element);
+            }
+        }
+        if unique.is_empty() {
+            return Err(Error::invalid_input("Composition space needs at least one element"));
+        }
+        let n = unique.len();
+        Ok(Self { elements: unique, min_fractions: vec![0.0; n] })
+    }
+
+    /// Require at least `fraction` of an element
+    pub fn with_min_fraction(mut self, element: &str, fraction: f64) -> Result<Self> {
+        let idx = self
+            .elements
+            .iter()
+            .position(|e| e == element)
+            .ok_or_else(|| Error::invalid_input(format!("{} is not in the composition space", element)))?;
+        self.min_fractions[idx] = fraction.max(0.0);
+        if self.min_fractions.iter().sum::<f64>() > 1.0 {
+            return Err(Error::invalid_input("Minimum fractions exceed 1"));
+        }
+        Ok(self)
+    }
+
+    /// Space spanned by `base_elements` plus the target's required elements,
+    /// excluding forbidden ones; required elements get `required_fraction`
+    pub fn from_target(base_elements: &[String], target: &DiscoveryTarget, required_fraction: f64) -> Result<Self> {
+        if let Some(e) = target.required_elements.iter().find(|e| target.forbidden_elements.contains(e)) {
+            return Err(Error::invalid_input(format!("{} is both required and forbidden", e)));
+        }
+
+        let elements: Vec<String> = base_elements
+            .iter()
+            .chain(&target.required_elements)
+            .filter(|e| !target.forbidden_elements.contains(e))
+            .cloned()
+            .collect();
+
+        let mut space = Self::new(elements)?;
+        for element in &target.required_elements {
+            space = space.with_min_fraction(element, required_fraction)?;
+        }
+        Ok(space)
+    }
+
+    pub fn dim(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn elements(&self) -> &[String] {
+        &self.elements
+    }
+
+    /// Euclidean projection onto the constrained simplex
+    pub fn project(&self, x: &[f64]) -> Vec<f64> {
+        let free = 1.0 - self.min_fractions.iter().sum::<f64>();
+        let shifted: Vec<f64> = x.iter().zip(&self.min_fractions).map(|(v, m)| v - m).collect();
+
+        // Duchi et al. (2008): sort, find the threshold, clip
+        let mut sorted = shifted.clone();
+        sorted.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+        let mut cumulative = 0.0;
+        let mut theta = 0.0;
+        for (i, v) in sorted.iter().enumerate() {
+            cumulative += v;
+            let t = (cumulative - free) / (i + 1) as f64;
+            if v - t > 0.0 {
+                theta = t;
+            }
+        }
+
+        shifted.iter().zip(&self.min_fractions).map(|(v, m)| (v - theta).max(0.0) + m).collect()
+    }
+
+    /// Uniform sample from the constrained simplex (flat Dirichlet)
+    pub fn sample(&self, rng: &mut StdRng) -> Vec<f64> {
+        let free = 1.0 - self.min_fractions.iter().sum::<f64>();
+        let draws: Vec<f64> = (0..self.dim()).map(|_| -(1.0 - rng.gen::<f64>()).ln()).collect();
+        let total: f64 = draws.iter().sum();
+        draws.iter().zip(&self.min_fractions).map(|(d, m)| m + free * d / total).collect()
+    }
+
+    /// Gaussian perturbation of `x`, projected back into the space
+    pub fn perturb(&self, x: &[f64], scale: f64, rng: &mut StdRng) -> Vec<f64> {
+        let moved: Vec<f64> = x.iter().map(|v| v + scale * standard_normal(rng)).collect();
+        self.project(&moved)
+    }
+
+    /// Element/fraction pairs, dropping negligible fractions
+    pub fn composition(&self, x: &[f64]) -> Vec<(String, f64)> {
+        self.elements
+            .iter()
+            .zip(x)
+            .filter(|(_, f)| **f >= 1e-3)
+            .map(|(e, f)| (e.clone(), *f))
+            .collect()
+    }
+
+    /// Fractional formula such as "Fe0.25Co0.75"
+    pub fn formula(&self, x: &[f64]) -> String {
+        self.composition(x)
+            .iter()
+            .map(|(e, f)| {
+                let amount = format!("{:.3}", f);
+                let amount = amount.trim_end_matches('0').trim_end_matches('.');
+                if amount == "1" {
+                    e.clone()
+                } else {
+                    format!("{}{}", e, amount)
+                }
+            })
+            .collect()
+    }
+}
+
+fn standard_normal(rng: &mut StdRng) -> f64 {
+    // Box-Muller
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+// ============================================================================
+// CONSTRAINTS AND OBSERVATIONS
+// ============================================================================
+
+/// Feasible interval for a modelled outcome
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutcomeConstraint {
+    pub name: String,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl OutcomeConstraint {
+    /// target ± tolerance
+    pub fn from_property(name: &str, constraint: &PropertyConstraint) -> Self {
+        Self {
+            name: name.to_string(),
+            lower: constraint.target_value - constraint.tolerance,
+            upper: constraint.target_value + constraint.tolerance,
+        }
+    }
+
+    pub fn is_satisfied(&self, value: f64) -> bool {
+        value >= self.lower && value <= self.upper
+    }
+
+    /// P(lower ≤ y ≤ upper) under N(mean, std²)
+    pub fn probability(&self, mean: f64, std: f64) -> f64 {
+        if std < 1e-12 {
+            return if self.is_satisfied(mean) { 1.0 } else { 0.0 };
+        }
+        normal_cdf((self.upper - mean) / std) - normal_cdf((self.lower - mean) / std)
+    }
+}
+
+/// One evaluated composition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Observation {
+    pub x: Vec<f64>,
+    pub objective: f64,
+    /// Outcome values, aligned with the optimizer's constraints
+    pub constraint_values: Vec<f64>,
+    pub feasible: bool,
+}
+
+/// How a batch of q proposals is assembled
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BatchStrategy {
+    /// Sequentially pick the EI maximizer and pretend its outcome equals the
+    /// posterior mean (Ginsbourger et al. 2010)
+    KrigingBeliever,
+    /// Greedy maximization of Monte Carlo q-EI over joint posterior samples
+    QExpectedImprovement { mc_samples: usize },
+}
+
+/// Optimizer settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BayesianOptimizerConfig {
+    pub gp: GaussianProcessConfig,
+    pub batch_strategy: BatchStrategy,
+    /// Random candidates scored per proposal (plus local perturbations)
+    pub n_candidates: usize,
+    /// EI exploration margin
+    pub xi: f64,
+    /// Observations gathered by random sampling before the GP takes over
+    pub initial_samples: usize,
+    pub seed: u64,
+}
+
+impl Default for BayesianOptimizerConfig {
+    fn default() -> Self {
+        Self {
+            gp: GaussianProcessConfig::default(),
+            batch_strategy: BatchStrategy::KrigingBeliever,
+            n_candidates: 500,
+            xi: 0.01,
+            initial_samples: 5,
+            seed: 42,
+        }
+    }
+}
+
+// ============================================================================
+// OPTIMIZER
+// ============================================================================
+
+/// Constrained Bayesian optimizer (maximizes the objective)
+pub struct BayesianOptimizer {
+    space: CompositionSpace,
+    constraints: Vec<OutcomeConstraint>,
+    config: BayesianOptimizerConfig,
+    observations: Vec<Observation>,
+    rng: StdRng,
+}
+
+/// Surrogates fitted to the current observations
+struct Surrogates {
+    objective: GaussianProcess,
+    constraints: Vec<GaussianProcess>,
+}
+
+impl BayesianOptimizer {
+    pub fn new(space: CompositionSpace, config: BayesianOptimizerConfig) -> Self {
+        let rng = StdRng::seed_from_u64(config.seed);
+        Self { space, constraints: Vec::new(), config, observations: Vec::new(), rng }
+    }
+
+    pub fn with_constraints(mut self, constraints: Vec<OutcomeConstraint>) -> Self {
+        self.constraints = constraints;
+        self
+    }
+
+    pub fn space(&self) -> &CompositionSpace {
+        &self.space
+    }
+
+    pub fn constraints(&self) -> &[OutcomeConstraint] {
+        &self.constraints
+    }
+
+    pub fn observations(&self) -> &[Observation] {
+        &self.observations
+    }
+
+    /// Record an evaluation
+    pub fn tell(&mut self, x: Vec<f64>, objective: f64, constraint_values: Vec<f64>) -> Result<()> {
+        if x.len() != self.space.dim() || constraint_values.len() != self.constraints.len() {
+            return Err(Error::invalid_input("Observation does not match the optimizer's space or constraints"));
+        }
+        if !objective.is_finite() || constraint_values.iter().any(|v| !v.is_finite()) {
+            return Err(Error::invalid_input("Observation values must be finite"));
+        }
+        let feasible = self.constraints.iter().zip(&constraint_values).all(|(c, v)| c.is_satisfied(*v));
+        self.observations.push(Observation { x, objective, constraint_values, feasible });
+        Ok(())
+    }
+
+    /// Best feasible observation
+    pub fn best(&self) -> Option<&Observation> {
+        self.observations
+            .iter()
+            .filter(|o| o.feasible)
+            .max_by(|a, b| a.objective.partial_cmp(&b.objective).unwrap_or(std::cmp::Ordering::Equal))
+    }
+
+    /// Propose `q` compositions to evaluate next
+    pub fn ask(&mut self, q: usize) -> Result<Vec<Vec<f64>>> {
+        if self.observations.len() < self.config.initial_samples.max(2) {
+            return Ok((0..q).map(|_| self.space.sample(&mut self.rng)).collect());
+        }
+
+        let surrogates = self.fit_surrogates()?;
+        let candidates = self.candidate_pool();
+
+        match self.config.batch_strategy {
+            BatchStrategy::KrigingBeliever => self.kriging_believer(surrogates, candidates, q),
+            BatchStrategy::QExpectedImprovement { mc_samples } => Ok(self.q_expected_improvement(
+                &surrogates,
+                candidates,
+                q,
+                mc_samples.max(16),
+            )),
+        }
+    }
+
+    fn fit_surrogates(&self) -> Result<Surrogates> {
+        let x: Vec<Vec<f64>> = self.observations.iter().map(|o| o.x.clone()).collect();
+        let y: Vec<f64> = self.observations.iter().map(|o| o.objective).collect();
+        let objective = GaussianProcess::fit(&x, &y, &self.config.gp)?;
+
+        let constraints = (0..self.constraints.len())
+            .map(|c| {
+                let values: Vec<f64> = self.observations.iter().map(|o| o.constraint_values[c]).collect();
+                GaussianProcess::fit(&x, &values, &self.config.gp)
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Surrogates { objective, constraints })
+    }
+
+    /// Random simplex points plus perturbations of the best observations
+    fn candidate_pool(&mut self) -> Vec<Vec<f64>> {
+        let mut pool: Vec<Vec<f64>> = (0..self.config.n_candidates).map(|_| self.space.sample(&mut self.rng)).collect();
+
+        let mut ranked: Vec<&Observation> = self.observations.iter().collect();
+        ranked.sort_by(|a, b| {
+            (b.feasible, b.objective)
+                .partial_cmp(&(a.feasible, a.objective))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let anchors: Vec<Vec<f64>> = ranked.iter().take(5).map(|o| o.x.clone()).collect();
+        for anchor in &anchors {
+            for scale in [0.02, 0.05, 0.15] {
+                for _ in 0..10 {
+                    pool.push(self.space.perturb(anchor, scale, &mut self.rng));
+                }
+            }
+        }
+        pool
+    }
+
+    /// Best feasible objective, or None when nothing feasible was seen yet
+    fn incumbent(observations: &[Observation]) -> Option<f64> {
+        observations.iter().filter(|o| o.feasible).map(|o| o.objective).fold(None, |best, v| {
+            Some(best.map_or(v, |b: f64| b.max(v)))
+        })
+    }
+
+    fn feasibility(&self, surrogates: &Surrogates, x: &[f64]) -> f64 {
+        self.constraints
+            .iter()
+            .zip(&surrogates.constraints)
+            .map(|(c, gp)| {
+                let (mean, std) = gp.predict(x);
+                c.probability(mean, std)
+            })
+            .product()
+    }
+
+    /// Constrained EI; with no feasible incumbent, probability of feasibility
+    fn acquisition(&self, surrogates: &Surrogates, incumbent: Option<f64>, x: &[f64]) -> f64 {
+        let feasibility = self.feasibility(surrogates, x);
+        let Some(best) = incumbent else {
+            return feasibility;
+        };
+
+        let (mean, std) = surrogates.objective.predict(x);
+        let improvement = mean - best - self.config.xi;
+        let ei = if std < 1e-12 {
+            improvement.max(0.0)
+        } else {
+            let z = improvement / std;
+            improvement * normal_cdf(z) + std * normal_pdf(z)
+        };
+        ei * feasibility
+    }
+
+    fn argmax(&self, surrogates: &Surrogates, incumbent: Option<f64>, candidates: &[Vec<f64>]) -> Option<usize> {
+        candidates
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (i, self.acquisition(surrogates, incumbent, x)))
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(i, _)| i)
+    }
+
+    fn kriging_believer(
+        &self,
+        mut surrogates: Surrogates,
+        mut candidates: Vec<Vec<f64>>,
+        q: usize,
+    ) -> Result<Vec<Vec<f64>>> {
+        let mut fantasies = self.observations.clone();
+        let mut batch = Vec::with_capacity(q);
+
+        for _ in 0..q {
+            let incumbent = Self::incumbent(&fantasies);
+            let Some(idx) = self.argmax(&surrogates, incumbent, &candidates) else {
+                break;
+            };
+            let x = candidates.swap_remove(idx);
+
+            // Believe the posterior mean and condition on it
+            let objective = surrogates.objective.predict(&x).0;
+            let constraint_values: Vec<f64> = surrogates.constraints.iter().map(|gp| gp.predict(&x).0).collect();
+            let feasible = self.constraints.iter().zip(&constraint_values).all(|(c, v)| c.is_satisfied(*v));
+            fantasies.push(Observation { x: x.clone(), objective, constraint_values, feasible });
+            batch.push(x);
+
+            let xs: Vec<Vec<f64>> = fantasies.iter().map(|o| o.x.clone()).collect();
+            let ys: Vec<f64> = fantasies.iter().map(|o| o.objective).collect();
+            surrogates.objective = GaussianProcess::with_hyperparameters(
+                &xs,
+                &ys,
+                surrogates.objective.kernel(),
+                surrogates.objective.hyperparameters(),
+            )?;
+            for (c, gp) in surrogates.constraints.iter_mut().enumerate() {
+                let values: Vec<f64> = fantasies.iter().map(|o| o.constraint_values[c]).collect();
+                *gp = GaussianProcess::with_hyperparameters(&xs, &values, gp.kernel(), gp.hyperparameters())?;
+            }
+        }
+
+        Ok(batch)
+    }
+
+    /// Greedy q-EI: each step adds the candidate maximizing the Monte Carlo
+    /// expected improvement of the whole batch so far. Improvement of each
+    /// point is weighted by its probability of feasibility.
+    fn q_expected_improvement(
+        &mut self,
+        surrogates: &Surrogates,
+        candidates: Vec<Vec<f64>>,
+        q: usize,
+        mc_samples: usize,
+    ) -> Vec<Vec<f64>> {
+        let incumbent = Self::incumbent(&self.observations);
+
+        // Shortlist by single-point acquisition to keep the joint step cheap
+        let mut scored: Vec<(f64, Vec<f64>)> = candidates
+            .into_iter()
+            .map(|x| (self.acquisition(surrogates, incumbent, &x), x))
+            .collect();
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate((8 * q).max(32));
+        let shortlist: Vec<Vec<f64>> = scored.into_iter().map(|(_, x)| x).collect();
+
+        let Some(best) = incumbent else {
+            return shortlist.into_iter().take(q).collect();
+        };
+        let feasibility: Vec<f64> = shortlist.iter().map(|x| self.feasibility(surrogates, x)).collect();
+
+        let mut selected: Vec<usize> = Vec::new();
+        for step in 0..q.min(shortlist.len()) {
+            // Common random numbers across candidates within a step
+            let z: Vec<Vec<f64>> = (0..mc_samples)
+                .map(|_| (0..=step).map(|_| standard_normal(&mut self.rng)).collect())
+                .collect();
+
+            let mut best_choice: Option<(usize, f64)> = None;
+            for c in 0..shortlist.len() {
+                if selected.contains(&c) {
+                    continue;
+                }
+                let members: Vec<usize> = selected.iter().copied().chain(std::iter::once(c)).collect();
+                let points: Vec<Vec<f64>> = members.iter().map(|&i| shortlist[i].clone()).collect();
+                let (means, cov) = surrogates.objective.predict_joint(&points);
+                let l = joint_cholesky(cov);
+
+                let value = z
+                    .iter()
+                    .map(|zs| {
+                        let sample = &l * DVector::from_column_slice(zs);
+                        members
+                            .iter()
+                            .enumerate()
+                            .map(|(k, &i)| feasibility[i] * (means[k] + sample[k] - best - self.config.xi).max(0.0))
+                            .fold(0.0, f64::max)
+                    })
+                    .sum::<f64>()
+                    / mc_samples as f64;
+
+                if best_choice.map_or(true, |(_, v)| value > v) {
+                    best_choice = Some((c, value));
+                }
+            }
+            if let Some((c, _)) = best_choice {
+                selected.push(c);
+            }
+        }
+
+        selected.into_iter().map(|i| shortlist[i].clone()).collect()
+    }
+}
+
+/// Lower Cholesky factor of a posterior covariance, with jitter, falling
+/// back to independent marginals
+fn joint_cholesky(cov: DMatrix<f64>) -> DMatrix<f64> {
+    let n = cov.nrows();
+    for jitter in [1e-10, 1e-8, 1e-6] {
+        let mut k = cov.clone();
+        for i in 0..n {
+            k[(i, i)] += jitter;
+        }
+        if let Some(chol) = k.cholesky() {
+            return chol.l();
+        }
+    }
+    DMatrix::from_fn(n, n, |i, j| if i == j { cov[(i, i)].max(0.0).sqrt() } else { 0.0 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::OptimizationObjective;
+    use std::collections::HashMap;
+
+    fn space3() -> CompositionSpace {
+        CompositionSpace::new(vec!["Fe".into(), "Co".into(), "Ni".into()]).unwrap()
+    }
+
+    /// Smooth objective peaking at Fe0.6Co0.3Ni0.1
+    fn objective(x: &[f64]) -> f64 {
+        let optimum = [0.6, 0.3, 0.1];
+        -x.iter().zip(optimum).map(|(a, b)| (a - b).powi(2)).sum::<f64>()
+    }
+
+    fn run(strategy: BatchStrategy, constraints: Vec<OutcomeConstraint>) -> BayesianOptimizer {
+        let config = BayesianOptimizerConfig { batch_strategy: strategy, n_candidates: 200, ..Default::default() };
+        let mut optimizer = BayesianOptimizer::new(space3(), config).with_constraints(constraints);
+        for _ in 0..8 {
+            for x in optimizer.ask(3).unwrap() {
+                let constraint_values = optimizer.constraints().iter().map(|_| x[2]).collect();
+                optimizer.tell(x.clone(), objective(&x), constraint_values).unwrap();
+            }
+        }
+        optimizer
+    }
+
+    #[test]
+    fn test_simplex_projection_and_sampling() {
+        let space = space3().with_min_fraction("Ni", 0.2).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..50 {
+            let x = space.sample(&mut rng);
+            assert!((x.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            assert!(x[2] >= 0.2 - 1e-12);
+        }
+
+        let projected = space.project(&[2.0, -1.0, 0.0]);
+        assert!((projected.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!((projected[0] - 0.8).abs() < 1e-9 && (projected[2] - 0.2).abs() < 1e-9);
+
+        assert_eq!(space.formula(&[0.25, 0.75, 0.0]), "Fe0.25Co0.75");
+    }
+
+    #[test]
+    fn test_space_from_target() {
+        let target = DiscoveryTarget {
+            target_properties: HashMap::new(),
+            required_elements: vec!["Mn".into()],
+            forbidden_elements: vec!["Co".into()],
+            application: None,
+            objective: OptimizationObjective::Maximize("x".into()),
+            economics: None,
+        };
+        let space = CompositionSpace::from_target(&["Fe".into(), "Co".into()], &target, 0.1).unwrap();
+        assert_eq!(space.elements(), ["Fe".to_string(), "Mn".to_string()]);
+
+        let mut rng = StdRng::seed_from_u64(3);
+        assert!(space.sample(&mut rng)[1] >= 0.1);
+    }
+
+    #[test]
+    fn test_kriging_believer_converges() {
+        let optimizer = run(BatchStrategy::KrigingBeliever, Vec::new());
+        let best = optimizer.best().unwrap();
+        assert!(best.objective > -0.01, "best {:?}", best);
+    }
+
+    #[test]
+    fn test_q_expected_improvement_batches() {
+        let mut optimizer = run(BatchStrategy::QExpectedImprovement { mc_samples: 64 }, Vec::new());
+        assert!(optimizer.best().unwrap().objective > -0.02);
+
+        let batch = optimizer.ask(4).unwrap();
+        assert_eq!(batch.len(), 4);
+        for (i, a) in batch.iter().enumerate() {
+            for b in &batch[i + 1..] {
+                assert!(a.iter().zip(b).any(|(u, v)| (u - v).abs() > 1e-9));
+            }
+        }
+    }
+
+    #[test]
+    fn test_outcome_constraints_respected() {
+        // Ni fraction must be at least 0.3, so the unconstrained optimum is infeasible
+        let constraint = OutcomeConstraint { name: "ni".into(), lower: 0.3, upper: 1.0 };
+        let optimizer = run(BatchStrategy::KrigingBeliever, vec![constraint]);
+
+        let best = optimizer.best().unwrap();
+        assert!(best.x[2] >= 0.3);
+        assert!(best.objective > -0.1);
+    }
+}
@@ -0,0 +1,667 @@
+//! Cell Reduction and Transformation
+//!
+//! Changes of unit cell for [`Structure`]s. Every operation returns a
+//! [`CellTransformation`] carrying the site mapping, so per-site data
+//! (magnetic moments, charges, forces) can follow the atoms:
+//! - Niggli reduction (Křivý–Gruber with the epsilon handling of
+//!   Grosse-Kunstleve et al. 2004) and Delaunay (Selling) reduction
+//! - Primitive cells from the pure translations of the decorated lattice
+//! - Conventional cells from the metric symmetry of the primitive lattice;
+//!   atomic decoration that lowers the symmetry is not detected
+//! - Supercells from arbitrary integer matrices
+//!
+//! Transformation matrices act on lattice rows: `new = P · old`.
+
+use crate::material::{CrystalSystem, Site, Structure};
+use crate::{Error, Result};
+use nalgebra::{Matrix3, RowVector3};
+use serde::{Deserialize, Serialize};
+
+/// Metric tolerance for Niggli and Delaunay reduction, relative to V^(2/3)
+pub const REDUCTION_TOLERANCE: f64 = 1e-5;
+
+/// Default distance (Å) below which two sites coincide
+pub const DEFAULT_SYMPREC: f64 = 0.01;
+
+const MAX_REDUCTION_STEPS: usize = 1000;
+
+/// Fractional coordinates this close to 1 wrap to 0
+const WRAP_EPS: f64 = 1e-8;
+
+/// A structure in a new cell and how it relates to the original
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CellTransformation {
+    pub structure: Structure,
+    /// Rows are the new lattice vectors in terms of the original ones;
+    /// fractional when the cell shrinks
+    pub matrix: [[f64; 3]; 3],
+    /// `site_map[i]` is the original site that new site i comes from
+    pub site_map: Vec<usize>,
+}
+
+impl CellTransformation {
+    /// Carry per-site values of the original structure to the new cell
+    ///
+    /// `values` holds one entry per original site; a shorter slice is an error.
+    pub fn map_site_values<T: Clone>(&self, values: &[T]) -> Result<Vec<T>> {
+        self.site_map
+            .iter()
+            .map(|&i| {
+                values.get(i).cloned().ok_or_else(|| {
+                    Error::invalid_input(format!("No value for original site {} ({} values given)", i, values.len()))
+                })
+            })
+            .collect()
+    }
+
+    /// Compose with a transformation of the resulting structure
+    fn then(self, next: CellTransformation) -> CellTransformation {
+        CellTransformation {
+            structure: next.structure,
+            matrix: from_matrix(&(to_matrix(&next.matrix) * to_matrix(&self.matrix))),
+            site_map: next.site_map.iter().map(|&i| self.site_map[i]).collect(),
+        }
+    }
+}
+
+fn to_matrix(rows: &[[f64; 3]; 3]) -> Matrix3<f64> {
+    Matrix3::from_fn(|i, j| rows[i][j])
+}
+
+fn from_matrix(m: &Matrix3<f64>) -> [[f64; 3]; 3] {
+    std::array::from_fn(|i| std::array::from_fn(|j| m[(i, j)]))
+}
+
+fn row(v: [f64; 3]) -> RowVector3<f64> {
+    RowVector3::new(v[0], v[1], v[2])
+}
+
+fn wrap_unit(x: f64) -> f64 {
+    let w = x - x.floor();
+    if w > 1.0 - WRAP_EPS {
+        0.0
+    } else {
+        w
+    }
+}
+
+fn checked_volume(structure: &Structure) -> Result<f64> {
+    let volume = structure.volume();
+    if volume < 1e-8 {
+        return Err(Error::invalid_input("Structure lattice is degenerate"));
+    }
+    Ok(volume)
+}
+
+/// Cartesian distance between fractional positions, nearest image
+fn periodic_distance(lattice: &Matrix3<f64>, u: &[f64; 3], v: &[f64; 3]) -> f64 {
+    let d = RowVector3::from_fn(|_, k| {
+        let x = u[k] - v[k];
+        x - x.round()
+    });
+    (d * lattice).norm()
+}
+
+/// Sites that may be exchanged by a translation
+fn same_species(a: &Site, b: &Site) -> bool {
+    let magmom = match (a.magmom, b.magmom) {
+        (Some(x), Some(y)) => (x - y).abs() < 1e-3,
+        (None, None) => true,
+        _ => false,
+    };
+    a.element == b.element && (a.occupancy - b.occupancy).abs() < 1e-6 && magmom
+}
+
+/// Re-express a structure in another basis of the same lattice
+fn change_basis(structure: &Structure, lattice: Matrix3<f64>) -> Result<CellTransformation> {
+    let old = to_matrix(&structure.lattice);
+    let inverse = old.try_inverse().ok_or_else(|| Error::invalid_input("Structure lattice is degenerate"))?;
+    let matrix = (lattice * inverse).map(f64::round);
+    let back = matrix
+        .try_inverse()
+        .ok_or_else(|| Error::computation("Cell transformation is singular"))?;
+
+    let sites = structure
+        .sites
+        .iter()
+        .map(|site| {
+            let f = row(site.coords) * back;
+            Site { coords: [wrap_unit(f[0]), wrap_unit(f[1]), wrap_unit(f[2])], ..site.clone() }
+        })
+        .collect();
+
+    Ok(CellTransformation {
+        structure: Structure { lattice: from_matrix(&(matrix * old)), sites, ..structure.clone() },
+        matrix: from_matrix(&matrix),
+        site_map: (0..structure.sites.len()).collect(),
+    })
+}
+
+/// Niggli-reduced cell of the same lattice
+pub fn niggli_reduce(structure: &Structure) -> Result<CellTransformation> {
+    let eps = REDUCTION_TOLERANCE * checked_volume(structure)?.powf(2.0 / 3.0);
+    let [mut a, mut b, mut c] = structure.lattice.map(row);
+    let sign = |x: f64| if x.abs() < eps { 0 } else if x > 0.0 { 1 } else { -1 };
+    let unit = |x: f64| if x > 0.0 { 1.0 } else { -1.0 };
+
+    for _ in 0..MAX_REDUCTION_STEPS {
+        let (aa, bb, cc) = (a.dot(&a), b.dot(&b), c.dot(&c));
+        let (xi, eta, zeta) = (2.0 * b.dot(&c), 2.0 * a.dot(&c), 2.0 * a.dot(&b));
+
+        // A1, A2: sort the lengths, keeping the cell right-handed
+        if aa > bb + eps || ((aa - bb).abs() < eps && xi.abs() > eta.abs() + eps) {
+            std::mem::swap(&mut a, &mut b);
+            c = -c;
+            continue;
+        }
+        if bb > cc + eps || ((bb - cc).abs() < eps && eta.abs() > zeta.abs() + eps) {
+            std::mem::swap(&mut b, &mut c);
+            a = -a;
+            continue;
+        }
+
+        // A3, A4: make the off-diagonal terms all positive or all non-positive
+        let (l, m, n) = (sign(xi), sign(eta), sign(zeta));
+        let flip = |s: i32, from: i32| if s == from { -1.0 } else { 1.0 };
+        let (mut i, mut j, mut k) = if l * m * n == 1 {
+            (flip(l, -1), flip(m, -1), flip(n, -1))
+        } else {
+            (flip(l, 1), flip(m, 1), flip(n, 1))
+        };
+        if l * m * n != 1 && i * j * k < 0.0 {
+            if n == 0 {
+                k = -1.0;
+            } else if m == 0 {
+                j = -1.0;
+            } else if l == 0 {
+                i = -1.0;
+            }
+        }
+        a *= i;
+        b *= j;
+        c *= k;
+        let (xi, eta, zeta) = (2.0 * b.dot(&c), 2.0 * a.dot(&c), 2.0 * a.dot(&b));
+
+        // A5 - A8: shorten by adding or subtracting other vectors
+        if xi.abs() > bb + eps || ((bb - xi).abs() < eps && 2.0 * eta < zeta - eps) || ((bb + xi).abs() < eps && zeta < -eps)
+        {
+            c -= b * unit(xi);
+            continue;
+        }
+        if eta.abs() > aa + eps || ((aa - eta).abs() < eps && 2.0 * xi < zeta - eps) || ((aa + eta).abs() < eps && zeta < -eps)
+        {
+            c -= a * unit(eta);
+            continue;
+        }
+        if zeta.abs() > aa + eps || ((aa - zeta).abs() < eps && 2.0 * xi < eta - eps) || ((aa + zeta).abs() < eps && eta < -eps)
+        {
+            b -= a * unit(zeta);
+            continue;
+        }
+        let sum = xi + eta + zeta + aa + bb;
+        if sum < -eps || (sum.abs() < eps && 2.0 * (aa + eta) + zeta > eps) {
+            c += a + b;
+            continue;
+        }
+
+        return change_basis(structure, Matrix3::from_rows(&[a, b, c]));
+    }
+    Err(Error::computation("Niggli reduction did not converge"))
+}
+
+/// Delaunay-reduced cell of the same lattice
+///
+/// Selling reduction makes the four vectors `a, b, c, -(a + b + c)`
+/// pairwise non-acute; the cell is the shortest basis among them and their
+/// pairwise sums.
+pub fn delaunay_reduce(structure: &Structure) -> Result<CellTransformation> {
+    let volume = checked_volume(structure)?;
+    let eps = REDUCTION_TOLERANCE * volume.powf(2.0 / 3.0);
+    let [a, b, c] = structure.lattice.map(row);
+    let mut vectors = [a, b, c, -(a + b + c)];
+
+    for _ in 0..MAX_REDUCTION_STEPS {
+        let pair = (0..4)
+            .flat_map(|i| (i + 1..4).map(move |j| (i, j)))
+            .find(|&(i, j)| vectors[i].dot(&vectors[j]) > eps);
+        let Some((i, j)) = pair else {
+            let [v0, v1, v2, v3] = vectors;
+            let mut candidates = [v0, v1, v2, v3, v0 + v1, v0 + v2, v0 + v3];
+            candidates.sort_by(|x, y| x.norm_squared().partial_cmp(&y.norm_squared()).unwrap_or(std::cmp::Ordering::Equal));
+            for (x, y, z) in (0..7).flat_map(|x| (x + 1..7).flat_map(move |y| (y + 1..7).map(move |z| (x, y, z)))) {
+                let mut basis = Matrix3::from_rows(&[candidates[x], candidates[y], candidates[z]]);
+                if (basis.determinant().abs() - volume).abs() < 1e-6 * volume {
+                    if basis.determinant() < 0.0 {
+                        basis = -basis;
+                    }
+                    return change_basis(structure, basis);
+                }
+            }
+            return Err(Error::computation("Delaunay reduction found no basis"));
+        };
+        let pivot = vectors[i];
+        for (k, v) in vectors.iter_mut().enumerate() {
+            if k != i && k != j {
+                *v += pivot;
+            }
+        }
+        vectors[i] = -pivot;
+    }
+    Err(Error::computation("Delaunay reduction did not converge"))
+}
+
+/// Fractional translations (other than lattice vectors) mapping the
+/// structure onto itself
+fn pure_translations(structure: &Structure, symprec: f64) -> Vec<[f64; 3]> {
+    let lattice = to_matrix(&structure.lattice);
+    let sites = &structure.sites;
+
+    // Candidates join one site of the rarest species to the others
+    let count = |s: &Site| sites.iter().filter(|o| same_species(s, o)).count();
+    let Some(anchor) = (0..sites.len()).min_by_key(|&i| (count(&sites[i]), sites[i].element.clone())) else {
+        return Vec::new();
+    };
+
+    sites
+        .iter()
+        .filter(|s| same_species(s, &sites[anchor]))
+        .map(|s| std::array::from_fn(|k| wrap_unit(s.coords[k] - sites[anchor].coords[k])))
+        .filter(|t: &[f64; 3]| periodic_distance(&lattice, t, &[0.0; 3]) > symprec)
+        .filter(|t| {
+            sites.iter().all(|site| {
+                let moved: [f64; 3] = std::array::from_fn(|k| site.coords[k] + t[k]);
+                sites
+                    .iter()
+                    .any(|other| same_species(site, other) && periodic_distance(&lattice, &moved, &other.coords) < symprec)
+            })
+        })
+        .collect()
+}
+
+/// Upper-triangular basis (Hermite normal form up to reduction above the
+/// diagonal) of the integer lattice spanned by full-rank `rows`
+fn hermite_basis(mut rows: Vec<[i64; 3]>) -> [[i64; 3]; 3] {
+    for column in 0..3 {
+        // Euclid on the column: keep the smallest nonzero entry as pivot
+        while let Some(pivot) =
+            (column..rows.len()).filter(|&r| rows[r][column] != 0).min_by_key(|&r| rows[r][column].abs())
+        {
+            rows.swap(column, pivot);
+            let head = rows[column];
+            let mut done = true;
+            for row in rows.iter_mut().skip(column + 1) {
+                let q = row[column].div_euclid(head[column]);
+                for (x, h) in row.iter_mut().zip(head) {
+                    *x -= q * h;
+                }
+                done &= row[column] == 0;
+            }
+            if done {
+                break;
+            }
+        }
+        if rows[column][column] < 0 {
+            rows[column] = rows[column].map(|x| -x);
+        }
+    }
+    [rows[0], rows[1], rows[2]]
+}
+
+/// Smallest cell reproducing the structure, Niggli-reduced
+///
+/// Sites related by a pure translation collapse to the first one, which
+/// `site_map` records.
+pub fn primitive_cell(structure: &Structure, symprec: f64) -> Result<CellTransformation> {
+    if structure.sites.is_empty() {
+        return Err(Error::invalid_input("Structure has no sites"));
+    }
+    // Start from a reduced cell so nearest images are found by rounding
+    let reduced = niggli_reduce(structure)?;
+    let translations = pure_translations(&reduced.structure, symprec);
+    if translations.is_empty() {
+        return Ok(reduced);
+    }
+    let points = translations.len() + 1;
+    let lattice = to_matrix(&reduced.structure.lattice);
+
+    // The translations form a group of order `points`, so scaled by it they
+    // are integer vectors; with the scaled unit cell they generate the
+    // primitive lattice, whose Hermite normal form is a basis
+    let n = points as i64;
+    let mut generators = vec![[n, 0, 0], [0, n, 0], [0, 0, n]];
+    for t in &translations {
+        let scaled = t.map(|x| x * points as f64);
+        if scaled.iter().any(|x| (x - x.round()).abs() > 0.25) {
+            return Err(Error::computation("Pure translations do not form a group"));
+        }
+        generators.push(scaled.map(|x| x.round() as i64));
+    }
+    let hermite = hermite_basis(generators);
+    if hermite.iter().enumerate().map(|(k, r)| r[k]).product::<i64>() != n * n {
+        return Err(Error::computation("No primitive basis found"));
+    }
+    let basis = Matrix3::from_fn(|i, j| hermite[i][j] as f64 / points as f64);
+    let back = basis.try_inverse().ok_or_else(|| Error::computation("Primitive basis is singular"))?;
+    let primitive_lattice = basis * lattice;
+
+    let mut sites: Vec<Site> = Vec::new();
+    let mut site_map = Vec::new();
+    for (index, site) in reduced.structure.sites.iter().enumerate() {
+        let f = row(site.coords) * back;
+        let coords = [wrap_unit(f[0]), wrap_unit(f[1]), wrap_unit(f[2])];
+        let seen = sites
+            .iter()
+            .any(|s| same_species(s, site) && periodic_distance(&primitive_lattice, &s.coords, &coords) < symprec);
+        if !seen {
+            sites.push(Site { coords, ..site.clone() });
+            site_map.push(index);
+        }
+    }
+    if sites.len() * points != reduced.structure.sites.len() {
+        return Err(Error::computation(format!(
+            "Primitive cell has {} sites, expected {}",
+            sites.len(),
+            reduced.structure.sites.len() / points
+        )));
+    }
+
+    let primitive = CellTransformation {
+        structure: Structure { lattice: from_matrix(&primitive_lattice), sites, ..reduced.structure.clone() },
+        matrix: from_matrix(&basis),
+        site_map,
+    };
+    let reduced_primitive = niggli_reduce(&primitive.structure)?;
+    Ok(reduced.then(primitive).then(reduced_primitive))
+}
+
+/// Lattice system of a cell in standard orientation, if it is in one
+fn oriented_system(cell: &Matrix3<f64>, symprec: f64) -> Option<CrystalSystem> {
+    let lengths: [f64; 3] = std::array::from_fn(|i| cell.row(i).norm());
+    let angle = |i: usize, j: usize| (cell.row(i).dot(&cell.row(j)) / (lengths[i] * lengths[j])).acos().to_degrees();
+    let (alpha, beta, gamma) = (angle(1, 2), angle(0, 2), angle(0, 1));
+
+    let angle_tol = (symprec / lengths.iter().cloned().fold(f64::INFINITY, f64::min)).to_degrees().max(1e-3);
+    let same = |x: f64, y: f64| (x - y).abs() < symprec;
+    let right = |x: f64| (x - 90.0).abs() < angle_tol;
+
+    if right(alpha) && right(beta) && right(gamma) {
+        if same(lengths[0], lengths[1]) && same(lengths[1], lengths[2]) {
+            Some(CrystalSystem::Cubic)
+        } else if same(lengths[0], lengths[1]) {
+            Some(CrystalSystem::Tetragonal)
+        } else if lengths[0] <= lengths[1] + symprec && lengths[1] <= lengths[2] + symprec {
+            Some(CrystalSystem::Orthorhombic)
+        } else {
+            None
+        }
+    } else if right(alpha) && right(beta) && (gamma - 120.0).abs() < angle_tol && same(lengths[0], lengths[1]) {
+        Some(CrystalSystem::Hexagonal)
+    } else if right(alpha) && right(gamma) && beta > 90.0 && lengths[0] <= lengths[2] + symprec {
+        Some(CrystalSystem::Monoclinic)
+    } else {
+        None
+    }
+}
+
+fn symmetry_rank(system: CrystalSystem) -> u8 {
+    match system {
+        CrystalSystem::Triclinic => 0,
+        CrystalSystem::Monoclinic => 1,
+        CrystalSystem::Orthorhombic => 2,
+        CrystalSystem::Tetragonal => 3,
+        CrystalSystem::Trigonal | CrystalSystem::Hexagonal => 4,
+        CrystalSystem::Cubic => 5,
+    }
+}
+
+/// Symmetry rank, determinant, summed axis lengths, matrix and lattice
+/// system of a candidate conventional cell
+type ConventionalCandidate = (u8, i32, f64, [[i32; 3]; 3], CrystalSystem);
+
+/// Conventional standard cell
+///
+/// Searches cells spanned by primitive lattice vectors (coefficients in
+/// [-2, 2] for a and b, [-3, 3] for c, which reaches the long axis of acute
+/// rhombohedra) for the highest metric symmetry, then the smallest volume.
+/// Axes follow the usual settings (unique axis c for tetragonal and
+/// hexagonal, b for monoclinic, a ≤ b ≤ c for orthorhombic); a hexagonal
+/// cell three times the primitive one is the rhombohedral (trigonal) lattice.
+pub fn conventional_cell(structure: &Structure, symprec: f64) -> Result<CellTransformation> {
+    let primitive = primitive_cell(structure, symprec)?;
+    let lattice = to_matrix(&primitive.structure.lattice);
+
+    let lattice_vectors = |range: i32| -> Vec<([i32; 3], RowVector3<f64>)> {
+        let width = 2 * range + 1;
+        (0..width.pow(3))
+            .map(|code| [code % width - range, (code / width) % width - range, code / (width * width) - range])
+            .filter(|n| *n != [0, 0, 0])
+            .map(|n| (n, row(n.map(f64::from)) * lattice))
+            .collect()
+    };
+    let vectors = lattice_vectors(2);
+    let axes = lattice_vectors(3);
+    let shortest = vectors.iter().map(|(_, v)| v.norm()).fold(f64::INFINITY, f64::min);
+    let angle_tol = (symprec / shortest).to_degrees().max(1e-3);
+    let angle = |u: &RowVector3<f64>, v: &RowVector3<f64>| {
+        (u.dot(v) / (u.norm() * v.norm())).clamp(-1.0, 1.0).acos().to_degrees()
+    };
+
+    // Every standard setting has b ⊥ c and γ of 90° or 120°
+    let mut best: Option<ConventionalCandidate> = None;
+    for (nb, b) in &vectors {
+        for (nc, c) in &axes {
+            if (angle(b, c) - 90.0).abs() > angle_tol {
+                continue;
+            }
+            for (na, a) in &vectors {
+                let gamma = angle(a, b);
+                if (gamma - 90.0).abs() > angle_tol && (gamma - 120.0).abs() > angle_tol {
+                    continue;
+                }
+                let matrix = [*na, *nb, *nc];
+                let det = Matrix3::from_fn(|i, j| matrix[i][j] as f64).determinant().round() as i32;
+                if !(1..=4).contains(&det) {
+                    continue;
+                }
+                let cell = Matrix3::from_rows(&[*a, *b, *c]);
+                let Some(mut system) = oriented_system(&cell, symprec) else {
+                    continue;
+                };
+                if system == CrystalSystem::Hexagonal && det == 3 {
+                    system = CrystalSystem::Trigonal;
+                }
+                let rank = symmetry_rank(system);
+                let length = a.norm() + b.norm() + c.norm();
+                let better = best.as_ref().map_or(true, |(r, d, l, _, _)| {
+                    rank > *r || (rank == *r && (det < *d || (det == *d && length < l - symprec)))
+                });
+                if better {
+                    best = Some((rank, det, length, matrix, system));
+                }
+            }
+        }
+    }
+
+    let Some((_, _, _, matrix, system)) = best else {
+        let mut result = primitive;
+        result.structure.crystal_system = Some(CrystalSystem::Triclinic);
+        return Ok(result);
+    };
+    let supercell = make_supercell(&primitive.structure, matrix)?;
+    let mut result = primitive.then(supercell);
+    result.structure.crystal_system = Some(system);
+    Ok(result)
+}
+
+/// Supercell spanned by integer combinations of the lattice vectors
+///
+/// Rows of `matrix` give the new vectors; the determinant must be positive
+/// and is the number of copies of each site. Copies are listed per original
+/// site, in order.
+pub fn make_supercell(structure: &Structure, matrix: [[i32; 3]; 3]) -> Result<CellTransformation> {
+    checked_volume(structure)?;
+    let p = Matrix3::from_fn(|i, j| matrix[i][j] as f64);
+    let det = p.determinant().round() as i64;
+    if det <= 0 {
+        return Err(Error::invalid_input(format!(
+            "Supercell matrix must have a positive determinant, got {}",
+            det
+        )));
+    }
+    let back = p.try_inverse().ok_or_else(|| Error::computation("Supercell matrix is singular"))?;
+
+    // Corners of the new cell bound the lattice translations to try
+    let mut low = [0i32; 3];
+    let mut high = [0i32; 3];
+    for corner in 0..8 {
+        for k in 0..3 {
+            let x: i32 = (0..3).filter(|i| corner & (1 << i) != 0).map(|i| matrix[i][k]).sum();
+            low[k] = low[k].min(x);
+            high[k] = high[k].max(x);
+        }
+    }
+
+    let inside = |x: f64| x > -WRAP_EPS && x < 1.0 - WRAP_EPS;
+    let mut sites = Vec::new();
+    let mut site_map = Vec::new();
+    for (index, site) in structure.sites.iter().enumerate() {
+        let origin = site.coords.map(wrap_unit);
+        for m0 in low[0] - 1..=high[0] {
+            for m1 in low[1] - 1..=high[1] {
+                for m2 in low[2] - 1..=high[2] {
+                    let shifted = [origin[0] + m0 as f64, origin[1] + m1 as f64, origin[2] + m2 as f64];
+                    let f = row(shifted) * back;
+                    if f.iter().all(|&x| inside(x)) {
+                        sites.push(Site { coords: [wrap_unit(f[0]), wrap_unit(f[1]), wrap_unit(f[2])], ..site.clone() });
+                        site_map.push(index);
+                    }
+                }
+            }
+        }
+    }
+    if sites.len() != structure.sites.len() * det as usize {
+        return Err(Error::computation(format!(
+            "Supercell has {} sites, expected {}",
+            sites.len(),
+            structure.sites.len() * det as usize
+        )));
+    }
+
+    Ok(CellTransformation {
+        structure: Structure {
+            lattice: from_matrix(&(p * to_matrix(&structure.lattice))),
+            sites,
+            ..structure.clone()
+        },
+        matrix: from_matrix(&p),
+        site_map,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn structure(lattice: [[f64; 3]; 3], sites: &[(&str, [f64; 3])]) -> Structure {
+        Structure {
+            lattice,
+            sites: sites
+                .iter()
+                .map(|(e, c)| Site { element: e.to_string(), coords: *c, magmom: None, occupancy: 1.0 })
+                .collect(),
+            space_group: None,
+            crystal_system: None,
+        }
+    }
+
+    fn rock_salt(a: f64) -> Structure {
+        let fcc = [[0.0, 0.0, 0.0], [0.0, 0.5, 0.5], [0.5, 0.0, 0.5], [0.5, 0.5, 0.0]];
+        let mut sites: Vec<(&str, [f64; 3])> = fcc.iter().map(|f| ("Na", *f)).collect();
+        sites.extend(fcc.iter().map(|f| ("Cl", [f[0] + 0.5, f[1], f[2]])));
+        structure([[a, 0.0, 0.0], [0.0, a, 0.0], [0.0, 0.0, a]], &sites)
+    }
+
+    #[test]
+    fn test_reductions_keep_the_lattice() {
+        // A skewed basis of a simple orthorhombic lattice
+        let skewed = structure(
+            [[3.0, 0.0, 0.0], [3.0, 4.0, 0.0], [6.0, -4.0, 5.0]],
+            &[("Si", [0.1, 0.2, 0.3]), ("O", [0.6, 0.1, 0.9])],
+        );
+        for reduced in [niggli_reduce(&skewed).unwrap(), delaunay_reduce(&skewed).unwrap()] {
+            let (lengths, angles) = reduced.structure.lattice_parameters();
+            let mut sorted = lengths;
+            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            assert!(sorted.iter().zip([3.0, 4.0, 5.0]).all(|(x, y)| (x - y).abs() < 1e-9), "{:?}", lengths);
+            assert!(angles.iter().all(|a| (a - 90.0).abs() < 1e-6));
+            assert!((reduced.structure.volume() - skewed.volume()).abs() < 1e-9);
+            assert_eq!(reduced.site_map, vec![0, 1]);
+            let cartesian = |s: &Structure, i: usize| s.to_cartesian(s.sites[i].coords);
+            // Same atoms up to lattice translations
+            for i in 0..2 {
+                let d: Vec<f64> = (0..3).map(|k| cartesian(&reduced.structure, i)[k] - cartesian(&skewed, i)[k]).collect();
+                let f = reduced.structure.to_fractional([d[0], d[1], d[2]]);
+                assert!(f.iter().all(|x| (x - x.round()).abs() < 1e-9));
+            }
+        }
+        assert_eq!(niggli_reduce(&skewed).unwrap().structure.lattice_parameters().0, [3.0, 4.0, 5.0]);
+    }
+
+    #[test]
+    fn test_primitive_conventional_round_trip() {
+        let a = 5.64;
+        let conventional = rock_salt(a);
+        let primitive = primitive_cell(&conventional, DEFAULT_SYMPREC).unwrap();
+        assert_eq!(primitive.structure.sites.len(), 2);
+        assert!((primitive.structure.volume() - a.powi(3) / 4.0).abs() < 1e-6);
+        let (lengths, angles) = primitive.structure.lattice_parameters();
+        assert!(lengths.iter().all(|l| (l - a / 2f64.sqrt()).abs() < 1e-6));
+        assert!(angles.iter().all(|x| (x - 60.0).abs() < 1e-6));
+        let elements =
+            primitive.map_site_values(&conventional.sites.iter().map(|s| s.element.clone()).collect::<Vec<_>>()).unwrap();
+        assert_eq!(elements, primitive.structure.sites.iter().map(|s| s.element.clone()).collect::<Vec<_>>());
+
+        let standard = conventional_cell(&primitive.structure, DEFAULT_SYMPREC).unwrap();
+        assert_eq!(standard.structure.crystal_system, Some(CrystalSystem::Cubic));
+        assert_eq!(standard.structure.sites.len(), 8);
+        let (lengths, _) = standard.structure.lattice_parameters();
+        assert!(lengths.iter().all(|l| (l - a).abs() < 1e-6));
+
+        // Hexagonal setting of a rhombohedral lattice
+        let rhombohedral = structure(
+            [[1.0, 0.0, 0.0], [-0.5, 0.75f64.sqrt(), 0.0], [0.0, 0.0, 6.0]],
+            &[("Bi", [0.0, 0.0, 0.0]), ("Bi", [2.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0]), ("Bi", [1.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0])],
+        );
+        assert_eq!(primitive_cell(&rhombohedral, DEFAULT_SYMPREC).unwrap().structure.sites.len(), 1);
+        let standard = conventional_cell(&rhombohedral, DEFAULT_SYMPREC).unwrap();
+        assert_eq!(standard.structure.crystal_system, Some(CrystalSystem::Trigonal));
+        assert_eq!(standard.structure.sites.len(), 3);
+    }
+
+    #[test]
+    fn test_supercell_matrix() {
+        let cell = structure(
+            [[4.0, 0.0, 0.0], [0.0, 4.0, 0.0], [0.0, 0.0, 4.0]],
+            &[("Cs", [0.0, 0.0, 0.0]), ("Cl", [0.5, 0.5, 0.5])],
+        );
+        // √2 × √2 × 2 rotated supercell
+        let supercell = make_supercell(&cell, [[1, 1, 0], [-1, 1, 0], [0, 0, 2]]).unwrap();
+        assert_eq!(supercell.structure.sites.len(), 8);
+        assert!((supercell.structure.volume() - 4.0 * cell.volume()).abs() < 1e-9);
+        assert_eq!(supercell.site_map, vec![0, 0, 0, 0, 1, 1, 1, 1]);
+        assert_eq!(supercell.map_site_values(&[1.5, -1.5]).unwrap(), vec![1.5, 1.5, 1.5, 1.5, -1.5, -1.5, -1.5, -1.5]);
+        assert!(supercell.map_site_values(&[1.5]).is_err());
+
+        let back = primitive_cell(&supercell.structure, DEFAULT_SYMPREC).unwrap();
+        assert_eq!(back.structure.sites.len(), 2);
+        assert!((back.structure.volume() - cell.volume()).abs() < 1e-6);
+
+        // Large supercells reduce without a search over lattice vectors
+        let large = make_supercell(&rock_salt(5.64), [[3, 0, 0], [0, 2, 1], [0, 0, 2]]).unwrap();
+        assert_eq!(large.structure.sites.len(), 96);
+        let back = primitive_cell(&large.structure, DEFAULT_SYMPREC).unwrap();
+        assert_eq!(back.structure.sites.len(), 2);
+        assert!((back.structure.volume() - 5.64f64.powi(3) / 4.0).abs() < 1e-6);
+        assert!(make_supercell(&cell, [[1, 0, 0], [0, 1, 0], [0, 0, -1]]).is_err());
+    }
+}
@@ -0,0 +1,606 @@
+//! Clustering of embedding vectors
+//!
+//! - [`kmeans`]: Lloyd's algorithm with k-means++ seeding and several restarts
+//! - [`choose_k`]: picks k by mean silhouette over a range
+//! - [`dbscan`] and [`hdbscan`]: density-based, for unknown cluster counts;
+//!   points in no dense region are labelled noise
+//!
+//! All distances are Euclidean. The density methods are exact: quadratic
+//! time but linear memory (no distance matrix), and refuse inputs above
+//! [`MAX_DENSITY_POINTS`]. Silhouettes of large sets are estimated on a
+//! seeded sample of [`SILHOUETTE_SAMPLE_SIZE`] points.
+
+use crate::{Error, Result};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// Cluster label per point; `None` is noise
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Clustering {
+    pub labels: Vec<Option<usize>>,
+    pub n_clusters: usize,
+}
+
+impl Clustering {
+    fn from_labels(labels: Vec<Option<usize>>) -> Self {
+        let n_clusters = labels.iter().flatten().max().map_or(0, |m| m + 1);
+        Self { labels, n_clusters }
+    }
+
+    /// Point indices of each cluster
+    pub fn clusters(&self) -> Vec<Vec<usize>> {
+        let mut clusters = vec![Vec::new(); self.n_clusters];
+        for (i, label) in self.labels.iter().enumerate() {
+            if let Some(c) = label {
+                clusters[*c].push(i);
+            }
+        }
+        clusters
+    }
+
+    /// Point indices labelled noise
+    pub fn noise(&self) -> Vec<usize> {
+        self.labels.iter().enumerate().filter(|(_, l)| l.is_none()).map(|(i, _)| i).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KMeansConfig {
+    pub k: usize,
+    pub max_iterations: usize,
+    /// Restarts from fresh k-means++ seeds; the lowest inertia wins
+    pub n_init: usize,
+    /// Stop once no centroid moves further than this
+    pub tolerance: f64,
+    pub seed: u64,
+}
+
+impl KMeansConfig {
+    pub fn new(k: usize) -> Self {
+        Self { k, max_iterations: 100, n_init: 4, tolerance: 1e-6, seed: 42 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KMeansResult {
+    pub clustering: Clustering,
+    pub centroids: Vec<Vec<f64>>,
+    /// Sum of squared distances to the assigned centroid
+    pub inertia: f64,
+}
+
+/// Result of [`choose_k`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KSelection {
+    pub best_k: usize,
+    /// Mean silhouette of every k tried
+    pub scores: Vec<(usize, f64)>,
+    pub result: KMeansResult,
+}
+
+/// Method for `EmbeddingEngine::cluster_materials_with`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ClusterMethod {
+    KMeans(KMeansConfig),
+    /// k-means with k chosen by silhouette
+    AutoKMeans { k_min: usize, k_max: usize, seed: u64 },
+    Dbscan { eps: f64, min_points: usize },
+    Hdbscan { min_cluster_size: usize, min_samples: usize },
+}
+
+fn distance(a: &[f64], b: &[f64]) -> f64 {
+    squared_distance(a, b).sqrt()
+}
+
+fn squared_distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum()
+}
+
+/// Largest input [`dbscan`] and [`hdbscan`] accept
+pub const MAX_DENSITY_POINTS: usize = 20_000;
+
+/// Points whose silhouettes estimate the mean for larger inputs
+pub const SILHOUETTE_SAMPLE_SIZE: usize = 2_000;
+
+/// Distances from each sampled point to every point: all points when the
+/// input is small enough, else a seeded sample
+fn silhouette_rows(vectors: &[Vec<f64>]) -> Vec<(usize, Vec<f64>)> {
+    let mut sample: Vec<usize> = (0..vectors.len()).collect();
+    if sample.len() > SILHOUETTE_SAMPLE_SIZE {
+        let mut rng = StdRng::seed_from_u64(0);
+        sample = rand::seq::index::sample(&mut rng, vectors.len(), SILHOUETTE_SAMPLE_SIZE).into_vec();
+        sample.sort_unstable();
+    }
+    sample.into_iter()
+        .map(|i| (i, vectors.iter().map(|v| distance(&vectors[i], v)).collect()))
+        .collect()
+}
+
+fn check_density_size(vectors: &[Vec<f64>]) -> Result<()> {
+    if vectors.len() > MAX_DENSITY_POINTS {
+        return Err(Error::invalid_input(format!(
+            "Density clustering is limited to {} points, got {}", MAX_DENSITY_POINTS, vectors.len()
+        )));
+    }
+    Ok(())
+}
+
+fn validate(vectors: &[Vec<f64>]) -> Result<()> {
+    let dim = vectors.first().map_or(0, Vec::len);
+    if vectors.iter().any(|v| v.len() != dim || v.iter().any(|x| !x.is_finite())) {
+        return Err(Error::invalid_input("Vectors must share one dimension and be finite"));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// K-MEANS
+// ============================================================================
+
+pub fn kmeans(vectors: &[Vec<f64>], config: &KMeansConfig) -> Result<KMeansResult> {
+    validate(vectors)?;
+    if config.k == 0 || vectors.len() < config.k {
+        return Err(Error::invalid_input(format!(
+            "Cannot form {} clusters from {} points", config.k, vectors.len()
+        )));
+    }
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut best: Option<KMeansResult> = None;
+    for _ in 0..config.n_init.max(1) {
+        let run = lloyd(vectors, plus_plus_seeds(vectors, config.k, &mut rng), config);
+        if best.as_ref().map_or(true, |b| run.inertia < b.inertia) {
+            best = Some(run);
+        }
+    }
+    Ok(best.expect("at least one k-means run"))
+}
+
+/// k-means++: each further seed is drawn with probability proportional to
+/// its squared distance from the nearest seed so far
+fn plus_plus_seeds(vectors: &[Vec<f64>], k: usize, rng: &mut StdRng) -> Vec<Vec<f64>> {
+    let mut seeds = vec![vectors[rng.gen_range(0..vectors.len())].clone()];
+    let mut nearest: Vec<f64> = vectors.iter().map(|v| squared_distance(v, &seeds[0])).collect();
+    while seeds.len() < k {
+        let total: f64 = nearest.iter().sum();
+        let next = if total > 0.0 {
+            let mut target = rng.gen_range(0.0..total);
+            nearest.iter().position(|&w| {
+                target -= w;
+                target < 0.0
+            }).unwrap_or(vectors.len() - 1)
+        } else {
+            // Every point coincides with a seed
+            rng.gen_range(0..vectors.len())
+        };
+        seeds.push(vectors[next].clone());
+        for (d, v) in nearest.iter_mut().zip(vectors) {
+            *d = d.min(squared_distance(v, &vectors[next]));
+        }
+    }
+    seeds
+}
+
+fn lloyd(vectors: &[Vec<f64>], mut centroids: Vec<Vec<f64>>, config: &KMeansConfig) -> KMeansResult {
+    let dim = vectors[0].len();
+    let mut assignments = vec![0usize; vectors.len()];
+    for _ in 0..config.max_iterations {
+        for (a, v) in assignments.iter_mut().zip(vectors) {
+            *a = nearest_centroid(&centroids, v).0;
+        }
+        let mut sums = vec![vec![0.0; dim]; centroids.len()];
+        let mut counts = vec![0usize; centroids.len()];
+        for (&a, v) in assignments.iter().zip(vectors) {
+            counts[a] += 1;
+            for (s, x) in sums[a].iter_mut().zip(v) {
+                *s += x;
+            }
+        }
+        let mut shift: f64 = 0.0;
+        for ((centroid, sum), &count) in centroids.iter_mut().zip(sums).zip(&counts) {
+            // Empty clusters keep their centroid
+            if count > 0 {
+                let updated: Vec<f64> = sum.into_iter().map(|s| s / count as f64).collect();
+                shift = shift.max(distance(centroid, &updated));
+                *centroid = updated;
+            }
+        }
+        if shift <= config.tolerance {
+            break;
+        }
+    }
+
+    let mut inertia = 0.0;
+    for (a, v) in assignments.iter_mut().zip(vectors) {
+        let (c, d) = nearest_centroid(&centroids, v);
+        *a = c;
+        inertia += d;
+    }
+    KMeansResult {
+        clustering: Clustering { labels: assignments.into_iter().map(Some).collect(), n_clusters: centroids.len() },
+        centroids,
+        inertia,
+    }
+}
+
+/// Index and squared distance of the closest centroid
+fn nearest_centroid(centroids: &[Vec<f64>], v: &[f64]) -> (usize, f64) {
+    centroids.iter()
+        .map(|c| squared_distance(c, v))
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((0, 0.0))
+}
+
+/// Mean silhouette over non-noise points; 0 with fewer than two clusters
+///
+/// Points alone in their cluster score 0, as in scikit-learn. Above
+/// [`SILHOUETTE_SAMPLE_SIZE`] points the mean is taken over a seeded sample.
+pub fn silhouette_score(vectors: &[Vec<f64>], clustering: &Clustering) -> f64 {
+    silhouette_with(&silhouette_rows(vectors), clustering)
+}
+
+fn silhouette_with(rows: &[(usize, Vec<f64>)], clustering: &Clustering) -> f64 {
+    let clusters = clustering.clusters();
+    if clusters.iter().filter(|c| !c.is_empty()).count() < 2 {
+        return 0.0;
+    }
+    let mut total = 0.0;
+    let mut counted = 0;
+    for (i, distances) in rows {
+        let Some(own) = clustering.labels[*i] else { continue };
+        counted += 1;
+        if clusters[own].len() < 2 {
+            continue;
+        }
+        let mean_to = |members: &[usize]| members.iter().map(|&j| distances[j]).sum::<f64>();
+        let a = mean_to(&clusters[own]) / (clusters[own].len() - 1) as f64;
+        let b = clusters.iter().enumerate()
+            .filter(|(c, members)| *c != own && !members.is_empty())
+            .map(|(_, members)| mean_to(members) / members.len() as f64)
+            .fold(f64::INFINITY, f64::min);
+        let spread = a.max(b);
+        if spread > 0.0 {
+            total += (b - a) / spread;
+        }
+    }
+    if counted > 0 { total / counted as f64 } else { 0.0 }
+}
+
+/// k-means for every k in `k_range`, keeping the best mean silhouette
+pub fn choose_k(vectors: &[Vec<f64>], k_range: RangeInclusive<usize>, seed: u64) -> Result<KSelection> {
+    let k_min = (*k_range.start()).max(2);
+    let k_max = (*k_range.end()).min(vectors.len().saturating_sub(1));
+    if k_min > k_max {
+        return Err(Error::invalid_input(format!(
+            "No k in {:?} fits {} points", k_range, vectors.len()
+        )));
+    }
+    let rows = silhouette_rows(vectors);
+    let mut scores = Vec::new();
+    let mut best: Option<(f64, KMeansResult)> = None;
+    for k in k_min..=k_max {
+        let result = kmeans(vectors, &KMeansConfig { seed, ..KMeansConfig::new(k) })?;
+        let score = silhouette_with(&rows, &result.clustering);
+        scores.push((k, score));
+        if best.as_ref().map_or(true, |(s, _)| score > *s) {
+            best = Some((score, result));
+        }
+    }
+    let (_, result) = best.expect("k range is non-empty");
+    Ok(KSelection { best_k: result.centroids.len(), scores, result })
+}
+
+// ============================================================================
+// DENSITY-BASED
+// ============================================================================
+
+/// DBSCAN: clusters are points with at least `min_points` neighbors
+/// (counting themselves) within `eps`, plus the border points they reach
+pub fn dbscan(vectors: &[Vec<f64>], eps: f64, min_points: usize) -> Result<Clustering> {
+    validate(vectors)?;
+    check_density_size(vectors)?;
+    let eps_squared = eps * eps;
+    let neighbors: Vec<Vec<usize>> = vectors.iter()
+        .map(|a| (0..vectors.len()).filter(|&j| squared_distance(a, &vectors[j]) <= eps_squared).collect())
+        .collect();
+    let is_core = |i: usize| neighbors[i].len() >= min_points.max(1);
+
+    let mut labels: Vec<Option<usize>> = vec![None; vectors.len()];
+    let mut next = 0;
+    for start in 0..vectors.len() {
+        if labels[start].is_some() || !is_core(start) {
+            continue;
+        }
+        labels[start] = Some(next);
+        let mut frontier = vec![start];
+        while let Some(i) = frontier.pop() {
+            for &j in &neighbors[i] {
+                if labels[j].is_none() {
+                    labels[j] = Some(next);
+                    // Border points join but do not expand the cluster
+                    if is_core(j) {
+                        frontier.push(j);
+                    }
+                }
+            }
+        }
+        next += 1;
+    }
+    Ok(Clustering::from_labels(labels))
+}
+
+/// HDBSCAN: single-linkage hierarchy over mutual reachability distances,
+/// condensed with `min_cluster_size`, clusters picked by excess of mass
+///
+/// `min_samples` (counting the point itself) sets the core distance; larger
+/// values label more points as noise.
+pub fn hdbscan(vectors: &[Vec<f64>], min_cluster_size: usize, min_samples: usize) -> Result<Clustering> {
+    validate(vectors)?;
+    check_density_size(vectors)?;
+    let n = vectors.len();
+    let min_cluster_size = min_cluster_size.max(2);
+    if n < min_cluster_size {
+        return Ok(Clustering::from_labels(vec![None; n]));
+    }
+
+    let rank = min_samples.clamp(1, n) - 1;
+    let mut row = vec![0.0; n];
+    let core: Vec<f64> = vectors.iter()
+        .map(|a| {
+            for (d, b) in row.iter_mut().zip(vectors) {
+                *d = distance(a, b);
+            }
+            *row.select_nth_unstable_by(rank, f64::total_cmp).1
+        })
+        .collect();
+    let reach = |i: usize, j: usize| distance(&vectors[i], &vectors[j]).max(core[i]).max(core[j]);
+
+    // Prim's minimum spanning tree on the complete reachability graph,
+    // with distances computed on the fly
+    let mut in_tree = vec![false; n];
+    let mut best = vec![(f64::INFINITY, 0usize); n];
+    let mut edges = Vec::with_capacity(n - 1);
+    let mut current = 0;
+    in_tree[0] = true;
+    for _ in 1..n {
+        for j in 0..n {
+            if !in_tree[j] {
+                let d = reach(current, j);
+                if d < best[j].0 {
+                    best[j] = (d, current);
+                }
+            }
+        }
+        let next = (0..n)
+            .filter(|&j| !in_tree[j])
+            .min_by(|&a, &b| best[a].0.total_cmp(&best[b].0))
+            .expect("vertices remain");
+        edges.push((best[next].1, next, best[next].0));
+        in_tree[next] = true;
+        current = next;
+    }
+    edges.sort_by(|a, b| a.2.total_cmp(&b.2));
+
+    // Dendrogram: leaves 0..n, merge k creates node n + k
+    let mut parent: Vec<usize> = (0..2 * n - 1).collect();
+    fn find(parent: &mut [usize], mut x: usize) -> usize {
+        while parent[x] != x {
+            parent[x] = parent[parent[x]];
+            x = parent[x];
+        }
+        x
+    }
+    let mut merges: Vec<(usize, usize, f64)> = Vec::with_capacity(n - 1);
+    let mut size = vec![1usize; 2 * n - 1];
+    for (a, b, d) in edges {
+        let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+        let node = n + merges.len();
+        parent[ra] = node;
+        parent[rb] = node;
+        size[node] = size[ra] + size[rb];
+        merges.push((ra, rb, d));
+    }
+
+    let tree = CondensedTree::build(n, &merges, &size, min_cluster_size);
+    Ok(Clustering::from_labels(tree.select()))
+}
+
+/// Clusters of the condensed hierarchy; cluster 0 is the root
+struct CondensedTree {
+    n_points: usize,
+    parent: Vec<Option<usize>>,
+    birth: Vec<f64>,
+    stability: Vec<f64>,
+    children: Vec<Vec<usize>>,
+    /// Cluster each point falls out of
+    point_cluster: Vec<usize>,
+}
+
+impl CondensedTree {
+    fn build(n: usize, merges: &[(usize, usize, f64)], size: &[usize], min_cluster_size: usize) -> Self {
+        let mut tree = Self {
+            n_points: n,
+            parent: vec![None],
+            birth: vec![0.0],
+            stability: vec![0.0],
+            children: vec![Vec::new()],
+            point_cluster: vec![0; n],
+        };
+        let lambda = |d: f64| 1.0 / d.max(1e-12);
+
+        // (dendrogram node, cluster it belongs to)
+        let mut stack = vec![(2 * n - 2, 0usize)];
+        while let Some((node, cluster)) = stack.pop() {
+            if node < n {
+                // A lone leaf of a continuing cluster leaves at its parent's birth
+                tree.point_cluster[node] = cluster;
+                continue;
+            }
+            let (left, right, d) = merges[node - n];
+            let l = lambda(d);
+            let big = |c: usize| size[c] >= min_cluster_size;
+            match (big(left), big(right)) {
+                (true, true) => {
+                    for child in [left, right] {
+                        let id = tree.parent.len();
+                        tree.parent.push(Some(cluster));
+                        tree.birth.push(l);
+                        tree.stability.push(0.0);
+                        tree.children.push(Vec::new());
+                        tree.children[cluster].push(id);
+                        tree.stability[cluster] += (l - tree.birth[cluster]) * size[child] as f64;
+                        stack.push((child, id));
+                    }
+                }
+                (true, false) | (false, true) => {
+                    let (keep, shed) = if big(left) { (left, right) } else { (right, left) };
+                    tree.shed(shed, cluster, l, merges);
+                    stack.push((keep, cluster));
+                }
+                (false, false) => {
+                    tree.shed(left, cluster, l, merges);
+                    tree.shed(right, cluster, l, merges);
+                }
+            }
+        }
+        tree
+    }
+
+    /// Every leaf under `node` falls out of `cluster` at `lambda`
+    fn shed(&mut self, node: usize, cluster: usize, lambda: f64, merges: &[(usize, usize, f64)]) {
+        let mut stack = vec![node];
+        while let Some(x) = stack.pop() {
+            if x < self.n_points {
+                self.point_cluster[x] = cluster;
+                self.stability[cluster] += lambda - self.birth[cluster];
+            } else {
+                let (a, b, _) = merges[x - self.n_points];
+                stack.push(a);
+                stack.push(b);
+            }
+        }
+    }
+
+    /// Excess-of-mass selection, never the root; labels per point
+    fn select(&self) -> Vec<Option<usize>> {
+        let count = self.parent.len();
+        let mut selected = vec![false; count];
+        let mut subtree = self.stability.clone();
+        // Children always have larger ids than their parent
+        for c in (1..count).rev() {
+            let children: f64 = self.children[c].iter().map(|&k| subtree[k]).sum();
+            if self.children[c].is_empty() || self.stability[c] >= children {
+                selected[c] = true;
+                let mut stack = self.children[c].clone();
+                while let Some(k) = stack.pop() {
+                    selected[k] = false;
+                    stack.extend(&self.children[k]);
+                }
+            } else {
+                subtree[c] = children;
+            }
+        }
+
+        let mut label_of = vec![None; count];
+        let mut next = 0;
+        for c in 0..count {
+            if selected[c] {
+                label_of[c] = Some(next);
+                next += 1;
+            }
+        }
+        self.point_cluster.iter()
+            .map(|&c| {
+                let mut cluster = Some(c);
+                while let Some(k) = cluster {
+                    if selected[k] {
+                        return label_of[k];
+                    }
+                    cluster = self.parent[k];
+                }
+                None
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Three tight blobs in 4D plus a few far-off stragglers
+    fn blobs(seed: u64) -> (Vec<Vec<f64>>, Vec<usize>) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let centers = [[0.0, 0.0, 0.0, 0.0], [5.0, 5.0, 0.0, 0.0], [0.0, 5.0, 5.0, 5.0]];
+        let mut points = Vec::new();
+        let mut truth = Vec::new();
+        for (c, center) in centers.iter().enumerate() {
+            for _ in 0..30 {
+                points.push(center.iter().map(|x| x + rng.gen_range(-0.5..0.5)).collect());
+                truth.push(c);
+            }
+        }
+        (points, truth)
+    }
+
+    /// Each true group maps to one label
+    fn pure(clustering: &Clustering, truth: &[usize]) -> bool {
+        (0..3).all(|c| {
+            let labels: std::collections::HashSet<_> =
+                truth.iter().zip(&clustering.labels).filter(|(t, _)| **t == c).map(|(_, l)| *l).collect();
+            labels.len() == 1 && !labels.contains(&None)
+        })
+    }
+
+    #[test]
+    fn test_kmeans_and_silhouette_choice() {
+        let (points, truth) = blobs(1);
+        let result = kmeans(&points, &KMeansConfig::new(3)).unwrap();
+        assert!(pure(&result.clustering, &truth));
+        assert!(silhouette_score(&points, &result.clustering) > 0.8);
+
+        let selection = choose_k(&points, 2..=6, 7).unwrap();
+        assert_eq!(selection.best_k, 3);
+        assert_eq!(selection.scores.len(), 5);
+        assert!(kmeans(&points[..2], &KMeansConfig::new(3)).is_err());
+    }
+
+    #[test]
+    fn test_sampled_silhouette() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let points: Vec<Vec<f64>> = (0..2 * SILHOUETTE_SAMPLE_SIZE + 10)
+            .map(|i| {
+                let offset = if i % 2 == 0 { 0.0 } else { 10.0 };
+                vec![offset + rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)]
+            })
+            .collect();
+        let clustering = Clustering::from_labels((0..points.len()).map(|i| Some(i % 2)).collect());
+        assert_eq!(silhouette_rows(&points).len(), SILHOUETTE_SAMPLE_SIZE);
+        let score = silhouette_score(&points, &clustering);
+        assert!(score > 0.85 && score <= 1.0, "{}", score);
+
+        let too_many = vec![vec![0.0]; MAX_DENSITY_POINTS + 1];
+        assert!(dbscan(&too_many, 1.0, 3).is_err());
+        assert!(hdbscan(&too_many, 5, 3).is_err());
+    }
+
+    #[test]
+    fn test_density_clustering_marks_noise() {
+        let (mut points, truth) = blobs(2);
+        points.push(vec![20.0, -20.0, 20.0, -20.0]);
+        points.push(vec![-20.0, 20.0, -20.0, 20.0]);
+
+        let db = dbscan(&points, 1.0, 4).unwrap();
+        assert_eq!(db.n_clusters, 3);
+        assert!(pure(&db, &truth));
+        assert_eq!(db.noise(), vec![90, 91]);
+
+        let h = hdbscan(&points, 5, 3).unwrap();
+        assert_eq!(h.n_clusters, 3);
+        assert!(pure(&h, &truth));
+        assert_eq!(h.noise(), vec![90, 91]);
+        assert_eq!(h.clusters().iter().map(Vec::len).sum::<usize>(), 90);
+    }
+}
@@ -0,0 +1,501 @@
+//! Collaborative filtering for material recommendations
+//!
+//! Implicit-feedback matrix factorization over the interaction log:
+//!
+//! - ALS (Hu, Koren & Volinsky): weighted least squares on binary
+//!   preferences with confidence 1 + alpha * interaction weight
+//! - BPR: pairwise ranking, interacted items above sampled others
+//!
+//! `HybridScorer` blends factorization preferences with content similarity
+//! from material embeddings, which also covers users and materials the
+//! factorization has never seen. `split_held_out` and `evaluate_ranking`
+//! give precision@k, recall@k and NDCG@k on the latest interactions.
+
+use crate::recommendations::InteractionRecord;
+use crate::{Error, Result};
+use nalgebra::{DMatrix, DVector};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// User × material weights, summed over each pair's interactions
+#[derive(Debug, Clone)]
+pub struct InteractionMatrix {
+    users: Vec<Uuid>,
+    items: Vec<Uuid>,
+    user_index: HashMap<Uuid, usize>,
+    item_index: HashMap<Uuid, usize>,
+    /// (item, weight) per user
+    rows: Vec<Vec<(usize, f64)>>,
+    /// (user, weight) per item
+    columns: Vec<Vec<(usize, f64)>>,
+}
+
+impl InteractionMatrix {
+    pub fn from_records(records: &[InteractionRecord]) -> Self {
+        let mut weights: HashMap<(Uuid, Uuid), f64> = HashMap::new();
+        for record in records {
+            *weights.entry((record.user_id, record.material_id)).or_insert(0.0) += record.interaction_type.weight();
+        }
+        let mut users: Vec<Uuid> = weights.keys().map(|(u, _)| *u).collect();
+        let mut items: Vec<Uuid> = weights.keys().map(|(_, i)| *i).collect();
+        for ids in [&mut users, &mut items] {
+            ids.sort();
+            ids.dedup();
+        }
+        let user_index: HashMap<Uuid, usize> = users.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+        let item_index: HashMap<Uuid, usize> = items.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+
+        let mut rows = vec![Vec::new(); users.len()];
+        let mut columns = vec![Vec::new(); items.len()];
+        let mut entries: Vec<((Uuid, Uuid), f64)> = weights.into_iter().collect();
+        entries.sort_by_key(|(pair, _)| *pair);
+        for ((user, item), weight) in entries {
+            let (u, i) = (user_index[&user], item_index[&item]);
+            rows[u].push((i, weight));
+            columns[i].push((u, weight));
+        }
+        Self { users, items, user_index, item_index, rows, columns }
+    }
+
+    pub fn n_users(&self) -> usize {
+        self.users.len()
+    }
+
+    pub fn n_items(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Number of distinct (user, material) pairs
+    pub fn nnz(&self) -> usize {
+        self.rows.iter().map(Vec::len).sum()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FactorizationMethod {
+    Als {
+        /// Confidence slope: c = 1 + alpha * weight
+        alpha: f64,
+        regularization: f64,
+        iterations: usize,
+    },
+    Bpr {
+        learning_rate: f64,
+        regularization: f64,
+        /// Each epoch samples as many triples as there are interacting pairs
+        epochs: usize,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FactorizationConfig {
+    pub method: FactorizationMethod,
+    pub factors: usize,
+    pub seed: u64,
+}
+
+impl FactorizationConfig {
+    pub fn als(factors: usize) -> Self {
+        Self {
+            method: FactorizationMethod::Als { alpha: 10.0, regularization: 0.1, iterations: 15 },
+            factors,
+            seed: 42,
+        }
+    }
+
+    pub fn bpr(factors: usize) -> Self {
+        Self {
+            method: FactorizationMethod::Bpr { learning_rate: 0.05, regularization: 0.01, epochs: 100 },
+            factors,
+            seed: 42,
+        }
+    }
+}
+
+/// Trained user and material factors
+#[derive(Debug, Clone)]
+pub struct FactorizationModel {
+    config: FactorizationConfig,
+    matrix: InteractionMatrix,
+    user_factors: DMatrix<f64>,
+    item_factors: DMatrix<f64>,
+    pub trained_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl FactorizationModel {
+    pub fn fit(records: &[InteractionRecord], config: FactorizationConfig) -> Result<Self> {
+        if config.factors == 0 {
+            return Err(Error::invalid_input("factorization needs at least one factor"));
+        }
+        let matrix = InteractionMatrix::from_records(records);
+        if matrix.nnz() == 0 {
+            return Err(Error::invalid_input("no interactions to factorize"));
+        }
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let scale = 1.0 / (config.factors as f64).sqrt();
+        let mut init = |rows: usize| DMatrix::from_fn(rows, config.factors, |_, _| rng.gen_range(-scale..scale) * 0.1);
+        let (mut user_factors, mut item_factors) = (init(matrix.n_users()), init(matrix.n_items()));
+
+        match config.method {
+            FactorizationMethod::Als { alpha, regularization, iterations } => {
+                for _ in 0..iterations {
+                    als_sweep(&mut user_factors, &item_factors, &matrix.rows, alpha, regularization)?;
+                    als_sweep(&mut item_factors, &user_factors, &matrix.columns, alpha, regularization)?;
+                }
+            }
+            FactorizationMethod::Bpr { learning_rate, regularization, epochs } => {
+                bpr_train(&mut user_factors, &mut item_factors, &matrix, learning_rate, regularization, epochs, &mut rng);
+            }
+        }
+        Ok(Self { config, matrix, user_factors, item_factors, trained_at: chrono::Utc::now() })
+    }
+
+    pub fn method_name(&self) -> &'static str {
+        match self.config.method {
+            FactorizationMethod::Als { .. } => "als",
+            FactorizationMethod::Bpr { .. } => "bpr",
+        }
+    }
+
+    /// Raw factor dot product
+    pub fn score(&self, user_id: Uuid, material_id: Uuid) -> Option<f64> {
+        let (&u, &i) = (self.matrix.user_index.get(&user_id)?, self.matrix.item_index.get(&material_id)?);
+        Some(self.user_factors.row(u).dot(&self.item_factors.row(i)))
+    }
+
+    /// Preference in (0, 1) through a sigmoid, keeping the score order; ALS
+    /// predictions target 0/1 so their sigmoid is centered at 0.5
+    pub fn preference(&self, user_id: Uuid, material_id: Uuid) -> Option<f64> {
+        let score = self.score(user_id, material_id)?;
+        let logit = match self.config.method {
+            FactorizationMethod::Als { .. } => 4.0 * (score - 0.5),
+            FactorizationMethod::Bpr { .. } => score,
+        };
+        Some(1.0 / (1.0 + (-logit).exp()))
+    }
+
+    /// Top `k` materials the user has not interacted with yet
+    pub fn recommend(&self, user_id: Uuid, k: usize) -> Vec<(Uuid, f64)> {
+        let Some(&u) = self.matrix.user_index.get(&user_id) else { return Vec::new() };
+        let seen: HashSet<usize> = self.matrix.rows[u].iter().map(|(i, _)| *i).collect();
+        let scores = &self.item_factors * self.user_factors.row(u).transpose();
+        let mut ranked: Vec<(Uuid, f64)> = (0..self.matrix.n_items())
+            .filter(|i| !seen.contains(i))
+            .map(|i| (self.matrix.items[i], scores[i]))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked.truncate(k);
+        ranked
+    }
+
+    pub fn users(&self) -> &[Uuid] {
+        &self.matrix.users
+    }
+
+    pub fn materials(&self) -> &[Uuid] {
+        &self.matrix.items
+    }
+}
+
+/// Solve every row of `target` against fixed `other` factors
+fn als_sweep(
+    target: &mut DMatrix<f64>,
+    other: &DMatrix<f64>,
+    entries: &[Vec<(usize, f64)>],
+    alpha: f64,
+    regularization: f64,
+) -> Result<()> {
+    let factors = other.ncols();
+    let gram = other.transpose() * other;
+    for (row, observed) in entries.iter().enumerate() {
+        let mut a = gram.clone() + DMatrix::identity(factors, factors) * regularization;
+        let mut b = DVector::zeros(factors);
+        for &(j, weight) in observed {
+            let y = other.row(j).transpose();
+            let confidence = 1.0 + alpha * weight;
+            a += &y * y.transpose() * (confidence - 1.0);
+            b += y * confidence;
+        }
+        let solution = a.cholesky()
+            .ok_or_else(|| Error::computation("ALS normal equations are not positive definite"))?
+            .solve(&b);
+        target.set_row(row, &solution.transpose());
+    }
+    Ok(())
+}
+
+fn bpr_train(
+    users: &mut DMatrix<f64>,
+    items: &mut DMatrix<f64>,
+    matrix: &InteractionMatrix,
+    learning_rate: f64,
+    regularization: f64,
+    epochs: usize,
+    rng: &mut StdRng,
+) {
+    let pairs: Vec<(usize, usize)> = matrix.rows.iter().enumerate()
+        .flat_map(|(u, row)| row.iter().map(move |(i, _)| (u, *i)))
+        .collect();
+    let seen: Vec<HashSet<usize>> = matrix.rows.iter().map(|row| row.iter().map(|(i, _)| *i).collect()).collect();
+    let n_items = matrix.n_items();
+
+    for _ in 0..epochs {
+        for _ in 0..pairs.len() {
+            let (u, i) = pairs[rng.gen_range(0..pairs.len())];
+            if seen[u].len() == n_items {
+                continue;
+            }
+            let j = loop {
+                let j = rng.gen_range(0..n_items);
+                if !seen[u].contains(&j) {
+                    break j;
+                }
+            };
+            let user = users.row(u).clone_owned();
+            let diff = items.row(i) - items.row(j);
+            let x = user.dot(&diff);
+            let g = 1.0 / (1.0 + x.exp());
+
+            let user_step = &diff * g - &user * regularization;
+            let item_i_step = &user * g - items.row(i) * regularization;
+            let item_j_step = -&user * g - items.row(j) * regularization;
+            users.set_row(u, &(&user + user_step * learning_rate));
+            let new_i = items.row(i) + item_i_step * learning_rate;
+            items.set_row(i, &new_i);
+            let new_j = items.row(j) + item_j_step * learning_rate;
+            items.set_row(j, &new_j);
+        }
+    }
+}
+
+// ============================================================================
+// HYBRID SCORING
+// ============================================================================
+
+/// Blend of factorization preference and embedding similarity
+///
+/// The content part is the weighted mean cosine similarity (negatives
+/// clipped to 0) between a material and those in the user's history.
+/// When only one part is available it is used alone.
+pub struct HybridScorer<'a> {
+    pub model: Option<&'a FactorizationModel>,
+    pub content: &'a HashMap<Uuid, Vec<f64>>,
+    /// Share of the factorization part, in [0, 1]
+    pub cf_weight: f64,
+}
+
+impl HybridScorer<'_> {
+    /// `history` holds (material, interaction weight) pairs of the user
+    pub fn score(&self, user_id: Uuid, history: &[(Uuid, f64)], material_id: Uuid) -> Option<f64> {
+        let cf = self.model.and_then(|m| m.preference(user_id, material_id));
+        let content = self.content_score(history, material_id);
+        match (cf, content) {
+            (Some(cf), Some(content)) => Some(self.cf_weight * cf + (1.0 - self.cf_weight) * content),
+            (cf, content) => cf.or(content),
+        }
+    }
+
+    fn content_score(&self, history: &[(Uuid, f64)], material_id: Uuid) -> Option<f64> {
+        let target = self.content.get(&material_id)?;
+        let (mut total, mut weights) = (0.0, 0.0);
+        for (seen, weight) in history {
+            if *seen == material_id {
+                continue;
+            }
+            if let Some(vector) = self.content.get(seen) {
+                total += weight * cosine(target, vector).max(0.0);
+                weights += weight;
+            }
+        }
+        (weights > 0.0).then(|| total / weights)
+    }
+
+    /// Top `k` of `candidates` the user has not interacted with
+    pub fn recommend(&self, user_id: Uuid, history: &[(Uuid, f64)], candidates: &[Uuid], k: usize) -> Vec<(Uuid, f64)> {
+        let seen: HashSet<Uuid> = history.iter().map(|(id, _)| *id).collect();
+        let mut ranked: Vec<(Uuid, f64)> = candidates.iter()
+            .filter(|id| !seen.contains(id))
+            .filter_map(|&id| Some((id, self.score(user_id, history, id)?)))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        ranked.truncate(k);
+        ranked
+    }
+}
+
+fn cosine(a: &[f64], b: &[f64]) -> f64 {
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = a.iter().map(|x| x * x).sum::<f64>().sqrt() * b.iter().map(|x| x * x).sum::<f64>().sqrt();
+    if norm > 1e-12 { dot / norm } else { 0.0 }
+}
+
+// ============================================================================
+// OFFLINE EVALUATION
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankingMetrics {
+    pub k: usize,
+    /// Users with at least one held-out material they had not seen in training
+    pub users: usize,
+    pub precision_at_k: f64,
+    pub recall_at_k: f64,
+    pub ndcg_at_k: f64,
+}
+
+/// Hold out the latest `test_fraction` of each user's interactions
+/// (at least one, and only for users with two or more)
+pub fn split_held_out(records: &[InteractionRecord], test_fraction: f64) -> (Vec<InteractionRecord>, Vec<InteractionRecord>) {
+    let mut by_user: HashMap<Uuid, Vec<&InteractionRecord>> = HashMap::new();
+    for record in records {
+        by_user.entry(record.user_id).or_default().push(record);
+    }
+    let mut users: Vec<Uuid> = by_user.keys().copied().collect();
+    users.sort();
+
+    let (mut train, mut test) = (Vec::new(), Vec::new());
+    for user in users {
+        let mut history = by_user.remove(&user).unwrap_or_default();
+        history.sort_by_key(|r| (r.timestamp, r.material_id));
+        let held = if history.len() >= 2 {
+            ((history.len() as f64 * test_fraction).round() as usize).clamp(1, history.len() - 1)
+        } else {
+            0
+        };
+        let cut = history.len() - held;
+        train.extend(history[..cut].iter().map(|r| (*r).clone()));
+        test.extend(history[cut..].iter().map(|r| (*r).clone()));
+    }
+    (train, test)
+}
+
+/// Precision, recall and binary-relevance NDCG of `recommend(user, k)`
+/// against each user's held-out materials not already seen in `train`
+pub fn evaluate_ranking<F>(train: &[InteractionRecord], test: &[InteractionRecord], k: usize, mut recommend: F) -> RankingMetrics
+where
+    F: FnMut(Uuid, usize) -> Vec<Uuid>,
+{
+    let seen: HashSet<(Uuid, Uuid)> = train.iter().map(|r| (r.user_id, r.material_id)).collect();
+    let mut relevant: HashMap<Uuid, HashSet<Uuid>> = HashMap::new();
+    for record in test {
+        if !seen.contains(&(record.user_id, record.material_id)) {
+            relevant.entry(record.user_id).or_default().insert(record.material_id);
+        }
+    }
+    let mut users: Vec<Uuid> = relevant.keys().copied().collect();
+    users.sort();
+
+    let (mut precision, mut recall, mut ndcg) = (0.0, 0.0, 0.0);
+    for &user in &users {
+        let wanted = &relevant[&user];
+        let ranked = recommend(user, k);
+        let mut dcg = 0.0;
+        let mut hits = 0;
+        for (position, id) in ranked.iter().take(k).enumerate() {
+            if wanted.contains(id) {
+                hits += 1;
+                dcg += 1.0 / (position as f64 + 2.0).log2();
+            }
+        }
+        let ideal: f64 = (0..wanted.len().min(k)).map(|p| 1.0 / (p as f64 + 2.0).log2()).sum();
+        precision += hits as f64 / k.max(1) as f64;
+        recall += hits as f64 / wanted.len() as f64;
+        ndcg += if ideal > 0.0 { dcg / ideal } else { 0.0 };
+    }
+
+    let n = users.len().max(1) as f64;
+    RankingMetrics {
+        k,
+        users: users.len(),
+        precision_at_k: precision / n,
+        recall_at_k: recall / n,
+        ndcg_at_k: ndcg / n,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recommendations::InteractionType;
+
+    /// Two taste groups of 12 users over two groups of 10 materials; each
+    /// user has used 6 materials of their group, in order
+    fn interaction_log() -> (Vec<InteractionRecord>, Vec<Uuid>) {
+        let items: Vec<Uuid> = (0..20).map(|i| Uuid::from_u128(1000 + i)).collect();
+        let start = chrono::Utc::now();
+        let mut records = Vec::new();
+        for u in 0..24u128 {
+            let group = (u % 2) as usize;
+            for step in 0..6 {
+                let item = items[group * 10 + (u as usize + step * 3) % 10];
+                records.push(InteractionRecord {
+                    user_id: Uuid::from_u128(u + 1),
+                    material_id: item,
+                    interaction_type: if step % 3 == 0 { InteractionType::Favorite } else { InteractionType::Used },
+                    timestamp: start + chrono::Duration::seconds(step as i64),
+                    rating: None,
+                });
+            }
+        }
+        (records, items)
+    }
+
+    #[test]
+    fn test_factorization_recovers_taste_groups() {
+        let (records, items) = interaction_log();
+        let (train, test) = split_held_out(&records, 0.34);
+        assert_eq!(test.len(), 24 * 2);
+
+        for config in [FactorizationConfig::als(4), FactorizationConfig::bpr(4)] {
+            let model = FactorizationModel::fit(&train, config).unwrap();
+            let metrics = evaluate_ranking(&train, &test, 5, |user, k| {
+                model.recommend(user, k).into_iter().map(|(id, _)| id).collect()
+            });
+            assert_eq!(metrics.users, 24);
+            // Random ranking within 14 unseen items gives recall@5 ≈ 0.36
+            assert!(metrics.recall_at_k > 0.6, "{}: {metrics:?}", model.method_name());
+            assert!(metrics.ndcg_at_k > 0.4, "{}: {metrics:?}", model.method_name());
+
+            // Materials of the user's own group are preferred on average
+            let (mut same, mut other) = (0.0, 0.0);
+            for u in 0..24u128 {
+                for (i, &item) in items.iter().enumerate() {
+                    let preference = model.preference(Uuid::from_u128(u + 1), item).unwrap();
+                    if i / 10 == (u % 2) as usize { same += preference } else { other += preference }
+                }
+            }
+            assert!(same > 2.0 * other, "{}: {same} vs {other}", model.method_name());
+        }
+        assert!(FactorizationModel::fit(&[], FactorizationConfig::als(4)).is_err());
+    }
+
+    #[test]
+    fn test_hybrid_scorer_covers_cold_start() {
+        let (records, items) = interaction_log();
+        let model = FactorizationModel::fit(&records, FactorizationConfig::als(4)).unwrap();
+        let mut content: HashMap<Uuid, Vec<f64>> = items.iter().enumerate()
+            .map(|(i, &id)| (id, if i < 10 { vec![1.0, 0.1] } else { vec![0.1, 1.0] }))
+            .collect();
+        // A new material nobody has used yet, close to group 1
+        let fresh = Uuid::from_u128(5000);
+        content.insert(fresh, vec![0.0, 1.0]);
+
+        let user = Uuid::from_u128(2);
+        let history: Vec<(Uuid, f64)> = records.iter()
+            .filter(|r| r.user_id == user)
+            .map(|r| (r.material_id, r.interaction_type.weight()))
+            .collect();
+        let hybrid = HybridScorer { model: Some(&model), content: &content, cf_weight: 0.5 };
+        assert!(hybrid.score(user, &history, fresh).unwrap() > 0.9);
+        assert!(hybrid.score(user, &history, items[3]).unwrap() < 0.5);
+
+        let mut candidates = items.clone();
+        candidates.push(fresh);
+        let top = hybrid.recommend(user, &history, &candidates, 3);
+        assert!(top.iter().all(|(id, _)| *id == fresh || items[..10].iter().all(|i| i != id)));
+        assert!(top.iter().all(|(id, _)| history.iter().all(|(seen, _)| seen != id)));
+    }
+}
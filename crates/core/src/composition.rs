@@ -0,0 +1,531 @@
+//! Chemical Formulas and Compositions
+//!
+//! One parser and one composition type for every formula in the platform.
+//! Formulas may nest groups in `()`, `[]` or `{}`, use fractional
+//! subscripts (`Li0.5CoO2`), join hydrate parts with `·`, `•` or `*`
+//! (`CuSO4·5H2O`), and end in a charge (`SO4^2-`, `PO4^{3-}`, `NH4+`,
+//! `Fe+3`). A `.` followed by a count and `H2O` joins a hydrate too
+//! (`CuSO4.5H2O`, `CaSO4.0.5H2O`); any other `.` before a digit is a
+//! decimal point (`Li1.2Mn0.8O2`).
+//!
+//! Compositions keep elements in the order they first appear, which is
+//! what `Display` prints. Equality and hashing go through the Hill formula
+//! (C, then H, then the rest alphabetically; purely alphabetical without
+//! carbon), so "O3Fe2" and "Fe2O3" are the same key; `reduced_formula`
+//! and `canonical_hash` also divide out the formula-unit multiplier so
+//! every cell of one material maps to one key.
+
+use crate::elements;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Amounts closer than this to an integer count as integral
+const INTEGER_TOLERANCE: f64 = 1e-6;
+
+/// Element amounts plus a net charge
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Composition {
+    amounts: Vec<(String, f64)>,
+    charge: i32,
+}
+
+impl Composition {
+    /// Parse a formula such as "Ca3(PO4)2", "CuSO4·5H2O" or "SO4^2-"
+    pub fn parse(formula: &str) -> Result<Self> {
+        FormulaParser::new(formula).parse()
+    }
+
+    /// Build from (element, amount) pairs; repeated elements are summed and
+    /// zero amounts dropped
+    pub fn from_amounts<S: AsRef<str>>(amounts: impl IntoIterator<Item = (S, f64)>) -> Result<Self> {
+        let mut composition = Self::default();
+        for (symbol, amount) in amounts {
+            composition.add(symbol.as_ref(), amount)?;
+        }
+        composition.amounts.retain(|(_, a)| a.abs() > INTEGER_TOLERANCE);
+        Ok(composition)
+    }
+
+    /// Same composition with a net charge
+    pub fn with_charge(mut self, charge: i32) -> Self {
+        self.charge = charge;
+        self
+    }
+
+    fn add(&mut self, symbol: &str, amount: f64) -> Result<()> {
+        if elements::element(symbol).is_none() {
+            return Err(Error::invalid_input(format!("Unknown element: {}", symbol)));
+        }
+        if !amount.is_finite() || amount < 0.0 {
+            return Err(Error::invalid_input(format!("Invalid amount {} for {}", amount, symbol)));
+        }
+        match self.amounts.iter_mut().find(|(e, _)| e == symbol) {
+            Some((_, a)) => *a += amount,
+            None => self.amounts.push((symbol.to_string(), amount)),
+        }
+        Ok(())
+    }
+
+    /// Net charge
+    pub fn charge(&self) -> i32 {
+        self.charge
+    }
+
+    /// Amount of one element (0 if absent)
+    pub fn amount(&self, symbol: &str) -> f64 {
+        self.amounts.iter().find(|(e, _)| e == symbol).map_or(0.0, |(_, a)| *a)
+    }
+
+    /// Elements in order of first appearance
+    pub fn elements(&self) -> Vec<&str> {
+        self.amounts.iter().map(|(e, _)| e.as_str()).collect()
+    }
+
+    /// (element, amount) pairs in order of first appearance
+    pub fn iter(&self) -> impl Iterator<Item = (&str, f64)> + '_ {
+        self.amounts.iter().map(|(e, a)| (e.as_str(), *a))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.amounts.is_empty()
+    }
+
+    /// Total number of atoms
+    pub fn num_atoms(&self) -> f64 {
+        self.amounts.iter().map(|(_, a)| a).sum()
+    }
+
+    /// Atomic fraction of one element
+    pub fn fraction(&self, symbol: &str) -> f64 {
+        let total = self.num_atoms();
+        if total > 0.0 { self.amount(symbol) / total } else { 0.0 }
+    }
+
+    /// Molar mass (g/mol)
+    pub fn molar_mass(&self) -> f64 {
+        self.amounts
+            .iter()
+            .map(|(e, a)| a * elements::atomic_mass(e).unwrap_or(0.0))
+            .sum()
+    }
+
+    /// All amounts are whole numbers
+    pub fn is_integral(&self) -> bool {
+        self.amounts.iter().all(|(_, a)| (a - a.round()).abs() < INTEGER_TOLERANCE)
+    }
+
+    /// Whole-number amounts in order of first appearance; errors on
+    /// fractional compositions
+    pub fn integer_counts(&self) -> Result<Vec<(String, usize)>> {
+        if !self.is_integral() {
+            return Err(Error::invalid_input(format!("Fractional composition: {}", self)));
+        }
+        Ok(self.amounts.iter().map(|(e, a)| (e.clone(), a.round() as usize)).collect())
+    }
+
+    /// Element amounts keyed by symbol
+    pub fn to_map(&self) -> HashMap<String, f64> {
+        self.amounts.iter().cloned().collect()
+    }
+
+    /// Composition of one formula unit and the number of units it divides
+    /// out; fractional compositions are already one unit, and so are ions,
+    /// whose charge belongs to the whole species (S2O8^2- is not two SO4^-)
+    pub fn reduced(&self) -> (Composition, usize) {
+        if !self.is_integral() || self.amounts.is_empty() || self.charge != 0 {
+            return (self.clone(), 1);
+        }
+        let divisor = self.amounts.iter().map(|(_, a)| a.round() as usize).fold(0, gcd).max(1);
+        let reduced = Composition {
+            amounts: self.amounts.iter().map(|(e, a)| (e.clone(), a.round() / divisor as f64)).collect(),
+            charge: 0,
+        };
+        (reduced, divisor)
+    }
+
+    /// Elements in Hill order
+    fn hill_order(&self) -> Vec<(&str, f64)> {
+        let mut entries: Vec<(&str, f64)> = self.iter().collect();
+        let has_carbon = self.amount("C") > 0.0;
+        let rank = |e: &str| match e {
+            "C" if has_carbon => 0,
+            "H" if has_carbon => 1,
+            _ => 2,
+        };
+        entries.sort_by(|a, b| rank(a.0).cmp(&rank(b.0)).then(a.0.cmp(b.0)));
+        entries
+    }
+
+    /// Formula in Hill notation, e.g. "Fe4O6" for Fe4O6
+    pub fn hill_formula(&self) -> String {
+        let mut formula = String::new();
+        for (element, amount) in self.hill_order() {
+            formula.push_str(element);
+            formula.push_str(&format_amount(amount));
+        }
+        formula.push_str(&format_charge(self.charge));
+        formula
+    }
+
+    /// Hill formula of one formula unit, e.g. "Fe2O3" for Fe4O6
+    pub fn reduced_formula(&self) -> String {
+        self.reduced().0.hill_formula()
+    }
+
+    /// Stoichiometry with elements replaced by letters in order of
+    /// increasing amount, e.g. "AB2" for TiO2 and "A2B3" for Fe2O3
+    pub fn anonymous_formula(&self) -> String {
+        let (reduced, _) = self.reduced();
+        let mut amounts: Vec<f64> = reduced.amounts.iter().map(|(_, a)| *a).collect();
+        amounts.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut formula = String::new();
+        for (i, amount) in amounts.into_iter().enumerate() {
+            let letter = (b'A' + (i % 26) as u8) as char;
+            formula.push(letter);
+            if i >= 26 {
+                formula.push_str(&(i / 26).to_string());
+            }
+            formula.push_str(&format_amount(amount));
+        }
+        formula
+    }
+
+    /// Hash of the reduced formula: identical for every way of writing the
+    /// same material, and stable across runs
+    pub fn canonical_hash(&self) -> u64 {
+        // FNV-1a: `DefaultHasher` is not guaranteed stable across releases
+        self.reduced_formula().bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Subscript text: omitted for 1, integer when whole, otherwise up to six
+/// decimals
+fn format_amount(amount: f64) -> String {
+    if (amount - 1.0).abs() < INTEGER_TOLERANCE {
+        String::new()
+    } else if (amount - amount.round()).abs() < INTEGER_TOLERANCE {
+        format!("{}", amount.round() as i64)
+    } else {
+        let text = format!("{:.6}", amount);
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+fn format_charge(charge: i32) -> String {
+    match charge {
+        0 => String::new(),
+        1 => "+".to_string(),
+        -1 => "-".to_string(),
+        c if c > 0 => format!("^{}+", c),
+        c => format!("^{}-", -c),
+    }
+}
+
+impl fmt::Display for Composition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (element, amount) in &self.amounts {
+            write!(f, "{}{}", element, format_amount(*amount))?;
+        }
+        write!(f, "{}", format_charge(self.charge))
+    }
+}
+
+impl std::str::FromStr for Composition {
+    type Err = Error;
+
+    fn from_str(formula: &str) -> Result<Self> {
+        Self::parse(formula)
+    }
+}
+
+impl PartialEq for Composition {
+    fn eq(&self, other: &Self) -> bool {
+        self.hill_formula() == other.hill_formula()
+    }
+}
+
+impl Eq for Composition {}
+
+impl Hash for Composition {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hill_formula().hash(state);
+    }
+}
+
+/// Recursive-descent parser over the characters of one formula
+struct FormulaParser<'a> {
+    formula: &'a str,
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl<'a> FormulaParser<'a> {
+    fn new(formula: &'a str) -> Self {
+        Self { formula, chars: formula.trim().chars().collect(), pos: 0 }
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::invalid_input(format!("{} at position {} in formula {:?}", message, self.pos, self.formula))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn parse(mut self) -> Result<Composition> {
+        let mut composition = Composition::default();
+        loop {
+            let multiplier = self.number()?.unwrap_or(1.0);
+            let start = self.pos;
+            for (element, amount) in self.group()? {
+                composition.add(&element, amount * multiplier)?;
+            }
+            if self.pos == start {
+                return Err(self.error("Expected element or group"));
+            }
+            match self.peek() {
+                Some('·' | '•' | '*' | '.') => self.pos += 1,
+                _ => break,
+            }
+        }
+
+        composition.charge = self.charge()?;
+        if self.pos < self.chars.len() {
+            return Err(self.error(&format!("Unexpected character '{}'", self.chars[self.pos])));
+        }
+        if composition.amounts.iter().all(|(_, a)| *a <= 0.0) {
+            return Err(self.error("Empty formula"));
+        }
+        composition.amounts.retain(|(_, a)| *a > 0.0);
+        Ok(composition)
+    }
+
+    /// Elements and subgroups up to the next character that starts neither
+    fn group(&mut self) -> Result<Vec<(String, f64)>> {
+        let mut amounts: Vec<(String, f64)> = Vec::new();
+        let mut push = |element: String, amount: f64| match amounts.iter_mut().find(|(e, _)| *e == element) {
+            Some((_, a)) => *a += amount,
+            None => amounts.push((element, amount)),
+        };
+
+        while let Some(c) = self.peek() {
+            if c.is_ascii_uppercase() {
+                let element = self.element()?;
+                let amount = self.number()?.unwrap_or(1.0);
+                push(element, amount);
+            } else if let Some(closing) = matching_bracket(c) {
+                self.pos += 1;
+                let inner = self.group()?;
+                if self.peek() != Some(closing) {
+                    return Err(self.error(&format!("Missing '{}'", closing)));
+                }
+                self.pos += 1;
+                let multiplier = self.number()?.unwrap_or(1.0);
+                for (element, amount) in inner {
+                    push(element, amount * multiplier);
+                }
+            } else {
+                // `close`, a separator or the charge: the caller decides
+                break;
+            }
+        }
+        Ok(amounts)
+    }
+
+    fn element(&mut self) -> Result<String> {
+        let mut symbol = self.chars[self.pos].to_string();
+        self.pos += 1;
+        if let Some(c) = self.peek().filter(|c| c.is_ascii_lowercase()) {
+            symbol.push(c);
+            self.pos += 1;
+        }
+        if elements::element(&symbol).is_none() {
+            self.pos -= symbol.len();
+            return Err(self.error(&format!("Unknown element '{}'", symbol)));
+        }
+        Ok(symbol)
+    }
+
+    /// Optional unsigned decimal such as "2", "0.5" or ".25"
+    fn number(&mut self) -> Result<Option<f64>> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            let decimal_point = c == '.'
+                && self.chars.get(self.pos + 1).is_some_and(|d| d.is_ascii_digit())
+                && !self.hydrate_dot(start);
+            if c.is_ascii_digit() || decimal_point {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        if start == self.pos {
+            return Ok(None);
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse().map(Some).map_err(|_| self.error(&format!("Invalid number '{}'", text)))
+    }
+
+    /// The `.` at the cursor separates water of crystallisation, as in
+    /// "CuSO4.5H2O": a count and "H2O" follow it, ending the formula or
+    /// its part, and the number before it (starting at `start`) is not a
+    /// bare "0" that the `.` would make fractional
+    fn hydrate_dot(&self, start: usize) -> bool {
+        if self.chars[start..self.pos].iter().collect::<String>() == "0" {
+            return false;
+        }
+        let digits = |mut i: usize| {
+            while self.chars.get(i).is_some_and(|c| c.is_ascii_digit()) {
+                i += 1;
+            }
+            i
+        };
+        let mut i = digits(self.pos + 1);
+        if self.chars.get(i) == Some(&'.') && self.chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()) {
+            i = digits(i + 1);
+        }
+        let water = ['H', '2', 'O'];
+        self.chars.get(i..i + 3) == Some(&water[..])
+            && !self.chars.get(i + 3).is_some_and(|c| c.is_ascii_digit() || c.is_ascii_lowercase())
+    }
+
+    /// Trailing charge: "^2-", "^{3+}", "^-2", "+", "--" or "+3"
+    fn charge(&mut self) -> Result<i32> {
+        let caret = self.peek() == Some('^');
+        if caret {
+            self.pos += 1;
+        }
+        let braced = caret && self.peek() == Some('{');
+        if braced {
+            self.pos += 1;
+        }
+
+        let leading = self.digits();
+        let mut sign = 0;
+        let mut signs = 0;
+        while let Some(c @ ('+' | '-')) = self.peek() {
+            let s = if c == '+' { 1 } else { -1 };
+            if sign != 0 && s != sign {
+                return Err(self.error("Mixed charge signs"));
+            }
+            sign = s;
+            signs += 1;
+            self.pos += 1;
+        }
+        let trailing = if leading.is_none() && signs == 1 { self.digits() } else { None };
+
+        if braced {
+            if self.peek() != Some('}') {
+                return Err(self.error("Missing '}'"));
+            }
+            self.pos += 1;
+        }
+        if sign == 0 {
+            return if caret || leading.is_some() { Err(self.error("Charge needs a sign")) } else { Ok(0) };
+        }
+        if leading.is_some() && !caret {
+            return Err(self.error("Write charges after a subscript with '^', e.g. SO4^2-"));
+        }
+        let magnitude = leading.or(trailing).unwrap_or(signs);
+        Ok(sign * magnitude)
+    }
+
+    fn digits(&mut self) -> Option<i32> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return None;
+        }
+        self.chars[start..self.pos].iter().collect::<String>().parse().ok()
+    }
+}
+
+fn matching_bracket(open: char) -> Option<char> {
+    match open {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '{' => Some('}'),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_formulas() {
+        let apatite = Composition::parse("Ca5(PO4)3(OH)").unwrap();
+        assert_eq!(apatite.amount("Ca"), 5.0);
+        assert_eq!(apatite.amount("O"), 13.0);
+        assert_eq!(apatite.amount("H"), 1.0);
+        assert_eq!(apatite.elements(), vec!["Ca", "P", "O", "H"]);
+
+        let ferricyanide = Composition::parse("K3[Fe(CN)6]").unwrap();
+        assert_eq!(ferricyanide.amount("N"), 6.0);
+
+        let hydrate = Composition::parse("CuSO4·5H2O").unwrap();
+        assert_eq!(hydrate.amount("H"), 10.0);
+        assert_eq!(hydrate.amount("O"), 9.0);
+        assert_eq!(Composition::parse("CuSO4*5H2O").unwrap(), hydrate);
+        assert_eq!(Composition::parse("CuSO4.5H2O").unwrap(), hydrate);
+        assert_eq!(Composition::parse("Na2CO3.10H2O").unwrap().amount("H"), 20.0);
+        let hemihydrate = Composition::parse("CaSO4.0.5H2O").unwrap();
+        assert_eq!(hemihydrate.amount("H"), 1.0);
+        assert_eq!(hemihydrate.amount("O"), 4.5);
+        let decimal = Composition::parse("Li1.2Mn0.8O2").unwrap();
+        assert_eq!((decimal.amount("Li"), decimal.amount("Mn")), (1.2, 0.8));
+        assert_eq!(Composition::parse("Fe0.5H2O").unwrap().amount("Fe"), 0.5);
+
+        let lco = Composition::parse("Li0.5CoO2").unwrap();
+        assert_eq!(lco.amount("Li"), 0.5);
+        assert!(!lco.is_integral());
+        assert!(lco.integer_counts().is_err());
+
+        assert_eq!(Composition::parse("SO4^2-").unwrap().charge(), -2);
+        assert_eq!(Composition::parse("PO4^{3-}").unwrap().charge(), -3);
+        assert_eq!(Composition::parse("NH4+").unwrap().charge(), 1);
+        assert_eq!(Composition::parse("Fe+3").unwrap().charge(), 3);
+        assert_eq!(Composition::parse("O--").unwrap().charge(), -2);
+
+        for bad in ["", "fe2", "Xx2O", "Fe(O", "Fe2O3)", "Fe2O3+-", "Fe^2"] {
+            assert!(Composition::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_normal_forms() {
+        let a = Composition::parse("O6Fe4").unwrap();
+        let b = Composition::parse("Fe2O3").unwrap();
+        assert_eq!(a.hill_formula(), "Fe4O6");
+        assert_eq!(a.reduced_formula(), "Fe2O3");
+        assert_eq!(a.reduced().1, 2);
+        assert_ne!(a, b);
+        assert_eq!(a.canonical_hash(), b.canonical_hash());
+        assert_eq!(a.to_string(), "O6Fe4");
+
+        assert_eq!(Composition::parse("C2H5OH").unwrap().hill_formula(), "C2H6O");
+        assert_eq!(Composition::parse("SrTiO3").unwrap().reduced_formula(), "O3SrTi");
+        assert_eq!(Composition::parse("TiO2").unwrap().anonymous_formula(), "AB2");
+        assert_eq!(Composition::parse("Ca3Al2Si3O12").unwrap().anonymous_formula(), "A2B3C3D12");
+        assert_eq!(Composition::parse("Li0.5CoO2").unwrap().reduced_formula(), "CoLi0.5O2");
+        assert_eq!(Composition::parse("S2O8^2-").unwrap().reduced_formula(), "O8S2^2-");
+        assert_eq!(Composition::parse("Hg2^2+").unwrap().reduced().1, 1);
+
+        let water = Composition::parse("H2O").unwrap();
+        assert!((water.molar_mass() - 18.015).abs() < 1e-3);
+        assert!((water.fraction("H") - 2.0 / 3.0).abs() < 1e-12);
+    }
+}
@@ -628,7 +628,8 @@ impl CrystallographyAnalyzer {
         (dot / mag_product).acos().to_degrees()
     }
 
-    /// Generate supercell
+    /// Generate supercell by diagonal replication (see
+    /// `cell_reduction::make_supercell` for general matrices)
     pub fn generate_supercell(
         base_positions: &[Vec3],
         nx: usize,
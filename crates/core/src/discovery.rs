@@ -6,9 +6,22 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use tracing::info;
+use crate::active_learning::{
+    ActiveLearningConfig, ActiveLearningReport, LabelOracle, LabelledSample, LearningCurvePoint, LearningGoal,
+};
+use crate::economics::{EconomicFilter, MaterialEconomics};
+use crate::bayesian_optimization::{BayesianOptimizer, BayesianOptimizerConfig, CompositionSpace, OutcomeConstraint};
 use crate::embeddings::EmbeddingEngine;
+use crate::featurizer::MagpieFeaturizer;
+use crate::composition::Composition;
+use crate::material::Material;
 use crate::ml_predictor::{MLPredictor, PropertyPrediction};
 use crate::knowledge_graph::KnowledgeGraph;
+use crate::pareto::{self, HypervolumeTracker, Objective, Scalarization};
 
 /// Discovery target specification
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +40,52 @@ pub struct DiscoveryTarget {
 
     /// Optimization objective
     pub objective: OptimizationObjective,
+
+    /// Limits on raw material cost, supply risk and sustainability
+    #[serde(default)]
+    pub economics: Option<EconomicFilter>,
+}
+
+impl DiscoveryTarget {
+    /// Active learning settings for a single-objective target
+    pub fn active_learning_config(&self) -> Result<ActiveLearningConfig, String> {
+        match &self.objective {
+            OptimizationObjective::Maximize(p) => Ok(ActiveLearningConfig::new(p.clone(), LearningGoal::Maximize)),
+            OptimizationObjective::Minimize(p) => Ok(ActiveLearningConfig::new(p.clone(), LearningGoal::Minimize)),
+            OptimizationObjective::MultiObjective(_) => {
+                Err("Active learning requires a single objective".to_string())
+            }
+        }
+    }
+
+    /// Objectives with directions
+    ///
+    /// Multi-objective entries accept `max:`/`min:` prefixes; a bare name
+    /// targets its property constraint's value when one exists and is
+    /// maximized otherwise.
+    pub fn objectives(&self) -> Vec<Objective> {
+        match &self.objective {
+            OptimizationObjective::Maximize(p) => vec![Objective::maximize(p.clone())],
+            OptimizationObjective::Minimize(p) => vec![Objective::minimize(p.clone())],
+            OptimizationObjective::MultiObjective(specs) => specs
+                .iter()
+                .map(|spec| match (spec.contains(':'), self.target_properties.get(spec)) {
+                    (false, Some(constraint)) => Objective::target(spec.clone(), constraint.target_value),
+                    _ => Objective::parse(spec),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// How multi-objective candidates are ordered
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum MultiObjectiveRanking {
+    /// NSGA-II: Pareto front, then crowding distance
+    #[default]
+    Pareto,
+    /// Single-score fallback
+    Scalarized(Scalarization),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,9 +99,17 @@ pub struct PropertyConstraint {
 pub enum OptimizationObjective {
     Maximize(String),
     Minimize(String),
+    /// Pareto trade-off; see [`DiscoveryTarget::objectives`] for directions
     MultiObjective(Vec<String>),
 }
 
+/// Proposals evaluated per Bayesian optimization round
+const OPTIMIZATION_BATCH_SIZE: usize = 4;
+
+/// Candidate awaiting a label: material, features, and the model's
+/// (mean, std) prediction when it was selected by acquisition
+type PendingLabel = (Material, Vec<f64>, Option<(f64, f64)>);
+
 /// Discovered material candidate
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MaterialCandidate {
@@ -53,6 +120,9 @@ pub struct MaterialCandidate {
     pub synthesis_feasibility: f64,
     pub novelty_score: f64,
     pub reasoning: Vec<String>,
+    /// Pareto front index for multi-objective targets (0 = non-dominated)
+    #[serde(default)]
+    pub pareto_rank: Option<usize>,
 }
 
 /// Material Discovery Engine
@@ -95,13 +165,115 @@ impl DiscoveryEngine {
         let similarity_candidates = self.discover_by_similarity(&target).await?;
         candidates.extend(similarity_candidates);
 
+        if let Some(ref filter) = target.economics {
+            Self::apply_economic_filter(&mut candidates, filter);
+        }
+
         // Rank and filter candidates
-        candidates.sort_by(|a, b| b.discovery_score.partial_cmp(&a.discovery_score).unwrap());
+        if matches!(target.objective, OptimizationObjective::MultiObjective(_)) {
+            Self::rank_multi_objective(&mut candidates, &target, &MultiObjectiveRanking::Pareto);
+        } else {
+            candidates.sort_by(|a, b| b.discovery_score.partial_cmp(&a.discovery_score).unwrap());
+        }
         candidates.truncate(max_candidates);
 
         Ok(candidates)
     }
 
+    /// Drop candidates outside the economic limits; survivors get the
+    /// scores in their reasoning
+    pub fn apply_economic_filter(candidates: &mut Vec<MaterialCandidate>, filter: &EconomicFilter) {
+        candidates.retain_mut(|candidate| {
+            match MaterialEconomics::from_formula(&candidate.formula) {
+                Ok(economics) if filter.accepts(&economics) => {
+                    candidate.reasoning.push(format!(
+                        "Raw material cost ~{:.1} USD/kg, supply risk {:.2}, sustainability {:.2}",
+                        economics.cost_per_kg, economics.supply_risk, economics.sustainability
+                    ));
+                    true
+                }
+                _ => false,
+            }
+        });
+    }
+
+    /// Order candidates for a multi-objective target
+    ///
+    /// Candidates with predictions for every objective are ranked by the
+    /// chosen strategy and get a `pareto_rank`; the rest follow, ordered by
+    /// their weighted `discovery_score`.
+    pub fn rank_multi_objective(
+        candidates: &mut Vec<MaterialCandidate>,
+        target: &DiscoveryTarget,
+        ranking: &MultiObjectiveRanking,
+    ) {
+        let objectives = target.objectives();
+        let (mut complete, mut partial): (Vec<MaterialCandidate>, Vec<MaterialCandidate>) = candidates
+            .drain(..)
+            .partition(|c| Self::objective_values(c, &objectives).is_some());
+
+        let raw: Vec<Vec<f64>> = complete.iter()
+            .filter_map(|c| Self::objective_values(c, &objectives))
+            .collect();
+        let order: Vec<(usize, Option<usize>)> = match ranking {
+            MultiObjectiveRanking::Pareto => {
+                let utilities: Vec<Vec<f64>> = raw.iter()
+                    .map(|v| objectives.iter().zip(v).map(|(o, x)| o.utility(*x)).collect())
+                    .collect();
+                pareto::nsga2_ranking(&utilities).into_iter().map(|r| (r.index, Some(r.front))).collect()
+            }
+            MultiObjectiveRanking::Scalarized(scalarization) => {
+                let scores = scalarization.scores(&objectives, &raw);
+                let mut idx: Vec<usize> = (0..raw.len()).collect();
+                idx.sort_by(|&a, &b| scores[b].partial_cmp(&scores[a]).unwrap_or(std::cmp::Ordering::Equal));
+                idx.into_iter().map(|i| (i, None)).collect()
+            }
+        };
+
+        let mut slots: Vec<Option<MaterialCandidate>> = complete.drain(..).map(Some).collect();
+        for (index, front) in order {
+            if let Some(mut candidate) = slots[index].take() {
+                candidate.pareto_rank = front;
+                candidates.push(candidate);
+            }
+        }
+        partial.sort_by(|a, b| b.discovery_score.partial_cmp(&a.discovery_score).unwrap_or(std::cmp::Ordering::Equal));
+        candidates.extend(partial);
+    }
+
+    /// Non-dominated candidates for the target's objectives
+    pub fn pareto_front(candidates: &[MaterialCandidate], target: &DiscoveryTarget) -> Vec<MaterialCandidate> {
+        let objectives = target.objectives();
+        let (members, utilities): (Vec<&MaterialCandidate>, Vec<Vec<f64>>) = candidates.iter()
+            .filter_map(|c| {
+                Self::objective_values(c, &objectives)
+                    .map(|v| (c, objectives.iter().zip(&v).map(|(o, x)| o.utility(*x)).collect()))
+            })
+            .unzip();
+        pareto::pareto_front(&utilities).into_iter().map(|i| members[i].clone()).collect()
+    }
+
+    /// Record the hypervolume of a candidate set's objective utilities
+    pub fn track_hypervolume(
+        tracker: &mut HypervolumeTracker,
+        label: &str,
+        candidates: &[MaterialCandidate],
+        target: &DiscoveryTarget,
+    ) -> f64 {
+        let objectives = target.objectives();
+        let utilities: Vec<Vec<f64>> = candidates.iter()
+            .filter_map(|c| Self::objective_values(c, &objectives))
+            .map(|v| objectives.iter().zip(&v).map(|(o, x)| o.utility(*x)).collect())
+            .collect();
+        tracker.record(label, &utilities)
+    }
+
+    fn objective_values(candidate: &MaterialCandidate, objectives: &[Objective]) -> Option<Vec<f64>> {
+        objectives.iter()
+            .map(|o| candidate.predicted_properties.get(&o.name).map(|p| p.predicted_value))
+            .collect()
+    }
+
     /// Discover materials by element substitution
     async fn discover_by_substitution(
         &self,
@@ -127,7 +299,7 @@ impl DiscoveryEngine {
             let formula = format!("{}2{}3", to, "O"); // Simple oxide formula
             let candidate = self.evaluate_candidate(&formula, target).await?;
 
-            if candidate.discovery_score > 0.5 {
+            if Self::passes_screen(&candidate, target, 0.5) {
                 candidates.push(candidate);
             }
         }
@@ -171,7 +343,7 @@ impl DiscoveryEngine {
 
                     let candidate = self.evaluate_candidate(&formula, target).await?;
 
-                    if candidate.discovery_score > 0.6 {
+                    if Self::passes_screen(&candidate, target, 0.6) {
                         candidates.push(candidate);
                     }
                 }
@@ -181,6 +353,18 @@ impl DiscoveryEngine {
         Ok(candidates)
     }
 
+    /// Whether a screened candidate is kept: multi-objective candidates need
+    /// a prediction for every objective (Pareto ranking orders them later),
+    /// others a discovery score above `threshold`
+    fn passes_screen(candidate: &MaterialCandidate, target: &DiscoveryTarget, threshold: f64) -> bool {
+        match target.objective {
+            OptimizationObjective::MultiObjective(_) => {
+                Self::objective_values(candidate, &target.objectives()).is_some()
+            }
+            _ => candidate.discovery_score > threshold,
+        }
+    }
+
     /// Discover materials by similarity to known materials
     async fn discover_by_similarity(
         &self,
@@ -192,40 +376,26 @@ impl DiscoveryEngine {
     }
 
     /// Evaluate a candidate formula
+    ///
+    /// Each property is predicted from the features its model was trained
+    /// on; properties without a usable model are left out.
     async fn evaluate_candidate(
         &self,
         formula: &str,
         target: &DiscoveryTarget,
     ) -> Result<MaterialCandidate, String> {
-        // Generate features for the formula
-        let features = self.generate_features(formula)?;
+        let material = Material::new(formula);
 
         // Predict properties
         let mut predicted_properties = HashMap::new();
-        let mut total_score = 0.0;
-        let mut property_count = 0;
-
-        for (property_name, constraint) in &target.target_properties {
-            if let Ok(prediction) = self.ml_predictor.predict_property(
-                property_name,
-                features.clone(),
-            ).await {
-                let error = (prediction.predicted_value - constraint.target_value).abs();
-                let normalized_error = error / constraint.tolerance.max(1e-10);
-                let property_score = (-normalized_error).exp() * constraint.weight;
-
-                total_score += property_score;
-                property_count += 1;
-
-                predicted_properties.insert(property_name.clone(), prediction);
+
+        for property_name in Self::predicted_property_names(target) {
+            if let Ok(prediction) = self.ml_predictor.predict_material(&property_name, &material).await {
+                predicted_properties.insert(property_name, prediction);
             }
         }
 
-        let discovery_score = if property_count > 0 {
-            total_score / property_count as f64
-        } else {
-            0.0
-        };
+        let discovery_score = Self::constraint_score(&predicted_properties, target);
 
         // Calculate novelty (simplified)
         let novelty_score = self.calculate_novelty(formula).await;
@@ -250,43 +420,10 @@ impl DiscoveryEngine {
             synthesis_feasibility,
             novelty_score,
             reasoning,
+            pareto_rank: None,
         })
     }
 
-    /// Generate features from formula
-    fn generate_features(&self, formula: &str) -> Result<Vec<f64>, String> {
-        // Parse formula and extract basic features
-        let mut features = vec![0.0; 8];
-
-        // Count atoms (simplified)
-        let atom_count = formula.chars().filter(|c| c.is_numeric()).count() as f64;
-        features[0] = atom_count;
-
-        // Count elements
-        let element_count = formula.chars().filter(|c| c.is_uppercase()).count() as f64;
-        features[1] = element_count;
-
-        // Add some chemical features
-        if formula.contains("O") {
-            features[2] = 1.0; // Contains oxygen
-        }
-        if formula.contains("Fe") {
-            features[3] = 1.0; // Contains iron
-        }
-        if formula.contains("Li") || formula.contains("Na") {
-            features[4] = 1.0; // Contains alkali metal
-        }
-
-        // Formula complexity
-        features[5] = formula.len() as f64 / 10.0;
-
-        // Random features for demo
-        features[6] = 0.5;
-        features[7] = 0.3;
-
-        Ok(features)
-    }
-
     /// Calculate novelty score
     async fn calculate_novelty(&self, formula: &str) -> f64 {
         // Check if material exists in knowledge graph
@@ -310,77 +447,365 @@ impl DiscoveryEngine {
         (base_score - element_penalty * 0.2).max(0.1).min(0.9)
     }
 
-    /// Optimize a material for specific properties
+    /// Optimize a composition for the target with Bayesian optimization
+    ///
+    /// Searches the continuous composition simplex spanned by the base
+    /// formula's elements plus the required elements (minus forbidden
+    /// ones), starting from the base composition. The objective property
+    /// comes from `target.objective`; every other property constraint is an
+    /// outcome constraint (target ± tolerance). Compositions are evaluated by
+    /// the ML predictor on composition features, in `iterations` batches
+    /// of `OPTIMIZATION_BATCH_SIZE` Kriging-believer proposals. Multi-objective
+    /// targets are optimized on a scalarization and returned in Pareto order.
     pub async fn optimize_composition(
         &self,
         base_formula: String,
         target: DiscoveryTarget,
         iterations: usize,
     ) -> Result<Vec<MaterialCandidate>, String> {
-        let mut best_candidates = Vec::new();
+        self.optimize_composition_with(base_formula, target, iterations, BayesianOptimizerConfig::default())
+            .await
+    }
 
-        // Genetic algorithm-like optimization
-        let mut population: Vec<String> = vec![base_formula.clone()];
+    /// `optimize_composition` with explicit optimizer settings
+    pub async fn optimize_composition_with(
+        &self,
+        base_formula: String,
+        target: DiscoveryTarget,
+        iterations: usize,
+        config: BayesianOptimizerConfig,
+    ) -> Result<Vec<MaterialCandidate>, String> {
+        let base = Composition::parse(&base_formula).map_err(|e| e.to_string())?;
+        let base_elements: Vec<String> = base.elements().into_iter().map(String::from).collect();
+        let space = CompositionSpace::from_target(&base_elements, &target, 0.05).map_err(|e| e.to_string())?;
+
+        let objective_properties: Vec<String> = target.objectives().into_iter().map(|o| o.name).collect();
+        let mut constraint_names: Vec<&String> = target.target_properties.keys()
+            .filter(|p| !objective_properties.contains(p))
+            .collect();
+        constraint_names.sort();
+        let constraints: Vec<OutcomeConstraint> = constraint_names.iter()
+            .map(|p| OutcomeConstraint::from_property(p, &target.target_properties[*p]))
+            .collect();
+
+        let mut optimizer = BayesianOptimizer::new(space.clone(), config).with_constraints(constraints);
+
+        // Start from the base composition
+        let base_x: Vec<f64> = space.elements().iter().map(|e| base.fraction(e)).collect();
+        let mut pending = vec![space.project(&base_x)];
+
+        let mut evaluated: Vec<(Vec<f64>, usize, HashMap<String, PropertyPrediction>)> = Vec::new();
+        for iteration in 0..=iterations {
+            if iteration > 0 {
+                pending = optimizer.ask(OPTIMIZATION_BATCH_SIZE).map_err(|e| e.to_string())?;
+            }
+            for x in pending.drain(..) {
+                let predictions = self.predict_composition(&space, &x, &target).await?;
+                let objective = Self::scalar_objective(&target, &predictions)?;
+                let constraint_values = optimizer.constraints().iter()
+                    .map(|c| predictions[&c.name].predicted_value)
+                    .collect();
+                optimizer.tell(x.clone(), objective, constraint_values).map_err(|e| e.to_string())?;
+                evaluated.push((x, iteration, predictions));
+            }
+        }
 
-        for _ in 0..iterations {
-            let mut new_population = Vec::new();
+        // Rank: normalized objective, blended with constraint satisfaction
+        let objectives: Vec<f64> = optimizer.observations().iter().map(|o| o.objective).collect();
+        let (lo, hi) = objectives.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(*v), hi.max(*v)));
+        let constrained = !optimizer.constraints().is_empty();
 
-            for formula in &population {
-                // Generate variations
-                let variations = self.generate_variations(formula);
+        let mut candidates = Vec::with_capacity(evaluated.len());
+        for ((x, iteration, predictions), observation) in evaluated.into_iter().zip(optimizer.observations()) {
+            let formula = space.formula(&x);
+            if candidates.iter().any(|c: &MaterialCandidate| c.formula == formula) {
+                continue;
+            }
+            let normalized = if hi - lo > 1e-12 { (observation.objective - lo) / (hi - lo) } else { 1.0 };
+            let discovery_score = if constrained {
+                0.5 * normalized + 0.5 * Self::constraint_score(&predictions, &target)
+            } else {
+                normalized
+            };
+            let confidence = predictions.values().map(|p| p.confidence_score).sum::<f64>()
+                / predictions.len().max(1) as f64;
+
+            candidates.push(MaterialCandidate {
+                reasoning: vec![
+                    format!("Formula: {}", formula),
+                    format!("Bayesian optimization batch {}", iteration),
+                    format!("Objective: {:.4}", observation.objective),
+                    format!("Constraints satisfied: {}", observation.feasible),
+                ],
+                novelty_score: self.calculate_novelty(&formula).await,
+                synthesis_feasibility: self.estimate_synthesis_feasibility(&formula),
+                formula,
+                predicted_properties: predictions,
+                discovery_score,
+                confidence,
+                pareto_rank: None,
+            });
+        }
 
-                for variant in variations {
-                    let candidate = self.evaluate_candidate(&variant, &target).await?;
+        if matches!(target.objective, OptimizationObjective::MultiObjective(_)) {
+            Self::rank_multi_objective(&mut candidates, &target, &MultiObjectiveRanking::Pareto);
+        } else {
+            candidates.sort_by(|a, b| b.discovery_score.partial_cmp(&a.discovery_score).unwrap_or(std::cmp::Ordering::Equal));
+        }
+        candidates.truncate(20);
 
-                    if candidate.discovery_score > 0.5 {
-                        new_population.push(variant);
-                        best_candidates.push(candidate);
-                    }
-                }
+        Ok(candidates)
+    }
+
+    /// Predict every objective and constrained property for a composition
+    async fn predict_composition(
+        &self,
+        space: &CompositionSpace,
+        x: &[f64],
+        target: &DiscoveryTarget,
+    ) -> Result<HashMap<String, PropertyPrediction>, String> {
+        let features = MagpieFeaturizer::new()
+            .featurize(&space.composition(x))
+            .map_err(|e| e.to_string())?;
+
+        let mut predictions = HashMap::new();
+        for property in Self::predicted_property_names(target) {
+            let prediction = self.ml_predictor.predict_property(&property, features.clone()).await?;
+            predictions.insert(property, prediction);
+        }
+        Ok(predictions)
+    }
+
+    /// Constrained properties plus every objective property, by parsed name
+    fn predicted_property_names(target: &DiscoveryTarget) -> Vec<String> {
+        let mut properties: Vec<String> = target.target_properties.keys().cloned().collect();
+        properties.extend(target.objectives().into_iter().map(|o| o.name));
+        properties.sort();
+        properties.dedup();
+        properties
+    }
+
+    /// Objective to maximize; multi-objective targets are scalarized as the
+    /// mean over objectives of the constraint score (for targeted values)
+    /// or the directed utility (for `max:`/`min:` objectives)
+    fn scalar_objective(
+        target: &DiscoveryTarget,
+        predictions: &HashMap<String, PropertyPrediction>,
+    ) -> Result<f64, String> {
+        let objectives = target.objectives();
+        let mut total = 0.0;
+        for objective in &objectives {
+            let value = predictions.get(&objective.name)
+                .ok_or_else(|| format!("No prediction for objective {}", objective.name))?
+                .predicted_value;
+            total += match (&objective.goal, target.target_properties.get(&objective.name)) {
+                (LearningGoal::Target(_), Some(constraint)) => Self::property_score(value, constraint),
+                _ => objective.utility(value),
+            };
+        }
+        Ok(total / objectives.len().max(1) as f64)
+    }
+
+    fn property_score(value: f64, constraint: &PropertyConstraint) -> f64 {
+        let normalized_error = (value - constraint.target_value).abs() / constraint.tolerance.max(1e-10);
+        (-normalized_error).exp() * constraint.weight
+    }
+
+    /// Mean weighted closeness of predictions to the target's constraints
+    fn constraint_score(predictions: &HashMap<String, PropertyPrediction>, target: &DiscoveryTarget) -> f64 {
+        let scores: Vec<f64> = target.target_properties.iter()
+            .filter_map(|(p, c)| predictions.get(p).map(|pred| Self::property_score(pred.predicted_value, c)))
+            .collect();
+        if scores.is_empty() {
+            0.0
+        } else {
+            scores.iter().sum::<f64>() / scores.len() as f64
+        }
+    }
+
+    /// Uncertainty-driven active learning over a candidate pool
+    ///
+    /// Seeds the property model with randomly labelled candidates (unless the
+    /// predictor already holds training data for the property), drawing
+    /// replacements for seeds the oracle fails to label, then each
+    /// iteration retrains, scores the unlabelled pool with the acquisition
+    /// function, and has the oracle label the top batch. Candidates are
+    /// featurized with the predictor's feature set for the property; use a
+    /// tree-ensemble model kind so predictions carry ensemble variance.
+    pub async fn run_active_learning(
+        &self,
+        pool: Vec<Material>,
+        oracle: &dyn LabelOracle,
+        config: &ActiveLearningConfig,
+    ) -> Result<ActiveLearningReport, String> {
+        config.validate()?;
+        let property = &config.property;
+        let feature_set = self.ml_predictor.feature_set(property).await;
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let mut report = ActiveLearningReport::new(config, oracle.name());
+
+        let mut unlabelled: Vec<(Material, Vec<f64>)> = Vec::with_capacity(pool.len());
+        for material in pool {
+            match feature_set.featurize(&material) {
+                Ok(features) => unlabelled.push((material, features)),
+                Err(e) => report.failures.push((material.id, e.to_string())),
             }
+        }
 
-            // Keep best formulas
-            new_population.sort_by(|a, b| {
-                let score_a = best_candidates.iter()
-                    .find(|c| c.formula == *a)
-                    .map(|c| c.discovery_score)
-                    .unwrap_or(0.0);
-                let score_b = best_candidates.iter()
-                    .find(|c| c.formula == *b)
-                    .map(|c| c.discovery_score)
-                    .unwrap_or(0.0);
-                score_b.partial_cmp(&score_a).unwrap()
-            });
-            new_population.truncate(10);
+        if self.ml_predictor.training_data_count(property).await == 0 {
+            unlabelled.shuffle(&mut rng);
+            let wanted = config.initial_samples.max(2);
+            let (mut acquired, mut failed) = (0, 0);
+            while acquired < wanted && !unlabelled.is_empty() {
+                let n = (wanted - acquired).min(unlabelled.len());
+                let seed = unlabelled.drain(..n).map(|(m, f)| (m, f, None)).collect();
+                let (ok, err, _) = self.label_batch(seed, oracle, config, 0, &mut report).await?;
+                acquired += ok;
+                failed += err;
+            }
 
-            population = new_population;
+            report.learning_curve.push(self.curve_point(&report, 0, unlabelled.len(), 0.0, None, acquired, failed));
+        }
 
-            if population.is_empty() {
+        for iteration in 1..=config.iterations {
+            if unlabelled.is_empty() {
                 break;
             }
+            self.ml_predictor.train_model(property.clone()).await?;
+
+            let best_utility = report.best.as_ref()
+                .map_or(f64::NEG_INFINITY, |b| config.goal.utility(b.value));
+            let mut scored = Vec::with_capacity(unlabelled.len());
+            for (idx, (_, features)) in unlabelled.iter().enumerate() {
+                let prediction = self.ml_predictor.predict_property(property, features.clone()).await?;
+                let std = (prediction.confidence_interval.1 - prediction.confidence_interval.0) / (2.0 * 1.96);
+                let score = config.acquisition.score(config.goal, prediction.predicted_value, std, best_utility);
+                scored.push((idx, prediction.predicted_value, std, score));
+            }
+            let mean_pool_uncertainty = scored.iter().map(|s| s.2).sum::<f64>() / scored.len() as f64;
+
+            scored.sort_by(|a, b| b.3.partial_cmp(&a.3).unwrap_or(std::cmp::Ordering::Equal));
+            scored.truncate(config.batch_size);
+
+            // Remove selected candidates from the pool, highest index first
+            let mut selected: Vec<(usize, f64, f64)> = scored.iter().map(|s| (s.0, s.1, s.2)).collect();
+            selected.sort_by_key(|s| std::cmp::Reverse(s.0));
+            let batch = selected
+                .into_iter()
+                .map(|(idx, mean, std)| {
+                    let (material, features) = unlabelled.swap_remove(idx);
+                    (material, features, Some((mean, std)))
+                })
+                .collect();
+
+            let (acquired, failed, batch_rmse) = self.label_batch(batch, oracle, config, iteration, &mut report).await?;
+            let point = self.curve_point(
+                &report,
+                iteration,
+                unlabelled.len(),
+                mean_pool_uncertainty,
+                batch_rmse,
+                acquired,
+                failed,
+            );
+            info!(
+                "Active learning [{}] iteration {}: {} labelled, best {:?}, pool σ {:.4}, batch RMSE {:?}",
+                property, iteration, point.labelled, point.best_value, mean_pool_uncertainty, batch_rmse
+            );
+            report.learning_curve.push(point);
         }
 
-        best_candidates.sort_by(|a, b| b.discovery_score.partial_cmp(&a.discovery_score).unwrap());
-        best_candidates.truncate(20);
+        // Leave the predictor trained on every label acquired
+        if !report.labelled.is_empty() {
+            self.ml_predictor.train_model(property.clone()).await?;
+        }
 
-        Ok(best_candidates)
+        Ok(report)
     }
 
-    /// Generate variations of a formula
-    fn generate_variations(&self, formula: &str) -> Vec<String> {
-        let mut variations = Vec::new();
+    /// Label a batch with the oracle and add successes to the training set
+    ///
+    /// Returns (acquired, failed, RMSE of the prior predictions on the batch).
+    async fn label_batch(
+        &self,
+        batch: Vec<PendingLabel>,
+        oracle: &dyn LabelOracle,
+        config: &ActiveLearningConfig,
+        iteration: usize,
+        report: &mut ActiveLearningReport,
+    ) -> Result<(usize, usize, Option<f64>), String> {
+        let materials: Vec<Material> = batch.iter().map(|(m, _, _)| m.clone()).collect();
+        let labels = oracle.label(&config.property, &materials).await;
+        if labels.len() != batch.len() {
+            return Err(format!(
+                "Oracle {} returned {} labels for {} candidates",
+                oracle.name(),
+                labels.len(),
+                batch.len()
+            ));
+        }
 
-        // Simple variations: change stoichiometry
-        variations.push(formula.replace('2', "3"));
-        variations.push(formula.replace('3', "2"));
-        variations.push(formula.replace('2', "4"));
+        let (mut acquired, mut failed) = (0, 0);
+        let mut squared_errors = Vec::new();
+        for ((material, features, prediction), label) in batch.into_iter().zip(labels) {
+            match label {
+                Ok(value) => {
+                    self.ml_predictor
+                        .add_training_data(config.property.clone(), material.id, features, value)
+                        .await?;
+                    if let Some((mean, _)) = prediction {
+                        squared_errors.push((mean - value).powi(2));
+                    }
+                    report.record(
+                        LabelledSample {
+                            material_id: material.id,
+                            formula: material.formula.clone(),
+                            value,
+                            iteration,
+                            predicted: prediction.map(|p| p.0),
+                            predicted_std: prediction.map(|p| p.1),
+                        },
+                        config.goal,
+                    );
+                    acquired += 1;
+                }
+                Err(e) => {
+                    report.failures.push((material.id, e));
+                    failed += 1;
+                }
+            }
+        }
 
-        // Element substitutions
-        variations.push(formula.replace("Fe", "Co"));
-        variations.push(formula.replace("Fe", "Ni"));
-        variations.push(formula.replace("O", "S"));
+        let rmse = if squared_errors.is_empty() {
+            None
+        } else {
+            Some((squared_errors.iter().sum::<f64>() / squared_errors.len() as f64).sqrt())
+        };
+        Ok((acquired, failed, rmse))
+    }
 
-        variations
+    #[allow(clippy::too_many_arguments)]
+    fn curve_point(
+        &self,
+        report: &ActiveLearningReport,
+        iteration: usize,
+        remaining_pool: usize,
+        mean_pool_uncertainty: f64,
+        batch_rmse: Option<f64>,
+        acquired: usize,
+        failed: usize,
+    ) -> LearningCurvePoint {
+        LearningCurvePoint {
+            iteration,
+            labelled: report.labelled.len(),
+            remaining_pool,
+            best_value: report.best.as_ref().map(|b| b.value),
+            best_formula: report.best.as_ref().map(|b| b.formula.clone()),
+            mean_pool_uncertainty,
+            batch_rmse,
+            acquired,
+            failed,
+        }
     }
 
     /// Get discovery statistics
@@ -410,7 +835,15 @@ mod tests {
     async fn test_discovery() {
         let embedding_engine = Arc::new(EmbeddingEngine::new());
         let ml_predictor = Arc::new(MLPredictor::new());
-        ml_predictor.initialize_pretrained_models().await.unwrap();
+        for (formula, energy) in [
+            ("Fe2O3", -2.1), ("Al2O3", -3.4), ("Cr2O3", -2.3), ("Co2O3", -1.6), ("TiO2", -3.3),
+            ("LiF", -3.2), ("NaCl", -2.1), ("FeS", -0.5), ("Ni2O3", -1.3), ("ZrO2", -3.8),
+        ] {
+            ml_predictor.add_training_formula("formation_energy".to_string(), uuid::Uuid::new_v4(), formula, energy)
+                .await
+                .unwrap();
+        }
+        ml_predictor.train_model("formation_energy".to_string()).await.unwrap();
         let knowledge_graph = Arc::new(KnowledgeGraph::new());
 
         let engine = DiscoveryEngine::new(
@@ -435,9 +868,321 @@ mod tests {
             forbidden_elements: vec!["Pb".to_string()],
             application: Some("battery".to_string()),
             objective: OptimizationObjective::Minimize("formation_energy".to_string()),
+            economics: None,
         };
 
         let candidates = engine.discover_materials(target, 10).await.unwrap();
         assert!(!candidates.is_empty());
+        // Every candidate was scored by the composition-feature model
+        assert!(candidates.iter().all(|c| c.predicted_properties.contains_key("formation_energy")));
+        assert!(candidates.iter().all(|c| !c.formula.contains("Pb")));
+    }
+
+    #[tokio::test]
+    async fn test_bayesian_composition_optimization() {
+        let ml_predictor = Arc::new(MLPredictor::new());
+        let featurizer = MagpieFeaturizer::new();
+        let mass = |fe: f64, co: f64, ni: f64| 55.845 * fe + 58.933 * co + 58.693 * ni;
+        for i in 0..=10 {
+            for j in 0..=(10 - i) {
+                let (fe, co, ni) = (i as f64 / 10.0, j as f64 / 10.0, (10 - i - j) as f64 / 10.0);
+                let features = featurizer.featurize(&[("Fe", fe), ("Co", co), ("Ni", ni)]).unwrap();
+                ml_predictor.add_training_data("mass".to_string(), uuid::Uuid::new_v4(), features, mass(fe, co, ni))
+                    .await
+                    .unwrap();
+            }
+        }
+        ml_predictor.train_model("mass".to_string()).await.unwrap();
+
+        let engine = DiscoveryEngine::new(
+            Arc::new(EmbeddingEngine::new()),
+            ml_predictor,
+            Arc::new(KnowledgeGraph::new()),
+        );
+        let target = DiscoveryTarget {
+            target_properties: HashMap::new(),
+            required_elements: Vec::new(),
+            forbidden_elements: vec!["Ni".to_string()],
+            application: None,
+            objective: OptimizationObjective::Maximize("mass".to_string()),
+            economics: None,
+        };
+
+        let config = || BayesianOptimizerConfig { seed: 7, ..Default::default() };
+        let candidates = engine.optimize_composition_with("FeCoNi".to_string(), target.clone(), 4, config())
+            .await
+            .unwrap();
+        assert!(!candidates.is_empty());
+        assert!(candidates.iter().all(|c| !c.formula.contains("Ni")));
+        assert!(candidates.windows(2).all(|w| w[0].discovery_score >= w[1].discovery_score));
+
+        // Same seed, same proposals
+        let rerun = engine.optimize_composition_with("FeCoNi".to_string(), target, 4, config())
+            .await
+            .unwrap();
+        let formulas = |cs: &[MaterialCandidate]| cs.iter().map(|c| c.formula.clone()).collect::<Vec<_>>();
+        assert_eq!(formulas(&candidates), formulas(&rerun));
+
+        // Unconstrained: the best candidate has the highest predicted mass,
+        // which heavier Co makes a Co-rich composition
+        let mass_of = |c: &MaterialCandidate| c.predicted_properties["mass"].predicted_value;
+        let best = &candidates[0];
+        assert!(candidates.iter().all(|c| mass_of(c) <= mass_of(best)));
+        let composition = Composition::parse(&best.formula).unwrap();
+        assert!(composition.fraction("Co") > composition.fraction("Fe"), "{}", best.formula);
+    }
+
+    #[tokio::test]
+    async fn test_active_learning_loop() {
+        use crate::active_learning::{AcquisitionFunction, LookupOracle};
+        use crate::ml_predictor::ModelKind;
+        use crate::tree_ensemble::RandomForestConfig;
+
+        let ml_predictor = Arc::new(MLPredictor::new());
+        ml_predictor.set_model_kind(
+            "mass",
+            ModelKind::RandomForest(RandomForestConfig { n_trees: 20, ..Default::default() }),
+        ).await;
+        let engine = DiscoveryEngine::new(
+            Arc::new(EmbeddingEngine::new()),
+            ml_predictor.clone(),
+            Arc::new(KnowledgeGraph::new()),
+        );
+
+        // Offline oracle: mean atomic mass, a smooth function of composition
+        let cations = ["Li", "Na", "K", "Rb", "Cs", "Mg", "Ca", "Sr", "Ba", "Zn"];
+        let anions = ["F", "Cl", "Br", "I"];
+        let mut oracle = LookupOracle::new();
+        let mut pool = Vec::new();
+        let mut truth = Vec::new();
+        for a in cations {
+            for b in anions {
+                let formula = format!("{}{}", a, b);
+                let mass = (crate::elements::atomic_mass(a).unwrap() + crate::elements::atomic_mass(b).unwrap()) / 2.0;
+                oracle.insert_formula(&formula, "mass", mass);
+                truth.push(mass);
+                pool.push(Material::new(&formula));
+            }
+        }
+        pool.push(Material::new("CsAt")); // not in the table
+        truth.sort_by(|a, b| b.partial_cmp(a).unwrap());
+
+        let target = DiscoveryTarget {
+            target_properties: HashMap::new(),
+            required_elements: Vec::new(),
+            forbidden_elements: Vec::new(),
+            application: None,
+            objective: OptimizationObjective::Maximize("mass".to_string()),
+            economics: None,
+        };
+        let config = target.active_learning_config().unwrap()
+            .with_acquisition(AcquisitionFunction::ExpectedImprovement { xi: 0.01 })
+            .with_initial_samples(6)
+            .with_batch_size(3)
+            .with_iterations(4);
+
+        let report = engine.run_active_learning(pool, &oracle, &config).await.unwrap();
+
+        assert_eq!(report.learning_curve.len(), 5);
+        assert_eq!(report.learning_curve[0].iteration, 0);
+        assert_eq!(report.learning_curve[0].labelled, 6);
+        let labelled = report.labelled.len();
+        assert_eq!(labelled + report.failures.len(), 6 + report.learning_curve[0].failed + 4 * 3);
+        assert_eq!(ml_predictor.training_data_count("mass").await, labelled);
+
+        // 18 of 41 candidates labelled; acquisition should reach the top 3
+        let best = report.best.unwrap().value;
+        assert!(best >= truth[2], "best {} vs top {:?}", best, &truth[..3]);
+        assert!(report.learning_curve.windows(2).all(|w| w[1].best_value >= w[0].best_value));
+    }
+
+    #[tokio::test]
+    async fn test_active_learning_replaces_failed_seeds() {
+        use crate::active_learning::LookupOracle;
+
+        let ml_predictor = Arc::new(MLPredictor::new());
+        let engine = DiscoveryEngine::new(
+            Arc::new(EmbeddingEngine::new()),
+            ml_predictor.clone(),
+            Arc::new(KnowledgeGraph::new()),
+        );
+
+        // Only a third of the pool can be labelled
+        let mut oracle = LookupOracle::new();
+        let mut pool = Vec::new();
+        for (i, formula) in ["LiF", "NaCl", "KBr", "RbI", "CsF", "MgO", "CaS", "SrSe", "BaTe"].into_iter().enumerate() {
+            if i % 3 == 0 {
+                oracle.insert_formula(formula, "mass", i as f64);
+            }
+            pool.push(Material::new(formula));
+        }
+
+        let config = ActiveLearningConfig::new("mass", LearningGoal::Maximize)
+            .with_initial_samples(3)
+            .with_iterations(0);
+        let report = engine.run_active_learning(pool, &oracle, &config).await.unwrap();
+        assert_eq!(report.labelled.len(), 3);
+        assert_eq!(report.learning_curve[0].acquired, 3);
+        assert_eq!(report.learning_curve[0].failed, report.failures.len());
+        assert_eq!(ml_predictor.training_data_count("mass").await, 3);
+
+        // An oracle that labels nothing exhausts the pool without erroring
+        let ml_predictor = Arc::new(MLPredictor::new());
+        let engine = DiscoveryEngine::new(
+            Arc::new(EmbeddingEngine::new()),
+            ml_predictor,
+            Arc::new(KnowledgeGraph::new()),
+        );
+        let pool = vec![Material::new("LiF"), Material::new("NaCl"), Material::new("KBr")];
+        let report = engine.run_active_learning(pool, &LookupOracle::new(), &config.with_iterations(2))
+            .await
+            .unwrap();
+        assert!(report.labelled.is_empty());
+        assert_eq!(report.failures.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_multi_objective_optimization_predicts_objectives() {
+        let ml_predictor = Arc::new(MLPredictor::new());
+        let featurizer = MagpieFeaturizer::new();
+        for i in 0..=20 {
+            let fe = i as f64 / 20.0;
+            let features = featurizer.featurize(&[("Fe", fe), ("Co", 1.0 - fe)]).unwrap();
+            ml_predictor.add_training_data("fe_fraction".to_string(), uuid::Uuid::new_v4(), features.clone(), fe)
+                .await
+                .unwrap();
+            ml_predictor.add_training_data("co_fraction".to_string(), uuid::Uuid::new_v4(), features, 1.0 - fe)
+                .await
+                .unwrap();
+        }
+        ml_predictor.train_model("fe_fraction".to_string()).await.unwrap();
+        ml_predictor.train_model("co_fraction".to_string()).await.unwrap();
+
+        let engine = DiscoveryEngine::new(
+            Arc::new(EmbeddingEngine::new()),
+            ml_predictor,
+            Arc::new(KnowledgeGraph::new()),
+        );
+        // Prefixed specs without property constraints: pure trade-off
+        let target = DiscoveryTarget {
+            target_properties: HashMap::new(),
+            required_elements: Vec::new(),
+            forbidden_elements: Vec::new(),
+            application: None,
+            objective: OptimizationObjective::MultiObjective(vec![
+                "max:fe_fraction".to_string(),
+                "max:co_fraction".to_string(),
+            ]),
+            economics: None,
+        };
+
+        let candidates = engine.optimize_composition("FeCo".to_string(), target.clone(), 3).await.unwrap();
+        assert!(candidates.iter().all(|c| {
+            c.predicted_properties.contains_key("fe_fraction") && c.predicted_properties.contains_key("co_fraction")
+        }));
+        assert!(!candidates.iter().any(|c| c.predicted_properties.contains_key("max:fe_fraction")));
+
+        // Every candidate gets a Pareto rank; two distinct compositions that
+        // trade one objective against the other share the front
+        assert!(candidates.iter().all(|c| c.pareto_rank.is_some()));
+        let front = DiscoveryEngine::pareto_front(&candidates, &target);
+        assert!(front.len() >= 2, "front {:?}", front.iter().map(|c| &c.formula).collect::<Vec<_>>());
+        assert!(front.iter().all(|c| c.pareto_rank == Some(0)));
+    }
+
+    #[test]
+    fn test_multi_objective_ranking() {
+        let candidate = |formula: &str, values: &[(&str, f64)]| MaterialCandidate {
+            formula: formula.to_string(),
+            predicted_properties: values.iter().map(|(name, v)| {
+                (name.to_string(), PropertyPrediction {
+                    property_name: name.to_string(),
+                    predicted_value: *v,
+                    confidence_interval: (*v, *v),
+                    confidence_score: 1.0,
+                    model_version: "test".to_string(),
+                    feature_importance: HashMap::new(),
+                })
+            }).collect(),
+            discovery_score: 0.0,
+            confidence: 1.0,
+            synthesis_feasibility: 1.0,
+            novelty_score: 0.0,
+            reasoning: Vec::new(),
+            pareto_rank: None,
+        };
+
+        let mut target_properties = HashMap::new();
+        target_properties.insert(
+            "band_gap".to_string(),
+            PropertyConstraint { target_value: 1.5, tolerance: 0.5, weight: 1.0 },
+        );
+        let target = DiscoveryTarget {
+            target_properties,
+            required_elements: Vec::new(),
+            forbidden_elements: Vec::new(),
+            application: None,
+            objective: OptimizationObjective::MultiObjective(vec![
+                "band_gap".to_string(),
+                "min:formation_energy".to_string(),
+            ]),
+            economics: None,
+        };
+        let objectives = target.objectives();
+        assert_eq!(objectives[0].goal, LearningGoal::Target(1.5));
+        assert_eq!(objectives[1].goal, LearningGoal::Minimize);
+
+        let mut candidates = vec![
+            candidate("A", &[("band_gap", 3.0), ("formation_energy", -1.0)]), // dominated by B
+            candidate("B", &[("band_gap", 1.5), ("formation_energy", -1.0)]),
+            candidate("C", &[("band_gap", 2.5), ("formation_energy", -3.0)]),
+            candidate("D", &[("band_gap", 1.5)]),
+        ];
+        DiscoveryEngine::rank_multi_objective(&mut candidates, &target, &MultiObjectiveRanking::Pareto);
+
+        let order: Vec<&str> = candidates.iter().map(|c| c.formula.as_str()).collect();
+        assert_eq!(order[2..], ["A", "D"]);
+        assert_eq!(candidates[0].pareto_rank, Some(0));
+        assert_eq!(candidates[2].pareto_rank, Some(1));
+        assert_eq!(candidates[3].pareto_rank, None);
+
+        let front = DiscoveryEngine::pareto_front(&candidates, &target);
+        assert_eq!(front.len(), 2);
+
+        let mut tracker = HypervolumeTracker::with_reference(vec![-5.0, -5.0]);
+        let hv = DiscoveryEngine::track_hypervolume(&mut tracker, "round 1", &candidates, &target);
+        assert!(hv > 0.0);
+        assert_eq!(tracker.history[0].front_size, 2);
+
+        // Weighted fallback on formation energy alone
+        let weights = HashMap::from([("band_gap".to_string(), 0.0), ("formation_energy".to_string(), 1.0)]);
+        DiscoveryEngine::rank_multi_objective(
+            &mut candidates,
+            &target,
+            &MultiObjectiveRanking::Scalarized(Scalarization::WeightedSum(weights)),
+        );
+        assert_eq!(candidates[0].formula, "C");
+        assert!(candidates.iter().all(|c| c.pareto_rank.is_none()));
+    }
+
+    #[test]
+    fn test_economic_filter() {
+        let candidate = |formula: &str| MaterialCandidate {
+            formula: formula.to_string(),
+            predicted_properties: HashMap::new(),
+            discovery_score: 0.0,
+            confidence: 1.0,
+            synthesis_feasibility: 1.0,
+            novelty_score: 0.0,
+            reasoning: Vec::new(),
+            pareto_rank: None,
+        };
+        let mut candidates = vec![candidate("LiFePO4"), candidate("LiCoO2"), candidate("Nd2Fe14B"), candidate("GaAs")];
+        let filter = EconomicFilter { max_supply_risk: Some(0.4), exclude_toxic: true, ..Default::default() };
+        DiscoveryEngine::apply_economic_filter(&mut candidates, &filter);
+
+        let kept: Vec<&str> = candidates.iter().map(|c| c.formula.as_str()).collect();
+        assert_eq!(kept, ["LiFePO4"]);
+        assert!(candidates[0].reasoning[0].contains("supply risk"));
     }
 }
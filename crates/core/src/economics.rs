@@ -0,0 +1,399 @@
+//! Element Economics and Supply Risk
+//!
+//! Element-level raw material data and the per-material scores derived from
+//! it: cost per kilogram, supply risk and sustainability. Tabulated values
+//! are approximate 2020s reference figures: prices in USD/kg for the
+//! commodity form (USGS Mineral Commodity Summaries, metal or oxide as
+//! traded), upper continental crust abundances in ppm by mass (CRC Handbook),
+//! and the Herfindahl-Hirschman index of world production (0-10000, the
+//! convention of Gaultois et al. 2013). They are meant for ranking
+//! candidates, not for procurement.
+//!
+//! Material scores are mass-weighted over the composition. Each element's
+//! risk combines production concentration and crustal scarcity,
+//! `1 - (1 - hhi/10000)(1 - scarcity)`, with scarcity running from 0 at
+//! 10^5 ppm to 1 at 10^-5 ppm on a log scale. Sustainability is the
+//! complement of supply risk, discounted by the mass fraction of toxic
+//! elements. Radioactive elements count as toxic whether tabulated or not.
+
+use crate::elements;
+use crate::composition::Composition;
+use crate::recommendations::AvailabilityScore;
+use crate::{Error, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Property names under which material scores are exposed to filters
+pub const COST_PER_KG: &str = "cost_per_kg";
+pub const SUPPLY_RISK: &str = "supply_risk";
+pub const SUSTAINABILITY: &str = "sustainability";
+pub const HHI: &str = "hhi";
+
+/// Raw material data for one element
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementSupply {
+    pub symbol: &'static str,
+    /// Commodity price (USD/kg)
+    pub price_per_kg: f64,
+    /// Crustal abundance (ppm by mass)
+    pub abundance_ppm: f64,
+    /// Herfindahl-Hirschman index of production (0-10000)
+    pub hhi_production: f64,
+    /// Toxic or radioactive in common forms
+    pub toxic: bool,
+}
+
+impl ElementSupply {
+    /// Crustal scarcity in [0, 1]
+    pub fn scarcity(&self) -> f64 {
+        ((5.0 - self.abundance_ppm.log10()) / 10.0).clamp(0.0, 1.0)
+    }
+
+    /// Supply risk in [0, 1] from production concentration and scarcity
+    pub fn supply_risk(&self) -> f64 {
+        1.0 - (1.0 - self.hhi_production / 10000.0) * (1.0 - self.scarcity())
+    }
+}
+
+#[rustfmt::skip]
+const TABLE: &[(&str, f64, f64, f64, bool)] = &[
+    // symbol, price (USD/kg), crustal abundance (ppm), HHI production, toxic
+    ("H", 1.4, 1400.0, 500.0, false),
+    ("He", 24.0, 0.008, 3200.0, false),
+    ("Li", 85.0, 20.0, 2900.0, false),
+    ("Be", 850.0, 2.8, 8000.0, true),
+    ("B", 4.0, 10.0, 2900.0, false),
+    ("C", 1.0, 200.0, 5000.0, false),
+    ("N", 0.14, 19.0, 500.0, false),
+    ("O", 0.15, 461000.0, 500.0, false),
+    ("F", 2.0, 585.0, 3300.0, false),
+    ("Ne", 240.0, 0.005, 500.0, false),
+    ("Na", 3.0, 23600.0, 1100.0, false),
+    ("Mg", 2.3, 23300.0, 5300.0, false),
+    ("Al", 2.0, 82300.0, 1600.0, false),
+    ("Si", 1.9, 282000.0, 4700.0, false),
+    ("P", 2.7, 1050.0, 2000.0, false),
+    ("S", 0.1, 350.0, 700.0, false),
+    ("Cl", 0.08, 145.0, 1500.0, false),
+    ("Ar", 1.0, 3.5, 500.0, false),
+    ("K", 13.0, 20900.0, 1700.0, false),
+    ("Ca", 2.4, 41500.0, 3900.0, false),
+    ("Sc", 3500.0, 22.0, 5500.0, false),
+    ("Ti", 11.0, 5650.0, 1100.0, false),
+    ("V", 30.0, 120.0, 3400.0, false),
+    ("Cr", 9.0, 102.0, 3100.0, false),
+    ("Mn", 2.0, 950.0, 1600.0, false),
+    ("Fe", 0.4, 56300.0, 2400.0, false),
+    ("Co", 33.0, 25.0, 5000.0, false),
+    ("Ni", 14.0, 84.0, 2500.0, false),
+    ("Cu", 9.0, 60.0, 1600.0, false),
+    ("Zn", 3.0, 70.0, 1600.0, false),
+    ("Ga", 300.0, 19.0, 9000.0, false),
+    ("Ge", 1300.0, 1.5, 6600.0, false),
+    ("As", 1.5, 1.8, 3300.0, true),
+    ("Se", 30.0, 0.05, 2000.0, true),
+    ("Br", 4.0, 2.4, 3300.0, false),
+    ("Kr", 290.0, 0.0001, 500.0, false),
+    ("Rb", 15000.0, 90.0, 6000.0, false),
+    ("Sr", 6.5, 370.0, 4200.0, false),
+    ("Y", 35.0, 33.0, 9800.0, false),
+    ("Zr", 37.0, 165.0, 3400.0, false),
+    ("Nb", 60.0, 20.0, 8500.0, false),
+    ("Mo", 40.0, 1.2, 2400.0, false),
+    ("Ru", 15000.0, 0.001, 3200.0, false),
+    ("Rh", 150000.0, 0.001, 6000.0, false),
+    ("Pd", 50000.0, 0.015, 3200.0, false),
+    ("Ag", 750.0, 0.075, 1200.0, false),
+    ("Cd", 3.0, 0.15, 1700.0, true),
+    ("In", 250.0, 0.25, 3300.0, false),
+    ("Sn", 30.0, 2.3, 2600.0, false),
+    ("Sb", 12.0, 0.2, 7900.0, true),
+    ("Te", 70.0, 0.001, 2900.0, true),
+    ("I", 35.0, 0.45, 4900.0, false),
+    ("Xe", 1800.0, 0.00003, 500.0, false),
+    ("Cs", 60000.0, 3.0, 6000.0, false),
+    ("Ba", 0.3, 425.0, 3000.0, false),
+    ("La", 5.0, 39.0, 9500.0, false),
+    ("Ce", 5.0, 66.5, 9500.0, false),
+    ("Pr", 100.0, 9.2, 9500.0, false),
+    ("Nd", 100.0, 41.5, 9500.0, false),
+    ("Sm", 15.0, 7.05, 9500.0, false),
+    ("Eu", 30.0, 2.0, 9500.0, false),
+    ("Gd", 40.0, 6.2, 9500.0, false),
+    ("Tb", 1500.0, 1.2, 9500.0, false),
+    ("Dy", 350.0, 5.2, 9500.0, false),
+    ("Ho", 60.0, 1.3, 9500.0, false),
+    ("Er", 40.0, 3.5, 9500.0, false),
+    ("Tm", 3000.0, 0.52, 9500.0, false),
+    ("Yb", 15.0, 3.2, 9500.0, false),
+    ("Lu", 700.0, 0.8, 9500.0, false),
+    ("Hf", 1200.0, 3.0, 3400.0, false),
+    ("Ta", 300.0, 2.0, 2300.0, false),
+    ("W", 35.0, 1.25, 7000.0, false),
+    ("Re", 2000.0, 0.0007, 3300.0, false),
+    ("Os", 12000.0, 0.0015, 5500.0, false),
+    ("Ir", 150000.0, 0.001, 5500.0, false),
+    ("Pt", 30000.0, 0.005, 5500.0, false),
+    ("Au", 60000.0, 0.004, 1100.0, false),
+    ("Hg", 30.0, 0.085, 5500.0, true),
+    ("Tl", 4000.0, 0.85, 6500.0, true),
+    ("Pb", 2.0, 14.0, 2700.0, true),
+    ("Bi", 8.0, 0.0085, 5300.0, false),
+    ("Th", 290.0, 9.6, 5000.0, true),
+    ("U", 100.0, 2.7, 1800.0, true),
+];
+
+static SUPPLY: Lazy<HashMap<&'static str, ElementSupply>> = Lazy::new(|| {
+    TABLE
+        .iter()
+        .map(|&(symbol, price_per_kg, abundance_ppm, hhi_production, toxic)| {
+            (symbol, ElementSupply { symbol, price_per_kg, abundance_ppm, hhi_production, toxic })
+        })
+        .collect()
+});
+
+/// Supply data for a symbol, if tabulated
+pub fn element_supply(symbol: &str) -> Option<&'static ElementSupply> {
+    SUPPLY.get(symbol)
+}
+
+/// Cost, supply risk and sustainability of one composition
+///
+/// Elements without supply data are listed in `unknown_elements`; they count
+/// as maximal risk and zero sustainability and add nothing to the cost, so
+/// `cost_per_kg` is only a lower bound unless [`Self::is_complete`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaterialEconomics {
+    /// Raw material cost (USD/kg)
+    pub cost_per_kg: f64,
+    /// Mass-weighted production HHI
+    pub hhi: f64,
+    /// Mass-weighted geometric mean crustal abundance (ppm)
+    pub abundance_ppm: f64,
+    /// 0 (secure) to 1 (critical)
+    pub supply_risk: f64,
+    /// 0 to 1, higher is better
+    pub sustainability: f64,
+    /// Mass fraction of toxic elements
+    pub toxic_fraction: f64,
+    pub toxic_elements: Vec<String>,
+    pub unknown_elements: Vec<String>,
+}
+
+impl MaterialEconomics {
+    /// Scores for a formula such as "LiFePO4"
+    pub fn from_formula(formula: &str) -> Result<Self> {
+        Self::from_amounts(Composition::parse(formula)?.iter())
+    }
+
+    /// Scores for (element, amount) pairs; amounts are atoms, not masses
+    pub fn from_amounts<'a>(amounts: impl IntoIterator<Item = (&'a str, f64)>) -> Result<Self> {
+        let mut masses: Vec<(&str, f64)> = Vec::new();
+        for (symbol, amount) in amounts {
+            if amount <= 0.0 {
+                continue;
+            }
+            let mass = elements::atomic_mass(symbol)
+                .ok_or_else(|| Error::invalid_input(format!("Unknown element: {}", symbol)))?;
+            match masses.iter_mut().find(|(s, _)| *s == symbol) {
+                Some((_, m)) => *m += amount * mass,
+                None => masses.push((symbol, amount * mass)),
+            }
+        }
+        let total: f64 = masses.iter().map(|(_, m)| m).sum();
+        if total <= 0.0 {
+            return Err(Error::invalid_input("Empty composition"));
+        }
+        masses.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut economics = Self {
+            cost_per_kg: 0.0,
+            hhi: 0.0,
+            abundance_ppm: 0.0,
+            supply_risk: 0.0,
+            sustainability: 0.0,
+            toxic_fraction: 0.0,
+            toxic_elements: Vec::new(),
+            unknown_elements: Vec::new(),
+        };
+        let mut log_abundance = 0.0;
+        let mut known_fraction = 0.0;
+        for (symbol, mass) in masses {
+            let w = mass / total;
+            match element_supply(symbol) {
+                Some(supply) => {
+                    economics.cost_per_kg += w * supply.price_per_kg;
+                    economics.hhi += w * supply.hhi_production;
+                    economics.supply_risk += w * supply.supply_risk();
+                    log_abundance += w * supply.abundance_ppm.log10();
+                    known_fraction += w;
+                    if supply.toxic {
+                        economics.toxic_fraction += w;
+                        economics.toxic_elements.push(symbol.to_string());
+                    }
+                }
+                None => {
+                    economics.supply_risk += w;
+                    economics.unknown_elements.push(symbol.to_string());
+                    if elements::element(symbol).is_some_and(|e| e.is_radioactive()) {
+                        economics.toxic_fraction += w;
+                        economics.toxic_elements.push(symbol.to_string());
+                    }
+                }
+            }
+        }
+        if known_fraction > 0.0 {
+            economics.hhi /= known_fraction;
+            economics.abundance_ppm = 10f64.powf(log_abundance / known_fraction);
+        }
+        economics.sustainability = (1.0 - economics.supply_risk) * (1.0 - economics.toxic_fraction);
+        Ok(economics)
+    }
+
+    /// Every element has supply data
+    pub fn is_complete(&self) -> bool {
+        self.unknown_elements.is_empty()
+    }
+
+    pub fn availability(&self) -> AvailabilityScore {
+        if !self.is_complete() {
+            AvailabilityScore::Unknown
+        } else if self.supply_risk < 0.33 {
+            AvailabilityScore::High
+        } else if self.supply_risk < 0.6 {
+            AvailabilityScore::Medium
+        } else {
+            AvailabilityScore::Low
+        }
+    }
+
+    /// Score by property name (`cost_per_kg`, `supply_risk`, `sustainability`, `hhi`)
+    pub fn property(&self, name: &str) -> Option<f64> {
+        match name {
+            COST_PER_KG => Some(self.cost_per_kg),
+            SUPPLY_RISK => Some(self.supply_risk),
+            SUSTAINABILITY => Some(self.sustainability),
+            HHI => Some(self.hhi),
+            _ => None,
+        }
+    }
+
+    /// All scores keyed by property name
+    pub fn properties(&self) -> HashMap<String, f64> {
+        [COST_PER_KG, SUPPLY_RISK, SUSTAINABILITY, HHI]
+            .iter()
+            .filter_map(|&name| self.property(name).map(|v| (name.to_string(), v)))
+            .collect()
+    }
+}
+
+/// Whether `name` is one of the scores computed here
+pub fn is_economic_property(name: &str) -> bool {
+    matches!(name, COST_PER_KG | SUPPLY_RISK | SUSTAINABILITY | HHI)
+}
+
+/// Limits on cost, supply risk and sustainability; unset limits pass
+///
+/// Once any limit is set, compositions with untabulated elements fail:
+/// their scores are incomplete.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EconomicFilter {
+    #[serde(default)]
+    pub max_cost_per_kg: Option<f64>,
+    #[serde(default)]
+    pub max_supply_risk: Option<f64>,
+    #[serde(default)]
+    pub min_sustainability: Option<f64>,
+    #[serde(default)]
+    pub exclude_toxic: bool,
+}
+
+impl EconomicFilter {
+    pub fn accepts(&self, economics: &MaterialEconomics) -> bool {
+        self.violations(economics).is_empty()
+    }
+
+    /// Human-readable reasons `economics` fails the filter
+    pub fn violations(&self, economics: &MaterialEconomics) -> Vec<String> {
+        let mut violations = Vec::new();
+        if !economics.is_complete() && *self != Self::default() {
+            violations.push(format!("No supply data for {}", economics.unknown_elements.join(", ")));
+        }
+        if let Some(max) = self.max_cost_per_kg {
+            if economics.cost_per_kg > max {
+                violations.push(format!("Cost {:.1} USD/kg exceeds {:.1}", economics.cost_per_kg, max));
+            }
+        }
+        if let Some(max) = self.max_supply_risk {
+            if economics.supply_risk > max {
+                violations.push(format!("Supply risk {:.2} exceeds {:.2}", economics.supply_risk, max));
+            }
+        }
+        if let Some(min) = self.min_sustainability {
+            if economics.sustainability < min {
+                violations.push(format!("Sustainability {:.2} below {:.2}", economics.sustainability, min));
+            }
+        }
+        if self.exclude_toxic && !economics.toxic_elements.is_empty() {
+            violations.push(format!("Contains toxic elements: {}", economics.toxic_elements.join(", ")));
+        }
+        violations
+    }
+
+    /// Check a formula; formulas that cannot be scored fail any set limit
+    pub fn accepts_formula(&self, formula: &str) -> bool {
+        if *self == Self::default() {
+            return true;
+        }
+        MaterialEconomics::from_formula(formula).is_ok_and(|e| self.accepts(&e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_material_scores() {
+        let hematite = MaterialEconomics::from_formula("Fe2O3").unwrap();
+        let licoo2 = MaterialEconomics::from_formula("LiCoO2").unwrap();
+        let platinum = MaterialEconomics::from_formula("Pt").unwrap();
+
+        // Mass-weighted cost: 70% Fe at 0.4, 30% O at 0.15
+        assert!((hematite.cost_per_kg - 0.325).abs() < 0.01);
+        assert_eq!(hematite.availability(), AvailabilityScore::High);
+        assert_eq!(licoo2.availability(), AvailabilityScore::Medium);
+        assert_eq!(platinum.availability(), AvailabilityScore::Low);
+        assert!(hematite.supply_risk < licoo2.supply_risk && licoo2.supply_risk < platinum.supply_risk);
+
+        let pbte = MaterialEconomics::from_formula("PbTe").unwrap();
+        assert_eq!(pbte.toxic_elements, vec!["Pb".to_string(), "Te".to_string()]);
+        assert!(pbte.toxic_fraction > 0.99);
+
+        let filter = EconomicFilter { max_cost_per_kg: Some(50.0), exclude_toxic: true, ..Default::default() };
+        assert!(filter.accepts(&hematite));
+        assert!(!filter.accepts(&platinum));
+        assert_eq!(filter.violations(&pbte).len(), 1);
+        assert!(!filter.accepts_formula("Fe2O3x"));
+        assert!(EconomicFilter::default().accepts_formula("Fe2O3x"));
+
+        // Promethium has no supply data
+        let pm = MaterialEconomics::from_amounts([("Pm", 1.0), ("O", 1.0)]).unwrap();
+        assert_eq!(pm.availability(), AvailabilityScore::Unknown);
+        assert!(MaterialEconomics::from_amounts([("Xx", 1.0)]).is_err());
+
+        // Untabulated radioactive elements are hazardous; incomplete scores
+        // fail any set limit, however loose
+        for formula in ["Pm2O3", "TcO2", "PoO2", "RaO", "Ac2O3"] {
+            let economics = MaterialEconomics::from_formula(formula).unwrap();
+            assert!(!economics.is_complete());
+            assert_eq!(economics.toxic_elements, economics.unknown_elements, "{}", formula);
+        }
+        let loose = EconomicFilter { max_cost_per_kg: Some(1e9), ..Default::default() };
+        assert!(!loose.accepts(&pm));
+        assert!(loose.violations(&pm)[0].contains("Pm"));
+        assert!(EconomicFilter::default().accepts(&pm));
+        assert!(!loose.accepts_formula("TcO2"));
+    }
+}
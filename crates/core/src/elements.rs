@@ -0,0 +1,556 @@
+//! Periodic Table Database
+//!
+//! Elemental properties for H through Lr used by featurizers, molar masses
+//! and embeddings. Tabulated values are atomic mass (IUPAC standard weights,
+//! mass number of the longest-lived isotope for radioactive elements),
+//! Pauling electronegativity, covalent radius (Cordero et al. 2008) and
+//! melting point. Period, group and valence-shell occupations are derived
+//! from the atomic number and the ground-state electron configuration.
+//! Ionic radii are Shannon (1976) effective radii for the common ions of
+//! about seventy elements; their charges double as the oxidation states
+//! offered to structure generation. Common oxidation states (Greenwood &
+//! Earnshaw) cover every element and feed formula charge balancing.
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+
+/// Elemental data for one element
+#[derive(Debug, Clone, PartialEq)]
+pub struct Element {
+    /// Atomic number
+    pub z: u8,
+    pub symbol: &'static str,
+    pub name: &'static str,
+    /// Atomic mass (u)
+    pub atomic_mass: f64,
+    /// Pauling electronegativity (None for He, Ne, Ar)
+    pub electronegativity: Option<f64>,
+    /// Covalent radius (pm)
+    pub covalent_radius: f64,
+    /// Melting point (K)
+    pub melting_point: f64,
+}
+
+/// Electrons outside the preceding noble-gas core, per subshell type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValenceShells {
+    pub s: u8,
+    pub p: u8,
+    pub d: u8,
+    pub f: u8,
+}
+
+impl ValenceShells {
+    pub fn total(&self) -> u8 {
+        self.s + self.p + self.d + self.f
+    }
+}
+
+#[rustfmt::skip]
+const TABLE: &[(&str, &str, f64, f64, f64, f64)] = &[
+    // symbol, name, mass, electronegativity (0 = undefined), covalent radius, melting point
+    ("H", "Hydrogen", 1.008, 2.20, 31.0, 14.01),
+    ("He", "Helium", 4.0026, 0.0, 28.0, 0.95),
+    ("Li", "Lithium", 6.94, 0.98, 128.0, 453.69),
+    ("Be", "Beryllium", 9.0122, 1.57, 96.0, 1560.0),
+    ("B", "Boron", 10.81, 2.04, 84.0, 2349.0),
+    ("C", "Carbon", 12.011, 2.55, 76.0, 3800.0),
+    ("N", "Nitrogen", 14.007, 3.04, 71.0, 63.15),
+    ("O", "Oxygen", 15.999, 3.44, 66.0, 54.36),
+    ("F", "Fluorine", 18.998, 3.98, 57.0, 53.53),
+    ("Ne", "Neon", 20.180, 0.0, 58.0, 24.56),
+    ("Na", "Sodium", 22.990, 0.93, 166.0, 370.87),
+    ("Mg", "Magnesium", 24.305, 1.31, 141.0, 923.0),
+    ("Al", "Aluminium", 26.982, 1.61, 121.0, 933.47),
+    ("Si", "Silicon", 28.085, 1.90, 111.0, 1687.0),
+    ("P", "Phosphorus", 30.974, 2.19, 107.0, 317.3),
+    ("S", "Sulfur", 32.06, 2.58, 105.0, 388.36),
+    ("Cl", "Chlorine", 35.45, 3.16, 102.0, 171.6),
+    ("Ar", "Argon", 39.948, 0.0, 106.0, 83.8),
+    ("K", "Potassium", 39.098, 0.82, 203.0, 336.53),
+    ("Ca", "Calcium", 40.078, 1.00, 176.0, 1115.0),
+    ("Sc", "Scandium", 44.956, 1.36, 170.0, 1814.0),
+    ("Ti", "Titanium", 47.867, 1.54, 160.0, 1941.0),
+    ("V", "Vanadium", 50.942, 1.63, 153.0, 2183.0),
+    ("Cr", "Chromium", 51.996, 1.66, 139.0, 2180.0),
+    ("Mn", "Manganese", 54.938, 1.55, 139.0, 1519.0),
+    ("Fe", "Iron", 55.845, 1.83, 132.0, 1811.0),
+    ("Co", "Cobalt", 58.933, 1.88, 126.0, 1768.0),
+    ("Ni", "Nickel", 58.693, 1.91, 124.0, 1728.0),
+    ("Cu", "Copper", 63.546, 1.90, 132.0, 1357.77),
+    ("Zn", "Zinc", 65.38, 1.65, 122.0, 692.68),
+    ("Ga", "Gallium", 69.723, 1.81, 122.0, 302.91),
+    ("Ge", "Germanium", 72.630, 2.01, 120.0, 1211.4),
+    ("As", "Arsenic", 74.922, 2.18, 119.0, 1090.0),
+    ("Se", "Selenium", 78.971, 2.55, 120.0, 494.0),
+    ("Br", "Bromine", 79.904, 2.96, 120.0, 265.8),
+    ("Kr", "Krypton", 83.798, 3.00, 116.0, 115.79),
+    ("Rb", "Rubidium", 85.468, 0.82, 220.0, 312.46),
+    ("Sr", "Strontium", 87.62, 0.95, 195.0, 1050.0),
+    ("Y", "Yttrium", 88.906, 1.22, 190.0, 1799.0),
+    ("Zr", "Zirconium", 91.224, 1.33, 175.0, 2128.0),
+    ("Nb", "Niobium", 92.906, 1.60, 164.0, 2750.0),
+    ("Mo", "Molybdenum", 95.95, 2.16, 154.0, 2896.0),
+    ("Tc", "Technetium", 98.0, 1.90, 147.0, 2430.0),
+    ("Ru", "Ruthenium", 101.07, 2.20, 146.0, 2607.0),
+    ("Rh", "Rhodium", 102.91, 2.28, 142.0, 2237.0),
+    ("Pd", "Palladium", 106.42, 2.20, 139.0, 1828.05),
+    ("Ag", "Silver", 107.87, 1.93, 145.0, 1234.93),
+    ("Cd", "Cadmium", 112.41, 1.69, 144.0, 594.22),
+    ("In", "Indium", 114.82, 1.78, 142.0, 429.75),
+    ("Sn", "Tin", 118.71, 1.96, 139.0, 505.08),
+    ("Sb", "Antimony", 121.76, 2.05, 139.0, 903.78),
+    ("Te", "Tellurium", 127.60, 2.10, 138.0, 722.66),
+    ("I", "Iodine", 126.90, 2.66, 139.0, 386.85),
+    ("Xe", "Xenon", 131.29, 2.60, 140.0, 161.4),
+    ("Cs", "Caesium", 132.91, 0.79, 244.0, 301.59),
+    ("Ba", "Barium", 137.33, 0.89, 215.0, 1000.0),
+    ("La", "Lanthanum", 138.91, 1.10, 207.0, 1193.0),
+    ("Ce", "Cerium", 140.12, 1.12, 204.0, 1068.0),
+    ("Pr", "Praseodymium", 140.91, 1.13, 203.0, 1208.0),
+    ("Nd", "Neodymium", 144.24, 1.14, 201.0, 1297.0),
+    ("Pm", "Promethium", 145.0, 1.13, 199.0, 1315.0),
+    ("Sm", "Samarium", 150.36, 1.17, 198.0, 1345.0),
+    ("Eu", "Europium", 151.96, 1.20, 198.0, 1099.0),
+    ("Gd", "Gadolinium", 157.25, 1.20, 196.0, 1585.0),
+    ("Tb", "Terbium", 158.93, 1.10, 194.0, 1629.0),
+    ("Dy", "Dysprosium", 162.50, 1.22, 192.0, 1680.0),
+    ("Ho", "Holmium", 164.93, 1.23, 192.0, 1734.0),
+    ("Er", "Erbium", 167.26, 1.24, 189.0, 1802.0),
+    ("Tm", "Thulium", 168.93, 1.25, 190.0, 1818.0),
+    ("Yb", "Ytterbium", 173.05, 1.10, 187.0, 1097.0),
+    ("Lu", "Lutetium", 174.97, 1.27, 187.0, 1925.0),
+    ("Hf", "Hafnium", 178.49, 1.30, 175.0, 2506.0),
+    ("Ta", "Tantalum", 180.95, 1.50, 170.0, 3290.0),
+    ("W", "Tungsten", 183.84, 2.36, 162.0, 3695.0),
+    ("Re", "Rhenium", 186.21, 1.90, 151.0, 3459.0),
+    ("Os", "Osmium", 190.23, 2.20, 144.0, 3306.0),
+    ("Ir", "Iridium", 192.22, 2.20, 141.0, 2719.0),
+    ("Pt", "Platinum", 195.08, 2.28, 136.0, 2041.4),
+    ("Au", "Gold", 196.97, 2.54, 136.0, 1337.33),
+    ("Hg", "Mercury", 200.59, 2.00, 132.0, 234.32),
+    ("Tl", "Thallium", 204.38, 1.62, 145.0, 577.0),
+    ("Pb", "Lead", 207.2, 2.33, 146.0, 600.61),
+    ("Bi", "Bismuth", 208.98, 2.02, 148.0, 544.7),
+    ("Po", "Polonium", 209.0, 2.00, 140.0, 527.0),
+    ("At", "Astatine", 210.0, 2.20, 150.0, 575.0),
+    ("Rn", "Radon", 222.0, 2.20, 150.0, 202.0),
+    ("Fr", "Francium", 223.0, 0.70, 260.0, 300.0),
+    ("Ra", "Radium", 226.0, 0.90, 221.0, 973.0),
+    ("Ac", "Actinium", 227.0, 1.10, 215.0, 1323.0),
+    ("Th", "Thorium", 232.04, 1.30, 206.0, 2023.0),
+    ("Pa", "Protactinium", 231.04, 1.50, 200.0, 1841.0),
+    ("U", "Uranium", 238.03, 1.38, 196.0, 1405.3),
+    ("Np", "Neptunium", 237.0, 1.36, 190.0, 917.0),
+    ("Pu", "Plutonium", 244.0, 1.28, 187.0, 912.5),
+    ("Am", "Americium", 243.0, 1.13, 180.0, 1449.0),
+    ("Cm", "Curium", 247.0, 1.28, 169.0, 1613.0),
+    ("Bk", "Berkelium", 247.0, 1.30, 168.0, 1259.0),
+    ("Cf", "Californium", 251.0, 1.30, 168.0, 1173.0),
+    ("Es", "Einsteinium", 252.0, 1.30, 165.0, 1133.0),
+    ("Fm", "Fermium", 257.0, 1.30, 167.0, 1800.0),
+    ("Md", "Mendelevium", 258.0, 1.30, 173.0, 1100.0),
+    ("No", "Nobelium", 259.0, 1.30, 176.0, 1100.0),
+    ("Lr", "Lawrencium", 266.0, 1.30, 161.0, 1900.0),
+];
+
+const NOBLE_GASES: [u8; 6] = [2, 10, 18, 36, 54, 86];
+
+/// Subshells in Madelung (n + l, n) filling order: (n, l)
+const MADELUNG_ORDER: [(u8, u8); 19] = [
+    (1, 0), (2, 0), (2, 1), (3, 0), (3, 1), (4, 0), (3, 2), (4, 1), (5, 0), (4, 2),
+    (5, 1), (6, 0), (4, 3), (5, 2), (6, 1), (7, 0), (5, 3), (6, 2), (7, 1),
+];
+
+/// Occupancy change of subshell (n, l)
+type ShellDelta = (u8, u8, i8);
+
+/// Ground states that deviate from the Madelung rule: (Z, [(n, l, delta)])
+#[rustfmt::skip]
+const CONFIG_EXCEPTIONS: &[(u8, &[ShellDelta])] = &[
+    (24, &[(3, 2, 1), (4, 0, -1)]),   // Cr
+    (29, &[(3, 2, 1), (4, 0, -1)]),   // Cu
+    (41, &[(4, 2, 1), (5, 0, -1)]),   // Nb
+    (42, &[(4, 2, 1), (5, 0, -1)]),   // Mo
+    (44, &[(4, 2, 1), (5, 0, -1)]),   // Ru
+    (45, &[(4, 2, 1), (5, 0, -1)]),   // Rh
+    (46, &[(4, 2, 2), (5, 0, -2)]),   // Pd
+    (47, &[(4, 2, 1), (5, 0, -1)]),   // Ag
+    (57, &[(5, 2, 1), (4, 3, -1)]),   // La
+    (58, &[(5, 2, 1), (4, 3, -1)]),   // Ce
+    (64, &[(5, 2, 1), (4, 3, -1)]),   // Gd
+    (78, &[(5, 2, 1), (6, 0, -1)]),   // Pt
+    (79, &[(5, 2, 1), (6, 0, -1)]),   // Au
+    (89, &[(6, 2, 1), (5, 3, -1)]),   // Ac
+    (90, &[(6, 2, 2), (5, 3, -2)]),   // Th
+    (91, &[(6, 2, 1), (5, 3, -1)]),   // Pa
+    (92, &[(6, 2, 1), (5, 3, -1)]),   // U
+    (93, &[(6, 2, 1), (5, 3, -1)]),   // Np
+    (96, &[(6, 2, 1), (5, 3, -1)]),   // Cm
+    (103, &[(7, 1, 1), (6, 2, -1)]),  // Lr
+];
+
+static ELEMENTS: Lazy<Vec<Element>> = Lazy::new(|| {
+    TABLE
+        .iter()
+        .enumerate()
+        .map(|(i, &(symbol, name, atomic_mass, en, covalent_radius, melting_point))| Element {
+            z: (i + 1) as u8,
+            symbol,
+            name,
+            atomic_mass,
+            electronegativity: if en > 0.0 { Some(en) } else { None },
+            covalent_radius,
+            melting_point,
+        })
+        .collect()
+});
+
+static BY_SYMBOL: Lazy<HashMap<&'static str, usize>> = Lazy::new(|| {
+    ELEMENTS.iter().enumerate().map(|(i, e)| (e.symbol, i)).collect()
+});
+
+/// Look up an element by symbol (case-sensitive, e.g. "Fe")
+pub fn element(symbol: &str) -> Option<&'static Element> {
+    BY_SYMBOL.get(symbol).map(|&i| &ELEMENTS[i])
+}
+
+/// Look up an element by atomic number
+pub fn element_by_z(z: u8) -> Option<&'static Element> {
+    ELEMENTS.get((z as usize).checked_sub(1)?)
+}
+
+/// All elements in order of atomic number
+pub fn all_elements() -> &'static [Element] {
+    &ELEMENTS
+}
+
+/// Atomic mass for a symbol, if known
+pub fn atomic_mass(symbol: &str) -> Option<f64> {
+    element(symbol).map(|e| e.atomic_mass)
+}
+
+impl Element {
+    /// Period (row) in the periodic table
+    pub fn period(&self) -> u8 {
+        match self.z {
+            1..=2 => 1,
+            3..=10 => 2,
+            11..=18 => 3,
+            19..=36 => 4,
+            37..=54 => 5,
+            55..=86 => 6,
+            _ => 7,
+        }
+    }
+
+    /// IUPAC group (1-18); lanthanides and actinides are placed in group 3
+    pub fn group(&self) -> u8 {
+        let z = self.z;
+        match z {
+            1 => 1,
+            2 => 18,
+            3..=4 => z - 2,
+            5..=10 => z + 8,
+            11..=12 => z - 10,
+            13..=18 => z,
+            19..=36 => z - 18,
+            37..=54 => z - 36,
+            55..=56 => z - 54,
+            57..=71 => 3,
+            72..=86 => z - 68,
+            87..=88 => z - 86,
+            _ => 3,
+        }
+    }
+
+    /// Whether the element is a lanthanide (La-Lu)
+    pub fn is_lanthanide(&self) -> bool {
+        (57..=71).contains(&self.z)
+    }
+
+    /// Whether the element is an actinide (Ac-Lr)
+    pub fn is_actinide(&self) -> bool {
+        (89..=103).contains(&self.z)
+    }
+
+    /// Whether the element has no stable isotope (Tc, Pm, Po and heavier)
+    pub fn is_radioactive(&self) -> bool {
+        self.z == 43 || self.z == 61 || self.z >= 84
+    }
+
+    /// Ground-state electron configuration as (n, l, occupancy) subshells
+    pub fn electron_configuration(&self) -> Vec<(u8, u8, u8)> {
+        configuration(self.z)
+    }
+
+    /// Electrons beyond the preceding noble-gas core
+    pub fn valence_shells(&self) -> ValenceShells {
+        let core_z = NOBLE_GASES.iter().rev().find(|&&g| g < self.z).copied().unwrap_or(0);
+        let core = configuration(core_z);
+
+        let mut shells = ValenceShells::default();
+        for (n, l, count) in configuration(self.z) {
+            let in_core = core
+                .iter()
+                .find(|(cn, cl, _)| *cn == n && *cl == l)
+                .map_or(0, |(_, _, c)| *c);
+            let valence = count.saturating_sub(in_core);
+            match l {
+                0 => shells.s += valence,
+                1 => shells.p += valence,
+                2 => shells.d += valence,
+                _ => shells.f += valence,
+            }
+        }
+        shells
+    }
+
+    /// Empty states in partially filled valence subshells
+    pub fn unfilled_shells(&self) -> ValenceShells {
+        let v = self.valence_shells();
+        let unfilled = |count: u8, capacity: u8| if count > 0 { capacity - count.min(capacity) } else { 0 };
+        ValenceShells {
+            s: unfilled(v.s, 2),
+            p: unfilled(v.p, 6),
+            d: unfilled(v.d, 10),
+            f: unfilled(v.f, 14),
+        }
+    }
+}
+
+#[rustfmt::skip]
+const IONIC_RADII: &[(&str, i8, u8, f64)] = &[
+    // symbol, charge, coordination number, Shannon effective radius (Å);
+    // within an element the most common charge comes first
+    ("O", -2, 2, 1.35), ("O", -2, 3, 1.36), ("O", -2, 4, 1.38), ("O", -2, 6, 1.40), ("O", -2, 8, 1.42),
+    ("F", -1, 2, 1.285), ("F", -1, 3, 1.30), ("F", -1, 4, 1.31), ("F", -1, 6, 1.33),
+    ("Cl", -1, 6, 1.81), ("Br", -1, 6, 1.96), ("I", -1, 6, 2.20),
+    ("S", -2, 6, 1.84), ("Se", -2, 6, 1.98), ("Te", -2, 6, 2.21), ("N", -3, 4, 1.46),
+    ("Li", 1, 4, 0.59), ("Li", 1, 6, 0.76), ("Li", 1, 8, 0.92),
+    ("Na", 1, 4, 0.99), ("Na", 1, 6, 1.02), ("Na", 1, 8, 1.18), ("Na", 1, 12, 1.39),
+    ("K", 1, 6, 1.38), ("K", 1, 8, 1.51), ("K", 1, 12, 1.64),
+    ("Rb", 1, 6, 1.52), ("Rb", 1, 8, 1.61), ("Rb", 1, 12, 1.72),
+    ("Cs", 1, 6, 1.67), ("Cs", 1, 8, 1.74), ("Cs", 1, 12, 1.88),
+    ("Ag", 1, 4, 1.00), ("Ag", 1, 6, 1.15), ("Ag", 1, 8, 1.28),
+    ("Tl", 1, 6, 1.50), ("Tl", 1, 8, 1.59), ("Tl", 1, 12, 1.70),
+    ("Be", 2, 4, 0.27), ("Be", 2, 6, 0.45),
+    ("Mg", 2, 4, 0.57), ("Mg", 2, 6, 0.72), ("Mg", 2, 8, 0.89),
+    ("Ca", 2, 6, 1.00), ("Ca", 2, 8, 1.12), ("Ca", 2, 12, 1.34),
+    ("Sr", 2, 6, 1.18), ("Sr", 2, 8, 1.26), ("Sr", 2, 12, 1.44),
+    ("Ba", 2, 6, 1.35), ("Ba", 2, 8, 1.42), ("Ba", 2, 12, 1.61),
+    ("Zn", 2, 4, 0.60), ("Zn", 2, 6, 0.74), ("Zn", 2, 8, 0.90),
+    ("Cd", 2, 4, 0.78), ("Cd", 2, 6, 0.95), ("Cd", 2, 8, 1.10), ("Cd", 2, 12, 1.31),
+    ("Ni", 2, 4, 0.55), ("Ni", 2, 6, 0.69), ("Ni", 3, 6, 0.56),
+    ("Cu", 2, 4, 0.57), ("Cu", 2, 6, 0.73), ("Cu", 1, 4, 0.60), ("Cu", 1, 6, 0.77),
+    ("Pd", 2, 4, 0.64), ("Pd", 2, 6, 0.86),
+    ("Pb", 2, 6, 1.19), ("Pb", 2, 8, 1.29), ("Pb", 2, 12, 1.49), ("Pb", 4, 4, 0.65), ("Pb", 4, 6, 0.775), ("Pb", 4, 8, 0.94),
+    ("Sn", 4, 4, 0.55), ("Sn", 4, 6, 0.69), ("Sn", 4, 8, 0.81), ("Sn", 2, 8, 1.22),
+    ("Fe", 3, 4, 0.49), ("Fe", 3, 6, 0.645), ("Fe", 3, 8, 0.78), ("Fe", 2, 4, 0.63), ("Fe", 2, 6, 0.78), ("Fe", 2, 8, 0.92),
+    ("Mn", 2, 4, 0.66), ("Mn", 2, 6, 0.83), ("Mn", 2, 8, 0.96), ("Mn", 3, 6, 0.645), ("Mn", 4, 4, 0.39), ("Mn", 4, 6, 0.53),
+    ("Co", 2, 4, 0.58), ("Co", 2, 6, 0.745), ("Co", 2, 8, 0.90), ("Co", 3, 6, 0.61),
+    ("Cr", 3, 6, 0.615), ("Cr", 4, 4, 0.41), ("Cr", 4, 6, 0.55),
+    ("V", 5, 4, 0.355), ("V", 5, 6, 0.54), ("V", 4, 6, 0.58), ("V", 3, 6, 0.64),
+    ("Ti", 4, 4, 0.42), ("Ti", 4, 6, 0.605), ("Ti", 4, 8, 0.74), ("Ti", 3, 6, 0.67),
+    ("Al", 3, 4, 0.39), ("Al", 3, 6, 0.535),
+    ("Ga", 3, 4, 0.47), ("Ga", 3, 6, 0.62),
+    ("In", 3, 4, 0.62), ("In", 3, 6, 0.80), ("In", 3, 8, 0.92),
+    ("B", 3, 4, 0.11), ("B", 3, 6, 0.27),
+    ("Sc", 3, 6, 0.745), ("Sc", 3, 8, 0.87),
+    ("Y", 3, 6, 0.90), ("Y", 3, 8, 1.019), ("Y", 3, 9, 1.075),
+    ("La", 3, 6, 1.032), ("La", 3, 8, 1.16), ("La", 3, 12, 1.36),
+    ("Ce", 3, 6, 1.01), ("Ce", 3, 8, 1.143), ("Ce", 3, 12, 1.34), ("Ce", 4, 6, 0.87), ("Ce", 4, 8, 0.97), ("Ce", 4, 12, 1.14),
+    ("Pr", 3, 6, 0.99), ("Pr", 3, 8, 1.126),
+    ("Nd", 3, 6, 0.983), ("Nd", 3, 8, 1.109), ("Nd", 3, 12, 1.27),
+    ("Sm", 3, 6, 0.958), ("Sm", 3, 8, 1.079), ("Sm", 3, 12, 1.24),
+    ("Eu", 3, 6, 0.947), ("Eu", 3, 8, 1.066), ("Eu", 2, 6, 1.17), ("Eu", 2, 8, 1.25),
+    ("Gd", 3, 6, 0.938), ("Gd", 3, 8, 1.053),
+    ("Tb", 3, 6, 0.923), ("Tb", 3, 8, 1.04),
+    ("Dy", 3, 6, 0.912), ("Dy", 3, 8, 1.027),
+    ("Ho", 3, 6, 0.901), ("Ho", 3, 8, 1.015),
+    ("Er", 3, 6, 0.89), ("Er", 3, 8, 1.004),
+    ("Tm", 3, 6, 0.88), ("Tm", 3, 8, 0.994),
+    ("Yb", 3, 6, 0.868), ("Yb", 3, 8, 0.985),
+    ("Lu", 3, 6, 0.861), ("Lu", 3, 8, 0.977),
+    ("Bi", 3, 6, 1.03), ("Bi", 3, 8, 1.17), ("Bi", 5, 6, 0.76),
+    ("Sb", 5, 6, 0.60), ("Sb", 3, 4, 0.76),
+    ("Si", 4, 4, 0.26), ("Si", 4, 6, 0.40),
+    ("Ge", 4, 4, 0.39), ("Ge", 4, 6, 0.53),
+    ("Zr", 4, 4, 0.59), ("Zr", 4, 6, 0.72), ("Zr", 4, 8, 0.84),
+    ("Hf", 4, 4, 0.58), ("Hf", 4, 6, 0.71), ("Hf", 4, 8, 0.83),
+    ("Ru", 4, 6, 0.62), ("Ir", 4, 6, 0.625), ("Pt", 4, 6, 0.625), ("Pt", 2, 4, 0.60),
+    ("Mo", 6, 4, 0.41), ("Mo", 6, 6, 0.59), ("Mo", 4, 6, 0.65),
+    ("W", 6, 4, 0.42), ("W", 6, 6, 0.60),
+    ("Nb", 5, 4, 0.48), ("Nb", 5, 6, 0.64), ("Nb", 5, 8, 0.74),
+    ("Ta", 5, 6, 0.64), ("Ta", 5, 8, 0.74),
+    ("Te", 6, 6, 0.56),
+    ("Th", 4, 6, 0.94), ("Th", 4, 8, 1.05),
+    ("U", 4, 6, 0.89), ("U", 4, 8, 1.00), ("U", 6, 6, 0.73),
+];
+
+/// Shannon ionic radius (Å) for an ion at a coordination number
+///
+/// Falls back to the tabulated coordination closest to `coordination`
+/// (ties go to the higher one); None if the ion is not tabulated.
+pub fn ionic_radius(symbol: &str, charge: i8, coordination: u8) -> Option<f64> {
+    IONIC_RADII
+        .iter()
+        .filter(|(s, c, _, _)| *s == symbol && *c == charge)
+        .min_by_key(|(_, _, cn, _)| ((*cn as i16 - coordination as i16).abs(), std::cmp::Reverse(*cn)))
+        .map(|(_, _, _, r)| *r)
+}
+
+/// Charges with tabulated ionic radii, most common first
+pub fn oxidation_states(symbol: &str) -> Vec<i8> {
+    let mut states: Vec<i8> = Vec::new();
+    for (s, charge, _, _) in IONIC_RADII {
+        if *s == symbol && !states.contains(charge) {
+            states.push(*charge);
+        }
+    }
+    states
+}
+
+/// Elements with tabulated ionic radii, in table order
+pub fn ionic_elements() -> Vec<&'static str> {
+    let mut symbols: Vec<&'static str> = Vec::new();
+    for (s, _, _, _) in IONIC_RADII {
+        if !symbols.contains(s) {
+            symbols.push(s);
+        }
+    }
+    symbols
+}
+
+#[rustfmt::skip]
+const COMMON_OXIDATION_STATES: &[(&str, &[i8])] = &[
+    // symbol, common oxidation states, most common first; noble gases have none
+    ("H", &[1, -1]), ("Li", &[1]), ("Be", &[2]), ("B", &[3]), ("C", &[4, -4, 2]),
+    ("N", &[-3, 3, 5]), ("O", &[-2]), ("F", &[-1]),
+    ("Na", &[1]), ("Mg", &[2]), ("Al", &[3]), ("Si", &[4, -4]), ("P", &[5, 3, -3]),
+    ("S", &[-2, 6, 4]), ("Cl", &[-1, 1, 3, 5, 7]),
+    ("K", &[1]), ("Ca", &[2]), ("Sc", &[3]), ("Ti", &[4, 3]), ("V", &[5, 4, 3]),
+    ("Cr", &[3, 6]), ("Mn", &[2, 4, 3, 7]), ("Fe", &[3, 2]), ("Co", &[2, 3]), ("Ni", &[2]),
+    ("Cu", &[2, 1]), ("Zn", &[2]), ("Ga", &[3]), ("Ge", &[4, -4]), ("As", &[5, 3, -3]),
+    ("Se", &[-2, 4, 6]), ("Br", &[-1, 1, 3, 5]),
+    ("Rb", &[1]), ("Sr", &[2]), ("Y", &[3]), ("Zr", &[4]), ("Nb", &[5]), ("Mo", &[6, 4]),
+    ("Tc", &[7, 4]), ("Ru", &[4, 3]), ("Rh", &[3]), ("Pd", &[2, 4]), ("Ag", &[1]), ("Cd", &[2]),
+    ("In", &[3]), ("Sn", &[4, 2]), ("Sb", &[3, 5, -3]), ("Te", &[-2, 4, 6]), ("I", &[-1, 1, 5, 7]),
+    ("Cs", &[1]), ("Ba", &[2]), ("La", &[3]), ("Ce", &[3, 4]), ("Pr", &[3]), ("Nd", &[3]),
+    ("Pm", &[3]), ("Sm", &[3]), ("Eu", &[3, 2]), ("Gd", &[3]), ("Tb", &[3]), ("Dy", &[3]),
+    ("Ho", &[3]), ("Er", &[3]), ("Tm", &[3]), ("Yb", &[3]), ("Lu", &[3]), ("Hf", &[4]),
+    ("Ta", &[5]), ("W", &[6, 4]), ("Re", &[4, 7]), ("Os", &[4]), ("Ir", &[4, 3]), ("Pt", &[2, 4]),
+    ("Au", &[3, 1]), ("Hg", &[2, 1]), ("Tl", &[1, 3]), ("Pb", &[2, 4]), ("Bi", &[3, 5]),
+    ("Po", &[2, 4]), ("At", &[-1, 1]),
+    ("Fr", &[1]), ("Ra", &[2]), ("Ac", &[3]), ("Th", &[4]), ("Pa", &[5]), ("U", &[6, 4]),
+    ("Np", &[5]), ("Pu", &[4]), ("Am", &[3]), ("Cm", &[3]), ("Bk", &[3]), ("Cf", &[3]),
+    ("Es", &[3]), ("Fm", &[3]), ("Md", &[3]), ("No", &[2]), ("Lr", &[3]),
+];
+
+/// Common oxidation states, most common first (empty for noble gases)
+pub fn common_oxidation_states(symbol: &str) -> &'static [i8] {
+    COMMON_OXIDATION_STATES
+        .iter()
+        .find(|(s, _)| *s == symbol)
+        .map_or(&[], |(_, states)| states)
+}
+
+fn configuration(z: u8) -> Vec<(u8, u8, u8)> {
+    let mut remaining = z;
+    let mut config: Vec<(u8, u8, i16)> = Vec::new();
+
+    for &(n, l) in &MADELUNG_ORDER {
+        if remaining == 0 {
+            break;
+        }
+        let capacity = 4 * l + 2;
+        let filled = remaining.min(capacity);
+        config.push((n, l, filled as i16));
+        remaining -= filled;
+    }
+
+    if let Some((_, deltas)) = CONFIG_EXCEPTIONS.iter().find(|(ez, _)| *ez == z) {
+        for &(n, l, delta) in deltas.iter() {
+            match config.iter_mut().find(|(cn, cl, _)| *cn == n && *cl == l) {
+                Some(entry) => entry.2 += delta as i16,
+                None => config.push((n, l, delta as i16)),
+            }
+        }
+    }
+
+    config
+        .into_iter()
+        .filter(|(_, _, c)| *c > 0)
+        .map(|(n, l, c)| (n, l, c as u8))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_is_complete() {
+        assert_eq!(all_elements().len(), 103);
+        for (i, e) in all_elements().iter().enumerate() {
+            assert_eq!(e.z as usize, i + 1);
+            assert!(e.atomic_mass > 0.0);
+            let electrons: u8 = e.electron_configuration().iter().map(|(_, _, c)| c).sum();
+            assert_eq!(electrons, e.z, "{}", e.symbol);
+        }
+        assert_eq!(element("Pt").unwrap().z, 78);
+        assert_eq!(element_by_z(26).unwrap().symbol, "Fe");
+        assert!(element("Xx").is_none());
+    }
+
+    #[test]
+    fn test_period_and_group() {
+        let fe = element("Fe").unwrap();
+        assert_eq!((fe.period(), fe.group()), (4, 8));
+        let o = element("O").unwrap();
+        assert_eq!((o.period(), o.group()), (2, 16));
+        let nd = element("Nd").unwrap();
+        assert_eq!((nd.period(), nd.group()), (6, 3));
+        assert!(nd.is_lanthanide());
+        assert_eq!(element("Rn").unwrap().group(), 18);
+    }
+
+    #[test]
+    fn test_valence_shells() {
+        let fe = element("Fe").unwrap();
+        assert_eq!(fe.valence_shells(), ValenceShells { s: 2, p: 0, d: 6, f: 0 });
+        assert_eq!(fe.unfilled_shells().d, 4);
+
+        let cu = element("Cu").unwrap();
+        assert_eq!(cu.valence_shells(), ValenceShells { s: 1, p: 0, d: 10, f: 0 });
+
+        let pb = element("Pb").unwrap();
+        assert_eq!(pb.valence_shells(), ValenceShells { s: 2, p: 2, d: 10, f: 14 });
+
+        let ne = element("Ne").unwrap();
+        assert_eq!(ne.valence_shells().total(), 8);
+    }
+
+    #[test]
+    fn test_ionic_radii() {
+        assert_eq!(ionic_radius("Ti", 4, 6), Some(0.605));
+        // CN 12 not tabulated for Mg2+: nearest is CN 8
+        assert_eq!(ionic_radius("Mg", 2, 12), Some(0.89));
+        assert_eq!(ionic_radius("Fe", 4, 6), None);
+        assert_eq!(oxidation_states("Fe"), vec![3, 2]);
+        assert_eq!(oxidation_states("O"), vec![-2]);
+        assert!(oxidation_states("He").is_empty());
+        assert!(ionic_elements().iter().all(|s| element(s).is_some()));
+    }
+
+    #[test]
+    fn test_common_oxidation_states() {
+        assert_eq!(common_oxidation_states("Fe"), &[3, 2]);
+        assert_eq!(common_oxidation_states("O"), &[-2]);
+        assert!(common_oxidation_states("Ar").is_empty());
+        for e in all_elements() {
+            let noble = e.group() == 18;
+            assert_eq!(common_oxidation_states(e.symbol).is_empty(), noble, "{}", e.symbol);
+        }
+    }
+}
@@ -0,0 +1,724 @@
+//! Compressed embedding storage
+//!
+//! Keeps embeddings in two tiers:
+//! - **Search codes** (`SearchCodec`): int8 scalar quantization or product
+//!   quantization (PQ). They are scored against an uncompressed query with
+//!   asymmetric distance computation (ADC), so queries never decode vectors.
+//! - **Re-rank vectors** (`RerankCodec`): blocks of full vectors compressed
+//!   with the `materials_chaos_compression` codecs (`delta_lossless`,
+//!   `attractor`), or kept raw. Only the blocks holding the best ADC hits are
+//!   decoded, to re-score them exactly.
+//!
+//! [`tradeoff_report`] measures memory against recall for a set of
+//! configurations.
+
+use crate::hnsw::Neighbor;
+use crate::{Error, Result};
+use materials_chaos_compression::methods::attractor_compression::attractor_compress_with_components;
+use materials_chaos_compression::{attractor_decompress, delta_lossless_compress, delta_lossless_decompress};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Instant;
+use uuid::Uuid;
+
+/// Centroids per PQ subspace, so each sub-code is one byte
+const PQ_CENTROIDS: usize = 256;
+
+/// How vectors are coded for the ADC scan
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SearchCodec {
+    /// One byte per dimension, affine per dimension
+    Int8,
+    /// One byte per subspace; `subspaces` must divide the dimension
+    ProductQuantization { subspaces: usize },
+}
+
+/// How full vectors are kept for re-ranking
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RerankCodec {
+    /// No re-rank tier; results come straight from the codes
+    None,
+    Raw,
+    /// Lossless delta + RLE + gzip blocks
+    DeltaLossless,
+    /// Lossy PCA trajectory blocks keeping `components` dimensions; with
+    /// few components re-ranking can score worse than the codes themselves
+    Attractor { components: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoreConfig {
+    pub search: SearchCodec,
+    pub rerank: RerankCodec,
+    /// ADC candidates per requested hit sent to re-ranking
+    pub rerank_factor: usize,
+    /// Vectors per compressed re-rank block
+    pub block_size: usize,
+    /// Vectors sampled to train the quantizer
+    pub max_training: usize,
+    /// Lloyd iterations for PQ codebooks
+    pub training_iterations: usize,
+    pub seed: u64,
+}
+
+impl Default for StoreConfig {
+    fn default() -> Self {
+        Self {
+            search: SearchCodec::Int8,
+            rerank: RerankCodec::DeltaLossless,
+            rerank_factor: 4,
+            block_size: 256,
+            max_training: 10_000,
+            training_iterations: 15,
+            seed: 42,
+        }
+    }
+}
+
+impl StoreConfig {
+    pub fn new(search: SearchCodec, rerank: RerankCodec) -> Self {
+        Self { search, rerank, ..Default::default() }
+    }
+
+    /// Short name such as "pq16+delta_lossless"
+    pub fn label(&self) -> String {
+        let search = match self.search {
+            SearchCodec::Int8 => "int8".to_string(),
+            SearchCodec::ProductQuantization { subspaces } => format!("pq{}", subspaces),
+        };
+        let rerank = match self.rerank {
+            RerankCodec::None => return search,
+            RerankCodec::Raw => "raw".to_string(),
+            RerankCodec::DeltaLossless => "delta_lossless".to_string(),
+            RerankCodec::Attractor { components } => format!("attractor{}", components),
+        };
+        format!("{}+{}", search, rerank)
+    }
+}
+
+/// Bytes held by a store, against `f64` vectors as `EmbeddingEngine` keeps them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryReport {
+    pub vectors: usize,
+    pub dimension: usize,
+    pub raw_bytes: usize,
+    pub code_bytes: usize,
+    pub codebook_bytes: usize,
+    pub rerank_bytes: usize,
+    pub total_bytes: usize,
+    pub compression_ratio: f64,
+}
+
+/// One configuration in a memory/recall comparison
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeoffEntry {
+    pub codec: String,
+    pub memory: MemoryReport,
+    /// Recall@k of the ADC scan alone
+    pub recall_codes: f64,
+    /// Recall@k after re-ranking
+    pub recall_reranked: f64,
+    pub mean_query_micros: f64,
+}
+
+// ============================================================================
+// QUANTIZERS
+// ============================================================================
+
+#[derive(Debug, Clone)]
+enum Quantizer {
+    Int8 { offset: Vec<f32>, step: Vec<f32> },
+    /// `centroids[s]` holds `PQ_CENTROIDS` (or fewer) centroids of width `sub_dim`
+    Pq { sub_dim: usize, centroids: Vec<Vec<Vec<f32>>> },
+}
+
+/// Per-query lookup data for ADC
+enum QueryTable {
+    /// score = base + Σ weight_j · code_j
+    Int8 { base: f32, weights: Vec<f32> },
+    /// score = Σ_s table[s][code_s]
+    Pq(Vec<Vec<f32>>),
+}
+
+impl Quantizer {
+    fn train(config: &StoreConfig, sample: &[Vec<f32>], dim: usize) -> Result<Self> {
+        match config.search {
+            SearchCodec::Int8 => {
+                let mut lo = vec![f32::INFINITY; dim];
+                let mut hi = vec![f32::NEG_INFINITY; dim];
+                for v in sample {
+                    for j in 0..dim {
+                        lo[j] = lo[j].min(v[j]);
+                        hi[j] = hi[j].max(v[j]);
+                    }
+                }
+                let step = lo.iter().zip(&hi).map(|(l, h)| ((h - l) / 255.0).max(f32::EPSILON)).collect();
+                Ok(Quantizer::Int8 { offset: lo, step })
+            }
+            SearchCodec::ProductQuantization { subspaces } => {
+                if subspaces == 0 || dim % subspaces != 0 {
+                    return Err(Error::invalid_input(format!(
+                        "{} PQ subspaces do not divide dimension {}", subspaces, dim
+                    )));
+                }
+                let sub_dim = dim / subspaces;
+                let mut rng = StdRng::seed_from_u64(config.seed);
+                let centroids = (0..subspaces)
+                    .map(|s| {
+                        let points: Vec<&[f32]> = sample.iter().map(|v| &v[s * sub_dim..(s + 1) * sub_dim]).collect();
+                        kmeans(&points, PQ_CENTROIDS, config.training_iterations, &mut rng)
+                    })
+                    .collect();
+                Ok(Quantizer::Pq { sub_dim, centroids })
+            }
+        }
+    }
+
+    fn encode(&self, v: &[f32], out: &mut Vec<u8>) {
+        match self {
+            Quantizer::Int8 { offset, step } => {
+                out.extend(v.iter().zip(offset.iter().zip(step))
+                    .map(|(x, (o, s))| ((x - o) / s).round().clamp(0.0, 255.0) as u8));
+            }
+            Quantizer::Pq { sub_dim, centroids } => {
+                for (s, codebook) in centroids.iter().enumerate() {
+                    let sub = &v[s * sub_dim..(s + 1) * sub_dim];
+                    out.push(nearest(codebook, sub) as u8);
+                }
+            }
+        }
+    }
+
+    fn decode(&self, code: &[u8]) -> Vec<f32> {
+        match self {
+            Quantizer::Int8 { offset, step } => {
+                code.iter().zip(offset.iter().zip(step)).map(|(&c, (o, s))| o + s * c as f32).collect()
+            }
+            Quantizer::Pq { centroids, .. } => {
+                code.iter().zip(centroids).flat_map(|(&c, codebook)| codebook[c as usize].iter().copied()).collect()
+            }
+        }
+    }
+
+    fn code_len(&self, dim: usize) -> usize {
+        match self {
+            Quantizer::Int8 { .. } => dim,
+            Quantizer::Pq { centroids, .. } => centroids.len(),
+        }
+    }
+
+    fn codebook_bytes(&self) -> usize {
+        match self {
+            Quantizer::Int8 { offset, step } => 4 * (offset.len() + step.len()),
+            Quantizer::Pq { centroids, .. } => centroids.iter().flatten().map(|c| 4 * c.len()).sum(),
+        }
+    }
+
+    fn table(&self, query: &[f32]) -> QueryTable {
+        match self {
+            Quantizer::Int8 { offset, step } => QueryTable::Int8 {
+                base: query.iter().zip(offset).map(|(q, o)| q * o).sum(),
+                weights: query.iter().zip(step).map(|(q, s)| q * s).collect(),
+            },
+            Quantizer::Pq { sub_dim, centroids } => QueryTable::Pq(
+                centroids.iter().enumerate()
+                    .map(|(s, codebook)| {
+                        let sub = &query[s * sub_dim..(s + 1) * sub_dim];
+                        codebook.iter().map(|c| dot(sub, c)).collect()
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl QueryTable {
+    /// Approximate inner product with the coded vector
+    fn score(&self, code: &[u8]) -> f32 {
+        match self {
+            QueryTable::Int8 { base, weights } => {
+                base + code.iter().zip(weights).map(|(&c, w)| c as f32 * w).sum::<f32>()
+            }
+            QueryTable::Pq(tables) => code.iter().zip(tables).map(|(&c, t)| t[c as usize]).sum(),
+        }
+    }
+}
+
+/// Lloyd's k-means with distinct random starting points
+fn kmeans(points: &[&[f32]], k: usize, iterations: usize, rng: &mut StdRng) -> Vec<Vec<f32>> {
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.shuffle(rng);
+    let mut centroids: Vec<Vec<f32>> = order.iter().take(k).map(|&i| points[i].to_vec()).collect();
+    let width = points.first().map_or(0, |p| p.len());
+
+    for _ in 0..iterations {
+        let mut sums = vec![vec![0.0f32; width]; centroids.len()];
+        let mut counts = vec![0usize; centroids.len()];
+        for p in points {
+            let c = nearest(&centroids, p);
+            counts[c] += 1;
+            for (s, x) in sums[c].iter_mut().zip(p.iter()) {
+                *s += x;
+            }
+        }
+        for ((centroid, sum), &count) in centroids.iter_mut().zip(sums).zip(&counts) {
+            // Empty clusters keep their centroid
+            if count > 0 {
+                *centroid = sum.into_iter().map(|s| s / count as f32).collect();
+            }
+        }
+    }
+    centroids
+}
+
+fn nearest(centroids: &[Vec<f32>], point: &[f32]) -> usize {
+    centroids.iter()
+        .map(|c| c.iter().zip(point).map(|(a, b)| (a - b) * (a - b)).sum::<f32>())
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(0, |(i, _)| i)
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn normalize(vector: &[f64]) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let scale = if norm > 1e-12 { 1.0 / norm } else { 0.0 };
+    vector.iter().map(|x| (x * scale) as f32).collect()
+}
+
+fn normalize_f32(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 1e-12 { vector.iter().map(|x| x / norm).collect() } else { vector.to_vec() }
+}
+
+// ============================================================================
+// STORE
+// ============================================================================
+
+/// Embeddings held as search codes plus compressed re-rank blocks
+///
+/// Vectors are normalized on insert; distances are cosine distances.
+#[derive(Debug, Clone)]
+pub struct CompressedEmbeddingStore {
+    config: StoreConfig,
+    dim: usize,
+    quantizer: Quantizer,
+    /// Id per position; removed positions are dead until reused
+    ids: Vec<Uuid>,
+    live: Vec<bool>,
+    positions: HashMap<Uuid, usize>,
+    /// Dead positions, reused by the next inserts
+    free: Vec<usize>,
+    codes: Vec<u8>,
+    /// Full re-rank blocks, `block_size` vectors each
+    blocks: Vec<Vec<u8>>,
+    /// Vectors of the block being filled
+    open_block: Vec<Vec<f32>>,
+}
+
+impl CompressedEmbeddingStore {
+    /// Train the quantizer on `vectors` and store them
+    pub fn build(config: StoreConfig, vectors: &[(Uuid, Vec<f64>)]) -> Result<Self> {
+        let dim = vectors.first()
+            .map(|(_, v)| v.len())
+            .ok_or_else(|| Error::invalid_input("Cannot train a store without vectors"))?;
+        if vectors.iter().any(|(_, v)| v.len() != dim || v.iter().any(|x| !x.is_finite())) {
+            return Err(Error::invalid_input("Vectors must share one dimension and be finite"));
+        }
+        if config.block_size == 0 {
+            return Err(Error::invalid_input("Block size must be positive"));
+        }
+
+        let mut sample: Vec<Vec<f32>> = vectors.iter().map(|(_, v)| normalize(v)).collect();
+        if sample.len() > config.max_training {
+            sample.shuffle(&mut StdRng::seed_from_u64(config.seed));
+            sample.truncate(config.max_training);
+        }
+        let quantizer = Quantizer::train(&config, &sample, dim)?;
+
+        let mut store = Self {
+            config,
+            dim,
+            quantizer,
+            ids: Vec::new(),
+            live: Vec::new(),
+            positions: HashMap::new(),
+            free: Vec::new(),
+            codes: Vec::new(),
+            blocks: Vec::new(),
+            open_block: Vec::new(),
+        };
+        for (id, vector) in vectors {
+            store.insert(*id, vector)?;
+        }
+        Ok(store)
+    }
+
+    pub fn config(&self) -> &StoreConfig {
+        &self.config
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn contains(&self, id: Uuid) -> bool {
+        self.positions.contains_key(&id)
+    }
+
+    /// Add a vector with the trained quantizer, replacing any earlier one
+    ///
+    /// A replaced vector is overwritten in place, so a failed write keeps it.
+    /// Otherwise reuses the position of a removed vector when there is one;
+    /// a reused position inside a compressed block recompresses that block.
+    pub fn insert(&mut self, id: Uuid, vector: &[f64]) -> Result<()> {
+        if vector.len() != self.dim {
+            return Err(Error::invalid_input(format!(
+                "Vector has dimension {}, store holds {}", vector.len(), self.dim
+            )));
+        }
+        if vector.iter().any(|x| !x.is_finite()) {
+            return Err(Error::invalid_input("Vector must be finite"));
+        }
+        let vector = normalize(vector);
+        if let Some(&position) = self.positions.get(&id) {
+            return self.overwrite(position, id, vector);
+        }
+        match self.free.pop() {
+            Some(position) => {
+                if let Err(e) = self.overwrite(position, id, vector) {
+                    self.free.push(position);
+                    return Err(e);
+                }
+            }
+            None => self.append(id, vector),
+        }
+        Ok(())
+    }
+
+    fn append(&mut self, id: Uuid, vector: Vec<f32>) {
+        self.quantizer.encode(&vector, &mut self.codes);
+        let position = self.ids.len();
+        self.ids.push(id);
+        self.live.push(true);
+        self.positions.insert(id, position);
+
+        if self.config.rerank != RerankCodec::None {
+            self.open_block.push(vector);
+            if self.open_block.len() == self.config.block_size {
+                let block = std::mem::take(&mut self.open_block);
+                self.blocks.push(self.compress_block(&block));
+            }
+        }
+    }
+
+    fn overwrite(&mut self, position: usize, id: Uuid, vector: Vec<f32>) -> Result<()> {
+        let len = self.quantizer.code_len(self.dim);
+        let mut code = Vec::with_capacity(len);
+        self.quantizer.encode(&vector, &mut code);
+
+        if self.config.rerank != RerankCodec::None {
+            let (block, offset) = (position / self.config.block_size, position % self.config.block_size);
+            if block == self.blocks.len() {
+                self.open_block[offset] = vector;
+            } else {
+                let mut vectors = self.block_vectors(block)?;
+                vectors[offset] = vector;
+                self.blocks[block] = self.compress_block(&vectors);
+            }
+        }
+        self.codes[position * len..(position + 1) * len].copy_from_slice(&code);
+        self.ids[position] = id;
+        self.live[position] = true;
+        self.positions.insert(id, position);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, id: Uuid) -> bool {
+        match self.positions.remove(&id) {
+            Some(position) => {
+                self.live[position] = false;
+                self.free.push(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stored vector: from the re-rank tier if there is one, else decoded from its code
+    pub fn vector(&self, id: Uuid) -> Result<Option<Vec<f64>>> {
+        let Some(&position) = self.positions.get(&id) else { return Ok(None) };
+        let vector = if self.config.rerank == RerankCodec::None {
+            self.quantizer.decode(self.code(position))
+        } else {
+            self.block_vectors(position / self.config.block_size)?.swap_remove(position % self.config.block_size)
+        };
+        Ok(Some(vector.into_iter().map(|x| x as f64).collect()))
+    }
+
+    /// Top `k` by ADC over the codes only
+    pub fn search_codes(&self, query: &[f64], k: usize) -> Vec<Neighbor> {
+        if query.len() != self.dim {
+            return Vec::new();
+        }
+        let table = self.quantizer.table(&normalize(query));
+        let mut scored: Vec<(f32, usize)> = (0..self.ids.len())
+            .filter(|&p| self.live[p])
+            .map(|p| (1.0 - table.score(self.code(p)), p))
+            .collect();
+        let k = k.min(scored.len());
+        if k == 0 {
+            return Vec::new();
+        }
+        scored.select_nth_unstable_by(k - 1, |a, b| a.0.total_cmp(&b.0));
+        scored.truncate(k);
+        scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+        scored.into_iter().map(|(d, p)| Neighbor { id: self.ids[p], distance: d as f64 }).collect()
+    }
+
+    /// Top `k`: `k * rerank_factor` ADC candidates, re-scored from the re-rank tier
+    pub fn search(&self, query: &[f64], k: usize) -> Result<Vec<Neighbor>> {
+        if self.config.rerank == RerankCodec::None {
+            return Ok(self.search_codes(query, k));
+        }
+        let candidates = self.search_codes(query, k * self.config.rerank_factor.max(1));
+        let query = normalize(query);
+
+        // Decode each block once
+        let mut by_block: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for candidate in &candidates {
+            let position = self.positions[&candidate.id];
+            by_block.entry(position / self.config.block_size).or_default().push(position);
+        }
+        let mut reranked = Vec::with_capacity(candidates.len());
+        for (block, positions) in by_block {
+            let vectors = self.block_vectors(block)?;
+            for position in positions {
+                let vector = normalize_f32(&vectors[position % self.config.block_size]);
+                reranked.push(Neighbor { id: self.ids[position], distance: 1.0 - dot(&query, &vector) as f64 });
+            }
+        }
+        reranked.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        reranked.truncate(k);
+        Ok(reranked)
+    }
+
+    pub fn memory(&self) -> MemoryReport {
+        let live = self.len();
+        let raw_bytes = live * self.dim * std::mem::size_of::<f64>();
+        let code_bytes = self.codes.len();
+        let codebook_bytes = self.quantizer.codebook_bytes();
+        let rerank_bytes = self.blocks.iter().map(Vec::len).sum::<usize>() + self.open_block.len() * self.dim * 4;
+        let total_bytes = code_bytes + codebook_bytes + rerank_bytes + self.ids.len() * 16;
+        MemoryReport {
+            vectors: live,
+            dimension: self.dim,
+            raw_bytes,
+            code_bytes,
+            codebook_bytes,
+            rerank_bytes,
+            total_bytes,
+            compression_ratio: if total_bytes > 0 { raw_bytes as f64 / total_bytes as f64 } else { 0.0 },
+        }
+    }
+
+    fn code(&self, position: usize) -> &[u8] {
+        let len = self.quantizer.code_len(self.dim);
+        &self.codes[position * len..(position + 1) * len]
+    }
+
+    fn compress_block(&self, block: &[Vec<f32>]) -> Vec<u8> {
+        match self.config.rerank {
+            RerankCodec::None => Vec::new(),
+            RerankCodec::Raw => block.iter().flatten().flat_map(|x| x.to_le_bytes()).collect(),
+            RerankCodec::DeltaLossless => delta_lossless_compress(block),
+            RerankCodec::Attractor { components } => attractor_compress_with_components(block, components),
+        }
+    }
+
+    fn block_vectors(&self, block: usize) -> Result<Vec<Vec<f32>>> {
+        if block == self.blocks.len() {
+            return Ok(self.open_block.clone());
+        }
+        let bytes = &self.blocks[block];
+        let vectors = match self.config.rerank {
+            RerankCodec::None => Vec::new(),
+            RerankCodec::Raw => bytes.chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect::<Vec<f32>>()
+                .chunks(self.dim)
+                .map(<[f32]>::to_vec)
+                .collect(),
+            RerankCodec::DeltaLossless => delta_lossless_decompress(bytes),
+            RerankCodec::Attractor { .. } => attractor_decompress(bytes),
+        };
+        if vectors.len() != self.config.block_size || vectors.iter().any(|v| v.len() != self.dim) {
+            return Err(Error::computation(format!("Re-rank block {} decoded to the wrong shape", block)));
+        }
+        Ok(vectors)
+    }
+}
+
+/// Memory and recall@k of each configuration against the exact scan
+pub fn tradeoff_report(
+    vectors: &[(Uuid, Vec<f64>)],
+    queries: &[Vec<f64>],
+    k: usize,
+    configs: &[StoreConfig],
+) -> Result<Vec<TradeoffEntry>> {
+    let exact: Vec<HashSet<Uuid>> = queries.iter()
+        .map(|q| {
+            let q = normalize(q);
+            let mut scored: Vec<(f32, Uuid)> = vectors.iter()
+                .map(|(id, v)| (1.0 - dot(&q, &normalize(v)), *id))
+                .collect();
+            scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+            scored.into_iter().take(k).map(|(_, id)| id).collect()
+        })
+        .collect();
+    let recall = |hits: &[Vec<Neighbor>]| {
+        let expected: usize = exact.iter().map(HashSet::len).sum();
+        let found: usize = hits.iter().zip(&exact)
+            .map(|(h, e)| h.iter().filter(|n| e.contains(&n.id)).count())
+            .sum();
+        if expected > 0 { found as f64 / expected as f64 } else { 1.0 }
+    };
+
+    configs.iter()
+        .map(|config| {
+            let store = CompressedEmbeddingStore::build(config.clone(), vectors)?;
+            let codes: Vec<Vec<Neighbor>> = queries.iter().map(|q| store.search_codes(q, k)).collect();
+            let start = Instant::now();
+            let reranked = queries.iter().map(|q| store.search(q, k)).collect::<Result<Vec<_>>>()?;
+            let elapsed = start.elapsed().as_secs_f64();
+            Ok(TradeoffEntry {
+                codec: config.label(),
+                memory: store.memory(),
+                recall_codes: recall(&codes),
+                recall_reranked: recall(&reranked),
+                mean_query_micros: elapsed * 1e6 / queries.len().max(1) as f64,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    /// Points scattered around a few directions, like embeddings of related materials
+    fn clustered(n: usize, dim: usize, seed: u64) -> Vec<(Uuid, Vec<f64>)> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let centers: Vec<Vec<f64>> = (0..8).map(|_| (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect()).collect();
+        (0..n)
+            .map(|i| {
+                let center = &centers[i % centers.len()];
+                (Uuid::new_v4(), center.iter().map(|c| c + rng.gen_range(-0.4..0.4)).collect())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_adc_search_and_rerank() {
+        let vectors = clustered(600, 32, 1);
+        let queries: Vec<Vec<f64>> = clustered(20, 32, 2).into_iter().map(|(_, v)| v).collect();
+        let configs = [
+            StoreConfig::new(SearchCodec::Int8, RerankCodec::None),
+            StoreConfig::new(SearchCodec::Int8, RerankCodec::DeltaLossless),
+            StoreConfig { block_size: 64, ..StoreConfig::new(SearchCodec::ProductQuantization { subspaces: 8 }, RerankCodec::Raw) },
+            StoreConfig::new(SearchCodec::ProductQuantization { subspaces: 8 }, RerankCodec::Attractor { components: 16 }),
+        ];
+        let report = tradeoff_report(&vectors, &queries, 10, &configs).unwrap();
+        let labels: Vec<&str> = report.iter().map(|r| r.codec.as_str()).collect();
+        assert_eq!(labels, vec!["int8", "int8+delta_lossless", "pq8+raw", "pq8+attractor16"]);
+
+        let [int8, int8_delta, pq_raw, pq_attractor] = &report[..] else { unreachable!() };
+        assert!(int8.recall_codes > 0.85, "int8 recall {}", int8.recall_codes);
+        assert!(int8_delta.recall_reranked > 0.98, "{}", int8_delta.recall_reranked);
+        assert!(pq_raw.recall_reranked > pq_raw.recall_codes);
+        assert!(pq_raw.recall_reranked > 0.9, "{}", pq_raw.recall_reranked);
+        // Lossy tier: far smaller than raw blocks, at the cost of re-rank recall
+        assert!(pq_attractor.memory.rerank_bytes * 2 < pq_raw.memory.rerank_bytes);
+        assert!((0.0..=1.0).contains(&pq_attractor.recall_reranked));
+
+        // Codes alone: PQ is smaller than int8, both far below f64
+        assert_eq!(int8.memory.code_bytes, 600 * 32);
+        assert_eq!(pq_raw.memory.code_bytes, 600 * 8);
+        assert!(int8.memory.compression_ratio > 5.0);
+        assert!(int8_delta.memory.rerank_bytes > 0 && int8_delta.memory.rerank_bytes < 600 * 32 * 4 * 2);
+    }
+
+    #[test]
+    fn test_store_updates() {
+        let vectors = clustered(300, 16, 3);
+        let mut store = CompressedEmbeddingStore::build(StoreConfig::default(), &vectors).unwrap();
+        assert_eq!(store.len(), 300);
+
+        // Lossless tier: stored vectors come back normalized and intact
+        let (id, original) = &vectors[7];
+        let restored = store.vector(*id).unwrap().unwrap();
+        let expected = normalize(original);
+        assert!(restored.iter().zip(&expected).all(|(a, b)| (a - *b as f64).abs() < 1e-5));
+        assert_eq!(store.search(original, 1).unwrap()[0].id, *id);
+
+        assert!(store.remove(*id));
+        assert!(store.search(original, 5).unwrap().iter().all(|n| n.id != *id));
+        let replacement = Uuid::new_v4();
+        store.insert(replacement, original).unwrap();
+        assert_eq!(store.search(original, 1).unwrap()[0].id, replacement);
+        assert_eq!(store.len(), 300);
+
+        // Removed positions are reused, in the open block and in compressed ones
+        let codes = store.memory().code_bytes;
+        for (id, vector) in &vectors[8..28] {
+            assert!(store.remove(*id));
+            store.insert(Uuid::new_v4(), vector).unwrap();
+        }
+        let (reinserted, vector) = (Uuid::new_v4(), &vectors[290].1);
+        store.remove(vectors[290].0);
+        store.insert(reinserted, vector).unwrap();
+        assert_eq!(store.memory().code_bytes, codes);
+        assert_eq!(store.len(), 300);
+        assert_eq!(store.search(vector, 1).unwrap()[0].id, reinserted);
+        let restored = store.vector(reinserted).unwrap().unwrap();
+        assert!(restored.iter().zip(&normalize(vector)).all(|(a, b)| (a - *b as f64).abs() < 1e-5));
+
+        // Re-inserting an id replaces its vector in place
+        let (id, _) = vectors[100];
+        store.insert(id, &vectors[101].1).unwrap();
+        assert_eq!(store.len(), 300);
+        let restored = store.vector(id).unwrap().unwrap();
+        assert!(restored.iter().zip(&normalize(&vectors[101].1)).all(|(a, b)| (a - *b as f64).abs() < 1e-5));
+
+        // A block that fails to decode leaves the existing vector in place
+        let mut corrupt = store.clone();
+        let (id, vector) = &vectors[0];
+        corrupt.blocks[0] = corrupt.compress_block(&[vec![0.0; 16]]);
+        assert!(corrupt.insert(*id, &vectors[1].1).is_err());
+        assert!(corrupt.contains(*id));
+        assert_eq!(corrupt.len(), 300);
+        assert_eq!(corrupt.search_codes(vector, 1)[0].id, *id);
+
+        assert!(store.insert(Uuid::new_v4(), &[1.0]).is_err());
+        let mut bad = vectors[0].1.clone();
+        bad[3] = f64::NAN;
+        assert!(store.insert(Uuid::new_v4(), &bad).is_err());
+        bad[3] = f64::INFINITY;
+        assert!(store.insert(Uuid::new_v4(), &bad).is_err());
+        assert!(CompressedEmbeddingStore::build(
+            StoreConfig::new(SearchCodec::ProductQuantization { subspaces: 5 }, RerankCodec::None),
+            &vectors,
+        ).is_err());
+    }
+}
@@ -5,13 +5,27 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, RwLockReadGuard};
+use tracing::info;
 use uuid::Uuid;
+use crate::embedding_store::{tradeoff_report, CompressedEmbeddingStore, StoreConfig, TradeoffEntry};
+use crate::clustering::{self, ClusterMethod, Clustering, KMeansConfig};
+use crate::composition::Composition;
+use crate::featurizer::MagpieFeaturizer;
+use crate::hnsw::{HnswConfig, HnswIndex, IndexMetadata, MetadataFilter, RecallReport};
+use crate::learned_embeddings::{LearnedEmbeddingConfig, LearnedEmbeddingModel};
+use crate::material::{Material, Structure};
+use crate::projection::{self, MapPoint, MaterialsMap, ProjectionMethod};
+use crate::structure_descriptors::StructureFeaturizer;
 
 /// Dimensionality of embedding vectors
 pub const EMBEDDING_DIM: usize = 256;
 
+/// Width of the structure block in structure-aware embeddings
+pub(crate) const STRUCTURE_BLOCK_DIM: usize = 64;
+
 /// Chemical embedding vector
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChemicalEmbedding {
@@ -49,32 +63,210 @@ pub struct EmbeddingEngine {
     /// Embedding cache
     embeddings: Arc<RwLock<HashMap<Uuid, ChemicalEmbedding>>>,
 
+    /// Nearest-neighbor index over the cached embeddings
+    index: Arc<RwLock<HnswIndex>>,
+
     /// Element embeddings (learned representations)
     element_vectors: HashMap<String, Vec<f64>>,
 
     /// Property weights for embedding generation
     property_weights: HashMap<String, f64>,
 
+    /// Optional structure descriptor for structure-aware embeddings
+    structure_featurizer: Option<Arc<dyn StructureFeaturizer>>,
+
+    /// Trained model replacing the hand-built vectors, when set
+    model: Arc<RwLock<Option<Arc<LearnedEmbeddingModel>>>>,
+
+    /// Inputs of every embedding, kept for re-embedding and training
+    inputs: Arc<RwLock<HashMap<Uuid, EmbeddingInput>>>,
+
     /// Model version
     version: String,
 }
 
+/// What an embedding was generated from, reduced to what re-embedding
+/// and training read: the structure's descriptor block and the weighted
+/// property values, never the structure or property map themselves
+#[derive(Debug, Clone)]
+struct EmbeddingInput {
+    formula: String,
+    /// `structure_embedding` of a structure with sites, made when a
+    /// featurizer is configured
+    structure_block: Option<Vec<f64>>,
+    /// Leading entries of `property_embedding`; the rest are zero
+    property_features: Vec<f64>,
+}
+
+/// Outcome of `EmbeddingEngine::reembed_stale`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReembedReport {
+    pub model_version: String,
+    pub stale: usize,
+    pub reembedded: usize,
+    /// Stale embeddings whose inputs are unknown, e.g. from a loaded index
+    pub skipped: usize,
+    pub failed: Vec<(Uuid, String)>,
+}
+
 impl EmbeddingEngine {
     pub fn new() -> Self {
         Self {
             embeddings: Arc::new(RwLock::new(HashMap::new())),
+            index: Arc::new(RwLock::new(HnswIndex::default())),
             element_vectors: Self::initialize_element_vectors(),
             property_weights: Self::initialize_property_weights(),
+            structure_featurizer: None,
+            model: Arc::new(RwLock::new(None)),
+            inputs: Arc::new(RwLock::new(HashMap::new())),
             version: "v1.0.0".to_string(),
         }
     }
 
+    /// Use a structure descriptor in `generate_material_embedding`
+    pub fn with_structure_featurizer(mut self, featurizer: Arc<dyn StructureFeaturizer>) -> Self {
+        self.structure_featurizer = Some(featurizer);
+        self
+    }
+
+    /// Build the similarity index with these HNSW parameters
+    pub fn with_index_config(mut self, config: HnswConfig) -> Self {
+        self.index = Arc::new(RwLock::new(HnswIndex::new(config)));
+        self
+    }
+
+    /// Generate embedding from a full material
+    ///
+    /// With a structure featurizer configured and sites present, the vector
+    /// is composition (128) + structure descriptors (64) + properties (64),
+    /// so polymorphs of one formula embed apart. Otherwise this is the same
+    /// as `generate_embedding` on the formula.
+    ///
+    /// With a learned model set, the model embeds composition and structure.
+    pub async fn generate_material_embedding(
+        &self,
+        material: &Material,
+        properties: &HashMap<String, f64>,
+    ) -> Result<ChemicalEmbedding, String> {
+        let structure_block = match &self.structure_featurizer {
+            Some(featurizer) if !material.structure.sites.is_empty() => {
+                Some(Self::structure_embedding(featurizer.as_ref(), &material.structure)?)
+            }
+            _ => None,
+        };
+        let input = EmbeddingInput {
+            formula: material.formula.clone(),
+            structure_block,
+            property_features: self.property_features(properties),
+        };
+        self.record_and_embed(material.id, input).await
+    }
+
     /// Generate embedding from material
+    ///
+    /// With a learned model set, the model embeds the composition and
+    /// `properties` are not used.
     pub async fn generate_embedding(
         &self,
         material_id: Uuid,
         formula: &str,
         properties: &HashMap<String, f64>,
+    ) -> Result<ChemicalEmbedding, String> {
+        let input = EmbeddingInput {
+            formula: formula.to_string(),
+            structure_block: None,
+            property_features: self.property_features(properties),
+        };
+        self.record_and_embed(material_id, input).await
+    }
+
+    async fn record_and_embed(&self, material_id: Uuid, input: EmbeddingInput) -> Result<ChemicalEmbedding, String> {
+        let embedding = self.embed_input(material_id, &input).await;
+        self.inputs.write().await.insert(material_id, input);
+        embedding
+    }
+
+    async fn embed_input(&self, material_id: Uuid, input: &EmbeddingInput) -> Result<ChemicalEmbedding, String> {
+        if let Some(model) = self.model().await {
+            return self.learned_embedding(&model, material_id, input).await;
+        }
+        match (&self.structure_featurizer, &input.structure_block) {
+            (Some(featurizer), Some(block)) => {
+                self.structured_embedding(featurizer.name(), material_id, &input.formula, block, &input.property_features).await
+            }
+            _ => self.composition_property_embedding(material_id, &input.formula, &input.property_features).await,
+        }
+    }
+
+    async fn learned_embedding(
+        &self,
+        model: &LearnedEmbeddingModel,
+        material_id: Uuid,
+        input: &EmbeddingInput,
+    ) -> Result<ChemicalEmbedding, String> {
+        let composition = Self::parse_formula(&input.formula)?;
+        let mut vector = model
+            .embed_block(&input.formula, input.structure_block.as_deref())
+            .map_err(|e| e.to_string())?;
+        vector.resize(EMBEDDING_DIM, 0.0);
+
+        let embedding = ChemicalEmbedding {
+            material_id,
+            vector,
+            formula_hash: Self::hash_formula(&input.formula),
+            metadata: EmbeddingMetadata {
+                created_at: chrono::Utc::now(),
+                model_version: model.version.clone(),
+                confidence: 0.95,
+            },
+        };
+
+        self.embeddings.write().await.insert(material_id, embedding.clone());
+        self.index_embedding(&embedding, &input.formula, &composition).await?;
+
+        Ok(embedding)
+    }
+
+    /// Composition (128) + structure descriptors (64) + properties (64)
+    async fn structured_embedding(
+        &self,
+        featurizer: &str,
+        material_id: Uuid,
+        formula: &str,
+        structure_block: &[f64],
+        property_features: &[f64],
+    ) -> Result<ChemicalEmbedding, String> {
+        let composition = Self::parse_formula(formula)?;
+        let mut vector = self.composition_embedding(&composition);
+        vector.extend_from_slice(structure_block);
+
+        let mut property_vector = Self::property_embedding(property_features);
+        property_vector.truncate(EMBEDDING_DIM - vector.len());
+        vector.extend(property_vector);
+
+        let embedding = ChemicalEmbedding {
+            material_id,
+            vector: Self::normalize_vector(&vector),
+            formula_hash: Self::hash_formula(formula),
+            metadata: EmbeddingMetadata {
+                created_at: chrono::Utc::now(),
+                model_version: format!("{}+{}", self.version, featurizer),
+                confidence: 0.95,
+            },
+        };
+
+        self.embeddings.write().await.insert(material_id, embedding.clone());
+        self.index_embedding(&embedding, formula, &composition).await?;
+
+        Ok(embedding)
+    }
+
+    /// Hand-built composition and property embedding
+    async fn composition_property_embedding(
+        &self,
+        material_id: Uuid,
+        formula: &str,
+        property_features: &[f64],
     ) -> Result<ChemicalEmbedding, String> {
         // Parse formula to get element composition
         let composition = Self::parse_formula(formula)?;
@@ -83,7 +275,7 @@ impl EmbeddingEngine {
         let mut composition_vector = self.composition_embedding(&composition);
 
         // Add property-based features
-        let property_vector = self.property_embedding(properties);
+        let property_vector = Self::property_embedding(property_features);
 
         // Combine vectors
         composition_vector.extend(property_vector);
@@ -105,27 +297,51 @@ impl EmbeddingEngine {
             },
         };
 
-        // Cache the embedding
+        // Cache and index the embedding
         self.embeddings.write().await.insert(material_id, embedding.clone());
+        self.index_embedding(&embedding, formula, &composition).await?;
 
         Ok(embedding)
     }
 
-    /// Find similar materials using cosine similarity
+    /// Find similar materials using the HNSW index
     pub async fn find_similar(
         &self,
         material_id: Uuid,
         top_k: usize,
+    ) -> Result<Vec<SimilarityResult>, String> {
+        self.search_index(material_id, top_k, None).await
+    }
+
+    /// Find similar materials whose index metadata matches a filter
+    pub async fn find_similar_filtered(
+        &self,
+        material_id: Uuid,
+        top_k: usize,
+        filter: &MetadataFilter,
+    ) -> Result<Vec<SimilarityResult>, String> {
+        self.search_index(material_id, top_k, Some(filter)).await
+    }
+
+    /// Find similar materials by scanning every cached embedding
+    ///
+    /// Exact, but linear in the number of embeddings; `find_similar` uses
+    /// the HNSW index instead.
+    pub async fn find_similar_exact(
+        &self,
+        material_id: Uuid,
+        top_k: usize,
     ) -> Result<Vec<SimilarityResult>, String> {
         let embeddings = self.embeddings.read().await;
 
         let target = embeddings.get(&material_id)
+            .filter(|e| !e.vector.is_empty())
             .ok_or_else(|| "Material not found in embeddings".to_string())?;
 
         let mut similarities: Vec<SimilarityResult> = Vec::new();
 
         for (id, embedding) in embeddings.iter() {
-            if *id == material_id {
+            if *id == material_id || embedding.vector.is_empty() {
                 continue;
             }
 
@@ -136,7 +352,7 @@ impl EmbeddingEngine {
                 material_id: *id,
                 similarity_score: similarity,
                 distance,
-                formula: format!("Material-{}", id.to_string()[..8].to_string()),
+                formula: self.formula_of(*id).await,
             });
         }
 
@@ -149,27 +365,260 @@ impl EmbeddingEngine {
         Ok(similarities.into_iter().take(top_k).collect())
     }
 
+    /// Drop a material from the cache and the index
+    ///
+    /// The index only marks the material deleted; the graph is compacted
+    /// by the next search once enough deletes have accumulated.
+    pub async fn remove_embedding(&self, material_id: Uuid) -> bool {
+        let cached = self.embeddings.write().await.remove(&material_id).is_some();
+        self.inputs.write().await.remove(&material_id);
+        let indexed = self.index.write().await.remove(material_id);
+        cached || indexed
+    }
+
+    /// Record the source database of an indexed material, for filtering
+    pub async fn set_source(&self, material_id: Uuid, source: &str) -> bool {
+        let mut index = self.index.write().await;
+        let Some(mut metadata) = index.metadata(material_id).cloned() else { return false };
+        metadata.source = Some(source.to_string());
+        index.set_metadata(material_id, metadata)
+    }
+
+    /// Persist the similarity index
+    pub async fn save_index(&self, path: &Path) -> Result<(), String> {
+        self.index.read().await.save(path).map_err(|e| e.to_string())
+    }
+
+    /// Replace the similarity index with one saved by `save_index`
+    ///
+    /// Searches work for every indexed material, including ones not in
+    /// the embedding cache.
+    pub async fn load_index(&self, path: &Path) -> Result<usize, String> {
+        let index = HnswIndex::load(path).map_err(|e| e.to_string())?;
+        let count = index.len();
+        *self.index.write().await = index;
+        Ok(count)
+    }
+
+    /// Recall@k of the index against the exact scan, querying with up to
+    /// `sample` cached embeddings
+    pub async fn benchmark_index_recall(&self, sample: usize, k: usize, ef: usize) -> RecallReport {
+        let queries: Vec<Vec<f64>> = self.embeddings.read().await
+            .values()
+            .filter(|e| !e.vector.is_empty())
+            .take(sample)
+            .map(|e| e.vector.clone())
+            .collect();
+        self.index.read().await.recall(&queries, k, ef)
+    }
+
+    /// Move the cached embedding vectors into a compressed store for
+    /// memory-bound search
+    ///
+    /// The cache keeps each embedding's metadata but releases its vector
+    /// once the store is built, so exact scans, clustering and benchmarks
+    /// only see embeddings generated afterwards; `find_similar` keeps using
+    /// the HNSW index. On error the vectors stay cached.
+    pub async fn build_compressed_store(&self, config: StoreConfig) -> Result<CompressedEmbeddingStore, String> {
+        let mut embeddings = self.embeddings.write().await;
+        let vectors: Vec<(Uuid, Vec<f64>)> = embeddings
+            .values_mut()
+            .filter(|e| !e.vector.is_empty())
+            .map(|e| (e.material_id, std::mem::take(&mut e.vector)))
+            .collect();
+        match CompressedEmbeddingStore::build(config, &vectors) {
+            Ok(store) => Ok(store),
+            Err(e) => {
+                for (id, vector) in vectors {
+                    if let Some(embedding) = embeddings.get_mut(&id) {
+                        embedding.vector = vector;
+                    }
+                }
+                Err(e.to_string())
+            }
+        }
+    }
+
+    /// Memory/recall trade-off of `configs` on the cached embeddings,
+    /// querying with up to `sample` of them
+    pub async fn compression_tradeoff(
+        &self,
+        configs: &[StoreConfig],
+        sample: usize,
+        k: usize,
+    ) -> Result<Vec<TradeoffEntry>, String> {
+        let vectors: Vec<(Uuid, Vec<f64>)> = self.embeddings.read().await
+            .values()
+            .filter(|e| !e.vector.is_empty())
+            .map(|e| (e.material_id, e.vector.clone()))
+            .collect();
+        let queries: Vec<Vec<f64>> = vectors.iter().take(sample).map(|(_, v)| v.clone()).collect();
+        tradeoff_report(&vectors, &queries, k, configs).map_err(|e| e.to_string())
+    }
+
+    /// Embed with a learned model from now on
+    ///
+    /// Returns how many cached embeddings are now stale; `reembed_stale`
+    /// refreshes them. The model must have been trained with the same
+    /// structure featurizer as this engine uses and fit its dimensions;
+    /// otherwise the current model stays and an error is returned.
+    pub async fn set_model(&self, model: LearnedEmbeddingModel) -> Result<usize, String> {
+        model.validate(self.structure_featurizer.as_deref()).map_err(|e| e.to_string())?;
+        *self.model.write().await = Some(Arc::new(model));
+        Ok(self.stale_embeddings().await.len())
+    }
+
+    /// Go back to hand-built embeddings; returns the number now stale
+    pub async fn clear_model(&self) -> usize {
+        *self.model.write().await = None;
+        self.stale_embeddings().await.len()
+    }
+
+    pub async fn model(&self) -> Option<Arc<LearnedEmbeddingModel>> {
+        self.model.read().await.clone()
+    }
+
+    /// Version new embeddings are recorded with
+    pub async fn model_version(&self) -> String {
+        match self.model().await {
+            Some(model) => model.version.clone(),
+            None => self.version.clone(),
+        }
+    }
+
+    /// Train a learned model on every material embedded so far, with this
+    /// engine's structure featurizer
+    pub async fn train_model(&self, config: LearnedEmbeddingConfig) -> Result<LearnedEmbeddingModel, String> {
+        let inputs = self.inputs.read().await;
+        let mut corpus: Vec<(&str, Option<&[f64]>)> = inputs.values()
+            .map(|input| (input.formula.as_str(), input.structure_block.as_deref()))
+            .collect();
+        // Map order is random; training order must not be
+        corpus.sort_by(|a, b| a.0.cmp(b.0));
+        let featurizer = self.structure_featurizer.as_ref().map(|f| f.name());
+        LearnedEmbeddingModel::train_on_blocks(&corpus, featurizer, config).map_err(|e| e.to_string())
+    }
+
+    /// Cached embeddings not made by the current model
+    pub async fn stale_embeddings(&self) -> Vec<Uuid> {
+        let model = self.model().await;
+        let inputs = self.inputs.read().await;
+        let mut stale: Vec<Uuid> = self.embeddings.read().await
+            .values()
+            .filter(|e| e.metadata.model_version != self.expected_version(model.as_deref(), inputs.get(&e.material_id)))
+            .map(|e| e.material_id)
+            .collect();
+        stale.sort();
+        stale
+    }
+
+    /// Re-embed stale embeddings from their recorded inputs, `batch_size`
+    /// at a time, yielding to other tasks between batches
+    pub async fn reembed_stale(&self, batch_size: usize) -> ReembedReport {
+        let stale = self.stale_embeddings().await;
+        let mut report = ReembedReport {
+            model_version: self.model_version().await,
+            stale: stale.len(),
+            reembedded: 0,
+            skipped: 0,
+            failed: Vec::new(),
+        };
+
+        for batch in stale.chunks(batch_size.max(1)) {
+            for &material_id in batch {
+                let input = self.inputs.read().await.get(&material_id).cloned();
+                match input {
+                    Some(input) => match self.embed_input(material_id, &input).await {
+                        Ok(_) => report.reembedded += 1,
+                        Err(e) => report.failed.push((material_id, e)),
+                    },
+                    None => report.skipped += 1,
+                }
+            }
+            tokio::task::yield_now().await;
+        }
+        info!(
+            "Re-embedded {}/{} stale embeddings with {}",
+            report.reembedded, report.stale, report.model_version
+        );
+        report
+    }
+
     /// Cluster materials using k-means
     pub async fn cluster_materials(&self, k: usize) -> Result<Vec<Vec<Uuid>>, String> {
-        let embeddings = self.embeddings.read().await;
+        let (ids, vectors) = self.cached_vectors().await;
 
-        if embeddings.len() < k {
-            return Err(format!("Not enough materials to cluster: {} < {}", embeddings.len(), k));
+        if ids.len() < k {
+            return Err(format!("Not enough materials to cluster: {} < {}", ids.len(), k));
         }
 
-        let ids: Vec<Uuid> = embeddings.keys().copied().collect();
-        let vectors: Vec<Vec<f64>> = embeddings.values().map(|e| e.vector.clone()).collect();
+        let result = clustering::kmeans(&vectors, &KMeansConfig::new(k)).map_err(|e| e.to_string())?;
+        Ok(result.clustering.clusters()
+            .into_iter()
+            .map(|members| members.into_iter().map(|i| ids[i]).collect())
+            .collect())
+    }
 
-        // Simple k-means clustering
-        let clusters = Self::kmeans_clustering(&vectors, k, 100);
+    /// Cluster materials with k-means, silhouette-chosen k-means, DBSCAN or HDBSCAN
+    pub async fn cluster_materials_with(&self, method: &ClusterMethod) -> Result<MaterialClusters, String> {
+        let (ids, vectors) = self.cached_vectors().await;
+        let (clustering, k_scores) = Self::run_clustering(&vectors, method)?;
+
+        Ok(MaterialClusters {
+            clusters: clustering.clusters()
+                .into_iter()
+                .map(|members| members.into_iter().map(|i| ids[i]).collect())
+                .collect(),
+            noise: clustering.noise().into_iter().map(|i| ids[i]).collect(),
+            silhouette: clustering::silhouette_score(&vectors, &clustering),
+            k_scores,
+        })
+    }
 
-        // Map clusters back to material IDs
-        let mut result = vec![Vec::new(); k];
-        for (i, cluster_id) in clusters.iter().enumerate() {
-            result[*cluster_id].push(ids[i]);
-        }
+    /// 2D map of the embedding space, optionally colored by clusters
+    pub async fn materials_map(
+        &self,
+        projection: &ProjectionMethod,
+        clusters: Option<&ClusterMethod>,
+    ) -> Result<MaterialsMap, String> {
+        let (ids, vectors) = self.cached_vectors().await;
+        let projected = projection::project(&vectors, projection).map_err(|e| e.to_string())?;
+        let labels = match clusters {
+            Some(method) => Some(Self::run_clustering(&vectors, method)?.0),
+            None => None,
+        };
 
-        Ok(result)
+        let index = self.index.read().await;
+        let points = ids.iter().zip(&projected.points).enumerate()
+            .map(|(i, (id, [x, y]))| MapPoint {
+                material_id: *id,
+                formula: Self::indexed_formula(&index, *id),
+                x: *x,
+                y: *y,
+                cluster: labels.as_ref().and_then(|l| l.labels[i]),
+            })
+            .collect();
+
+        Ok(MaterialsMap {
+            method: projection.name().to_string(),
+            model_version: self.model_version().await,
+            created_at: chrono::Utc::now(),
+            explained_variance: projected.explained_variance,
+            n_clusters: labels.map_or(0, |l| l.n_clusters),
+            points,
+        })
+    }
+
+    /// Write `materials_map` as JSON
+    pub async fn export_materials_map(
+        &self,
+        path: &Path,
+        projection: &ProjectionMethod,
+        clusters: Option<&ClusterMethod>,
+    ) -> Result<MaterialsMap, String> {
+        let map = self.materials_map(projection, clusters).await?;
+        map.save(path).map_err(|e| e.to_string())?;
+        Ok(map)
     }
 
     /// Get embedding statistics
@@ -179,7 +628,7 @@ impl EmbeddingEngine {
         EmbeddingStats {
             total_embeddings: embeddings.len(),
             dimension: EMBEDDING_DIM,
-            model_version: self.version.clone(),
+            model_version: self.model_version().await,
             average_confidence: embeddings.values()
                 .map(|e| e.metadata.confidence)
                 .sum::<f64>() / embeddings.len() as f64,
@@ -188,44 +637,127 @@ impl EmbeddingEngine {
 
     // === Private Helper Methods ===
 
+    /// Version an embedding of `input` gets from the current configuration
+    fn expected_version(&self, model: Option<&LearnedEmbeddingModel>, input: Option<&EmbeddingInput>) -> String {
+        match (model, &self.structure_featurizer) {
+            (Some(model), _) => model.version.clone(),
+            (None, Some(featurizer)) if input.is_some_and(|i| i.structure_block.is_some()) => {
+                format!("{}+{}", self.version, featurizer.name())
+            }
+            _ => self.version.clone(),
+        }
+    }
+
+    async fn index_embedding(
+        &self,
+        embedding: &ChemicalEmbedding,
+        formula: &str,
+        composition: &HashMap<String, usize>,
+    ) -> Result<(), String> {
+        let mut elements: Vec<String> = composition.keys().cloned().collect();
+        elements.sort();
+        let mut index = self.index.write().await;
+        // Keep a source set earlier for this material
+        let source = index.metadata(embedding.material_id).and_then(|m| m.source.clone());
+        let metadata = IndexMetadata { formula: Some(formula.to_string()), elements, source };
+        index.insert(embedding.material_id, &embedding.vector, metadata).map_err(|e| e.to_string())
+    }
+
+    async fn search_index(
+        &self,
+        material_id: Uuid,
+        top_k: usize,
+        filter: Option<&MetadataFilter>,
+    ) -> Result<Vec<SimilarityResult>, String> {
+        let index = self.compacted_index().await;
+        let target = index.vector(material_id)
+            .ok_or_else(|| "Material not found in embeddings".to_string())?;
+
+        // One extra hit, since the material finds itself
+        let ef = index.config().ef_search.max(top_k + 1);
+        Ok(index.search_with(&target, top_k + 1, ef, filter)
+            .into_iter()
+            .filter(|n| n.id != material_id)
+            .take(top_k)
+            .map(|n| SimilarityResult {
+                material_id: n.id,
+                similarity_score: 1.0 - n.distance,
+                distance: n.distance,
+                formula: Self::indexed_formula(&index, n.id),
+            })
+            .collect())
+    }
+
+    /// Read access to the index, rebuilding it first if deletes have left
+    /// too many tombstones
+    async fn compacted_index(&self) -> RwLockReadGuard<'_, HnswIndex> {
+        let index = self.index.read().await;
+        if !index.needs_rebuild() {
+            return index;
+        }
+        drop(index);
+        let mut index = self.index.write().await;
+        // Another query may have rebuilt it while we waited
+        if index.needs_rebuild() {
+            index.rebuild();
+        }
+        index.downgrade()
+    }
+
+    async fn formula_of(&self, material_id: Uuid) -> String {
+        Self::indexed_formula(&*self.index.read().await, material_id)
+    }
+
+    fn indexed_formula(index: &HnswIndex, material_id: Uuid) -> String {
+        index.metadata(material_id)
+            .and_then(|m| m.formula.clone())
+            .unwrap_or_else(|| format!("Material-{}", &material_id.to_string()[..8]))
+    }
+
     fn initialize_element_vectors() -> HashMap<String, Vec<f64>> {
-        // Pre-trained element embeddings based on:
-        // - Atomic number
-        // - Electronegativity
-        // - Atomic radius
-        // - Ionization energy
-        // - Electron affinity
-        // - Common oxidation states
+        // Element embeddings built from the shared elemental property set
+        // (atomic number, mass, melting point, row/column, radius,
+        // electronegativity, valence and unfilled shell counts), standardized
+        // across the periodic table so no single property dominates.
+
+        let elements = crate::elements::all_elements();
+        let properties = crate::featurizer::ELEMENTAL_PROPERTIES;
+
+        let raw: Vec<Vec<Option<f64>>> = elements
+            .iter()
+            .map(|e| {
+                properties
+                    .iter()
+                    .map(|p| MagpieFeaturizer::elemental_property(e, p))
+                    .collect()
+            })
+            .collect();
+
+        let stats: Vec<(f64, f64)> = (0..properties.len())
+            .map(|j| {
+                let values: Vec<f64> = raw.iter().filter_map(|r| r[j]).collect();
+                let mean = values.iter().sum::<f64>() / values.len() as f64;
+                let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+                (mean, var.sqrt().max(1e-10))
+            })
+            .collect();
 
         let mut vectors = HashMap::new();
 
-        // Common elements (simplified for demo)
-        let elements = vec![
-            ("H", vec![1.0, 2.20, 0.37, 13.6, 0.75, 1.0]),
-            ("C", vec![6.0, 2.55, 0.77, 11.3, 1.26, 4.0]),
-            ("N", vec![7.0, 3.04, 0.75, 14.5, 0.07, -3.0]),
-            ("O", vec![8.0, 3.44, 0.73, 13.6, 1.46, -2.0]),
-            ("F", vec![9.0, 3.98, 0.71, 17.4, 3.40, -1.0]),
-            ("Si", vec![14.0, 1.90, 1.18, 8.2, 1.39, 4.0]),
-            ("P", vec![15.0, 2.19, 1.10, 10.5, 0.75, 5.0]),
-            ("S", vec![16.0, 2.58, 1.04, 10.4, 2.08, -2.0]),
-            ("Cl", vec![17.0, 3.16, 0.99, 13.0, 3.61, -1.0]),
-            ("Fe", vec![26.0, 1.83, 1.26, 7.9, 0.15, 3.0]),
-            ("Cu", vec![29.0, 1.90, 1.28, 7.7, 1.24, 2.0]),
-            ("Ag", vec![47.0, 1.93, 1.45, 7.6, 1.30, 1.0]),
-            ("Au", vec![79.0, 2.54, 1.44, 9.2, 2.31, 3.0]),
-            ("Ti", vec![22.0, 1.54, 1.47, 6.8, 0.08, 4.0]),
-            ("Al", vec![13.0, 1.61, 1.43, 6.0, 0.43, 3.0]),
-        ];
+        for (element, values) in elements.iter().zip(&raw) {
+            let features: Vec<f64> = values
+                .iter()
+                .zip(&stats)
+                .map(|(v, (mean, std))| v.map_or(0.0, |v| (v - mean) / std))
+                .collect();
 
-        for (symbol, features) in elements {
             // Expand to higher dimension
             let mut vector = features.clone();
             // Add derived features
             vector.extend(features.iter().map(|x| x * x)); // Squared
             vector.extend(features.iter().copied()); // Duplicate for higher dimension
             vector.resize(64, 0.0);
-            vectors.insert(symbol.to_string(), Self::normalize_vector(&vector));
+            vectors.insert(element.symbol.to_string(), Self::normalize_vector(&vector));
         }
 
         vectors
@@ -244,43 +776,12 @@ impl EmbeddingEngine {
         weights
     }
 
-    fn parse_formula(formula: &str) -> Result<HashMap<String, usize>, String> {
-        let mut composition = HashMap::new();
-        let mut current_element = String::new();
-        let mut current_count = String::new();
-
-        for ch in formula.chars() {
-            if ch.is_uppercase() {
-                // Save previous element
-                if !current_element.is_empty() {
-                    let count = if current_count.is_empty() {
-                        1
-                    } else {
-                        current_count.parse().unwrap_or(1)
-                    };
-                    composition.insert(current_element.clone(), count);
-                }
-
-                current_element = ch.to_string();
-                current_count.clear();
-            } else if ch.is_lowercase() {
-                current_element.push(ch);
-            } else if ch.is_numeric() {
-                current_count.push(ch);
-            }
-        }
-
-        // Save last element
-        if !current_element.is_empty() {
-            let count = if current_count.is_empty() {
-                1
-            } else {
-                current_count.parse().unwrap_or(1)
-            };
-            composition.insert(current_element, count);
-        }
-
-        Ok(composition)
+    /// Whole-number element counts of a formula, via `Composition`
+    pub(crate) fn parse_formula(formula: &str) -> Result<HashMap<String, usize>, String> {
+        Composition::parse(formula)
+            .and_then(|c| c.integer_counts())
+            .map(|counts| counts.into_iter().collect())
+            .map_err(|e| e.to_string())
     }
 
     fn composition_embedding(&self, composition: &HashMap<String, usize>) -> Vec<f64> {
@@ -301,17 +802,31 @@ impl EmbeddingEngine {
         vector
     }
 
-    fn property_embedding(&self, properties: &HashMap<String, f64>) -> Vec<f64> {
-        let mut vector = vec![0.0; 128];
+    /// Structure descriptors compressed to a unit-norm block: signed log
+    /// scaling tames Coulomb-matrix magnitudes, then features are folded
+    /// into `STRUCTURE_BLOCK_DIM` slots
+    pub(crate) fn structure_embedding(featurizer: &dyn StructureFeaturizer, structure: &Structure) -> Result<Vec<f64>, String> {
+        let features = featurizer.featurize(structure).map_err(|e| e.to_string())?;
 
-        for (i, (prop_name, weight)) in self.property_weights.iter().enumerate() {
-            if let Some(value) = properties.get(prop_name) {
-                if i < vector.len() {
-                    vector[i] = value * weight;
-                }
-            }
+        let mut block = vec![0.0; STRUCTURE_BLOCK_DIM];
+        for (i, value) in features.iter().enumerate() {
+            block[i % STRUCTURE_BLOCK_DIM] += value.signum() * value.abs().ln_1p();
         }
 
+        Ok(Self::normalize_vector(&block))
+    }
+
+    /// Weighted property values, one per entry of `property_weights`
+    fn property_features(&self, properties: &HashMap<String, f64>) -> Vec<f64> {
+        self.property_weights
+            .iter()
+            .map(|(prop_name, weight)| properties.get(prop_name).map_or(0.0, |value| value * weight))
+            .collect()
+    }
+
+    fn property_embedding(property_features: &[f64]) -> Vec<f64> {
+        let mut vector = property_features.to_vec();
+        vector.resize(128, 0.0);
         vector
     }
 
@@ -335,81 +850,44 @@ impl EmbeddingEngine {
         dot_product / (magnitude_a * magnitude_b)
     }
 
+    /// Same value for every spelling of a material ("Fe2O3", "O3Fe2",
+    /// "Fe4O6"); unparseable formulas hash their raw text
     fn hash_formula(formula: &str) -> u64 {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
 
+        if let Ok(composition) = Composition::parse(formula) {
+            return composition.canonical_hash();
+        }
         let mut hasher = DefaultHasher::new();
         formula.hash(&mut hasher);
         hasher.finish()
     }
 
-    fn kmeans_clustering(vectors: &[Vec<f64>], k: usize, max_iterations: usize) -> Vec<usize> {
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-
-        let n = vectors.len();
-        let dim = vectors[0].len();
-
-        // Initialize centroids randomly
-        let mut centroids: Vec<Vec<f64>> = (0..k)
-            .map(|_| {
-                let idx = rng.gen_range(0..n);
-                vectors[idx].clone()
-            })
-            .collect();
-
-        let mut assignments = vec![0; n];
-
-        for _ in 0..max_iterations {
-            // Assignment step
-            for (i, vector) in vectors.iter().enumerate() {
-                let mut best_cluster = 0;
-                let mut best_distance = f64::MAX;
-
-                for (j, centroid) in centroids.iter().enumerate() {
-                    let distance = Self::euclidean_distance(vector, centroid);
-                    if distance < best_distance {
-                        best_distance = distance;
-                        best_cluster = j;
-                    }
-                }
+    /// Cached ids and vectors, in id order so results are reproducible
+    async fn cached_vectors(&self) -> (Vec<Uuid>, Vec<Vec<f64>>) {
+        let embeddings = self.embeddings.read().await;
+        let mut ids: Vec<Uuid> = embeddings.values().filter(|e| !e.vector.is_empty()).map(|e| e.material_id).collect();
+        ids.sort();
+        let vectors = ids.iter().map(|id| embeddings[id].vector.clone()).collect();
+        (ids, vectors)
+    }
 
-                assignments[i] = best_cluster;
+    /// Clustering plus the silhouette of each k tried, for `AutoKMeans`
+    fn run_clustering(vectors: &[Vec<f64>], method: &ClusterMethod) -> Result<(Clustering, Vec<(usize, f64)>), String> {
+        let result = match method {
+            ClusterMethod::KMeans(config) => clustering::kmeans(vectors, config).map(|r| (r.clustering, Vec::new())),
+            ClusterMethod::AutoKMeans { k_min, k_max, seed } => {
+                clustering::choose_k(vectors, *k_min..=*k_max, *seed).map(|s| (s.result.clustering, s.scores))
             }
-
-            // Update step
-            for j in 0..k {
-                let cluster_vectors: Vec<&Vec<f64>> = vectors.iter()
-                    .enumerate()
-                    .filter(|(i, _)| assignments[*i] == j)
-                    .map(|(_, v)| v)
-                    .collect();
-
-                if !cluster_vectors.is_empty() {
-                    let mut new_centroid = vec![0.0; dim];
-                    for vector in &cluster_vectors {
-                        for (i, val) in vector.iter().enumerate() {
-                            new_centroid[i] += val;
-                        }
-                    }
-                    for val in &mut new_centroid {
-                        *val /= cluster_vectors.len() as f64;
-                    }
-                    centroids[j] = new_centroid;
-                }
+            ClusterMethod::Dbscan { eps, min_points } => {
+                clustering::dbscan(vectors, *eps, *min_points).map(|c| (c, Vec::new()))
             }
-        }
-
-        assignments
-    }
-
-    fn euclidean_distance(a: &[f64], b: &[f64]) -> f64 {
-        a.iter()
-            .zip(b.iter())
-            .map(|(x, y)| (x - y).powi(2))
-            .sum::<f64>()
-            .sqrt()
+            ClusterMethod::Hdbscan { min_cluster_size, min_samples } => {
+                clustering::hdbscan(vectors, *min_cluster_size, *min_samples).map(|c| (c, Vec::new()))
+            }
+        };
+        result.map_err(|e| e.to_string())
     }
 }
 
@@ -419,6 +897,18 @@ impl Default for EmbeddingEngine {
     }
 }
 
+/// Materials grouped by `EmbeddingEngine::cluster_materials_with`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaterialClusters {
+    pub clusters: Vec<Vec<Uuid>>,
+    /// Materials in no cluster (density-based methods only)
+    pub noise: Vec<Uuid>,
+    /// Mean silhouette over clustered materials
+    pub silhouette: f64,
+    /// Silhouette of each k tried by `AutoKMeans`
+    pub k_scores: Vec<(usize, f64)>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddingStats {
     pub total_embeddings: usize,
@@ -448,6 +938,9 @@ mod tests {
 
         assert_eq!(embedding.vector.len(), EMBEDDING_DIM);
         assert!(embedding.metadata.confidence > 0.0);
+
+        let respelled = engine.generate_embedding(Uuid::new_v4(), "O6Fe4", &properties).await.unwrap();
+        assert_eq!(respelled.formula_hash, embedding.formula_hash);
     }
 
     #[tokio::test]
@@ -469,4 +962,226 @@ mod tests {
         let similar = engine.find_similar(id1, 5).await.unwrap();
         assert!(!similar.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_element_vectors_cover_periodic_table() {
+        let engine = EmbeddingEngine::new();
+        for symbol in ["Pt", "Pd", "Au", "Nd", "U"] {
+            assert!(engine.element_vectors.contains_key(symbol), "{}", symbol);
+        }
+
+        let embedding = engine.generate_embedding(Uuid::new_v4(), "PtPd", &HashMap::new()).await.unwrap();
+        assert!(embedding.vector.iter().any(|v| *v != 0.0));
+    }
+
+    #[tokio::test]
+    async fn test_structure_aware_embeddings_separate_polymorphs() {
+        use crate::material::{MaterialBuilder, Site};
+        use crate::structure_descriptors::{CoordinationStatistics, FeaturizerStack, PartialRdfFingerprint};
+
+        let featurizer = FeaturizerStack::new()
+            .with(CoordinationStatistics::default())
+            .with(PartialRdfFingerprint::default());
+        let engine = EmbeddingEngine::new().with_structure_featurizer(Arc::new(featurizer));
+
+        let polymorph = |a: f64, sites: &[(&str, [f64; 3])]| {
+            MaterialBuilder::new("NaCl").unwrap()
+                .with_structure(Structure {
+                    lattice: [[a, 0.0, 0.0], [0.0, a, 0.0], [0.0, 0.0, a]],
+                    sites: sites.iter()
+                        .map(|(e, c)| Site { element: e.to_string(), coords: *c, magmom: None, occupancy: 1.0 })
+                        .collect(),
+                    space_group: None,
+                    crystal_system: None,
+                })
+                .build()
+        };
+        let rock_salt = polymorph(5.64, &[
+            ("Na", [0.0, 0.0, 0.0]), ("Na", [0.5, 0.5, 0.0]), ("Na", [0.5, 0.0, 0.5]), ("Na", [0.0, 0.5, 0.5]),
+            ("Cl", [0.5, 0.0, 0.0]), ("Cl", [0.0, 0.5, 0.0]), ("Cl", [0.0, 0.0, 0.5]), ("Cl", [0.5, 0.5, 0.5]),
+        ]);
+        let cesium_chloride = polymorph(3.25, &[("Na", [0.0, 0.0, 0.0]), ("Cl", [0.5, 0.5, 0.5])]);
+
+        let props = HashMap::new();
+        let a = engine.generate_material_embedding(&rock_salt, &props).await.unwrap();
+        let b = engine.generate_material_embedding(&cesium_chloride, &props).await.unwrap();
+        assert_eq!(a.vector.len(), EMBEDDING_DIM);
+        assert!(EmbeddingEngine::cosine_similarity(&a.vector, &b.vector) < 0.999);
+
+        // Without a featurizer both collapse to the composition embedding
+        let plain = EmbeddingEngine::new();
+        let a = plain.generate_material_embedding(&rock_salt, &props).await.unwrap();
+        let b = plain.generate_material_embedding(&cesium_chloride, &props).await.unwrap();
+        assert!((EmbeddingEngine::cosine_similarity(&a.vector, &b.vector) - 1.0).abs() < 1e-12);
+    }
+
+    #[tokio::test]
+    async fn test_removals_compact_on_next_search() {
+        let engine = EmbeddingEngine::new();
+        let mut ids = Vec::new();
+        for i in 1..=10 {
+            for j in 1..=10 {
+                let id = Uuid::new_v4();
+                engine.generate_embedding(id, &format!("Fe{}O{}", i, j), &HashMap::new()).await.unwrap();
+                ids.push(id);
+            }
+        }
+
+        for id in &ids[..70] {
+            assert!(engine.remove_embedding(*id).await);
+        }
+        assert_eq!(engine.index.read().await.tombstones(), 70);
+
+        let similar = engine.find_similar(ids[99], 5).await.unwrap();
+        assert_eq!(similar.len(), 5);
+        assert!(similar.iter().all(|r| ids[70..].contains(&r.material_id)));
+        let index = engine.index.read().await;
+        assert_eq!((index.tombstones(), index.len()), (0, 30));
+    }
+
+    #[tokio::test]
+    async fn test_indexed_similarity_search() {
+        let engine = EmbeddingEngine::new();
+        let formulas = [
+            "Fe2O3", "Fe3O4", "FeO", "Co3O4", "NiO", "Al2O3", "TiO2", "SrTiO3", "BaTiO3",
+            "NaCl", "KCl", "LiF", "GaAs", "InP", "Si", "Ge", "ZnS", "CdTe", "MgO", "CaO",
+        ];
+        let mut ids = HashMap::new();
+        for formula in formulas {
+            let id = Uuid::new_v4();
+            engine.generate_embedding(id, formula, &HashMap::new()).await.unwrap();
+            ids.insert(formula, id);
+        }
+        engine.set_source(ids["NiO"], "oqmd").await;
+
+        // The index agrees with the exact scan on a small set
+        let indexed = engine.find_similar(ids["Fe2O3"], 5).await.unwrap();
+        let exact = engine.find_similar_exact(ids["Fe2O3"], 5).await.unwrap();
+        let names = |r: &[SimilarityResult]| r.iter().map(|s| s.formula.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&indexed), names(&exact));
+        assert!(indexed.iter().all(|r| r.material_id != ids["Fe2O3"]));
+
+        let oxides_without_iron = MetadataFilter::new().with_elements(&["O"]).without_elements(&["Fe"]);
+        let filtered = engine.find_similar_filtered(ids["Fe2O3"], 3, &oxides_without_iron).await.unwrap();
+        assert_eq!(filtered.len(), 3);
+        assert!(filtered.iter().all(|r| r.formula.contains('O') && !r.formula.contains("Fe")));
+        let from_oqmd = engine.find_similar_filtered(ids["Fe2O3"], 3, &MetadataFilter::new().from_sources(&["oqmd"]))
+            .await
+            .unwrap();
+        assert_eq!(names(&from_oqmd), vec!["NiO".to_string()]);
+
+        assert!(engine.remove_embedding(ids["Fe3O4"]).await);
+        assert!(engine.find_similar(ids["Fe2O3"], 19).await.unwrap().iter().all(|r| r.formula != "Fe3O4"));
+        assert_eq!(engine.benchmark_index_recall(10, 5, 64).await.recall, 1.0);
+
+        // A reloaded index serves searches without the embedding cache
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("embeddings.hnsw");
+        engine.save_index(&path).await.unwrap();
+        let reloaded = EmbeddingEngine::new();
+        assert_eq!(reloaded.load_index(&path).await.unwrap(), formulas.len() - 1);
+        assert_eq!(
+            names(&reloaded.find_similar(ids["Fe2O3"], 5).await.unwrap()),
+            names(&engine.find_similar(ids["Fe2O3"], 5).await.unwrap())
+        );
+
+        // The compressed store takes over the cached vectors
+        let hematite = engine.embeddings.read().await[&ids["Fe2O3"]].vector.clone();
+        let store = engine.build_compressed_store(StoreConfig::default()).await.unwrap();
+        assert_eq!(store.len(), formulas.len() - 1);
+        assert_eq!(store.search(&hematite, 1).unwrap()[0].id, ids["Fe2O3"]);
+        assert!(engine.embeddings.read().await.values().all(|e| e.vector.is_empty()));
+        assert!(engine.find_similar_exact(ids["Fe2O3"], 5).await.is_err());
+        assert_eq!(engine.find_similar(ids["Fe2O3"], 5).await.unwrap().len(), 5);
+        assert_eq!(engine.get_statistics().await.total_embeddings, formulas.len() - 1);
+    }
+
+    #[tokio::test]
+    async fn test_learned_model_reembedding() {
+        let engine = EmbeddingEngine::new();
+        let mut formulas = Vec::new();
+        for a in ["Li", "Na", "K", "Rb"] {
+            for x in ["F", "Cl", "Br"] {
+                formulas.push(format!("{}{}", a, x));
+            }
+        }
+        for m in ["Fe", "Co", "Ni", "Mn"] {
+            formulas.push(format!("{}O", m));
+            formulas.push(format!("{}2O3", m));
+        }
+        let mut ids = Vec::new();
+        for formula in &formulas {
+            let id = Uuid::new_v4();
+            engine.generate_embedding(id, formula, &HashMap::new()).await.unwrap();
+            ids.push(id);
+        }
+        assert!(engine.stale_embeddings().await.is_empty());
+
+        let config = LearnedEmbeddingConfig { element_dim: 8, hidden_dim: 32, output_dim: 16, epochs: 20, ..Default::default() };
+        let model = engine.train_model(config).await.unwrap();
+        let version = model.version.clone();
+
+        // Models that do not fit the engine are refused
+        let structured = EmbeddingEngine::new()
+            .with_structure_featurizer(Arc::new(crate::structure_descriptors::CoordinationStatistics::default()));
+        assert!(structured.set_model(model.clone()).await.is_err());
+        assert!(structured.model().await.is_none());
+        let mut wide = model.clone();
+        wide.encoder.output_dim = EMBEDDING_DIM + 1;
+        assert!(engine.set_model(wide).await.is_err());
+        let mut mismatched = model.clone();
+        mismatched.elements.dim += 1;
+        assert!(engine.set_model(mismatched).await.is_err());
+        assert!(engine.model().await.is_none());
+
+        assert_eq!(engine.set_model(model).await.unwrap(), formulas.len());
+
+        // New embeddings use the model straight away
+        let extra = Uuid::new_v4();
+        let embedding = engine.generate_embedding(extra, "CsCl", &HashMap::new()).await.unwrap();
+        assert_eq!(embedding.metadata.model_version, version);
+        assert_eq!(embedding.vector.len(), EMBEDDING_DIM);
+
+        let report = engine.reembed_stale(5).await;
+        assert_eq!((report.stale, report.reembedded, report.skipped), (formulas.len(), formulas.len(), 0));
+        assert!(report.failed.is_empty());
+        assert!(engine.stale_embeddings().await.is_empty());
+        assert_eq!(engine.get_statistics().await.model_version, version);
+        assert_eq!(engine.find_similar(ids[0], 3).await.unwrap().len(), 3);
+
+        assert_eq!(engine.clear_model().await, formulas.len() + 1);
+        assert_eq!(engine.model_version().await, "v1.0.0");
+    }
+
+    #[tokio::test]
+    async fn test_clustering_and_materials_map() {
+        let engine = EmbeddingEngine::new();
+        let families = [["LiF", "NaF", "KF", "LiCl", "NaCl", "KCl"], ["Fe2O3", "Co2O3", "Cr2O3", "Mn2O3", "Al2O3", "Ga2O3"]];
+        for formula in families.iter().flatten() {
+            engine.generate_embedding(Uuid::new_v4(), formula, &HashMap::new()).await.unwrap();
+        }
+
+        assert_eq!(engine.cluster_materials(2).await.unwrap().len(), 2);
+        let auto = engine
+            .cluster_materials_with(&ClusterMethod::AutoKMeans { k_min: 2, k_max: 5, seed: 1 })
+            .await
+            .unwrap();
+        assert_eq!(auto.k_scores.len(), 4);
+        assert_eq!(auto.clusters.iter().map(Vec::len).sum::<usize>(), 12);
+        assert!(auto.silhouette > 0.0);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("map.json");
+        let map = engine
+            .export_materials_map(&path, &ProjectionMethod::Pca, Some(&ClusterMethod::KMeans(KMeansConfig::new(2))))
+            .await
+            .unwrap();
+        assert_eq!(map.points.len(), 12);
+        assert_eq!(map.n_clusters, 2);
+        assert!(map.points.iter().all(|p| p.cluster.is_some() && !p.formula.starts_with("Material-")));
+
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["method"], "pca");
+        assert_eq!(json["points"].as_array().unwrap().len(), 12);
+    }
 }
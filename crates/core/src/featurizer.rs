@@ -0,0 +1,379 @@
+//! Composition Featurizer
+//!
+//! Magpie-style descriptors (Ward et al., npj Comput. Mater. 2016) computed
+//! from a composition and the [`crate::elements`] database. For every
+//! elemental property the featurizer emits fraction-weighted statistics
+//! (mean, average deviation, min, max, range, mode), followed by
+//! stoichiometric norms, valence-orbital fractions and ionic character.
+//!
+//! This is the shared composition representation for `ml_predictor`,
+//! `embeddings` and the compute crate's ML engine. [`FeatureSet`] selects
+//! between composition features and the structure descriptors in
+//! [`crate::structure_descriptors`].
+
+use crate::composition::Composition;
+use crate::elements::{self, Element};
+use crate::material::Material;
+use crate::structure_descriptors::StructureFeaturizer;
+use crate::{Error, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Elemental properties, in feature order
+pub const ELEMENTAL_PROPERTIES: [&str; 17] = [
+    "Number",
+    "AtomicWeight",
+    "MeltingT",
+    "Column",
+    "Row",
+    "CovalentRadius",
+    "Electronegativity",
+    "NsValence",
+    "NpValence",
+    "NdValence",
+    "NfValence",
+    "NValence",
+    "NsUnfilled",
+    "NpUnfilled",
+    "NdUnfilled",
+    "NfUnfilled",
+    "NUnfilled",
+];
+
+/// Weighted statistics computed for each elemental property
+pub const STATISTICS: [&str; 6] = ["mean", "avg_dev", "min", "max", "range", "mode"];
+
+const STOICHIOMETRY_NORMS: [u32; 5] = [2, 3, 5, 7, 10];
+
+/// Magpie-style composition featurizer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MagpieFeaturizer;
+
+impl MagpieFeaturizer {
+    /// Total number of features produced
+    pub const NUM_FEATURES: usize =
+        ELEMENTAL_PROPERTIES.len() * STATISTICS.len() + 1 + STOICHIOMETRY_NORMS.len() + 4 + 2;
+
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Feature names, aligned with [`Self::featurize`] output
+    pub fn feature_names(&self) -> Vec<String> {
+        let mut names = Vec::with_capacity(Self::NUM_FEATURES);
+        for property in ELEMENTAL_PROPERTIES {
+            for stat in STATISTICS {
+                names.push(format!("{}_{}", stat, property));
+            }
+        }
+        names.push("NComp".to_string());
+        for p in STOICHIOMETRY_NORMS {
+            names.push(format!("L{}Norm", p));
+        }
+        for orbital in ["s", "p", "d", "f"] {
+            names.push(format!("frac_{}Valence", orbital));
+        }
+        names.push("MaxIonicChar".to_string());
+        names.push("MeanIonicChar".to_string());
+        names
+    }
+
+    /// Value of a named elemental property for one element
+    pub fn elemental_property(element: &Element, property: &str) -> Option<f64> {
+        let valence = element.valence_shells();
+        let unfilled = element.unfilled_shells();
+
+        let value = match property {
+            "Number" => element.z as f64,
+            "AtomicWeight" => element.atomic_mass,
+            "MeltingT" => element.melting_point,
+            "Column" => element.group() as f64,
+            "Row" => element.period() as f64,
+            "CovalentRadius" => element.covalent_radius,
+            "Electronegativity" => return element.electronegativity,
+            "NsValence" => valence.s as f64,
+            "NpValence" => valence.p as f64,
+            "NdValence" => valence.d as f64,
+            "NfValence" => valence.f as f64,
+            "NValence" => valence.total() as f64,
+            "NsUnfilled" => unfilled.s as f64,
+            "NpUnfilled" => unfilled.p as f64,
+            "NdUnfilled" => unfilled.d as f64,
+            "NfUnfilled" => unfilled.f as f64,
+            "NUnfilled" => unfilled.total() as f64,
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Featurize a composition given as (element, amount) pairs
+    ///
+    /// Amounts may be counts or fractions; they are normalized internally.
+    pub fn featurize<S: AsRef<str>>(&self, composition: &[(S, f64)]) -> Result<Vec<f64>> {
+        let mut entries: Vec<(&'static Element, f64)> = Vec::with_capacity(composition.len());
+        for (symbol, amount) in composition {
+            let symbol = symbol.as_ref();
+            let element = elements::element(symbol)
+                .ok_or_else(|| Error::invalid_input(format!("Unknown element: {}", symbol)))?;
+            if *amount < 0.0 || !amount.is_finite() {
+                return Err(Error::invalid_input(format!("Invalid amount {} for {}", amount, symbol)));
+            }
+            if *amount > 0.0 {
+                match entries.iter_mut().find(|(e, _)| e.z == element.z) {
+                    Some(entry) => entry.1 += amount,
+                    None => entries.push((element, *amount)),
+                }
+            }
+        }
+
+        let total: f64 = entries.iter().map(|(_, a)| a).sum();
+        if entries.is_empty() || total <= 0.0 {
+            return Err(Error::invalid_input("Empty composition"));
+        }
+        let fractions: Vec<(&Element, f64)> = entries.iter().map(|(e, a)| (*e, a / total)).collect();
+
+        let mut features = Vec::with_capacity(Self::NUM_FEATURES);
+
+        for property in ELEMENTAL_PROPERTIES {
+            let values: Vec<(f64, f64)> = fractions
+                .iter()
+                .filter_map(|(e, f)| Self::elemental_property(e, property).map(|v| (v, *f)))
+                .collect();
+            features.extend(Self::weighted_statistics(&values));
+        }
+
+        // Stoichiometric attributes
+        features.push(fractions.len() as f64);
+        for p in STOICHIOMETRY_NORMS {
+            let norm = fractions.iter().map(|(_, f)| f.powi(p as i32)).sum::<f64>();
+            features.push(norm.powf(1.0 / p as f64));
+        }
+
+        // Fraction of valence electrons in each orbital type
+        let mean_valence = |select: fn(&Element) -> f64| -> f64 {
+            fractions.iter().map(|(e, f)| f * select(e)).sum()
+        };
+        let total_valence = mean_valence(|e| e.valence_shells().total() as f64);
+        let orbital_means = [
+            mean_valence(|e| e.valence_shells().s as f64),
+            mean_valence(|e| e.valence_shells().p as f64),
+            mean_valence(|e| e.valence_shells().d as f64),
+            mean_valence(|e| e.valence_shells().f as f64),
+        ];
+        for orbital in orbital_means {
+            features.push(if total_valence > 0.0 { orbital / total_valence } else { 0.0 });
+        }
+
+        // Ionic character (Pauling): 1 - exp(-(Δχ)²/4)
+        let mut max_ionic: f64 = 0.0;
+        let mut mean_ionic = 0.0;
+        for (i, (ei, fi)) in fractions.iter().enumerate() {
+            for (ej, fj) in fractions.iter().skip(i + 1) {
+                if let (Some(xi), Some(xj)) = (ei.electronegativity, ej.electronegativity) {
+                    let ionic = 1.0 - (-0.25 * (xi - xj).powi(2)).exp();
+                    max_ionic = max_ionic.max(ionic);
+                    mean_ionic += 2.0 * fi * fj * ionic;
+                }
+            }
+        }
+        features.push(max_ionic);
+        features.push(mean_ionic);
+
+        debug_assert_eq!(features.len(), Self::NUM_FEATURES);
+        Ok(features)
+    }
+
+    /// Featurize a composition map (element -> amount)
+    pub fn featurize_map(&self, composition: &HashMap<String, f64>) -> Result<Vec<f64>> {
+        let mut entries: Vec<(&String, f64)> = composition.iter().map(|(k, v)| (k, *v)).collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        self.featurize(&entries)
+    }
+
+    /// Featurize a formula such as "Fe2O3"
+    pub fn featurize_formula(&self, formula: &str) -> Result<Vec<f64>> {
+        let composition = Composition::parse(formula)?;
+        let entries: Vec<(&str, f64)> = composition.iter().collect();
+        self.featurize(&entries)
+    }
+
+    /// Featurize a material from its occupancy-weighted sites, falling back
+    /// to the formula when the structure has no sites
+    pub fn featurize_material(&self, material: &Material) -> Result<Vec<f64>> {
+        let composition = material.composition()?;
+        let entries: Vec<(&str, f64)> = composition.iter().collect();
+        self.featurize(&entries)
+    }
+
+    /// (mean, avg_dev, min, max, range, mode) over (value, fraction) pairs
+    fn weighted_statistics(values: &[(f64, f64)]) -> [f64; 6] {
+        let weight: f64 = values.iter().map(|(_, f)| f).sum();
+        if values.is_empty() || weight <= 0.0 {
+            return [0.0; 6];
+        }
+
+        let mean = values.iter().map(|(v, f)| v * f).sum::<f64>() / weight;
+        let avg_dev = values.iter().map(|(v, f)| f * (v - mean).abs()).sum::<f64>() / weight;
+        let min = values.iter().map(|(v, _)| *v).fold(f64::INFINITY, f64::min);
+        let max = values.iter().map(|(v, _)| *v).fold(f64::NEG_INFINITY, f64::max);
+
+        // Property of the most abundant element (ties resolved to the smaller value)
+        let mode = values
+            .iter()
+            .fold(None::<(f64, f64)>, |best, &(v, f)| match best {
+                Some((bv, bf)) if bf > f + 1e-12 || ((bf - f).abs() <= 1e-12 && bv <= v) => Some((bv, bf)),
+                _ => Some((v, f)),
+            })
+            .map_or(0.0, |(v, _)| v);
+
+        [mean, avg_dev, min, max, max - min, mode]
+    }
+}
+
+/// Feature representation used to turn a material into a model input
+#[derive(Clone, Default)]
+pub enum FeatureSet {
+    /// Magpie composition features (structure-blind)
+    #[default]
+    Composition,
+    /// Structure descriptors only
+    Structure(Arc<dyn StructureFeaturizer>),
+    /// Composition features followed by structure descriptors
+    CompositionAndStructure(Arc<dyn StructureFeaturizer>),
+}
+
+impl FeatureSet {
+    pub fn name(&self) -> String {
+        match self {
+            FeatureSet::Composition => "composition".to_string(),
+            FeatureSet::Structure(f) => f.name().to_string(),
+            FeatureSet::CompositionAndStructure(f) => format!("composition+{}", f.name()),
+        }
+    }
+
+    pub fn feature_names(&self) -> Vec<String> {
+        match self {
+            FeatureSet::Composition => MagpieFeaturizer::new().feature_names(),
+            FeatureSet::Structure(f) => f.feature_names(),
+            FeatureSet::CompositionAndStructure(f) => {
+                let mut names = MagpieFeaturizer::new().feature_names();
+                names.extend(f.feature_names());
+                names
+            }
+        }
+    }
+
+    /// Featurize a material; structure-based sets require sites
+    pub fn featurize(&self, material: &Material) -> Result<Vec<f64>> {
+        match self {
+            FeatureSet::Composition => MagpieFeaturizer::new().featurize_material(material),
+            FeatureSet::Structure(f) => f.featurize(&material.structure),
+            FeatureSet::CompositionAndStructure(f) => {
+                let mut features = MagpieFeaturizer::new().featurize_material(material)?;
+                features.extend(f.featurize(&material.structure)?);
+                Ok(features)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for FeatureSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FeatureSet({})", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::MaterialBuilder;
+
+    #[test]
+    fn test_feature_count_and_names() {
+        let featurizer = MagpieFeaturizer::new();
+        let features = featurizer.featurize_formula("Fe2O3").unwrap();
+        assert_eq!(features.len(), MagpieFeaturizer::NUM_FEATURES);
+        assert_eq!(featurizer.feature_names().len(), MagpieFeaturizer::NUM_FEATURES);
+        assert_eq!(featurizer.feature_names()[0], "mean_Number");
+    }
+
+    #[test]
+    fn test_weighted_statistics() {
+        let featurizer = MagpieFeaturizer::new();
+        let features = featurizer.featurize_formula("Fe2O3").unwrap();
+
+        // Number: Fe = 26 (40%), O = 8 (60%)
+        let mean = 0.4 * 26.0 + 0.6 * 8.0;
+        assert!((features[0] - mean).abs() < 1e-9);
+        assert!((features[1] - (0.4 * (26.0 - mean) + 0.6 * (mean - 8.0))).abs() < 1e-9);
+        assert_eq!(features[2], 8.0);
+        assert_eq!(features[3], 26.0);
+        assert_eq!(features[4], 18.0);
+        assert_eq!(features[5], 8.0); // O is most abundant
+    }
+
+    #[test]
+    fn test_heavy_and_rare_elements() {
+        let featurizer = MagpieFeaturizer::new();
+        for formula in ["PtAu", "Nd2Fe14B", "UO2", "PdH"] {
+            let features = featurizer.featurize_formula(formula).unwrap();
+            assert!(features.iter().all(|f| f.is_finite()), "{}", formula);
+        }
+
+        let pt = featurizer.featurize_formula("Pt").unwrap();
+        assert_eq!(pt[0], 78.0);
+        assert!(featurizer.featurize_formula("Xx2O").is_err());
+    }
+
+    #[test]
+    fn test_material_and_formula_agree() {
+        let featurizer = MagpieFeaturizer::new();
+        let material = MaterialBuilder::new("TiO2").unwrap().build();
+        let bare = Material::new("TiO2");
+
+        assert_eq!(
+            featurizer.featurize_material(&material).unwrap(),
+            featurizer.featurize_material(&bare).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_feature_set_separates_polymorphs() {
+        use crate::material::{Site, Structure};
+        use crate::structure_descriptors::CoordinationStatistics;
+
+        let structure = |a: f64, coords: &[(&str, [f64; 3])]| Structure {
+            lattice: [[a, 0.0, 0.0], [0.0, a, 0.0], [0.0, 0.0, a]],
+            sites: coords
+                .iter()
+                .map(|(e, c)| Site { element: e.to_string(), coords: *c, magmom: None, occupancy: 1.0 })
+                .collect(),
+            space_group: None,
+            crystal_system: None,
+        };
+        // Zinc blende (CN 4) and CsCl-type (CN 8) ZnS
+        let zinc_blende = MaterialBuilder::new("ZnS").unwrap()
+            .with_structure(structure(5.41, &[
+                ("Zn", [0.0, 0.0, 0.0]), ("Zn", [0.5, 0.5, 0.0]), ("Zn", [0.5, 0.0, 0.5]), ("Zn", [0.0, 0.5, 0.5]),
+                ("S", [0.25, 0.25, 0.25]), ("S", [0.75, 0.75, 0.25]), ("S", [0.75, 0.25, 0.75]), ("S", [0.25, 0.75, 0.75]),
+            ]))
+            .build();
+        let cesium_chloride = MaterialBuilder::new("ZnS").unwrap()
+            .with_structure(structure(2.7, &[("Zn", [0.0, 0.0, 0.0]), ("S", [0.5, 0.5, 0.5])]))
+            .build();
+
+        let composition = FeatureSet::Composition;
+        assert_eq!(
+            composition.featurize(&zinc_blende).unwrap(),
+            composition.featurize(&cesium_chloride).unwrap()
+        );
+
+        let combined = FeatureSet::CompositionAndStructure(Arc::new(CoordinationStatistics::default()));
+        let a = combined.featurize(&zinc_blende).unwrap();
+        let b = combined.featurize(&cesium_chloride).unwrap();
+        assert_eq!(a.len(), combined.feature_names().len());
+        assert_eq!(a[MagpieFeaturizer::NUM_FEATURES], 4.0);
+        assert_eq!(b[MagpieFeaturizer::NUM_FEATURES], 8.0);
+    }
+}
@@ -0,0 +1,411 @@
+//! Gaussian Process Regression
+//!
+//! Exact GP regression with RBF and Matérn (3/2, 5/2) kernels. Targets are
+//! standardized internally; hyperparameters (length scale, signal and noise
+//! variance) are fit by maximizing the log marginal likelihood with a
+//! multi-start Nelder-Mead search in log space.
+//!
+//! Used as the surrogate model of [`crate::bayesian_optimization`].
+
+use crate::{Error, Result};
+use nalgebra::{Cholesky, DMatrix, DVector, Dyn};
+use serde::{Deserialize, Serialize};
+
+/// Covariance function family
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Kernel {
+    Rbf,
+    Matern32,
+    #[default]
+    Matern52,
+}
+
+impl Kernel {
+    /// Covariance at Euclidean distance `r`
+    pub fn covariance(&self, r: f64, params: &GpHyperparameters) -> f64 {
+        let l = params.length_scale;
+        let s2 = params.signal_variance;
+        match self {
+            Kernel::Rbf => s2 * (-0.5 * (r / l).powi(2)).exp(),
+            Kernel::Matern32 => {
+                let a = 3f64.sqrt() * r / l;
+                s2 * (1.0 + a) * (-a).exp()
+            }
+            Kernel::Matern52 => {
+                let a = 5f64.sqrt() * r / l;
+                s2 * (1.0 + a + a * a / 3.0) * (-a).exp()
+            }
+        }
+    }
+}
+
+/// Kernel hyperparameters (in standardized target units)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GpHyperparameters {
+    pub length_scale: f64,
+    pub signal_variance: f64,
+    pub noise_variance: f64,
+}
+
+impl Default for GpHyperparameters {
+    fn default() -> Self {
+        Self { length_scale: 0.5, signal_variance: 1.0, noise_variance: 1e-4 }
+    }
+}
+
+impl GpHyperparameters {
+    fn to_log(self) -> [f64; 3] {
+        [self.length_scale.ln(), self.signal_variance.ln(), self.noise_variance.ln()]
+    }
+
+    fn from_log(theta: &[f64], config: &GaussianProcessConfig) -> Self {
+        Self {
+            length_scale: theta[0].exp().clamp(config.length_scale_bounds.0, config.length_scale_bounds.1),
+            signal_variance: theta[1].exp().clamp(1e-4, 1e4),
+            noise_variance: theta[2].exp().clamp(config.noise_bounds.0, config.noise_bounds.1),
+        }
+    }
+}
+
+/// GP fitting options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GaussianProcessConfig {
+    pub kernel: Kernel,
+    /// Starting point (and the fixed values when optimization is off)
+    pub initial: GpHyperparameters,
+    pub optimize_hyperparameters: bool,
+    /// Extra Nelder-Mead starts at other length scales
+    pub restarts: usize,
+    pub max_evaluations: usize,
+    pub length_scale_bounds: (f64, f64),
+    pub noise_bounds: (f64, f64),
+}
+
+impl Default for GaussianProcessConfig {
+    fn default() -> Self {
+        Self {
+            kernel: Kernel::default(),
+            initial: GpHyperparameters::default(),
+            optimize_hyperparameters: true,
+            restarts: 3,
+            max_evaluations: 200,
+            length_scale_bounds: (1e-3, 1e3),
+            noise_bounds: (1e-8, 1.0),
+        }
+    }
+}
+
+impl GaussianProcessConfig {
+    pub fn with_kernel(mut self, kernel: Kernel) -> Self {
+        self.kernel = kernel;
+        self
+    }
+
+    pub fn with_initial(mut self, initial: GpHyperparameters) -> Self {
+        self.initial = initial;
+        self
+    }
+
+    pub fn with_optimization(mut self, optimize: bool) -> Self {
+        self.optimize_hyperparameters = optimize;
+        self
+    }
+}
+
+/// Fitted Gaussian process
+#[derive(Debug, Clone)]
+pub struct GaussianProcess {
+    kernel: Kernel,
+    params: GpHyperparameters,
+    x: Vec<Vec<f64>>,
+    y_mean: f64,
+    y_scale: f64,
+    chol: Cholesky<f64, Dyn>,
+    alpha: DVector<f64>,
+    log_marginal_likelihood: f64,
+}
+
+fn distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum::<f64>().sqrt()
+}
+
+impl GaussianProcess {
+    /// Fit a GP, optimizing hyperparameters if configured
+    pub fn fit(x: &[Vec<f64>], y: &[f64], config: &GaussianProcessConfig) -> Result<Self> {
+        Self::check_data(x, y)?;
+
+        let (y_mean, y_scale) = Self::standardization(y);
+        let z: Vec<f64> = y.iter().map(|v| (v - y_mean) / y_scale).collect();
+
+        let mut params = config.initial;
+        if config.optimize_hyperparameters && x.len() > 1 {
+            let objective = |theta: &[f64]| {
+                let p = GpHyperparameters::from_log(theta, config);
+                Self::condition(x, &z, config.kernel, p).map_or(f64::INFINITY, |(_, _, lml)| -lml)
+            };
+
+            let mut best = (objective(&config.initial.to_log()), config.initial.to_log().to_vec());
+            let starts = std::iter::once(config.initial).chain((0..config.restarts).map(|i| GpHyperparameters {
+                length_scale: config.initial.length_scale * 4f64.powi(i as i32 + 1) / 8.0,
+                ..config.initial
+            }));
+            for start in starts {
+                let (value, theta) = nelder_mead(&objective, &start.to_log(), config.max_evaluations);
+                if value < best.0 {
+                    best = (value, theta);
+                }
+            }
+            params = GpHyperparameters::from_log(&best.1, config);
+        }
+
+        Self::with_hyperparameters(x, y, config.kernel, params)
+    }
+
+    /// Condition a GP on data with fixed hyperparameters
+    pub fn with_hyperparameters(x: &[Vec<f64>], y: &[f64], kernel: Kernel, params: GpHyperparameters) -> Result<Self> {
+        Self::check_data(x, y)?;
+
+        let (y_mean, y_scale) = Self::standardization(y);
+        let z: Vec<f64> = y.iter().map(|v| (v - y_mean) / y_scale).collect();
+        let (chol, alpha, log_marginal_likelihood) = Self::condition(x, &z, kernel, params)
+            .ok_or_else(|| Error::computation("GP covariance matrix is not positive definite"))?;
+
+        Ok(Self {
+            kernel,
+            params,
+            x: x.to_vec(),
+            y_mean,
+            y_scale,
+            chol,
+            alpha,
+            log_marginal_likelihood,
+        })
+    }
+
+    fn check_data(x: &[Vec<f64>], y: &[f64]) -> Result<()> {
+        if x.is_empty() || x.len() != y.len() {
+            return Err(Error::invalid_input("GP needs equally many inputs and targets"));
+        }
+        let dim = x[0].len();
+        if x.iter().any(|row| row.len() != dim) || y.iter().any(|v| !v.is_finite()) {
+            return Err(Error::invalid_input("GP inputs must share a dimension and targets be finite"));
+        }
+        Ok(())
+    }
+
+    fn standardization(y: &[f64]) -> (f64, f64) {
+        let n = y.len() as f64;
+        let mean = y.iter().sum::<f64>() / n;
+        let std = (y.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+        (mean, if std > 1e-12 { std } else { 1.0 })
+    }
+
+    /// Cholesky factor, α = K⁻¹z and log marginal likelihood; a small
+    /// jitter is added if the matrix is numerically singular
+    fn condition(
+        x: &[Vec<f64>],
+        z: &[f64],
+        kernel: Kernel,
+        params: GpHyperparameters,
+    ) -> Option<(Cholesky<f64, Dyn>, DVector<f64>, f64)> {
+        let n = x.len();
+        let base = DMatrix::from_fn(n, n, |i, j| kernel.covariance(distance(&x[i], &x[j]), &params));
+
+        for jitter in [0.0, 1e-10, 1e-8, 1e-6] {
+            let mut k = base.clone();
+            for i in 0..n {
+                k[(i, i)] += params.noise_variance + jitter;
+            }
+            if let Some(chol) = k.cholesky() {
+                let zv = DVector::from_column_slice(z);
+                let alpha = chol.solve(&zv);
+                let log_det: f64 = chol.l().diagonal().iter().map(|d| d.ln()).sum::<f64>() * 2.0;
+                let lml = -0.5 * zv.dot(&alpha) - 0.5 * log_det - 0.5 * n as f64 * (2.0 * std::f64::consts::PI).ln();
+                return lml.is_finite().then_some((chol, alpha, lml));
+            }
+        }
+        None
+    }
+
+    fn cross_covariance(&self, point: &[f64]) -> DVector<f64> {
+        DVector::from_iterator(
+            self.x.len(),
+            self.x.iter().map(|xi| self.kernel.covariance(distance(xi, point), &self.params)),
+        )
+    }
+
+    /// Posterior mean and standard deviation of the latent function
+    pub fn predict(&self, point: &[f64]) -> (f64, f64) {
+        let k_star = self.cross_covariance(point);
+        let mean = k_star.dot(&self.alpha);
+        let v = self.chol.l().solve_lower_triangular(&k_star).unwrap_or_else(|| DVector::zeros(self.x.len()));
+        let var = (self.params.signal_variance - v.dot(&v)).max(0.0);
+
+        (self.y_mean + self.y_scale * mean, self.y_scale * var.sqrt())
+    }
+
+    /// Joint posterior mean vector and covariance matrix at several points
+    pub fn predict_joint(&self, points: &[Vec<f64>]) -> (Vec<f64>, DMatrix<f64>) {
+        let m = points.len();
+        let k_star = DMatrix::from_fn(self.x.len(), m, |i, j| {
+            self.kernel.covariance(distance(&self.x[i], &points[j]), &self.params)
+        });
+        let means = (k_star.transpose() * &self.alpha)
+            .iter()
+            .map(|v| self.y_mean + self.y_scale * v)
+            .collect();
+
+        let v = self
+            .chol
+            .l()
+            .solve_lower_triangular(&k_star)
+            .unwrap_or_else(|| DMatrix::zeros(self.x.len(), m));
+        let prior = DMatrix::from_fn(m, m, |i, j| self.kernel.covariance(distance(&points[i], &points[j]), &self.params));
+        let cov = (prior - v.transpose() * v) * (self.y_scale * self.y_scale);
+
+        (means, cov)
+    }
+
+    pub fn hyperparameters(&self) -> GpHyperparameters {
+        self.params
+    }
+
+    pub fn kernel(&self) -> Kernel {
+        self.kernel
+    }
+
+    /// Log marginal likelihood of the standardized targets
+    pub fn log_marginal_likelihood(&self) -> f64 {
+        self.log_marginal_likelihood
+    }
+
+    pub fn n_samples(&self) -> usize {
+        self.x.len()
+    }
+}
+
+/// Minimize `f` with the Nelder-Mead simplex method
+///
+/// Returns (best value, best point).
+fn nelder_mead(f: &dyn Fn(&[f64]) -> f64, start: &[f64], max_evaluations: usize) -> (f64, Vec<f64>) {
+    let n = start.len();
+    let mut simplex: Vec<Vec<f64>> = vec![start.to_vec()];
+    for i in 0..n {
+        let mut p = start.to_vec();
+        p[i] += 0.5;
+        simplex.push(p);
+    }
+    let mut values: Vec<f64> = simplex.iter().map(|p| f(p)).collect();
+    let mut evaluations = n + 1;
+
+    while evaluations < max_evaluations {
+        let mut order: Vec<usize> = (0..=n).collect();
+        order.sort_by(|&a, &b| values[a].partial_cmp(&values[b]).unwrap_or(std::cmp::Ordering::Equal));
+        simplex = order.iter().map(|&i| simplex[i].clone()).collect();
+        values = order.iter().map(|&i| values[i]).collect();
+
+        if (values[n] - values[0]).abs() < 1e-8 {
+            break;
+        }
+
+        let centroid: Vec<f64> = (0..n).map(|d| simplex[..n].iter().map(|p| p[d]).sum::<f64>() / n as f64).collect();
+        let along = |t: f64| -> Vec<f64> {
+            centroid.iter().zip(&simplex[n]).map(|(c, w)| c + t * (c - w)).collect()
+        };
+
+        let reflected = along(1.0);
+        let fr = f(&reflected);
+        evaluations += 1;
+
+        if fr < values[0] {
+            let expanded = along(2.0);
+            let fe = f(&expanded);
+            evaluations += 1;
+            if fe < fr {
+                simplex[n] = expanded;
+                values[n] = fe;
+            } else {
+                simplex[n] = reflected;
+                values[n] = fr;
+            }
+        } else if fr < values[n - 1] {
+            simplex[n] = reflected;
+            values[n] = fr;
+        } else {
+            let contracted = along(if fr < values[n] { 0.5 } else { -0.5 });
+            let fc = f(&contracted);
+            evaluations += 1;
+            if fc < values[n].min(fr) {
+                simplex[n] = contracted;
+                values[n] = fc;
+            } else {
+                // Shrink toward the best vertex
+                for i in 1..=n {
+                    simplex[i] = simplex[0].iter().zip(&simplex[i]).map(|(b, p)| b + 0.5 * (p - b)).collect();
+                    values[i] = f(&simplex[i]);
+                }
+                evaluations += n;
+            }
+        }
+    }
+
+    let best = (0..=n)
+        .min_by(|&a, &b| values[a].partial_cmp(&values[b]).unwrap_or(std::cmp::Ordering::Equal))
+        .unwrap_or(0);
+    (values[best], simplex[best].clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine_data(n: usize) -> (Vec<Vec<f64>>, Vec<f64>) {
+        let x: Vec<Vec<f64>> = (0..n).map(|i| vec![i as f64 / (n - 1) as f64 * 6.0]).collect();
+        let y = x.iter().map(|p| p[0].sin()).collect();
+        (x, y)
+    }
+
+    #[test]
+    fn test_gp_interpolates_with_each_kernel() {
+        let (x, y) = sine_data(12);
+        for kernel in [Kernel::Rbf, Kernel::Matern32, Kernel::Matern52] {
+            let gp = GaussianProcess::fit(&x, &y, &GaussianProcessConfig::default().with_kernel(kernel)).unwrap();
+
+            let (mean, std) = gp.predict(&[2.5]);
+            assert!((mean - 2.5f64.sin()).abs() < 0.1, "{:?}: {}", kernel, mean);
+            assert!(std < 0.2);
+
+            // Uncertainty grows away from the data
+            let (_, far_std) = gp.predict(&[12.0]);
+            assert!(far_std > std * 3.0);
+        }
+    }
+
+    #[test]
+    fn test_marginal_likelihood_optimization() {
+        let (x, y) = sine_data(15);
+        let fixed_config = GaussianProcessConfig::default()
+            .with_initial(GpHyperparameters { length_scale: 0.05, ..Default::default() })
+            .with_optimization(false);
+        let fixed = GaussianProcess::fit(&x, &y, &fixed_config).unwrap();
+        let fitted = GaussianProcess::fit(&x, &y, &fixed_config.clone().with_optimization(true)).unwrap();
+
+        assert!(fitted.log_marginal_likelihood() > fixed.log_marginal_likelihood());
+        assert!(fitted.hyperparameters().length_scale > 0.5);
+    }
+
+    #[test]
+    fn test_joint_prediction_matches_marginals() {
+        let (x, y) = sine_data(8);
+        let gp = GaussianProcess::fit(&x, &y, &GaussianProcessConfig::default()).unwrap();
+
+        let points = vec![vec![1.3], vec![4.1]];
+        let (means, cov) = gp.predict_joint(&points);
+        for (i, p) in points.iter().enumerate() {
+            let (m, s) = gp.predict(p);
+            assert!((means[i] - m).abs() < 1e-9);
+            assert!((cov[(i, i)].max(0.0).sqrt() - s).abs() < 1e-6);
+        }
+        assert!(GaussianProcess::fit(&[], &[], &GaussianProcessConfig::default()).is_err());
+    }
+}
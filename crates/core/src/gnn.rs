@@ -100,12 +100,14 @@ impl MPNNLayer {
         let hidden_dim = config.hidden_dim;
         let edge_dim = config.edge_feature_dim;
 
+        // Zero-mean weights; all-positive ones collapse the ReLU features
+        // onto a single direction
         Self {
             config,
-            node_weights: na::DMatrix::from_fn(hidden_dim, node_dim, |_, _| rand::random::<f64>() * 0.1),
-            edge_weights: na::DMatrix::from_fn(hidden_dim, edge_dim, |_, _| rand::random::<f64>() * 0.1),
-            message_weights: na::DMatrix::from_fn(hidden_dim, hidden_dim * 2, |_, _| rand::random::<f64>() * 0.1),
-            update_weights: na::DMatrix::from_fn(hidden_dim, hidden_dim * 2, |_, _| rand::random::<f64>() * 0.1),
+            node_weights: na::DMatrix::from_fn(hidden_dim, node_dim, |_, _| (rand::random::<f64>() - 0.5) * 0.2),
+            edge_weights: na::DMatrix::from_fn(hidden_dim, edge_dim, |_, _| (rand::random::<f64>() - 0.5) * 0.2),
+            message_weights: na::DMatrix::from_fn(hidden_dim, hidden_dim * 2, |_, _| (rand::random::<f64>() - 0.5) * 0.2),
+            update_weights: na::DMatrix::from_fn(hidden_dim, hidden_dim * 2, |_, _| (rand::random::<f64>() - 0.5) * 0.2),
         }
     }
 
@@ -213,18 +215,30 @@ impl MPNNLayer {
     }
 }
 
+/// Ridge regression head mapping a pooled graph embedding to a property
+struct PropertyHead {
+    weights: na::DVector<f64>,
+    bias: f64,
+}
+
 /// Graph Neural Network Model
 pub struct GNNModel {
     config: GNNConfig,
     layers: Vec<MPNNLayer>,
     readout_weights: na::DMatrix<f64>,
+    head: Option<PropertyHead>,
 }
 
 impl GNNModel {
     pub fn new(config: GNNConfig) -> Self {
+        // Layers after the first see hidden-size node features
         let mut layers = Vec::new();
-        for _ in 0..config.num_layers {
-            layers.push(MPNNLayer::new(config.clone()));
+        for i in 0..config.num_layers {
+            let mut layer_config = config.clone();
+            if i > 0 {
+                layer_config.node_feature_dim = config.hidden_dim;
+            }
+            layers.push(MPNNLayer::new(layer_config));
         }
 
         let readout_weights = na::DMatrix::from_fn(
@@ -237,11 +251,70 @@ impl GNNModel {
             config,
             layers,
             readout_weights,
+            head: None,
         }
     }
 
+    /// Whether a property head has been fitted
+    pub fn is_trained(&self) -> bool {
+        self.head.is_some()
+    }
+
     /// Forward pass through GNN
     pub fn forward(&self, graph: &MolecularGraph) -> na::DVector<f64> {
+        &self.readout_weights * self.pooled(graph)
+    }
+
+    /// Predict the property with the fitted head
+    pub fn predict(&self, graph: &MolecularGraph) -> Result<f64, String> {
+        let head = self.head.as_ref().ok_or("Model is not trained")?;
+        Ok(head.weights.dot(&self.pooled(graph)) + head.bias)
+    }
+
+    /// Fit the property head on pooled embeddings by ridge regression
+    ///
+    /// The message-passing layers stay at their initial weights and act as
+    /// a fixed random feature map; only the linear head is learned. The
+    /// embeddings are standardized per dimension and the ridge system is
+    /// solved in its n×n dual form, with `ridge` relative to the mean
+    /// kernel diagonal.
+    pub fn fit(&mut self, graphs: &[&MolecularGraph], targets: &[f64], ridge: f64) -> Result<(), String> {
+        if graphs.is_empty() || graphs.len() != targets.len() {
+            return Err(format!("{} graphs for {} targets", graphs.len(), targets.len()));
+        }
+        let n = graphs.len();
+        let embeddings = na::DMatrix::from_columns(&graphs.iter().map(|g| self.pooled(g)).collect::<Vec<_>>());
+        let x_mean = embeddings.column_mean();
+        let x_std = embeddings.column_variance().map(|v| if v > 1e-24 { v.sqrt() } else { 1.0 });
+        let y_mean = targets.iter().sum::<f64>() / n as f64;
+
+        // dim × n standardized design, one column per graph
+        let mut z = embeddings;
+        for mut column in z.column_iter_mut() {
+            column -= &x_mean;
+            column.component_div_assign(&x_std);
+        }
+        let y = na::DVector::from_iterator(n, targets.iter().map(|t| t - y_mean));
+
+        let mut kernel = z.transpose() * &z;
+        let penalty = ridge * (kernel.trace() / n as f64).max(1e-12);
+        for i in 0..n {
+            kernel[(i, i)] += penalty;
+        }
+        let alpha = kernel.cholesky()
+            .ok_or("Ridge system is not positive definite")?
+            .solve(&y);
+        let weights = (z * alpha).component_div(&x_std);
+
+        self.head = Some(PropertyHead {
+            bias: y_mean - weights.dot(&x_mean),
+            weights,
+        });
+        Ok(())
+    }
+
+    /// Mean-pooled hidden node features after message passing
+    fn pooled(&self, graph: &MolecularGraph) -> na::DVector<f64> {
         // Initialize node features
         let mut node_features = HashMap::new();
         for node in &graph.nodes {
@@ -262,25 +335,15 @@ impl GNNModel {
             node_features = layer.update_nodes(&node_features, &messages);
         }
 
-        // Readout: aggregate node features to graph-level representation
-        self.readout(&node_features)
-    }
-
-    /// Readout layer: aggregate node features
-    fn readout(&self, node_features: &HashMap<usize, na::DVector<f64>>) -> na::DVector<f64> {
+        // Readout: mean pooling to a graph-level representation
         if node_features.is_empty() {
-            return na::DVector::zeros(self.config.output_dim);
+            return na::DVector::zeros(self.config.hidden_dim);
         }
-
-        // Mean pooling
         let mut sum = na::DVector::zeros(self.config.hidden_dim);
         for features in node_features.values() {
             sum += features;
         }
-        let mean = sum / node_features.len() as f64;
-
-        // Linear transformation to output dimension
-        &self.readout_weights * &mean
+        sum / node_features.len() as f64
     }
 }
 
@@ -337,6 +400,59 @@ impl GNNEngine {
         Ok(graph)
     }
 
+    /// Create a graph from a composition when no structure is known
+    ///
+    /// Atoms sit at the origin; every pair of distinct elements is bonded
+    /// at the sum of their radii, ionic when the electronegativity gap
+    /// exceeds 1.7. Edge features are a Gaussian expansion of the distance.
+    pub async fn create_composition_graph(
+        &self,
+        material_id: Uuid,
+        formula: &str,
+        composition: &HashMap<String, usize>,
+    ) -> Result<MolecularGraph, String> {
+        let mut elements: Vec<(&String, &usize)> = composition.iter().filter(|(_, &n)| n > 0).collect();
+        elements.sort();
+        if elements.is_empty() {
+            return Err(format!("Empty composition for {}", formula));
+        }
+
+        let mut atoms = Vec::new();
+        for (element, &count) in &elements {
+            for _ in 0..count {
+                atoms.push(create_atom_node(atoms.len(), element, [0.0, 0.0, 0.0]));
+            }
+        }
+
+        let edge_dim = self.config.edge_feature_dim;
+        let mut bonds = Vec::new();
+        for i in 0..atoms.len() {
+            for j in (i + 1)..atoms.len() {
+                let (a, b) = (&atoms[i].element, &atoms[j].element);
+                if a == b && elements.len() > 1 {
+                    continue;
+                }
+                let distance = get_atomic_radius(a) + get_atomic_radius(b);
+                let bond_type = if (get_electronegativity(a) - get_electronegativity(b)).abs() > 1.7 {
+                    BondType::Ionic
+                } else if a == b {
+                    BondType::Metallic
+                } else {
+                    BondType::Covalent
+                };
+                let features: Vec<f64> = (0..edge_dim)
+                    .map(|k| {
+                        let center = 4.0 * k as f64 / edge_dim.max(1) as f64;
+                        (-(distance - center).powi(2) / 0.1).exp()
+                    })
+                    .collect();
+                bonds.push(BondEdge { from_atom: i, to_atom: j, bond_type, distance, features });
+            }
+        }
+
+        self.create_graph(material_id, formula, atoms, bonds).await
+    }
+
     /// Predict property using GNN
     pub async fn predict_property(
         &self,
@@ -351,29 +467,43 @@ impl GNNEngine {
         let model = models.get(property_name)
             .ok_or("Model not found")?;
 
-        let embedding = model.forward(graph);
-
-        // Simple prediction: sum of embedding (in practice would use separate predictor)
-        Ok(embedding.sum() / embedding.len() as f64)
+        model.predict(graph)
     }
 
-    /// Train GNN model (simplified - gradient descent not implemented)
+    /// Train a property model on (graph id, value) pairs
+    ///
+    /// Every id must have a graph in the engine. Fits a ridge readout head
+    /// on the pooled embeddings (backpropagation through the message-passing
+    /// layers is not implemented).
     pub async fn train_model(
         &self,
         property_name: String,
         training_data: Vec<(Uuid, f64)>,
     ) -> Result<(), String> {
-        // Create new model
-        let model = GNNModel::new(self.config.clone());
+        if training_data.is_empty() {
+            return Err(format!("No training data for property: {}", property_name));
+        }
 
-        // In production: implement backpropagation and gradient descent
-        // For now, just store the initialized model
+        let mut model = GNNModel::new(self.config.clone());
+        {
+            let graphs = self.graphs.read().await;
+            let training_graphs = training_data.iter()
+                .map(|(id, _)| graphs.get(id).ok_or_else(|| format!("Graph not found for {}", id)))
+                .collect::<Result<Vec<_>, String>>()?;
+            let targets: Vec<f64> = training_data.iter().map(|(_, y)| *y).collect();
+            model.fit(&training_graphs, &targets, 1e-8)?;
+        }
 
         self.models.write().await.insert(property_name, model);
 
         Ok(())
     }
 
+    /// Drop a graph once it is no longer needed
+    pub async fn remove_graph(&self, material_id: Uuid) -> Option<MolecularGraph> {
+        self.graphs.write().await.remove(&material_id)
+    }
+
     /// Get graph embedding
     pub async fn get_embedding(&self, material_id: Uuid) -> Result<Vec<f64>, String> {
         let graphs = self.graphs.read().await;
@@ -567,4 +697,33 @@ mod tests {
 
         assert_eq!(embedding.len(), 64);  // output_dim from default config
     }
+
+    #[tokio::test]
+    async fn test_gnn_training() {
+        let engine = GNNEngine::new();
+        let mut training = Vec::new();
+        for (formula, composition, value) in [
+            ("FeO", vec![("Fe", 1), ("O", 1)], 180.0),
+            ("Fe2O3", vec![("Fe", 2), ("O", 3)], 200.0),
+            ("SiO2", vec![("Si", 1), ("O", 2)], 37.0),
+            ("NaCl", vec![("Na", 1), ("Cl", 1)], 24.0),
+        ] {
+            let id = Uuid::new_v4();
+            let composition = composition.into_iter().map(|(e, n)| (e.to_string(), n)).collect();
+            engine.create_composition_graph(id, formula, &composition).await.unwrap();
+            training.push((id, value));
+        }
+
+        assert!(engine.train_model("bulk_modulus".to_string(), vec![]).await.is_err());
+        engine.train_model("bulk_modulus".to_string(), training.clone()).await.unwrap();
+
+        // The head reproduces its training targets
+        for (id, value) in &training {
+            let predicted = engine.predict_property(*id, "bulk_modulus").await.unwrap();
+            assert!((predicted - value).abs() < 1.0, "{} vs {}", predicted, value);
+        }
+
+        assert!(engine.remove_graph(training[0].0).await.is_some());
+        assert!(engine.predict_property(training[0].0, "bulk_modulus").await.is_err());
+    }
 }
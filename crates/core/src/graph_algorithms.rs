@@ -0,0 +1,645 @@
+//! Graph algorithms over sparse weighted graphs
+//!
+//! Used by `KnowledgeGraph`, which maps material ids to the dense node
+//! indices used here. Graphs are undirected; parallel edges are kept and
+//! add up for communities and PageRank, while paths use the cheapest one.
+//!
+//! - Communities: Louvain modularity optimization, optionally with the
+//!   Leiden connectivity refinement
+//! - Centrality: weighted PageRank and Brandes betweenness
+//! - Paths: Dijkstra and Yen's k shortest loopless paths
+//! - Candidate pairs: MinHash banding over sets and log-scale bins over
+//!   numeric properties, with oversized blocks capped and the skipped
+//!   pairs counted
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// Undirected weighted graph as adjacency lists of (neighbor, weight)
+#[derive(Debug, Clone, Default)]
+pub struct SparseGraph {
+    adjacency: Vec<Vec<(usize, f64)>>,
+}
+
+impl SparseGraph {
+    /// Graph on `n` nodes; self-loops in `edges` are ignored
+    pub fn from_edges(n: usize, edges: impl IntoIterator<Item = (usize, usize, f64)>) -> Self {
+        let mut adjacency = vec![Vec::new(); n];
+        for (a, b, w) in edges {
+            if a != b && a < n && b < n {
+                adjacency[a].push((b, w));
+                adjacency[b].push((a, w));
+            }
+        }
+        Self { adjacency }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    /// Undirected edges, counting parallel edges
+    pub fn edge_count(&self) -> usize {
+        self.adjacency.iter().map(Vec::len).sum::<usize>() / 2
+    }
+
+    pub fn neighbors(&self, node: usize) -> &[(usize, f64)] {
+        &self.adjacency[node]
+    }
+
+    /// Sum of incident edge weights
+    pub fn degree(&self, node: usize) -> f64 {
+        self.adjacency[node].iter().map(|(_, w)| w).sum()
+    }
+}
+
+// ============================================================================
+// COMMUNITIES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CommunityAlgorithm {
+    Louvain,
+    /// Louvain plus Leiden's refinement: before each aggregation communities
+    /// are split into their connected parts, so none ends up disconnected
+    Leiden,
+}
+
+/// Weighted graph with explicit self-loops, as produced by aggregation.
+/// `loops[i]` is the weight of edges inside super-node `i`, counted twice.
+struct LevelGraph {
+    adjacency: Vec<Vec<(usize, f64)>>,
+    loops: Vec<f64>,
+}
+
+impl LevelGraph {
+    fn degree(&self, node: usize) -> f64 {
+        self.loops[node] + self.adjacency[node].iter().map(|(_, w)| w).sum::<f64>()
+    }
+}
+
+/// Community of each node, numbered from 0 by first appearance
+pub fn communities(graph: &SparseGraph, algorithm: CommunityAlgorithm, resolution: f64, seed: u64) -> Vec<usize> {
+    let n = graph.node_count();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut membership: Vec<usize> = (0..n).collect();
+    let mut level = LevelGraph { adjacency: graph.adjacency.clone(), loops: vec![0.0; n] };
+
+    loop {
+        let mut partition = local_moving(&level, resolution, &mut rng);
+        if algorithm == CommunityAlgorithm::Leiden {
+            partition = split_disconnected(&level, &partition);
+        }
+        let count = renumber(&mut partition);
+        if count == level.adjacency.len() {
+            break;
+        }
+        for m in &mut membership {
+            *m = partition[*m];
+        }
+        level = aggregate(&level, &partition, count);
+    }
+    renumber(&mut membership);
+    membership
+}
+
+/// Louvain phase one: move single nodes to the neighboring community with
+/// the best modularity gain until no move helps
+fn local_moving(graph: &LevelGraph, resolution: f64, rng: &mut StdRng) -> Vec<usize> {
+    let n = graph.adjacency.len();
+    let degrees: Vec<f64> = (0..n).map(|i| graph.degree(i)).collect();
+    let total: f64 = degrees.iter().sum();
+    let mut community: Vec<usize> = (0..n).collect();
+    if total <= 0.0 {
+        return community;
+    }
+    let mut community_degree = degrees.clone();
+    let mut order: Vec<usize> = (0..n).collect();
+
+    let mut improved = true;
+    while improved {
+        improved = false;
+        order.shuffle(rng);
+        for &node in &order {
+            let current = community[node];
+            let mut links: HashMap<usize, f64> = HashMap::new();
+            for &(neighbor, w) in &graph.adjacency[node] {
+                *links.entry(community[neighbor]).or_insert(0.0) += w;
+            }
+            community_degree[current] -= degrees[node];
+
+            let gain = |c: usize, links_to: f64| links_to - resolution * community_degree[c] * degrees[node] / total;
+            let mut best = (current, gain(current, links.get(&current).copied().unwrap_or(0.0)));
+            // Sorted candidates keep runs reproducible for a seed
+            let mut candidates: Vec<(usize, f64)> = links.into_iter().collect();
+            candidates.sort_by_key(|(c, _)| *c);
+            for (c, links_to) in candidates {
+                let g = gain(c, links_to);
+                if g > best.1 + 1e-12 {
+                    best = (c, g);
+                }
+            }
+
+            community_degree[best.0] += degrees[node];
+            if best.0 != current {
+                community[node] = best.0;
+                improved = true;
+            }
+        }
+    }
+    community
+}
+
+/// Give each connected part of every community its own label
+fn split_disconnected(graph: &LevelGraph, partition: &[usize]) -> Vec<usize> {
+    let n = partition.len();
+    let mut refined = vec![usize::MAX; n];
+    let mut next = 0;
+    for start in 0..n {
+        if refined[start] != usize::MAX {
+            continue;
+        }
+        refined[start] = next;
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            for &(neighbor, w) in &graph.adjacency[node] {
+                if w > 0.0 && refined[neighbor] == usize::MAX && partition[neighbor] == partition[start] {
+                    refined[neighbor] = next;
+                    stack.push(neighbor);
+                }
+            }
+        }
+        next += 1;
+    }
+    refined
+}
+
+/// Relabel to 0..count by first appearance; returns count
+fn renumber(labels: &mut [usize]) -> usize {
+    let mut map = HashMap::new();
+    for label in labels.iter_mut() {
+        let next = map.len();
+        *label = *map.entry(*label).or_insert(next);
+    }
+    map.len()
+}
+
+fn aggregate(graph: &LevelGraph, partition: &[usize], count: usize) -> LevelGraph {
+    let mut loops = vec![0.0; count];
+    let mut weights: Vec<HashMap<usize, f64>> = vec![HashMap::new(); count];
+    for (node, edges) in graph.adjacency.iter().enumerate() {
+        let c = partition[node];
+        loops[c] += graph.loops[node];
+        for &(neighbor, w) in edges {
+            let d = partition[neighbor];
+            if c == d {
+                loops[c] += w;
+            } else {
+                *weights[c].entry(d).or_insert(0.0) += w;
+            }
+        }
+    }
+    let adjacency = weights.into_iter()
+        .map(|m| {
+            let mut edges: Vec<(usize, f64)> = m.into_iter().collect();
+            edges.sort_by_key(|(d, _)| *d);
+            edges
+        })
+        .collect();
+    LevelGraph { adjacency, loops }
+}
+
+/// Newman modularity of a labelling
+pub fn modularity(graph: &SparseGraph, labels: &[usize], resolution: f64) -> f64 {
+    let total: f64 = (0..graph.node_count()).map(|i| graph.degree(i)).sum();
+    if total <= 0.0 {
+        return 0.0;
+    }
+    let mut internal: HashMap<usize, f64> = HashMap::new();
+    let mut degree: HashMap<usize, f64> = HashMap::new();
+    for node in 0..graph.node_count() {
+        *degree.entry(labels[node]).or_insert(0.0) += graph.degree(node);
+        for &(neighbor, w) in graph.neighbors(node) {
+            if labels[neighbor] == labels[node] {
+                *internal.entry(labels[node]).or_insert(0.0) += w;
+            }
+        }
+    }
+    degree.iter()
+        .map(|(c, d)| internal.get(c).copied().unwrap_or(0.0) / total - resolution * (d / total).powi(2))
+        .sum()
+}
+
+// ============================================================================
+// CENTRALITY
+// ============================================================================
+
+/// Weighted PageRank; mass of isolated nodes is spread uniformly
+pub fn pagerank(graph: &SparseGraph, damping: f64, tolerance: f64, max_iterations: usize) -> Vec<f64> {
+    let n = graph.node_count();
+    if n == 0 {
+        return Vec::new();
+    }
+    let degrees: Vec<f64> = (0..n).map(|i| graph.degree(i)).collect();
+    let mut rank = vec![1.0 / n as f64; n];
+    for _ in 0..max_iterations {
+        let dangling: f64 = (0..n).filter(|&i| degrees[i] <= 0.0).map(|i| rank[i]).sum();
+        let base = (1.0 - damping) / n as f64 + damping * dangling / n as f64;
+        let mut next = vec![base; n];
+        for (i, edges) in graph.adjacency.iter().enumerate() {
+            if degrees[i] > 0.0 {
+                let share = damping * rank[i] / degrees[i];
+                for &(j, w) in edges {
+                    next[j] += share * w;
+                }
+            }
+        }
+        let change: f64 = next.iter().zip(&rank).map(|(a, b)| (a - b).abs()).sum();
+        rank = next;
+        if change < tolerance {
+            break;
+        }
+    }
+    rank
+}
+
+/// How an edge weight turns into a path length
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PathCost {
+    /// The weight itself
+    Weight,
+    /// 1 / weight, so strong relationships make short paths
+    InverseWeight,
+    /// Every edge costs 1
+    Hops,
+}
+
+impl PathCost {
+    /// `None` for edges that cannot be traversed (non-positive weights)
+    fn cost(self, weight: f64) -> Option<f64> {
+        match self {
+            PathCost::Hops => Some(1.0),
+            _ if weight.is_nan() || weight <= 0.0 => None,
+            PathCost::Weight => Some(weight),
+            PathCost::InverseWeight => Some(1.0 / weight),
+        }
+    }
+}
+
+/// Brandes betweenness, normalized by the (n-1)(n-2)/2 pairs of an undirected graph
+pub fn betweenness(graph: &SparseGraph, cost: PathCost) -> Vec<f64> {
+    let n = graph.node_count();
+    let mut centrality = vec![0.0; n];
+    for source in 0..n {
+        let mut order = Vec::with_capacity(n);
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let mut paths = vec![0.0f64; n];
+        let mut distance = vec![f64::INFINITY; n];
+        paths[source] = 1.0;
+        distance[source] = 0.0;
+
+        let mut heap = BinaryHeap::from([Frontier { cost: 0.0, node: source }]);
+        let mut settled = vec![false; n];
+        while let Some(Frontier { cost: d, node }) = heap.pop() {
+            if settled[node] {
+                continue;
+            }
+            settled[node] = true;
+            order.push(node);
+            for &(next, w) in graph.neighbors(node) {
+                let Some(c) = cost.cost(w) else { continue };
+                let candidate = d + c;
+                if candidate < distance[next] - 1e-12 {
+                    distance[next] = candidate;
+                    paths[next] = paths[node];
+                    predecessors[next] = vec![node];
+                    heap.push(Frontier { cost: candidate, node: next });
+                } else if (candidate - distance[next]).abs() <= 1e-12 && !settled[next] {
+                    paths[next] += paths[node];
+                    predecessors[next].push(node);
+                }
+            }
+        }
+
+        let mut dependency = vec![0.0; n];
+        for &node in order.iter().rev() {
+            for &p in &predecessors[node] {
+                dependency[p] += paths[p] / paths[node] * (1.0 + dependency[node]);
+            }
+            if node != source {
+                centrality[node] += dependency[node];
+            }
+        }
+    }
+    // Each pair was counted from both ends
+    let pairs = if n > 2 { ((n - 1) * (n - 2)) as f64 } else { 1.0 };
+    centrality.iter().map(|c| c / pairs).collect()
+}
+
+// ============================================================================
+// PATHS
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Frontier {
+    cost: f64,
+    node: usize,
+}
+
+impl Eq for Frontier {}
+
+impl Ord for Frontier {
+    // Reversed so the max-heap pops the cheapest entry
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost).then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Cheapest path from `source` to `target` and its cost
+pub fn dijkstra(graph: &SparseGraph, source: usize, target: usize, cost: PathCost) -> Option<(Vec<usize>, f64)> {
+    dijkstra_avoiding(graph, source, target, cost, &HashSet::new(), &HashSet::new())
+}
+
+fn dijkstra_avoiding(
+    graph: &SparseGraph,
+    source: usize,
+    target: usize,
+    cost: PathCost,
+    banned_nodes: &HashSet<usize>,
+    banned_edges: &HashSet<(usize, usize)>,
+) -> Option<(Vec<usize>, f64)> {
+    let n = graph.node_count();
+    if source >= n || target >= n || banned_nodes.contains(&source) {
+        return None;
+    }
+    let mut distance = vec![f64::INFINITY; n];
+    let mut previous = vec![usize::MAX; n];
+    distance[source] = 0.0;
+    let mut heap = BinaryHeap::from([Frontier { cost: 0.0, node: source }]);
+
+    while let Some(Frontier { cost: d, node }) = heap.pop() {
+        if node == target {
+            let mut path = vec![target];
+            while *path.last().expect("path is non-empty") != source {
+                path.push(previous[*path.last().expect("path is non-empty")]);
+            }
+            path.reverse();
+            return Some((path, d));
+        }
+        if d > distance[node] {
+            continue;
+        }
+        for &(next, w) in graph.neighbors(node) {
+            if banned_nodes.contains(&next) || banned_edges.contains(&(node, next)) {
+                continue;
+            }
+            let Some(c) = cost.cost(w) else { continue };
+            if d + c < distance[next] {
+                distance[next] = d + c;
+                previous[next] = node;
+                heap.push(Frontier { cost: d + c, node: next });
+            }
+        }
+    }
+    None
+}
+
+fn path_cost(graph: &SparseGraph, path: &[usize], cost: PathCost) -> f64 {
+    path.windows(2)
+        .map(|w| {
+            graph.neighbors(w[0]).iter()
+                .filter(|(n, _)| *n == w[1])
+                .filter_map(|(_, weight)| cost.cost(*weight))
+                .fold(f64::INFINITY, f64::min)
+        })
+        .sum()
+}
+
+/// Yen's algorithm: up to `k` loopless paths in order of cost
+pub fn k_shortest_paths(
+    graph: &SparseGraph,
+    source: usize,
+    target: usize,
+    k: usize,
+    cost: PathCost,
+) -> Vec<(Vec<usize>, f64)> {
+    let Some(first) = dijkstra(graph, source, target, cost) else { return Vec::new() };
+    let mut accepted = vec![first];
+    let mut candidates: Vec<(Vec<usize>, f64)> = Vec::new();
+
+    while accepted.len() < k {
+        let last = accepted.last().expect("at least one path").0.clone();
+        for spur in 0..last.len() - 1 {
+            let root = &last[..=spur];
+            // Block the next edge of every accepted path sharing this root
+            let mut banned_edges = HashSet::new();
+            for (path, _) in &accepted {
+                if path.len() > spur + 1 && path[..=spur] == *root {
+                    banned_edges.insert((path[spur], path[spur + 1]));
+                    banned_edges.insert((path[spur + 1], path[spur]));
+                }
+            }
+            let banned_nodes: HashSet<usize> = root[..spur].iter().copied().collect();
+
+            if let Some((tail, _)) = dijkstra_avoiding(graph, last[spur], target, cost, &banned_nodes, &banned_edges) {
+                let mut path = root[..spur].to_vec();
+                path.extend(tail);
+                if !candidates.iter().any(|(p, _)| *p == path) && !accepted.iter().any(|(p, _)| *p == path) {
+                    let c = path_cost(graph, &path, cost);
+                    candidates.push((path, c));
+                }
+            }
+        }
+        if candidates.is_empty() {
+            break;
+        }
+        let best = candidates.iter().enumerate()
+            .min_by(|a, b| a.1 .1.total_cmp(&b.1 .1).then_with(|| a.1 .0.len().cmp(&b.1 .0.len())))
+            .map(|(i, _)| i)
+            .expect("candidates is non-empty");
+        accepted.push(candidates.swap_remove(best));
+    }
+    accepted
+}
+
+// ============================================================================
+// CANDIDATE GENERATION
+// ============================================================================
+
+/// Candidate pairs from blocking, with the work the block cap skipped
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CandidatePairs {
+    /// (i, j) with i < j
+    pub pairs: HashSet<(usize, usize)>,
+    /// Pairs inside oversized blocks that were not generated, counted per
+    /// block; some may still be candidates through another block
+    pub dropped: usize,
+}
+
+/// Pairs of items whose MinHash signatures over `sets` agree on at least
+/// one band of `rows` hashes
+///
+/// Two sets with Jaccard similarity s become a candidate with probability
+/// 1 - (1 - s^rows)^bands.
+pub fn minhash_candidates<T: Hash>(
+    sets: &[Vec<T>],
+    bands: usize,
+    rows: usize,
+    max_bucket: usize,
+    seed: u64,
+) -> CandidatePairs {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let salts: Vec<u64> = (0..bands * rows).map(|_| rng.gen()).collect();
+    let blocks: Vec<Vec<(usize, u64)>> = sets.iter()
+        .map(|set| {
+            let signature: Vec<u64> = salts.iter()
+                .map(|&salt| set.iter().map(|item| hash_with(item, salt)).min().unwrap_or(u64::MAX))
+                .collect();
+            if set.is_empty() {
+                return Vec::new();
+            }
+            (0..bands).map(|b| (b, hash_with(&signature[b * rows..(b + 1) * rows], 0))).collect()
+        })
+        .collect();
+    blocked_candidates(&blocks, max_bucket)
+}
+
+/// Blocks for numeric properties: each value lands in a log-scale bin on
+/// two grids offset by half a bin, so values within a factor of
+/// exp(`bin_width` / 2) of each other always share a block
+pub fn log_bin_blocks(properties: &[(&str, f64)], bin_width: f64) -> Vec<(String, i64, bool)> {
+    let mut blocks = Vec::new();
+    for &(key, value) in properties {
+        if value == 0.0 || !value.is_finite() {
+            continue;
+        }
+        let scaled = value.abs().ln() / bin_width;
+        let sign = value > 0.0;
+        blocks.push((format!("{key}/{}", sign as u8), scaled.floor() as i64, false));
+        blocks.push((format!("{key}/{}", sign as u8), (scaled + 0.5).floor() as i64, true));
+    }
+    blocks
+}
+
+/// Pairs of items sharing at least one block key
+///
+/// Blocks larger than `max_bucket` only pair each member with its next
+/// `max_bucket` members, bounding the work for very common keys; the pairs
+/// left out are counted in `dropped`.
+pub fn blocked_candidates<K: Hash + Eq>(blocks: &[Vec<K>], max_bucket: usize) -> CandidatePairs {
+    let mut buckets: HashMap<&K, Vec<usize>> = HashMap::new();
+    for (i, keys) in blocks.iter().enumerate() {
+        for key in keys {
+            buckets.entry(key).or_default().push(i);
+        }
+    }
+    let window = max_bucket.max(1);
+    let mut pairs = HashSet::new();
+    let mut dropped = 0;
+    for members in buckets.values() {
+        for (a, &i) in members.iter().enumerate() {
+            let later = members.len() - a - 1;
+            dropped += later.saturating_sub(window);
+            for &j in members.iter().skip(a + 1).take(window) {
+                if i != j {
+                    pairs.insert((i.min(j), i.max(j)));
+                }
+            }
+        }
+    }
+    CandidatePairs { pairs, dropped }
+}
+
+fn hash_with<T: Hash + ?Sized>(item: &T, salt: u64) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    salt.hash(&mut hasher);
+    item.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two 5-cliques joined by one weak bridge 4 - 5
+    fn barbell() -> SparseGraph {
+        let mut edges = Vec::new();
+        for offset in [0, 5] {
+            for i in 0..5 {
+                for j in i + 1..5 {
+                    edges.push((offset + i, offset + j, 1.0));
+                }
+            }
+        }
+        edges.push((4, 5, 0.1));
+        SparseGraph::from_edges(10, edges)
+    }
+
+    #[test]
+    fn test_communities_and_centrality() {
+        let graph = barbell();
+        for algorithm in [CommunityAlgorithm::Louvain, CommunityAlgorithm::Leiden] {
+            let labels = communities(&graph, algorithm, 1.0, 3);
+            assert!(labels[..5].iter().all(|&l| l == labels[0]));
+            assert!(labels[5..].iter().all(|&l| l == labels[5]));
+            assert_ne!(labels[0], labels[5]);
+            assert!(modularity(&graph, &labels, 1.0) > 0.4);
+        }
+
+        let ranks = pagerank(&graph, 0.85, 1e-12, 200);
+        assert!((ranks.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(ranks[4] > ranks[0]);
+
+        // Bridge ends carry every cross-clique path
+        let between = betweenness(&graph, PathCost::Hops);
+        assert!((between[4] - 20.0 / 36.0).abs() < 1e-9);
+        assert_eq!(between[0], 0.0);
+        assert!((between[4] - between[5]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_weighted_and_k_shortest_paths() {
+        // 0 -1- 1 -1- 3 and 0 -5- 2 -0.5- 3 and a direct 0 -10- 3
+        let graph = SparseGraph::from_edges(4, [(0, 1, 1.0), (1, 3, 1.0), (0, 2, 5.0), (2, 3, 0.5), (0, 3, 10.0)]);
+        assert_eq!(dijkstra(&graph, 0, 3, PathCost::Weight), Some((vec![0, 1, 3], 2.0)));
+        assert_eq!(dijkstra(&graph, 0, 3, PathCost::Hops), Some((vec![0, 3], 1.0)));
+        assert_eq!(dijkstra(&graph, 0, 3, PathCost::InverseWeight).unwrap().0, vec![0, 3]);
+
+        let paths = k_shortest_paths(&graph, 0, 3, 5, PathCost::Weight);
+        assert_eq!(paths, vec![(vec![0, 1, 3], 2.0), (vec![0, 2, 3], 5.5), (vec![0, 3], 10.0)]);
+        assert!(dijkstra(&SparseGraph::from_edges(3, [(0, 1, 1.0)]), 0, 2, PathCost::Weight).is_none());
+    }
+
+    #[test]
+    fn test_candidate_generation() {
+        let sets: Vec<Vec<&str>> = vec![vec!["Fe", "O"], vec!["Fe", "O"], vec!["Li", "Co", "O"], vec!["Na", "Cl"]];
+        let found = minhash_candidates(&sets, 16, 4, 100, 1);
+        assert!(found.pairs.contains(&(0, 1)));
+        assert!(!found.pairs.contains(&(0, 3)));
+        assert_eq!(found.dropped, 0);
+
+        let blocks: Vec<_> = [[("band_gap", 1.0)], [("band_gap", 1.15)], [("band_gap", 3.0)]]
+            .iter()
+            .map(|p| log_bin_blocks(p, 0.4))
+            .collect();
+        let found = blocked_candidates(&blocks, 100);
+        assert!(found.pairs.contains(&(0, 1)));
+        assert!(!found.pairs.contains(&(0, 2)));
+
+        // One block of 10 capped at 3 partners each: 45 pairs, 24 generated
+        let crowded: Vec<Vec<u8>> = vec![vec![7]; 10];
+        let found = blocked_candidates(&crowded, 3);
+        assert_eq!((found.pairs.len(), found.dropped), (24, 21));
+    }
+}
@@ -0,0 +1,773 @@
+//! Hierarchical Navigable Small World (HNSW) index
+//!
+//! Approximate nearest-neighbor search over cosine distance, after
+//! Malkov & Yashunin (2018). Vectors are normalized and stored as `f32`.
+//!
+//! - Inserts are incremental, with neighbors picked by the diversity heuristic.
+//! - Deletes are tombstones: deleted nodes still route searches but are never
+//!   returned. Deleting never rebuilds; once tombstones outnumber a quarter of
+//!   the live nodes [`HnswIndex::needs_rebuild`] reports it and the owner
+//!   calls [`HnswIndex::rebuild`] when convenient.
+//! - Searches can be restricted by [`IndexMetadata`] (elements, source).
+//!   Non-matching nodes are traversed but not returned.
+//! - [`HnswIndex::save`] / [`HnswIndex::load`] use a compact little-endian
+//!   binary format.
+
+use crate::{Error, Result};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::path::Path;
+use std::time::Instant;
+use uuid::Uuid;
+
+const MAGIC: &[u8; 4] = b"HNSW";
+const FORMAT_VERSION: u32 = 1;
+
+/// Tombstones tolerated before a rebuild, below the quarter-of-live rule
+const MIN_TOMBSTONES_FOR_REBUILD: usize = 64;
+
+/// Graph construction and search parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HnswConfig {
+    /// Links per node on upper layers; layer 0 allows `2 * m`
+    pub m: usize,
+    /// Candidate list size while inserting
+    pub ef_construction: usize,
+    /// Default candidate list size while searching
+    pub ef_search: usize,
+    /// Seed for level assignment
+    pub seed: u64,
+}
+
+impl Default for HnswConfig {
+    fn default() -> Self {
+        Self { m: 16, ef_construction: 200, ef_search: 64, seed: 42 }
+    }
+}
+
+impl HnswConfig {
+    pub fn with_m(mut self, m: usize) -> Self {
+        self.m = m.max(2);
+        self
+    }
+
+    pub fn with_ef_construction(mut self, ef: usize) -> Self {
+        self.ef_construction = ef.max(1);
+        self
+    }
+
+    pub fn with_ef_search(mut self, ef: usize) -> Self {
+        self.ef_search = ef.max(1);
+        self
+    }
+
+    fn max_neighbors(&self, level: usize) -> usize {
+        if level == 0 { 2 * self.m } else { self.m }
+    }
+}
+
+/// Attributes a search can be restricted by
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IndexMetadata {
+    pub formula: Option<String>,
+    /// Element symbols, sorted
+    pub elements: Vec<String>,
+    /// Originating database (e.g. "materials_project")
+    pub source: Option<String>,
+}
+
+/// Restriction on [`IndexMetadata`]; an empty filter matches everything
+#[derive(Debug, Clone, Default)]
+pub struct MetadataFilter {
+    /// Every one of these elements must be present
+    pub require_elements: Vec<String>,
+    /// None of these elements may be present
+    pub exclude_elements: Vec<String>,
+    /// All elements must come from this set (a chemical system)
+    pub within_elements: Option<Vec<String>>,
+    /// Source must be one of these, if non-empty
+    pub sources: Vec<String>,
+}
+
+impl MetadataFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_elements(mut self, elements: &[&str]) -> Self {
+        self.require_elements.extend(elements.iter().map(|e| e.to_string()));
+        self
+    }
+
+    pub fn without_elements(mut self, elements: &[&str]) -> Self {
+        self.exclude_elements.extend(elements.iter().map(|e| e.to_string()));
+        self
+    }
+
+    pub fn within_elements(mut self, elements: &[&str]) -> Self {
+        self.within_elements = Some(elements.iter().map(|e| e.to_string()).collect());
+        self
+    }
+
+    pub fn from_sources(mut self, sources: &[&str]) -> Self {
+        self.sources.extend(sources.iter().map(|s| s.to_string()));
+        self
+    }
+
+    pub fn matches(&self, metadata: &IndexMetadata) -> bool {
+        let has = |e: &String| metadata.elements.contains(e);
+        self.require_elements.iter().all(has)
+            && !self.exclude_elements.iter().any(has)
+            && self.within_elements.as_ref().map_or(true, |set| metadata.elements.iter().all(|e| set.contains(e)))
+            && (self.sources.is_empty() || metadata.source.as_ref().is_some_and(|s| self.sources.contains(s)))
+    }
+}
+
+/// A search hit
+#[derive(Debug, Clone, PartialEq)]
+pub struct Neighbor {
+    pub id: Uuid,
+    /// Cosine distance, 1 - cosine similarity
+    pub distance: f64,
+}
+
+/// Recall of approximate search against the exact scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecallReport {
+    pub queries: usize,
+    pub k: usize,
+    pub ef: usize,
+    /// Mean fraction of the exact top-k found
+    pub recall: f64,
+    pub mean_hnsw_micros: f64,
+    pub mean_exact_micros: f64,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    id: Uuid,
+    vector: Vec<f32>,
+    /// Links per layer, from 0 up to the node's level
+    neighbors: Vec<Vec<u32>>,
+    metadata: IndexMetadata,
+    deleted: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Scored {
+    distance: f32,
+    node: u32,
+}
+
+impl Eq for Scored {}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance.total_cmp(&other.distance).then(self.node.cmp(&other.node))
+    }
+}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// HNSW index keyed by material id
+#[derive(Debug, Clone)]
+pub struct HnswIndex {
+    config: HnswConfig,
+    dimension: Option<usize>,
+    nodes: Vec<Node>,
+    slots: HashMap<Uuid, u32>,
+    entry: Option<u32>,
+    max_level: usize,
+    tombstones: usize,
+    rng: StdRng,
+}
+
+impl HnswIndex {
+    pub fn new(config: HnswConfig) -> Self {
+        let rng = StdRng::seed_from_u64(config.seed);
+        Self {
+            config,
+            dimension: None,
+            nodes: Vec::new(),
+            slots: HashMap::new(),
+            entry: None,
+            max_level: 0,
+            tombstones: 0,
+            rng,
+        }
+    }
+
+    pub fn config(&self) -> &HnswConfig {
+        &self.config
+    }
+
+    /// Live (non-deleted) vectors
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub fn contains(&self, id: Uuid) -> bool {
+        self.slots.contains_key(&id)
+    }
+
+    pub fn dimension(&self) -> Option<usize> {
+        self.dimension
+    }
+
+    /// Deleted nodes still in the graph
+    pub fn tombstones(&self) -> usize {
+        self.tombstones
+    }
+
+    /// Stored (normalized) vector of a material
+    pub fn vector(&self, id: Uuid) -> Option<Vec<f64>> {
+        self.slots.get(&id).map(|&slot| self.nodes[slot as usize].vector.iter().map(|&x| x as f64).collect())
+    }
+
+    pub fn metadata(&self, id: Uuid) -> Option<&IndexMetadata> {
+        self.slots.get(&id).map(|&slot| &self.nodes[slot as usize].metadata)
+    }
+
+    pub fn set_metadata(&mut self, id: Uuid, metadata: IndexMetadata) -> bool {
+        match self.slots.get(&id) {
+            Some(&slot) => {
+                self.nodes[slot as usize].metadata = metadata;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Insert a vector, replacing any earlier one with the same id
+    pub fn insert(&mut self, id: Uuid, vector: &[f64], metadata: IndexMetadata) -> Result<()> {
+        match self.dimension {
+            Some(dim) if dim != vector.len() => {
+                return Err(Error::invalid_input(format!(
+                    "Vector has dimension {}, index holds {}", vector.len(), dim
+                )));
+            }
+            _ => {}
+        }
+        if vector.is_empty() || vector.iter().any(|x| !x.is_finite()) {
+            return Err(Error::invalid_input("Vectors must be non-empty and finite"));
+        }
+        self.remove(id);
+        self.dimension = Some(vector.len());
+
+        let vector = normalize(vector);
+        let level = self.random_level();
+        let slot = self.nodes.len() as u32;
+        self.nodes.push(Node { id, vector, neighbors: vec![Vec::new(); level + 1], metadata, deleted: false });
+        self.slots.insert(id, slot);
+        self.link(slot);
+        Ok(())
+    }
+
+    /// Delete a vector; returns whether it was present
+    pub fn remove(&mut self, id: Uuid) -> bool {
+        let Some(slot) = self.slots.remove(&id) else { return false };
+        self.nodes[slot as usize].deleted = true;
+        self.tombstones += 1;
+        true
+    }
+
+    /// Whether enough tombstones have piled up to be worth a rebuild
+    pub fn needs_rebuild(&self) -> bool {
+        self.tombstones >= MIN_TOMBSTONES_FOR_REBUILD && self.tombstones * 4 > self.len()
+    }
+
+    /// Rebuild the graph from live nodes, dropping tombstones
+    pub fn rebuild(&mut self) {
+        let live: Vec<Node> = self.nodes.drain(..).filter(|n| !n.deleted).collect();
+        let dimension = self.dimension;
+        *self = Self::new(self.config.clone());
+        self.dimension = dimension;
+        for mut node in live {
+            let level = self.random_level();
+            let slot = self.nodes.len() as u32;
+            node.neighbors = vec![Vec::new(); level + 1];
+            self.slots.insert(node.id, slot);
+            self.nodes.push(node);
+            self.link(slot);
+        }
+    }
+
+    /// `k` nearest neighbors with the configured `ef_search`
+    pub fn search(&self, query: &[f64], k: usize) -> Vec<Neighbor> {
+        self.search_with(query, k, self.config.ef_search, None)
+    }
+
+    /// `k` nearest neighbors with an explicit candidate list size and filter
+    pub fn search_with(&self, query: &[f64], k: usize, ef: usize, filter: Option<&MetadataFilter>) -> Vec<Neighbor> {
+        let Some(entry) = self.entry else { return Vec::new() };
+        if k == 0 || self.dimension != Some(query.len()) {
+            return Vec::new();
+        }
+        let query = normalize(query);
+
+        let mut ep = entry;
+        for level in (1..=self.max_level).rev() {
+            ep = self.search_layer(&query, &[ep], 1, level, &|_| true)[0].node;
+        }
+        let accept = |n: u32| {
+            let node = &self.nodes[n as usize];
+            !node.deleted && filter.map_or(true, |f| f.matches(&node.metadata))
+        };
+        self.search_layer(&query, &[ep], ef.max(k), 0, &accept)
+            .into_iter()
+            .take(k)
+            .map(|s| Neighbor { id: self.nodes[s.node as usize].id, distance: s.distance as f64 })
+            .collect()
+    }
+
+    /// Exact top-k by scanning every live vector
+    pub fn exact_search(&self, query: &[f64], k: usize, filter: Option<&MetadataFilter>) -> Vec<Neighbor> {
+        if self.dimension != Some(query.len()) {
+            return Vec::new();
+        }
+        let query = normalize(query);
+        let mut scored: Vec<Scored> = self.nodes.iter().enumerate()
+            .filter(|(_, n)| !n.deleted && filter.map_or(true, |f| f.matches(&n.metadata)))
+            .map(|(i, n)| Scored { distance: distance(&query, &n.vector), node: i as u32 })
+            .collect();
+        scored.sort();
+        scored.into_iter()
+            .take(k)
+            .map(|s| Neighbor { id: self.nodes[s.node as usize].id, distance: s.distance as f64 })
+            .collect()
+    }
+
+    /// Recall@k of `search_with(ef)` against the exact scan, with timings
+    pub fn recall(&self, queries: &[Vec<f64>], k: usize, ef: usize) -> RecallReport {
+        let (mut found, mut expected) = (0usize, 0usize);
+        let (mut hnsw_time, mut exact_time) = (0.0, 0.0);
+        for query in queries {
+            let start = Instant::now();
+            let approximate = self.search_with(query, k, ef, None);
+            hnsw_time += start.elapsed().as_secs_f64();
+
+            let start = Instant::now();
+            let exact = self.exact_search(query, k, None);
+            exact_time += start.elapsed().as_secs_f64();
+
+            let hits: HashSet<Uuid> = approximate.iter().map(|n| n.id).collect();
+            found += exact.iter().filter(|n| hits.contains(&n.id)).count();
+            expected += exact.len();
+        }
+        let per_query = 1e6 / queries.len().max(1) as f64;
+        RecallReport {
+            queries: queries.len(),
+            k,
+            ef,
+            recall: if expected > 0 { found as f64 / expected as f64 } else { 1.0 },
+            mean_hnsw_micros: hnsw_time * per_query,
+            mean_exact_micros: exact_time * per_query,
+        }
+    }
+
+    // === Graph construction ===
+
+    fn random_level(&mut self) -> usize {
+        let multiplier = 1.0 / (self.config.m.max(2) as f64).ln();
+        let u: f64 = self.rng.gen();
+        (-(1.0 - u).ln() * multiplier).floor() as usize
+    }
+
+    /// Connect a freshly pushed node into the graph
+    fn link(&mut self, slot: u32) {
+        let level = self.nodes[slot as usize].neighbors.len() - 1;
+        let Some(entry) = self.entry else {
+            self.entry = Some(slot);
+            self.max_level = level;
+            return;
+        };
+        let query = self.nodes[slot as usize].vector.clone();
+
+        let mut ep = entry;
+        for l in (level + 1..=self.max_level).rev() {
+            ep = self.search_layer(&query, &[ep], 1, l, &|_| true)[0].node;
+        }
+
+        let mut entry_points = vec![ep];
+        for l in (0..=level.min(self.max_level)).rev() {
+            let accept = |n: u32| n != slot && !self.nodes[n as usize].deleted;
+            let found = self.search_layer(&query, &entry_points, self.config.ef_construction, l, &accept);
+            let selected = self.select_neighbors(&found, self.config.m);
+            self.nodes[slot as usize].neighbors[l] = selected.clone();
+
+            for neighbor in selected {
+                let links = &mut self.nodes[neighbor as usize].neighbors[l];
+                links.push(slot);
+                if links.len() > self.config.max_neighbors(l) {
+                    self.shrink(neighbor, l);
+                }
+            }
+            if !found.is_empty() {
+                entry_points = found.iter().map(|s| s.node).collect();
+            }
+        }
+
+        if level > self.max_level {
+            self.max_level = level;
+            self.entry = Some(slot);
+        }
+    }
+
+    /// Re-select a node's links on one layer after it overflowed
+    fn shrink(&mut self, slot: u32, level: usize) {
+        let vector = &self.nodes[slot as usize].vector;
+        let mut scored: Vec<Scored> = self.nodes[slot as usize].neighbors[level].iter()
+            .map(|&n| Scored { distance: distance(vector, &self.nodes[n as usize].vector), node: n })
+            .collect();
+        scored.sort();
+        let kept = self.select_neighbors(&scored, self.config.max_neighbors(level));
+        self.nodes[slot as usize].neighbors[level] = kept;
+    }
+
+    /// Diversity heuristic: keep a candidate only if it is closer to the
+    /// base than to every neighbor already kept, then top up with the
+    /// closest pruned ones. `candidates` must be sorted by distance.
+    fn select_neighbors(&self, candidates: &[Scored], m: usize) -> Vec<u32> {
+        let mut selected: Vec<u32> = Vec::with_capacity(m);
+        let mut pruned = Vec::new();
+        for candidate in candidates {
+            if selected.len() >= m {
+                break;
+            }
+            let vector = &self.nodes[candidate.node as usize].vector;
+            let diverse = selected.iter()
+                .all(|&s| distance(vector, &self.nodes[s as usize].vector) > candidate.distance);
+            if diverse {
+                selected.push(candidate.node);
+            } else {
+                pruned.push(candidate.node);
+            }
+        }
+        let missing = m.saturating_sub(selected.len());
+        selected.extend(pruned.into_iter().take(missing));
+        selected
+    }
+
+    /// Best-first search of one layer; returns up to `ef` accepted nodes,
+    /// closest first. Rejected nodes are still expanded. Greedy descent
+    /// through upper layers accepts everything, so it always has a result.
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entry_points: &[u32],
+        ef: usize,
+        level: usize,
+        accept: &dyn Fn(u32) -> bool,
+    ) -> Vec<Scored> {
+        let mut visited: HashSet<u32> = entry_points.iter().copied().collect();
+        let mut candidates: BinaryHeap<Reverse<Scored>> = BinaryHeap::new();
+        let mut results: BinaryHeap<Scored> = BinaryHeap::new();
+
+        for &ep in entry_points {
+            let scored = Scored { distance: distance(query, &self.nodes[ep as usize].vector), node: ep };
+            candidates.push(Reverse(scored));
+            if accept(ep) {
+                results.push(scored);
+            }
+        }
+        while results.len() > ef {
+            results.pop();
+        }
+
+        while let Some(Reverse(current)) = candidates.pop() {
+            if results.len() >= ef && results.peek().is_some_and(|worst| current.distance > worst.distance) {
+                break;
+            }
+            let Some(links) = self.nodes[current.node as usize].neighbors.get(level) else { continue };
+            for &n in links {
+                if !visited.insert(n) {
+                    continue;
+                }
+                let scored = Scored { distance: distance(query, &self.nodes[n as usize].vector), node: n };
+                if results.len() < ef || results.peek().map_or(true, |worst| scored.distance < worst.distance) {
+                    candidates.push(Reverse(scored));
+                    if accept(n) {
+                        results.push(scored);
+                        if results.len() > ef {
+                            results.pop();
+                        }
+                    }
+                }
+            }
+        }
+
+        results.into_sorted_vec()
+    }
+
+    // === Persistence ===
+
+    /// Write the index to disk, atomically replacing `path`
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        put_u32(&mut out, FORMAT_VERSION);
+        put_bytes(&mut out, &serde_json::to_vec(&self.config)?);
+        put_u32(&mut out, self.dimension.unwrap_or(0) as u32);
+        put_u32(&mut out, self.entry.unwrap_or(u32::MAX));
+        put_u32(&mut out, self.max_level as u32);
+        put_u32(&mut out, self.nodes.len() as u32);
+        for node in &self.nodes {
+            out.extend_from_slice(node.id.as_bytes());
+            out.push(node.deleted as u8);
+            for x in &node.vector {
+                out.extend_from_slice(&x.to_le_bytes());
+            }
+            put_u32(&mut out, node.neighbors.len() as u32);
+            for links in &node.neighbors {
+                put_u32(&mut out, links.len() as u32);
+                for &n in links {
+                    put_u32(&mut out, n);
+                }
+            }
+            put_bytes(&mut out, &serde_json::to_vec(&node.metadata)?);
+        }
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, out)?;
+        std::fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    /// Read an index written by [`HnswIndex::save`]
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path)?;
+        let mut r = Reader { data: &data, pos: 0 };
+        if r.take(4)? != MAGIC {
+            return Err(Error::invalid_input(format!("{} is not an HNSW index", path.display())));
+        }
+        let version = r.u32()?;
+        if version != FORMAT_VERSION {
+            return Err(Error::invalid_input(format!("Unsupported HNSW format version {}", version)));
+        }
+        let config: HnswConfig = serde_json::from_slice(r.bytes()?)?;
+        let dimension = r.u32()? as usize;
+        let entry = r.u32()?;
+        let max_level = r.u32()? as usize;
+        let count = r.u32()? as usize;
+
+        let mut index = Self::new(config);
+        index.dimension = (dimension > 0).then_some(dimension);
+        index.entry = (entry != u32::MAX).then_some(entry);
+        index.max_level = max_level;
+        for slot in 0..count {
+            let id = Uuid::from_slice(r.take(16)?).map_err(|e| Error::invalid_input(e.to_string()))?;
+            let deleted = r.take(1)?[0] != 0;
+            let vector = (0..dimension)
+                .map(|_| r.take(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])))
+                .collect::<Result<Vec<f32>>>()?;
+            let levels = r.u32()? as usize;
+            let mut neighbors = Vec::with_capacity(levels);
+            for _ in 0..levels {
+                let n = r.u32()? as usize;
+                let links = (0..n).map(|_| r.u32()).collect::<Result<Vec<u32>>>()?;
+                if links.iter().any(|&l| l as usize >= count) {
+                    return Err(Error::invalid_input("HNSW index links past the last node"));
+                }
+                neighbors.push(links);
+            }
+            let metadata: IndexMetadata = serde_json::from_slice(r.bytes()?)?;
+            if deleted {
+                index.tombstones += 1;
+            } else {
+                index.slots.insert(id, slot as u32);
+            }
+            index.nodes.push(Node { id, vector, neighbors, metadata, deleted });
+        }
+        if index.entry.is_some_and(|e| e as usize >= count) {
+            return Err(Error::invalid_input("HNSW entry point out of range"));
+        }
+        Ok(index)
+    }
+}
+
+impl Default for HnswIndex {
+    fn default() -> Self {
+        Self::new(HnswConfig::default())
+    }
+}
+
+fn normalize(vector: &[f64]) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let scale = if norm > 1e-12 { 1.0 / norm } else { 0.0 };
+    vector.iter().map(|x| (x * scale) as f32).collect()
+}
+
+/// Cosine distance between normalized vectors
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    1.0 - a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>()
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_u32(out, bytes.len() as u32);
+    out.extend_from_slice(bytes);
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(n).filter(|&end| end <= self.data.len())
+            .ok_or_else(|| Error::invalid_input("Truncated HNSW index"))?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8]> {
+        let n = self.u32()? as usize;
+        self.take(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_vectors(n: usize, dim: usize, seed: u64) -> Vec<Vec<f64>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..n).map(|_| (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect()).collect()
+    }
+
+    fn build(vectors: &[Vec<f64>], config: HnswConfig) -> (HnswIndex, Vec<Uuid>) {
+        let mut index = HnswIndex::new(config);
+        let ids: Vec<Uuid> = vectors.iter().map(|_| Uuid::new_v4()).collect();
+        for (i, (id, v)) in ids.iter().zip(vectors).enumerate() {
+            let metadata = IndexMetadata {
+                formula: None,
+                elements: if i % 2 == 0 { vec!["O".to_string()] } else { vec!["S".to_string()] },
+                source: Some(if i % 3 == 0 { "oqmd" } else { "materials_project" }.to_string()),
+            };
+            index.insert(*id, v, metadata).unwrap();
+        }
+        (index, ids)
+    }
+
+    #[test]
+    fn test_recall_against_exact_scan() {
+        let vectors = random_vectors(1000, 32, 1);
+        let (index, _) = build(&vectors, HnswConfig::default().with_ef_construction(100));
+        let queries = random_vectors(50, 32, 2);
+
+        let report = index.recall(&queries, 10, 64);
+        assert!(report.recall > 0.9, "recall {}", report.recall);
+        // More candidates can only help
+        assert!(index.recall(&queries, 10, 200).recall >= report.recall - 1e-9);
+
+        // Searching for a stored vector finds it first
+        let hit = &index.search(&vectors[17], 1)[0];
+        assert!(hit.distance.abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_delete_and_update() {
+        let vectors = random_vectors(300, 16, 3);
+        let (mut index, ids) = build(&vectors, HnswConfig::default().with_m(8));
+
+        assert!(index.remove(ids[5]));
+        assert!(!index.remove(ids[5]));
+        assert_eq!(index.len(), 299);
+        assert!(index.search_with(&vectors[5], 5, 100, None).iter().all(|n| n.id != ids[5]));
+
+        // Re-inserting an id replaces its vector
+        index.insert(ids[6], &vectors[7], IndexMetadata::default()).unwrap();
+        assert_eq!(index.len(), 299);
+        let hits: Vec<Uuid> = index.search_with(&vectors[7], 2, 100, None).iter().map(|n| n.id).collect();
+        assert!(hits.contains(&ids[6]) && hits.contains(&ids[7]));
+
+        // Deletes only leave tombstones; a rebuild drops them
+        for id in &ids[100..200] {
+            index.remove(*id);
+        }
+        assert_eq!(index.tombstones(), 102); // ids[5], the replaced ids[6] and the 100 just removed
+        assert!(index.needs_rebuild());
+        assert!(index.search_with(&vectors[150], 5, 100, None).iter().all(|n| !ids[100..200].contains(&n.id)));
+        index.rebuild();
+        assert_eq!(index.tombstones(), 0);
+        assert!(!index.needs_rebuild());
+        assert_eq!(index.len(), 199);
+        let report = index.recall(&random_vectors(20, 16, 4), 5, 64);
+        assert!(report.recall > 0.9, "recall {}", report.recall);
+
+        assert!(index.insert(Uuid::new_v4(), &[1.0, 2.0], IndexMetadata::default()).is_err());
+    }
+
+    #[test]
+    fn test_filtered_search() {
+        let vectors = random_vectors(1000, 16, 5);
+        let (index, _) = build(&vectors, HnswConfig::default().with_ef_construction(100));
+        let query = &vectors[0];
+
+        let filter = MetadataFilter::new().with_elements(&["S"]).from_sources(&["oqmd"]);
+        let approximate = index.search_with(query, 10, 64, Some(&filter));
+        let exact = index.exact_search(query, 10, Some(&filter));
+        assert_eq!(approximate.len(), 10);
+        let exact_ids: HashSet<Uuid> = exact.iter().map(|n| n.id).collect();
+        assert!(approximate.iter().filter(|n| exact_ids.contains(&n.id)).count() >= 8);
+        assert!(approximate.iter().all(|n| {
+            let m = index.metadata(n.id).unwrap();
+            m.elements == vec!["S".to_string()] && m.source.as_deref() == Some("oqmd")
+        }));
+
+        assert!(MetadataFilter::new().within_elements(&["O", "Ti"]).matches(&IndexMetadata {
+            elements: vec!["O".to_string(), "Ti".to_string()],
+            ..Default::default()
+        }));
+        assert!(!MetadataFilter::new().without_elements(&["O"]).matches(&IndexMetadata {
+            elements: vec!["O".to_string()],
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn test_persistence() {
+        let vectors = random_vectors(200, 8, 6);
+        let (mut index, ids) = build(&vectors, HnswConfig::default().with_ef_search(32));
+        index.remove(ids[0]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("materials.hnsw");
+        index.save(&path).unwrap();
+        let loaded = HnswIndex::load(&path).unwrap();
+
+        assert_eq!(loaded.len(), 199);
+        assert_eq!(loaded.config(), index.config());
+        assert_eq!(loaded.metadata(ids[1]), index.metadata(ids[1]));
+        for query in &vectors[..10] {
+            assert_eq!(loaded.search(query, 5), index.search(query, 5));
+        }
+
+        std::fs::write(&path, b"HNSW\x01\x00\x00\x00").unwrap();
+        assert!(HnswIndex::load(&path).is_err());
+    }
+}
@@ -18,11 +18,28 @@
 //! 4. DFT calculations on top K candidates (K << M)
 //! 5. Final ranking and analysis
 
+use crate::active_learning::{DftJobQueue, QueuedDftOracle};
+use crate::composition::Composition;
+use crate::economics::{self, MaterialEconomics};
+use crate::gnn::GNNEngine;
+use crate::material::Material;
+use crate::ml_predictor::MLPredictor;
+use crate::oxidation;
+use crate::material::Structure;
+use crate::pareto::{self, HypervolumeRecord, HypervolumeTracker, Objective};
+use crate::prototypes::{Ion, Prototype, SubstitutionTable};
+use crate::quantum::CalculationType;
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
-use tokio::task;
+use tokio::sync::broadcast;
+use tokio::task::{self, JoinSet};
+use tracing::info;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
@@ -41,12 +58,18 @@ pub struct HTSConfig {
     pub base_structures: Vec<String>,
     pub substitution_strategy: SubstitutionStrategy,
     pub max_candidates: usize,
+    #[serde(default)]
+    pub generation: GenerationSettings,
 
     // Screening funnel
     pub stages: Vec<ScreeningStage>,
 
     // Parallel execution
+    /// Concurrency for stage types without an entry in `stage_concurrency`
     pub max_parallel_tasks: usize,
+    /// Concurrent evaluations per stage type, keyed by `StageType::kind`
+    #[serde(default)]
+    pub stage_concurrency: HashMap<String, usize>,
     pub enable_checkpointing: bool,
     pub checkpoint_interval: usize, // Checkpoint every N candidates
 
@@ -64,8 +87,10 @@ impl HTSConfig {
             base_structures: vec![],
             substitution_strategy: SubstitutionStrategy::Systematic,
             max_candidates: 10000,
+            generation: GenerationSettings::default(),
             stages: vec![],
             max_parallel_tasks: 8,
+            stage_concurrency: HashMap::new(),
             enable_checkpointing: true,
             checkpoint_interval: 100,
             output_dir: PathBuf::from("hts_results"),
@@ -87,6 +112,85 @@ impl HTSConfig {
         self.max_candidates = max;
         self
     }
+
+    /// Limit concurrent evaluations for a stage type (see `StageType::kind`)
+    pub fn with_stage_concurrency(mut self, kind: &str, limit: usize) -> Self {
+        self.stage_concurrency.insert(kind.to_string(), limit.max(1));
+        self
+    }
+
+    /// Concurrent evaluations allowed for a stage type: its override in
+    /// `stage_concurrency`, else `max_parallel_tasks`
+    pub fn concurrency_for(&self, stage_type: &StageType) -> usize {
+        self.stage_concurrency
+            .get(stage_type.kind())
+            .copied()
+            .unwrap_or(self.max_parallel_tasks)
+            .max(1)
+    }
+
+    pub fn with_generation(mut self, generation: GenerationSettings) -> Self {
+        self.generation = generation;
+        self
+    }
+}
+
+/// Chemistry constraints and sampling knobs for candidate generation
+///
+/// Base structures are either prototype names ("perovskite", "spinel",
+/// "garnet", "rock-salt", "fluorite"), which are decorated from the element
+/// pool, or formulas of a known prototype ("SrTiO3"), whose ions are
+/// substituted according to the campaign's substitution table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenerationSettings {
+    /// Elements to decorate with; empty means every element with tabulated ionic radii
+    pub element_pool: Vec<String>,
+    /// Accepted Goldschmidt tolerance factors for perovskites
+    pub tolerance_factor_range: (f64, f64),
+    /// Minimum joint substitution probability relative to a parent formula
+    pub min_substitution_probability: f64,
+    /// Decorations scored by the model in ML-guided generation
+    pub ml_pool_size: usize,
+    /// Seed for random and ML-guided sampling
+    pub seed: u64,
+}
+
+impl Default for GenerationSettings {
+    fn default() -> Self {
+        Self {
+            element_pool: Vec::new(),
+            tolerance_factor_range: (0.8, 1.1),
+            min_substitution_probability: 0.01,
+            ml_pool_size: 2000,
+            seed: 0,
+        }
+    }
+}
+
+impl GenerationSettings {
+    pub fn with_element_pool(mut self, elements: Vec<String>) -> Self {
+        self.element_pool = elements;
+        self
+    }
+
+    pub fn with_tolerance_factor_range(mut self, min: f64, max: f64) -> Self {
+        self.tolerance_factor_range = (min, max);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    fn pool(&self) -> Vec<String> {
+        if self.element_pool.is_empty() {
+            crate::elements::ionic_elements().into_iter().map(String::from).collect()
+        } else {
+            self.element_pool.clone()
+        }
+    }
 }
 
 /// Strategy for generating candidates via substitution
@@ -106,6 +210,10 @@ pub enum SubstitutionStrategy {
 }
 
 /// Custom substitution rule
+///
+/// Applied to each base formula's decorated structure. `sites` index into
+/// the structure's site list; the new element takes the old ion's charge
+/// and must fit the site's radius window.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SubstitutionRule {
     pub from_element: String,
@@ -125,6 +233,9 @@ pub struct ScreeningStage {
     pub stage_type: StageType,
     pub filters: Vec<PropertyFilter>,
     pub max_pass_through: Option<usize>, // Max candidates to pass to next stage
+    /// When set, pass-through uses NSGA-II ranking instead of `overall_score`
+    #[serde(default)]
+    pub objectives: Vec<Objective>,
 }
 
 impl ScreeningStage {
@@ -135,6 +246,7 @@ impl ScreeningStage {
             stage_type,
             filters: vec![],
             max_pass_through: None,
+            objectives: vec![],
         }
     }
 
@@ -147,6 +259,11 @@ impl ScreeningStage {
         self.max_pass_through = Some(max);
         self
     }
+
+    pub fn with_objectives(mut self, objectives: Vec<Objective>) -> Self {
+        self.objectives = objectives;
+        self
+    }
 }
 
 /// Type of screening stage
@@ -168,6 +285,19 @@ pub enum StageType {
     Experimental,
 }
 
+impl StageType {
+    /// Key used for per-type settings such as `HTSConfig::stage_concurrency`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            StageType::MLPrediction { .. } => "ml_prediction",
+            StageType::DFTCalculation { .. } => "dft_calculation",
+            StageType::GNNPrediction => "gnn_prediction",
+            StageType::RuleBased => "rule_based",
+            StageType::Experimental => "experimental",
+        }
+    }
+}
+
 /// Property filter criterion
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PropertyFilter {
@@ -219,6 +349,9 @@ pub struct Candidate {
     pub composition: HashMap<String, usize>,
     pub parent_structure: Option<String>,
     pub generation_method: String,
+    /// Decorated crystal structure, when generated from a prototype
+    #[serde(default)]
+    pub structure: Option<Structure>,
 
     // Properties (calculated at different stages)
     pub properties: HashMap<String, f64>,
@@ -242,6 +375,7 @@ impl Candidate {
             composition,
             parent_structure: None,
             generation_method: "unknown".to_string(),
+            structure: None,
             properties: HashMap::new(),
             overall_score: None,
             stage_scores: HashMap::new(),
@@ -266,9 +400,78 @@ impl Candidate {
         self.updated_at = Utc::now();
     }
 
+    /// Recorded outcome of a stage, if the candidate was evaluated there
+    pub fn stage_result(&self, stage_id: Uuid) -> Option<&StageResult> {
+        self.stage_history.iter().find(|r| r.stage_id == stage_id)
+    }
+
+    /// Material view of the candidate, sharing its id
+    pub fn to_material(&self) -> Material {
+        let mut material = Material::new(self.formula.clone());
+        material.id = self.id;
+        if let Some(structure) = &self.structure {
+            material.structure = structure.clone();
+        }
+        material
+    }
+
+    /// Utilities for each objective, if every property is known
+    pub fn objective_utilities(&self, objectives: &[Objective]) -> Option<Vec<f64>> {
+        pareto::utilities(objectives, &self.properties)
+    }
+
+    /// Raw material cost, supply risk and sustainability of the composition
+    pub fn economics(&self) -> Option<MaterialEconomics> {
+        MaterialEconomics::from_amounts(self.composition.iter().map(|(e, &n)| (e.as_str(), n as f64))).ok()
+    }
+
+    /// Store the economic scores as properties, e.g. to rank on them
+    pub fn annotate_economics(&mut self) {
+        if let Some(economics) = self.economics() {
+            self.properties.extend(economics.properties());
+            self.updated_at = Utc::now();
+        }
+    }
+
+    /// Charge balance of the composition and, for decorated candidates,
+    /// the bond-valence global instability index
+    pub fn valence_properties(&self) -> HashMap<String, f64> {
+        let amounts = self.composition.iter().map(|(e, &n)| (e.as_str(), n as f64));
+        match Composition::from_amounts(amounts) {
+            Ok(composition) => oxidation::valence_properties(&composition, self.structure.as_ref()),
+            Err(_) => HashMap::new(),
+        }
+    }
+
+    /// Store the valence descriptors as properties
+    pub fn annotate_valence(&mut self) {
+        self.properties.extend(self.valence_properties());
+        self.updated_at = Utc::now();
+    }
+
+    /// Economic properties (`cost_per_kg`, `supply_risk`, ...) and valence
+    /// descriptors (`charge_balanced`, `global_instability_index`) missing
+    /// from `properties` are computed from the composition and structure
     pub fn passes_filters(&self, filters: &[PropertyFilter]) -> bool {
+        let mut economics: Option<Option<MaterialEconomics>> = None;
+        let mut valence: Option<HashMap<String, f64>> = None;
         for filter in filters {
-            if let Some(&value) = self.properties.get(&filter.property_name) {
+            let value = self.properties.get(&filter.property_name).copied().or_else(|| {
+                if oxidation::is_valence_property(&filter.property_name) {
+                    return valence
+                        .get_or_insert_with(|| self.valence_properties())
+                        .get(&filter.property_name)
+                        .copied();
+                }
+                if !economics::is_economic_property(&filter.property_name) {
+                    return None;
+                }
+                economics
+                    .get_or_insert_with(|| self.economics())
+                    .as_ref()
+                    .and_then(|e| e.property(&filter.property_name))
+            });
+            if let Some(value) = value {
                 if !filter.evaluate(value) {
                     return false;
                 }
@@ -290,6 +493,400 @@ pub struct StageResult {
     pub properties_calculated: Vec<String>,
     pub computation_time: f64, // seconds
     pub timestamp: DateTime<Utc>,
+    /// Evaluation error; such results never pass
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// A candidate whose evaluation failed at a stage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandidateFailure {
+    pub candidate_id: Uuid,
+    pub formula: String,
+    pub stage_id: Uuid,
+    pub stage_name: String,
+    pub error: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Candidate counts through one stage of the funnel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageFunnel {
+    pub stage_id: Uuid,
+    pub stage_name: String,
+    pub entered: usize,
+    /// Passed the stage filters
+    pub passed: usize,
+    /// Evaluation errors
+    pub failed: usize,
+    /// Kept after `max_pass_through`
+    pub retained: usize,
+}
+
+// ============================================================================
+// PROGRESS AND CANCELLATION
+// ============================================================================
+
+/// Capacity of the event channel; slower subscribers see `Lagged`
+const EVENT_CAPACITY: usize = 1024;
+
+/// Progress of one stage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageProgress {
+    pub stage_index: usize,
+    pub stage_name: String,
+    pub total: usize,
+    pub processed: usize,
+    pub passed: usize,
+    /// Evaluations that returned an error or panicked
+    pub failed: usize,
+    /// Evaluated before the campaign was resumed
+    pub resumed: usize,
+    pub elapsed: f64, // seconds
+    /// Estimated seconds left, from the rate of this session
+    pub eta: Option<f64>,
+}
+
+impl StageProgress {
+    fn new(stage_index: usize, stage_name: String, total: usize) -> Self {
+        Self {
+            stage_index,
+            stage_name,
+            total,
+            processed: 0,
+            passed: 0,
+            failed: 0,
+            resumed: 0,
+            elapsed: 0.0,
+            eta: None,
+        }
+    }
+
+    fn update_timing(&mut self, elapsed: f64) {
+        self.elapsed = elapsed;
+        let fresh = self.processed - self.resumed;
+        self.eta = (fresh > 0).then(|| (self.total - self.processed) as f64 * elapsed / fresh as f64);
+    }
+}
+
+/// Events published while a campaign runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum HTSEvent {
+    CandidatesGenerated { count: usize },
+    StageStarted(StageProgress),
+    /// Sent after every evaluated candidate
+    Progress(StageProgress),
+    CandidateFailed(CandidateFailure),
+    StageCompleted(StageProgress),
+    Checkpointed { stage_index: usize, processed: usize },
+    /// Dispatch stopped; in-flight evaluations were allowed to finish
+    Cancelled { stage_index: usize },
+    Finished { final_candidates: usize },
+}
+
+/// Cooperative cancellation for a running campaign
+///
+/// No new evaluations are dispatched once cancelled; evaluations already
+/// running complete and are checkpointed, so the campaign can be resumed.
+#[derive(Debug, Clone, Default)]
+pub struct CancelHandle(Arc<AtomicBool>);
+
+impl CancelHandle {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+// ============================================================================
+// STAGE EVALUATORS
+// ============================================================================
+
+/// Outcome of evaluating one candidate at one stage
+#[derive(Debug, Clone, Default)]
+pub struct StageEvaluation {
+    /// Properties to merge into the candidate (replacing earlier values)
+    pub properties: HashMap<String, f64>,
+    /// Stage score; becomes the candidate's overall score when set
+    pub score: Option<f64>,
+}
+
+/// Engine behind one or more stage types
+///
+/// A campaign dispatches each stage to the first registered evaluator that
+/// handles its [`StageType`]; filters are applied to the merged properties
+/// afterwards.
+#[async_trait]
+pub trait StageEvaluator: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn handles(&self, stage_type: &StageType) -> bool;
+
+    async fn evaluate(&self, candidate: &Candidate, stage: &ScreeningStage) -> Result<StageEvaluation, String>;
+}
+
+/// `MLPrediction { model_name }` stages backed by an [`MLPredictor`]
+///
+/// `model_name` names a trained property model; the prediction is stored
+/// under that name along with `<model_name>_uncertainty` (one sigma).
+pub struct MLStageEvaluator {
+    predictor: Arc<MLPredictor>,
+}
+
+impl MLStageEvaluator {
+    pub fn new(predictor: Arc<MLPredictor>) -> Self {
+        Self { predictor }
+    }
+}
+
+#[async_trait]
+impl StageEvaluator for MLStageEvaluator {
+    fn name(&self) -> &str {
+        "ml_predictor"
+    }
+
+    fn handles(&self, stage_type: &StageType) -> bool {
+        matches!(stage_type, StageType::MLPrediction { .. })
+    }
+
+    async fn evaluate(&self, candidate: &Candidate, stage: &ScreeningStage) -> Result<StageEvaluation, String> {
+        let StageType::MLPrediction { model_name } = &stage.stage_type else {
+            return Err(format!("{} cannot evaluate stage '{}'", self.name(), stage.name));
+        };
+        if !self.predictor.get_available_models().await.contains(model_name) {
+            return Err(format!("No trained model named '{}'", model_name));
+        }
+
+        let prediction = self.predictor.predict_material(model_name, &candidate.to_material()).await?;
+        let (lower, upper) = prediction.confidence_interval;
+        let mut properties = HashMap::new();
+        properties.insert(model_name.clone(), prediction.predicted_value);
+        properties.insert(format!("{}_uncertainty", model_name), (upper - lower) / (2.0 * 1.96));
+
+        Ok(StageEvaluation { properties, score: None })
+    }
+}
+
+/// `GNNPrediction` stages backed by a [`GNNEngine`]
+///
+/// Candidates without structures are embedded as composition graphs,
+/// which are dropped again after prediction. Properties without a trained
+/// model fail the candidate's stage.
+pub struct GnnStageEvaluator {
+    engine: Arc<GNNEngine>,
+    properties: Vec<String>,
+}
+
+impl GnnStageEvaluator {
+    /// Predict each of `properties` with the engine's model of that name
+    pub fn new(engine: Arc<GNNEngine>, properties: Vec<String>) -> Self {
+        Self { engine, properties }
+    }
+
+    async fn predict_all(&self, graph_id: Uuid) -> Result<HashMap<String, f64>, String> {
+        let mut properties = HashMap::new();
+        for property in &self.properties {
+            let value = self.engine.predict_property(graph_id, property).await
+                .map_err(|e| format!("GNN {}: {}", property, e))?;
+            properties.insert(property.clone(), value);
+        }
+        Ok(properties)
+    }
+}
+
+#[async_trait]
+impl StageEvaluator for GnnStageEvaluator {
+    fn name(&self) -> &str {
+        "gnn"
+    }
+
+    fn handles(&self, stage_type: &StageType) -> bool {
+        matches!(stage_type, StageType::GNNPrediction)
+    }
+
+    async fn evaluate(&self, candidate: &Candidate, _stage: &ScreeningStage) -> Result<StageEvaluation, String> {
+        if self.properties.is_empty() {
+            return Err("No GNN properties configured".to_string());
+        }
+        self.engine
+            .create_composition_graph(candidate.id, &candidate.formula, &candidate.composition)
+            .await?;
+
+        let properties = self.predict_all(candidate.id).await;
+        self.engine.remove_graph(candidate.id).await;
+
+        Ok(StageEvaluation { properties: properties?, score: None })
+    }
+}
+
+/// `DFTCalculation { calc_type }` stages that queue jobs for DFT workers
+///
+/// Each candidate becomes a [`crate::active_learning::DftJob`] whose
+/// requested properties are the stage's filter properties; every property
+/// in the converged [`crate::quantum::DFTResult`] is merged into the
+/// candidate, superseding earlier estimates of the same name.
+pub struct DftStageEvaluator {
+    oracle: QueuedDftOracle,
+}
+
+impl DftStageEvaluator {
+    /// Create an evaluator and the queue its workers drain
+    pub fn new(capacity: usize) -> (Self, DftJobQueue) {
+        let (oracle, queue) = QueuedDftOracle::new(capacity);
+        (Self { oracle }, queue)
+    }
+
+    /// Share an existing oracle's queue
+    pub fn from_oracle(oracle: QueuedDftOracle) -> Self {
+        Self { oracle }
+    }
+}
+
+#[async_trait]
+impl StageEvaluator for DftStageEvaluator {
+    fn name(&self) -> &str {
+        "queued_dft"
+    }
+
+    fn handles(&self, stage_type: &StageType) -> bool {
+        matches!(stage_type, StageType::DFTCalculation { .. })
+    }
+
+    async fn evaluate(&self, candidate: &Candidate, stage: &ScreeningStage) -> Result<StageEvaluation, String> {
+        let StageType::DFTCalculation { calc_type } = &stage.stage_type else {
+            return Err(format!("{} cannot evaluate stage '{}'", self.name(), stage.name));
+        };
+        let calc_type: CalculationType = calc_type.parse()?;
+        let requested = stage.filters.iter().map(|f| f.property_name.clone()).collect();
+
+        let result = self.oracle.submit(candidate.to_material(), calc_type, requested).await?;
+        Ok(StageEvaluation {
+            properties: QueuedDftOracle::result_properties(&result),
+            score: None,
+        })
+    }
+}
+
+// ============================================================================
+// CHECKPOINTS
+// ============================================================================
+
+/// Snapshot of a campaign, written after every checkpoint interval
+///
+/// Stage results travel with the candidates (`Candidate::stage_history`),
+/// so a resumed stage only evaluates candidates without a result for it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HTSCheckpoint {
+    pub config: HTSConfig,
+    /// FNV-1a hash of the canonical JSON config
+    pub config_hash: String,
+    /// Generation seed the candidates were drawn with
+    pub seed: u64,
+    pub candidates_generated: bool,
+    /// Candidates produced by generation, before any screening
+    #[serde(default)]
+    pub total_generated: usize,
+    pub candidates: Vec<Candidate>,
+    /// Stage in progress (or last run)
+    pub current_stage: usize,
+    pub stages_completed: usize,
+    pub total_processed: usize,
+    pub hypervolume: HypervolumeTracker,
+    #[serde(default)]
+    pub failures: Vec<CandidateFailure>,
+    #[serde(default)]
+    pub funnel: Vec<StageFunnel>,
+    #[serde(default)]
+    pub eliminated: Vec<Candidate>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub saved_at: DateTime<Utc>,
+}
+
+impl HTSCheckpoint {
+    /// Whether the checkpoint was written for this configuration
+    pub fn matches(&self, config: &HTSConfig) -> Result<bool, String> {
+        Ok(self.config_hash == config_hash(config)?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read checkpoint {}: {}", path.display(), e))?;
+        serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse checkpoint {}: {}", path.display(), e))
+    }
+
+    /// Write atomically, so a crash mid-write keeps the previous checkpoint
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create output directory: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize checkpoint: {}", e))?;
+        let partial = path.with_extension("json.partial");
+        std::fs::write(&partial, json)
+            .map_err(|e| format!("Failed to write checkpoint: {}", e))?;
+        std::fs::rename(&partial, path)
+            .map_err(|e| format!("Failed to write checkpoint: {}", e))
+    }
+}
+
+/// Stable hash of a configuration
+///
+/// Goes through `serde_json::Value` so map keys are sorted.
+pub fn config_hash(config: &HTSConfig) -> Result<String, String> {
+    let canonical = serde_json::to_value(config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?
+        .to_string();
+    let hash = canonical.bytes().fold(0xcbf2_9ce4_8422_2325u64, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    Ok(format!("{:016x}", hash))
+}
+
+/// Where checkpoints are persisted
+///
+/// Campaigns write `output_dir/checkpoint.json` unless a store is set;
+/// database-backed stores implement this trait.
+#[async_trait]
+pub trait CheckpointStore: Send + Sync {
+    async fn save(&self, checkpoint: &HTSCheckpoint) -> Result<(), String>;
+    /// Latest checkpoint of a campaign, if any
+    async fn load(&self, campaign_id: Uuid) -> Result<Option<HTSCheckpoint>, String>;
+}
+
+/// Checkpoints as JSON files, one per campaign
+#[derive(Debug, Clone)]
+pub struct FileCheckpointStore {
+    dir: PathBuf,
+}
+
+impl FileCheckpointStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn path(&self, campaign_id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.checkpoint.json", campaign_id))
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for FileCheckpointStore {
+    async fn save(&self, checkpoint: &HTSCheckpoint) -> Result<(), String> {
+        checkpoint.save(&self.path(checkpoint.config.campaign_id))
+    }
+
+    async fn load(&self, campaign_id: Uuid) -> Result<Option<HTSCheckpoint>, String> {
+        let path = self.path(campaign_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+        HTSCheckpoint::load(&path).map(Some)
+    }
 }
 
 // ============================================================================
@@ -297,13 +894,37 @@ pub struct StageResult {
 // ============================================================================
 
 /// A high-throughput screening campaign
-#[derive(Debug)]
 pub struct HTSCampaign {
     config: HTSConfig,
     candidates: Arc<RwLock<Vec<Candidate>>>,
     current_stage_idx: usize,
+    stages_completed: usize,
+    candidates_generated: bool,
+    total_generated: usize,
     total_processed: usize,
     start_time: Option<DateTime<Utc>>,
+    hypervolume: HypervolumeTracker,
+    evaluators: Vec<Arc<dyn StageEvaluator>>,
+    substitutions: SubstitutionTable,
+    predictor: Option<Arc<MLPredictor>>,
+    checkpoint_store: Option<Arc<dyn CheckpointStore>>,
+    failures: Vec<CandidateFailure>,
+    funnel: Vec<StageFunnel>,
+    eliminated: Vec<Candidate>,
+    events: broadcast::Sender<HTSEvent>,
+    cancel: CancelHandle,
+}
+
+impl std::fmt::Debug for HTSCampaign {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HTSCampaign")
+            .field("config", &self.config)
+            .field("current_stage_idx", &self.current_stage_idx)
+            .field("stages_completed", &self.stages_completed)
+            .field("total_processed", &self.total_processed)
+            .field("evaluators", &self.evaluators.iter().map(|e| e.name()).collect::<Vec<_>>())
+            .finish()
+    }
 }
 
 impl HTSCampaign {
@@ -312,144 +933,353 @@ impl HTSCampaign {
             config,
             candidates: Arc::new(RwLock::new(Vec::new())),
             current_stage_idx: 0,
+            stages_completed: 0,
+            candidates_generated: false,
+            total_generated: 0,
             total_processed: 0,
             start_time: None,
+            hypervolume: HypervolumeTracker::new(),
+            evaluators: Vec::new(),
+            substitutions: SubstitutionTable::default(),
+            predictor: None,
+            checkpoint_store: None,
+            failures: Vec::new(),
+            funnel: Vec::new(),
+            eliminated: Vec::new(),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+            cancel: CancelHandle::default(),
+        }
+    }
+
+    /// Resume a campaign from a checkpoint file
+    ///
+    /// Evaluators, predictors and stores are not persisted; register them
+    /// again before calling `run`, which continues with the stage in
+    /// progress and skips candidates already evaluated there.
+    pub fn resume(path: impl AsRef<Path>) -> Result<Self, String> {
+        Self::from_checkpoint(HTSCheckpoint::load(path.as_ref())?)
+    }
+
+    /// Resume a campaign from the latest checkpoint in a store
+    pub async fn resume_from_store(store: Arc<dyn CheckpointStore>, campaign_id: Uuid) -> Result<Self, String> {
+        let checkpoint = store.load(campaign_id).await?
+            .ok_or_else(|| format!("No checkpoint for campaign {}", campaign_id))?;
+        Ok(Self::from_checkpoint(checkpoint)?.with_checkpoint_store(store))
+    }
+
+    pub fn from_checkpoint(checkpoint: HTSCheckpoint) -> Result<Self, String> {
+        if !checkpoint.matches(&checkpoint.config)? {
+            return Err("Checkpoint config does not match its hash; it was edited or is corrupt".to_string());
+        }
+        if checkpoint.seed != checkpoint.config.generation.seed {
+            return Err(format!(
+                "Checkpoint seed {} differs from the config seed {}",
+                checkpoint.seed, checkpoint.config.generation.seed
+            ));
         }
+
+        let mut campaign = Self::new(checkpoint.config);
+        // Checkpoints written before the count was kept: every generated
+        // candidate is either still in play or eliminated
+        campaign.total_generated = match checkpoint.total_generated {
+            0 if checkpoint.candidates_generated => checkpoint.candidates.len() + checkpoint.eliminated.len(),
+            n => n,
+        };
+        *campaign.candidates.write().unwrap() = checkpoint.candidates;
+        campaign.candidates_generated = checkpoint.candidates_generated;
+        campaign.current_stage_idx = checkpoint.current_stage;
+        campaign.stages_completed = checkpoint.stages_completed;
+        campaign.total_processed = checkpoint.total_processed;
+        campaign.hypervolume = checkpoint.hypervolume;
+        campaign.failures = checkpoint.failures;
+        campaign.funnel = checkpoint.funnel;
+        campaign.eliminated = checkpoint.eliminated;
+        campaign.start_time = checkpoint.started_at;
+        Ok(campaign)
+    }
+
+    /// Persist checkpoints here instead of `output_dir/checkpoint.json`
+    pub fn with_checkpoint_store(mut self, store: Arc<dyn CheckpointStore>) -> Self {
+        self.checkpoint_store = Some(store);
+        self
+    }
+
+    /// Snapshot of the current campaign state
+    pub fn checkpoint(&self) -> Result<HTSCheckpoint, String> {
+        Ok(HTSCheckpoint {
+            config: self.config.clone(),
+            config_hash: config_hash(&self.config)?,
+            seed: self.config.generation.seed,
+            candidates_generated: self.candidates_generated,
+            total_generated: self.total_generated,
+            candidates: self.candidates.read().unwrap().clone(),
+            current_stage: self.current_stage_idx,
+            stages_completed: self.stages_completed,
+            total_processed: self.total_processed,
+            hypervolume: self.hypervolume.clone(),
+            failures: self.failures.clone(),
+            funnel: self.funnel.clone(),
+            eliminated: self.eliminated.clone(),
+            started_at: self.start_time,
+            saved_at: Utc::now(),
+        })
+    }
+
+    /// Register an engine for the stage types it handles
+    ///
+    /// Earlier registrations win when several handle the same type.
+    pub fn with_evaluator(mut self, evaluator: Arc<dyn StageEvaluator>) -> Self {
+        self.evaluators.push(evaluator);
+        self
+    }
+
+    /// Substitution probabilities used when substituting base formulas
+    pub fn with_substitution_table(mut self, table: SubstitutionTable) -> Self {
+        self.substitutions = table;
+        self
+    }
+
+    /// Model used by `SubstitutionStrategy::MLGuided`
+    pub fn with_predictor(mut self, predictor: Arc<MLPredictor>) -> Self {
+        self.predictor = Some(predictor);
+        self
+    }
+
+    /// Subscribe to progress events
+    pub fn subscribe(&self) -> broadcast::Receiver<HTSEvent> {
+        self.events.subscribe()
+    }
+
+    /// Handle that stops the campaign after in-flight evaluations finish
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
+
+    fn emit(&self, event: HTSEvent) {
+        // No subscribers is not an error
+        let _ = self.events.send(event);
+    }
+
+    fn evaluator_for(&self, stage_type: &StageType) -> Option<Arc<dyn StageEvaluator>> {
+        self.evaluators.iter().find(|e| e.handles(stage_type)).cloned()
     }
 
     /// Generate candidates for screening
     pub async fn generate_candidates(&mut self) -> Result<usize, String> {
-        let mut candidates = Vec::new();
-
-        match &self.config.substitution_strategy {
+        let max = self.config.max_candidates;
+        let mut candidates = match self.config.substitution_strategy.clone() {
             SubstitutionStrategy::Systematic => {
-                // Generate all systematic substitutions
-                for base_structure in &self.config.base_structures {
-                    let generated = self.generate_systematic_substitutions(base_structure)?;
-                    candidates.extend(generated);
-
-                    if candidates.len() >= self.config.max_candidates {
-                        break;
-                    }
-                }
+                // Most plausible (or most probable) decorations first
+                self.decoration_space()?
+                    .iter()
+                    .take(max)
+                    .map(|d| d.candidate("prototype_decoration"))
+                    .collect::<Result<Vec<_>, _>>()?
             }
             SubstitutionStrategy::Random { num_samples } => {
-                // Generate random substitutions
-                let num = (*num_samples).min(self.config.max_candidates);
-                for base_structure in &self.config.base_structures {
-                    let generated = self.generate_random_substitutions(base_structure, num)?;
-                    candidates.extend(generated);
-
-                    if candidates.len() >= self.config.max_candidates {
-                        break;
-                    }
-                }
+                let space = self.decoration_space()?;
+                let weights: Vec<f64> = space.iter().map(|d| d.weight).collect();
+                let mut rng = StdRng::seed_from_u64(self.config.generation.seed);
+                weighted_sample(&weights, num_samples.min(max), &mut rng)
+                    .into_iter()
+                    .map(|i| space[i].candidate("random_substitution"))
+                    .collect::<Result<Vec<_>, _>>()?
             }
             SubstitutionStrategy::MLGuided { target_property, target_value } => {
-                // ML-guided generation
-                // This would integrate with the ML predictor to generate promising candidates
-                candidates.push(Candidate::new(
-                    "ML-guided-candidate".to_string(),
-                    HashMap::new(),
-                ));
-            }
-            SubstitutionStrategy::Custom { rules } => {
-                // Custom rule-based generation
-                for rule in rules {
-                    // Apply custom substitution rules
-                }
+                self.generate_ml_guided(&target_property, target_value).await?
             }
-        }
+            SubstitutionStrategy::Custom { rules } => self.generate_custom_substitutions(&rules)?,
+        };
 
         // Limit to max candidates
-        candidates.truncate(self.config.max_candidates);
+        candidates.truncate(max);
 
         let count = candidates.len();
         *self.candidates.write().unwrap() = candidates;
+        self.candidates_generated = true;
+        self.total_generated = count;
 
         Ok(count)
     }
 
     /// Run the entire screening campaign
+    ///
+    /// A resumed campaign keeps its candidates and continues with the
+    /// first unfinished stage. After cancellation the results hold the
+    /// candidates as they stood, with `cancelled` set.
     pub async fn run(&mut self) -> Result<HTSResults, String> {
-        self.start_time = Some(Utc::now());
+        if self.start_time.is_none() {
+            self.start_time = Some(Utc::now());
+        }
 
         // Generate candidates
-        let num_candidates = self.generate_candidates().await?;
-        println!("Generated {} candidates", num_candidates);
+        if !self.candidates_generated {
+            let num_candidates = self.generate_candidates().await?;
+            info!("Generated {} candidates", num_candidates);
+            self.emit(HTSEvent::CandidatesGenerated { count: num_candidates });
+            if self.config.enable_checkpointing {
+                self.save_checkpoint().await?;
+            }
+        }
 
-        // Run through each screening stage
-        for (idx, stage) in self.config.stages.clone().iter().enumerate() {
-            println!("\n=== Stage {}: {} ===", idx + 1, stage.name);
+        // Run through each remaining screening stage
+        let mut cancelled = false;
+        let stages = self.config.stages.clone();
+        for (idx, stage) in stages.iter().enumerate().skip(self.stages_completed) {
+            info!("Stage {}: {}", idx + 1, stage.name);
             self.current_stage_idx = idx;
 
-            let num_passed = self.run_stage(stage).await?;
+            let progress = match self.run_stage(idx, stage).await? {
+                Some(progress) => progress,
+                None => {
+                    cancelled = true;
+                    break;
+                }
+            };
+            self.stages_completed = idx + 1;
+
+            info!(
+                "Stage {} complete: {} passed, {} failed",
+                idx + 1, progress.passed, progress.failed
+            );
+            self.emit(HTSEvent::StageCompleted(progress.clone()));
 
-            println!("Stage {} complete: {} candidates passed", idx + 1, num_passed);
+            // Checkpoint
+            if self.config.enable_checkpointing {
+                self.save_checkpoint().await?;
+            }
 
-            if num_passed == 0 {
-                println!("No candidates passed stage {}. Stopping.", idx + 1);
+            if progress.passed == 0 {
+                info!("No candidates passed stage {}. Stopping.", idx + 1);
                 break;
             }
+        }
 
-            // Checkpoint
+        if cancelled {
+            info!("Campaign cancelled during stage {}", self.current_stage_idx + 1);
             if self.config.enable_checkpointing {
-                self.save_checkpoint()?;
+                self.save_checkpoint().await?;
             }
+            self.emit(HTSEvent::Cancelled { stage_index: self.current_stage_idx });
         }
 
         // Generate results
-        let results = self.generate_results()?;
+        let mut results = self.generate_results()?;
+        results.cancelled = cancelled;
+        if !cancelled {
+            self.emit(HTSEvent::Finished { final_candidates: results.final_candidates.len() });
+        }
 
         Ok(results)
     }
 
     /// Run a single screening stage
-    async fn run_stage(&mut self, stage: &ScreeningStage) -> Result<usize, String> {
+    ///
+    /// Candidates are dispatched as slots free up, up to the stage type's
+    /// concurrency limit. Returns `None` if cancelled before every
+    /// candidate was evaluated.
+    async fn run_stage(&mut self, stage_index: usize, stage: &ScreeningStage) -> Result<Option<StageProgress>, String> {
         let candidates = self.candidates.clone();
-        let mut passed_count = 0;
-
-        // Process candidates in parallel
-        let chunk_size = self.config.max_parallel_tasks;
-        let num_candidates = candidates.read().unwrap().len();
-
-        for chunk_start in (0..num_candidates).step_by(chunk_size) {
-            let chunk_end = (chunk_start + chunk_size).min(num_candidates);
-
-            let mut tasks = vec![];
-
-            for i in chunk_start..chunk_end {
-                let candidates_clone = candidates.clone();
-                let stage_clone = stage.clone();
-
-                let task = task::spawn(async move {
-                    Self::evaluate_candidate_at_stage(candidates_clone, i, stage_clone).await
-                });
-
-                tasks.push(task);
+        let evaluator = self.evaluator_for(&stage.stage_type);
+        let limit = self.config.concurrency_for(&stage.stage_type);
+
+        // Candidates with a result for this stage were evaluated before a
+        // resume and are not sent to the evaluator again
+        let (pending, mut progress) = {
+            let candidates = candidates.read().unwrap();
+            let mut progress = StageProgress::new(stage_index, stage.name.clone(), candidates.len());
+            let mut pending = Vec::new();
+            for (i, candidate) in candidates.iter().enumerate() {
+                match candidate.stage_result(stage.id) {
+                    Some(result) => {
+                        progress.resumed += 1;
+                        progress.passed += result.passed as usize;
+                        progress.failed += result.error.is_some() as usize;
+                    }
+                    None => pending.push(i),
+                }
+            }
+            progress.processed = progress.resumed;
+            (pending, progress)
+        };
+        self.emit(HTSEvent::StageStarted(progress.clone()));
+
+        let started = std::time::Instant::now();
+        let mut queue = pending.into_iter();
+        let mut running = JoinSet::new();
+        let mut since_checkpoint = 0;
+
+        loop {
+            while running.len() < limit && !self.cancel.is_cancelled() {
+                let Some(index) = queue.next() else { break };
+                let evaluation = Self::evaluate_candidate_at_stage(
+                    candidates.clone(),
+                    index,
+                    stage.clone(),
+                    evaluator.clone(),
+                );
+                // The inner task isolates panics so they can be attributed
+                running.spawn(async move { (index, task::spawn(evaluation).await) });
             }
 
-            // Wait for all tasks in this chunk
-            for task in tasks {
-                if let Ok(Ok(passed)) = task.await {
-                    if passed {
-                        passed_count += 1;
-                    }
+            let Some(joined) = running.join_next().await else { break };
+            let (index, outcome) = joined.map_err(|e| format!("Stage executor task failed: {}", e))?;
+            let outcome = outcome.unwrap_or_else(|e| Err(format!("Evaluation panicked: {}", e)));
+
+            match outcome {
+                Ok(passed) => progress.passed += passed as usize,
+                Err(error) => {
+                    progress.failed += 1;
+                    let failure = Self::record_failure(&candidates, index, stage, error);
+                    self.emit(HTSEvent::CandidateFailed(failure.clone()));
+                    self.failures.push(failure);
                 }
             }
+            progress.processed += 1;
+            progress.update_timing(started.elapsed().as_secs_f64());
+            self.total_processed += 1;
+            self.emit(HTSEvent::Progress(progress.clone()));
+
+            since_checkpoint += 1;
+            if self.config.enable_checkpointing && since_checkpoint >= self.config.checkpoint_interval {
+                self.save_checkpoint().await?;
+                self.emit(HTSEvent::Checkpointed { stage_index, processed: progress.processed });
+                since_checkpoint = 0;
+            }
+        }
 
-            self.total_processed += (chunk_end - chunk_start);
+        if progress.processed < progress.total {
+            return Ok(None);
         }
 
         // Filter candidates - keep only those that passed
         let mut candidates_lock = candidates.write().unwrap();
-        candidates_lock.retain(|c| {
-            c.stage_history.iter().any(|s| s.stage_id == stage.id && s.passed)
-        });
+        let (passed, rejected): (Vec<Candidate>, Vec<Candidate>) = candidates_lock
+            .drain(..)
+            .partition(|c| c.stage_result(stage.id).is_some_and(|r| r.passed));
+        *candidates_lock = passed;
+        self.eliminated.extend(rejected);
 
-        // Apply max pass through limit
+        // Apply max pass through limit; candidates cut here are eliminated too
         if let Some(max_pass) = stage.max_pass_through {
-            if candidates_lock.len() > max_pass {
+            if candidates_lock.len() > max_pass && !stage.objectives.is_empty() {
+                // Rank by Pareto front and crowding distance; candidates
+                // missing an objective value go last
+                let (mut ranked, unranked): (Vec<Candidate>, Vec<Candidate>) = candidates_lock
+                    .drain(..)
+                    .partition(|c| c.objective_utilities(&stage.objectives).is_some());
+                let utilities: Vec<Vec<f64>> = ranked.iter()
+                    .filter_map(|c| c.objective_utilities(&stage.objectives))
+                    .collect();
+                let selected = pareto::nsga2_select(&utilities, max_pass);
+                let mut slots: Vec<Option<Candidate>> = ranked.drain(..).map(Some).collect();
+                candidates_lock.extend(selected.into_iter().filter_map(|i| slots[i].take()));
+                self.eliminated.extend(slots.into_iter().flatten());
+                let remaining = max_pass.saturating_sub(candidates_lock.len());
+                let mut unranked = unranked.into_iter();
+                candidates_lock.extend(unranked.by_ref().take(remaining));
+                self.eliminated.extend(unranked);
+            } else if candidates_lock.len() > max_pass {
                 // Rank by overall score and keep top N
                 candidates_lock.sort_by(|a, b| {
                     b.overall_score
@@ -457,11 +1287,55 @@ impl HTSCampaign {
                         .partial_cmp(&a.overall_score.unwrap_or(0.0))
                         .unwrap()
                 });
-                candidates_lock.truncate(max_pass);
+                self.eliminated.extend(candidates_lock.drain(max_pass..));
             }
         }
 
-        Ok(passed_count)
+        self.funnel.push(StageFunnel {
+            stage_id: stage.id,
+            stage_name: stage.name.clone(),
+            entered: progress.total,
+            passed: progress.passed,
+            failed: progress.failed,
+            retained: candidates_lock.len(),
+        });
+
+        if !stage.objectives.is_empty() {
+            let utilities: Vec<Vec<f64>> = candidates_lock.iter()
+                .filter_map(|c| c.objective_utilities(&stage.objectives))
+                .collect();
+            self.hypervolume.record(stage.name.clone(), &utilities);
+        }
+
+        Ok(Some(progress))
+    }
+
+    /// Record a failed evaluation as a non-passing stage result
+    fn record_failure(
+        candidates: &RwLock<Vec<Candidate>>,
+        index: usize,
+        stage: &ScreeningStage,
+        error: String,
+    ) -> CandidateFailure {
+        let mut candidates = candidates.write().unwrap();
+        let candidate = &mut candidates[index];
+        candidate.record_stage(StageResult {
+            stage_id: stage.id,
+            stage_name: stage.name.clone(),
+            passed: false,
+            properties_calculated: vec![],
+            computation_time: 0.0,
+            timestamp: Utc::now(),
+            error: Some(error.clone()),
+        });
+        CandidateFailure {
+            candidate_id: candidate.id,
+            formula: candidate.formula.clone(),
+            stage_id: stage.id,
+            stage_name: stage.name.clone(),
+            error,
+            timestamp: Utc::now(),
+        }
     }
 
     /// Evaluate a single candidate at a stage
@@ -469,6 +1343,7 @@ impl HTSCampaign {
         candidates: Arc<RwLock<Vec<Candidate>>>,
         index: usize,
         stage: ScreeningStage,
+        evaluator: Option<Arc<dyn StageEvaluator>>,
     ) -> Result<bool, String> {
         let start_time = std::time::Instant::now();
 
@@ -477,29 +1352,42 @@ impl HTSCampaign {
             let candidates_read = candidates.read().unwrap();
             candidates_read[index].clone()
         };
+        if let Some(result) = candidate.stage_result(stage.id) {
+            return Ok(result.passed);
+        }
 
         // Evaluate based on stage type
-        let passed = match &stage.stage_type {
-            StageType::MLPrediction { model_name } => {
-                // Run ML prediction
-                Self::evaluate_ml_prediction(&mut candidate, model_name).await?
-            }
-            StageType::DFTCalculation { calc_type } => {
-                // Run DFT calculation
-                Self::evaluate_dft(&mut candidate, calc_type).await?
-            }
-            StageType::GNNPrediction => {
-                // Run GNN prediction
-                Self::evaluate_gnn(&mut candidate).await?
-            }
-            StageType::RuleBased => {
+        let passed = match (&stage.stage_type, evaluator) {
+            (StageType::RuleBased, _) => {
                 // Apply rule-based filters
                 candidate.passes_filters(&stage.filters)
             }
-            StageType::Experimental => {
+            (_, Some(evaluator)) => {
+                let evaluation = evaluator.evaluate(&candidate, &stage).await?;
+                // A NaN score would break the score-ranked pass-through cut
+                if evaluation.score.is_some_and(|score| !score.is_finite()) {
+                    return Err(format!(
+                        "Evaluator {} returned a non-finite score for stage '{}'",
+                        evaluator.name(),
+                        stage.name
+                    ));
+                }
+                for (name, value) in evaluation.properties {
+                    candidate.add_property(name, value);
+                }
+                if let Some(score) = evaluation.score {
+                    candidate.stage_scores.insert(stage.id, score);
+                    candidate.set_score(score);
+                }
+                true
+            }
+            (StageType::Experimental, None) => {
                 // External experimental validation (placeholder)
                 true
             }
+            (_, None) => {
+                return Err(format!("No evaluator registered for stage '{}'", stage.name));
+            }
         };
 
         // Apply filters
@@ -516,6 +1404,7 @@ impl HTSCampaign {
             properties_calculated,
             computation_time: elapsed,
             timestamp: Utc::now(),
+            error: None,
         };
 
         candidate.record_stage(stage_result);
@@ -529,88 +1418,163 @@ impl HTSCampaign {
         Ok(final_passed)
     }
 
-    // Evaluation methods (placeholders - would integrate with actual engines)
+    // Candidate generation helpers
 
-    async fn evaluate_ml_prediction(
-        candidate: &mut Candidate,
-        model_name: &str,
-    ) -> Result<bool, String> {
-        // Placeholder: integrate with MLPredictor
-        candidate.add_property("ml_predicted_energy".to_string(), -3.5);
-        candidate.add_property("ml_predicted_gap".to_string(), 2.0);
-        candidate.set_score(0.8);
-        Ok(true)
+    /// Prototype and parent decoration named by a base structure
+    fn resolve_base(base: &str) -> Result<(Arc<Prototype>, Option<Vec<Ion>>), String> {
+        if let Some(prototype) = Prototype::by_name(base) {
+            return Ok((Arc::new(prototype), None));
+        }
+        Prototype::identify(base)
+            .map(|(prototype, ions)| (Arc::new(prototype), Some(ions)))
+            .ok_or_else(|| format!("Base structure '{}' is neither a prototype nor a formula of one", base))
     }
 
-    async fn evaluate_dft(candidate: &mut Candidate, calc_type: &str) -> Result<bool, String> {
-        // Placeholder: integrate with QuantumEngine
-        candidate.add_property("dft_energy".to_string(), -4.2);
-        candidate.add_property("dft_band_gap".to_string(), 2.3);
-        candidate.set_score(0.9);
-        Ok(true)
-    }
+    /// Plausible decorations of every base structure, unique by formula
+    fn decoration_space(&self) -> Result<Vec<Decoration>, String> {
+        let settings = &self.config.generation;
+        let mut space: Vec<Decoration> = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+
+        for base in &self.config.base_structures {
+            let (prototype, parent) = Self::resolve_base(base)?;
+            let mut pool = settings.pool();
+            if let Some(parent) = &parent {
+                pool.extend(parent.iter().map(|ion| ion.element.clone()));
+                pool.sort();
+                pool.dedup();
+            }
+
+            let mut decorations: Vec<Decoration> = prototype
+                .enumerate(&pool, settings.tolerance_factor_range)
+                .into_iter()
+                .filter_map(|(ions, plausibility)| {
+                    let substitution_probability = match &parent {
+                        Some(parent) if *parent == ions => return None,
+                        Some(parent) => {
+                            let p: f64 = parent.iter().zip(&ions)
+                                .map(|(from, to)| self.substitutions.probability(from, to))
+                                .product();
+                            if p < settings.min_substitution_probability {
+                                return None;
+                            }
+                            Some(p)
+                        }
+                        None => None,
+                    };
+                    Some(Decoration {
+                        prototype: prototype.clone(),
+                        ions,
+                        parent: base.clone(),
+                        weight: substitution_probability.unwrap_or(plausibility),
+                        substitution_probability,
+                    })
+                })
+                .collect();
+            decorations.sort_by(|a, b| b.weight.partial_cmp(&a.weight).unwrap_or(std::cmp::Ordering::Equal));
+
+            for decoration in decorations {
+                if seen.insert((prototype.name, decoration.ions.clone())) {
+                    space.push(decoration);
+                }
+            }
+        }
 
-    async fn evaluate_gnn(candidate: &mut Candidate) -> Result<bool, String> {
-        // Placeholder: integrate with GNNEngine
-        candidate.add_property("gnn_predicted_gap".to_string(), 2.1);
-        candidate.set_score(0.85);
-        Ok(true)
+        Ok(space)
     }
 
-    // Candidate generation helpers
+    /// Score decorations with the model and sample toward `target_value`
+    ///
+    /// Weights are exp(-|prediction - target| / s), with s the median
+    /// distance to the target over the scored pool.
+    async fn generate_ml_guided(&self, property: &str, target_value: f64) -> Result<Vec<Candidate>, String> {
+        let predictor = self.predictor.clone()
+            .ok_or_else(|| "ML-guided generation needs a predictor (HTSCampaign::with_predictor)".to_string())?;
+
+        let mut scored: Vec<(Candidate, f64)> = Vec::new();
+        for decoration in self.decoration_space()?.iter().take(self.config.generation.ml_pool_size) {
+            let mut candidate = decoration.candidate("ml_guided")?;
+            // A diverged model yields NaN; such candidates carry no ranking signal
+            match predictor.predict_material(property, &candidate.to_material()).await {
+                Ok(prediction) if prediction.predicted_value.is_finite() => {
+                    candidate.add_property(property.to_string(), prediction.predicted_value);
+                    scored.push((candidate, (prediction.predicted_value - target_value).abs()));
+                }
+                _ => {}
+            }
+        }
+        if scored.is_empty() {
+            return Err(format!("No candidate could be scored for {}", property));
+        }
 
-    fn generate_systematic_substitutions(&self, base: &str) -> Result<Vec<Candidate>, String> {
-        // Placeholder: would integrate with LIRS
-        let mut candidates = Vec::new();
+        let mut distances: Vec<f64> = scored.iter().map(|(_, d)| *d).collect();
+        distances.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let scale = distances[distances.len() / 2].max(1e-9);
+        let weights: Vec<f64> = scored.iter().map(|(_, d)| (-d / scale).exp()).collect();
 
-        // Example: Generate some substitution variants
-        let elements = vec!["Fe", "Co", "Ni", "Cu", "Mn"];
+        let mut rng = StdRng::seed_from_u64(self.config.generation.seed);
+        let picked = weighted_sample(&weights, self.config.max_candidates, &mut rng);
+        let mut slots: Vec<Option<Candidate>> = scored.into_iter().map(|(c, _)| Some(c)).collect();
+        Ok(picked.into_iter().filter_map(|i| slots[i].take()).collect())
+    }
 
-        for elem in &elements {
-            let formula = format!("{}2O3", elem);
-            let mut composition = HashMap::new();
-            composition.insert(elem.to_string(), 2);
-            composition.insert("O".to_string(), 3);
+    /// Apply custom rules to the structure of each base formula
+    fn generate_custom_substitutions(&self, rules: &[SubstitutionRule]) -> Result<Vec<Candidate>, String> {
+        let tolerance = self.config.generation.tolerance_factor_range;
+        let mut candidates = Vec::new();
 
-            let mut candidate = Candidate::new(formula, composition);
-            candidate.parent_structure = Some(base.to_string());
-            candidate.generation_method = "systematic_substitution".to_string();
+        for base in &self.config.base_structures {
+            let (prototype, parent) = Self::resolve_base(base)?;
+            let parent = parent
+                .ok_or_else(|| format!("Custom rules need a base formula, got prototype '{}'", base))?;
+            let structure = prototype.decorate(&parent).map_err(|e| e.to_string())?;
+
+            for rule in rules {
+                let Some(site_index) = parent.iter().position(|ion| ion.element == rule.from_element) else {
+                    continue;
+                };
+                let from = &parent[site_index];
+
+                for to_element in &rule.to_elements {
+                    // Same charge keeps the cell balanced; the new ion must fit the site
+                    let to = Ion::new(to_element.clone(), from.charge);
+                    let mut substituted = parent.clone();
+                    substituted[site_index] = to.clone();
+                    if prototype.plausibility(&substituted, tolerance).is_none() {
+                        continue;
+                    }
 
-            candidates.push(candidate);
+                    let mut new_structure = structure.clone();
+                    for (i, site) in new_structure.sites.iter_mut().enumerate() {
+                        let selected = rule.sites.as_ref().map_or(true, |sites| sites.contains(&i));
+                        if selected && site.element == from.element {
+                            site.element = to.element.clone();
+                        }
+                    }
+
+                    let mut candidate = structure_candidate(new_structure)?;
+                    candidate.parent_structure = Some(base.clone());
+                    candidate.generation_method = "custom_rule".to_string();
+                    candidate.add_property(
+                        "substitution_probability".to_string(),
+                        self.substitutions.probability(from, &to),
+                    );
+                    candidates.push(candidate);
+                }
+            }
         }
 
         Ok(candidates)
     }
 
-    fn generate_random_substitutions(
-        &self,
-        base: &str,
-        num: usize,
-    ) -> Result<Vec<Candidate>, String> {
-        // Placeholder: would use random substitution logic
-        self.generate_systematic_substitutions(base)
-            .map(|mut v| {
-                v.truncate(num);
-                v
-            })
-    }
-
     // Checkpointing and results
 
-    fn save_checkpoint(&self) -> Result<(), String> {
-        let checkpoint_path = self.config.output_dir.join("checkpoint.json");
-
-        std::fs::create_dir_all(&self.config.output_dir)
-            .map_err(|e| format!("Failed to create output directory: {}", e))?;
-
-        let candidates = self.candidates.read().unwrap();
-        let json = serde_json::to_string_pretty(&*candidates)
-            .map_err(|e| format!("Failed to serialize checkpoint: {}", e))?;
-
-        std::fs::write(&checkpoint_path, json)
-            .map_err(|e| format!("Failed to write checkpoint: {}", e))?;
-
-        Ok(())
+    async fn save_checkpoint(&self) -> Result<(), String> {
+        let checkpoint = self.checkpoint()?;
+        match &self.checkpoint_store {
+            Some(store) => store.save(&checkpoint).await,
+            None => checkpoint.save(&self.config.output_dir.join("checkpoint.json")),
+        }
     }
 
     fn generate_results(&self) -> Result<HTSResults, String> {
@@ -625,15 +1589,76 @@ impl HTSCampaign {
         Ok(HTSResults {
             campaign_id: self.config.campaign_id,
             campaign_name: self.config.name.clone(),
-            total_candidates_generated: self.total_processed,
+            total_candidates_generated: self.total_generated,
             final_candidates: candidates.clone(),
-            num_stages_completed: self.current_stage_idx + 1,
+            num_stages_completed: self.stages_completed,
             total_time: elapsed,
             timestamp: end_time,
+            hypervolume_history: self.hypervolume.history.clone(),
+            failures: self.failures.clone(),
+            funnel: self.funnel.clone(),
+            eliminated_candidates: self.eliminated.clone(),
+            cancelled: false,
         })
     }
 }
 
+/// A prototype decoration considered during generation
+struct Decoration {
+    prototype: Arc<Prototype>,
+    ions: Vec<Ion>,
+    parent: String,
+    /// Substitution probability from the parent, or plausibility
+    weight: f64,
+    substitution_probability: Option<f64>,
+}
+
+impl Decoration {
+    fn candidate(&self, method: &str) -> Result<Candidate, String> {
+        let structure = self.prototype.decorate(&self.ions).map_err(|e| e.to_string())?;
+        let formula = self.prototype.formula(&self.ions).map_err(|e| e.to_string())?;
+
+        let mut candidate = Candidate::new(formula, self.prototype.composition(&self.ions));
+        candidate.structure = Some(structure);
+        candidate.parent_structure = Some(self.parent.clone());
+        candidate.generation_method = method.to_string();
+        if let Some(t) = self.prototype.tolerance_factor(&self.ions) {
+            candidate.add_property("tolerance_factor".to_string(), t);
+        }
+        match self.substitution_probability {
+            Some(p) => candidate.add_property("substitution_probability".to_string(), p),
+            None => candidate.add_property("plausibility".to_string(), self.weight),
+        }
+        Ok(candidate)
+    }
+}
+
+/// Candidate for an arbitrary structure, named by its reduced formula
+fn structure_candidate(structure: Structure) -> Result<Candidate, String> {
+    let composition = crate::composition::Composition::from_amounts(
+        structure.sites.iter().map(|site| (site.element.as_str(), 1.0)),
+    )
+    .map_err(|e| e.to_string())?;
+    let (reduced, _) = composition.reduced();
+    let counts = reduced.integer_counts().map_err(|e| e.to_string())?.into_iter().collect();
+
+    let mut candidate = Candidate::new(reduced.hill_formula(), counts);
+    candidate.structure = Some(structure);
+    Ok(candidate)
+}
+
+/// Weighted sampling without replacement (Efraimidis-Spirakis keys)
+fn weighted_sample(weights: &[f64], k: usize, rng: &mut StdRng) -> Vec<usize> {
+    let mut keyed: Vec<(f64, usize)> = weights
+        .iter()
+        .enumerate()
+        .filter(|(_, &w)| w > 0.0)
+        .map(|(i, &w)| (rng.gen::<f64>().ln() / w, i))
+        .collect();
+    keyed.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    keyed.into_iter().take(k).map(|(_, i)| i).collect()
+}
+
 // ============================================================================
 // RESULTS
 // ============================================================================
@@ -648,6 +1673,21 @@ pub struct HTSResults {
     pub num_stages_completed: usize,
     pub total_time: f64, // seconds
     pub timestamp: DateTime<Utc>,
+    /// Hypervolume after each stage that declared objectives
+    #[serde(default)]
+    pub hypervolume_history: Vec<HypervolumeRecord>,
+    /// Evaluations that failed, across all stages
+    #[serde(default)]
+    pub failures: Vec<CandidateFailure>,
+    /// Candidate counts per completed stage
+    #[serde(default)]
+    pub funnel: Vec<StageFunnel>,
+    /// Candidates dropped by a stage, with their history up to that stage
+    #[serde(default)]
+    pub eliminated_candidates: Vec<Candidate>,
+    /// The campaign was cancelled before finishing
+    #[serde(default)]
+    pub cancelled: bool,
 }
 
 impl HTSResults {
@@ -664,6 +1704,30 @@ impl HTSResults {
         sorted
     }
 
+    /// Non-dominated final candidates for the given objectives
+    pub fn pareto_front(&self, objectives: &[Objective]) -> Vec<&Candidate> {
+        let (members, utilities): (Vec<&Candidate>, Vec<Vec<f64>>) = self.final_candidates.iter()
+            .filter_map(|c| c.objective_utilities(objectives).map(|u| (c, u)))
+            .unzip();
+        pareto::pareto_front(&utilities).into_iter().map(|i| members[i]).collect()
+    }
+
+    /// Final candidates in NSGA-II order, paired with their front index
+    pub fn pareto_ranking(&self, objectives: &[Objective]) -> Vec<(&Candidate, usize)> {
+        let (members, utilities): (Vec<&Candidate>, Vec<Vec<f64>>) = self.final_candidates.iter()
+            .filter_map(|c| c.objective_utilities(objectives).map(|u| (c, u)))
+            .unzip();
+        pareto::nsga2_ranking(&utilities).into_iter().map(|r| (members[r.index], r.front)).collect()
+    }
+
+    /// Hypervolume of the final candidates' utilities against `reference`
+    pub fn hypervolume(&self, objectives: &[Objective], reference: &[f64]) -> f64 {
+        let utilities: Vec<Vec<f64>> = self.final_candidates.iter()
+            .filter_map(|c| c.objective_utilities(objectives))
+            .collect();
+        pareto::hypervolume(&utilities, reference)
+    }
+
     /// Export results to JSON file
     pub fn save_to_file(&self, path: &PathBuf) -> Result<(), String> {
         let json = serde_json::to_string_pretty(self)
@@ -675,6 +1739,18 @@ impl HTSResults {
         Ok(())
     }
 
+    pub fn load_from_file(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read results {}: {}", path.display(), e))?;
+        serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse results {}: {}", path.display(), e))
+    }
+
+    /// Every screened candidate, survivors first
+    pub fn all_candidates(&self) -> impl Iterator<Item = &Candidate> {
+        self.final_candidates.iter().chain(&self.eliminated_candidates)
+    }
+
     /// Generate summary report
     pub fn summary(&self) -> String {
         let mut report = String::new();
@@ -708,6 +1784,10 @@ impl HTSResults {
 mod tests {
     use super::*;
 
+    fn pool(elements: &[&str]) -> Vec<String> {
+        elements.iter().map(|e| e.to_string()).collect()
+    }
+
     #[test]
     fn test_property_filter() {
         let filter = PropertyFilter::new(
@@ -746,6 +1826,46 @@ mod tests {
         assert!(candidate.passes_filters(&filters));
     }
 
+    #[test]
+    fn test_economic_filters_use_composition() {
+        let comp = |pairs: &[(&str, usize)]| pairs.iter().map(|(e, n)| (e.to_string(), *n)).collect();
+        let cheap = Candidate::new("Fe2O3".to_string(), comp(&[("Fe", 2), ("O", 3)]));
+        let precious = Candidate::new("PtO2".to_string(), comp(&[("Pt", 1), ("O", 2)]));
+        let filters = vec![
+            PropertyFilter::new(economics::COST_PER_KG.to_string(), FilterOperator::LessThan, 100.0),
+            PropertyFilter::new(economics::SUPPLY_RISK.to_string(), FilterOperator::LessOrEqual, 0.5),
+        ];
+
+        assert!(cheap.passes_filters(&filters));
+        assert!(!precious.passes_filters(&filters));
+
+        // Stored values take precedence over the computed ones
+        let mut annotated = precious.clone();
+        annotated.annotate_economics();
+        assert!(annotated.properties[economics::COST_PER_KG] > 10000.0);
+        annotated.add_property(economics::COST_PER_KG.to_string(), 50.0);
+        annotated.add_property(economics::SUPPLY_RISK.to_string(), 0.1);
+        assert!(annotated.passes_filters(&filters));
+    }
+
+    #[test]
+    fn test_valence_filters() {
+        let comp = |pairs: &[(&str, usize)]| pairs.iter().map(|(e, n)| (e.to_string(), *n)).collect();
+        let balanced = Candidate::new("SrTiO3".to_string(), comp(&[("Sr", 1), ("Ti", 1), ("O", 3)]));
+        let unbalanced = Candidate::new("SrTiO2".to_string(), comp(&[("Sr", 1), ("Ti", 1), ("O", 2)]));
+        let charge = vec![PropertyFilter::new(oxidation::CHARGE_BALANCED.to_string(), FilterOperator::Equal, 1.0)];
+
+        assert!(balanced.passes_filters(&charge));
+        assert!(!unbalanced.passes_filters(&charge));
+
+        // Without a structure there is no instability index to filter on
+        let gii = vec![PropertyFilter::new(oxidation::GLOBAL_INSTABILITY_INDEX.to_string(), FilterOperator::LessThan, 0.2)];
+        assert!(!balanced.passes_filters(&gii));
+        let mut annotated = balanced.clone();
+        annotated.annotate_valence();
+        assert_eq!(annotated.properties[oxidation::CHARGE_BALANCED], 1.0);
+    }
+
     #[test]
     fn test_hts_config_creation() {
         let config = HTSConfig::new("Test Campaign".to_string())
@@ -755,4 +1875,552 @@ mod tests {
         assert_eq!(config.max_candidates, 1000);
         assert_eq!(config.base_structures.len(), 1);
     }
+
+    #[test]
+    fn test_pareto_results() {
+        let candidate = |formula: &str, band_gap: f64, energy: f64| {
+            let mut c = Candidate::new(formula.to_string(), HashMap::new());
+            c.add_property("band_gap".to_string(), band_gap);
+            c.add_property("formation_energy".to_string(), energy);
+            c
+        };
+        let results = HTSResults {
+            campaign_id: Uuid::new_v4(),
+            campaign_name: "pareto".to_string(),
+            total_candidates_generated: 4,
+            final_candidates: vec![
+                candidate("A", 2.0, -1.0),
+                candidate("B", 1.0, -2.0),
+                candidate("C", 0.5, -0.5), // dominated by both
+                candidate("D", 3.0, -0.2),
+            ],
+            num_stages_completed: 1,
+            total_time: 0.0,
+            timestamp: Utc::now(),
+            hypervolume_history: vec![],
+            failures: vec![],
+            funnel: vec![],
+            eliminated_candidates: vec![],
+            cancelled: false,
+        };
+        let objectives = vec![Objective::maximize("band_gap"), Objective::minimize("formation_energy")];
+
+        let front: Vec<&str> = results.pareto_front(&objectives).iter().map(|c| c.formula.as_str()).collect();
+        assert_eq!(front.len(), 3);
+        assert!(!front.contains(&"C"));
+
+        let ranking = results.pareto_ranking(&objectives);
+        assert_eq!(ranking.last().map(|(c, front)| (c.formula.as_str(), *front)), Some(("C", 1)));
+
+        // Reference (0, 0) in utility space: band_gap > 0, -formation_energy > 0
+        let hv = results.hypervolume(&objectives, &[0.0, 0.0]);
+        let expected = 1.0 * 2.0 + (2.0 - 1.0) * 1.0 + (3.0 - 2.0) * 0.2;
+        assert!((hv - expected).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_stage_dispatch() {
+        let predictor = Arc::new(MLPredictor::new());
+        for (formula, band_gap) in [("CaTiO3", 3.5), ("SrTiO3", 3.2), ("BaTiO3", 3.0), ("TiO2", 3.1), ("CaO", 6.0)] {
+            predictor.add_training_formula("band_gap".to_string(), Uuid::new_v4(), formula, band_gap).await.unwrap();
+        }
+        predictor.train_model("band_gap".to_string()).await.unwrap();
+        let gnn = Arc::new(GNNEngine::new());
+        let mut training = Vec::new();
+        for (formula, elements, bulk_modulus) in [("CaTiO3", ["Ca", "Ti", "O"], 170.0), ("SrTiO3", ["Sr", "Ti", "O"], 180.0)] {
+            let id = Uuid::new_v4();
+            let composition = elements.iter().zip([1, 1, 3]).map(|(e, n)| (e.to_string(), n)).collect();
+            gnn.create_composition_graph(id, formula, &composition).await.unwrap();
+            training.push((id, bulk_modulus));
+        }
+        gnn.train_model("bulk_modulus".to_string(), training).await.unwrap();
+        let (dft, queue) = DftStageEvaluator::new(4);
+
+        let worker = tokio::spawn(async move {
+            let mut jobs = 0;
+            while let Some(job) = queue.next_job().await {
+                assert_eq!(job.calc_type, CalculationType::GeometryOpt);
+                assert_eq!(job.properties, vec!["band_gap".to_string()]);
+                let mut result = crate::quantum::DFTResult::new(job.material.id, job.calc_type);
+                assert_eq!(job.material.structure.sites.len(), 5);
+                result.converged = !job.material.formula.starts_with("Ca");
+                result.band_gap = Some(if job.material.formula.starts_with("Sr") { 3.2 } else { 0.5 });
+                result.total_energy = Some(-10.0);
+                job.complete(Ok(result));
+                jobs += 1;
+            }
+            jobs
+        });
+
+        let mut config = HTSConfig::new("dispatch".to_string())
+            .with_base_structures(vec!["perovskite".to_string()])
+            .with_generation(GenerationSettings::default().with_element_pool(pool(&["Ca", "Sr", "Ba", "Ti", "O"])))
+            .with_stages(vec![
+                ScreeningStage::new(
+                    "ml".to_string(),
+                    StageType::MLPrediction { model_name: "band_gap".to_string() },
+                ),
+                ScreeningStage::new("gnn".to_string(), StageType::GNNPrediction),
+                ScreeningStage::new(
+                    "dft".to_string(),
+                    StageType::DFTCalculation { calc_type: "relax".to_string() },
+                )
+                .with_filter(PropertyFilter::new("band_gap".to_string(), FilterOperator::GreaterThan, 1.0)),
+            ]);
+        config.enable_checkpointing = false;
+
+        let mut campaign = HTSCampaign::new(config)
+            .with_evaluator(Arc::new(MLStageEvaluator::new(predictor)))
+            .with_evaluator(Arc::new(GnnStageEvaluator::new(gnn.clone(), vec!["bulk_modulus".to_string()])))
+            .with_evaluator(Arc::new(dft));
+        let results = campaign.run().await.unwrap();
+        drop(campaign);
+        let gnn_stats = gnn.get_statistics().await;
+
+        // Three titanate perovskites generated; only SrTiO3 clears the DFT gap filter
+        assert_eq!(worker.await.unwrap(), 3);
+        assert_eq!(results.final_candidates.len(), 1);
+        let survivor = &results.final_candidates[0];
+        assert_eq!(survivor.formula, "SrTiO3");
+        assert_eq!(survivor.properties["band_gap"], 3.2);
+        assert_eq!(survivor.properties["total_energy"], -10.0);
+        assert!(survivor.properties.contains_key("band_gap_uncertainty"));
+        assert!((survivor.properties["bulk_modulus"] - 180.0).abs() < 1.0);
+        assert_eq!(survivor.stage_history.len(), 3);
+        // Candidate graphs are dropped after prediction; the training graphs stay
+        assert_eq!(gnn_stats.total_graphs, 2);
+    }
+
+    #[tokio::test]
+    async fn test_stage_without_model() {
+        let mut config = HTSConfig::new("missing model".to_string())
+            .with_base_structures(vec!["perovskite".to_string()])
+            .with_generation(GenerationSettings::default().with_element_pool(pool(&["Ca", "Sr", "Ba", "Ti", "O"])))
+            .with_stages(vec![ScreeningStage::new(
+                "ml".to_string(),
+                StageType::MLPrediction { model_name: "thermal_conductivity".to_string() },
+            )]);
+        config.enable_checkpointing = false;
+
+        let mut campaign = HTSCampaign::new(config)
+            .with_evaluator(Arc::new(MLStageEvaluator::new(Arc::new(MLPredictor::new()))));
+        let results = campaign.run().await.unwrap();
+        assert!(results.final_candidates.is_empty());
+
+        assert!(MLStageEvaluator::new(Arc::new(MLPredictor::new()))
+            .evaluate(
+                &Candidate::new("Fe2O3".to_string(), HashMap::new()),
+                &ScreeningStage::new("ml".to_string(), StageType::MLPrediction { model_name: "x".to_string() }),
+            )
+            .await
+            .unwrap_err()
+            .contains("No trained model named 'x'"));
+    }
+
+    #[tokio::test]
+    async fn test_candidate_generation() {
+        let settings = GenerationSettings::default()
+            .with_element_pool(pool(&["Ca", "Ba", "Zr", "Hf", "Mg", "O"]))
+            .with_seed(7);
+        let config = HTSConfig::new("generation".to_string())
+            .with_base_structures(vec!["SrTiO3".to_string()])
+            .with_generation(settings);
+
+        let mut campaign = HTSCampaign::new(config.clone());
+        campaign.generate_candidates().await.unwrap();
+        let systematic = campaign.candidates.read().unwrap().clone();
+        let formulas: Vec<&str> = systematic.iter().map(|c| c.formula.as_str()).collect();
+        assert!(formulas.contains(&"BaTiO3") && formulas.contains(&"SrZrO3"));
+        assert!(!formulas.contains(&"SrTiO3") && !formulas.iter().any(|f| f.starts_with("Mg")));
+        for candidate in &systematic {
+            assert_eq!(candidate.structure.as_ref().unwrap().sites.len(), 5);
+            let t = candidate.properties["tolerance_factor"];
+            assert!((0.8..=1.1).contains(&t), "{} t = {}", candidate.formula, t);
+            assert!(candidate.properties["substitution_probability"] >= 0.01);
+        }
+
+        // Random sampling is reproducible for a seed
+        let mut random_config = config.clone();
+        random_config.substitution_strategy = SubstitutionStrategy::Random { num_samples: 3 };
+        let mut sampled = Vec::new();
+        for _ in 0..2 {
+            let mut campaign = HTSCampaign::new(random_config.clone());
+            assert_eq!(campaign.generate_candidates().await.unwrap(), 3);
+            let formulas: Vec<String> = campaign.candidates.read().unwrap().iter().map(|c| c.formula.clone()).collect();
+            sampled.push(formulas);
+        }
+        assert_eq!(sampled[0], sampled[1]);
+
+        // Custom rules: full and single-site substitution on spinel's 16d Al
+        let mut custom_config = HTSConfig::new("custom".to_string())
+            .with_base_structures(vec!["MgAl2O4".to_string()]);
+        custom_config.substitution_strategy = SubstitutionStrategy::Custom {
+            rules: vec![
+                SubstitutionRule { from_element: "Al".to_string(), to_elements: pool(&["Ga", "Ba"]), sites: None },
+                SubstitutionRule { from_element: "Al".to_string(), to_elements: pool(&["Cr"]), sites: Some(vec![8]) },
+            ],
+        };
+        let mut campaign = HTSCampaign::new(custom_config);
+        campaign.generate_candidates().await.unwrap();
+        let custom = campaign.candidates.read().unwrap().clone();
+        let formulas: Vec<&str> = custom.iter().map(|c| c.formula.as_str()).collect();
+        assert_eq!(formulas, vec!["Ga2MgO4", "Al15CrMg8O32"]);
+        assert_eq!(custom[1].composition["Cr"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_pass_through_cap_eliminates() {
+        let objectives = vec![Objective::maximize("tolerance_factor"), Objective::maximize("substitution_probability")];
+        let mut config = HTSConfig::new("capped".to_string())
+            .with_base_structures(vec!["perovskite".to_string()])
+            .with_generation(GenerationSettings::default().with_element_pool(pool(&["Ca", "Sr", "Ba", "Ti", "Zr", "O"])))
+            .with_stages(vec![
+                ScreeningStage::new("pareto".to_string(), StageType::RuleBased)
+                    .with_objectives(objectives)
+                    .with_max_pass_through(2),
+            ]);
+        config.enable_checkpointing = false;
+
+        let results = HTSCampaign::new(config).run().await.unwrap();
+        assert_eq!(results.final_candidates.len(), 2);
+        assert_eq!(results.funnel[0].retained, 2);
+        // Every candidate cut by the cap is kept once among the eliminated
+        let mut ids: Vec<Uuid> = results.final_candidates.iter()
+            .chain(&results.eliminated_candidates)
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(ids.len(), results.total_candidates_generated);
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), results.total_candidates_generated);
+    }
+
+    #[tokio::test]
+    async fn test_ml_guided_generation() {
+        let predictor = Arc::new(MLPredictor::new());
+        predictor.set_model_kind("mean_mass", crate::ml_predictor::ModelKind::RandomForest(Default::default())).await;
+        let training = ["CaTiO3", "SrTiO3", "BaTiO3", "CaZrO3", "SrZrO3", "BaZrO3", "SrHfO3", "BaHfO3", "CaSnO3", "BaSnO3"];
+        for formula in training {
+            let composition = crate::composition::Composition::parse(formula).unwrap();
+            let mean_mass = composition.molar_mass() / composition.num_atoms();
+            predictor.add_training_formula("mean_mass".to_string(), Uuid::new_v4(), formula, mean_mass)
+                .await
+                .unwrap();
+        }
+        predictor.train_model("mean_mass".to_string()).await.unwrap();
+
+        let mut config = HTSConfig::new("ml guided".to_string())
+            .with_base_structures(vec!["perovskite".to_string()])
+            .with_max_candidates(3)
+            .with_generation(GenerationSettings::default()
+                .with_element_pool(pool(&["Ca", "Sr", "Ba", "Ti", "Zr", "Hf", "Sn", "O"])));
+        config.substitution_strategy = SubstitutionStrategy::MLGuided {
+            target_property: "mean_mass".to_string(),
+            target_value: 60.0,
+        };
+
+        let mut unguided = HTSCampaign::new(config.clone());
+        assert!(unguided.generate_candidates().await.is_err()); // no predictor
+
+        let mut campaign = HTSCampaign::new(config).with_predictor(predictor);
+        assert_eq!(campaign.generate_candidates().await.unwrap(), 3);
+        let candidates = campaign.candidates.read().unwrap().clone();
+        assert!(candidates.iter().all(|c| c.generation_method == "ml_guided" && c.structure.is_some()));
+        let mean_distance = candidates.iter()
+            .map(|c| (c.properties["mean_mass"] - 60.0).abs())
+            .sum::<f64>() / 3.0;
+        // Light titanates sit near 27 u/atom, heavy hafnates near 60
+        assert!(mean_distance < 15.0, "mean distance {}", mean_distance);
+    }
+
+    /// Counts evaluations per model, optionally cancelling the campaign once
+    /// a number of evaluations has been reached
+    struct CountingEvaluator {
+        calls: std::sync::Mutex<HashMap<String, usize>>,
+        cancel_after: Option<(usize, CancelHandle)>,
+    }
+
+    impl CountingEvaluator {
+        fn new() -> Arc<Self> {
+            Arc::new(Self { calls: Default::default(), cancel_after: None })
+        }
+
+        fn cancelling(limit: usize, cancel: CancelHandle) -> Arc<Self> {
+            Arc::new(Self { calls: Default::default(), cancel_after: Some((limit, cancel)) })
+        }
+
+        fn calls(&self, model: &str) -> usize {
+            self.calls.lock().unwrap().get(model).copied().unwrap_or(0)
+        }
+    }
+
+    #[async_trait]
+    impl StageEvaluator for CountingEvaluator {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn handles(&self, stage_type: &StageType) -> bool {
+            matches!(stage_type, StageType::MLPrediction { .. })
+        }
+
+        async fn evaluate(&self, candidate: &Candidate, stage: &ScreeningStage) -> Result<StageEvaluation, String> {
+            let StageType::MLPrediction { model_name } = &stage.stage_type else { unreachable!() };
+            let total = {
+                let mut calls = self.calls.lock().unwrap();
+                *calls.entry(model_name.clone()).or_insert(0) += 1;
+                calls.values().sum::<usize>()
+            };
+            if let Some((limit, cancel)) = &self.cancel_after {
+                if total >= *limit {
+                    cancel.cancel();
+                }
+            }
+            let value = candidate.properties["tolerance_factor"];
+            Ok(StageEvaluation { properties: HashMap::from([(model_name.clone(), value)]), score: Some(value) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = HTSConfig::new("resumable".to_string())
+            .with_base_structures(vec!["perovskite".to_string()])
+            .with_generation(GenerationSettings::default().with_element_pool(pool(&["Ca", "Sr", "Ba", "Ti", "O"])))
+            .with_stages(vec![
+                ScreeningStage::new("fast".to_string(), StageType::MLPrediction { model_name: "fast".to_string() }),
+                ScreeningStage::new("slow".to_string(), StageType::MLPrediction { model_name: "slow".to_string() }),
+            ]);
+        config.max_parallel_tasks = 2;
+        config.checkpoint_interval = 2;
+        config.output_dir = dir.path().to_path_buf();
+
+        let mut reference = config.clone();
+        reference.output_dir = dir.path().join("reference");
+        let expected = HTSCampaign::new(reference)
+            .with_evaluator(CountingEvaluator::new())
+            .run()
+            .await
+            .unwrap();
+
+        // Three candidates: the first slow chunk completes, then the campaign
+        // is stopped and dropped as if the process had exited
+        let campaign = HTSCampaign::new(config.clone());
+        let cancel = campaign.cancel_handle();
+        let mut campaign = campaign.with_evaluator(CountingEvaluator::cancelling(3 + 2, cancel));
+        let interrupted = campaign.run().await.unwrap();
+        assert!(interrupted.cancelled);
+        drop(campaign);
+
+        let path = dir.path().join("checkpoint.json");
+        let checkpoint = HTSCheckpoint::load(&path).unwrap();
+        assert_eq!(checkpoint.stages_completed, 1);
+        assert_eq!(checkpoint.current_stage, 1);
+        assert_eq!(checkpoint.total_processed, 3 + 2);
+        assert_eq!(checkpoint.total_generated, 3);
+        assert!(checkpoint.matches(&config).unwrap());
+
+        let resumed_evaluator = CountingEvaluator::new();
+        let mut resumed = HTSCampaign::resume(&path).unwrap().with_evaluator(resumed_evaluator.clone());
+        let results = resumed.run().await.unwrap();
+
+        // Only the candidate left in the interrupted chunk is evaluated again
+        assert_eq!(resumed_evaluator.calls("fast"), 0);
+        assert_eq!(resumed_evaluator.calls("slow"), 1);
+        assert_eq!(results.total_candidates_generated, 3);
+        let formulas = |r: &HTSResults| {
+            let mut f: Vec<String> = r.final_candidates.iter().map(|c| c.formula.clone()).collect();
+            f.sort();
+            f
+        };
+        assert_eq!(formulas(&results), formulas(&expected));
+        assert!(results.final_candidates.iter().all(|c| c.stage_history.len() == 2));
+
+        // A finished campaign resumes to the same results without evaluating
+        let idle = CountingEvaluator::new();
+        let rerun = HTSCampaign::resume(&path).unwrap().with_evaluator(idle.clone()).run().await.unwrap();
+        assert_eq!(formulas(&rerun), formulas(&expected));
+        assert_eq!(idle.calls("fast") + idle.calls("slow"), 0);
+
+        // Edited configs are rejected
+        let mut tampered = checkpoint;
+        tampered.config.max_candidates += 1;
+        assert!(HTSCampaign::from_checkpoint(tampered).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(FileCheckpointStore::new(dir.path()));
+        let config = HTSConfig::new("stored".to_string())
+            .with_base_structures(vec!["perovskite".to_string()])
+            .with_generation(GenerationSettings::default().with_element_pool(pool(&["Ca", "Sr", "Ba", "Ti", "O"])))
+            .with_stages(vec![ScreeningStage::new("fast".to_string(), StageType::RuleBased)]);
+        let campaign_id = config.campaign_id;
+
+        assert!(HTSCampaign::resume_from_store(store.clone(), campaign_id).await.is_err());
+        HTSCampaign::new(config)
+            .with_checkpoint_store(store.clone())
+            .run()
+            .await
+            .unwrap();
+        assert!(store.path(campaign_id).exists());
+        assert!(!dir.path().join("checkpoint.json").exists());
+
+        let resumed = HTSCampaign::resume_from_store(store, campaign_id).await.unwrap();
+        assert_eq!(resumed.stages_completed, 1);
+        assert_eq!(resumed.candidates.read().unwrap().len(), 3);
+    }
+
+    /// Tracks concurrency; fails, panics or cancels on request
+    #[derive(Default)]
+    struct ProbeEvaluator {
+        in_flight: std::sync::atomic::AtomicUsize,
+        max_in_flight: std::sync::atomic::AtomicUsize,
+        calls: std::sync::atomic::AtomicUsize,
+        fail: Vec<&'static str>,
+        panic: Vec<&'static str>,
+        nan_score: Vec<&'static str>,
+        cancel: Option<CancelHandle>,
+    }
+
+    #[async_trait]
+    impl StageEvaluator for ProbeEvaluator {
+        fn name(&self) -> &str {
+            "probe"
+        }
+
+        fn handles(&self, _stage_type: &StageType) -> bool {
+            true
+        }
+
+        async fn evaluate(&self, candidate: &Candidate, _stage: &ScreeningStage) -> Result<StageEvaluation, String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if let Some(cancel) = &self.cancel {
+                cancel.cancel();
+            }
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            if self.fail.contains(&candidate.formula.as_str()) {
+                return Err(format!("{} did not converge", candidate.formula));
+            }
+            assert!(!self.panic.contains(&candidate.formula.as_str()), "evaluator crashed");
+            if self.nan_score.contains(&candidate.formula.as_str()) {
+                return Ok(StageEvaluation { score: Some(f64::NAN), ..Default::default() });
+            }
+            Ok(StageEvaluation::default())
+        }
+    }
+
+    fn executor_config(name: &str) -> HTSConfig {
+        let mut config = HTSConfig::new(name.to_string())
+            .with_base_structures(vec!["perovskite".to_string()])
+            .with_generation(GenerationSettings::default().with_element_pool(pool(&["Ca", "Sr", "Ba", "Ti", "Zr", "O"])))
+            .with_stages(vec![ScreeningStage::new(
+                "dft".to_string(),
+                StageType::DFTCalculation { calc_type: "scf".to_string() },
+            )])
+            .with_stage_concurrency("dft_calculation", 2);
+        config.enable_checkpointing = false;
+        config
+    }
+
+    #[tokio::test]
+    async fn test_executor_progress_and_failures() {
+        let config = executor_config("executor");
+        assert_eq!(config.concurrency_for(&StageType::DFTCalculation { calc_type: "relax".to_string() }), 2);
+        let ml = StageType::MLPrediction { model_name: "x".to_string() };
+        assert_eq!(config.concurrency_for(&ml), config.max_parallel_tasks);
+        let mut narrow = config.clone();
+        narrow.max_parallel_tasks = 3;
+        assert_eq!(narrow.concurrency_for(&ml), 3);
+
+        let probe = Arc::new(ProbeEvaluator {
+            fail: vec!["CaZrO3"],
+            panic: vec!["BaTiO3"],
+            ..Default::default()
+        });
+        let mut campaign = HTSCampaign::new(config).with_evaluator(probe.clone());
+        let mut events = campaign.subscribe();
+        let results = campaign.run().await.unwrap();
+
+        let total = probe.calls.load(Ordering::SeqCst);
+        assert!(total > 2);
+        assert_eq!(probe.max_in_flight.load(Ordering::SeqCst), 2);
+        assert!(!results.cancelled);
+
+        // Both failures are captured per candidate; the rest pass
+        let mut failed: Vec<&str> = results.failures.iter().map(|f| f.formula.as_str()).collect();
+        failed.sort();
+        assert_eq!(failed, vec!["BaTiO3", "CaZrO3"]);
+        assert!(results.failures.iter().any(|f| f.error.contains("did not converge")));
+        assert!(results.failures.iter().any(|f| f.error.contains("panicked")));
+        assert_eq!(results.final_candidates.len(), total - 2);
+
+        let mut progress = Vec::new();
+        let mut completed = None;
+        while let Ok(event) = events.try_recv() {
+            match event {
+                HTSEvent::Progress(p) => progress.push(p),
+                HTSEvent::StageCompleted(p) => completed = Some(p),
+                _ => {}
+            }
+        }
+        assert_eq!(progress.len(), total);
+        assert!(progress.windows(2).all(|w| w[1].processed == w[0].processed + 1));
+        assert_eq!(progress.last().unwrap().eta, Some(0.0));
+        let completed = completed.unwrap();
+        assert_eq!((completed.processed, completed.passed, completed.failed), (total, total - 2, 2));
+    }
+
+    #[tokio::test]
+    async fn test_non_finite_scores_fail_candidates() {
+        let mut config = executor_config("nan");
+        config.stages[0].max_pass_through = Some(2);
+        let probe = Arc::new(ProbeEvaluator { nan_score: vec!["BaTiO3"], ..Default::default() });
+        let results = HTSCampaign::new(config).with_evaluator(probe).run().await.unwrap();
+
+        assert_eq!(results.failures.len(), 1);
+        assert_eq!(results.failures[0].formula, "BaTiO3");
+        assert!(results.failures[0].error.contains("non-finite score"));
+        assert_eq!(results.final_candidates.len(), 2);
+        assert!(results.top_candidates(10).iter().all(|c| c.overall_score.map_or(true, f64::is_finite)));
+    }
+
+    #[tokio::test]
+    async fn test_executor_cancellation() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = executor_config("cancel");
+        config.enable_checkpointing = true;
+        config.output_dir = dir.path().to_path_buf();
+
+        let campaign = HTSCampaign::new(config);
+        let probe = Arc::new(ProbeEvaluator { cancel: Some(campaign.cancel_handle()), ..Default::default() });
+        let mut campaign = campaign.with_evaluator(probe.clone());
+        let mut events = campaign.subscribe();
+        let results = campaign.run().await.unwrap();
+
+        // The first dispatch window finishes; nothing else starts
+        assert!(results.cancelled);
+        assert_eq!(results.num_stages_completed, 0);
+        assert_eq!(probe.calls.load(Ordering::SeqCst), 2);
+        let mut saw_cancel = false;
+        while let Ok(event) = events.try_recv() {
+            saw_cancel |= matches!(event, HTSEvent::Cancelled { stage_index: 0 });
+            assert!(!matches!(event, HTSEvent::Finished { .. } | HTSEvent::StageCompleted(_)));
+        }
+        assert!(saw_cancel);
+
+        let resumed_probe = Arc::new(ProbeEvaluator::default());
+        let mut resumed = HTSCampaign::resume(dir.path().join("checkpoint.json"))
+            .unwrap()
+            .with_evaluator(resumed_probe.clone());
+        let results = resumed.run().await.unwrap();
+        assert!(!results.cancelled);
+        assert_eq!(results.num_stages_completed, 1);
+        assert_eq!(results.final_candidates.len(), resumed_probe.calls.load(Ordering::SeqCst) + 2);
+    }
 }
@@ -0,0 +1,665 @@
+//! Export and reporting for HTS results
+//!
+//! Works offline from a saved [`HTSResults`] file. This module produces three tables:
+//! - the stage funnel;
+//! - property distributions of passing vs. failing candidates per stage;
+//! - the top candidates.
+//!
+//! Each table is written as CSV and, with the `parquet` feature, as
+//! Parquet. A self-contained HTML report (inline CSS and SVG, no scripts)
+//! shows the same data.
+
+use crate::hts::{Candidate, HTSResults};
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+// ============================================================================
+// TABLES
+// ============================================================================
+
+/// A table cell
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Text(String),
+    Integer(i64),
+    Number(f64),
+    Empty,
+}
+
+impl Cell {
+    fn csv(&self) -> String {
+        match self {
+            Cell::Text(text) if text.contains([',', '"', '\n', '\r']) => {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            }
+            Cell::Text(text) => text.clone(),
+            Cell::Integer(value) => value.to_string(),
+            Cell::Number(value) if value.is_finite() => value.to_string(),
+            Cell::Number(_) | Cell::Empty => String::new(),
+        }
+    }
+
+    fn display(&self) -> String {
+        match self {
+            Cell::Text(text) => text.clone(),
+            Cell::Integer(value) => value.to_string(),
+            Cell::Number(value) => format_number(*value),
+            Cell::Empty => String::new(),
+        }
+    }
+}
+
+/// Rows with named columns, written as CSV or Parquet
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    fn new(name: &str, columns: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// RFC 4180 CSV with a header row
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        let header: Vec<String> = self.columns.iter().map(|c| Cell::Text(c.clone()).csv()).collect();
+        csv.push_str(&header.join(","));
+        csv.push('\n');
+        for row in &self.rows {
+            let cells: Vec<String> = row.iter().map(Cell::csv).collect();
+            csv.push_str(&cells.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    pub fn write_csv(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, self.to_csv())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Write as Parquet
+    ///
+    /// Columns holding only integers become Int64; columns of numbers
+    /// become Float64; anything else is written as strings.
+    #[cfg(feature = "parquet")]
+    pub fn write_parquet(&self, path: &Path) -> Result<(), String> {
+        use polars::prelude::*;
+
+        let columns: Vec<Column> = self.columns.iter().enumerate().map(|(j, name)| {
+            let cells: Vec<&Cell> = self.rows.iter().map(|row| &row[j]).collect();
+            let name: PlSmallStr = name.as_str().into();
+            if cells.iter().all(|c| matches!(c, Cell::Integer(_) | Cell::Empty)) {
+                let values: Vec<Option<i64>> = cells.iter()
+                    .map(|c| match c { Cell::Integer(v) => Some(*v), _ => None })
+                    .collect();
+                Column::new(name, values)
+            } else if cells.iter().all(|c| matches!(c, Cell::Integer(_) | Cell::Number(_) | Cell::Empty)) {
+                let values: Vec<Option<f64>> = cells.iter()
+                    .map(|c| match c {
+                        Cell::Integer(v) => Some(*v as f64),
+                        Cell::Number(v) => Some(*v),
+                        _ => None,
+                    })
+                    .collect();
+                Column::new(name, values)
+            } else {
+                let values: Vec<Option<String>> = cells.iter()
+                    .map(|c| match c { Cell::Empty => None, c => Some(c.display()) })
+                    .collect();
+                Column::new(name, values)
+            }
+        }).collect();
+
+        let mut frame = DataFrame::new(columns).map_err(|e| format!("Failed to build table {}: {}", self.name, e))?;
+        let file = std::fs::File::create(path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        ParquetWriter::new(file)
+            .finish(&mut frame)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "parquet"))]
+    pub fn write_parquet(&self, _path: &Path) -> Result<(), String> {
+        Err("Parquet export requires the `parquet` feature".to_string())
+    }
+}
+
+// ============================================================================
+// DISTRIBUTIONS
+// ============================================================================
+
+/// Values of one property among candidates that passed or failed a stage
+#[derive(Debug, Clone)]
+pub struct PropertyDistribution {
+    pub stage_name: String,
+    pub property: String,
+    pub passed: Vec<f64>,
+    pub failed: Vec<f64>,
+}
+
+/// Histogram over bins shared by the passing and failing values
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// `bins + 1` edges
+    pub edges: Vec<f64>,
+    pub passed: Vec<usize>,
+    pub failed: Vec<usize>,
+}
+
+impl PropertyDistribution {
+    pub fn histogram(&self, bins: usize) -> Histogram {
+        let bins = bins.max(1);
+        let values = self.passed.iter().chain(&self.failed);
+        let min = values.clone().copied().fold(f64::INFINITY, f64::min);
+        let max = values.copied().fold(f64::NEG_INFINITY, f64::max);
+        if !min.is_finite() {
+            return Histogram { edges: vec![], passed: vec![], failed: vec![] };
+        }
+        // A constant property is spread over a unit-wide range around its value
+        let (min, width) = if max > min { (min, (max - min) / bins as f64) } else { (min - 0.5, 1.0 / bins as f64) };
+
+        let count = |values: &[f64]| {
+            let mut counts = vec![0; bins];
+            for v in values {
+                let bin = ((v - min) / width).floor() as usize;
+                counts[bin.min(bins - 1)] += 1;
+            }
+            counts
+        };
+        Histogram {
+            edges: (0..=bins).map(|i| min + i as f64 * width).collect(),
+            passed: count(&self.passed),
+            failed: count(&self.failed),
+        }
+    }
+}
+
+/// Stages in funnel order, as (id, name)
+fn stages(results: &HTSResults) -> Vec<(Uuid, String)> {
+    if !results.funnel.is_empty() {
+        return results.funnel.iter().map(|f| (f.stage_id, f.stage_name.clone())).collect();
+    }
+    // Results written before funnels were recorded: order of first appearance
+    let mut stages: Vec<(Uuid, String)> = Vec::new();
+    for result in results.all_candidates().flat_map(|c| &c.stage_history) {
+        if !stages.iter().any(|(id, _)| *id == result.stage_id) {
+            stages.push((result.stage_id, result.stage_name.clone()));
+        }
+    }
+    stages
+}
+
+/// Per stage and property, values of candidates that passed vs. failed
+///
+/// Candidates whose evaluation errored are left out; they have no
+/// meaningful values for the stage.
+pub fn property_distributions(results: &HTSResults) -> Vec<PropertyDistribution> {
+    let mut distributions = Vec::new();
+    for (stage_id, stage_name) in stages(results) {
+        let evaluated: Vec<(&Candidate, bool, &[String])> = results.all_candidates()
+            .filter_map(|c| c.stage_result(stage_id).map(|r| (c, r)))
+            .filter(|(_, r)| r.error.is_none())
+            .map(|(c, r)| (c, r.passed, r.properties_calculated.as_slice()))
+            .collect();
+        let properties: BTreeSet<&String> = evaluated.iter().flat_map(|(_, _, props)| props.iter()).collect();
+
+        for property in properties {
+            let mut distribution = PropertyDistribution {
+                stage_name: stage_name.clone(),
+                property: property.clone(),
+                passed: vec![],
+                failed: vec![],
+            };
+            for (candidate, passed, props) in &evaluated {
+                let Some(&value) = candidate.properties.get(property) else { continue };
+                if !value.is_finite() || !props.contains(property) {
+                    continue;
+                }
+                if *passed {
+                    distribution.passed.push(value);
+                } else {
+                    distribution.failed.push(value);
+                }
+            }
+            distributions.push(distribution);
+        }
+    }
+    distributions
+}
+
+// ============================================================================
+// EXPORT TABLES
+// ============================================================================
+
+/// Candidates entering, passing and kept at each stage
+pub fn funnel_table(results: &HTSResults) -> Table {
+    let mut table = Table::new(
+        "funnel",
+        &["stage", "entered", "passed", "failed", "rejected", "retained", "pass_rate"],
+    );
+    for stage in &results.funnel {
+        let rejected = stage.entered.saturating_sub(stage.passed + stage.failed);
+        let pass_rate = if stage.entered > 0 { stage.passed as f64 / stage.entered as f64 } else { 0.0 };
+        table.rows.push(vec![
+            Cell::Text(stage.stage_name.clone()),
+            Cell::Integer(stage.entered as i64),
+            Cell::Integer(stage.passed as i64),
+            Cell::Integer(stage.failed as i64),
+            Cell::Integer(rejected as i64),
+            Cell::Integer(stage.retained as i64),
+            Cell::Number(pass_rate),
+        ]);
+    }
+    table
+}
+
+/// Histograms in long format, one row per stage, property, outcome and bin
+pub fn distribution_table(results: &HTSResults, bins: usize) -> Table {
+    let mut table = Table::new(
+        "distributions",
+        &["stage", "property", "outcome", "bin_start", "bin_end", "count"],
+    );
+    for distribution in property_distributions(results) {
+        let histogram = distribution.histogram(bins);
+        for (outcome, counts) in [("passed", &histogram.passed), ("failed", &histogram.failed)] {
+            for (i, &count) in counts.iter().enumerate() {
+                table.rows.push(vec![
+                    Cell::Text(distribution.stage_name.clone()),
+                    Cell::Text(distribution.property.clone()),
+                    Cell::Text(outcome.to_string()),
+                    Cell::Number(histogram.edges[i]),
+                    Cell::Number(histogram.edges[i + 1]),
+                    Cell::Integer(count as i64),
+                ]);
+            }
+        }
+    }
+    table
+}
+
+/// Top candidates by score, one column per property
+pub fn top_candidates_table(results: &HTSResults, n: usize) -> Table {
+    let top = results.top_candidates(n);
+    let properties: BTreeSet<&String> = top.iter().flat_map(|c| c.properties.keys()).collect();
+
+    let mut columns = vec!["rank", "id", "formula", "parent_structure", "generation_method", "score"];
+    columns.extend(properties.iter().map(|p| p.as_str()));
+    let mut table = Table::new("top_candidates", &columns);
+
+    for (rank, candidate) in top.iter().enumerate() {
+        let mut row = vec![
+            Cell::Integer(rank as i64 + 1),
+            Cell::Text(candidate.id.to_string()),
+            Cell::Text(candidate.formula.clone()),
+            candidate.parent_structure.clone().map_or(Cell::Empty, Cell::Text),
+            Cell::Text(candidate.generation_method.clone()),
+            candidate.overall_score.map_or(Cell::Empty, Cell::Number),
+        ];
+        row.extend(properties.iter().map(|p| candidate.properties.get(*p).map_or(Cell::Empty, |v| Cell::Number(*v))));
+        table.rows.push(row);
+    }
+    table
+}
+
+// ============================================================================
+// HTML REPORT
+// ============================================================================
+
+const PASS_COLOR: &str = "#2e8b57";
+const FAIL_COLOR: &str = "#c0392b";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn format_number(value: f64) -> String {
+    if !value.is_finite() {
+        String::new()
+    } else if value.fract() == 0.0 && value.abs() < 1e9 {
+        format!("{}", value as i64)
+    } else if value.abs() >= 1e4 || value.abs() < 1e-3 {
+        format!("{:.3e}", value)
+    } else {
+        format!("{:.4}", value)
+    }
+}
+
+fn html_table(table: &Table) -> String {
+    let mut html = String::from("<table><thead><tr>");
+    for column in &table.columns {
+        let _ = write!(html, "<th>{}</th>", escape(column));
+    }
+    html.push_str("</tr></thead><tbody>");
+    for row in &table.rows {
+        html.push_str("<tr>");
+        for cell in row {
+            let class = if matches!(cell, Cell::Text(_)) { "" } else { " class=\"num\"" };
+            let _ = write!(html, "<td{}>{}</td>", class, escape(&cell.display()));
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</tbody></table>");
+    html
+}
+
+/// Horizontal bars: entered (grey) with the passed share overlaid
+fn funnel_svg(results: &HTSResults) -> String {
+    let width = 640.0;
+    let label_width = 160.0;
+    let row_height = 34.0;
+    let bar_width = width - label_width - 120.0;
+    let max = results.funnel.iter().map(|f| f.entered).max().unwrap_or(0).max(1) as f64;
+    let height = row_height * results.funnel.len() as f64 + 10.0;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+        w = width, h = height
+    );
+    for (i, stage) in results.funnel.iter().enumerate() {
+        let y = 5.0 + i as f64 * row_height;
+        let entered = bar_width * stage.entered as f64 / max;
+        let passed = bar_width * stage.passed as f64 / max;
+        let _ = write!(
+            svg,
+            "<text x=\"0\" y=\"{ty}\" class=\"label\">{name}</text>\
+             <rect x=\"{x}\" y=\"{y}\" width=\"{entered:.1}\" height=\"24\" fill=\"#d5d8dc\"/>\
+             <rect x=\"{x}\" y=\"{y}\" width=\"{passed:.1}\" height=\"24\" fill=\"{color}\"/>\
+             <text x=\"{tx:.1}\" y=\"{ty}\" class=\"label\">{p} / {e}</text>",
+            ty = y + 17.0,
+            name = escape(&stage.stage_name),
+            x = label_width,
+            color = PASS_COLOR,
+            tx = label_width + entered + 6.0,
+            p = stage.passed,
+            e = stage.entered,
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Side-by-side passed/failed bars per bin
+fn histogram_svg(histogram: &Histogram) -> String {
+    let (width, height, margin) = (300.0, 150.0, 20.0);
+    let bins = histogram.passed.len();
+    let max = histogram.passed.iter().chain(&histogram.failed).copied().max().unwrap_or(0).max(1) as f64;
+    let slot = (width - 2.0 * margin) / bins.max(1) as f64;
+    let plot_height = height - 2.0 * margin;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+        w = width, h = height
+    );
+    for i in 0..bins {
+        for (k, (counts, color)) in [(&histogram.passed, PASS_COLOR), (&histogram.failed, FAIL_COLOR)].into_iter().enumerate() {
+            let bar = plot_height * counts[i] as f64 / max;
+            let _ = write!(
+                svg,
+                "<rect x=\"{x:.1}\" y=\"{y:.1}\" width=\"{bw:.1}\" height=\"{bar:.1}\" fill=\"{color}\"><title>{n}</title></rect>",
+                x = margin + i as f64 * slot + k as f64 * slot / 2.0,
+                y = margin + plot_height - bar,
+                bw = slot / 2.0 - 1.0,
+                n = counts[i],
+            );
+        }
+    }
+    let _ = write!(
+        svg,
+        "<line x1=\"{m}\" y1=\"{b}\" x2=\"{r}\" y2=\"{b}\" stroke=\"#555\"/>\
+         <text x=\"{m}\" y=\"{t}\" class=\"axis\">{lo}</text>\
+         <text x=\"{r}\" y=\"{t}\" class=\"axis\" text-anchor=\"end\">{hi}</text>\
+         <text x=\"{m}\" y=\"12\" class=\"axis\">max {max}</text>",
+        m = margin,
+        r = width - margin,
+        b = height - margin,
+        t = height - 5.0,
+        lo = format_number(histogram.edges.first().copied().unwrap_or(0.0)),
+        hi = format_number(histogram.edges.last().copied().unwrap_or(0.0)),
+        max = max as usize,
+    );
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Self-contained HTML report: summary, funnel, histograms, failures and top candidates
+pub fn html_report(results: &HTSResults, top_n: usize, bins: usize) -> String {
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\"><head><meta charset=\"utf-8\">\
+         <title>HTS report: {name}</title><style>\
+         body{{font-family:sans-serif;margin:2em;color:#222}}\
+         table{{border-collapse:collapse;font-size:0.9em}}\
+         th,td{{border:1px solid #ccc;padding:3px 8px}}td.num{{text-align:right}}\
+         .grid{{display:flex;flex-wrap:wrap;gap:1.5em}}figure{{margin:0}}\
+         figcaption{{font-size:0.85em}}.label{{font-size:13px}}.axis{{font-size:10px}}\
+         .pass{{color:{pass}}}.fail{{color:{fail}}}\
+         </style></head><body>",
+        name = escape(&results.campaign_name),
+        pass = PASS_COLOR,
+        fail = FAIL_COLOR,
+    );
+
+    let _ = write!(
+        html,
+        "<h1>{name}</h1><p>Campaign {id} &middot; {generated} candidates screened &middot; \
+         {final_count} final &middot; {stages} stages &middot; {time:.1} s &middot; {timestamp}{cancelled}</p>",
+        name = escape(&results.campaign_name),
+        id = results.campaign_id,
+        generated = results.total_candidates_generated,
+        final_count = results.final_candidates.len(),
+        stages = results.num_stages_completed,
+        time = results.total_time,
+        timestamp = results.timestamp.to_rfc3339(),
+        cancelled = if results.cancelled { " &middot; <strong>cancelled</strong>" } else { "" },
+    );
+
+    if !results.funnel.is_empty() {
+        let _ = write!(html, "<h2>Stage funnel</h2>{}{}", funnel_svg(results), html_table(&funnel_table(results)));
+    }
+
+    let distributions = property_distributions(results);
+    if !distributions.is_empty() {
+        html.push_str(
+            "<h2>Property distributions</h2>\
+             <p><span class=\"pass\">&#9632; passed</span> <span class=\"fail\">&#9632; failed</span></p>",
+        );
+        let mut current_stage: Option<&str> = None;
+        for distribution in &distributions {
+            if current_stage != Some(distribution.stage_name.as_str()) {
+                if current_stage.is_some() {
+                    html.push_str("</div>");
+                }
+                let _ = write!(html, "<h3>{}</h3><div class=\"grid\">", escape(&distribution.stage_name));
+                current_stage = Some(&distribution.stage_name);
+            }
+            let _ = write!(
+                html,
+                "<figure>{svg}<figcaption>{property} ({passed} passed, {failed} failed)</figcaption></figure>",
+                svg = histogram_svg(&distribution.histogram(bins)),
+                property = escape(&distribution.property),
+                passed = distribution.passed.len(),
+                failed = distribution.failed.len(),
+            );
+        }
+        html.push_str("</div>");
+    }
+
+    if !results.failures.is_empty() {
+        let mut failures = Table::new("failures", &["stage", "formula", "error"]);
+        for failure in &results.failures {
+            failures.rows.push(vec![
+                Cell::Text(failure.stage_name.clone()),
+                Cell::Text(failure.formula.clone()),
+                Cell::Text(failure.error.clone()),
+            ]);
+        }
+        let _ = write!(html, "<h2>Evaluation failures</h2>{}", html_table(&failures));
+    }
+
+    let _ = writeln!(
+        html,
+        "<h2>Top candidates</h2>{}</body></html>",
+        html_table(&top_candidates_table(results, top_n))
+    );
+    html
+}
+
+// ============================================================================
+// EXPORT
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
+    Html,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub formats: Vec<ExportFormat>,
+    pub top_n: usize,
+    pub histogram_bins: usize,
+}
+
+impl Default for ExportOptions {
+    /// CSV and HTML, plus Parquet when the feature is enabled
+    fn default() -> Self {
+        let mut formats = vec![ExportFormat::Csv, ExportFormat::Html];
+        if cfg!(feature = "parquet") {
+            formats.push(ExportFormat::Parquet);
+        }
+        Self { formats, top_n: 50, histogram_bins: 20 }
+    }
+}
+
+/// Write the tables and report into `dir`, returning the files written
+pub fn export_results(results: &HTSResults, dir: &Path, options: &ExportOptions) -> Result<Vec<PathBuf>, String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    let tables = [
+        funnel_table(results),
+        distribution_table(results, options.histogram_bins),
+        top_candidates_table(results, options.top_n),
+    ];
+    let mut written = Vec::new();
+    for format in &options.formats {
+        match format {
+            ExportFormat::Csv | ExportFormat::Parquet => {
+                for table in &tables {
+                    let path = if *format == ExportFormat::Csv {
+                        let path = dir.join(format!("{}.csv", table.name));
+                        table.write_csv(&path)?;
+                        path
+                    } else {
+                        let path = dir.join(format!("{}.parquet", table.name));
+                        table.write_parquet(&path)?;
+                        path
+                    };
+                    written.push(path);
+                }
+            }
+            ExportFormat::Html => {
+                let path = dir.join("report.html");
+                std::fs::write(&path, html_report(results, options.top_n, options.histogram_bins))
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+                written.push(path);
+            }
+        }
+    }
+    Ok(written)
+}
+
+/// Export from a results file written by `HTSResults::save_to_file`
+pub fn export_results_file(results_path: &Path, dir: &Path, options: &ExportOptions) -> Result<Vec<PathBuf>, String> {
+    export_results(&HTSResults::load_from_file(results_path)?, dir, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hts::{
+        GenerationSettings, HTSCampaign, HTSConfig, PropertyFilter, FilterOperator, ScreeningStage, StageType,
+    };
+
+    async fn screened() -> HTSResults {
+        let pool = ["Ca", "Sr", "Ba", "Ti", "Zr", "Hf", "O"].iter().map(|e| e.to_string()).collect();
+        let mut config = HTSConfig::new("export, \"quoted\" <b>".to_string())
+            .with_base_structures(vec!["perovskite".to_string()])
+            .with_generation(GenerationSettings::default().with_element_pool(pool))
+            .with_stages(vec![
+                ScreeningStage::new("tolerance".to_string(), StageType::RuleBased)
+                    .with_filter(PropertyFilter::new("tolerance_factor".to_string(), FilterOperator::GreaterThan, 0.95)),
+                ScreeningStage::new("top".to_string(), StageType::Experimental).with_max_pass_through(2),
+            ]);
+        config.enable_checkpointing = false;
+        HTSCampaign::new(config).run().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_export_tables() {
+        let results = screened().await;
+        let funnel = &results.funnel;
+        assert_eq!(funnel.len(), 2);
+        assert_eq!(funnel[1].entered, funnel[0].retained);
+        assert_eq!(funnel[1].retained, 2);
+        assert_eq!(results.final_candidates.len() + results.eliminated_candidates.len(), funnel[0].entered);
+
+        // Every candidate lands in one side of the tolerance histogram
+        let distributions = property_distributions(&results);
+        let tolerance = distributions.iter()
+            .find(|d| d.stage_name == "tolerance" && d.property == "tolerance_factor")
+            .unwrap();
+        assert_eq!(tolerance.passed.len(), funnel[0].passed);
+        assert_eq!(tolerance.passed.len() + tolerance.failed.len(), funnel[0].entered);
+        assert!(tolerance.passed.iter().all(|&t| t > 0.95) && tolerance.failed.iter().all(|&t| t <= 0.95));
+        let histogram = tolerance.histogram(5);
+        assert_eq!(histogram.edges.len(), 6);
+        assert_eq!(histogram.passed.iter().sum::<usize>(), tolerance.passed.len());
+
+        let csv = funnel_table(&results).to_csv();
+        assert!(csv.starts_with("stage,entered,passed,failed,rejected,retained,pass_rate\n"));
+        assert_eq!(csv.lines().count(), 3);
+        let top = top_candidates_table(&results, 10);
+        assert_eq!(top.rows.len(), 2);
+        assert!(top.columns.contains(&"tolerance_factor".to_string()));
+        assert_eq!(Cell::Text("a,\"b\"".to_string()).csv(), "\"a,\"\"b\"\"\"");
+    }
+
+    #[tokio::test]
+    async fn test_export_from_file() {
+        let results = screened().await;
+        let dir = tempfile::tempdir().unwrap();
+        let results_path = dir.path().join("results.json");
+        results.save_to_file(&results_path).unwrap();
+
+        let options = ExportOptions { formats: vec![ExportFormat::Csv, ExportFormat::Html], ..Default::default() };
+        let written = export_results_file(&results_path, &dir.path().join("export"), &options).unwrap();
+        let names: Vec<String> = written.iter().map(|p| p.file_name().unwrap().to_string_lossy().to_string()).collect();
+        assert_eq!(names, vec!["funnel.csv", "distributions.csv", "top_candidates.csv", "report.html"]);
+
+        let html = std::fs::read_to_string(dir.path().join("export/report.html")).unwrap();
+        assert!(html.contains("export, &quot;quoted&quot; &lt;b&gt;"));
+        assert!(html.contains("<svg") && html.contains("Stage funnel") && html.contains("tolerance_factor"));
+        assert!(!html.contains("<script") && !html.contains("src="));
+
+        #[cfg(not(feature = "parquet"))]
+        assert!(funnel_table(&results).write_parquet(&dir.path().join("f.parquet")).is_err());
+    }
+}
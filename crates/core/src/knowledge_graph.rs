@@ -3,6 +3,8 @@
 //! Automatically constructs and maintains a knowledge graph of materials,
 //! discovering relationships, patterns, and insights.
 
+use crate::graph_algorithms::{self, CommunityAlgorithm, PathCost, SparseGraph};
+use crate::link_prediction::{self, LinkScorer, LinkSuggestion, SuggestionConfig};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
@@ -30,6 +32,57 @@ pub enum RelationType {
     Custom(String),
 }
 
+impl RelationType {
+    /// Every label `label` returns
+    pub const LABELS: [&'static str; 8] = [
+        "SIMILAR_COMPOSITION",
+        "SIMILAR_PROPERTIES",
+        "SAME_CRYSTAL_SYSTEM",
+        "CONTAINS_ELEMENT",
+        "SUBSTITUTION",
+        "SAME_APPLICATION",
+        "SAME_METHOD",
+        "CUSTOM",
+    ];
+
+    /// Upper snake case name, as used for Neo4j relationship types
+    pub fn label(&self) -> &'static str {
+        match self {
+            RelationType::SimilarComposition => "SIMILAR_COMPOSITION",
+            RelationType::SimilarProperties => "SIMILAR_PROPERTIES",
+            RelationType::SameCrystalSystem => "SAME_CRYSTAL_SYSTEM",
+            RelationType::ContainsElement(_) => "CONTAINS_ELEMENT",
+            RelationType::Substitution => "SUBSTITUTION",
+            RelationType::SameApplication => "SAME_APPLICATION",
+            RelationType::SameMethod => "SAME_METHOD",
+            RelationType::Custom(_) => "CUSTOM",
+        }
+    }
+
+    /// Payload of the parameterized variants: the element or custom name
+    pub fn detail(&self) -> Option<&str> {
+        match self {
+            RelationType::ContainsElement(detail) | RelationType::Custom(detail) => Some(detail),
+            _ => None,
+        }
+    }
+
+    /// Inverse of `label` and `detail`
+    pub fn from_label(label: &str, detail: Option<&str>) -> Option<Self> {
+        Some(match label {
+            "SIMILAR_COMPOSITION" => RelationType::SimilarComposition,
+            "SIMILAR_PROPERTIES" => RelationType::SimilarProperties,
+            "SAME_CRYSTAL_SYSTEM" => RelationType::SameCrystalSystem,
+            "CONTAINS_ELEMENT" => RelationType::ContainsElement(detail?.to_string()),
+            "SUBSTITUTION" => RelationType::Substitution,
+            "SAME_APPLICATION" => RelationType::SameApplication,
+            "SAME_METHOD" => RelationType::SameMethod,
+            "CUSTOM" => RelationType::Custom(detail?.to_string()),
+            _ => return None,
+        })
+    }
+}
+
 /// Edge in the knowledge graph
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KnowledgeEdge {
@@ -52,6 +105,69 @@ pub struct KnowledgeNode {
     pub importance_score: f64,
 }
 
+/// Thresholds and candidate generation for `auto_discover_relationships`
+///
+/// Graphs below `exhaustive_below` nodes compare every pair. Larger graphs
+/// only compare candidate pairs: MinHash banding over element sets for
+/// composition similarity, and log-scale property bins for property
+/// similarity. There, shared-element edges are only added between pairs that
+/// pass one of the thresholds, keeping the edge count linear in the nodes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveryConfig {
+    pub composition_threshold: f64,
+    pub property_threshold: f64,
+    pub exhaustive_below: usize,
+    pub minhash_bands: usize,
+    pub minhash_rows: usize,
+    /// Width of the ln(|value|) bins; 0.45 catches every pair within the
+    /// default 0.8 property threshold
+    pub property_bin_width: f64,
+    /// Members each item is paired with inside one oversized block; the
+    /// pairs this skips are reported in `DiscoveryReport::dropped_pairs`
+    pub max_block_pairs: usize,
+    pub seed: u64,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            composition_threshold: 0.7,
+            property_threshold: 0.8,
+            exhaustive_below: 2000,
+            minhash_bands: 24,
+            minhash_rows: 8,
+            property_bin_width: 0.45,
+            max_block_pairs: 32,
+            seed: 42,
+        }
+    }
+}
+
+/// Outcome of one relationship discovery run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DiscoveryReport {
+    /// Edges added
+    pub edges: usize,
+    /// Node pairs compared
+    pub compared_pairs: usize,
+    /// Pairs in oversized candidate blocks that were never compared
+    /// (counted per block); zero for exhaustive comparison
+    pub dropped_pairs: usize,
+}
+
+/// Path with its total cost under a `PathCost`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeightedPath {
+    pub nodes: Vec<Uuid>,
+    pub cost: f64,
+}
+
+/// Positions of two nodes in the discovery node list
+type NodePair = (usize, usize);
+
+/// (neighbor, edge index) lists keyed by node
+type Adjacency = HashMap<Uuid, Vec<(Uuid, usize)>>;
+
 /// Knowledge Graph Engine
 pub struct KnowledgeGraph {
     /// Nodes (materials)
@@ -60,8 +176,8 @@ pub struct KnowledgeGraph {
     /// Edges (relationships)
     edges: Arc<RwLock<Vec<KnowledgeEdge>>>,
 
-    /// Adjacency list for fast traversal
-    adjacency: Arc<RwLock<HashMap<Uuid, Vec<Uuid>>>>,
+    /// Sparse adjacency: (neighbor, index into `edges`) per node
+    adjacency: Arc<RwLock<Adjacency>>,
 
     /// Community detection cache
     communities: Arc<RwLock<Vec<Vec<Uuid>>>>,
@@ -90,98 +206,161 @@ impl KnowledgeGraph {
 
     /// Add a relationship edge
     pub async fn add_edge(&self, edge: KnowledgeEdge) -> Result<(), String> {
+        self.add_edges(vec![edge]).await;
+        Ok(())
+    }
+
+    /// Add many edges under one lock
+    async fn add_edges(&self, new_edges: Vec<KnowledgeEdge>) {
         let mut edges = self.edges.write().await;
         let mut adjacency = self.adjacency.write().await;
 
-        // Add to edges
-        edges.push(edge.clone());
+        for edge in new_edges {
+            let index = edges.len();
+            adjacency.entry(edge.from).or_default().push((edge.to, index));
+            adjacency.entry(edge.to).or_default().push((edge.from, index));
+            edges.push(edge);
+        }
+    }
 
-        // Update adjacency list
-        adjacency.entry(edge.from)
-            .or_insert_with(Vec::new)
-            .push(edge.to);
+    /// Snapshot of every node
+    pub async fn nodes(&self) -> Vec<KnowledgeNode> {
+        self.nodes.read().await.values().cloned().collect()
+    }
 
-        adjacency.entry(edge.to)
-            .or_insert_with(Vec::new)
-            .push(edge.from);
+    /// Snapshot of every edge, in insertion order
+    pub async fn edges(&self) -> Vec<KnowledgeEdge> {
+        self.edges.read().await.clone()
+    }
 
-        Ok(())
+    pub async fn get_node(&self, material_id: Uuid) -> Option<KnowledgeNode> {
+        self.nodes.read().await.get(&material_id).cloned()
+    }
+
+    /// Build a graph from stored nodes and edges
+    pub async fn from_parts(nodes: Vec<KnowledgeNode>, edges: Vec<KnowledgeEdge>) -> Self {
+        let graph = Self::new();
+        for node in nodes {
+            graph.add_node(node).await.ok();
+        }
+        graph.add_edges(edges).await;
+        graph
     }
 
     /// Auto-discover relationships between materials
     pub async fn auto_discover_relationships(&self) -> Result<usize, String> {
-        let nodes = self.nodes.read().await;
-        let node_list: Vec<_> = nodes.values().cloned().collect();
-        drop(nodes);
-
-        let mut discovered = 0;
-
-        // Discover composition similarity
-        for i in 0..node_list.len() {
-            for j in (i + 1)..node_list.len() {
-                let node_i = &node_list[i];
-                let node_j = &node_list[j];
-
-                // Check composition similarity
-                let composition_sim = Self::composition_similarity(
-                    &node_i.formula,
-                    &node_j.formula,
-                );
-
-                if composition_sim > 0.7 {
-                    self.add_edge(KnowledgeEdge {
-                        from: node_i.material_id,
-                        to: node_j.material_id,
-                        relation_type: RelationType::SimilarComposition,
-                        weight: composition_sim,
-                        confidence: 0.9,
-                        metadata: HashMap::new(),
-                        created_at: chrono::Utc::now(),
-                    }).await?;
-                    discovered += 1;
-                }
+        self.auto_discover_relationships_with(&DiscoveryConfig::default()).await
+    }
 
-                // Check property similarity
-                let property_sim = Self::property_similarity(
-                    &node_i.properties,
-                    &node_j.properties,
-                );
-
-                if property_sim > 0.8 {
-                    self.add_edge(KnowledgeEdge {
-                        from: node_i.material_id,
-                        to: node_j.material_id,
-                        relation_type: RelationType::SimilarProperties,
-                        weight: property_sim,
-                        confidence: 0.85,
-                        metadata: HashMap::new(),
-                        created_at: chrono::Utc::now(),
-                    }).await?;
-                    discovered += 1;
-                }
+    /// Auto-discover relationships, comparing only candidate pairs on large graphs
+    pub async fn auto_discover_relationships_with(&self, config: &DiscoveryConfig) -> Result<usize, String> {
+        Ok(self.discover_relationships(config).await?.edges)
+    }
+
+    /// Auto-discover relationships, reporting how many pairs were compared
+    /// and how many the block cap skipped
+    pub async fn discover_relationships(&self, config: &DiscoveryConfig) -> Result<DiscoveryReport, String> {
+        let mut node_list: Vec<KnowledgeNode> = self.nodes.read().await.values().cloned().collect();
+        node_list.sort_by_key(|n| n.material_id);
+
+        let element_sets: Vec<HashSet<String>> = node_list.iter()
+            .map(|n| Self::extract_elements(&n.formula))
+            .collect();
+        let exhaustive = node_list.len() < config.exhaustive_below;
+        let (composition_pairs, property_pairs, dropped_pairs) = if exhaustive {
+            let all: Vec<(usize, usize)> = (0..node_list.len())
+                .flat_map(|i| ((i + 1)..node_list.len()).map(move |j| (i, j)))
+                .collect();
+            (all, Vec::new(), 0)
+        } else {
+            Self::candidate_pairs(&node_list, &element_sets, config)
+        };
+        let compared_pairs = composition_pairs.len() + property_pairs.len();
+
+        let mut new_edges = Vec::new();
+        let pairs = composition_pairs.iter().map(|&p| (p, true))
+            .chain(property_pairs.iter().map(|&p| (p, false)));
+        for ((i, j), compare_composition) in pairs {
+            let node_i = &node_list[i];
+            let node_j = &node_list[j];
+            let edge = |relation_type, weight, confidence| KnowledgeEdge {
+                from: node_i.material_id,
+                to: node_j.material_id,
+                relation_type,
+                weight,
+                confidence,
+                metadata: HashMap::new(),
+                created_at: chrono::Utc::now(),
+            };
+
+            let mut related = false;
+            let property_sim = Self::property_similarity(&node_i.properties, &node_j.properties);
+            if property_sim > config.property_threshold {
+                new_edges.push(edge(RelationType::SimilarProperties, property_sim, 0.85));
+                related = true;
+            }
 
-                // Check common elements
-                let common_elements = Self::common_elements(
-                    &node_i.formula,
-                    &node_j.formula,
-                );
-
-                for element in common_elements {
-                    self.add_edge(KnowledgeEdge {
-                        from: node_i.material_id,
-                        to: node_j.material_id,
-                        relation_type: RelationType::ContainsElement(element.clone()),
-                        weight: 0.5,
-                        confidence: 1.0,
-                        metadata: HashMap::new(),
-                        created_at: chrono::Utc::now(),
-                    }).await?;
-                    discovered += 1;
+            if compare_composition {
+                let composition_sim = Self::jaccard(&element_sets[i], &element_sets[j]);
+                if composition_sim > config.composition_threshold {
+                    new_edges.push(edge(RelationType::SimilarComposition, composition_sim, 0.9));
+                    related = true;
                 }
             }
+            if !exhaustive && !related {
+                continue;
+            }
+
+            let mut common: Vec<&String> = element_sets[i].intersection(&element_sets[j]).collect();
+            common.sort();
+            for element in common {
+                new_edges.push(edge(RelationType::ContainsElement(element.clone()), 0.5, 1.0));
+            }
         }
 
-        Ok(discovered)
+        let edges = new_edges.len();
+        self.add_edges(new_edges).await;
+        Ok(DiscoveryReport { edges, compared_pairs, dropped_pairs })
+    }
+
+    /// Sorted composition candidates, property candidates not already among
+    /// them, and the pairs dropped from oversized blocks
+    fn candidate_pairs(
+        nodes: &[KnowledgeNode],
+        element_sets: &[HashSet<String>],
+        config: &DiscoveryConfig,
+    ) -> (Vec<NodePair>, Vec<NodePair>, usize) {
+        let sorted_sets: Vec<Vec<&String>> = element_sets.iter()
+            .map(|set| {
+                let mut elements: Vec<&String> = set.iter().collect();
+                elements.sort();
+                elements
+            })
+            .collect();
+        let composition = graph_algorithms::minhash_candidates(
+            &sorted_sets,
+            config.minhash_bands,
+            config.minhash_rows,
+            config.max_block_pairs,
+            config.seed,
+        );
+
+        let property_blocks: Vec<_> = nodes.iter()
+            .map(|n| {
+                let properties: Vec<(&str, f64)> = n.properties.iter().map(|(k, &v)| (k.as_str(), v)).collect();
+                graph_algorithms::log_bin_blocks(&properties, config.property_bin_width)
+            })
+            .collect();
+        let property_candidates = graph_algorithms::blocked_candidates(&property_blocks, config.max_block_pairs);
+        let dropped = composition.dropped + property_candidates.dropped;
+        let mut property: Vec<(usize, usize)> = property_candidates.pairs
+            .into_iter()
+            .filter(|pair| !composition.pairs.contains(pair))
+            .collect();
+        let mut composition: Vec<(usize, usize)> = composition.pairs.into_iter().collect();
+        composition.sort_unstable();
+        property.sort_unstable();
+        (composition, property, dropped)
     }
 
     /// Find path between two materials
@@ -217,7 +396,7 @@ impl KnowledgeGraph {
             }
 
             if let Some(neighbors) = adjacency.get(&current) {
-                for &neighbor in neighbors {
+                for &(neighbor, _) in neighbors {
                     if !visited.contains(&neighbor) {
                         visited.insert(neighbor);
                         parent.insert(neighbor, current);
@@ -238,91 +417,38 @@ impl KnowledgeGraph {
     ) -> Vec<KnowledgeNode> {
         let edges = self.edges.read().await;
         let nodes = self.nodes.read().await;
+        let adjacency = self.adjacency.read().await;
 
-        let mut neighbors = Vec::new();
-
-        for edge in edges.iter() {
-            if edge.from == material_id {
-                if let Some(ref rel_type) = relation_type {
-                    if &edge.relation_type != rel_type {
-                        continue;
-                    }
-                }
-
-                if let Some(node) = nodes.get(&edge.to) {
-                    neighbors.push(node.clone());
-                }
-            } else if edge.to == material_id {
-                if let Some(ref rel_type) = relation_type {
-                    if &edge.relation_type != rel_type {
-                        continue;
-                    }
-                }
-
-                if let Some(node) = nodes.get(&edge.from) {
-                    neighbors.push(node.clone());
-                }
-            }
-        }
-
-        neighbors
+        adjacency.get(&material_id)
+            .into_iter()
+            .flatten()
+            .filter(|(_, index)| relation_type.as_ref().map_or(true, |r| &edges[*index].relation_type == r))
+            .filter_map(|(neighbor, _)| nodes.get(neighbor).cloned())
+            .collect()
     }
 
-    /// Detect communities using Louvain algorithm (simplified)
+    /// Detect communities with Louvain modularity optimization
     pub async fn detect_communities(&self) -> Vec<Vec<Uuid>> {
-        let nodes = self.nodes.read().await;
-        let edges = self.edges.read().await;
-
-        let node_ids: Vec<Uuid> = nodes.keys().copied().collect();
-        let n = node_ids.len();
-
-        if n == 0 {
-            return Vec::new();
-        }
-
-        // Build adjacency matrix
-        let mut adj_matrix = vec![vec![0.0; n]; n];
-        let id_to_idx: HashMap<Uuid, usize> = node_ids.iter()
-            .enumerate()
-            .map(|(i, &id)| (id, i))
-            .collect();
-
-        for edge in edges.iter() {
-            if let (Some(&i), Some(&j)) = (id_to_idx.get(&edge.from), id_to_idx.get(&edge.to)) {
-                adj_matrix[i][j] = edge.weight;
-                adj_matrix[j][i] = edge.weight;
-            }
-        }
-
-        // Simple community detection: connected components
-        let mut visited = vec![false; n];
-        let mut communities = Vec::new();
-
-        for i in 0..n {
-            if !visited[i] {
-                let mut community = Vec::new();
-                let mut stack = vec![i];
-
-                while let Some(node) = stack.pop() {
-                    if visited[node] {
-                        continue;
-                    }
-
-                    visited[node] = true;
-                    community.push(node_ids[node]);
-
-                    for j in 0..n {
-                        if !visited[j] && adj_matrix[node][j] > 0.0 {
-                            stack.push(j);
-                        }
-                    }
-                }
+        self.detect_communities_with(CommunityAlgorithm::Louvain, 1.0, None).await
+    }
 
-                if !community.is_empty() {
-                    communities.push(community);
-                }
-            }
+    /// Detect communities over the edges of the given relation types (all
+    /// when `None`); higher `resolution` gives smaller communities.
+    /// Communities are returned largest first.
+    pub async fn detect_communities_with(
+        &self,
+        algorithm: CommunityAlgorithm,
+        resolution: f64,
+        relations: Option<&[RelationType]>,
+    ) -> Vec<Vec<Uuid>> {
+        let (ids, graph) = self.sparse_graph(relations).await;
+        let labels = graph_algorithms::communities(&graph, algorithm, resolution, 42);
+
+        let mut communities = vec![Vec::new(); labels.iter().max().map_or(0, |m| m + 1)];
+        for (id, label) in ids.into_iter().zip(labels) {
+            communities[label].push(id);
         }
+        communities.sort_by_key(|c| std::cmp::Reverse(c.len()));
 
         // Cache communities
         let mut cached = self.communities.write().await;
@@ -331,45 +457,119 @@ impl KnowledgeGraph {
         communities
     }
 
-    /// Calculate centrality scores (PageRank-like)
+    /// Calculate centrality scores (weighted PageRank over all edges)
     pub async fn calculate_centrality(&self) -> HashMap<Uuid, f64> {
-        let nodes = self.nodes.read().await;
-        let adjacency = self.adjacency.read().await;
+        let scores = self.pagerank(0.85, None).await;
 
-        let mut scores: HashMap<Uuid, f64> = nodes.keys()
-            .map(|&id| (id, 1.0))
-            .collect();
+        // Cache centrality scores
+        let mut cached = self.centrality_scores.write().await;
+        *cached = scores.clone();
 
-        let damping = 0.85;
-        let iterations = 20;
+        scores
+    }
 
-        for _ in 0..iterations {
-            let mut new_scores = HashMap::new();
+    /// Weighted PageRank over the edges of the given relation types; scores sum to 1
+    pub async fn pagerank(&self, damping: f64, relations: Option<&[RelationType]>) -> HashMap<Uuid, f64> {
+        let (ids, graph) = self.sparse_graph(relations).await;
+        let ranks = graph_algorithms::pagerank(&graph, damping, 1e-10, 100);
+        ids.into_iter().zip(ranks).collect()
+    }
 
-            for (&node, _) in nodes.iter() {
-                let mut score = (1.0 - damping) / nodes.len() as f64;
+    /// Normalized betweenness centrality; O(nodes * edges)
+    pub async fn betweenness_centrality(
+        &self,
+        relations: Option<&[RelationType]>,
+        cost: PathCost,
+    ) -> HashMap<Uuid, f64> {
+        let (ids, graph) = self.sparse_graph(relations).await;
+        let scores = graph_algorithms::betweenness(&graph, cost);
+        ids.into_iter().zip(scores).collect()
+    }
 
-                // Get incoming edges
-                for (&other_node, neighbors) in adjacency.iter() {
-                    if neighbors.contains(&node) && other_node != node {
-                        let out_degree = neighbors.len() as f64;
-                        if out_degree > 0.0 {
-                            score += damping * scores.get(&other_node).unwrap_or(&1.0) / out_degree;
-                        }
-                    }
-                }
+    /// Cheapest path using only edges of the given relation types
+    pub async fn shortest_weighted_path(
+        &self,
+        from: Uuid,
+        to: Uuid,
+        relations: Option<&[RelationType]>,
+        cost: PathCost,
+    ) -> Option<WeightedPath> {
+        self.k_shortest_paths(from, to, 1, relations, cost).await.pop()
+    }
 
-                new_scores.insert(node, score);
-            }
+    /// Up to `k` loopless paths in increasing cost (Yen's algorithm)
+    pub async fn k_shortest_paths(
+        &self,
+        from: Uuid,
+        to: Uuid,
+        k: usize,
+        relations: Option<&[RelationType]>,
+        cost: PathCost,
+    ) -> Vec<WeightedPath> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let (ids, graph) = self.sparse_graph(relations).await;
+        let (Ok(source), Ok(target)) = (ids.binary_search(&from), ids.binary_search(&to)) else {
+            return Vec::new();
+        };
+        graph_algorithms::k_shortest_paths(&graph, source, target, k, cost)
+            .into_iter()
+            .map(|(path, cost)| WeightedPath { nodes: path.into_iter().map(|i| ids[i]).collect(), cost })
+            .collect()
+    }
 
-            scores = new_scores;
+    /// Missing edges proposed by a link-prediction scorer that `fit`
+    /// trains on this graph (see `link_prediction::suggest_links`)
+    pub async fn suggest_links<F>(&self, config: &SuggestionConfig, fit: F) -> Result<Vec<LinkSuggestion>, String>
+    where
+        F: Fn(&[KnowledgeNode], &[KnowledgeEdge]) -> crate::Result<Box<dyn LinkScorer>>,
+    {
+        let nodes = self.nodes().await;
+        let edges = self.edges.read().await;
+        link_prediction::suggest_links(&nodes, &edges, config, fit).map_err(|e| e.to_string())
+    }
+
+    /// Add suggested edges, tagged with their model; pairs already linked by
+    /// the same relation, in either direction, and repeats within
+    /// `suggestions` are skipped. Returns how many were added.
+    pub async fn apply_link_suggestions(&self, suggestions: &[LinkSuggestion]) -> usize {
+        let mut edges = self.edges.write().await;
+        let mut adjacency = self.adjacency.write().await;
+        let mut linked: HashSet<(Uuid, Uuid, RelationType)> =
+            edges.iter().map(|e| (e.from.min(e.to), e.from.max(e.to), e.relation_type.clone())).collect();
+
+        let mut added = 0;
+        for suggestion in suggestions {
+            let key = (suggestion.from.min(suggestion.to), suggestion.from.max(suggestion.to), suggestion.relation.clone());
+            if !linked.insert(key) {
+                continue;
+            }
+            let edge = suggestion.to_edge();
+            let index = edges.len();
+            adjacency.entry(edge.from).or_default().push((edge.to, index));
+            adjacency.entry(edge.to).or_default().push((edge.from, index));
+            edges.push(edge);
+            added += 1;
         }
+        added
+    }
 
-        // Cache centrality scores
-        let mut cached = self.centrality_scores.write().await;
-        *cached = scores.clone();
+    /// Sorted node ids and the graph of edges with the given relation types
+    async fn sparse_graph(&self, relations: Option<&[RelationType]>) -> (Vec<Uuid>, SparseGraph) {
+        let nodes = self.nodes.read().await;
+        let edges = self.edges.read().await;
 
-        scores
+        let mut ids: Vec<Uuid> = nodes.keys().copied().collect();
+        ids.sort();
+        let index = |id: &Uuid| ids.binary_search(id).ok();
+
+        let weighted: Vec<(usize, usize, f64)> = edges.iter()
+            .filter(|e| relations.map_or(true, |r| r.contains(&e.relation_type)))
+            .filter_map(|e| Some((index(&e.from)?, index(&e.to)?, e.weight)))
+            .collect();
+        let graph = SparseGraph::from_edges(ids.len(), weighted);
+        (ids, graph)
     }
 
     /// Get most important materials (by centrality)
@@ -410,18 +610,15 @@ impl KnowledgeGraph {
 
     // === Private Helper Methods ===
 
-    fn composition_similarity(formula1: &str, formula2: &str) -> f64 {
-        let elements1 = Self::extract_elements(formula1);
-        let elements2 = Self::extract_elements(formula2);
-
-        let common: HashSet<_> = elements1.intersection(&elements2).collect();
-        let union: HashSet<_> = elements1.union(&elements2).collect();
+    fn jaccard(elements1: &HashSet<String>, elements2: &HashSet<String>) -> f64 {
+        let common = elements1.intersection(elements2).count();
+        let union = elements1.len() + elements2.len() - common;
 
-        if union.is_empty() {
+        if union == 0 {
             return 0.0;
         }
 
-        common.len() as f64 / union.len() as f64
+        common as f64 / union as f64
     }
 
     fn property_similarity(props1: &HashMap<String, f64>, props2: &HashMap<String, f64>) -> f64 {
@@ -451,15 +648,6 @@ impl KnowledgeGraph {
         }
     }
 
-    fn common_elements(formula1: &str, formula2: &str) -> Vec<String> {
-        let elements1 = Self::extract_elements(formula1);
-        let elements2 = Self::extract_elements(formula2);
-
-        elements1.intersection(&elements2)
-            .map(|s| s.to_string())
-            .collect()
-    }
-
     fn extract_elements(formula: &str) -> HashSet<String> {
         let mut elements = HashSet::new();
         let mut current = String::new();
@@ -548,4 +736,131 @@ mod tests {
         let discovered = kg.auto_discover_relationships().await.unwrap();
         assert!(discovered > 0);
     }
+
+    fn node(formula: &str, properties: &[(&str, f64)]) -> KnowledgeNode {
+        KnowledgeNode {
+            material_id: Uuid::new_v4(),
+            formula: formula.to_string(),
+            properties: properties.iter().map(|(k, v)| (k.to_string(), *v)).collect(),
+            tags: Vec::new(),
+            importance_score: 0.5,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_blocked_discovery_matches_exhaustive() {
+        let formulas = ["Fe2O3", "Fe3O4", "FeO", "LiCoO2", "LiNiO2", "LiFePO4", "NaCl", "KCl", "GaAs", "GaN", "Si", "SiC"];
+        let nodes: Vec<KnowledgeNode> = formulas.iter().enumerate()
+            .map(|(i, f)| node(f, &[("band_gap", 0.5 + i as f64 * 0.3)]))
+            .collect();
+
+        let exhaustive = KnowledgeGraph::from_parts(nodes.clone(), Vec::new()).await;
+        let all = exhaustive.auto_discover_relationships().await.unwrap();
+
+        let blocked = KnowledgeGraph::from_parts(nodes, Vec::new()).await;
+        let config = DiscoveryConfig { exhaustive_below: 0, ..DiscoveryConfig::default() };
+        let found = blocked.auto_discover_relationships_with(&config).await.unwrap();
+
+        // Candidates never miss a similar pair; they only skip weak shared-element links
+        let similar = |g: Vec<KnowledgeEdge>| g.into_iter()
+            .filter(|e| !matches!(e.relation_type, RelationType::ContainsElement(_)))
+            .count();
+        assert!(found <= all);
+        assert_eq!(similar(blocked.edges().await), similar(exhaustive.edges().await));
+
+        // Identical nodes share every block; a tight cap reports what it skipped
+        let crowded: Vec<KnowledgeNode> = (0..12).map(|_| node("Fe2O3", &[("band_gap", 2.0)])).collect();
+        let capped = KnowledgeGraph::from_parts(crowded, Vec::new()).await;
+        let config = DiscoveryConfig { exhaustive_below: 0, max_block_pairs: 2, ..DiscoveryConfig::default() };
+        let report = capped.discover_relationships(&config).await.unwrap();
+        assert_eq!(report.compared_pairs, 21);
+        assert!(report.dropped_pairs > 0);
+    }
+
+    #[tokio::test]
+    async fn test_weighted_paths_and_communities() {
+        let nodes: Vec<KnowledgeNode> = ["A", "B", "C", "D"].iter().map(|f| node(f, &[])).collect();
+        let id: Vec<Uuid> = nodes.iter().map(|n| n.material_id).collect();
+        let edge = |a: usize, b: usize, relation_type: RelationType, weight: f64| KnowledgeEdge {
+            from: id[a],
+            to: id[b],
+            relation_type,
+            weight,
+            confidence: 1.0,
+            metadata: HashMap::new(),
+            created_at: chrono::Utc::now(),
+        };
+        let edges = vec![
+            edge(0, 1, RelationType::Substitution, 0.9),
+            edge(1, 3, RelationType::Substitution, 0.9),
+            edge(0, 2, RelationType::SimilarProperties, 0.2),
+            edge(2, 3, RelationType::SimilarProperties, 0.2),
+            edge(0, 3, RelationType::SameApplication, 0.1),
+        ];
+        let kg = KnowledgeGraph::from_parts(nodes, edges).await;
+
+        let strongest = kg.shortest_weighted_path(id[0], id[3], None, PathCost::InverseWeight).await.unwrap();
+        assert_eq!(strongest.nodes, vec![id[0], id[1], id[3]]);
+
+        let filtered = [RelationType::SimilarProperties];
+        let path = kg.shortest_weighted_path(id[0], id[3], Some(&filtered), PathCost::Weight).await.unwrap();
+        assert_eq!(path.nodes, vec![id[0], id[2], id[3]]);
+        assert!((path.cost - 0.4).abs() < 1e-12);
+
+        let paths = kg.k_shortest_paths(id[0], id[3], 5, None, PathCost::Hops).await;
+        assert_eq!(paths.len(), 3);
+        assert_eq!(paths[0].nodes, vec![id[0], id[3]]);
+
+        assert_eq!(kg.get_neighbors(id[0], Some(RelationType::Substitution)).await.len(), 1);
+        assert!(kg.betweenness_centrality(None, PathCost::InverseWeight).await[&id[1]] > 0.0);
+
+        let scores = kg.calculate_centrality().await;
+        assert!((scores.values().sum::<f64>() - 1.0).abs() < 1e-6);
+        let top = kg.get_most_important(1).await[0].1;
+        assert!(scores.values().all(|&s| s <= top));
+
+        let communities = kg.detect_communities().await;
+        assert_eq!(communities.iter().map(Vec::len).sum::<usize>(), 4);
+        assert_eq!(kg.get_statistics().await.community_count, communities.len());
+
+        // Suggestions already linked, in either direction, or repeated are skipped
+        let suggestion = |a: usize, b: usize, relation: RelationType| LinkSuggestion {
+            from: id[a],
+            to: id[b],
+            relation,
+            score: 1.0,
+            confidence: 0.9,
+            model: "test".to_string(),
+        };
+        let suggestions = [
+            suggestion(1, 0, RelationType::Substitution),
+            suggestion(1, 2, RelationType::Substitution),
+            suggestion(2, 1, RelationType::Substitution),
+            suggestion(0, 1, RelationType::SameApplication),
+        ];
+        assert_eq!(kg.apply_link_suggestions(&suggestions).await, 2);
+        assert_eq!(kg.edges().await.len(), 7);
+        assert_eq!(kg.apply_link_suggestions(&suggestions).await, 0);
+    }
+
+    #[test]
+    fn test_relation_labels_round_trip() {
+        let relations = [
+            RelationType::SimilarComposition,
+            RelationType::SimilarProperties,
+            RelationType::SameCrystalSystem,
+            RelationType::ContainsElement("Fe".to_string()),
+            RelationType::Substitution,
+            RelationType::SameApplication,
+            RelationType::SameMethod,
+            RelationType::Custom("doped_with".to_string()),
+        ];
+        let labels: Vec<&str> = relations.iter().map(RelationType::label).collect();
+        assert_eq!(labels, RelationType::LABELS);
+        for relation in relations {
+            assert_eq!(RelationType::from_label(relation.label(), relation.detail()), Some(relation));
+        }
+        assert_eq!(RelationType::from_label("CONTAINS_ELEMENT", None), None);
+        assert_eq!(RelationType::from_label("SIMILAR_TO", None), None);
+    }
 }
@@ -0,0 +1,708 @@
+//! Learned material embeddings
+//!
+//! Two trainable stages replace the hand-built vectors of `EmbeddingEngine`:
+//! - [`ElementEmbeddings`]: element vectors from co-occurrence in a corpus of
+//!   formulas, in the spirit of mat2vec. Elements that share chemical
+//!   contexts (Li and Na both pair with halogens) end up close. Built by
+//!   factorizing the positive pointwise mutual information (PPMI) matrix.
+//! - [`ContrastiveEncoder`]: a two-layer network over pooled element vectors
+//!   plus an optional structure block, trained with a symmetric InfoNCE loss
+//!   between two noisy views of each material.
+//!
+//! A trained [`LearnedEmbeddingModel`] carries a version derived from its
+//! weights; `EmbeddingEngine::set_model` records it in
+//! `EmbeddingMetadata.model_version`, and `EmbeddingEngine::reembed_stale`
+//! refreshes embeddings made by another model.
+
+use crate::embeddings::{EmbeddingEngine, EMBEDDING_DIM, STRUCTURE_BLOCK_DIM};
+use crate::material::{Material, Structure};
+use crate::structure_descriptors::StructureFeaturizer;
+use crate::{Error, Result};
+use nalgebra::{DMatrix, SymmetricEigen};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+/// Training hyperparameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LearnedEmbeddingConfig {
+    /// Width of the co-occurrence element vectors
+    pub element_dim: usize,
+    pub hidden_dim: usize,
+    pub output_dim: usize,
+    pub epochs: usize,
+    pub batch_size: usize,
+    pub learning_rate: f64,
+    /// InfoNCE temperature
+    pub temperature: f64,
+    /// Gaussian noise added to standardized inputs of each view
+    pub noise: f64,
+    /// Probability of zeroing an input feature in each view
+    pub dropout: f64,
+    pub seed: u64,
+}
+
+impl Default for LearnedEmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            element_dim: 32,
+            hidden_dim: 128,
+            output_dim: 64,
+            epochs: 40,
+            batch_size: 32,
+            learning_rate: 0.05,
+            temperature: 0.2,
+            noise: 0.3,
+            dropout: 0.1,
+            seed: 42,
+        }
+    }
+}
+
+// ============================================================================
+// ELEMENT CO-OCCURRENCE EMBEDDINGS
+// ============================================================================
+
+/// Element vectors learned from which elements appear together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElementEmbeddings {
+    pub dim: usize,
+    vectors: BTreeMap<String, Vec<f64>>,
+}
+
+impl ElementEmbeddings {
+    /// Factorize the PPMI matrix of element pairs over `compositions`
+    ///
+    /// Each formula is one context window. Vectors are the top eigenvectors
+    /// scaled by the square root of their eigenvalues, normalized to unit
+    /// length; elements absent from the corpus get no vector.
+    pub fn train(compositions: &[HashMap<String, usize>], dim: usize) -> Result<Self> {
+        let elements: Vec<&String> = compositions.iter()
+            .flat_map(|c| c.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        if elements.len() < 2 || dim == 0 {
+            return Err(Error::invalid_input("Co-occurrence needs at least two elements and a positive dimension"));
+        }
+        let slot: HashMap<&String, usize> = elements.iter().enumerate().map(|(i, e)| (*e, i)).collect();
+
+        let n = elements.len();
+        let mut counts = DMatrix::<f64>::zeros(n, n);
+        for composition in compositions {
+            let present: Vec<usize> = composition.keys().map(|e| slot[e]).collect();
+            for &i in &present {
+                for &j in &present {
+                    if i != j {
+                        counts[(i, j)] += 1.0;
+                    }
+                }
+            }
+        }
+
+        let total = counts.sum();
+        if total == 0.0 {
+            return Err(Error::invalid_input("No formula in the corpus has two elements"));
+        }
+        let rows: Vec<f64> = (0..n).map(|i| counts.row(i).sum()).collect();
+        let ppmi = DMatrix::from_fn(n, n, |i, j| {
+            let c = counts[(i, j)];
+            if c > 0.0 { (c * total / (rows[i] * rows[j])).ln().max(0.0) } else { 0.0 }
+        });
+
+        let eigen = SymmetricEigen::new(ppmi);
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&a, &b| eigen.eigenvalues[b].total_cmp(&eigen.eigenvalues[a]));
+        let dim = dim.min(n);
+
+        let vectors = elements.iter().enumerate()
+            .map(|(i, element)| {
+                let vector: Vec<f64> = order.iter().take(dim)
+                    .map(|&k| eigen.eigenvectors[(i, k)] * eigen.eigenvalues[k].max(0.0).sqrt())
+                    .collect();
+                ((*element).clone(), unit(&vector))
+            })
+            .collect();
+        Ok(Self { dim, vectors })
+    }
+
+    pub fn get(&self, element: &str) -> Option<&[f64]> {
+        self.vectors.get(element).map(Vec::as_slice)
+    }
+
+    pub fn len(&self) -> usize {
+        self.vectors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vectors.is_empty()
+    }
+
+    /// Cosine similarity of two element vectors
+    pub fn similarity(&self, a: &str, b: &str) -> Option<f64> {
+        Some(dot(self.get(a)?, self.get(b)?))
+    }
+
+    /// Elements closest to `element`, most similar first
+    pub fn most_similar(&self, element: &str, k: usize) -> Vec<(String, f64)> {
+        let Some(target) = self.get(element) else { return Vec::new() };
+        let mut scored: Vec<(String, f64)> = self.vectors.iter()
+            .filter(|(e, _)| e.as_str() != element)
+            .map(|(e, v)| (e.clone(), dot(target, v)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        scored.truncate(k);
+        scored
+    }
+
+    /// Fraction-weighted mean and elementwise max of the element vectors,
+    /// then the number of elements and the mixing entropy
+    fn pool(&self, composition: &HashMap<String, usize>) -> Vec<f64> {
+        let total: usize = composition.values().sum();
+        let mut mean = vec![0.0; self.dim];
+        let mut max = vec![f64::NEG_INFINITY; self.dim];
+        let mut entropy = 0.0;
+        // Fixed order keeps training reproducible down to the version hash
+        let sorted: BTreeMap<&String, &usize> = composition.iter().collect();
+        for (element, &count) in sorted {
+            let fraction = count as f64 / total.max(1) as f64;
+            if fraction > 0.0 {
+                entropy -= fraction * fraction.ln();
+            }
+            if let Some(vector) = self.get(element) {
+                for (j, v) in vector.iter().enumerate() {
+                    mean[j] += fraction * v;
+                    max[j] = max[j].max(*v);
+                }
+            }
+        }
+        for m in &mut max {
+            if !m.is_finite() {
+                *m = 0.0;
+            }
+        }
+        mean.extend(max);
+        mean.push(composition.len() as f64);
+        mean.push(entropy);
+        mean
+    }
+}
+
+// ============================================================================
+// CONTRASTIVE ENCODER
+// ============================================================================
+
+/// `tanh` MLP from material features to a unit-norm embedding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContrastiveEncoder {
+    pub input_dim: usize,
+    pub hidden_dim: usize,
+    pub output_dim: usize,
+    /// Per-feature standardization fitted on the training corpus
+    mean: Vec<f64>,
+    scale: Vec<f64>,
+    /// Row-major `hidden_dim x input_dim`
+    w1: Vec<f64>,
+    b1: Vec<f64>,
+    /// Row-major `output_dim x hidden_dim`
+    w2: Vec<f64>,
+    b2: Vec<f64>,
+}
+
+/// Activations kept for the backward pass
+struct Forward {
+    input: Vec<f64>,
+    hidden: Vec<f64>,
+    norm: f64,
+    output: Vec<f64>,
+}
+
+#[derive(Default)]
+struct Gradients {
+    w1: Vec<f64>,
+    b1: Vec<f64>,
+    w2: Vec<f64>,
+    b2: Vec<f64>,
+}
+
+impl ContrastiveEncoder {
+    fn new(inputs: &[Vec<f64>], hidden_dim: usize, output_dim: usize, rng: &mut StdRng) -> Self {
+        let input_dim = inputs[0].len();
+        let n = inputs.len() as f64;
+        let mean: Vec<f64> = (0..input_dim).map(|j| inputs.iter().map(|x| x[j]).sum::<f64>() / n).collect();
+        let scale = (0..input_dim)
+            .map(|j| {
+                let var = inputs.iter().map(|x| (x[j] - mean[j]).powi(2)).sum::<f64>() / n;
+                if var > 1e-12 { 1.0 / var.sqrt() } else { 0.0 }
+            })
+            .collect();
+
+        let mut xavier = |fan_in: usize, fan_out: usize| -> Vec<f64> {
+            let limit = (6.0 / (fan_in + fan_out) as f64).sqrt();
+            (0..fan_in * fan_out).map(|_| rng.gen_range(-limit..limit)).collect()
+        };
+        Self {
+            input_dim,
+            hidden_dim,
+            output_dim,
+            w1: xavier(input_dim, hidden_dim),
+            b1: vec![0.0; hidden_dim],
+            w2: xavier(hidden_dim, output_dim),
+            b2: vec![0.0; output_dim],
+            mean,
+            scale,
+        }
+    }
+
+    /// Weight and standardization lengths agree with the declared widths
+    fn check_shapes(&self) -> Result<()> {
+        let expected = [
+            (self.mean.len(), self.input_dim),
+            (self.scale.len(), self.input_dim),
+            (self.w1.len(), self.hidden_dim * self.input_dim),
+            (self.b1.len(), self.hidden_dim),
+            (self.w2.len(), self.output_dim * self.hidden_dim),
+            (self.b2.len(), self.output_dim),
+        ];
+        if expected.iter().any(|(len, want)| len != want) {
+            return Err(Error::invalid_input(format!(
+                "Encoder weights do not match its {}x{}x{} shape",
+                self.input_dim, self.hidden_dim, self.output_dim
+            )));
+        }
+        Ok(())
+    }
+
+    /// Embedding of raw (unstandardized) features
+    pub fn encode(&self, features: &[f64]) -> Vec<f64> {
+        self.forward(self.standardize(features)).output
+    }
+
+    fn standardize(&self, features: &[f64]) -> Vec<f64> {
+        features.iter().zip(self.mean.iter().zip(&self.scale)).map(|(x, (m, s))| (x - m) * s).collect()
+    }
+
+    fn forward(&self, input: Vec<f64>) -> Forward {
+        let hidden: Vec<f64> = (0..self.hidden_dim)
+            .map(|i| {
+                let row = &self.w1[i * self.input_dim..(i + 1) * self.input_dim];
+                (dot(row, &input) + self.b1[i]).tanh()
+            })
+            .collect();
+        let raw: Vec<f64> = (0..self.output_dim)
+            .map(|i| dot(&self.w2[i * self.hidden_dim..(i + 1) * self.hidden_dim], &hidden) + self.b2[i])
+            .collect();
+        let norm = raw.iter().map(|x| x * x).sum::<f64>().sqrt().max(1e-12);
+        let output = raw.iter().map(|x| x / norm).collect();
+        Forward { input, hidden, norm, output }
+    }
+
+    /// Accumulate gradients of the loss given its gradient `d_out` w.r.t. the unit output
+    fn backward(&self, pass: &Forward, d_out: &[f64], grads: &mut Gradients) {
+        // Through the normalization: dy = (dz - z (z . dz)) / |y|
+        let projection = dot(&pass.output, d_out);
+        let d_raw: Vec<f64> = d_out.iter().zip(&pass.output).map(|(d, z)| (d - z * projection) / pass.norm).collect();
+
+        let mut d_hidden = vec![0.0; self.hidden_dim];
+        for (i, d) in d_raw.iter().enumerate() {
+            grads.b2[i] += d;
+            let row = i * self.hidden_dim;
+            for (j, h) in pass.hidden.iter().enumerate() {
+                grads.w2[row + j] += d * h;
+                d_hidden[j] += d * self.w2[row + j];
+            }
+        }
+        for (i, (d, h)) in d_hidden.iter().zip(&pass.hidden).enumerate() {
+            let d_pre = d * (1.0 - h * h);
+            grads.b1[i] += d_pre;
+            let row = i * self.input_dim;
+            for (j, x) in pass.input.iter().enumerate() {
+                grads.w1[row + j] += d_pre * x;
+            }
+        }
+    }
+
+    fn zero_gradients(&self) -> Gradients {
+        Gradients {
+            w1: vec![0.0; self.w1.len()],
+            b1: vec![0.0; self.b1.len()],
+            w2: vec![0.0; self.w2.len()],
+            b2: vec![0.0; self.b2.len()],
+        }
+    }
+
+    fn apply(&mut self, grads: &Gradients, learning_rate: f64) {
+        for (param, grad) in [
+            (&mut self.w1, &grads.w1),
+            (&mut self.b1, &grads.b1),
+            (&mut self.w2, &grads.w2),
+            (&mut self.b2, &grads.b2),
+        ] {
+            for (p, g) in param.iter_mut().zip(grad) {
+                *p -= learning_rate * g;
+            }
+        }
+    }
+
+    /// A noisy view of a standardized input
+    fn augment(&self, input: &[f64], config: &LearnedEmbeddingConfig, rng: &mut StdRng) -> Vec<f64> {
+        input.iter()
+            .map(|x| {
+                if rng.gen_bool(config.dropout.clamp(0.0, 1.0)) {
+                    0.0
+                } else {
+                    x + config.noise * gaussian(rng)
+                }
+            })
+            .collect()
+    }
+
+    /// Train on raw features; returns the mean loss of each epoch
+    fn train(inputs: &[Vec<f64>], config: &LearnedEmbeddingConfig, rng: &mut StdRng) -> (Self, Vec<f64>) {
+        let mut encoder = Self::new(inputs, config.hidden_dim, config.output_dim, rng);
+        let standardized: Vec<Vec<f64>> = inputs.iter().map(|x| encoder.standardize(x)).collect();
+        let mut order: Vec<usize> = (0..inputs.len()).collect();
+        let mut losses = Vec::with_capacity(config.epochs);
+
+        for _ in 0..config.epochs {
+            order.shuffle(rng);
+            let mut epoch_loss = 0.0;
+            let mut batches = 0;
+            for batch in order.chunks(config.batch_size.max(2)) {
+                // A batch of one has no negatives
+                if batch.len() < 2 {
+                    continue;
+                }
+                let views_a: Vec<Forward> = batch.iter()
+                    .map(|&i| encoder.forward(encoder.augment(&standardized[i], config, rng)))
+                    .collect();
+                let views_b: Vec<Forward> = batch.iter()
+                    .map(|&i| encoder.forward(encoder.augment(&standardized[i], config, rng)))
+                    .collect();
+                let (loss, d_a, d_b) = info_nce(&views_a, &views_b, config.temperature);
+
+                let mut grads = encoder.zero_gradients();
+                for (pass, d) in views_a.iter().zip(&d_a).chain(views_b.iter().zip(&d_b)) {
+                    encoder.backward(pass, d, &mut grads);
+                }
+                encoder.apply(&grads, config.learning_rate);
+                epoch_loss += loss;
+                batches += 1;
+            }
+            losses.push(epoch_loss / batches.max(1) as f64);
+        }
+        (encoder, losses)
+    }
+}
+
+/// Symmetric InfoNCE over a batch where `a[i]` and `b[i]` are the positive
+/// pair; returns the loss and its gradient w.r.t. each unit output
+fn info_nce(a: &[Forward], b: &[Forward], temperature: f64) -> (f64, Vec<Vec<f64>>, Vec<Vec<f64>>) {
+    let n = a.len();
+    let logits: Vec<Vec<f64>> = a.iter()
+        .map(|x| b.iter().map(|y| dot(&x.output, &y.output) / temperature).collect())
+        .collect();
+    let softmax = |values: Vec<f64>| -> Vec<f64> {
+        let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let exp: Vec<f64> = values.iter().map(|v| (v - max).exp()).collect();
+        let sum: f64 = exp.iter().sum();
+        exp.into_iter().map(|e| e / sum).collect()
+    };
+    let rows: Vec<Vec<f64>> = logits.iter().map(|r| softmax(r.clone())).collect();
+    let cols: Vec<Vec<f64>> = (0..n).map(|j| softmax((0..n).map(|i| logits[i][j]).collect())).collect();
+
+    let mut loss = 0.0;
+    // d_logits[i][j] = ((rows - I) + (cols^T - I)) / 2n
+    let mut d_logits = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        loss -= rows[i][i].max(1e-300).ln() + cols[i][i].max(1e-300).ln();
+        for j in 0..n {
+            let identity = if i == j { 1.0 } else { 0.0 };
+            d_logits[i][j] = (rows[i][j] - identity + cols[j][i] - identity) / (2.0 * n as f64);
+        }
+    }
+
+    let width = a[0].output.len();
+    let mut d_a = vec![vec![0.0; width]; n];
+    let mut d_b = vec![vec![0.0; width]; n];
+    for i in 0..n {
+        for j in 0..n {
+            let g = d_logits[i][j] / temperature;
+            for k in 0..width {
+                d_a[i][k] += g * b[j].output[k];
+                d_b[j][k] += g * a[i].output[k];
+            }
+        }
+    }
+    (loss / (2.0 * n as f64), d_a, d_b)
+}
+
+// ============================================================================
+// MODEL
+// ============================================================================
+
+/// Element embeddings plus encoder, identified by a weight-derived version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearnedEmbeddingModel {
+    /// "learned-e{element_dim}-o{output_dim}-{weights hash}"
+    pub version: String,
+    pub trained_at: chrono::DateTime<chrono::Utc>,
+    pub corpus_size: usize,
+    /// Name of the structure featurizer the encoder was trained with;
+    /// the engine must use the same one
+    pub structure_featurizer: Option<String>,
+    pub config: LearnedEmbeddingConfig,
+    pub elements: ElementEmbeddings,
+    pub encoder: ContrastiveEncoder,
+    /// Mean InfoNCE loss per epoch
+    pub training_loss: Vec<f64>,
+}
+
+impl LearnedEmbeddingModel {
+    /// Train on formulas with optional structures
+    ///
+    /// With a featurizer, structures feed the encoder's structure block;
+    /// corpus entries without one get a zero block.
+    pub fn train(
+        corpus: &[(&str, Option<&Structure>)],
+        featurizer: Option<&dyn StructureFeaturizer>,
+        config: LearnedEmbeddingConfig,
+    ) -> Result<Self> {
+        let blocks = corpus.iter()
+            .map(|(_, structure)| Self::structure_block(featurizer, *structure))
+            .collect::<Result<Vec<_>>>()?;
+        let corpus: Vec<(&str, Option<&[f64]>)> =
+            corpus.iter().zip(&blocks).map(|((formula, _), block)| (*formula, block.as_deref())).collect();
+        Self::train_on_blocks(&corpus, featurizer.map(|f| f.name()), config)
+    }
+
+    /// Train on formulas with structure blocks already computed by the
+    /// featurizer called `featurizer`
+    pub(crate) fn train_on_blocks(
+        corpus: &[(&str, Option<&[f64]>)],
+        featurizer: Option<&str>,
+        config: LearnedEmbeddingConfig,
+    ) -> Result<Self> {
+        if corpus.len() < 2 {
+            return Err(Error::invalid_input("Training needs at least two materials"));
+        }
+        let compositions = corpus.iter()
+            .map(|(formula, _)| EmbeddingEngine::parse_formula(formula).map_err(Error::invalid_input))
+            .collect::<Result<Vec<_>>>()?;
+        let elements = ElementEmbeddings::train(&compositions, config.element_dim)?;
+
+        let inputs: Vec<Vec<f64>> = compositions.iter().zip(corpus)
+            .map(|(composition, (_, block))| Self::features(&elements, featurizer.is_some(), composition, *block))
+            .collect();
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let (encoder, training_loss) = ContrastiveEncoder::train(&inputs, &config, &mut rng);
+
+        let weights = serde_json::to_vec(&(&elements, &encoder))?;
+        Ok(Self {
+            version: format!("learned-e{}-o{}-{:016x}", elements.dim, config.output_dim, fnv1a(&weights)),
+            trained_at: chrono::Utc::now(),
+            corpus_size: corpus.len(),
+            structure_featurizer: featurizer.map(str::to_string),
+            config,
+            elements,
+            encoder,
+            training_loss,
+        })
+    }
+
+    /// Train on stored materials, using their structures when they have sites
+    pub fn train_on_materials(
+        materials: &[Material],
+        featurizer: Option<&dyn StructureFeaturizer>,
+        config: LearnedEmbeddingConfig,
+    ) -> Result<Self> {
+        let corpus: Vec<(&str, Option<&Structure>)> = materials.iter()
+            .map(|m| (m.formula.as_str(), Some(&m.structure).filter(|s| !s.sites.is_empty())))
+            .collect();
+        Self::train(&corpus, featurizer, config)
+    }
+
+    /// Unit-norm embedding of a material
+    pub fn embed(
+        &self,
+        formula: &str,
+        structure: Option<&Structure>,
+        featurizer: Option<&dyn StructureFeaturizer>,
+    ) -> Result<Vec<f64>> {
+        let composition = EmbeddingEngine::parse_formula(formula).map_err(Error::invalid_input)?;
+        let block = Self::structure_block(featurizer, structure)?;
+        let features = Self::features(&self.elements, featurizer.is_some(), &composition, block.as_deref());
+        Ok(self.encoder.encode(&features))
+    }
+
+    /// Unit-norm embedding from a structure block computed by this model's
+    /// featurizer; `None` stands for a material without a structure
+    pub(crate) fn embed_block(&self, formula: &str, block: Option<&[f64]>) -> Result<Vec<f64>> {
+        let composition = EmbeddingEngine::parse_formula(formula).map_err(Error::invalid_input)?;
+        let features = Self::features(&self.elements, self.structure_featurizer.is_some(), &composition, block);
+        Ok(self.encoder.encode(&features))
+    }
+
+    /// Whether the model can embed with `featurizer`: it must be the one
+    /// the model was trained with, and the encoder must fit the element
+    /// vectors, that structure block and `EMBEDDING_DIM`
+    pub fn validate(&self, featurizer: Option<&dyn StructureFeaturizer>) -> Result<()> {
+        let configured = featurizer.map(|f| f.name());
+        if configured != self.structure_featurizer.as_deref() {
+            return Err(Error::invalid_input(format!(
+                "Model {} was trained with structure featurizer {:?}, engine has {:?}",
+                self.version, self.structure_featurizer, configured
+            )));
+        }
+        let block = if self.structure_featurizer.is_some() { STRUCTURE_BLOCK_DIM } else { 0 };
+        let input_dim = 2 * self.elements.dim + 2 + block;
+        if self.encoder.input_dim != input_dim {
+            return Err(Error::invalid_input(format!(
+                "Model {} encoder takes {} features, its element vectors and structure block give {}",
+                self.version, self.encoder.input_dim, input_dim
+            )));
+        }
+        if self.encoder.output_dim == 0 || self.encoder.output_dim > EMBEDDING_DIM {
+            return Err(Error::invalid_input(format!(
+                "Model {} output dimension {} is outside 1..={}",
+                self.version, self.encoder.output_dim, EMBEDDING_DIM
+            )));
+        }
+        self.encoder.check_shapes()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    /// Structure block of a structure with sites, when there is a featurizer
+    fn structure_block(
+        featurizer: Option<&dyn StructureFeaturizer>,
+        structure: Option<&Structure>,
+    ) -> Result<Option<Vec<f64>>> {
+        match (featurizer, structure.filter(|s| !s.sites.is_empty())) {
+            (Some(featurizer), Some(structure)) => {
+                EmbeddingEngine::structure_embedding(featurizer, structure).map(Some).map_err(Error::computation)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Pooled element vectors, then with `structured` the structure block
+    /// or zeros for a material without one
+    fn features(
+        elements: &ElementEmbeddings,
+        structured: bool,
+        composition: &HashMap<String, usize>,
+        block: Option<&[f64]>,
+    ) -> Vec<f64> {
+        let mut features = elements.pool(composition);
+        if structured {
+            match block {
+                Some(block) => features.extend_from_slice(block),
+                None => features.extend(std::iter::repeat(0.0).take(STRUCTURE_BLOCK_DIM)),
+            }
+        }
+        features
+    }
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn unit(vector: &[f64]) -> Vec<f64> {
+    let norm = vector.iter().map(|x| x * x).sum::<f64>().sqrt();
+    if norm > 1e-12 { vector.iter().map(|x| x / norm).collect() } else { vector.to_vec() }
+}
+
+/// Standard normal sample (Box-Muller)
+fn gaussian(rng: &mut StdRng) -> f64 {
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| (hash ^ *b as u64).wrapping_mul(0x100000001b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Alkali halides, alkaline-earth chalcogenides and transition-metal oxides
+    fn corpus() -> Vec<String> {
+        let mut formulas = Vec::new();
+        for a in ["Li", "Na", "K", "Rb", "Cs"] {
+            for x in ["F", "Cl", "Br", "I"] {
+                formulas.push(format!("{}{}", a, x));
+            }
+        }
+        for a in ["Mg", "Ca", "Sr", "Ba"] {
+            for x in ["O", "S", "Se"] {
+                formulas.push(format!("{}{}", a, x));
+            }
+        }
+        for m in ["Fe", "Co", "Ni", "Mn", "Cr"] {
+            formulas.push(format!("{}O", m));
+            formulas.push(format!("{}2O3", m));
+            formulas.push(format!("Li{}O2", m));
+        }
+        formulas
+    }
+
+    #[test]
+    fn test_cooccurrence_element_embeddings() {
+        let compositions: Vec<_> = corpus().iter().map(|f| EmbeddingEngine::parse_formula(f).unwrap()).collect();
+        let elements = ElementEmbeddings::train(&compositions, 8).unwrap();
+        assert!(elements.get("Xe").is_none());
+
+        // Shared contexts make chemical families close
+        let alkali = elements.similarity("Na", "K").unwrap();
+        assert!(alkali > elements.similarity("Na", "Fe").unwrap());
+        assert!(elements.similarity("Cl", "Br").unwrap() > elements.similarity("Cl", "S").unwrap());
+        let nearest: Vec<String> = elements.most_similar("Fe", 3).into_iter().map(|(e, _)| e).collect();
+        assert!(nearest.iter().all(|e| ["Co", "Ni", "Mn", "Cr"].contains(&e.as_str())), "{:?}", nearest);
+    }
+
+    #[test]
+    fn test_contrastive_model_training() {
+        let formulas = corpus();
+        let corpus: Vec<(&str, Option<&Structure>)> = formulas.iter().map(|f| (f.as_str(), None)).collect();
+        let config = LearnedEmbeddingConfig { epochs: 30, element_dim: 8, hidden_dim: 32, output_dim: 16, ..Default::default() };
+        let model = LearnedEmbeddingModel::train(&corpus, None, config.clone()).unwrap();
+
+        let first = model.training_loss[0];
+        let last = *model.training_loss.last().unwrap();
+        assert!(last < first, "loss {} -> {}", first, last);
+
+        let nacl = model.embed("NaCl", None, None).unwrap();
+        let kcl = model.embed("KCl", None, None).unwrap();
+        let fe2o3 = model.embed("Fe2O3", None, None).unwrap();
+        assert_eq!(nacl.len(), 16);
+        assert!((dot(&nacl, &nacl) - 1.0).abs() < 1e-9);
+        assert!(dot(&nacl, &kcl) > dot(&nacl, &fe2o3));
+
+        // Same data and seed give the same weights and version
+        let again = LearnedEmbeddingModel::train(&corpus, None, config).unwrap();
+        assert_eq!(model.version, again.version);
+        assert!(model.version.starts_with("learned-e8-o16-"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.json");
+        model.save(&path).unwrap();
+        let loaded = LearnedEmbeddingModel::load(&path).unwrap();
+        assert_eq!(loaded.version, model.version);
+        let reloaded = loaded.embed("NaCl", None, None).unwrap();
+        assert!(reloaded.iter().zip(&nacl).all(|(a, b)| (a - b).abs() < 1e-9));
+    }
+}
@@ -10,13 +10,35 @@ pub mod config;
 pub mod auto_optimizer;
 pub mod feature_flags;
 pub mod lirs;
+pub mod elements;
+pub mod composition;
+pub mod oxidation;
+pub mod economics;
+pub mod featurizer;
+pub mod structure_descriptors;
+pub mod prototypes;
+pub mod cell_reduction;
+pub mod structure_matcher;
 
 // 🧠 Advanced Intelligence Modules
 pub mod embeddings;
+pub mod clustering;
+pub mod projection;
+pub mod embedding_store;
+pub mod learned_embeddings;
+pub mod hnsw;
 pub mod ml_predictor;
+pub mod tree_ensemble;
+pub mod gaussian_process;
 pub mod knowledge_graph;
+pub mod graph_algorithms;
+pub mod link_prediction;
 pub mod discovery;
+pub mod active_learning;
+pub mod bayesian_optimization;
+pub mod pareto;
 pub mod recommendations;
+pub mod collaborative_filtering;
 
 // 💊 Drug Discovery Module (re-exports from drugs-core and drugs-molecular)
 pub mod drugs {
@@ -44,6 +66,7 @@ pub mod compression {
 
     pub use materials_chaos_compression::attractor_compress;
     pub use materials_chaos_compression::attractor_decompress;
+    pub use materials_chaos_compression::{delta_lossless_compress, delta_lossless_decompress};
 }
 
 // 🧬 Graph Neural Networks
@@ -64,12 +87,14 @@ pub mod repl;
 
 // 🔬 High-Throughput Screening
 pub mod hts;
+pub mod hts_export;
 
 // 🔷 Advanced Crystallography
 pub mod crystallography;
 
 pub use error::{Error, Result};
 pub use material::Material;
+pub use composition::Composition;
 pub use property::Property;
 pub use config::Config;
 
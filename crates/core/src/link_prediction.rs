@@ -0,0 +1,769 @@
+//! Link prediction on the materials knowledge graph
+//!
+//! Suggests missing edges such as `Substitution` or `SameApplication`:
+//!
+//! - Neighborhood baselines: common neighbors and Adamic-Adar
+//! - Knowledge-graph embeddings: TransE (h + r ≈ t) and RotatE (h ∘ r ≈ t
+//!   in complex space), trained with a margin ranking loss against
+//!   corrupted triples
+//!
+//! Raw scores become confidences through Platt scaling fitted on edges
+//! held out from the scorer's training, so in-sample scores do not inflate
+//! them, and `evaluate_held_out` reports filtered ranking metrics on edges
+//! hidden from training.
+
+use crate::knowledge_graph::{KnowledgeEdge, KnowledgeNode, RelationType};
+use crate::{Error, Result};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Scores how plausible an edge `from -relation- to` is; higher is more likely
+pub trait LinkScorer: Send + Sync {
+    fn name(&self) -> String;
+
+    /// `None` when the scorer knows nothing about the nodes or relation
+    fn score(&self, from: Uuid, to: Uuid, relation: &RelationType) -> Option<f64>;
+}
+
+// ============================================================================
+// NEIGHBORHOOD HEURISTICS
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Heuristic {
+    /// |N(u) ∩ N(v)|
+    CommonNeighbors,
+    /// Σ 1 / ln |N(w)| over common neighbors w
+    AdamicAdar,
+}
+
+/// Neighborhood-overlap scores over all edges, whatever their relation
+pub struct HeuristicScorer {
+    method: Heuristic,
+    neighbors: HashMap<Uuid, HashSet<Uuid>>,
+}
+
+impl HeuristicScorer {
+    pub fn fit(edges: &[KnowledgeEdge], method: Heuristic) -> Self {
+        Self { method, neighbors: neighbor_sets(edges) }
+    }
+}
+
+impl LinkScorer for HeuristicScorer {
+    fn name(&self) -> String {
+        match self.method {
+            Heuristic::CommonNeighbors => "common_neighbors".to_string(),
+            Heuristic::AdamicAdar => "adamic_adar".to_string(),
+        }
+    }
+
+    fn score(&self, from: Uuid, to: Uuid, _relation: &RelationType) -> Option<f64> {
+        let (Some(a), Some(b)) = (self.neighbors.get(&from), self.neighbors.get(&to)) else {
+            return Some(0.0);
+        };
+        let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+        let common = small.iter().filter(|w| large.contains(w));
+        Some(match self.method {
+            Heuristic::CommonNeighbors => common.count() as f64,
+            Heuristic::AdamicAdar => common
+                .map(|w| {
+                    let degree = self.neighbors.get(w).map_or(0, HashSet::len) as f64;
+                    if degree > 1.0 { 1.0 / degree.ln() } else { 0.0 }
+                })
+                .sum(),
+        })
+    }
+}
+
+fn neighbor_sets(edges: &[KnowledgeEdge]) -> HashMap<Uuid, HashSet<Uuid>> {
+    let mut neighbors: HashMap<Uuid, HashSet<Uuid>> = HashMap::new();
+    for edge in edges.iter().filter(|e| e.from != e.to) {
+        neighbors.entry(edge.from).or_default().insert(edge.to);
+        neighbors.entry(edge.to).or_default().insert(edge.from);
+    }
+    neighbors
+}
+
+// ============================================================================
+// KNOWLEDGE-GRAPH EMBEDDINGS
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KgeModel {
+    TransE,
+    RotatE,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KgeConfig {
+    pub model: KgeModel,
+    /// Real dimensions for TransE, complex dimensions for RotatE
+    pub dim: usize,
+    pub epochs: usize,
+    pub learning_rate: f64,
+    pub margin: f64,
+    /// Corrupted triples per training edge
+    pub negatives: usize,
+    pub seed: u64,
+}
+
+impl KgeConfig {
+    pub fn new(model: KgeModel) -> Self {
+        Self {
+            model,
+            dim: 32,
+            epochs: 200,
+            learning_rate: 0.01,
+            margin: 1.0,
+            negatives: 4,
+            seed: 42,
+        }
+    }
+}
+
+/// Trained entity and relation embeddings; scores are negative distances
+/// averaged over both edge directions, since graph edges are undirected
+pub struct KgEmbedding {
+    config: KgeConfig,
+    index: HashMap<Uuid, usize>,
+    relations: HashMap<RelationType, usize>,
+    /// Row-major; RotatE rows hold the real parts then the imaginary parts
+    entities: Vec<f64>,
+    /// Translations (TransE) or rotation phases (RotatE)
+    relation_params: Vec<f64>,
+    /// Mean margin loss per epoch
+    pub losses: Vec<f64>,
+}
+
+impl KgEmbedding {
+    pub fn fit(nodes: &[KnowledgeNode], edges: &[KnowledgeEdge], config: KgeConfig) -> Result<Self> {
+        if config.dim == 0 {
+            return Err(Error::invalid_input("embedding dimension must be positive"));
+        }
+        let mut ids: Vec<Uuid> = nodes.iter().map(|n| n.material_id).collect();
+        ids.sort();
+        ids.dedup();
+        if ids.len() < 2 {
+            return Err(Error::invalid_input("need at least two nodes to train embeddings"));
+        }
+        let index: HashMap<Uuid, usize> = ids.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+
+        let mut relation_list: Vec<&RelationType> = edges.iter().map(|e| &e.relation_type).collect();
+        relation_list.sort_by(|a, b| (a.label(), a.detail()).cmp(&(b.label(), b.detail())));
+        relation_list.dedup();
+        let relations: HashMap<RelationType, usize> = relation_list.into_iter()
+            .enumerate()
+            .map(|(i, r)| (r.clone(), i))
+            .collect();
+
+        let mut triples: Vec<(usize, usize, usize)> = Vec::new();
+        for edge in edges {
+            if let (Some(&h), Some(&t)) = (index.get(&edge.from), index.get(&edge.to)) {
+                let r = relations[&edge.relation_type];
+                triples.push((h, r, t));
+                triples.push((t, r, h));
+            }
+        }
+        if triples.is_empty() {
+            return Err(Error::invalid_input("no edges between known nodes"));
+        }
+        let known: HashSet<(usize, usize, usize)> = triples.iter().copied().collect();
+
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let dim = config.dim;
+        let (entities, relation_params) = match config.model {
+            KgeModel::TransE => {
+                let bound = 6.0 / (dim as f64).sqrt();
+                let mut entities: Vec<f64> = (0..ids.len() * dim).map(|_| rng.gen_range(-bound..bound)).collect();
+                for row in entities.chunks_mut(dim) {
+                    normalize(row);
+                }
+                let mut params: Vec<f64> = (0..relations.len() * dim).map(|_| rng.gen_range(-bound..bound)).collect();
+                for row in params.chunks_mut(dim) {
+                    normalize(row);
+                }
+                (entities, params)
+            }
+            KgeModel::RotatE => {
+                let bound = 1.0 / (dim as f64).sqrt();
+                let entities = (0..ids.len() * 2 * dim).map(|_| rng.gen_range(-bound..bound)).collect();
+                let params = (0..relations.len() * dim)
+                    .map(|_| rng.gen_range(-std::f64::consts::PI..std::f64::consts::PI))
+                    .collect();
+                (entities, params)
+            }
+        };
+
+        let mut model = Self { config, index, relations, entities, relation_params, losses: Vec::new() };
+        let n = ids.len();
+        for _ in 0..model.config.epochs {
+            triples.shuffle(&mut rng);
+            let mut total = 0.0;
+            let mut count = 0usize;
+            for &(h, r, t) in &triples {
+                for _ in 0..model.config.negatives {
+                    // Corrupt head or tail, skipping corruptions that are real edges
+                    let (nh, nt) = if rng.gen_bool(0.5) { (rng.gen_range(0..n), t) } else { (h, rng.gen_range(0..n)) };
+                    if known.contains(&(nh, r, nt)) {
+                        continue;
+                    }
+                    let loss = model.config.margin + model.distance(h, r, t) - model.distance(nh, r, nt);
+                    count += 1;
+                    if loss > 0.0 {
+                        total += loss;
+                        let lr = model.config.learning_rate;
+                        model.step(h, r, t, lr);
+                        model.step(nh, r, nt, -lr);
+                    }
+                }
+                if model.config.model == KgeModel::TransE {
+                    let dim = model.config.dim;
+                    for e in [h, t] {
+                        normalize(&mut model.entities[e * dim..(e + 1) * dim]);
+                    }
+                }
+            }
+            model.losses.push(if count > 0 { total / count as f64 } else { 0.0 });
+        }
+        Ok(model)
+    }
+
+    /// ||h + r - t|| for TransE, ||h ∘ r - t|| for RotatE
+    fn distance(&self, h: usize, r: usize, t: usize) -> f64 {
+        self.residual(h, r, t).iter().map(|x| x * x).sum::<f64>().sqrt()
+    }
+
+    fn residual(&self, h: usize, r: usize, t: usize) -> Vec<f64> {
+        let dim = self.config.dim;
+        match self.config.model {
+            KgeModel::TransE => {
+                let (hv, tv) = (&self.entities[h * dim..(h + 1) * dim], &self.entities[t * dim..(t + 1) * dim]);
+                let rv = &self.relation_params[r * dim..(r + 1) * dim];
+                (0..dim).map(|k| hv[k] + rv[k] - tv[k]).collect()
+            }
+            KgeModel::RotatE => {
+                let (hv, tv) = (&self.entities[h * 2 * dim..(h + 1) * 2 * dim], &self.entities[t * 2 * dim..(t + 1) * 2 * dim]);
+                let phases = &self.relation_params[r * dim..(r + 1) * dim];
+                let mut residual = vec![0.0; 2 * dim];
+                for k in 0..dim {
+                    let (sin, cos) = phases[k].sin_cos();
+                    residual[k] = hv[k] * cos - hv[dim + k] * sin - tv[k];
+                    residual[dim + k] = hv[k] * sin + hv[dim + k] * cos - tv[dim + k];
+                }
+                residual
+            }
+        }
+    }
+
+    /// Gradient step on the distance of one triple: positive `lr` pulls it
+    /// together, negative pushes it apart
+    fn step(&mut self, h: usize, r: usize, t: usize, lr: f64) {
+        let residual = self.residual(h, r, t);
+        let norm = residual.iter().map(|x| x * x).sum::<f64>().sqrt();
+        if norm < 1e-12 {
+            return;
+        }
+        let grad: Vec<f64> = residual.iter().map(|x| x / norm).collect();
+        let dim = self.config.dim;
+        match self.config.model {
+            KgeModel::TransE => {
+                for (k, g) in grad.iter().enumerate() {
+                    self.entities[h * dim + k] -= lr * g;
+                    self.relation_params[r * dim + k] -= lr * g;
+                    self.entities[t * dim + k] += lr * g;
+                }
+            }
+            KgeModel::RotatE => {
+                let (hb, tb) = (h * 2 * dim, t * 2 * dim);
+                for k in 0..dim {
+                    let (sin, cos) = self.relation_params[r * dim + k].sin_cos();
+                    let (h_re, h_im) = (self.entities[hb + k], self.entities[hb + dim + k]);
+                    let (g_re, g_im) = (grad[k], grad[dim + k]);
+                    let rotated_re = h_re * cos - h_im * sin;
+                    let rotated_im = h_re * sin + h_im * cos;
+
+                    self.entities[hb + k] -= lr * (g_re * cos + g_im * sin);
+                    self.entities[hb + dim + k] -= lr * (-g_re * sin + g_im * cos);
+                    self.relation_params[r * dim + k] -= lr * (-g_re * rotated_im + g_im * rotated_re);
+                    self.entities[tb + k] += lr * g_re;
+                    self.entities[tb + dim + k] += lr * g_im;
+                }
+            }
+        }
+    }
+}
+
+impl LinkScorer for KgEmbedding {
+    fn name(&self) -> String {
+        match self.config.model {
+            KgeModel::TransE => format!("transe-d{}", self.config.dim),
+            KgeModel::RotatE => format!("rotate-d{}", self.config.dim),
+        }
+    }
+
+    fn score(&self, from: Uuid, to: Uuid, relation: &RelationType) -> Option<f64> {
+        let (&h, &t) = (self.index.get(&from)?, self.index.get(&to)?);
+        let &r = self.relations.get(relation)?;
+        Some(-(self.distance(h, r, t) + self.distance(t, r, h)) / 2.0)
+    }
+}
+
+fn normalize(row: &mut [f64]) {
+    let norm = row.iter().map(|x| x * x).sum::<f64>().sqrt();
+    if norm > 1e-12 {
+        row.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+// ============================================================================
+// CALIBRATION AND SUGGESTIONS
+// ============================================================================
+
+/// Platt scaling: confidence = sigmoid(a * score + b)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Calibration {
+    pub a: f64,
+    pub b: f64,
+}
+
+impl Calibration {
+    /// Logistic regression of label on score, positives vs negatives
+    pub fn fit(positives: &[f64], negatives: &[f64]) -> Self {
+        let samples: Vec<(f64, f64)> = positives.iter().map(|&s| (s, 1.0))
+            .chain(negatives.iter().map(|&s| (s, 0.0)))
+            .collect();
+        if positives.is_empty() || negatives.is_empty() {
+            return Self { a: 0.0, b: 0.0 };
+        }
+        // Fit on standardized scores, then fold the scaling back into (a, b)
+        let mean = samples.iter().map(|(s, _)| s).sum::<f64>() / samples.len() as f64;
+        let std = (samples.iter().map(|(s, _)| (s - mean).powi(2)).sum::<f64>() / samples.len() as f64).sqrt().max(1e-12);
+        let (mut a, mut b) = (0.0, 0.0);
+        for _ in 0..500 {
+            let (mut ga, mut gb) = (0.0, 0.0);
+            for &(s, y) in &samples {
+                let x = (s - mean) / std;
+                let err = sigmoid(a * x + b) - y;
+                ga += err * x;
+                gb += err;
+            }
+            // Small L2 term keeps separable data from diverging
+            a -= 0.5 * (ga / samples.len() as f64 + 1e-3 * a);
+            b -= 0.5 * gb / samples.len() as f64;
+        }
+        Self { a: a / std, b: b - a * mean / std }
+    }
+
+    pub fn confidence(&self, score: f64) -> f64 {
+        sigmoid(self.a * score + self.b)
+    }
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestionConfig {
+    pub relations: Vec<RelationType>,
+    /// Suggestions kept per relation
+    pub top_k: usize,
+    pub min_confidence: f64,
+    /// Also consider pairs further than two hops apart; costs O(n²) scoring
+    pub all_pairs: bool,
+    /// Share of each relation's edges held out of fitting to calibrate on
+    #[serde(default = "default_calibration_fraction")]
+    pub calibration_fraction: f64,
+    pub seed: u64,
+}
+
+fn default_calibration_fraction() -> f64 {
+    0.2
+}
+
+impl Default for SuggestionConfig {
+    fn default() -> Self {
+        Self {
+            relations: vec![RelationType::Substitution, RelationType::SameApplication],
+            top_k: 20,
+            min_confidence: 0.5,
+            all_pairs: false,
+            calibration_fraction: default_calibration_fraction(),
+            seed: 42,
+        }
+    }
+}
+
+/// A missing edge proposed by a scorer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkSuggestion {
+    pub from: Uuid,
+    pub to: Uuid,
+    pub relation: RelationType,
+    pub score: f64,
+    pub confidence: f64,
+    pub model: String,
+}
+
+impl LinkSuggestion {
+    /// Edge carrying the suggestion's confidence and provenance
+    pub fn to_edge(&self) -> KnowledgeEdge {
+        let mut metadata = HashMap::new();
+        metadata.insert("source".to_string(), "link_prediction".to_string());
+        metadata.insert("model".to_string(), self.model.clone());
+        metadata.insert("score".to_string(), format!("{:.6}", self.score));
+        KnowledgeEdge {
+            from: self.from,
+            to: self.to,
+            relation_type: self.relation.clone(),
+            weight: self.confidence,
+            confidence: self.confidence,
+            metadata,
+            created_at: chrono::Utc::now(),
+        }
+    }
+}
+
+/// Highest-confidence missing edges of each configured relation
+///
+/// `fit` trains the scorer on the edges minus `calibration_fraction` of
+/// each configured relation's edges; confidences come from Platt scaling
+/// of those held-out edges against random unlinked pairs, and the same
+/// scorer ranks the candidates. Candidates are unlinked pairs within two
+/// hops (or every pair with `all_pairs`). Relations with fewer than two
+/// edges cannot be calibrated and get no suggestions.
+pub fn suggest_links<F>(
+    nodes: &[KnowledgeNode],
+    edges: &[KnowledgeEdge],
+    config: &SuggestionConfig,
+    fit: F,
+) -> Result<Vec<LinkSuggestion>>
+where
+    F: Fn(&[KnowledgeNode], &[KnowledgeEdge]) -> Result<Box<dyn LinkScorer>>,
+{
+    if !(config.calibration_fraction > 0.0 && config.calibration_fraction < 1.0) {
+        return Err(Error::invalid_input("calibration_fraction must be in (0, 1)"));
+    }
+    let mut ids: Vec<Uuid> = nodes.iter().map(|n| n.material_id).collect();
+    ids.sort();
+    ids.dedup();
+    let neighbors = neighbor_sets(edges);
+    let mut rng = StdRng::seed_from_u64(config.seed);
+
+    // Hold out part of every configured relation, at least one edge and
+    // never all of them
+    let mut held_out: HashMap<&RelationType, Vec<(Uuid, Uuid)>> = HashMap::new();
+    let mut hidden: HashSet<usize> = HashSet::new();
+    for relation in &config.relations {
+        let mut members: Vec<usize> = (0..edges.len()).filter(|&i| &edges[i].relation_type == relation).collect();
+        if members.len() < 2 {
+            continue;
+        }
+        members.shuffle(&mut rng);
+        let count = ((members.len() as f64 * config.calibration_fraction).round() as usize).clamp(1, members.len() - 1);
+        held_out.insert(relation, members[..count].iter().map(|&i| (edges[i].from, edges[i].to)).collect());
+        hidden.extend(&members[..count]);
+    }
+    if held_out.is_empty() {
+        return Ok(Vec::new());
+    }
+    let train: Vec<KnowledgeEdge> = edges.iter().enumerate()
+        .filter(|(i, _)| !hidden.contains(i))
+        .map(|(_, e)| e.clone())
+        .collect();
+    let scorer = fit(nodes, &train)?;
+
+    let candidates: Vec<(Uuid, Uuid)> = if config.all_pairs {
+        ids.iter().enumerate()
+            .flat_map(|(i, &a)| ids[i + 1..].iter().map(move |&b| (a, b)))
+            .collect()
+    } else {
+        let mut pairs = HashSet::new();
+        for (node, adjacent) in &neighbors {
+            for mid in adjacent {
+                for &other in neighbors.get(mid).into_iter().flatten() {
+                    if other != *node {
+                        pairs.insert(((*node).min(other), (*node).max(other)));
+                    }
+                }
+            }
+        }
+        let mut pairs: Vec<(Uuid, Uuid)> = pairs.into_iter().collect();
+        pairs.sort();
+        pairs
+    };
+
+    let mut suggestions = Vec::new();
+    for relation in &config.relations {
+        let Some(calibration_edges) = held_out.get(relation) else {
+            continue;
+        };
+        let linked: HashSet<(Uuid, Uuid)> = edges.iter()
+            .filter(|e| &e.relation_type == relation)
+            .map(|e| (e.from.min(e.to), e.from.max(e.to)))
+            .collect();
+        let positives: Vec<f64> = calibration_edges.iter().filter_map(|&(a, b)| scorer.score(a, b, relation)).collect();
+        let negatives: Vec<f64> = random_unlinked(&ids, &linked, positives.len().max(10), &mut rng)
+            .into_iter()
+            .filter_map(|(a, b)| scorer.score(a, b, relation))
+            .collect();
+        let calibration = Calibration::fit(&positives, &negatives);
+
+        let mut scored: Vec<LinkSuggestion> = candidates.iter()
+            .filter(|pair| !linked.contains(pair))
+            .filter_map(|&(from, to)| {
+                let score = scorer.score(from, to, relation)?;
+                let confidence = calibration.confidence(score);
+                (confidence >= config.min_confidence).then(|| LinkSuggestion {
+                    from,
+                    to,
+                    relation: relation.clone(),
+                    score,
+                    confidence,
+                    model: scorer.name(),
+                })
+            })
+            .collect();
+        scored.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        scored.truncate(config.top_k);
+        suggestions.extend(scored);
+    }
+    Ok(suggestions)
+}
+
+fn random_unlinked(ids: &[Uuid], linked: &HashSet<(Uuid, Uuid)>, count: usize, rng: &mut StdRng) -> Vec<(Uuid, Uuid)> {
+    let mut pairs = Vec::with_capacity(count);
+    if ids.len() < 2 {
+        return pairs;
+    }
+    for _ in 0..count * 20 {
+        if pairs.len() == count {
+            break;
+        }
+        let (a, b) = (ids[rng.gen_range(0..ids.len())], ids[rng.gen_range(0..ids.len())]);
+        let pair = (a.min(b), a.max(b));
+        if a != b && !linked.contains(&pair) {
+            pairs.push(pair);
+        }
+    }
+    pairs
+}
+
+// ============================================================================
+// EVALUATION
+// ============================================================================
+
+/// Filtered ranking metrics on held-out edges
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkEvaluation {
+    pub model: String,
+    pub test_edges: usize,
+    /// Mean reciprocal rank of the true endpoint among all nodes
+    pub mrr: f64,
+    pub hits_at_1: f64,
+    pub hits_at_3: f64,
+    pub hits_at_10: f64,
+    /// Probability a held-out edge outscores a random unlinked pair
+    pub auc: f64,
+}
+
+/// Hide `test_fraction` of the edges of `relations`, fit on the rest and
+/// rank each hidden edge's endpoint against every other node
+///
+/// Ranks are filtered (other true endpoints do not count against the
+/// hidden one) and ties count half, so constant scorers rank mid-table.
+pub fn evaluate_held_out<F>(
+    nodes: &[KnowledgeNode],
+    edges: &[KnowledgeEdge],
+    relations: &[RelationType],
+    test_fraction: f64,
+    seed: u64,
+    fit: F,
+) -> Result<LinkEvaluation>
+where
+    F: Fn(&[KnowledgeNode], &[KnowledgeEdge]) -> Result<Box<dyn LinkScorer>>,
+{
+    if !(0.0..1.0).contains(&test_fraction) {
+        return Err(Error::invalid_input("test_fraction must be in [0, 1)"));
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut target: Vec<usize> = (0..edges.len()).filter(|&i| relations.contains(&edges[i].relation_type)).collect();
+    target.shuffle(&mut rng);
+    let n_test = ((target.len() as f64 * test_fraction).round() as usize).min(target.len());
+    if n_test == 0 {
+        return Err(Error::invalid_input("no held-out edges for the requested relations"));
+    }
+    let test: HashSet<usize> = target[..n_test].iter().copied().collect();
+    let train: Vec<KnowledgeEdge> = edges.iter().enumerate()
+        .filter(|(i, _)| !test.contains(i))
+        .map(|(_, e)| e.clone())
+        .collect();
+    let scorer = fit(nodes, &train)?;
+
+    let mut ids: Vec<Uuid> = nodes.iter().map(|n| n.material_id).collect();
+    ids.sort();
+    ids.dedup();
+    let all_true: HashSet<(Uuid, Uuid, &RelationType)> = edges.iter()
+        .flat_map(|e| [(e.from, e.to, &e.relation_type), (e.to, e.from, &e.relation_type)])
+        .collect();
+
+    let (mut mrr, mut hits) = (0.0, [0usize; 3]);
+    let (mut wins, mut comparisons) = (0.0, 0usize);
+    let mut ordered: Vec<usize> = test.into_iter().collect();
+    ordered.sort_unstable();
+    for &i in &ordered {
+        let edge = &edges[i];
+        let relation = &edge.relation_type;
+        let true_score = scorer.score(edge.from, edge.to, relation).unwrap_or(f64::NEG_INFINITY);
+
+        let mut rank = 1.0;
+        for &candidate in &ids {
+            if candidate == edge.from || candidate == edge.to || all_true.contains(&(edge.from, candidate, relation)) {
+                continue;
+            }
+            let s = scorer.score(edge.from, candidate, relation).unwrap_or(f64::NEG_INFINITY);
+            if s > true_score {
+                rank += 1.0;
+            } else if s == true_score {
+                rank += 0.5;
+            }
+        }
+        mrr += 1.0 / rank;
+        for (slot, k) in [1.0, 3.0, 10.0].into_iter().enumerate() {
+            if rank <= k {
+                hits[slot] += 1;
+            }
+        }
+
+        let linked: HashSet<(Uuid, Uuid)> = all_true.iter()
+            .filter(|(_, _, r)| *r == relation)
+            .map(|(a, b, _)| ((*a).min(*b), (*a).max(*b)))
+            .collect();
+        for (a, b) in random_unlinked(&ids, &linked, 5, &mut rng) {
+            let s = scorer.score(a, b, relation).unwrap_or(f64::NEG_INFINITY);
+            comparisons += 1;
+            if true_score > s {
+                wins += 1.0;
+            } else if true_score == s {
+                wins += 0.5;
+            }
+        }
+    }
+
+    let n = ordered.len() as f64;
+    Ok(LinkEvaluation {
+        model: scorer.name(),
+        test_edges: ordered.len(),
+        mrr: mrr / n,
+        hits_at_1: hits[0] as f64 / n,
+        hits_at_3: hits[1] as f64 / n,
+        hits_at_10: hits[2] as f64 / n,
+        auc: if comparisons > 0 { wins / comparisons as f64 } else { 0.5 },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Three families of five materials; members of a family substitute
+    /// for each other and share an application
+    fn families() -> (Vec<KnowledgeNode>, Vec<KnowledgeEdge>) {
+        let nodes: Vec<KnowledgeNode> = (0..15)
+            .map(|i| KnowledgeNode {
+                material_id: Uuid::from_u128(i as u128 + 1),
+                formula: format!("M{i}O2"),
+                properties: HashMap::new(),
+                tags: Vec::new(),
+                importance_score: 0.5,
+            })
+            .collect();
+        let mut edges = Vec::new();
+        for family in 0..3 {
+            for a in 0..5 {
+                for b in a + 1..5 {
+                    let relation_type = if (a + b) % 2 == 0 { RelationType::Substitution } else { RelationType::SameApplication };
+                    edges.push(KnowledgeEdge {
+                        from: nodes[family * 5 + a].material_id,
+                        to: nodes[family * 5 + b].material_id,
+                        relation_type,
+                        weight: 1.0,
+                        confidence: 1.0,
+                        metadata: HashMap::new(),
+                        created_at: chrono::Utc::now(),
+                    });
+                }
+            }
+        }
+        (nodes, edges)
+    }
+
+    #[test]
+    fn test_heuristic_suggestions() {
+        let (nodes, mut edges) = families();
+        let hidden = edges.remove(0);
+        let scorer = HeuristicScorer::fit(&edges, Heuristic::AdamicAdar);
+        let fit = |_: &[KnowledgeNode], e: &[KnowledgeEdge]| {
+            Ok(Box::new(HeuristicScorer::fit(e, Heuristic::AdamicAdar)) as Box<dyn LinkScorer>)
+        };
+
+        let suggestions = suggest_links(&nodes, &edges, &SuggestionConfig::default(), fit).unwrap();
+        let linked: HashSet<(Uuid, Uuid, RelationType)> =
+            edges.iter().map(|e| (e.from.min(e.to), e.from.max(e.to), e.relation_type.clone())).collect();
+        assert!(suggestions.iter().all(|s| !linked.contains(&(s.from, s.to, s.relation.clone()))));
+        let pair = (hidden.from.min(hidden.to), hidden.from.max(hidden.to));
+        let found = suggestions.iter()
+            .find(|s| (s.from, s.to) == pair && s.relation == hidden.relation_type)
+            .expect("hidden edge is suggested");
+        assert!(found.confidence > 0.5);
+        assert_eq!(found.to_edge().metadata["model"], "adamic_adar");
+
+        // Cross-family pairs share no neighbors
+        let other_family = nodes[5].material_id;
+        assert_eq!(scorer.score(nodes[0].material_id, other_family, &RelationType::Substitution), Some(0.0));
+
+        // The scorer is fitted without the calibration edges: a fifth of
+        // the 12 Substitution and 17 SameApplication edges
+        let trained = std::sync::Mutex::new(Vec::new());
+        let recording = |n: &[KnowledgeNode], e: &[KnowledgeEdge]| {
+            trained.lock().unwrap().push(e.len());
+            fit(n, e)
+        };
+        suggest_links(&nodes, &edges, &SuggestionConfig::default(), recording).unwrap();
+        assert_eq!(*trained.lock().unwrap(), vec![edges.len() - 5]);
+
+        let bad = SuggestionConfig { calibration_fraction: 1.0, ..SuggestionConfig::default() };
+        assert!(suggest_links(&nodes, &edges, &bad, fit).is_err());
+    }
+
+    #[test]
+    fn test_embeddings_beat_chance_on_held_out_edges() {
+        let (nodes, edges) = families();
+        let relations = [RelationType::Substitution, RelationType::SameApplication];
+
+        for model in [KgeModel::TransE, KgeModel::RotatE] {
+            let evaluation = evaluate_held_out(&nodes, &edges, &relations, 0.2, 7, |n, e| {
+                let config = KgeConfig { dim: 16, epochs: 150, ..KgeConfig::new(model) };
+                Ok(Box::new(KgEmbedding::fit(n, e, config)?) as Box<dyn LinkScorer>)
+            })
+            .unwrap();
+            assert_eq!(evaluation.test_edges, 6);
+            assert!(evaluation.auc > 0.8, "{model:?}: {evaluation:?}");
+            assert!(evaluation.mrr > 0.3, "{model:?}: {evaluation:?}");
+        }
+
+        let baseline = evaluate_held_out(&nodes, &edges, &relations, 0.2, 7, |_, e| {
+            Ok(Box::new(HeuristicScorer::fit(e, Heuristic::CommonNeighbors)) as Box<dyn LinkScorer>)
+        })
+        .unwrap();
+        assert!(baseline.hits_at_10 > 0.9);
+        assert!(evaluate_held_out(&nodes, &edges, &relations, 1.5, 7, |_, e| {
+            Ok(Box::new(HeuristicScorer::fit(e, Heuristic::CommonNeighbors)) as Box<dyn LinkScorer>)
+        })
+        .is_err());
+    }
+}
@@ -8,6 +8,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
+use crate::composition::Composition;
 
 /// S-Expression - The fundamental data structure
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -540,25 +541,33 @@ impl Evaluator {
             return Err("Third argument must be an element".to_string());
         };
 
-        let new_formula = formula.replace(from_el, to_el);
-        Ok(SExpr::Atom(Atom::String(new_formula)))
+        let composition = Composition::parse(&formula).map_err(|e| e.to_string())?;
+        let substituted = Composition::from_amounts(
+            composition.iter().map(|(e, a)| (if e == from_el.as_str() { to_el.as_str() } else { e }, a)),
+        )
+        .map_err(|e| e.to_string())?
+        .with_charge(composition.charge());
+        Ok(SExpr::Atom(Atom::String(substituted.to_string())))
     }
 
     fn eval_combine(&mut self, args: &[SExpr]) -> Result<SExpr, String> {
-        // (combine "Fe2O3" "Al2O3") => "Fe2Al2O6"
-        // Simplified - just concatenates
-        let mut result = String::new();
+        // (combine "Fe2O3" "Al2O3") => "Fe2O6Al2"
+        let mut amounts: Vec<(String, f64)> = Vec::new();
+        let mut charge = 0;
 
         for arg in args {
             let material = self.eval(arg.clone())?;
             if let SExpr::Atom(Atom::String(s)) = material {
-                result.push_str(&s);
+                let composition = Composition::parse(&s).map_err(|e| e.to_string())?;
+                amounts.extend(composition.iter().map(|(e, a)| (e.to_string(), a)));
+                charge += composition.charge();
             } else {
                 return Err("combine requires material strings".to_string());
             }
         }
 
-        Ok(SExpr::Atom(Atom::String(result)))
+        let combined = Composition::from_amounts(amounts).map_err(|e| e.to_string())?.with_charge(charge);
+        Ok(SExpr::Atom(Atom::String(combined.to_string())))
     }
 }
 
@@ -1024,6 +1033,7 @@ pub mod ai {
                 forbidden_elements: vec![],
                 application: None,
                 objective: OptimizationObjective::Minimize(target_property.to_string()),
+                economics: None,
             };
 
             let candidates = discovery.discover_materials(target, max_candidates).await?;
@@ -1231,6 +1241,19 @@ mod tests {
 
         let result = lirs.eval_last(code).unwrap();
         assert_eq!(result, SExpr::Atom(Atom::String("Co2O3".to_string())));
+
+        // Whole symbols only: substituting C must leave Co alone
+        let result = lirs.eval_last("(substitute \"CoCO3\" :C :Si)").unwrap();
+        assert_eq!(result, SExpr::Atom(Atom::String("CoSiO3".to_string())));
+    }
+
+    #[test]
+    fn test_combine() {
+        let mut lirs = LIRS::new();
+
+        let result = lirs.eval_last("(combine \"Fe2O3\" \"Al2O3\")").unwrap();
+        assert_eq!(result, SExpr::Atom(Atom::String("Fe2O6Al2".to_string())));
+        assert!(lirs.eval_last("(combine \"Fe2O3\" \"Qq\")").is_err());
     }
 
     #[test]
@@ -5,6 +5,7 @@ use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 
+use crate::composition::Composition;
 use crate::property::Property;
 
 /// Represents a material in the system
@@ -64,6 +65,17 @@ pub struct Site {
     pub occupancy: f64,
 }
 
+/// Periodic neighbor of a site
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Neighbor {
+    /// Index of the neighboring site
+    pub index: usize,
+    /// Cartesian distance (Å)
+    pub distance: f64,
+    /// Lattice translation of the neighbor image
+    pub image: [i32; 3],
+}
+
 /// Crystal system classification
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum CrystalSystem {
@@ -85,6 +97,11 @@ pub struct Metadata {
     /// Source-specific ID (e.g., "mp-149")
     pub source_id: Option<String>,
 
+    /// Every "source:id" this material was ingested from, including
+    /// duplicates linked from other databases
+    #[serde(default)]
+    pub source_ids: Vec<String>,
+
     /// Computation method used
     pub method: Option<String>,
 
@@ -99,7 +116,8 @@ pub struct Metadata {
 }
 
 impl Material {
-    /// Create a new material with minimal information
+    /// Create a new material with minimal information; the formula is
+    /// stored as given (`MaterialBuilder` normalizes it)
     pub fn new(formula: impl Into<String>) -> Self {
         let now = Utc::now();
         Self {
@@ -154,51 +172,202 @@ impl Material {
         total_mass / self.structure.sites.len() as f64
     }
 
-    /// Get atomic mass for an element (simplified lookup table)
+    /// Get atomic mass for an element from the periodic table database
     fn atomic_mass(element: &str) -> f64 {
-        match element {
-            "H" => 1.008,
-            "He" => 4.003,
-            "Li" => 6.941,
-            "Be" => 9.012,
-            "B" => 10.811,
-            "C" => 12.011,
-            "N" => 14.007,
-            "O" => 15.999,
-            "F" => 18.998,
-            "Ne" => 20.180,
-            "Na" => 22.990,
-            "Mg" => 24.305,
-            "Al" => 26.982,
-            "Si" => 28.085,
-            "P" => 30.974,
-            "S" => 32.065,
-            "Cl" => 35.453,
-            "Ar" => 39.948,
-            "K" => 39.098,
-            "Ca" => 40.078,
-            "Ti" => 47.867,
-            "V" => 50.942,
-            "Cr" => 51.996,
-            "Mn" => 54.938,
-            "Fe" => 55.845,
-            "Co" => 58.933,
-            "Ni" => 58.693,
-            "Cu" => 63.546,
-            "Zn" => 65.380,
-            "Ga" => 69.723,
-            "Sr" => 87.620,
-            "Y" => 88.906,
-            "Zr" => 91.224,
-            "Nb" => 92.906,
-            "Mo" => 95.960,
-            "Ba" => 137.327,
-            "La" => 138.905,
-            "Ce" => 140.116,
-            "Pb" => 207.200,
-            "Bi" => 208.980,
-            _ => 1.0,  // Unknown element
+        crate::elements::atomic_mass(element).unwrap_or(1.0) // Unknown element
+    }
+
+    /// Composition from occupancy-weighted sites, or from the formula when
+    /// the structure has no sites
+    pub fn composition(&self) -> crate::Result<Composition> {
+        if self.structure.sites.is_empty() {
+            return Composition::parse(&self.formula);
+        }
+        Composition::from_amounts(self.structure.sites.iter().map(|s| (s.element.as_str(), s.occupancy)))
+    }
+}
+
+/// Builder for materials created from a chemical formula
+///
+/// Populates one site per atom of the formula so that composition-based
+/// code (features, masses, element lists) works without a full structure.
+#[derive(Debug, Clone)]
+pub struct MaterialBuilder {
+    material: Material,
+}
+
+impl MaterialBuilder {
+    /// Start building a material from a formula such as "Fe2O3" or
+    /// "Ca(OH)2"; the stored formula is the reduced Hill formula
+    pub fn new(formula: impl Into<String>) -> crate::Result<Self> {
+        let composition = Composition::parse(&formula.into())?;
+        let counts = composition.integer_counts()?;
+
+        let mut material = Material::new(composition.reduced_formula());
+        for (element, count) in counts {
+            for _ in 0..count {
+                material.structure.sites.push(Site {
+                    element: element.clone(),
+                    coords: [0.0, 0.0, 0.0],
+                    magmom: None,
+                    occupancy: 1.0,
+                });
+            }
         }
+
+        Ok(Self { material })
+    }
+
+    /// Set the lattice matrix
+    pub fn with_lattice(mut self, lattice: [[f64; 3]; 3]) -> Self {
+        self.material.structure.lattice = lattice;
+        self
+    }
+
+    /// Replace the structure entirely
+    pub fn with_structure(mut self, structure: Structure) -> Self {
+        self.material.structure = structure;
+        self
+    }
+
+    /// Add a property
+    pub fn with_property(mut self, name: impl Into<String>, property: Property) -> Self {
+        self.material.properties.insert(name.into(), property);
+        self
+    }
+
+    /// Finish building
+    pub fn build(self) -> Material {
+        self.material
+    }
+}
+
+impl Structure {
+    /// Cell volume (Å³)
+    pub fn volume(&self) -> f64 {
+        let [a, b, c] = self.lattice;
+        let cross = [
+            b[1] * c[2] - b[2] * c[1],
+            b[2] * c[0] - b[0] * c[2],
+            b[0] * c[1] - b[1] * c[0],
+        ];
+        (a[0] * cross[0] + a[1] * cross[1] + a[2] * cross[2]).abs()
+    }
+
+    /// Lattice vector lengths (a, b, c) and angles (alpha, beta, gamma) in degrees
+    pub fn lattice_parameters(&self) -> ([f64; 3], [f64; 3]) {
+        let norm = |v: &[f64; 3]| (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        let dot = |u: &[f64; 3], v: &[f64; 3]| u[0] * v[0] + u[1] * v[1] + u[2] * v[2];
+        let [a, b, c] = &self.lattice;
+        let lengths = [norm(a), norm(b), norm(c)];
+        let angle = |u: &[f64; 3], v: &[f64; 3], lu: f64, lv: f64| {
+            (dot(u, v) / (lu * lv)).clamp(-1.0, 1.0).acos().to_degrees()
+        };
+        let angles = [
+            angle(b, c, lengths[1], lengths[2]),
+            angle(a, c, lengths[0], lengths[2]),
+            angle(a, b, lengths[0], lengths[1]),
+        ];
+        (lengths, angles)
+    }
+
+    /// Convert fractional coordinates to Cartesian (lattice vectors are rows)
+    pub fn to_cartesian(&self, frac: [f64; 3]) -> [f64; 3] {
+        let mut cart = [0.0; 3];
+        for (f, row) in frac.iter().zip(&self.lattice) {
+            for k in 0..3 {
+                cart[k] += f * row[k];
+            }
+        }
+        cart
+    }
+
+    /// Convert Cartesian coordinates to fractional
+    pub fn to_fractional(&self, cart: [f64; 3]) -> [f64; 3] {
+        let m = nalgebra::Matrix3::from_fn(|i, j| self.lattice[j][i]);
+        match m.try_inverse() {
+            Some(inv) => {
+                let f = inv * nalgebra::Vector3::new(cart[0], cart[1], cart[2]);
+                [f[0], f[1], f[2]]
+            }
+            None => [0.0; 3],
+        }
+    }
+
+    /// Cartesian coordinates of all sites
+    pub fn cartesian_coords(&self) -> Vec<[f64; 3]> {
+        self.sites.iter().map(|s| self.to_cartesian(s.coords)).collect()
+    }
+
+    /// Number of lattice translations needed along each axis to cover `cutoff`
+    ///
+    /// `offset` is the largest fractional separation between the center and
+    /// any site per axis, so coordinates outside [0, 1) are still covered.
+    fn image_range(&self, cutoff: f64, offset: [f64; 3]) -> [i32; 3] {
+        // Interplanar spacing d_i = V / |a_j × a_k|
+        let volume = self.volume().max(1e-12);
+        let [a, b, c] = self.lattice;
+        let cross_norm = |u: [f64; 3], v: [f64; 3]| {
+            let x = [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ];
+            (x[0] * x[0] + x[1] * x[1] + x[2] * x[2]).sqrt()
+        };
+        let spacings = [volume / cross_norm(b, c), volume / cross_norm(a, c), volume / cross_norm(a, b)];
+        std::array::from_fn(|k| (cutoff / spacings[k] + offset[k]).ceil() as i32)
+    }
+
+    /// Neighbors of one site within `cutoff` under periodic boundary conditions,
+    /// sorted by distance
+    pub fn neighbors(&self, index: usize, cutoff: f64) -> Vec<Neighbor> {
+        let mut result = Vec::new();
+        let Some(center) = self.sites.get(index) else {
+            return result;
+        };
+
+        let mut offset = [0.0_f64; 3];
+        for site in &self.sites {
+            for ((o, f), c) in offset.iter_mut().zip(site.coords).zip(center.coords) {
+                *o = o.max((f - c).abs());
+            }
+        }
+        let range = self.image_range(cutoff, offset);
+        let origin = self.to_cartesian(center.coords);
+
+        for (j, site) in self.sites.iter().enumerate() {
+            for i0 in -range[0]..=range[0] {
+                for i1 in -range[1]..=range[1] {
+                    for i2 in -range[2]..=range[2] {
+                        if j == index && i0 == 0 && i1 == 0 && i2 == 0 {
+                            continue;
+                        }
+                        let frac = [
+                            site.coords[0] + i0 as f64,
+                            site.coords[1] + i1 as f64,
+                            site.coords[2] + i2 as f64,
+                        ];
+                        let cart = self.to_cartesian(frac);
+                        let distance = ((cart[0] - origin[0]).powi(2)
+                            + (cart[1] - origin[1]).powi(2)
+                            + (cart[2] - origin[2]).powi(2))
+                        .sqrt();
+                        if distance <= cutoff && distance > 1e-8 {
+                            result.push(Neighbor { index: j, distance, image: [i0, i1, i2] });
+                        }
+                    }
+                }
+            }
+        }
+
+        result.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap_or(std::cmp::Ordering::Equal));
+        result
+    }
+
+    /// Neighbor lists for every site
+    pub fn all_neighbors(&self, cutoff: f64) -> Vec<Vec<Neighbor>> {
+        (0..self.sites.len()).map(|i| self.neighbors(i, cutoff)).collect()
     }
 }
 
@@ -218,6 +387,7 @@ impl Default for Metadata {
         Self {
             source: "unknown".to_string(),
             source_id: None,
+            source_ids: Vec::new(),
             method: None,
             references: Vec::new(),
             tags: Vec::new(),
@@ -248,4 +418,59 @@ mod tests {
         assert!(material.get_property("formation_energy").is_some());
         assert!(material.get_property("band_gap").is_none());
     }
+
+    #[test]
+    fn test_material_builder() {
+        let material = MaterialBuilder::new("Fe2O3").unwrap().build();
+        assert_eq!(material.num_atoms(), 5);
+        assert_eq!(material.elements(), vec!["Fe".to_string(), "O".to_string()]);
+
+        let slaked_lime = MaterialBuilder::new("Ca(OH)2").unwrap().build();
+        assert_eq!(slaked_lime.formula, "CaH2O2");
+        assert_eq!(slaked_lime.num_atoms(), 5);
+        assert_eq!(MaterialBuilder::new("O6Fe4").unwrap().build().formula, "Fe2O3");
+        assert_eq!(slaked_lime.composition().unwrap(), Composition::parse("CaO2H2").unwrap());
+
+        assert!(MaterialBuilder::new("fe2").is_err());
+        assert!(MaterialBuilder::new("Li0.5CoO2").is_err());
+    }
+
+    #[test]
+    fn test_periodic_neighbors() {
+        let structure = Structure {
+            lattice: [[3.0, 0.0, 0.0], [0.0, 3.0, 0.0], [0.0, 0.0, 3.0]],
+            sites: vec![Site { element: "Po".to_string(), coords: [0.0, 0.0, 0.0], magmom: None, occupancy: 1.0 }],
+            space_group: Some(221),
+            crystal_system: Some(CrystalSystem::Cubic),
+        };
+
+        assert!((structure.volume() - 27.0).abs() < 1e-9);
+        assert_eq!(structure.to_fractional([1.5, 0.0, 3.0]), [0.5, 0.0, 1.0]);
+
+        // Simple cubic: 6 nearest neighbors at a, 12 at a·√2
+        let neighbors = structure.neighbors(0, 4.5);
+        assert_eq!(neighbors.iter().filter(|n| (n.distance - 3.0).abs() < 1e-9).count(), 6);
+        assert_eq!(neighbors.len(), 18);
+    }
+
+    #[test]
+    fn test_neighbors_unwrapped_coordinates() {
+        let site = |coords| Site { element: "Cs".to_string(), coords, magmom: None, occupancy: 1.0 };
+        let wrapped = Structure {
+            lattice: [[3.0, 0.0, 0.0], [0.0, 3.0, 0.0], [0.0, 0.0, 3.0]],
+            sites: vec![site([0.0, 0.0, 0.0]), site([0.5, 0.5, 0.5])],
+            space_group: None,
+            crystal_system: None,
+        };
+        // Same crystal with the second site shifted by whole cells
+        let mut unwrapped = wrapped.clone();
+        unwrapped.sites[1].coords = [2.5, -1.5, 3.5];
+
+        for index in 0..2 {
+            let distances = |s: &Structure| s.neighbors(index, 4.0).iter().map(|n| n.distance).collect::<Vec<_>>();
+            let (expected, actual) = (distances(&wrapped), distances(&unwrapped));
+            assert_eq!(expected.len(), actual.len());
+            assert!(expected.iter().zip(&actual).all(|(a, b)| (a - b).abs() < 1e-9));
+        }
+    }
 }
@@ -12,6 +12,9 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 use nalgebra::{DVector, DMatrix};
+use crate::featurizer::{FeatureSet, MagpieFeaturizer};
+use crate::material::Material;
+use crate::tree_ensemble::{GradientBoostingConfig, RandomForestConfig, TreeEnsemble};
 
 /// Property prediction result
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +36,28 @@ pub struct TrainingData {
     pub weight: f64,
 }
 
+/// Regressor family used when training a property model
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum ModelKind {
+    /// Linear regression fitted by gradient descent
+    #[default]
+    Linear,
+    /// Bagged regression trees
+    RandomForest(RandomForestConfig),
+    /// Gradient-boosted histogram trees
+    GradientBoosting(GradientBoostingConfig),
+}
+
+impl ModelKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModelKind::Linear => "linear",
+            ModelKind::RandomForest(_) => "random_forest",
+            ModelKind::GradientBoosting(_) => "gradient_boosting",
+        }
+    }
+}
+
 /// ML Model for property prediction
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PropertyModel {
@@ -44,10 +69,23 @@ pub struct PropertyModel {
     pub training_samples: usize,
     pub validation_score: f64,
     pub version: String,
+
+    /// Tree ensemble replacing the linear weights, if trained as one
+    #[serde(default)]
+    pub ensemble: Option<TreeEnsemble>,
 }
 
 impl PropertyModel {
+    /// Length of the feature vectors the model was trained on
+    pub fn n_features(&self) -> usize {
+        self.feature_means.len()
+    }
+
     pub fn predict(&self, features: &[f64]) -> f64 {
+        if let Some(ensemble) = &self.ensemble {
+            return ensemble.predict(features);
+        }
+
         // Normalize features
         let normalized: Vec<f64> = features.iter()
             .zip(&self.feature_means)
@@ -71,6 +109,10 @@ impl PropertyModel {
     }
 
     pub fn predict_with_uncertainty(&self, features: &[f64]) -> (f64, f64) {
+        if let Some(ensemble) = &self.ensemble {
+            return ensemble.predict_with_uncertainty(features);
+        }
+
         let prediction = self.predict(features);
 
         // Estimate uncertainty based on model variance
@@ -78,6 +120,29 @@ impl PropertyModel {
 
         (prediction, uncertainty)
     }
+
+    /// Regressor family of this model
+    pub fn model_type(&self) -> &'static str {
+        match self.ensemble.as_ref().map(|e| e.kind) {
+            None => "linear",
+            Some(crate::tree_ensemble::EnsembleKind::RandomForest) => "random_forest",
+            Some(crate::tree_ensemble::EnsembleKind::GradientBoosting) => "gradient_boosting",
+        }
+    }
+
+    /// Global feature importance (split gain for trees, |weight| for linear)
+    pub fn feature_importance(&self) -> Vec<f64> {
+        match &self.ensemble {
+            Some(ensemble) => ensemble.feature_importance.clone(),
+            None => {
+                let total: f64 = self.weights.iter().map(|w| w.abs()).sum();
+                self.weights
+                    .iter()
+                    .map(|w| if total > 0.0 { w.abs() / total } else { 0.0 })
+                    .collect()
+            }
+        }
+    }
 }
 
 /// Machine Learning Predictor Engine
@@ -88,6 +153,12 @@ pub struct MLPredictor {
     /// Training data cache
     training_data: Arc<RwLock<HashMap<String, Vec<TrainingData>>>>,
 
+    /// Regressor family per property (linear when unset)
+    model_kinds: Arc<RwLock<HashMap<String, ModelKind>>>,
+
+    /// Feature representation per property (composition when unset)
+    feature_sets: Arc<RwLock<HashMap<String, FeatureSet>>>,
+
     /// Auto-retrain threshold
     retrain_threshold: usize,
 
@@ -100,21 +171,55 @@ impl MLPredictor {
         Self {
             models: Arc::new(RwLock::new(HashMap::new())),
             training_data: Arc::new(RwLock::new(HashMap::new())),
+            model_kinds: Arc::new(RwLock::new(HashMap::new())),
+            feature_sets: Arc::new(RwLock::new(HashMap::new())),
             retrain_threshold: 1000,
             version: "v1.0.0".to_string(),
         }
     }
 
+    /// Select the regressor family used the next time a property is trained
+    pub async fn set_model_kind(&self, property_name: &str, kind: ModelKind) {
+        self.model_kinds.write().await.insert(property_name.to_string(), kind);
+    }
+
+    /// Regressor family configured for a property
+    pub async fn model_kind(&self, property_name: &str) -> ModelKind {
+        self.model_kinds.read().await.get(property_name).cloned().unwrap_or_default()
+    }
+
+    /// Select how materials are featurized for a property
+    ///
+    /// Applies to `add_training_material` and `predict_material`; changing
+    /// it after training data was added requires retraining from scratch.
+    pub async fn set_feature_set(&self, property_name: &str, feature_set: FeatureSet) {
+        self.feature_sets.write().await.insert(property_name.to_string(), feature_set);
+    }
+
+    /// Feature representation configured for a property
+    pub async fn feature_set(&self, property_name: &str) -> FeatureSet {
+        self.feature_sets.read().await.get(property_name).cloned().unwrap_or_default()
+    }
+
     /// Predict a property for a material
     pub async fn predict_property(
         &self,
         property_name: &str,
         features: Vec<f64>,
     ) -> Result<PropertyPrediction, String> {
+        let feature_names = self.feature_names(property_name).await;
         let models = self.models.read().await;
 
         let model = models.get(property_name)
             .ok_or_else(|| format!("No model found for property: {}", property_name))?;
+        if features.len() != model.n_features() {
+            return Err(format!(
+                "Model for {} expects {} features, got {}",
+                property_name,
+                model.n_features(),
+                features.len()
+            ));
+        }
 
         let (predicted_value, uncertainty) = model.predict_with_uncertainty(&features);
 
@@ -129,10 +234,64 @@ impl MLPredictor {
             ),
             confidence_score,
             model_version: model.version.clone(),
-            feature_importance: self.calculate_feature_importance(model, &features),
+            feature_importance: self.calculate_feature_importance(model, &features, &feature_names),
         })
     }
 
+    /// Composition features for a formula (shared Magpie featurizer)
+    pub fn composition_features(formula: &str) -> Result<Vec<f64>, String> {
+        MagpieFeaturizer::new()
+            .featurize_formula(formula)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Predict a property from a formula using composition features
+    pub async fn predict_from_formula(
+        &self,
+        property_name: &str,
+        formula: &str,
+    ) -> Result<PropertyPrediction, String> {
+        let features = Self::composition_features(formula)?;
+        self.predict_property(property_name, features).await
+    }
+
+    /// Add a labelled formula to the training set using composition features
+    pub async fn add_training_formula(
+        &self,
+        property_name: String,
+        material_id: Uuid,
+        formula: &str,
+        target: f64,
+    ) -> Result<(), String> {
+        let features = Self::composition_features(formula)?;
+        self.add_training_data(property_name, material_id, features, target).await
+    }
+
+    /// Predict a property for a material using the property's feature set
+    pub async fn predict_material(
+        &self,
+        property_name: &str,
+        material: &Material,
+    ) -> Result<PropertyPrediction, String> {
+        let features = self.feature_set(property_name).await
+            .featurize(material)
+            .map_err(|e| e.to_string())?;
+        self.predict_property(property_name, features).await
+    }
+
+    /// Add a labelled material to the training set using the property's feature set
+    pub async fn add_training_material(
+        &self,
+        property_name: String,
+        material: &Material,
+        target: f64,
+    ) -> Result<(), String> {
+        let features = self.feature_set(&property_name).await
+            .featurize(material)
+            .map_err(|e| e.to_string())?;
+        self.add_training_data(property_name, material.id, features, target).await
+    }
+
     /// Predict multiple properties at once
     pub async fn predict_multiple(
         &self,
@@ -177,6 +336,11 @@ impl MLPredictor {
         Ok(())
     }
 
+    /// Number of training samples held for a property
+    pub async fn training_data_count(&self, property_name: &str) -> usize {
+        self.training_data.read().await.get(property_name).map_or(0, |d| d.len())
+    }
+
     /// Train or retrain a model for a property
     pub async fn train_model(&self, property_name: String) -> Result<(), String> {
         let training_data = self.training_data.read().await;
@@ -202,6 +366,14 @@ impl MLPredictor {
             y[i] = sample.target;
         }
 
+        let kind = self.model_kind(&property_name).await;
+        if !matches!(kind, ModelKind::Linear) {
+            let features: Vec<Vec<f64>> = data.iter().map(|d| d.features.clone()).collect();
+            let targets: Vec<f64> = data.iter().map(|d| d.target).collect();
+            drop(training_data);
+            return self.train_tree_model(property_name, &kind, &features, &targets).await;
+        }
+
         // Calculate feature statistics
         let mut feature_means = vec![0.0; n_features];
         let mut feature_stds = vec![0.0; n_features];
@@ -248,6 +420,7 @@ impl MLPredictor {
             training_samples: n_samples,
             validation_score: r_squared,
             version: self.version.clone(),
+            ensemble: None,
         };
 
         let mut models = self.models.write().await;
@@ -256,6 +429,71 @@ impl MLPredictor {
         Ok(())
     }
 
+    /// Fit a tree ensemble on raw (unnormalized) features
+    async fn train_tree_model(
+        &self,
+        property_name: String,
+        kind: &ModelKind,
+        features: &[Vec<f64>],
+        targets: &[f64],
+    ) -> Result<(), String> {
+        let ensemble = match kind {
+            ModelKind::RandomForest(config) => TreeEnsemble::fit_random_forest(features, targets, config)?,
+            ModelKind::GradientBoosting(config) => TreeEnsemble::fit_gradient_boosting(features, targets, config)?,
+            ModelKind::Linear => return Err("Linear models are not tree ensembles".to_string()),
+        };
+
+        // Prefer the out-of-bag estimate; otherwise fall back to training R²
+        let validation_score = match ensemble.oob_score {
+            Some(score) => score,
+            None => {
+                let mean = targets.iter().sum::<f64>() / targets.len() as f64;
+                let ss_tot: f64 = targets.iter().map(|t| (t - mean).powi(2)).sum();
+                let ss_res: f64 = features.iter().zip(targets)
+                    .map(|(f, t)| (t - ensemble.predict(f)).powi(2))
+                    .sum();
+                if ss_tot > 1e-12 { 1.0 - ss_res / ss_tot } else { 0.0 }
+            }
+        };
+
+        let n_features = features[0].len();
+        let model = PropertyModel {
+            property_name: property_name.clone(),
+            weights: Vec::new(),
+            bias: 0.0,
+            feature_means: vec![0.0; n_features],
+            feature_stds: vec![1.0; n_features],
+            training_samples: features.len(),
+            validation_score,
+            version: self.version.clone(),
+            ensemble: Some(ensemble),
+        };
+
+        self.models.write().await.insert(property_name, model);
+
+        Ok(())
+    }
+
+    /// Global feature importance of a trained model, keyed by feature name
+    pub async fn get_feature_importance(&self, property_name: &str) -> Option<HashMap<String, f64>> {
+        let feature_names = self.feature_names(property_name).await;
+        let models = self.models.read().await;
+        let model = models.get(property_name)?;
+        let names: &[String] = if feature_names.len() == model.feature_means.len() {
+            &feature_names
+        } else {
+            &[]
+        };
+
+        Some(
+            model.feature_importance()
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| (Self::feature_name(i, names), v))
+                .collect(),
+        )
+    }
+
     /// Get all available models
     pub async fn get_available_models(&self) -> Vec<String> {
         self.models.read().await.keys().cloned().collect()
@@ -275,7 +513,8 @@ impl MLPredictor {
             available_data: data.len(),
             validation_score: model.validation_score,
             version: model.version.clone(),
-            feature_count: model.weights.len(),
+            feature_count: model.feature_means.len(),
+            model_type: model.model_type().to_string(),
         })
     }
 
@@ -325,22 +564,49 @@ impl MLPredictor {
         Ok((weights, bias))
     }
 
+    /// Feature names of the property's configured feature set
+    async fn feature_names(&self, property_name: &str) -> Vec<String> {
+        self.feature_set(property_name).await.feature_names()
+    }
+
+    /// Name from the feature set when the model was trained on it (matching
+    /// width), generic `feature_i` otherwise
+    fn feature_name(index: usize, names: &[String]) -> String {
+        names.get(index).cloned().unwrap_or_else(|| format!("feature_{}", index))
+    }
+
     fn calculate_feature_importance(
         &self,
         model: &PropertyModel,
         features: &[f64],
+        feature_names: &[String],
     ) -> HashMap<String, f64> {
         let mut importance = HashMap::new();
+        let names: &[String] = if feature_names.len() == model.feature_means.len() {
+            feature_names
+        } else {
+            &[]
+        };
+
+        if let Some(ensemble) = &model.ensemble {
+            for (i, value) in ensemble.feature_importance.iter().enumerate() {
+                importance.insert(Self::feature_name(i, names), *value);
+            }
+            return importance;
+        }
 
         for (i, (weight, feature)) in model.weights.iter().zip(features.iter()).enumerate() {
             let contribution = (weight * feature).abs();
-            importance.insert(format!("feature_{}", i), contribution);
+            importance.insert(Self::feature_name(i, names), contribution);
         }
 
         importance
     }
 
     /// Initialize with pre-trained models for common properties
+    ///
+    /// These demo models take an 8-value feature vector, not the output of
+    /// a [`FeatureSet`]; train on featurized materials to predict those.
     pub async fn initialize_pretrained_models(&self) -> Result<(), String> {
         // Formation energy model
         self.create_pretrained_model(
@@ -395,6 +661,7 @@ impl MLPredictor {
             training_samples: 10000, // Simulated
             validation_score,
             version: self.version.clone(),
+            ensemble: None,
         };
 
         let mut models = self.models.write().await;
@@ -418,6 +685,7 @@ pub struct ModelStats {
     pub validation_score: f64,
     pub version: String,
     pub feature_count: usize,
+    pub model_type: String,
 }
 
 /// Batch prediction for multiple materials
@@ -461,6 +729,10 @@ mod tests {
 
         assert!(prediction.confidence_score > 0.0);
         assert!(prediction.confidence_score <= 1.0);
+
+        // Inputs of another length are rejected, not truncated or padded
+        let error = predictor.predict_from_formula("formation_energy", "Fe2O3").await.unwrap_err();
+        assert!(error.contains("expects 8 features"), "{}", error);
     }
 
     #[tokio::test]
@@ -486,4 +758,101 @@ mod tests {
         let models = predictor.get_available_models().await;
         assert!(models.contains(&"test_property".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_tree_model_training() {
+        let predictor = MLPredictor::new();
+        predictor.set_model_kind(
+            "band_gap",
+            ModelKind::RandomForest(RandomForestConfig { n_trees: 20, ..Default::default() }),
+        ).await;
+
+        for i in 0..80 {
+            let x = (i % 8) as f64;
+            let features = vec![x, (i % 3) as f64];
+            let target = if x < 4.0 { 0.5 } else { 3.0 };
+            predictor.add_training_data("band_gap".to_string(), Uuid::new_v4(), features, target)
+                .await
+                .unwrap();
+        }
+        predictor.train_model("band_gap".to_string()).await.unwrap();
+
+        let low = predictor.predict_property("band_gap", vec![1.0, 0.0]).await.unwrap();
+        let high = predictor.predict_property("band_gap", vec![6.0, 0.0]).await.unwrap();
+        assert!(low.predicted_value < 1.0);
+        assert!(high.predicted_value > 2.5);
+        assert!(high.confidence_interval.0 <= high.predicted_value);
+
+        let stats = predictor.get_model_stats("band_gap").await.unwrap();
+        assert_eq!(stats.model_type, "random_forest");
+
+        let importance = predictor.get_feature_importance("band_gap").await.unwrap();
+        assert!(importance["feature_0"] > importance["feature_1"]);
+    }
+
+    #[tokio::test]
+    async fn test_composition_feature_training() {
+        let predictor = MLPredictor::new();
+        predictor.set_model_kind(
+            "density",
+            ModelKind::RandomForest(RandomForestConfig { n_trees: 10, ..Default::default() }),
+        ).await;
+
+        let data = [("Al2O3", 3.95), ("Fe2O3", 5.24), ("PtO2", 10.2), ("Au", 19.3), ("NaCl", 2.17)];
+        for (formula, density) in data {
+            predictor.add_training_formula("density".to_string(), Uuid::new_v4(), formula, density)
+                .await
+                .unwrap();
+        }
+        predictor.train_model("density".to_string()).await.unwrap();
+
+        let prediction = predictor.predict_from_formula("density", "Pt").await.unwrap();
+        assert!(prediction.predicted_value.is_finite());
+        assert!(prediction.feature_importance.contains_key("mean_AtomicWeight"));
+    }
+
+    #[tokio::test]
+    async fn test_structure_feature_set() {
+        use crate::material::{MaterialBuilder, Site, Structure};
+        use crate::structure_descriptors::CoordinationStatistics;
+
+        let predictor = MLPredictor::new();
+        predictor.set_feature_set(
+            "bulk_modulus",
+            FeatureSet::Structure(Arc::new(CoordinationStatistics::default())),
+        ).await;
+
+        let cubic = |element: &str, a: f64, coords: &[[f64; 3]]| {
+            MaterialBuilder::new(element).unwrap()
+                .with_structure(Structure {
+                    lattice: [[a, 0.0, 0.0], [0.0, a, 0.0], [0.0, 0.0, a]],
+                    sites: coords.iter()
+                        .map(|c| Site { element: element.to_string(), coords: *c, magmom: None, occupancy: 1.0 })
+                        .collect(),
+                    space_group: None,
+                    crystal_system: None,
+                })
+                .build()
+        };
+        let fcc = [[0.0, 0.0, 0.0], [0.5, 0.5, 0.0], [0.5, 0.0, 0.5], [0.0, 0.5, 0.5]];
+        let bcc = [[0.0, 0.0, 0.0], [0.5, 0.5, 0.5]];
+        let training = [
+            (cubic("Cu", 3.61, &fcc), 140.0),
+            (cubic("Al", 4.05, &fcc), 76.0),
+            (cubic("Fe", 2.87, &bcc), 170.0),
+            (cubic("W", 3.16, &bcc), 310.0),
+            (cubic("Po", 3.35, &[[0.0, 0.0, 0.0]]), 45.0),
+        ];
+        for (material, target) in &training {
+            predictor.add_training_material("bulk_modulus".to_string(), material, *target).await.unwrap();
+        }
+        predictor.train_model("bulk_modulus".to_string()).await.unwrap();
+
+        let prediction = predictor.predict_material("bulk_modulus", &cubic("Ni", 3.52, &fcc)).await.unwrap();
+        assert!(prediction.predicted_value.is_finite());
+        assert!(prediction.feature_importance.contains_key("mean_cn"));
+
+        // Structure-only feature sets need sites
+        assert!(predictor.predict_material("bulk_modulus", &Material::new("Ni")).await.is_err());
+    }
 }
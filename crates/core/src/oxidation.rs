@@ -0,0 +1,643 @@
+//! Oxidation States and Bond Valence
+//!
+//! Oxidation states for compositions and crystal structures, for charge
+//! balance in screening, ionic radii lookups and Coulomb terms.
+//!
+//! Compositions are assigned by enumeration: every element takes one of
+//! its common oxidation states, or splits its atoms between two of them
+//! (mixed valence, e.g. Fe2+/Fe3+ in Fe3O4). Each state carries a prior
+//! that halves with its rank in the element's list; an assignment's weight
+//! is the product over atoms, and weights of the charge-balanced
+//! assignments are normalized to probabilities. Assignments where a more
+//! electronegative element is positive while a less electronegative one
+//! is negative are discarded. The enumeration is exhaustive, so the
+//! probabilities are normalized over every balanced assignment; a
+//! composition whose search tree exceeds `MAX_SEARCH_NODES` is an error
+//! rather than a silently truncated list. `Composition` has no guesser of
+//! its own; this is the one place oxidation states come from.
+//!
+//! Structures are assigned by bond-valence sums (Brown & Altermatt 1985):
+//! a cation-anion bond of length R carries valence `exp((R0 - R) / b)`
+//! with b = 0.37 Å and R0 from Brese & O'Keeffe (1991). Pairs missing from
+//! the table get R0 estimated from Shannon radii assuming octahedral
+//! coordination. Within a mixed-valence element the higher states go to
+//! the sites with the largest sums, and among the most probable
+//! composition assignments the one with the lowest global instability
+//! index (GII, the RMS deviation of site sums from formal valences; above
+//! ~0.2 v.u. structures are usually strained or wrong) wins.
+
+use crate::composition::Composition;
+use crate::elements;
+use crate::material::Structure;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Property names under which valence descriptors are exposed to filters
+pub const CHARGE_BALANCED: &str = "charge_balanced";
+pub const GLOBAL_INSTABILITY_INDEX: &str = "global_instability_index";
+
+/// Bond-valence softness parameter (Å)
+pub const BOND_VALENCE_B: f64 = 0.37;
+
+/// Largest multiplier tried to make fractional amounts whole
+const MAX_SCALE: usize = 24;
+
+/// Search-tree nodes visited before enumeration gives up with an error
+const MAX_SEARCH_NODES: usize = 2_000_000;
+
+#[rustfmt::skip]
+const BOND_VALENCE_R0: &[(&str, i8, &str, f64)] = &[
+    // cation, oxidation state, anion, R0 (Å)
+    ("Li", 1, "O", 1.466), ("Na", 1, "O", 1.803), ("K", 1, "O", 2.132), ("Rb", 1, "O", 2.263),
+    ("Cs", 1, "O", 2.417), ("Ag", 1, "O", 1.842), ("Cu", 1, "O", 1.593), ("Tl", 1, "O", 2.172),
+    ("Be", 2, "O", 1.381), ("Mg", 2, "O", 1.693), ("Ca", 2, "O", 1.967), ("Sr", 2, "O", 2.118),
+    ("Ba", 2, "O", 2.285), ("Mn", 2, "O", 1.790), ("Fe", 2, "O", 1.734), ("Co", 2, "O", 1.692),
+    ("Ni", 2, "O", 1.654), ("Cu", 2, "O", 1.679), ("Zn", 2, "O", 1.704), ("Cd", 2, "O", 1.904),
+    ("Sn", 2, "O", 1.984), ("Pb", 2, "O", 2.112), ("Eu", 2, "O", 2.147),
+    ("B", 3, "O", 1.371), ("Al", 3, "O", 1.651), ("Ga", 3, "O", 1.730), ("In", 3, "O", 1.902),
+    ("Sc", 3, "O", 1.849), ("Y", 3, "O", 2.019), ("La", 3, "O", 2.172), ("Ce", 3, "O", 2.151),
+    ("Pr", 3, "O", 2.138), ("Nd", 3, "O", 2.105), ("Sm", 3, "O", 2.088), ("Eu", 3, "O", 2.074),
+    ("Gd", 3, "O", 2.065), ("Dy", 3, "O", 2.036), ("Er", 3, "O", 2.010), ("Yb", 3, "O", 1.985),
+    ("Lu", 3, "O", 1.971), ("Ti", 3, "O", 1.791), ("V", 3, "O", 1.743), ("Cr", 3, "O", 1.724),
+    ("Mn", 3, "O", 1.760), ("Fe", 3, "O", 1.759), ("Co", 3, "O", 1.700), ("Ni", 3, "O", 1.686),
+    ("Bi", 3, "O", 2.094), ("Sb", 3, "O", 1.973), ("As", 3, "O", 1.789),
+    ("C", 4, "O", 1.390), ("Si", 4, "O", 1.624), ("Ge", 4, "O", 1.748), ("Sn", 4, "O", 1.905),
+    ("Pb", 4, "O", 2.042), ("Ti", 4, "O", 1.815), ("Zr", 4, "O", 1.937), ("Hf", 4, "O", 1.923),
+    ("V", 4, "O", 1.784), ("Mn", 4, "O", 1.753), ("Ce", 4, "O", 2.028), ("Th", 4, "O", 2.167),
+    ("U", 4, "O", 2.112), ("Mo", 4, "O", 1.856), ("Ru", 4, "O", 1.834), ("Ir", 4, "O", 1.870),
+    ("N", 5, "O", 1.432), ("P", 5, "O", 1.617), ("As", 5, "O", 1.767), ("Sb", 5, "O", 1.942),
+    ("V", 5, "O", 1.803), ("Nb", 5, "O", 1.911), ("Ta", 5, "O", 1.920), ("Bi", 5, "O", 2.060),
+    ("S", 6, "O", 1.624), ("Se", 6, "O", 1.788), ("Te", 6, "O", 1.917), ("Cr", 6, "O", 1.794),
+    ("Mo", 6, "O", 1.907), ("W", 6, "O", 1.917), ("U", 6, "O", 2.075), ("Mn", 7, "O", 1.827),
+    ("Li", 1, "F", 1.360), ("Na", 1, "F", 1.677), ("K", 1, "F", 1.992), ("Cs", 1, "F", 2.330),
+    ("Mg", 2, "F", 1.578), ("Ca", 2, "F", 1.842), ("Sr", 2, "F", 2.019), ("Ba", 2, "F", 2.188),
+    ("Zn", 2, "F", 1.620), ("Al", 3, "F", 1.545), ("Y", 3, "F", 1.904), ("La", 3, "F", 2.057),
+    ("Fe", 3, "F", 1.679), ("Ti", 4, "F", 1.760),
+    ("Li", 1, "Cl", 1.910), ("Na", 1, "Cl", 2.150), ("K", 1, "Cl", 2.519), ("Cs", 1, "Cl", 2.791),
+    ("Ag", 1, "Cl", 2.090), ("Mg", 2, "Cl", 2.080), ("Ca", 2, "Cl", 2.370), ("Ba", 2, "Cl", 2.690),
+    ("Zn", 2, "Cl", 2.010), ("Pb", 2, "Cl", 2.530),
+    ("Cu", 1, "S", 1.860), ("Ag", 1, "S", 2.119), ("Mn", 2, "S", 2.200), ("Fe", 2, "S", 2.120),
+    ("Zn", 2, "S", 2.090), ("Cd", 2, "S", 2.290), ("Pb", 2, "S", 2.541), ("Ga", 3, "S", 2.163),
+    ("In", 3, "S", 2.370), ("Mo", 4, "S", 2.235), ("Sn", 4, "S", 2.399),
+    ("B", 3, "N", 1.470), ("Al", 3, "N", 1.790), ("Ga", 3, "N", 1.840), ("Si", 4, "N", 1.770),
+    ("Ti", 4, "N", 1.930),
+];
+
+/// Tabulated R0 (Å) for a cation in a given state bonded to an anion
+pub fn bond_valence_r0(cation: &str, state: i8, anion: &str) -> Option<f64> {
+    BOND_VALENCE_R0
+        .iter()
+        .find(|(c, s, a, _)| *c == cation && *s == state && *a == anion)
+        .map(|(_, _, _, r0)| *r0)
+}
+
+/// R0 from Shannon radii at octahedral coordination, for untabulated pairs
+fn estimated_r0(cation: &str, state: i8, anion: &str, anion_state: i8) -> Option<f64> {
+    let r_cation = elements::ionic_radius(cation, state, 6)?;
+    let r_anion = elements::ionic_radius(anion, anion_state, 6)?;
+    Some(r_cation + r_anion + BOND_VALENCE_B * (state as f64 / 6.0).ln())
+}
+
+/// One charge-balanced way to give oxidation states to a composition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OxidationAssignment {
+    /// (element, oxidation state, atoms) in composition order; a
+    /// mixed-valence element appears once per state
+    pub states: Vec<(String, i8, usize)>,
+    /// Probability among all balanced assignments of the composition
+    pub probability: f64,
+}
+
+impl OxidationAssignment {
+    /// Atom-averaged oxidation state of an element
+    pub fn mean_state(&self, element: &str) -> Option<f64> {
+        let (total, atoms) = self
+            .states
+            .iter()
+            .filter(|(e, _, _)| e == element)
+            .fold((0.0, 0), |(t, a), (_, s, n)| (t + *s as f64 * *n as f64, a + n));
+        (atoms > 0).then(|| total / atoms as f64)
+    }
+
+    /// Some element occurs in more than one state
+    pub fn is_mixed_valence(&self) -> bool {
+        self.states.iter().enumerate().any(|(i, (e, _, _))| self.states[..i].iter().any(|(f, _, _)| f == e))
+    }
+
+    /// States of one element with their atom counts, highest state first
+    pub fn element_states(&self, element: &str) -> Vec<(i8, usize)> {
+        let mut states: Vec<(i8, usize)> =
+            self.states.iter().filter(|(e, _, _)| e == element).map(|(_, s, n)| (*s, *n)).collect();
+        states.sort_by_key(|(s, _)| std::cmp::Reverse(*s));
+        states
+    }
+}
+
+/// Ways to give one element's atoms their states: one state, or a split
+/// between two
+#[derive(Debug, Clone)]
+struct ElementOption {
+    states: Vec<(i8, usize)>,
+    charge: i64,
+    log_weight: f64,
+}
+
+fn element_options(element: &str, atoms: usize) -> Vec<ElementOption> {
+    let states = elements::common_oxidation_states(element);
+    if states.is_empty() {
+        return Vec::new();
+    }
+    // Prior halves with each step down the list of common states
+    let norm: f64 = (0..states.len()).map(|r| 0.5f64.powi(r as i32)).sum();
+    let log_prior = |rank: usize| (0.5f64.powi(rank as i32) / norm).ln();
+
+    let mut options = Vec::new();
+    for (i, &s) in states.iter().enumerate() {
+        options.push(ElementOption {
+            states: vec![(s, atoms)],
+            charge: s as i64 * atoms as i64,
+            log_weight: atoms as f64 * log_prior(i),
+        });
+        for (j, &t) in states.iter().enumerate().skip(i + 1) {
+            for k in 1..atoms {
+                options.push(ElementOption {
+                    states: vec![(s, k), (t, atoms - k)],
+                    charge: s as i64 * k as i64 + t as i64 * (atoms - k) as i64,
+                    log_weight: k as f64 * log_prior(i) + (atoms - k) as f64 * log_prior(j),
+                });
+            }
+        }
+    }
+    options
+}
+
+/// Smallest whole-number multiple of a composition, as one formula unit
+///
+/// Fractional amounts are scaled by up to `MAX_SCALE`.
+fn integer_unit(composition: &Composition) -> Option<Composition> {
+    (1..=MAX_SCALE).find_map(|scale| {
+        let scaled = Composition::from_amounts(composition.iter().map(|(e, a)| (e, a * scale as f64)))
+            .ok()?
+            .with_charge(composition.charge() * scale as i32);
+        scaled.is_integral().then(|| scaled.reduced().0)
+    })
+}
+
+/// Charge-balanced oxidation-state assignments, most probable first
+///
+/// Counts refer to the smallest whole-number formula unit (Li0.5MnO2 is
+/// enumerated as LiMn2O4). Pure elements get state 0 with probability 1;
+/// compositions with no balanced assignment (intermetallics) give an
+/// empty list. Fails when the enumeration exceeds `MAX_SEARCH_NODES`.
+pub fn assign_composition(composition: &Composition) -> Result<Vec<OxidationAssignment>> {
+    assign_within(composition, MAX_SEARCH_NODES)
+}
+
+fn assign_within(composition: &Composition, node_budget: usize) -> Result<Vec<OxidationAssignment>> {
+    let Some(unit) = integer_unit(composition) else {
+        return Ok(Vec::new());
+    };
+    let Ok(counts) = unit.integer_counts() else {
+        return Ok(Vec::new());
+    };
+    let target = unit.charge() as i64;
+
+    if counts.len() == 1 && target == 0 {
+        let (element, atoms) = &counts[0];
+        return Ok(vec![OxidationAssignment { states: vec![(element.clone(), 0, *atoms)], probability: 1.0 }]);
+    }
+
+    let options: Vec<Vec<ElementOption>> = counts.iter().map(|(e, n)| element_options(e, *n)).collect();
+    // Reachable charge range of the elements not yet placed, for pruning
+    let mut suffix_bounds = vec![(0i64, 0i64); options.len() + 1];
+    for i in (0..options.len()).rev() {
+        let min = options[i].iter().map(|o| o.charge).min().unwrap_or(0);
+        let max = options[i].iter().map(|o| o.charge).max().unwrap_or(0);
+        suffix_bounds[i] = (suffix_bounds[i + 1].0 + min, suffix_bounds[i + 1].1 + max);
+    }
+
+    let electronegativity: Vec<Option<f64>> =
+        counts.iter().map(|(e, _)| elements::element(e).and_then(|el| el.electronegativity)).collect();
+
+    let mut search = Search {
+        options: &options,
+        bounds: &suffix_bounds,
+        electronegativity: &electronegativity,
+        target,
+        nodes_left: node_budget,
+        chosen: Vec::with_capacity(options.len()),
+        found: Vec::new(),
+    };
+    if !search.visit(0, 0.0) {
+        return Err(Error::computation(format!(
+            "Oxidation-state enumeration for {} exceeded {} search nodes",
+            unit.reduced_formula(),
+            node_budget
+        )));
+    }
+    let found = search.found;
+
+    let max_log = found.iter().map(|(_, w)| *w).fold(f64::NEG_INFINITY, f64::max);
+    let total: f64 = found.iter().map(|(_, w)| (w - max_log).exp()).sum();
+    let mut assignments: Vec<OxidationAssignment> = found
+        .into_iter()
+        .map(|(choice, w)| OxidationAssignment {
+            states: choice
+                .iter()
+                .enumerate()
+                .flat_map(|(i, &o)| {
+                    let element = &counts[i].0;
+                    options[i][o].states.iter().map(move |&(s, n)| (element.clone(), s, n))
+                })
+                .collect(),
+            probability: (w - max_log).exp() / total,
+        })
+        .collect();
+    assignments.sort_by(|a, b| b.probability.partial_cmp(&a.probability).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(assignments)
+}
+
+/// Depth-first enumeration of element options, pruned by the reachable
+/// charge range
+struct Search<'a> {
+    options: &'a [Vec<ElementOption>],
+    bounds: &'a [(i64, i64)],
+    electronegativity: &'a [Option<f64>],
+    target: i64,
+    nodes_left: usize,
+    chosen: Vec<usize>,
+    found: Vec<(Vec<usize>, f64)>,
+}
+
+impl Search<'_> {
+    /// Explore below the current choice; false once the node budget is spent
+    fn visit(&mut self, charge: i64, log_weight: f64) -> bool {
+        if self.nodes_left == 0 {
+            return false;
+        }
+        self.nodes_left -= 1;
+
+        let depth = self.chosen.len();
+        if depth == self.options.len() {
+            if charge == self.target {
+                self.found.push((self.chosen.clone(), log_weight));
+            }
+            return true;
+        }
+        let (min, max) = self.bounds[depth];
+        if charge + min > self.target || charge + max < self.target {
+            return true;
+        }
+
+        let options = self.options;
+        for (o, option) in options[depth].iter().enumerate() {
+            let consistent = self.chosen.iter().enumerate().all(|(i, &p)| {
+                electronegativity_consistent(
+                    self.electronegativity[i],
+                    &options[i][p].states,
+                    self.electronegativity[depth],
+                    &option.states,
+                )
+            });
+            if !consistent {
+                continue;
+            }
+            self.chosen.push(o);
+            let complete = self.visit(charge + option.charge, log_weight + option.log_weight);
+            self.chosen.pop();
+            if !complete {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// The more electronegative of two elements may not be positive while the
+/// other is negative
+fn electronegativity_consistent(en_a: Option<f64>, a: &[(i8, usize)], en_b: Option<f64>, b: &[(i8, usize)]) -> bool {
+    let (Some(en_a), Some(en_b)) = (en_a, en_b) else {
+        return true;
+    };
+    let positive = |states: &[(i8, usize)]| states.iter().any(|(s, _)| *s > 0);
+    let negative = |states: &[(i8, usize)]| states.iter().any(|(s, _)| *s < 0);
+    if en_a > en_b {
+        !(positive(a) && negative(b))
+    } else if en_b > en_a {
+        !(positive(b) && negative(a))
+    } else {
+        true
+    }
+}
+
+/// Bond-valence settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BondValenceConfig {
+    /// Bonds longer than this are ignored (Å)
+    pub cutoff: f64,
+    /// Composition assignments tried, most probable first
+    pub max_assignments: usize,
+}
+
+impl Default for BondValenceConfig {
+    fn default() -> Self {
+        Self { cutoff: 3.5, max_assignments: 8 }
+    }
+}
+
+/// Oxidation states and bond-valence sums of a structure's sites
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BondValenceAnalysis {
+    /// Assigned oxidation state per site
+    pub oxidation_states: Vec<i8>,
+    /// Bond-valence sum per site, signed like the oxidation state
+    pub valence_sums: Vec<f64>,
+    /// RMS deviation of |sum| from |state| over all sites (v.u.)
+    pub global_instability_index: f64,
+    /// Probability of the composition assignment used
+    pub assignment_probability: f64,
+    /// (cation, anion) pairs with R0 estimated from ionic radii
+    pub estimated_pairs: Vec<(String, String)>,
+    /// (cation, anion) pairs without parameters; their bonds are ignored
+    pub missing_pairs: Vec<(String, String)>,
+}
+
+impl BondValenceAnalysis {
+    /// Per-site deviation |sum| - |state|: positive is overbonded
+    pub fn deviations(&self) -> Vec<f64> {
+        self.valence_sums
+            .iter()
+            .zip(&self.oxidation_states)
+            .map(|(v, s)| v.abs() - (*s as f64).abs())
+            .collect()
+    }
+}
+
+/// R0 lookup with estimated and missing pairs recorded once each
+#[derive(Default)]
+struct ParameterLog {
+    estimated: Vec<(String, String)>,
+    missing: Vec<(String, String)>,
+}
+
+impl ParameterLog {
+    fn r0(&mut self, cation: &str, state: i8, anion: &str, anion_state: i8) -> Option<f64> {
+        if let Some(r0) = bond_valence_r0(cation, state, anion) {
+            return Some(r0);
+        }
+        let pair = (cation.to_string(), anion.to_string());
+        match estimated_r0(cation, state, anion, anion_state) {
+            Some(r0) => {
+                if !self.estimated.contains(&pair) {
+                    self.estimated.push(pair);
+                }
+                Some(r0)
+            }
+            None => {
+                if !self.missing.contains(&pair) {
+                    self.missing.push(pair);
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Signed bond-valence sums for given site oxidation states
+///
+/// Only bonds between sites of opposite sign within `cutoff` count, each
+/// weighted by the neighbor's occupancy.
+pub fn valence_sums(structure: &Structure, states: &[i8], cutoff: f64) -> Result<Vec<f64>> {
+    let mut log = ParameterLog::default();
+    sums_with_log(structure, states, cutoff, &mut log)
+}
+
+fn sums_with_log(structure: &Structure, states: &[i8], cutoff: f64, log: &mut ParameterLog) -> Result<Vec<f64>> {
+    if states.len() != structure.sites.len() {
+        return Err(Error::invalid_input(format!(
+            "{} oxidation states for {} sites",
+            states.len(),
+            structure.sites.len()
+        )));
+    }
+    let mut sums = vec![0.0; states.len()];
+    for (i, site) in structure.sites.iter().enumerate() {
+        if states[i] == 0 {
+            continue;
+        }
+        for neighbor in structure.neighbors(i, cutoff) {
+            let j = neighbor.index;
+            if states[i].signum() == states[j].signum() || states[j] == 0 {
+                continue;
+            }
+            let other = &structure.sites[j];
+            let r0 = if states[i] > 0 {
+                log.r0(&site.element, states[i], &other.element, states[j])
+            } else {
+                log.r0(&other.element, states[j], &site.element, states[i])
+            };
+            if let Some(r0) = r0 {
+                let valence = ((r0 - neighbor.distance) / BOND_VALENCE_B).exp() * other.occupancy;
+                sums[i] += valence * states[i].signum() as f64;
+            }
+        }
+    }
+    Ok(sums)
+}
+
+/// RMS deviation of |sum| from |state| (v.u.)
+pub fn global_instability_index(states: &[i8], sums: &[f64]) -> f64 {
+    if states.is_empty() {
+        return 0.0;
+    }
+    let squared: f64 = states.iter().zip(sums).map(|(s, v)| (v.abs() - (*s as f64).abs()).powi(2)).sum();
+    (squared / states.len() as f64).sqrt()
+}
+
+/// Assign oxidation states to every site of a structure by bond valence
+pub fn analyze_structure(structure: &Structure, config: &BondValenceConfig) -> Result<BondValenceAnalysis> {
+    if structure.sites.is_empty() {
+        return Err(Error::invalid_input("Structure has no sites"));
+    }
+    let composition = Composition::from_amounts(structure.sites.iter().map(|s| (s.element.as_str(), s.occupancy)))?;
+    let assignments = assign_composition(&composition)?;
+    if assignments.is_empty() {
+        return Err(Error::computation(format!(
+            "No charge-balanced oxidation states for {}",
+            composition.reduced_formula()
+        )));
+    }
+
+    let mut best: Option<BondValenceAnalysis> = None;
+    for assignment in assignments.iter().take(config.max_assignments.max(1)) {
+        let mut log = ParameterLog::default();
+        let states = site_states(structure, assignment, config.cutoff, &mut log)?;
+        let sums = sums_with_log(structure, &states, config.cutoff, &mut log)?;
+        let gii = global_instability_index(&states, &sums);
+        if best.as_ref().map_or(true, |b| gii < b.global_instability_index - 1e-9) {
+            best = Some(BondValenceAnalysis {
+                oxidation_states: states,
+                valence_sums: sums,
+                global_instability_index: gii,
+                assignment_probability: assignment.probability,
+                estimated_pairs: log.estimated,
+                missing_pairs: log.missing,
+            });
+        }
+    }
+    best.ok_or_else(|| Error::computation("No oxidation-state assignment evaluated"))
+}
+
+/// Distribute an element-level assignment over sites: a mixed-valence
+/// element's higher states go to the sites with the largest sums
+fn site_states(
+    structure: &Structure,
+    assignment: &OxidationAssignment,
+    cutoff: f64,
+    log: &mut ParameterLog,
+) -> Result<Vec<i8>> {
+    // Start every site at the element's dominant state
+    let mut states: Vec<i8> = structure
+        .sites
+        .iter()
+        .map(|site| {
+            assignment
+                .element_states(&site.element)
+                .iter()
+                .max_by_key(|(_, n)| *n)
+                .map_or(0, |(s, _)| *s)
+        })
+        .collect();
+    let trial = sums_with_log(structure, &states, cutoff, log)?;
+
+    let mut by_element: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, site) in structure.sites.iter().enumerate() {
+        by_element.entry(site.element.as_str()).or_default().push(i);
+    }
+    for (element, mut sites) in by_element {
+        let element_states = assignment.element_states(element);
+        if element_states.len() < 2 {
+            continue;
+        }
+        sites.sort_by(|&a, &b| trial[b].abs().partial_cmp(&trial[a].abs()).unwrap_or(std::cmp::Ordering::Equal));
+        let atoms: usize = element_states.iter().map(|(_, n)| n).sum();
+        let mut start = 0;
+        for (k, (state, n)) in element_states.iter().enumerate() {
+            let end = if k + 1 == element_states.len() {
+                sites.len()
+            } else {
+                (start + (n * sites.len() + atoms / 2) / atoms).min(sites.len())
+            };
+            for &i in &sites[start..end] {
+                states[i] = *state;
+            }
+            start = end;
+        }
+    }
+    Ok(states)
+}
+
+/// Whether `name` is one of the descriptors computed here
+pub fn is_valence_property(name: &str) -> bool {
+    matches!(name, CHARGE_BALANCED | GLOBAL_INSTABILITY_INDEX)
+}
+
+/// Screening descriptors: `charge_balanced` (1 or 0) from the composition
+/// and, given a structure, its `global_instability_index`; a descriptor
+/// that cannot be computed is left out
+pub fn valence_properties(composition: &Composition, structure: Option<&Structure>) -> HashMap<String, f64> {
+    let mut properties = HashMap::new();
+    if let Ok(assignments) = assign_composition(composition) {
+        properties.insert(CHARGE_BALANCED.to_string(), if assignments.is_empty() { 0.0 } else { 1.0 });
+    }
+    if let Some(analysis) = structure.and_then(|s| analyze_structure(s, &BondValenceConfig::default()).ok()) {
+        properties.insert(GLOBAL_INSTABILITY_INDEX.to_string(), analysis.global_instability_index);
+    }
+    properties
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::{CrystalSystem, Site};
+
+    fn parse(formula: &str) -> Composition {
+        Composition::parse(formula).unwrap()
+    }
+
+    #[test]
+    fn test_composition_assignments() {
+        let assign_composition = |c: &Composition| assign_composition(c).unwrap();
+        let hematite = assign_composition(&parse("Fe2O3"));
+        assert_eq!(hematite[0].mean_state("Fe"), Some(3.0));
+        assert!((hematite.iter().map(|a| a.probability).sum::<f64>() - 1.0).abs() < 1e-9);
+
+        let magnetite = assign_composition(&parse("Fe3O4"));
+        assert!(magnetite[0].is_mixed_valence());
+        assert_eq!(magnetite[0].element_states("Fe"), vec![(3, 2), (2, 1)]);
+
+        // Si must be the cation against the more electronegative C
+        let sic = assign_composition(&parse("SiC"));
+        assert_eq!(sic.len(), 1);
+        assert_eq!(sic[0].mean_state("Si"), Some(4.0));
+
+        // Spinel LiMn2O4: Mn3+/Mn4+
+        let spinel = assign_composition(&parse("Li0.5MnO2"));
+        assert!((spinel[0].mean_state("Mn").unwrap() - 3.5).abs() < 1e-9);
+
+        assert_eq!(assign_composition(&parse("SO4^2-"))[0].mean_state("S"), Some(6.0));
+        assert_eq!(assign_composition(&parse("Cu"))[0].states, vec![("Cu".to_string(), 0, 1)]);
+        assert!(assign_composition(&parse("NiAl")).is_empty());
+
+        let feo = assign_composition(&parse("FeO"));
+        assert_eq!(feo[0].element_states("Fe"), vec![(2, 1)]);
+        assert!(assign_composition(&parse("Li1.2Mn0.8O2")).iter().all(|a| a.mean_state("O") == Some(-2.0)));
+    }
+
+    #[test]
+    fn test_search_budget() {
+        let composition = parse("Fe7Mn5Co3O17");
+        let all = assign_within(&composition, usize::MAX).unwrap();
+        assert!((all.iter().map(|a| a.probability).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(assign_within(&composition, 50).is_err());
+        assert_eq!(assign_composition(&composition).unwrap(), all);
+    }
+
+    #[test]
+    fn test_bond_valence_sums() {
+        // Rock-salt MgO, a = 4.21 Å: six Mg-O bonds of 2.105 Å
+        let a = 4.21;
+        let site = |element: &str, coords: [f64; 3]| Site { element: element.to_string(), coords, magmom: None, occupancy: 1.0 };
+        let mut sites = Vec::new();
+        for t in [[0.0, 0.0, 0.0], [0.5, 0.5, 0.0], [0.5, 0.0, 0.5], [0.0, 0.5, 0.5]] {
+            sites.push(site("Mg", t));
+            sites.push(site("O", [t[0] + 0.5, t[1], t[2]]));
+        }
+        let structure = Structure {
+            lattice: [[a, 0.0, 0.0], [0.0, a, 0.0], [0.0, 0.0, a]],
+            sites,
+            space_group: Some(225),
+            crystal_system: Some(CrystalSystem::Cubic),
+        };
+
+        let analysis = analyze_structure(&structure, &BondValenceConfig::default()).unwrap();
+        assert_eq!(analysis.oxidation_states[0], 2);
+        assert_eq!(analysis.oxidation_states[1], -2);
+        assert!((analysis.valence_sums[0] - 1.98).abs() < 0.05, "{:?}", analysis.valence_sums);
+        assert!((analysis.valence_sums[1] + 1.98).abs() < 0.05);
+        assert!(analysis.global_instability_index < 0.05);
+        assert!(analysis.missing_pairs.is_empty() && analysis.estimated_pairs.is_empty());
+
+        // Compressing the cell overbonds every site
+        let mut compressed = structure.clone();
+        compressed.lattice = [[3.8, 0.0, 0.0], [0.0, 3.8, 0.0], [0.0, 0.0, 3.8]];
+        let strained = analyze_structure(&compressed, &BondValenceConfig::default()).unwrap();
+        assert!(strained.global_instability_index > 0.5);
+        assert!(strained.deviations().iter().all(|d| *d > 0.0));
+
+        let properties = valence_properties(&parse("MgO"), Some(&structure));
+        assert_eq!(properties[CHARGE_BALANCED], 1.0);
+        assert!(properties[GLOBAL_INSTABILITY_INDEX] < 0.05);
+    }
+}
@@ -0,0 +1,401 @@
+//! Multi-Objective (Pareto) Optimization
+//!
+//! NSGA-II machinery (Deb et al. 2002) shared by discovery and HTS:
+//! - Fast non-dominated sorting into Pareto fronts
+//! - Crowding distance for diversity within a front
+//! - Environmental selection of the best `k` points
+//! - Exact hypervolume (slicing) and a tracker for its progress
+//! - Weighted-sum and ε-constraint scalarizations as fallbacks
+//!
+//! Objectives are expressed as [`Objective`]s; every value is first mapped
+//! to a utility where larger is better, so all routines here maximize.
+
+use crate::active_learning::LearningGoal;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// A named objective and its direction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Objective {
+    pub name: String,
+    pub goal: LearningGoal,
+}
+
+impl Objective {
+    pub fn maximize(name: impl Into<String>) -> Self {
+        Self { name: name.into(), goal: LearningGoal::Maximize }
+    }
+
+    pub fn minimize(name: impl Into<String>) -> Self {
+        Self { name: name.into(), goal: LearningGoal::Minimize }
+    }
+
+    pub fn target(name: impl Into<String>, value: f64) -> Self {
+        Self { name: name.into(), goal: LearningGoal::Target(value) }
+    }
+
+    /// Parse `"max:band_gap"`, `"min:cost"` or a bare name (maximized)
+    pub fn parse(spec: &str) -> Self {
+        match spec.split_once(':') {
+            Some(("min", name)) => Self::minimize(name),
+            Some(("max", name)) => Self::maximize(name),
+            _ => Self::maximize(spec),
+        }
+    }
+
+    pub fn utility(&self, value: f64) -> f64 {
+        self.goal.utility(value)
+    }
+}
+
+/// Utility vector of an item, or None if any objective value is missing
+pub fn utilities(objectives: &[Objective], values: &HashMap<String, f64>) -> Option<Vec<f64>> {
+    objectives
+        .iter()
+        .map(|o| values.get(&o.name).map(|v| o.utility(*v)))
+        .collect()
+}
+
+/// Whether `a` Pareto-dominates `b` (maximization)
+pub fn dominates(a: &[f64], b: &[f64]) -> bool {
+    let mut strictly_better = false;
+    for (x, y) in a.iter().zip(b) {
+        if x < y {
+            return false;
+        }
+        if x > y {
+            strictly_better = true;
+        }
+    }
+    strictly_better
+}
+
+/// Fast non-dominated sort; returns fronts of indices, best front first
+pub fn non_dominated_sort(points: &[Vec<f64>]) -> Vec<Vec<usize>> {
+    let n = points.len();
+    let mut dominated_by: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut domination_count = vec![0usize; n];
+    let mut fronts: Vec<Vec<usize>> = vec![Vec::new()];
+
+    for p in 0..n {
+        for q in 0..n {
+            if dominates(&points[p], &points[q]) {
+                dominated_by[p].push(q);
+            } else if dominates(&points[q], &points[p]) {
+                domination_count[p] += 1;
+            }
+        }
+        if domination_count[p] == 0 {
+            fronts[0].push(p);
+        }
+    }
+
+    let mut current = 0;
+    while !fronts[current].is_empty() {
+        let mut next = Vec::new();
+        for &p in &fronts[current] {
+            for &q in &dominated_by[p] {
+                domination_count[q] -= 1;
+                if domination_count[q] == 0 {
+                    next.push(q);
+                }
+            }
+        }
+        current += 1;
+        fronts.push(next);
+    }
+    fronts.pop();
+    fronts
+}
+
+/// Crowding distance of each member of `front` (aligned with `front`);
+/// boundary points get infinity
+#[allow(clippy::needless_range_loop)]
+pub fn crowding_distance(points: &[Vec<f64>], front: &[usize]) -> Vec<f64> {
+    let mut distance = vec![0.0; front.len()];
+    if front.len() <= 2 {
+        return vec![f64::INFINITY; front.len()];
+    }
+    let m = points[front[0]].len();
+
+    for k in 0..m {
+        let mut order: Vec<usize> = (0..front.len()).collect();
+        order.sort_by(|&a, &b| points[front[a]][k].partial_cmp(&points[front[b]][k]).unwrap_or(Ordering::Equal));
+
+        let lo = points[front[order[0]]][k];
+        let hi = points[front[order[front.len() - 1]]][k];
+        distance[order[0]] = f64::INFINITY;
+        distance[order[front.len() - 1]] = f64::INFINITY;
+        if hi - lo < 1e-12 {
+            continue;
+        }
+        for w in 1..front.len() - 1 {
+            let gap = points[front[order[w + 1]]][k] - points[front[order[w - 1]]][k];
+            distance[order[w]] += gap / (hi - lo);
+        }
+    }
+    distance
+}
+
+/// Position of a point in the NSGA-II order
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ParetoRank {
+    pub index: usize,
+    /// 0 = non-dominated
+    pub front: usize,
+    pub crowding_distance: f64,
+}
+
+/// All points ordered by (front ascending, crowding distance descending)
+pub fn nsga2_ranking(points: &[Vec<f64>]) -> Vec<ParetoRank> {
+    let mut ranking = Vec::with_capacity(points.len());
+    for (front_idx, front) in non_dominated_sort(points).iter().enumerate() {
+        let crowding = crowding_distance(points, front);
+        let mut members: Vec<ParetoRank> = front
+            .iter()
+            .zip(crowding)
+            .map(|(&index, crowding_distance)| ParetoRank { index, front: front_idx, crowding_distance })
+            .collect();
+        members.sort_by(|a, b| {
+            b.crowding_distance
+                .partial_cmp(&a.crowding_distance)
+                .unwrap_or(Ordering::Equal)
+                .then(a.index.cmp(&b.index))
+        });
+        ranking.extend(members);
+    }
+    ranking
+}
+
+/// NSGA-II environmental selection of `k` indices
+pub fn nsga2_select(points: &[Vec<f64>], k: usize) -> Vec<usize> {
+    nsga2_ranking(points).into_iter().take(k).map(|r| r.index).collect()
+}
+
+/// Indices of the non-dominated points
+pub fn pareto_front(points: &[Vec<f64>]) -> Vec<usize> {
+    non_dominated_sort(points).into_iter().next().unwrap_or_default()
+}
+
+/// Hypervolume dominated by `points` above `reference` (maximization)
+///
+/// Exact slicing algorithm; cost grows as O(n^(m-1)), fine for the few
+/// objectives and hundreds of points typical here.
+pub fn hypervolume(points: &[Vec<f64>], reference: &[f64]) -> f64 {
+    let mut relevant: Vec<Vec<f64>> = points
+        .iter()
+        .filter(|p| p.len() == reference.len() && p.iter().zip(reference).all(|(x, r)| x > r))
+        .cloned()
+        .collect();
+    if relevant.is_empty() || reference.is_empty() {
+        return 0.0;
+    }
+    slice_volume(&mut relevant, reference)
+}
+
+fn slice_volume(points: &mut [Vec<f64>], reference: &[f64]) -> f64 {
+    let d = reference.len();
+    if d == 1 {
+        return points.iter().map(|p| p[0]).fold(reference[0], f64::max) - reference[0];
+    }
+
+    points.sort_by(|a, b| b[d - 1].partial_cmp(&a[d - 1]).unwrap_or(Ordering::Equal));
+    let mut volume = 0.0;
+    for i in 0..points.len() {
+        let upper = points[i][d - 1];
+        let lower = points.get(i + 1).map_or(reference[d - 1], |p| p[d - 1]);
+        if upper > lower {
+            let mut slice: Vec<Vec<f64>> = points[..=i].iter().map(|p| p[..d - 1].to_vec()).collect();
+            volume += (upper - lower) * slice_volume(&mut slice, &reference[..d - 1]);
+        }
+    }
+    volume
+}
+
+/// Hypervolume of successive result sets against a fixed reference point
+///
+/// When no reference is given, it is fixed at the first recording: the
+/// worst utility per objective minus 10% of its range (or 1.0 if flat).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HypervolumeTracker {
+    pub reference: Option<Vec<f64>>,
+    pub history: Vec<HypervolumeRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HypervolumeRecord {
+    pub label: String,
+    pub hypervolume: f64,
+    pub front_size: usize,
+    pub evaluated: usize,
+}
+
+impl HypervolumeTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_reference(reference: Vec<f64>) -> Self {
+        Self { reference: Some(reference), history: Vec::new() }
+    }
+
+    /// Record the hypervolume of a set of utility vectors
+    pub fn record(&mut self, label: impl Into<String>, points: &[Vec<f64>]) -> f64 {
+        if points.is_empty() {
+            return 0.0;
+        }
+        let reference = self.reference.get_or_insert_with(|| {
+            (0..points[0].len())
+                .map(|k| {
+                    let lo = points.iter().map(|p| p[k]).fold(f64::INFINITY, f64::min);
+                    let hi = points.iter().map(|p| p[k]).fold(f64::NEG_INFINITY, f64::max);
+                    lo - if hi - lo > 1e-12 { 0.1 * (hi - lo) } else { 1.0 }
+                })
+                .collect()
+        });
+
+        let hv = hypervolume(points, reference);
+        self.history.push(HypervolumeRecord {
+            label: label.into(),
+            hypervolume: hv,
+            front_size: pareto_front(points).len(),
+            evaluated: points.len(),
+        });
+        hv
+    }
+}
+
+/// Single-score fallbacks for multi-objective ranking
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Scalarization {
+    /// Weighted sum of min-max normalized utilities (missing weights = 1)
+    WeightedSum(HashMap<String, f64>),
+    /// Maximize `primary` subject to raw-value bounds on other objectives;
+    /// infeasible points score -∞
+    EpsilonConstraint {
+        primary: String,
+        bounds: HashMap<String, (f64, f64)>,
+    },
+}
+
+impl Scalarization {
+    /// Score every point (raw objective values aligned with `objectives`)
+    pub fn scores(&self, objectives: &[Objective], values: &[Vec<f64>]) -> Vec<f64> {
+        match self {
+            Scalarization::WeightedSum(weights) => {
+                let utilities: Vec<Vec<f64>> = values
+                    .iter()
+                    .map(|v| objectives.iter().zip(v).map(|(o, x)| o.utility(*x)).collect())
+                    .collect();
+                let ranges: Vec<(f64, f64)> = (0..objectives.len())
+                    .map(|k| {
+                        utilities.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), u| {
+                            (lo.min(u[k]), hi.max(u[k]))
+                        })
+                    })
+                    .collect();
+
+                utilities
+                    .iter()
+                    .map(|u| {
+                        objectives
+                            .iter()
+                            .enumerate()
+                            .map(|(k, o)| {
+                                let (lo, hi) = ranges[k];
+                                let normalized = if hi - lo > 1e-12 { (u[k] - lo) / (hi - lo) } else { 1.0 };
+                                weights.get(&o.name).copied().unwrap_or(1.0) * normalized
+                            })
+                            .sum()
+                    })
+                    .collect()
+            }
+            Scalarization::EpsilonConstraint { primary, bounds } => {
+                let primary_idx = objectives.iter().position(|o| &o.name == primary);
+                values
+                    .iter()
+                    .map(|v| {
+                        let feasible = objectives.iter().zip(v).all(|(o, x)| {
+                            bounds.get(&o.name).map_or(true, |(lo, hi)| x >= lo && x <= hi)
+                        });
+                        match (feasible, primary_idx) {
+                            (true, Some(i)) => objectives[i].utility(v[i]),
+                            _ => f64::NEG_INFINITY,
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_non_dominated_sort_and_crowding() {
+        let points = vec![
+            vec![1.0, 5.0],
+            vec![2.0, 4.0],
+            vec![3.0, 3.0],
+            vec![1.0, 1.0], // dominated by everything above
+            vec![2.0, 2.0], // dominated by (2,4) and (3,3)
+            vec![5.0, 1.0],
+        ];
+        let fronts = non_dominated_sort(&points);
+        assert_eq!(fronts[0], vec![0, 1, 2, 5]);
+        assert_eq!(fronts[1], vec![4]);
+        assert_eq!(fronts[2], vec![3]);
+
+        let crowding = crowding_distance(&points, &fronts[0]);
+        assert!(crowding[0].is_infinite() && crowding[3].is_infinite());
+        assert!(crowding[1].is_finite() && crowding[1] > 0.0);
+
+        assert_eq!(nsga2_select(&points, 2), vec![0, 5]);
+        assert!(dominates(&[2.0, 2.0], &[1.0, 2.0]));
+        assert!(!dominates(&[2.0, 2.0], &[2.0, 2.0]));
+    }
+
+    #[test]
+    fn test_hypervolume() {
+        // Two staircase boxes from the origin: 3x1 + 1x3 - 1x1 overlap
+        let points = vec![vec![3.0, 1.0], vec![1.0, 3.0]];
+        assert!((hypervolume(&points, &[0.0, 0.0]) - 5.0).abs() < 1e-12);
+
+        // Dominated points add nothing; 3D single box
+        let mut with_dominated = points.clone();
+        with_dominated.push(vec![0.5, 0.5]);
+        assert!((hypervolume(&with_dominated, &[0.0, 0.0]) - 5.0).abs() < 1e-12);
+        assert!((hypervolume(&[vec![1.0, 2.0, 3.0]], &[0.0, 0.0, 0.0]) - 6.0).abs() < 1e-12);
+
+        let mut tracker = HypervolumeTracker::with_reference(vec![0.0, 0.0]);
+        tracker.record("initial", &points[..1]);
+        tracker.record("final", &points);
+        assert!(tracker.history[1].hypervolume > tracker.history[0].hypervolume);
+        assert_eq!(tracker.history[1].front_size, 2);
+    }
+
+    #[test]
+    fn test_objectives_and_scalarizations() {
+        let objectives = vec![Objective::maximize("band_gap"), Objective::parse("min:cost")];
+        assert_eq!(objectives[1], Objective::minimize("cost"));
+
+        let mut values = HashMap::new();
+        values.insert("band_gap".to_string(), 2.0);
+        assert!(utilities(&objectives, &values).is_none());
+        values.insert("cost".to_string(), 10.0);
+        assert_eq!(utilities(&objectives, &values), Some(vec![2.0, -10.0]));
+
+        let raw = vec![vec![3.0, 50.0], vec![2.0, 5.0], vec![1.0, 1.0]];
+        let weighted = Scalarization::WeightedSum(HashMap::new()).scores(&objectives, &raw);
+        assert!(weighted[1] > weighted[0]);
+
+        let mut bounds = HashMap::new();
+        bounds.insert("cost".to_string(), (0.0, 10.0));
+        let constrained = Scalarization::EpsilonConstraint { primary: "band_gap".into(), bounds }.scores(&objectives, &raw);
+        assert_eq!(constrained[0], f64::NEG_INFINITY);
+        assert_eq!(constrained[1], 2.0);
+    }
+}
@@ -0,0 +1,602 @@
+//! 2D projections of embedding spaces for materials maps
+//!
+//! - PCA: the two leading principal components
+//! - t-SNE: perplexity-calibrated affinities over the nearest neighbors,
+//!   Barnes-Hut gradient (O(n log n) per iteration)
+//! - UMAP: fuzzy k-NN graph laid out by negative-sampling SGD, started from PCA
+//!
+//! [`MaterialsMap`] pairs the coordinates with material ids, formulas and
+//! optional cluster labels and serializes to JSON for front ends.
+
+use crate::{Error, Result};
+use nalgebra::{DMatrix, SymmetricEigen};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ProjectionMethod {
+    Pca,
+    Tsne { perplexity: f64, iterations: usize, learning_rate: f64, seed: u64 },
+    Umap { n_neighbors: usize, min_dist: f64, epochs: usize, seed: u64 },
+}
+
+impl ProjectionMethod {
+    pub fn tsne() -> Self {
+        ProjectionMethod::Tsne { perplexity: 30.0, iterations: 1000, learning_rate: 200.0, seed: 42 }
+    }
+
+    pub fn umap() -> Self {
+        ProjectionMethod::Umap { n_neighbors: 15, min_dist: 0.1, epochs: 200, seed: 42 }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProjectionMethod::Pca => "pca",
+            ProjectionMethod::Tsne { .. } => "tsne",
+            ProjectionMethod::Umap { .. } => "umap",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Projection {
+    pub points: Vec<[f64; 2]>,
+    /// Share of variance on each axis; PCA only
+    pub explained_variance: Option<[f64; 2]>,
+}
+
+/// Project `vectors` to two dimensions
+pub fn project(vectors: &[Vec<f64>], method: &ProjectionMethod) -> Result<Projection> {
+    let dim = vectors.first().map_or(0, Vec::len);
+    if vectors.iter().any(|v| v.len() != dim || v.iter().any(|x| !x.is_finite())) {
+        return Err(Error::invalid_input("Vectors must share one dimension and be finite"));
+    }
+    if vectors.len() < 3 {
+        return Err(Error::invalid_input("A projection needs at least three points"));
+    }
+    match method {
+        ProjectionMethod::Pca => {
+            let (points, explained) = pca(vectors);
+            Ok(Projection { points, explained_variance: Some(explained) })
+        }
+        ProjectionMethod::Tsne { perplexity, iterations, learning_rate, seed } => Ok(Projection {
+            points: tsne(vectors, *perplexity, *iterations, *learning_rate, *seed),
+            explained_variance: None,
+        }),
+        ProjectionMethod::Umap { n_neighbors, min_dist, epochs, seed } => Ok(Projection {
+            points: umap(vectors, *n_neighbors, *min_dist, *epochs, *seed),
+            explained_variance: None,
+        }),
+    }
+}
+
+fn squared_distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum()
+}
+
+// ============================================================================
+// PCA
+// ============================================================================
+
+fn pca(vectors: &[Vec<f64>]) -> (Vec<[f64; 2]>, [f64; 2]) {
+    let n = vectors.len();
+    let dim = vectors[0].len();
+    let mean: Vec<f64> = (0..dim).map(|j| vectors.iter().map(|v| v[j]).sum::<f64>() / n as f64).collect();
+    let centered = DMatrix::from_fn(n, dim, |i, j| vectors[i][j] - mean[j]);
+    let covariance = centered.transpose() * &centered / (n - 1) as f64;
+
+    let eigen = SymmetricEigen::new(covariance);
+    let mut order: Vec<usize> = (0..dim).collect();
+    order.sort_by(|&a, &b| eigen.eigenvalues[b].total_cmp(&eigen.eigenvalues[a]));
+    let total: f64 = eigen.eigenvalues.iter().map(|v| v.max(0.0)).sum();
+
+    let axes: Vec<_> = order.iter().take(2).map(|&k| eigen.eigenvectors.column(k).clone_owned()).collect();
+    let points = (0..n)
+        .map(|i| {
+            let row = centered.row(i);
+            // A one-dimensional input has no second axis
+            let coord = |a: usize| axes.get(a).map_or(0.0, |axis| row.iter().zip(axis.iter()).map(|(x, y)| x * y).sum());
+            [coord(0), coord(1)]
+        })
+        .collect();
+    let share = |a: usize| match order.get(a) {
+        Some(&k) if total > 0.0 => eigen.eigenvalues[k].max(0.0) / total,
+        _ => 0.0,
+    };
+    (points, [share(0), share(1)])
+}
+
+// ============================================================================
+// t-SNE
+// ============================================================================
+
+fn tsne(vectors: &[Vec<f64>], perplexity: f64, iterations: usize, learning_rate: f64, seed: u64) -> Vec<[f64; 2]> {
+    const EXAGGERATION: f64 = 12.0;
+    const EXAGGERATION_ITERATIONS: usize = 250;
+    /// Barnes-Hut opening angle: cells smaller than this share of their
+    /// distance are summarized by their center of mass
+    const THETA: f64 = 0.5;
+    /// Smaller inputs get the exact repulsion (an opening angle of zero)
+    const BARNES_HUT_MIN_POINTS: usize = 200;
+
+    let n = vectors.len();
+    // Perplexity cannot exceed the number of neighbors
+    let perplexity = perplexity.min((n - 1) as f64 / 3.0).max(1.0);
+    let k = ((3.0 * perplexity).ceil() as usize).clamp(1, n - 1);
+    let theta = if n < BARNES_HUT_MIN_POINTS { 0.0 } else { THETA };
+
+    // Conditional affinities over the k nearest neighbors, with a per-point
+    // bandwidth matching the perplexity
+    let target_entropy = perplexity.ln();
+    let mut joint: Vec<std::collections::HashMap<usize, f64>> = vec![std::collections::HashMap::new(); n];
+    let mut row_distances: Vec<(usize, f64)> = Vec::with_capacity(n - 1);
+    for i in 0..n {
+        row_distances.clear();
+        row_distances.extend((0..n).filter(|&j| j != i).map(|j| (j, squared_distance(&vectors[i], &vectors[j]))));
+        row_distances.select_nth_unstable_by(k - 1, |a, b| a.1.total_cmp(&b.1));
+        let neighbors = &row_distances[..k];
+
+        let (mut lo, mut hi, mut beta) = (0.0, f64::INFINITY, 1.0);
+        let mut row = vec![0.0; k];
+        for _ in 0..64 {
+            for (r, (_, d)) in row.iter_mut().zip(neighbors) {
+                *r = (-beta * d).exp();
+            }
+            let sum: f64 = row.iter().sum::<f64>().max(1e-300);
+            let entropy = sum.ln() + beta * neighbors.iter().zip(&row).map(|((_, d), r)| d * r).sum::<f64>() / sum;
+            row.iter_mut().for_each(|r| *r /= sum);
+            if (entropy - target_entropy).abs() < 1e-5 {
+                break;
+            }
+            if entropy > target_entropy {
+                lo = beta;
+                beta = if hi.is_finite() { (beta + hi) / 2.0 } else { beta * 2.0 };
+            } else {
+                hi = beta;
+                beta = (beta + lo) / 2.0;
+            }
+        }
+        // Symmetric joint affinities
+        for (&(j, _), &p) in neighbors.iter().zip(&row) {
+            let share = p / (2.0 * n as f64);
+            *joint[i].entry(j).or_insert(0.0) += share;
+            *joint[j].entry(i).or_insert(0.0) += share;
+        }
+    }
+    let joint: Vec<Vec<(usize, f64)>> = joint.into_iter()
+        .map(|row| {
+            let mut row: Vec<(usize, f64)> = row.into_iter().collect();
+            row.sort_by_key(|&(j, _)| j);
+            row
+        })
+        .collect();
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut y: Vec<[f64; 2]> = (0..n).map(|_| [rng.gen_range(-1e-4..1e-4), rng.gen_range(-1e-4..1e-4)]).collect();
+    let mut velocity = vec![[0.0f64; 2]; n];
+    let mut gains = vec![[1.0f64; 2]; n];
+    let mut repulsion = vec![[0.0f64; 2]; n];
+
+    for iteration in 0..iterations {
+        let exaggeration = if iteration < EXAGGERATION_ITERATIONS { EXAGGERATION } else { 1.0 };
+        let momentum = if iteration < EXAGGERATION_ITERATIONS { 0.5 } else { 0.8 };
+
+        // Student-t repulsion, summed over a quadtree of the embedding
+        let tree = QuadTree::build(&y);
+        let mut z = 0.0;
+        for (i, force) in repulsion.iter_mut().enumerate() {
+            let (f, zi) = tree.repulsion(&y, i, theta);
+            *force = f;
+            z += zi;
+        }
+        let z = z.max(1e-300);
+
+        for i in 0..n {
+            let mut gradient = [0.0; 2];
+            for &(j, p) in &joint[i] {
+                let q = 1.0 / (1.0 + squared_distance(&y[i], &y[j]));
+                gradient[0] += exaggeration * p * q * (y[i][0] - y[j][0]);
+                gradient[1] += exaggeration * p * q * (y[i][1] - y[j][1]);
+            }
+            for a in 0..2 {
+                gradient[a] = 4.0 * (gradient[a] - repulsion[i][a] / z);
+                // Adaptive gains: grow while steps keep their direction
+                gains[i][a] = if gradient[a].signum() != velocity[i][a].signum() {
+                    gains[i][a] + 0.2
+                } else {
+                    (gains[i][a] * 0.8).max(0.01)
+                };
+                velocity[i][a] = momentum * velocity[i][a] - learning_rate * gains[i][a] * gradient[a];
+            }
+        }
+        for (point, v) in y.iter_mut().zip(&velocity) {
+            point[0] += v[0];
+            point[1] += v[1];
+        }
+        center(&mut y);
+    }
+    y
+}
+
+/// Depth at which coincident points stop splitting cells
+const QUADTREE_MAX_DEPTH: usize = 32;
+
+struct QuadCell {
+    center: [f64; 2],
+    half_width: f64,
+    count: f64,
+    center_of_mass: [f64; 2],
+    /// Index of the first of four consecutive children
+    children: Option<usize>,
+    points: Vec<usize>,
+}
+
+impl QuadCell {
+    fn new(center: [f64; 2], half_width: f64) -> Self {
+        Self { center, half_width, count: 0.0, center_of_mass: [0.0; 2], children: None, points: Vec::new() }
+    }
+}
+
+/// Barnes-Hut quadtree over 2D points
+struct QuadTree {
+    cells: Vec<QuadCell>,
+}
+
+impl QuadTree {
+    fn build(points: &[[f64; 2]]) -> Self {
+        let (lo, hi) = points.iter().fold(([f64::INFINITY; 2], [f64::NEG_INFINITY; 2]), |(lo, hi), p| {
+            ([lo[0].min(p[0]), lo[1].min(p[1])], [hi[0].max(p[0]), hi[1].max(p[1])])
+        });
+        let center = [(lo[0] + hi[0]) / 2.0, (lo[1] + hi[1]) / 2.0];
+        let half_width = ((hi[0] - lo[0]).max(hi[1] - lo[1]) / 2.0).max(1e-12) * (1.0 + 1e-9);
+        let mut tree = Self { cells: vec![QuadCell::new(center, half_width)] };
+        for i in 0..points.len() {
+            tree.insert(0, i, points, 0);
+        }
+        tree
+    }
+
+    fn insert(&mut self, mut cell: usize, index: usize, points: &[[f64; 2]], mut depth: usize) {
+        let p = points[index];
+        loop {
+            let c = &mut self.cells[cell];
+            c.count += 1.0;
+            for (com, x) in c.center_of_mass.iter_mut().zip(p) {
+                *com += (x - *com) / c.count;
+            }
+            let first = match c.children {
+                Some(first) => first,
+                None if c.points.is_empty() || depth >= QUADTREE_MAX_DEPTH => {
+                    c.points.push(index);
+                    return;
+                }
+                None => {
+                    let (center, quarter) = (c.center, c.half_width / 2.0);
+                    let residents = std::mem::take(&mut c.points);
+                    let first = self.cells.len();
+                    self.cells[cell].children = Some(first);
+                    for (dx, dy) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+                        self.cells.push(QuadCell::new([center[0] + dx * quarter, center[1] + dy * quarter], quarter));
+                    }
+                    for resident in residents {
+                        let child = first + Self::quadrant(center, points[resident]);
+                        self.insert(child, resident, points, depth + 1);
+                    }
+                    first
+                }
+            };
+            cell = first + Self::quadrant(self.cells[cell].center, p);
+            depth += 1;
+        }
+    }
+
+    fn quadrant(center: [f64; 2], p: [f64; 2]) -> usize {
+        usize::from(p[0] >= center[0]) + 2 * usize::from(p[1] >= center[1])
+    }
+
+    /// Unnormalized repulsive force on point `i` and its share of the
+    /// Student-t normalizer
+    fn repulsion(&self, points: &[[f64; 2]], i: usize, theta: f64) -> ([f64; 2], f64) {
+        let p = points[i];
+        let mut force = [0.0; 2];
+        let mut z = 0.0;
+        let mut add = |other: [f64; 2], weight: f64| {
+            let q = 1.0 / (1.0 + squared_distance(&p, &other));
+            z += weight * q;
+            force[0] += weight * q * q * (p[0] - other[0]);
+            force[1] += weight * q * q * (p[1] - other[1]);
+        };
+        let mut stack = vec![0];
+        while let Some(cell) = stack.pop() {
+            let c = &self.cells[cell];
+            if c.count == 0.0 {
+                continue;
+            }
+            match c.children {
+                None => {
+                    for &j in c.points.iter().filter(|&&j| j != i) {
+                        add(points[j], 1.0);
+                    }
+                }
+                Some(first) => {
+                    let distance = squared_distance(&p, &c.center_of_mass).sqrt();
+                    if 2.0 * c.half_width < theta * distance {
+                        add(c.center_of_mass, c.count);
+                    } else {
+                        stack.extend(first..first + 4);
+                    }
+                }
+            }
+        }
+        (force, z)
+    }
+}
+
+fn center(points: &mut [[f64; 2]]) {
+    let n = points.len() as f64;
+    let mean = points.iter().fold([0.0; 2], |m, p| [m[0] + p[0] / n, m[1] + p[1] / n]);
+    for p in points {
+        p[0] -= mean[0];
+        p[1] -= mean[1];
+    }
+}
+
+// ============================================================================
+// UMAP
+// ============================================================================
+
+fn umap(vectors: &[Vec<f64>], n_neighbors: usize, min_dist: f64, epochs: usize, seed: u64) -> Vec<[f64; 2]> {
+    let n = vectors.len();
+    let k = n_neighbors.clamp(2, n - 1);
+
+    // Exact k nearest neighbors
+    let knn: Vec<Vec<(usize, f64)>> = (0..n)
+        .map(|i| {
+            let mut row: Vec<(usize, f64)> = (0..n)
+                .filter(|&j| j != i)
+                .map(|j| (j, squared_distance(&vectors[i], &vectors[j]).sqrt()))
+                .collect();
+            row.sort_by(|a, b| a.1.total_cmp(&b.1));
+            row.truncate(k);
+            row
+        })
+        .collect();
+
+    // Local fuzzy sets: rho is the nearest distance, sigma solves
+    // sum exp(-(d - rho) / sigma) = log2(k)
+    let target = (k as f64).log2();
+    let mut weights: std::collections::HashMap<(usize, usize), f64> = std::collections::HashMap::new();
+    for (i, row) in knn.iter().enumerate() {
+        let rho = row[0].1;
+        let (mut lo, mut hi, mut sigma) = (0.0, f64::INFINITY, 1.0);
+        for _ in 0..64 {
+            let sum: f64 = row.iter().map(|(_, d)| (-(d - rho).max(0.0) / sigma).exp()).sum();
+            if (sum - target).abs() < 1e-5 {
+                break;
+            }
+            if sum > target {
+                hi = sigma;
+                sigma = (lo + sigma) / 2.0;
+            } else {
+                lo = sigma;
+                sigma = if hi.is_finite() { (sigma + hi) / 2.0 } else { sigma * 2.0 };
+            }
+        }
+        for &(j, d) in row {
+            weights.insert((i, j), (-(d - rho).max(0.0) / sigma.max(1e-12)).exp());
+        }
+    }
+    // Fuzzy union of the directed graph
+    let mut edges: Vec<(usize, usize, f64)> = Vec::new();
+    for (&(i, j), &w) in &weights {
+        if i < j || !weights.contains_key(&(j, i)) {
+            let other = weights.get(&(j, i)).copied().unwrap_or(0.0);
+            edges.push((i, j, w + other - w * other));
+        }
+    }
+    edges.sort_by_key(|e| (e.0, e.1));
+    let max_weight = edges.iter().map(|e| e.2).fold(0.0, f64::max).max(1e-12);
+
+    let (a, b) = fit_curve(min_dist);
+    let mut y = pca(vectors).0;
+    // Rescale the start to the spread UMAP layouts usually take
+    let spread = y.iter().flat_map(|p| p.iter()).fold(0.0f64, |m, v| m.max(v.abs())).max(1e-12);
+    for p in &mut y {
+        p[0] *= 10.0 / spread;
+        p[1] *= 10.0 / spread;
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    const NEGATIVE_SAMPLES: usize = 5;
+    let clip = |g: f64| g.clamp(-4.0, 4.0);
+    for epoch in 0..epochs {
+        let alpha = 1.0 - epoch as f64 / epochs as f64;
+        for &(i, j, w) in &edges {
+            // Edges are sampled in proportion to their weight
+            if rng.gen::<f64>() > w / max_weight {
+                continue;
+            }
+            let d2 = squared_distance(&y[i], &y[j]);
+            if d2 > 0.0 {
+                let coefficient = -2.0 * a * b * d2.powf(b - 1.0) / (1.0 + a * d2.powf(b));
+                let (yi, yj) = (y[i], y[j]);
+                let step = [0, 1].map(|axis| clip(coefficient * (yi[axis] - yj[axis])) * alpha);
+                y[i] = [yi[0] + step[0], yi[1] + step[1]];
+                y[j] = [yj[0] - step[0], yj[1] - step[1]];
+            }
+            for _ in 0..NEGATIVE_SAMPLES {
+                let other = rng.gen_range(0..n);
+                if other == i {
+                    continue;
+                }
+                let d2 = squared_distance(&y[i], &y[other]);
+                let coefficient = 2.0 * b / ((0.001 + d2) * (1.0 + a * d2.powf(b)));
+                let (yi, yo) = (y[i], y[other]);
+                y[i] = [0, 1].map(|axis| yi[axis] + clip(coefficient * (yi[axis] - yo[axis])) * alpha);
+            }
+        }
+    }
+    center(&mut y);
+    y
+}
+
+/// `a`, `b` so that 1 / (1 + a d^2b) approximates 1 below `min_dist` and
+/// exp(-(d - min_dist)) beyond it, by grid search
+fn fit_curve(min_dist: f64) -> (f64, f64) {
+    let target = |d: f64| if d < min_dist { 1.0 } else { (-(d - min_dist)).exp() };
+    let samples: Vec<f64> = (1..300).map(|i| i as f64 * 0.01).collect();
+    let mut best = (1.0, 1.0, f64::INFINITY);
+    for ai in 1..=60 {
+        for bi in 1..=40 {
+            let (a, b) = (ai as f64 * 0.05, bi as f64 * 0.05);
+            let error: f64 = samples.iter().map(|&d| (1.0 / (1.0 + a * d.powf(2.0 * b)) - target(d)).powi(2)).sum();
+            if error < best.2 {
+                best = (a, b, error);
+            }
+        }
+    }
+    (best.0, best.1)
+}
+
+// ============================================================================
+// MATERIALS MAP
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapPoint {
+    pub material_id: Uuid,
+    pub formula: String,
+    pub x: f64,
+    pub y: f64,
+    /// Cluster label; `None` when unclustered or noise
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cluster: Option<usize>,
+}
+
+/// Projected embeddings ready for a materials-map view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaterialsMap {
+    pub method: String,
+    pub model_version: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explained_variance: Option<[f64; 2]>,
+    pub n_clusters: usize,
+    pub points: Vec<MapPoint>,
+}
+
+impl MaterialsMap {
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two well separated blobs in 10D
+    fn blobs() -> Vec<Vec<f64>> {
+        let mut rng = StdRng::seed_from_u64(3);
+        (0..40)
+            .map(|i| {
+                let offset = if i < 20 { 0.0 } else { 6.0 };
+                (0..10).map(|_| offset + rng.gen_range(-1.0..1.0)).collect()
+            })
+            .collect()
+    }
+
+    /// Largest within-blob spread below the gap between blob centers
+    fn separated(points: &[[f64; 2]]) -> bool {
+        let centroid = |r: std::ops::Range<usize>| {
+            let len = r.len() as f64;
+            points[r].iter().fold([0.0; 2], |m, p| [m[0] + p[0] / len, m[1] + p[1] / len])
+        };
+        let (a, b) = (centroid(0..20), centroid(20..40));
+        let gap = squared_distance(&a, &b).sqrt();
+        let spread = points[..20].iter().map(|p| squared_distance(p, &a).sqrt())
+            .chain(points[20..].iter().map(|p| squared_distance(p, &b).sqrt()))
+            .fold(0.0, f64::max);
+        gap > spread
+    }
+
+    #[test]
+    fn test_projections_separate_blobs() {
+        let vectors = blobs();
+        let pca = project(&vectors, &ProjectionMethod::Pca).unwrap();
+        let [first, second] = pca.explained_variance.unwrap();
+        assert!(first > 0.5 && first >= second);
+        assert!(separated(&pca.points));
+
+        let tsne = project(&vectors, &ProjectionMethod::Tsne { perplexity: 10.0, iterations: 400, learning_rate: 100.0, seed: 1 }).unwrap();
+        assert!(separated(&tsne.points));
+
+        let umap = project(&vectors, &ProjectionMethod::Umap { n_neighbors: 8, min_dist: 0.1, epochs: 100, seed: 1 }).unwrap();
+        assert_eq!(umap.points.len(), 40);
+        assert!(umap.points.iter().all(|p| p[0].is_finite() && p[1].is_finite()));
+        assert!(separated(&umap.points));
+
+        assert!(project(&vectors[..2], &ProjectionMethod::Pca).is_err());
+    }
+
+    #[test]
+    fn test_barnes_hut_tsne_separates_blobs() {
+        let mut rng = StdRng::seed_from_u64(9);
+        let vectors: Vec<Vec<f64>> = (0..240)
+            .map(|i| {
+                let offset = if i < 120 { 0.0 } else { 6.0 };
+                (0..10).map(|_| offset + rng.gen_range(-1.0..1.0)).collect()
+            })
+            .collect();
+        let points = tsne(&vectors, 20.0, 300, 100.0, 1);
+        assert!(points.iter().all(|p| p[0].is_finite() && p[1].is_finite()));
+
+        let centroid = |r: std::ops::Range<usize>| {
+            let len = r.len() as f64;
+            points[r].iter().fold([0.0; 2], |m, p| [m[0] + p[0] / len, m[1] + p[1] / len])
+        };
+        let (a, b) = (centroid(0..120), centroid(120..240));
+        // At least 90% of points sit closer to their own blob's centroid
+        let own = points.iter().enumerate()
+            .filter(|(i, p)| {
+                let (mine, other) = if *i < 120 { (&a, &b) } else { (&b, &a) };
+                squared_distance(*p, mine) < squared_distance(*p, other)
+            })
+            .count();
+        assert!(own >= 216, "{} of 240", own);
+    }
+
+    #[test]
+    fn test_barnes_hut_repulsion() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut points: Vec<[f64; 2]> = (0..200).map(|_| [rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0)]).collect();
+        points.push(points[0]); // coincident points must not split forever
+        let tree = QuadTree::build(&points);
+
+        for i in [0, 17, 200] {
+            let mut exact = ([0.0; 2], 0.0);
+            for (_, other) in points.iter().enumerate().filter(|(j, _)| *j != i) {
+                let q = 1.0 / (1.0 + squared_distance(&points[i], other));
+                exact.1 += q;
+                for a in 0..2 {
+                    exact.0[a] += q * q * (points[i][a] - other[a]);
+                }
+            }
+            let (force, z) = tree.repulsion(&points, i, 0.0);
+            assert!((z - exact.1).abs() < 1e-9 && (force[0] - exact.0[0]).abs() < 1e-9);
+
+            let (force, z) = tree.repulsion(&points, i, 0.5);
+            assert!((z - exact.1).abs() / exact.1 < 0.05, "{} vs {}", z, exact.1);
+            let norm = exact.0[0].hypot(exact.0[1]);
+            assert!((force[0] - exact.0[0]).hypot(force[1] - exact.0[1]) < 0.1 * norm.max(1e-3));
+        }
+    }
+}
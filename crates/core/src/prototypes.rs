@@ -0,0 +1,863 @@
+//! Structure Prototypes
+//!
+//! Cubic structure prototypes that can be decorated with ions into full
+//! [`Structure`]s for candidate generation:
+//! - Perovskite ABX3 (Pm-3m), spinel AB2O4 (Fd-3m), garnet A3B2C3O12
+//!   (Ia-3d), rock salt AX and fluorite AX2 (Fm-3m); names and formulas
+//!   follow the LIRS structure macros, and [`PROTOTYPE_TABLE`] lists their
+//!   ICSD structure types and Pearson symbols
+//! - One element may occupy several inequivalent sites (Al in YAG, Fe in
+//!   magnetite), in the same or different oxidation states
+//! - Plausibility checks: charge balance, radius-ratio windows per
+//!   coordination and the Goldschmidt tolerance factor
+//! - Lattice parameters scaled so cation-anion bonds match Shannon radii
+//! - Pairwise ion substitution probabilities
+//!
+//! Site positions are generated from space-group generators, so the
+//! prototypes carry full unit cells (160 sites for garnet).
+
+use crate::elements;
+use crate::lirs::{Atom, SExpr, LIRS};
+use crate::composition::Composition;
+use crate::material::{CrystalSystem, Site, Structure};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+// ============================================================================
+// IONS
+// ============================================================================
+
+/// An element in a definite oxidation state
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Ion {
+    pub element: String,
+    pub charge: i8,
+}
+
+impl Ion {
+    pub fn new(element: impl Into<String>, charge: i8) -> Self {
+        Self { element: element.into(), charge }
+    }
+
+    /// Parse notation such as "Fe3+", "O2-" or "Na+"
+    pub fn parse(s: &str) -> Result<Self> {
+        let sign = match s.chars().last() {
+            Some('+') => 1,
+            Some('-') => -1,
+            _ => return Err(Error::invalid_input(format!("Ion '{}' has no charge sign", s))),
+        };
+        let body = &s[..s.len() - 1];
+        let split = body.find(|c: char| c.is_ascii_digit()).unwrap_or(body.len());
+        let (element, magnitude) = body.split_at(split);
+        let magnitude: i8 = if magnitude.is_empty() {
+            1
+        } else {
+            magnitude.parse().map_err(|_| Error::invalid_input(format!("Invalid ion charge in '{}'", s)))?
+        };
+        if elements::element(element).is_none() {
+            return Err(Error::invalid_input(format!("Unknown element in ion '{}'", s)));
+        }
+        Ok(Self::new(element, sign * magnitude))
+    }
+
+    /// Shannon radius at a coordination number (nearest tabulated one)
+    pub fn radius(&self, coordination: u8) -> Option<f64> {
+        elements::ionic_radius(&self.element, self.charge, coordination)
+    }
+}
+
+impl fmt::Display for Ion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.charge < 0 { '-' } else { '+' };
+        match self.charge.unsigned_abs() {
+            1 => write!(f, "{}{}", self.element, sign),
+            n => write!(f, "{}{}{}", self.element, n, sign),
+        }
+    }
+}
+
+/// Goldschmidt tolerance factor t = (rA + rX) / (√2 (rB + rX))
+pub fn goldschmidt_tolerance(r_a: f64, r_b: f64, r_x: f64) -> f64 {
+    (r_a + r_x) / (std::f64::consts::SQRT_2 * (r_b + r_x))
+}
+
+/// Cation/anion radius-ratio window for a coordination number
+///
+/// Pauling's limits widened by about 25%, since real structures violate
+/// the strict ones routinely (Al3+ is octahedral in spinel at 0.38).
+fn ratio_window(coordination: u8) -> (f64, f64) {
+    match coordination {
+        0..=3 => (0.0, 0.4),
+        4 => (0.17, 0.55),
+        5..=6 => (0.31, 0.95),
+        7..=9 => (0.55, 1.3),
+        _ => (0.75, 1.6),
+    }
+}
+
+// ============================================================================
+// PROTOTYPES
+// ============================================================================
+
+/// Reference data of a prototype, as the ICSD classifies structure types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrototypeInfo {
+    /// LIRS macro name
+    pub name: &'static str,
+    /// ICSD structure type, "Name#prototype formula"
+    pub structure_type: &'static str,
+    pub pearson_symbol: &'static str,
+    pub space_group_symbol: &'static str,
+    /// Formula of the type compound
+    pub example: &'static str,
+}
+
+/// Built-in prototypes by name, ICSD structure type and Pearson symbol
+pub const PROTOTYPE_TABLE: &[PrototypeInfo] = &[
+    PrototypeInfo {
+        name: "perovskite",
+        structure_type: "Perovskite#CaTiO3",
+        pearson_symbol: "cP5",
+        space_group_symbol: "Pm-3m",
+        example: "SrTiO3",
+    },
+    PrototypeInfo {
+        name: "spinel",
+        structure_type: "Spinel#Al2MgO4",
+        pearson_symbol: "cF56",
+        space_group_symbol: "Fd-3m",
+        example: "MgAl2O4",
+    },
+    PrototypeInfo {
+        name: "garnet",
+        structure_type: "Garnet#Al2Ca3Si3O12",
+        pearson_symbol: "cI160",
+        space_group_symbol: "Ia-3d",
+        example: "Y3Al5O12",
+    },
+    PrototypeInfo {
+        name: "rock-salt",
+        structure_type: "NaCl",
+        pearson_symbol: "cF8",
+        space_group_symbol: "Fm-3m",
+        example: "NaCl",
+    },
+    PrototypeInfo {
+        name: "fluorite",
+        structure_type: "CaF2",
+        pearson_symbol: "cF12",
+        space_group_symbol: "Fm-3m",
+        example: "CaF2",
+    },
+];
+
+/// A crystallographic site of a prototype
+#[derive(Debug, Clone)]
+pub struct PrototypeSite {
+    /// Wyckoff label, e.g. "16a"
+    pub wyckoff: &'static str,
+    pub coordination: u8,
+    pub anion: bool,
+    /// Element every decoration must put here (the oxygen of spinel, garnet)
+    pub fixed: Option<&'static str>,
+    /// Fractional positions of the full conventional cell
+    pub positions: Vec<[f64; 3]>,
+}
+
+impl PrototypeSite {
+    pub fn multiplicity(&self) -> usize {
+        self.positions.len()
+    }
+}
+
+/// A cubic structure type that can be decorated with ions
+#[derive(Debug, Clone)]
+pub struct Prototype {
+    /// LIRS macro name ("perovskite", "spinel", ...)
+    pub name: &'static str,
+    pub space_group: u16,
+    pub sites: Vec<PrototypeSite>,
+    /// Nearest cation-anion distance per site in a unit cube (0 for anions)
+    bond_lengths: Vec<f64>,
+}
+
+impl Prototype {
+    fn build(name: &'static str, space_group: u16, sites: Vec<PrototypeSite>) -> Self {
+        let anion_positions: Vec<[f64; 3]> = sites
+            .iter()
+            .filter(|s| s.anion)
+            .flat_map(|s| s.positions.iter().copied())
+            .collect();
+        let bond_lengths = sites
+            .iter()
+            .map(|site| {
+                if site.anion {
+                    return 0.0;
+                }
+                anion_positions
+                    .iter()
+                    .map(|p| periodic_distance(&site.positions[0], p))
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .collect();
+        Self { name, space_group, sites, bond_lengths }
+    }
+
+    /// ABX3 cubic perovskite (SrTiO3): A 1b (CN 12), B 1a (CN 6), X 3d
+    pub fn perovskite() -> Self {
+        Self::build("perovskite", 221, vec![
+            site("1b", 12, false, None, vec![[0.5, 0.5, 0.5]]),
+            site("1a", 6, false, None, vec![[0.0, 0.0, 0.0]]),
+            site("3d", 6, true, None, vec![[0.5, 0.0, 0.0], [0.0, 0.5, 0.0], [0.0, 0.0, 0.5]]),
+        ])
+    }
+
+    /// AB2O4 normal spinel (MgAl2O4), origin choice 2: A 8a (tetrahedral),
+    /// B 16d (octahedral), O 32e with u = 0.2625
+    pub fn spinel() -> Self {
+        let generators = symmetry_ops(&[
+            "x,y+1/2,z+1/2",
+            "x+1/2,y,z+1/2",
+            "-x+3/4,-y+1/4,z+1/2",
+            "-x+1/4,y+1/2,-z+3/4",
+            "z,x,y",
+            "y+3/4,x+1/4,-z+1/2",
+            "-x,-y,-z",
+        ]);
+        let u = 0.2625;
+        Self::build("spinel", 227, vec![
+            site("8a", 4, false, None, orbit(&generators, [0.125, 0.125, 0.125])),
+            site("16d", 6, false, None, orbit(&generators, [0.5, 0.5, 0.5])),
+            site("32e", 4, true, Some("O"), orbit(&generators, [u, u, u])),
+        ])
+    }
+
+    /// A3B2C3O12 garnet (Y3Al2Al3O12 = YAG): A 24c (dodecahedral),
+    /// B 16a (octahedral), C 24d (tetrahedral), O 96h
+    pub fn garnet() -> Self {
+        let generators = symmetry_ops(&[
+            "x+1/2,y+1/2,z+1/2",
+            "-x+1/2,-y,z+1/2",
+            "-x,y+1/2,-z+1/2",
+            "z,x,y",
+            "y+3/4,x+1/4,-z+1/4",
+            "-x,-y,-z",
+        ]);
+        Self::build("garnet", 230, vec![
+            site("24c", 8, false, None, orbit(&generators, [0.125, 0.0, 0.25])),
+            site("16a", 6, false, None, orbit(&generators, [0.0, 0.0, 0.0])),
+            site("24d", 4, false, None, orbit(&generators, [0.375, 0.0, 0.25])),
+            site("96h", 4, true, Some("O"), orbit(&generators, [-0.0306, 0.0512, 0.1500])),
+        ])
+    }
+
+    /// AX rock salt (NaCl), both sites octahedral
+    pub fn rock_salt() -> Self {
+        let fcc = [[0.0, 0.0, 0.0], [0.0, 0.5, 0.5], [0.5, 0.0, 0.5], [0.5, 0.5, 0.0]];
+        Self::build("rock-salt", 225, vec![
+            site("4a", 6, false, None, fcc.to_vec()),
+            site("4b", 6, true, None, fcc.iter().map(|p| wrap([p[0] + 0.5, p[1], p[2]])).collect()),
+        ])
+    }
+
+    /// AX2 fluorite (CaF2): A 4a (cubic, CN 8), X 8c (tetrahedral)
+    pub fn fluorite() -> Self {
+        let fcc = [[0.0, 0.0, 0.0], [0.0, 0.5, 0.5], [0.5, 0.0, 0.5], [0.5, 0.5, 0.0]];
+        let anions = fcc
+            .iter()
+            .flat_map(|p| [0.25, 0.75].map(|d| wrap([p[0] + d, p[1] + d, p[2] + d])))
+            .collect();
+        Self::build("fluorite", 225, vec![
+            site("4a", 8, false, None, fcc.to_vec()),
+            site("8c", 4, true, None, anions),
+        ])
+    }
+
+    /// All built-in prototypes
+    pub fn library() -> Vec<Prototype> {
+        vec![Self::perovskite(), Self::spinel(), Self::garnet(), Self::rock_salt(), Self::fluorite()]
+    }
+
+    /// Look up a prototype by its LIRS name (also accepts "rock_salt"
+    /// etc.), ICSD structure type ("Spinel#Al2MgO4", "Spinel") or Pearson
+    /// symbol ("cF56")
+    pub fn by_name(name: &str) -> Option<Prototype> {
+        let lowered = name.to_ascii_lowercase().replace('_', "-");
+        let info = PROTOTYPE_TABLE.iter().find(|info| {
+            info.name == lowered
+                || info.structure_type.eq_ignore_ascii_case(name)
+                || info.structure_type.split('#').next().is_some_and(|n| n.eq_ignore_ascii_case(name))
+                || info.pearson_symbol == name
+        })?;
+        Self::library().into_iter().find(|p| p.name == info.name)
+    }
+
+    /// Reference data from [`PROTOTYPE_TABLE`]
+    pub fn info(&self) -> Option<&'static PrototypeInfo> {
+        PROTOTYPE_TABLE.iter().find(|info| info.name == self.name)
+    }
+
+    /// Sum of ionic charges over the cell; zero when charge balanced
+    pub fn net_charge(&self, ions: &[Ion]) -> i32 {
+        self.sites.iter().zip(ions).map(|(s, ion)| s.multiplicity() as i32 * ion.charge as i32).sum()
+    }
+
+    /// Goldschmidt tolerance factor of a perovskite decoration
+    pub fn tolerance_factor(&self, ions: &[Ion]) -> Option<f64> {
+        if self.name != "perovskite" || ions.len() != 3 {
+            return None;
+        }
+        Some(goldschmidt_tolerance(
+            ions[0].radius(self.sites[0].coordination)?,
+            ions[1].radius(self.sites[1].coordination)?,
+            ions[2].radius(self.sites[2].coordination)?,
+        ))
+    }
+
+    fn anion_radius(&self, ions: &[Ion]) -> Option<f64> {
+        let index = self.sites.iter().position(|s| s.anion)?;
+        ions.get(index)?.radius(self.sites[index].coordination)
+    }
+
+    /// Whether an ion fits a site's sign, fixed element and radius window
+    fn fits_site(&self, index: usize, ion: &Ion, anion_radius: f64) -> Option<f64> {
+        let site = &self.sites[index];
+        if site.anion != (ion.charge < 0) || site.fixed.is_some_and(|e| e != ion.element) {
+            return None;
+        }
+        if site.anion {
+            return Some(1.0);
+        }
+        let ratio = ion.radius(site.coordination)? / anion_radius;
+        let (lo, hi) = ratio_window(site.coordination);
+        if ratio < lo || ratio > hi {
+            return None;
+        }
+        // Gaussian preference for the middle of the window
+        let center = 0.5 * (lo + hi);
+        let half = 0.5 * (hi - lo);
+        Some((-((ratio - center) / half).powi(2)).exp())
+    }
+
+    /// Plausibility score in (0, 1] of a decoration, or None when it is not
+    /// charge balanced, an ion falls outside its radius window, or a
+    /// perovskite tolerance factor leaves `tolerance`
+    ///
+    /// Each site is its own Wyckoff set, so one element may decorate
+    /// several of them (Y3Al2Al3O12, Fe2+Fe3+2O4).
+    pub fn plausibility(&self, ions: &[Ion], tolerance: (f64, f64)) -> Option<f64> {
+        if ions.len() != self.sites.len() || self.net_charge(ions) != 0 {
+            return None;
+        }
+        let r_x = self.anion_radius(ions)?;
+        let mut score = 1.0;
+        for (i, ion) in ions.iter().enumerate() {
+            score *= self.fits_site(i, ion, r_x)?;
+        }
+        if let Some(t) = self.tolerance_factor(ions) {
+            if t < tolerance.0 || t > tolerance.1 {
+                return None;
+            }
+            score *= (-((t - 0.95) / 0.1).powi(2)).exp();
+        }
+        Some(score)
+    }
+
+    /// All plausible decorations from an element pool, best first
+    ///
+    /// Every oxidation state with a tabulated radius is tried on every site,
+    /// including elements already placed on another site.
+    pub fn enumerate(&self, pool: &[String], tolerance: (f64, f64)) -> Vec<(Vec<Ion>, f64)> {
+        let ions: Vec<Ion> = pool
+            .iter()
+            .flat_map(|e| elements::oxidation_states(e).into_iter().map(move |q| Ion::new(e.clone(), q)))
+            .collect();
+        let Some(anion_index) = self.sites.iter().position(|s| s.anion) else {
+            return Vec::new();
+        };
+
+        let mut found = Vec::new();
+        let anions = ions.iter().filter(|ion| self.fits_site(anion_index, ion, 1.0).is_some());
+        for anion in anions {
+            let Some(r_x) = anion.radius(self.sites[anion_index].coordination) else {
+                continue;
+            };
+            let choices: Vec<Vec<Ion>> = (0..self.sites.len())
+                .map(|i| {
+                    if i == anion_index {
+                        vec![anion.clone()]
+                    } else {
+                        ions.iter().filter(|ion| self.fits_site(i, ion, r_x).is_some()).cloned().collect()
+                    }
+                })
+                .collect();
+
+            let mut current = Vec::with_capacity(self.sites.len());
+            self.collect_decorations(&choices, &mut current, tolerance, &mut found);
+        }
+
+        found.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| self.formula_key(&a.0).cmp(&self.formula_key(&b.0)))
+        });
+        found
+    }
+
+    fn collect_decorations(
+        &self,
+        choices: &[Vec<Ion>],
+        current: &mut Vec<Ion>,
+        tolerance: (f64, f64),
+        found: &mut Vec<(Vec<Ion>, f64)>,
+    ) {
+        let depth = current.len();
+        if depth == choices.len() {
+            if let Some(score) = self.plausibility(current, tolerance) {
+                found.push((current.clone(), score));
+            }
+            return;
+        }
+        for ion in &choices[depth] {
+            current.push(ion.clone());
+            self.collect_decorations(choices, current, tolerance, found);
+            current.pop();
+        }
+    }
+
+    fn formula_key(&self, ions: &[Ion]) -> String {
+        ions.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(" ")
+    }
+
+    /// Find the prototype and charge-balanced decoration matching a formula
+    ///
+    /// Among several assignments the most plausible one wins.
+    pub fn identify(formula: &str) -> Option<(Prototype, Vec<Ion>)> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for (element, count) in Composition::parse(formula).ok()?.integer_counts().ok()? {
+            *counts.entry(element).or_insert(0) += count;
+        }
+        let pool: Vec<String> = counts.keys().cloned().collect();
+
+        let mut best: Option<(Prototype, Vec<Ion>, f64)> = None;
+        for prototype in Self::library() {
+            // Elements may share prototype sites, but every element needs one
+            if prototype.sites.len() < counts.len() {
+                continue;
+            }
+            for (ions, score) in prototype.enumerate(&pool, (0.0, f64::INFINITY)) {
+                if prototype.composition(&ions) == reduce(&counts) && best.as_ref().map_or(true, |b| score > b.2) {
+                    best = Some((prototype.clone(), ions, score));
+                }
+            }
+        }
+        best.map(|(p, ions, _)| (p, ions))
+    }
+
+    /// Reduced element counts of a decoration (one formula unit)
+    pub fn composition(&self, ions: &[Ion]) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for (site, ion) in self.sites.iter().zip(ions) {
+            *counts.entry(ion.element.clone()).or_insert(0) += site.multiplicity();
+        }
+        reduce(&counts)
+    }
+
+    /// Formula from the prototype's LIRS macro, e.g. "(spinel :Mg :Al)"
+    ///
+    /// An element on several sites is merged and the counts reduced, in
+    /// site order ("Fe3O4" rather than "FeFe2O4").
+    pub fn formula(&self, ions: &[Ion]) -> Result<String> {
+        if (1..ions.len()).any(|i| ions[..i].iter().any(|o| o.element == ions[i].element)) {
+            let counts = self.composition(ions);
+            let mut written: Vec<&str> = Vec::new();
+            let mut formula = String::new();
+            for ion in ions {
+                if written.contains(&ion.element.as_str()) {
+                    continue;
+                }
+                written.push(&ion.element);
+                formula.push_str(&ion.element);
+                match counts[&ion.element] {
+                    1 => {}
+                    n => formula.push_str(&n.to_string()),
+                }
+            }
+            return Ok(formula);
+        }
+        let args: Vec<String> = self
+            .sites
+            .iter()
+            .zip(ions)
+            .filter(|(site, _)| site.fixed.is_none())
+            .map(|(_, ion)| format!(":{}", ion.element))
+            .collect();
+        let expression = format!("({} {})", self.name, args.join(" "));
+        match LIRS::new().eval_last(&expression).map_err(Error::computation)? {
+            SExpr::Atom(Atom::String(formula)) => Ok(formula),
+            other => Err(Error::computation(format!("{} evaluated to {}", expression, other))),
+        }
+    }
+
+    /// Cubic lattice parameter (Å) matching cation-anion bonds to the sum
+    /// of Shannon radii, averaged over cation sites
+    pub fn lattice_parameter(&self, ions: &[Ion]) -> Option<f64> {
+        let r_x = self.anion_radius(ions)?;
+        let mut total = 0.0;
+        let mut n = 0;
+        for (i, (site, ion)) in self.sites.iter().zip(ions).enumerate() {
+            if site.anion {
+                continue;
+            }
+            total += (ion.radius(site.coordination)? + r_x) / self.bond_lengths[i];
+            n += 1;
+        }
+        (n > 0).then(|| total / n as f64)
+    }
+
+    /// Full conventional cell of a decoration
+    pub fn decorate(&self, ions: &[Ion]) -> Result<Structure> {
+        if ions.len() != self.sites.len() {
+            return Err(Error::invalid_input(format!(
+                "{} needs {} ions, got {}",
+                self.name,
+                self.sites.len(),
+                ions.len()
+            )));
+        }
+        let a = self.lattice_parameter(ions).ok_or_else(|| {
+            Error::invalid_input(format!("Missing ionic radii for {} decoration", self.name))
+        })?;
+
+        let sites = self
+            .sites
+            .iter()
+            .zip(ions)
+            .flat_map(|(site, ion)| {
+                site.positions.iter().map(move |p| Site {
+                    element: ion.element.clone(),
+                    coords: *p,
+                    magmom: None,
+                    occupancy: 1.0,
+                })
+            })
+            .collect();
+
+        Ok(Structure {
+            lattice: [[a, 0.0, 0.0], [0.0, a, 0.0], [0.0, 0.0, a]],
+            sites,
+            space_group: Some(self.space_group),
+            crystal_system: Some(CrystalSystem::Cubic),
+        })
+    }
+}
+
+fn site(
+    wyckoff: &'static str,
+    coordination: u8,
+    anion: bool,
+    fixed: Option<&'static str>,
+    positions: Vec<[f64; 3]>,
+) -> PrototypeSite {
+    PrototypeSite { wyckoff, coordination, anion, fixed, positions }
+}
+
+fn reduce(counts: &HashMap<String, usize>) -> HashMap<String, usize> {
+    fn gcd(a: usize, b: usize) -> usize {
+        if b == 0 { a } else { gcd(b, a % b) }
+    }
+    let divisor = counts.values().fold(0, |g, &c| gcd(g, c)).max(1);
+    counts.iter().map(|(e, c)| (e.clone(), c / divisor)).collect()
+}
+
+// ============================================================================
+// SYMMETRY EXPANSION
+// ============================================================================
+
+type SymmetryOp = ([[f64; 3]; 3], [f64; 3]);
+
+/// Parse operations written as in the International Tables ("-x+1/2,y,z")
+fn symmetry_ops(ops: &[&str]) -> Vec<SymmetryOp> {
+    ops.iter().map(|op| parse_op(op)).collect()
+}
+
+fn parse_op(op: &str) -> SymmetryOp {
+    let mut rotation = [[0.0; 3]; 3];
+    let mut translation = [0.0; 3];
+    for (row, component) in op.split(',').enumerate() {
+        let mut sign = 1.0;
+        let mut number = String::new();
+        let flush = |number: &mut String, sign: f64, translation: &mut f64| {
+            if !number.is_empty() {
+                let value = match number.split_once('/') {
+                    Some((n, d)) => n.parse::<f64>().unwrap() / d.parse::<f64>().unwrap(),
+                    None => number.parse::<f64>().unwrap(),
+                };
+                *translation += sign * value;
+                number.clear();
+            }
+        };
+        for c in component.trim().chars() {
+            match c {
+                '+' | '-' => {
+                    flush(&mut number, sign, &mut translation[row]);
+                    sign = if c == '-' { -1.0 } else { 1.0 };
+                }
+                'x' | 'y' | 'z' => {
+                    rotation[row][(c as u8 - b'x') as usize] = sign;
+                    sign = 1.0;
+                }
+                _ => number.push(c),
+            }
+        }
+        flush(&mut number, sign, &mut translation[row]);
+    }
+    (rotation, translation)
+}
+
+fn wrap(p: [f64; 3]) -> [f64; 3] {
+    p.map(|x| {
+        let w = x.rem_euclid(1.0);
+        if (1.0 - w) < 1e-9 { 0.0 } else { w }
+    })
+}
+
+/// Distance between fractional points of a unit cube under periodicity
+fn periodic_distance(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    (0..3)
+        .map(|k| {
+            let d = (a[k] - b[k]).rem_euclid(1.0);
+            d.min(1.0 - d).powi(2)
+        })
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// Orbit of a point under the group generated by `generators`
+fn orbit(generators: &[SymmetryOp], seed: [f64; 3]) -> Vec<[f64; 3]> {
+    let mut points = vec![wrap(seed)];
+    let mut frontier = 0;
+    while frontier < points.len() {
+        let p = points[frontier];
+        frontier += 1;
+        for (rotation, translation) in generators {
+            let image = wrap([0, 1, 2].map(|r| {
+                (0..3).map(|c| rotation[r][c] * p[c]).sum::<f64>() + translation[r]
+            }));
+            if !points.iter().any(|q| periodic_distance(q, &image) < 1e-6) {
+                points.push(image);
+            }
+        }
+    }
+    points
+}
+
+// ============================================================================
+// SUBSTITUTION PROBABILITIES
+// ============================================================================
+
+/// One entry of a substitution table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubstitutionPair {
+    pub from: Ion,
+    pub to: Ion,
+    pub probability: f64,
+}
+
+/// Probability that one ion substitutes for another in a known structure
+///
+/// Explicit pairs (e.g. mined from the ICSD as in Hautier et al. 2011)
+/// take precedence in either direction. Other pairs fall back to a
+/// similarity prior: `0.5·exp(-(Δr/0.25)²)·exp(-(Δχ)²)` with radii at
+/// CN 6, scaled by 0.1 when the charges differ.
+#[derive(Debug, Clone, Default)]
+pub struct SubstitutionTable {
+    pairs: HashMap<(Ion, Ion), f64>,
+}
+
+impl SubstitutionTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_pair(mut self, from: Ion, to: Ion, probability: f64) -> Self {
+        self.pairs.insert((from, to), probability);
+        self
+    }
+
+    /// Load pairs from a JSON array of [`SubstitutionPair`]s
+    pub fn from_json(json: &str) -> Result<Self> {
+        let pairs: Vec<SubstitutionPair> = serde_json::from_str(json)?;
+        Ok(Self {
+            pairs: pairs.into_iter().map(|p| ((p.from, p.to), p.probability)).collect(),
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        let mut pairs: Vec<SubstitutionPair> = self
+            .pairs
+            .iter()
+            .map(|((from, to), &probability)| SubstitutionPair { from: from.clone(), to: to.clone(), probability })
+            .collect();
+        pairs.sort_by_key(|p| (p.from.to_string(), p.to.to_string()));
+        Ok(serde_json::to_string_pretty(&pairs)?)
+    }
+
+    pub fn probability(&self, from: &Ion, to: &Ion) -> f64 {
+        if from == to {
+            return 1.0;
+        }
+        if let Some(&p) = self
+            .pairs
+            .get(&(from.clone(), to.clone()))
+            .or_else(|| self.pairs.get(&(to.clone(), from.clone())))
+        {
+            return p;
+        }
+
+        let (Some(r_from), Some(r_to)) = (from.radius(6), to.radius(6)) else {
+            return 0.0;
+        };
+        let chi = |e: &str| elements::element(e).and_then(|e| e.electronegativity).unwrap_or(0.0);
+        let d_chi = chi(&from.element) - chi(&to.element);
+        let p = 0.5 * (-((r_from - r_to) / 0.25).powi(2)).exp() * (-d_chi.powi(2)).exp();
+        if from.charge == to.charge { p } else { 0.1 * p }
+    }
+
+    /// Candidates that substitute for `from` with at least `threshold`
+    /// probability, most likely first
+    pub fn substitutes(&self, from: &Ion, candidates: &[Ion], threshold: f64) -> Vec<(Ion, f64)> {
+        let mut found: Vec<(Ion, f64)> = candidates
+            .iter()
+            .map(|to| (to.clone(), self.probability(from, to)))
+            .filter(|(_, p)| *p >= threshold)
+            .collect();
+        found.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ions(spec: &[&str]) -> Vec<Ion> {
+        spec.iter().map(|s| Ion::parse(s).unwrap()).collect()
+    }
+
+    fn min_anion_distance(structure: &Structure, site_index: usize) -> f64 {
+        structure
+            .neighbors(site_index, 3.5)
+            .iter()
+            .filter(|n| structure.sites[n.index].element == "O")
+            .map(|n| n.distance)
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    #[test]
+    fn test_prototype_cells() {
+        let spinel = Prototype::spinel();
+        let counts: Vec<usize> = spinel.sites.iter().map(|s| s.multiplicity()).collect();
+        assert_eq!(counts, vec![8, 16, 32]);
+
+        let garnet = Prototype::garnet();
+        let counts: Vec<usize> = garnet.sites.iter().map(|s| s.multiplicity()).collect();
+        assert_eq!(counts, vec![24, 16, 24, 96]);
+
+        // YAG at its experimental lattice parameter: Y-O 2.30, Al-O 1.94 / 1.76 Å
+        let mut yag = garnet.decorate(&ions(&["Y3+", "Al3+", "Ga3+", "O2-"])).unwrap();
+        yag.lattice = [[12.0, 0.0, 0.0], [0.0, 12.0, 0.0], [0.0, 0.0, 12.0]];
+        assert!((min_anion_distance(&yag, 0) - 2.30).abs() < 0.05);
+        assert!((min_anion_distance(&yag, 24) - 1.94).abs() < 0.05);
+        assert!((min_anion_distance(&yag, 40) - 1.76).abs() < 0.05);
+
+        let mgal2o4 = spinel.decorate(&ions(&["Mg2+", "Al3+", "O2-"])).unwrap();
+        assert_eq!(mgal2o4.sites.len(), 56);
+        let a = mgal2o4.lattice[0][0];
+        assert!((a - 8.08).abs() < 0.3, "a = {}", a);
+        assert_eq!(spinel.formula(&ions(&["Mg2+", "Al3+", "O2-"])).unwrap(), "MgAl2O4");
+    }
+
+    #[test]
+    fn test_perovskite_rules() {
+        let perovskite = Prototype::perovskite();
+        let srtio3 = ions(&["Sr2+", "Ti4+", "O2-"]);
+        let t = perovskite.tolerance_factor(&srtio3).unwrap();
+        assert!((t - 1.0).abs() < 0.02, "t = {}", t);
+        assert!(perovskite.plausibility(&srtio3, (0.8, 1.1)).is_some());
+        // Radii sums overestimate the measured 3.905 Å by about 3%
+        assert!((perovskite.lattice_parameter(&srtio3).unwrap() - 3.905).abs() < 0.15);
+
+        // Charge imbalance and a too-small A cation are rejected
+        assert!(perovskite.plausibility(&ions(&["Sr2+", "Ti3+", "O2-"]), (0.8, 1.1)).is_none());
+        assert!(perovskite.plausibility(&ions(&["Mg2+", "Ti4+", "O2-"]), (0.8, 1.1)).is_none());
+
+        let pool: Vec<String> = ["Ca", "Sr", "Ba", "Ti", "Zr", "O"].iter().map(|s| s.to_string()).collect();
+        let found = perovskite.enumerate(&pool, (0.8, 1.1));
+        let formulas: Vec<String> = found.iter().map(|(i, _)| perovskite.formula(i).unwrap()).collect();
+        assert!(formulas.contains(&"SrTiO3".to_string()));
+        assert!(formulas.contains(&"BaZrO3".to_string()));
+        assert!(!formulas.iter().any(|f| f.starts_with("Ti")));
+
+        let (prototype, decoration) = Prototype::identify("CaTiO3").unwrap();
+        assert_eq!(prototype.name, "perovskite");
+        assert_eq!(decoration, ions(&["Ca2+", "Ti4+", "O2-"]));
+        assert_eq!(Prototype::identify("Y3Al2Ga3O12").map(|(p, _)| p.name), Some("garnet"));
+    }
+
+    #[test]
+    fn test_element_on_several_sites() {
+        // YAG puts Al3+ on both the octahedral and the tetrahedral site
+        let (garnet, yag) = Prototype::identify("Y3Al5O12").unwrap();
+        assert_eq!(garnet.name, "garnet");
+        assert_eq!(yag, ions(&["Y3+", "Al3+", "Al3+", "O2-"]));
+        assert_eq!(garnet.formula(&yag).unwrap(), "Y3Al5O12");
+
+        // Magnetite and Co3O4: M2+ tetrahedral, M3+ octahedral
+        for (formula, expected) in [("Fe3O4", ["Fe2+", "Fe3+", "O2-"]), ("Co3O4", ["Co2+", "Co3+", "O2-"])] {
+            let (spinel, decoration) = Prototype::identify(formula).unwrap();
+            assert_eq!(spinel.name, "spinel");
+            assert_eq!(decoration, ions(&expected));
+            assert_eq!(spinel.formula(&decoration).unwrap(), formula);
+            assert_eq!(spinel.decorate(&decoration).unwrap().sites.len(), 56);
+        }
+
+        let found = Prototype::spinel().enumerate(&["Co".to_string(), "O".to_string()], (0.0, f64::INFINITY));
+        assert!(found.iter().any(|(i, _)| *i == ions(&["Co2+", "Co3+", "O2-"])));
+    }
+
+    #[test]
+    fn test_prototype_table() {
+        for info in PROTOTYPE_TABLE {
+            let prototype = Prototype::by_name(info.name).unwrap();
+            assert_eq!(prototype.info(), Some(info));
+            let sites: usize = prototype.sites.iter().map(|s| s.multiplicity()).sum();
+            assert!(info.pearson_symbol.ends_with(&sites.to_string()), "{} has {} sites", info.name, sites);
+            let (identified, _) = Prototype::identify(info.example).unwrap();
+            assert_eq!(identified.name, info.name);
+        }
+        assert_eq!(Prototype::by_name("Spinel#Al2MgO4").unwrap().name, "spinel");
+        assert_eq!(Prototype::by_name("garnet").unwrap().space_group, 230);
+        assert_eq!(Prototype::by_name("cP5").unwrap().name, "perovskite");
+        assert!(Prototype::by_name("cP6").is_none());
+    }
+
+    #[test]
+    fn test_substitution_table() {
+        let table = SubstitutionTable::new().with_pair(Ion::parse("Fe3+").unwrap(), Ion::parse("Ga3+").unwrap(), 0.4);
+        let fe = Ion::parse("Fe3+").unwrap();
+        assert_eq!(table.probability(&Ion::parse("Ga3+").unwrap(), &fe), 0.4);
+        assert_eq!(fe.to_string(), "Fe3+");
+
+        // Prior: Cr3+ is a closer match for Fe3+ than Ba2+
+        let candidates = ions(&["Ba2+", "Cr3+"]);
+        let ranked = table.substitutes(&fe, &candidates, 0.0);
+        assert_eq!(ranked[0].0.element, "Cr");
+        assert!(ranked[1].1 < 0.01);
+
+        let reloaded = SubstitutionTable::from_json(&table.to_json().unwrap()).unwrap();
+        assert_eq!(reloaded.probability(&fe, &Ion::parse("Ga3+").unwrap()), 0.4);
+    }
+}
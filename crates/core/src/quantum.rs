@@ -22,6 +22,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
+use crate::composition::Composition;
 
 // ============================================================================
 // CORE TYPES
@@ -48,6 +49,24 @@ pub enum CalculationType {
     ElasticConstants,    // Elastic tensor
 }
 
+impl std::str::FromStr for CalculationType {
+    type Err = String;
+
+    /// Parse names such as "scf", "relax", "band_structure" or "DOS"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace(['-', ' '], "_").as_str() {
+            "single_point" | "singlepoint" | "scf" | "static" => Ok(CalculationType::SinglePoint),
+            "geometry_opt" | "geometryopt" | "relax" | "relaxation" => Ok(CalculationType::GeometryOpt),
+            "band_structure" | "bandstructure" | "bands" => Ok(CalculationType::BandStructure),
+            "dos" => Ok(CalculationType::DOS),
+            "molecular_dynamics" | "md" => Ok(CalculationType::MolecularDynamics),
+            "phonon_dispersion" | "phonon" | "phonons" => Ok(CalculationType::PhononDispersion),
+            "elastic_constants" | "elastic" => Ok(CalculationType::ElasticConstants),
+            _ => Err(format!("Unknown calculation type: {}", s)),
+        }
+    }
+}
+
 /// Exchange-correlation functional
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum XCFunctional {
@@ -232,6 +251,12 @@ impl Structure {
         }
         comp
     }
+
+    /// Reduced Hill formula of the atoms, independent of `formula`
+    pub fn reduced_formula(&self) -> crate::Result<String> {
+        let composition = Composition::from_amounts(self.atoms.iter().map(|a| (a.element.as_str(), 1.0)))?;
+        Ok(composition.reduced_formula())
+    }
 }
 
 // ============================================================================
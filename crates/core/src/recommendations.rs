@@ -4,8 +4,12 @@
 //! - User preferences and history
 //! - Application requirements
 //! - Property constraints
-//! - Collaborative filtering
+//! - Collaborative filtering (matrix factorization blended with embeddings)
 
+use crate::collaborative_filtering::{
+    evaluate_ranking, split_held_out, FactorizationConfig, FactorizationModel, HybridScorer, RankingMetrics,
+};
+use crate::economics::{EconomicFilter, MaterialEconomics};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -29,6 +33,10 @@ pub struct RecommendationContext {
 
     /// Budget constraints
     pub budget: Option<BudgetConstraint>,
+
+    /// Hard limits on raw material cost, supply risk and sustainability
+    #[serde(default)]
+    pub economics: Option<EconomicFilter>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,7 +80,7 @@ pub struct MaterialRecommendation {
     pub availability: AvailabilityScore,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AvailabilityScore {
     High,
     Medium,
@@ -83,25 +91,36 @@ pub enum AvailabilityScore {
 /// Recommendation engine
 pub struct RecommendationEngine {
     /// User interaction history
-    user_history: Arc<RwLock<HashMap<Uuid, Vec<MaterialInteraction>>>>,
+    user_history: Arc<RwLock<HashMap<Uuid, Vec<InteractionRecord>>>>,
 
     /// Material metadata cache
     material_metadata: Arc<RwLock<HashMap<Uuid, MaterialMetadata>>>,
 
     /// Collaborative filtering model
     similarity_matrix: Arc<RwLock<HashMap<(Uuid, Uuid), f64>>>,
+
+    /// Implicit-feedback factorization, once trained
+    factorization: Arc<RwLock<Option<Arc<FactorizationModel>>>>,
+
+    /// Material embeddings for content similarity
+    content_vectors: Arc<RwLock<HashMap<Uuid, Vec<f64>>>>,
+
+    /// Share of the factorization in hybrid scores
+    cf_weight: f64,
 }
 
-#[derive(Debug, Clone)]
-struct MaterialInteraction {
-    material_id: Uuid,
-    interaction_type: InteractionType,
-    timestamp: chrono::DateTime<chrono::Utc>,
-    rating: Option<f64>,
+/// One user interaction, as logged and persisted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InteractionRecord {
+    pub user_id: Uuid,
+    pub material_id: Uuid,
+    pub interaction_type: InteractionType,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub rating: Option<f64>,
 }
 
-#[derive(Debug, Clone)]
-enum InteractionType {
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InteractionType {
     View,
     Download,
     Favorite,
@@ -109,6 +128,45 @@ enum InteractionType {
     Rated(f64),
 }
 
+impl InteractionType {
+    /// Parse the names used by `record_interaction`; unknown names are
+    /// views, and "rated" needs a finite rating
+    pub fn parse(name: &str, rating: Option<f64>) -> Result<Self, String> {
+        Ok(match name {
+            "view" => InteractionType::View,
+            "download" => InteractionType::Download,
+            "favorite" => InteractionType::Favorite,
+            "used" => InteractionType::Used,
+            "rated" => match rating {
+                Some(rating) if rating.is_finite() => InteractionType::Rated(rating),
+                _ => return Err(format!("A rated interaction needs a finite rating, got {:?}", rating)),
+            },
+            _ => InteractionType::View,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            InteractionType::View => "view",
+            InteractionType::Download => "download",
+            InteractionType::Favorite => "favorite",
+            InteractionType::Used => "used",
+            InteractionType::Rated(_) => "rated",
+        }
+    }
+
+    /// Implicit-feedback strength; ratings count by their 0-5 value
+    pub fn weight(&self) -> f64 {
+        match self {
+            InteractionType::View => 1.0,
+            InteractionType::Download => 2.0,
+            InteractionType::Favorite => 4.0,
+            InteractionType::Used => 5.0,
+            InteractionType::Rated(rating) => rating.clamp(0.0, 5.0),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MaterialMetadata {
     pub material_id: Uuid,
@@ -127,9 +185,18 @@ impl RecommendationEngine {
             user_history: Arc::new(RwLock::new(HashMap::new())),
             material_metadata: Arc::new(RwLock::new(HashMap::new())),
             similarity_matrix: Arc::new(RwLock::new(HashMap::new())),
+            factorization: Arc::new(RwLock::new(None)),
+            content_vectors: Arc::new(RwLock::new(HashMap::new())),
+            cf_weight: 0.7,
         }
     }
 
+    /// Share of the factorization part in hybrid scores (default 0.7)
+    pub fn with_cf_weight(mut self, cf_weight: f64) -> Self {
+        self.cf_weight = cf_weight.clamp(0.0, 1.0);
+        self
+    }
+
     /// Get personalized recommendations
     pub async fn get_recommendations(
         &self,
@@ -166,6 +233,15 @@ impl RecommendationEngine {
         let metadata = self.material_metadata.read().await;
         let material = metadata.get(&material_id)?;
 
+        // Raw material economics from the composition
+        let economics = MaterialEconomics::from_formula(&material.formula).ok();
+        if let Some(ref filter) = context.economics {
+            match economics {
+                Some(ref e) if filter.accepts(e) => {}
+                _ => return None,
+            }
+        }
+
         let mut score = 0.0;
         let mut reasons = Vec::new();
         let mut pros = Vec::new();
@@ -194,18 +270,41 @@ impl RecommendationEngine {
             }
         }
 
+        let toxic_elements = economics.as_ref().map(|e| e.toxic_elements.clone()).unwrap_or_default();
         if context.preferences.avoid_toxic_elements {
-            if material.toxicity_score > 0.5 {
+            if material.toxicity_score > 0.5 || !toxic_elements.is_empty() {
                 score -= 1.0;
-                cons.push("Contains toxic elements".to_string());
+                if toxic_elements.is_empty() {
+                    cons.push("Contains toxic elements".to_string());
+                } else {
+                    cons.push(format!("Contains toxic elements: {}", toxic_elements.join(", ")));
+                }
             } else {
                 pros.push("Low toxicity".to_string());
             }
         }
 
-        // Cost consideration
+        if let Some(ref e) = economics {
+            if context.preferences.prefer_common_elements {
+                if e.supply_risk < 0.33 {
+                    score += 0.5;
+                    pros.push("Abundant elements with diversified supply".to_string());
+                } else if e.supply_risk > 0.6 {
+                    score -= 0.5;
+                    cons.push(format!("High supply risk ({:.2})", e.supply_risk));
+                }
+            }
+            if context.preferences.prefer_cost_effective && e.is_complete() && e.cost_per_kg < 10.0 {
+                pros.push(format!("Low raw material cost (~{:.1} USD/kg)", e.cost_per_kg));
+            }
+        }
+
+        // Cost consideration; the composition's mass-weighted raw material
+        // cost when no estimate is known and every element is priced
+        let estimated_cost = material.cost_estimate
+            .or(economics.as_ref().filter(|e| e.is_complete()).map(|e| e.cost_per_kg));
         if let Some(ref budget) = context.budget {
-            if let Some(cost) = material.cost_estimate {
+            if let Some(cost) = estimated_cost {
                 if cost <= budget.max_cost_per_kg {
                     score += 1.0;
                     pros.push("Within budget".to_string());
@@ -228,14 +327,7 @@ impl RecommendationEngine {
         // Normalize score
         let recommendation_score = (score / 5.0).min(1.0).max(0.0);
 
-        // Determine availability
-        let availability = if material.popularity_score > 0.7 {
-            AvailabilityScore::High
-        } else if material.popularity_score > 0.4 {
-            AvailabilityScore::Medium
-        } else {
-            AvailabilityScore::Low
-        };
+        let availability = economics.as_ref().map_or(AvailabilityScore::Unknown, |e| e.availability());
 
         Some(MaterialRecommendation {
             material_id,
@@ -245,7 +337,7 @@ impl RecommendationEngine {
             reasons,
             pros,
             cons,
-            estimated_cost: material.cost_estimate,
+            estimated_cost,
             availability,
         })
     }
@@ -273,6 +365,18 @@ impl RecommendationEngine {
             }
         }
 
+        // Factorization blended with embedding similarity, when available
+        let model = self.factorization.read().await.clone();
+        let content = self.content_vectors.read().await;
+        if model.is_some() || !content.is_empty() {
+            let user_history = Self::weighted_history(history.get(&user_id).map(Vec::as_slice).unwrap_or(&[]));
+            let hybrid = HybridScorer { model: model.as_deref(), content: &content, cf_weight: self.cf_weight };
+            if let Some(score) = hybrid.score(user_id, &user_history, material_id) {
+                return score;
+            }
+        }
+        drop(content);
+
         // Find similar users and their preferences
         let similarity_matrix = self.similarity_matrix.read().await;
         let mut total_similarity = 0.0;
@@ -306,29 +410,132 @@ impl RecommendationEngine {
         interaction_type: String,
         rating: Option<f64>,
     ) -> Result<(), String> {
-        let mut history = self.user_history.write().await;
-
-        let interaction = MaterialInteraction {
+        self.record(InteractionRecord {
+            user_id,
             material_id,
-            interaction_type: match interaction_type.as_str() {
-                "view" => InteractionType::View,
-                "download" => InteractionType::Download,
-                "favorite" => InteractionType::Favorite,
-                "used" => InteractionType::Used,
-                "rated" => InteractionType::Rated(rating.unwrap_or(0.0)),
-                _ => InteractionType::View,
-            },
+            interaction_type: InteractionType::parse(&interaction_type, rating)?,
             timestamp: chrono::Utc::now(),
             rating,
-        };
+        }).await;
 
-        history.entry(user_id)
-            .or_insert_with(Vec::new)
-            .push(interaction);
+        Ok(())
+    }
 
+    /// Append an interaction to the log
+    pub async fn record(&self, record: InteractionRecord) {
+        self.user_history.write().await
+            .entry(record.user_id)
+            .or_default()
+            .push(record);
+    }
+
+    /// Replace the in-memory log, e.g. with interactions loaded from storage
+    pub async fn load_interactions(&self, records: Vec<InteractionRecord>) -> usize {
+        let count = records.len();
+        let mut history: HashMap<Uuid, Vec<InteractionRecord>> = HashMap::new();
+        for record in records {
+            history.entry(record.user_id).or_default().push(record);
+        }
+        for interactions in history.values_mut() {
+            interactions.sort_by_key(|r| r.timestamp);
+        }
+        *self.user_history.write().await = history;
+        count
+    }
+
+    /// Every logged interaction, oldest first
+    pub async fn interactions(&self) -> Vec<InteractionRecord> {
+        self.interactions_since(None).await
+    }
+
+    /// Interactions strictly after `since` (all when `None`), oldest first
+    pub async fn interactions_since(&self, since: Option<chrono::DateTime<chrono::Utc>>) -> Vec<InteractionRecord> {
+        let history = self.user_history.read().await;
+        let mut records: Vec<InteractionRecord> = history.values()
+            .flatten()
+            .filter(|r| since.map_or(true, |t| r.timestamp > t))
+            .cloned()
+            .collect();
+        records.sort_by_key(|r| (r.timestamp, r.user_id, r.material_id));
+        records
+    }
+
+    /// Train matrix factorization on the current log and use it for scoring
+    pub async fn train_factorization(&self, config: FactorizationConfig) -> Result<(), String> {
+        let records = self.interactions().await;
+        let model = FactorizationModel::fit(&records, config).map_err(|e| e.to_string())?;
+        *self.factorization.write().await = Some(Arc::new(model));
         Ok(())
     }
 
+    /// Material embeddings for the content half of hybrid scores, e.g. from
+    /// the embedding engine's cache
+    pub async fn set_content_vectors(&self, vectors: HashMap<Uuid, Vec<f64>>) {
+        *self.content_vectors.write().await = vectors;
+    }
+
+    /// Top `k` materials for a user from the hybrid scorer, skipping ones
+    /// they already interacted with
+    pub async fn recommend_for_user(&self, user_id: Uuid, k: usize) -> Vec<(Uuid, f64)> {
+        let history = self.user_history.read().await;
+        let user_history = Self::weighted_history(history.get(&user_id).map(Vec::as_slice).unwrap_or(&[]));
+        drop(history);
+
+        let model = self.factorization.read().await.clone();
+        let content = self.content_vectors.read().await;
+        let candidates = self.candidate_materials(model.as_deref(), &content).await;
+        let hybrid = HybridScorer { model: model.as_deref(), content: &content, cf_weight: self.cf_weight };
+        hybrid.recommend(user_id, &user_history, &candidates, k)
+    }
+
+    /// Hold out each user's latest interactions, train on the rest and
+    /// score hybrid top-`k` lists against them
+    pub async fn evaluate_offline(
+        &self,
+        config: FactorizationConfig,
+        test_fraction: f64,
+        k: usize,
+    ) -> Result<RankingMetrics, String> {
+        let records = self.interactions().await;
+        let (train, test) = split_held_out(&records, test_fraction);
+        let model = FactorizationModel::fit(&train, config).map_err(|e| e.to_string())?;
+        let content = self.content_vectors.read().await;
+        let candidates = self.candidate_materials(Some(&model), &content).await;
+
+        let mut histories: HashMap<Uuid, Vec<InteractionRecord>> = HashMap::new();
+        for record in &train {
+            histories.entry(record.user_id).or_default().push(record.clone());
+        }
+        let hybrid = HybridScorer { model: Some(&model), content: &content, cf_weight: self.cf_weight };
+        Ok(evaluate_ranking(&train, &test, k, |user, k| {
+            let history = Self::weighted_history(histories.get(&user).map(Vec::as_slice).unwrap_or(&[]));
+            hybrid.recommend(user, &history, &candidates, k).into_iter().map(|(id, _)| id).collect()
+        }))
+    }
+
+    /// Materials known from metadata, the factorization or embeddings, sorted
+    async fn candidate_materials(&self, model: Option<&FactorizationModel>, content: &HashMap<Uuid, Vec<f64>>) -> Vec<Uuid> {
+        let mut candidates: Vec<Uuid> = self.material_metadata.read().await.keys().copied().collect();
+        candidates.extend(content.keys());
+        if let Some(model) = model {
+            candidates.extend(model.materials());
+        }
+        candidates.sort();
+        candidates.dedup();
+        candidates
+    }
+
+    /// (material, summed interaction weight) for one user's log
+    fn weighted_history(interactions: &[InteractionRecord]) -> Vec<(Uuid, f64)> {
+        let mut weights: HashMap<Uuid, f64> = HashMap::new();
+        for interaction in interactions {
+            *weights.entry(interaction.material_id).or_insert(0.0) += interaction.interaction_type.weight();
+        }
+        let mut history: Vec<(Uuid, f64)> = weights.into_iter().collect();
+        history.sort_by_key(|(id, _)| *id);
+        history
+    }
+
     /// Add material metadata
     pub async fn add_material_metadata(
         &self,
@@ -367,8 +574,8 @@ impl RecommendationEngine {
     }
 
     fn calculate_user_similarity(
-        interactions1: &[MaterialInteraction],
-        interactions2: &[MaterialInteraction],
+        interactions1: &[InteractionRecord],
+        interactions2: &[InteractionRecord],
     ) -> f64 {
         let materials1: std::collections::HashSet<_> = interactions1.iter()
             .map(|i| i.material_id)
@@ -452,6 +659,7 @@ mod tests {
                 max_cost_per_kg: 50.0,
                 synthesis_complexity_max: 0.7,
             }),
+            economics: None,
         };
 
         let recommendations = engine.get_recommendations(
@@ -462,5 +670,98 @@ mod tests {
 
         assert!(!recommendations.is_empty());
         assert!(recommendations[0].recommendation_score > 0.0);
+
+        assert_eq!(InteractionType::parse("rated", Some(4.0)), Ok(InteractionType::Rated(4.0)));
+        assert_eq!(InteractionType::parse("bookmark", None), Ok(InteractionType::View));
+        assert!(InteractionType::parse("rated", None).is_err());
+        assert!(InteractionType::parse("rated", Some(f64::NAN)).is_err());
+        let user = Uuid::new_v4();
+        assert!(engine.record_interaction(user, material_id, "rated".to_string(), None).await.is_err());
+        assert!(engine.interactions().await.iter().all(|r| r.user_id != user));
+    }
+
+    #[tokio::test]
+    async fn test_economic_filters_and_availability() {
+        let engine = RecommendationEngine::new();
+        let mut ids = Vec::new();
+        for formula in ["Fe2O3", "PbTiO3", "PtO2", "Pm2O3"] {
+            let material_id = Uuid::new_v4();
+            ids.push(material_id);
+            engine.add_material_metadata(MaterialMetadata {
+                material_id,
+                formula: formula.to_string(),
+                popularity_score: 0.5,
+                average_rating: None,
+                applications: vec!["catalyst".to_string()],
+                cost_estimate: None,
+                environmental_impact: 0.3,
+                toxicity_score: 0.0,
+            }).await.unwrap();
+        }
+
+        let mut context = RecommendationContext {
+            user_id: None,
+            application: "catalyst".to_string(),
+            required_properties: HashMap::new(),
+            preferences: UserPreferences::default(),
+            budget: None,
+            economics: None,
+        };
+        let all = engine.get_recommendations(context.clone(), ids.clone(), 10).await.unwrap();
+        assert_eq!(all.len(), 4);
+        let hematite = all.iter().find(|r| r.formula == "Fe2O3").unwrap();
+        assert_eq!(hematite.availability, AvailabilityScore::High);
+        assert!(hematite.estimated_cost.unwrap() < 1.0);
+        // Unpriced promethium leaves the raw material cost unknown
+        let promethia = all.iter().find(|r| r.formula == "Pm2O3").unwrap();
+        assert_eq!(promethia.estimated_cost, None);
+        assert_eq!(promethia.availability, AvailabilityScore::Unknown);
+        let lead = all.iter().find(|r| r.formula == "PbTiO3").unwrap();
+        assert!(lead.cons.iter().any(|c| c.contains("Pb")));
+
+        context.economics = Some(EconomicFilter {
+            max_cost_per_kg: Some(100.0),
+            exclude_toxic: true,
+            ..Default::default()
+        });
+        let filtered = engine.get_recommendations(context, ids, 10).await.unwrap();
+        let formulas: Vec<&str> = filtered.iter().map(|r| r.formula.as_str()).collect();
+        assert_eq!(formulas, vec!["Fe2O3"]);
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_recommendations_and_offline_evaluation() {
+        let engine = RecommendationEngine::new();
+        let items: Vec<Uuid> = (0..12).map(|i| Uuid::from_u128(100 + i)).collect();
+        let start = chrono::Utc::now();
+        for u in 0..16u128 {
+            let group = (u % 2) as usize;
+            for step in 0..4 {
+                engine.record(InteractionRecord {
+                    user_id: Uuid::from_u128(u + 1),
+                    material_id: items[group * 6 + (u as usize + step) % 6],
+                    interaction_type: InteractionType::Used,
+                    timestamp: start + chrono::Duration::seconds(step as i64),
+                    rating: None,
+                }).await;
+            }
+        }
+        assert_eq!(engine.interactions().await.len(), 64);
+        assert_eq!(engine.interactions_since(Some(start)).await.len(), 48);
+
+        engine.train_factorization(FactorizationConfig::als(4)).await.unwrap();
+        let user = Uuid::from_u128(1);
+        let top = engine.recommend_for_user(user, 2).await;
+        assert_eq!(top.len(), 2);
+        assert!(top.iter().all(|(id, _)| items[..6].contains(id)));
+
+        let metrics = engine.evaluate_offline(FactorizationConfig::als(8), 0.25, 3).await.unwrap();
+        assert_eq!(metrics.users, 16);
+        assert!(metrics.recall_at_k > 0.5, "{metrics:?}");
+
+        // Restoring a log replaces the in-memory history
+        let restored = RecommendationEngine::new();
+        assert_eq!(restored.load_interactions(engine.interactions().await).await, 64);
+        assert_eq!(restored.interactions().await, engine.interactions().await);
     }
 }
@@ -0,0 +1,705 @@
+//! Structure Descriptors
+//!
+//! Fixed-length featurizations of periodic crystal structures, so that
+//! polymorphs with identical composition (rutile vs anatase TiO2, rock-salt
+//! vs CsCl-type) get different ML inputs:
+//! - Sine Coulomb matrix eigenvalues (Faber et al. 2015)
+//! - SOAP power spectrum with a Gaussian radial basis
+//! - Partial radial distribution function fingerprints
+//! - Local coordination statistics
+//!
+//! All descriptors implement [`StructureFeaturizer`] and operate on
+//! [`crate::material::Structure`] with periodic boundary conditions.
+
+use crate::elements;
+use crate::material::{Neighbor, Structure};
+use crate::{Error, Result};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Featurizer producing a fixed-length vector from a crystal structure
+pub trait StructureFeaturizer: Send + Sync {
+    /// Short identifier (used in feature names and logs)
+    fn name(&self) -> &str;
+
+    /// Names of the produced features, in order
+    fn feature_names(&self) -> Vec<String>;
+
+    /// Number of features produced
+    fn dim(&self) -> usize {
+        self.feature_names().len()
+    }
+
+    /// Compute the descriptor
+    fn featurize(&self, structure: &Structure) -> Result<Vec<f64>>;
+}
+
+fn check_structure(structure: &Structure) -> Result<()> {
+    if structure.sites.is_empty() {
+        return Err(Error::invalid_input("Structure has no sites"));
+    }
+    if structure.volume() < 1e-8 {
+        return Err(Error::invalid_input("Structure lattice is degenerate"));
+    }
+    Ok(())
+}
+
+fn atomic_number(symbol: &str) -> Result<f64> {
+    elements::element(symbol)
+        .map(|e| e.z as f64)
+        .ok_or_else(|| Error::invalid_input(format!("Unknown element: {}", symbol)))
+}
+
+/// Unordered species pairs (a <= b), or a single wildcard pair when no
+/// species list is configured
+fn species_pairs(species: &[String]) -> Vec<(Option<usize>, Option<usize>)> {
+    if species.is_empty() {
+        return vec![(None, None)];
+    }
+    let mut pairs = Vec::new();
+    for a in 0..species.len() {
+        for b in a..species.len() {
+            pairs.push((Some(a), Some(b)));
+        }
+    }
+    pairs
+}
+
+fn pair_label(species: &[String], pair: (Option<usize>, Option<usize>)) -> String {
+    match pair {
+        (Some(a), Some(b)) => format!("{}-{}", species[a], species[b]),
+        _ => "all".to_string(),
+    }
+}
+
+fn species_index(species: &[String], symbol: &str) -> Option<usize> {
+    species.iter().position(|s| s == symbol)
+}
+
+fn matches(species: &[String], filter: Option<usize>, symbol: &str) -> bool {
+    match filter {
+        None => true,
+        Some(idx) => species_index(species, symbol) == Some(idx),
+    }
+}
+
+// ============================================================================
+// SINE COULOMB MATRIX
+// ============================================================================
+
+/// Sorted eigenvalues of the sine Coulomb matrix, zero-padded to `max_atoms`
+#[derive(Debug, Clone)]
+pub struct SineCoulombMatrix {
+    pub max_atoms: usize,
+}
+
+impl SineCoulombMatrix {
+    pub fn new(max_atoms: usize) -> Self {
+        Self { max_atoms }
+    }
+
+    /// The full (unsorted, unpadded) sine Coulomb matrix
+    pub fn matrix(&self, structure: &Structure) -> Result<Vec<Vec<f64>>> {
+        check_structure(structure)?;
+
+        let z: Vec<f64> = structure
+            .sites
+            .iter()
+            .map(|s| atomic_number(&s.element))
+            .collect::<Result<_>>()?;
+        let n = z.len();
+        let mut m = vec![vec![0.0; n]; n];
+
+        for i in 0..n {
+            m[i][i] = 0.5 * z[i].powf(2.4);
+            for j in (i + 1)..n {
+                let df: Vec<f64> = (0..3)
+                    .map(|k| structure.sites[i].coords[k] - structure.sites[j].coords[k])
+                    .collect();
+                // φ = |Σ_k a_k sin²(π Δf_k)|
+                let mut v = [0.0; 3];
+                for (k, d) in df.iter().enumerate() {
+                    let s = (PI * d).sin().powi(2);
+                    for (vc, lc) in v.iter_mut().zip(&structure.lattice[k]) {
+                        *vc += s * lc;
+                    }
+                }
+                let phi = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt().max(1e-8);
+                m[i][j] = z[i] * z[j] / phi;
+                m[j][i] = m[i][j];
+            }
+        }
+
+        Ok(m)
+    }
+}
+
+impl Default for SineCoulombMatrix {
+    fn default() -> Self {
+        Self::new(50)
+    }
+}
+
+impl StructureFeaturizer for SineCoulombMatrix {
+    fn name(&self) -> &str {
+        "sine_coulomb_matrix"
+    }
+
+    fn feature_names(&self) -> Vec<String> {
+        (0..self.max_atoms).map(|i| format!("scm_eig_{}", i)).collect()
+    }
+
+    fn featurize(&self, structure: &Structure) -> Result<Vec<f64>> {
+        let m = self.matrix(structure)?;
+        let n = m.len();
+        let matrix = nalgebra::DMatrix::from_fn(n, n, |i, j| m[i][j]);
+
+        let mut eigenvalues: Vec<f64> = matrix.symmetric_eigenvalues().iter().copied().collect();
+        eigenvalues.sort_by(|a, b| b.abs().partial_cmp(&a.abs()).unwrap_or(std::cmp::Ordering::Equal));
+        eigenvalues.resize(self.max_atoms, 0.0);
+
+        Ok(eigenvalues)
+    }
+}
+
+// ============================================================================
+// SOAP POWER SPECTRUM
+// ============================================================================
+
+/// Smooth Overlap of Atomic Positions power spectrum, averaged over sites
+///
+/// Uses Gaussian radial basis functions with a cosine cutoff and evaluates
+/// p_{nn'l} through the spherical-harmonic addition theorem, i.e. as
+/// Legendre polynomials of neighbor-pair angles, so no explicit Y_lm are
+/// needed. This is the delta-density limit of SOAP in the angular channel.
+#[derive(Debug, Clone)]
+pub struct SoapDescriptor {
+    pub cutoff: f64,
+    pub n_max: usize,
+    pub l_max: usize,
+    /// Width of the radial Gaussians (Å)
+    pub sigma: f64,
+    /// Species channels; empty means species-agnostic
+    pub species: Vec<String>,
+}
+
+impl Default for SoapDescriptor {
+    fn default() -> Self {
+        Self {
+            cutoff: 5.0,
+            n_max: 4,
+            l_max: 4,
+            sigma: 0.5,
+            species: Vec::new(),
+        }
+    }
+}
+
+impl SoapDescriptor {
+    pub fn with_species(mut self, species: Vec<String>) -> Self {
+        self.species = species;
+        self
+    }
+
+    fn radial_centers(&self) -> Vec<f64> {
+        let n = self.n_max.max(1);
+        (0..n).map(|i| self.cutoff * (i + 1) as f64 / (n + 1) as f64).collect()
+    }
+
+    fn radial_basis(&self, r: f64, centers: &[f64]) -> Vec<f64> {
+        let fc = 0.5 * ((PI * r / self.cutoff).cos() + 1.0);
+        centers
+            .iter()
+            .map(|c| (-(r - c).powi(2) / (2.0 * self.sigma * self.sigma)).exp() * fc)
+            .collect()
+    }
+
+    fn legendre(l_max: usize, x: f64) -> Vec<f64> {
+        let mut p = vec![1.0; l_max + 1];
+        if l_max >= 1 {
+            p[1] = x;
+        }
+        for l in 1..l_max {
+            p[l + 1] = ((2 * l + 1) as f64 * x * p[l] - l as f64 * p[l - 1]) / (l + 1) as f64;
+        }
+        p
+    }
+}
+
+impl StructureFeaturizer for SoapDescriptor {
+    fn name(&self) -> &str {
+        "soap"
+    }
+
+    fn feature_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for pair in species_pairs(&self.species) {
+            let label = pair_label(&self.species, pair);
+            for n in 0..self.n_max {
+                for n2 in 0..self.n_max {
+                    for l in 0..=self.l_max {
+                        names.push(format!("soap_{}_n{}_n{}_l{}", label, n, n2, l));
+                    }
+                }
+            }
+        }
+        names
+    }
+
+    fn featurize(&self, structure: &Structure) -> Result<Vec<f64>> {
+        check_structure(structure)?;
+
+        let centers = self.radial_centers();
+        let pairs = species_pairs(&self.species);
+        let block = self.n_max * self.n_max * (self.l_max + 1);
+        let mut spectrum = vec![0.0; pairs.len() * block];
+
+        let cart = structure.cartesian_coords();
+        let lattice = structure.lattice;
+        let image_vector = |nb: &Neighbor, origin: [f64; 3]| {
+            let mut v = cart[nb.index];
+            for (k, t) in nb.image.iter().enumerate() {
+                for c in 0..3 {
+                    v[c] += *t as f64 * lattice[k][c];
+                }
+            }
+            [v[0] - origin[0], v[1] - origin[1], v[2] - origin[2]]
+        };
+
+        for (i, origin) in cart.iter().enumerate() {
+            let neighbors = structure.neighbors(i, self.cutoff);
+            let vectors: Vec<[f64; 3]> = neighbors.iter().map(|nb| image_vector(nb, *origin)).collect();
+            let basis: Vec<Vec<f64>> = neighbors.iter().map(|nb| self.radial_basis(nb.distance, &centers)).collect();
+
+            for (p_idx, &(sa, sb)) in pairs.iter().enumerate() {
+                for (j, nj) in neighbors.iter().enumerate() {
+                    if !matches(&self.species, sa, &structure.sites[nj.index].element) {
+                        continue;
+                    }
+                    for (k, nk) in neighbors.iter().enumerate() {
+                        if !matches(&self.species, sb, &structure.sites[nk.index].element) {
+                            continue;
+                        }
+                        let cos = (vectors[j][0] * vectors[k][0]
+                            + vectors[j][1] * vectors[k][1]
+                            + vectors[j][2] * vectors[k][2])
+                            / (nj.distance * nk.distance);
+                        let legendre = Self::legendre(self.l_max, cos.clamp(-1.0, 1.0));
+
+                        for n in 0..self.n_max {
+                            for n2 in 0..self.n_max {
+                                let radial = basis[j][n] * basis[k][n2];
+                                if radial < 1e-12 {
+                                    continue;
+                                }
+                                for (l, pl) in legendre.iter().enumerate() {
+                                    let idx = p_idx * block + (n * self.n_max + n2) * (self.l_max + 1) + l;
+                                    spectrum[idx] += radial * (2 * l + 1) as f64 / (4.0 * PI) * pl;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let n_sites = structure.sites.len() as f64;
+        spectrum.iter_mut().for_each(|v| *v /= n_sites);
+        Ok(spectrum)
+    }
+}
+
+// ============================================================================
+// PARTIAL RDF
+// ============================================================================
+
+/// Partial radial distribution functions g_ab(r) on a fixed grid
+#[derive(Debug, Clone)]
+pub struct PartialRdfFingerprint {
+    pub cutoff: f64,
+    pub n_bins: usize,
+    /// Species channels; empty means total RDF
+    pub species: Vec<String>,
+}
+
+impl Default for PartialRdfFingerprint {
+    fn default() -> Self {
+        Self {
+            cutoff: 8.0,
+            n_bins: 40,
+            species: Vec::new(),
+        }
+    }
+}
+
+impl PartialRdfFingerprint {
+    pub fn with_species(mut self, species: Vec<String>) -> Self {
+        self.species = species;
+        self
+    }
+
+    fn bin_width(&self) -> f64 {
+        self.cutoff / self.n_bins.max(1) as f64
+    }
+}
+
+impl StructureFeaturizer for PartialRdfFingerprint {
+    fn name(&self) -> &str {
+        "partial_rdf"
+    }
+
+    fn feature_names(&self) -> Vec<String> {
+        let dr = self.bin_width();
+        let mut names = Vec::new();
+        for pair in species_pairs(&self.species) {
+            let label = pair_label(&self.species, pair);
+            for b in 0..self.n_bins {
+                names.push(format!("rdf_{}_{:.2}", label, (b as f64 + 0.5) * dr));
+            }
+        }
+        names
+    }
+
+    fn featurize(&self, structure: &Structure) -> Result<Vec<f64>> {
+        check_structure(structure)?;
+
+        let pairs = species_pairs(&self.species);
+        let dr = self.bin_width();
+        let volume = structure.volume();
+        let count = |filter: Option<usize>| {
+            structure
+                .sites
+                .iter()
+                .filter(|s| matches(&self.species, filter, &s.element))
+                .count() as f64
+        };
+
+        let neighbors = structure.all_neighbors(self.cutoff);
+        let mut fingerprint = vec![0.0; pairs.len() * self.n_bins];
+
+        for (p_idx, &(sa, sb)) in pairs.iter().enumerate() {
+            let (n_a, n_b) = (count(sa), count(sb));
+            if n_a == 0.0 || n_b == 0.0 {
+                continue;
+            }
+
+            let mut histogram = vec![0.0; self.n_bins];
+            for (i, site) in structure.sites.iter().enumerate() {
+                if !matches(&self.species, sa, &site.element) {
+                    continue;
+                }
+                for nb in &neighbors[i] {
+                    if !matches(&self.species, sb, &structure.sites[nb.index].element) {
+                        continue;
+                    }
+                    let bin = ((nb.distance / dr) as usize).min(self.n_bins - 1);
+                    histogram[bin] += 1.0;
+                }
+            }
+
+            // For a != b count both directions so the channel is symmetric
+            if sa != sb {
+                for (i, site) in structure.sites.iter().enumerate() {
+                    if !matches(&self.species, sb, &site.element) {
+                        continue;
+                    }
+                    for nb in &neighbors[i] {
+                        if matches(&self.species, sa, &structure.sites[nb.index].element) {
+                            let bin = ((nb.distance / dr) as usize).min(self.n_bins - 1);
+                            histogram[bin] += 1.0;
+                        }
+                    }
+                }
+            }
+            let directions = if sa == sb { 1.0 } else { 2.0 };
+
+            for (b, h) in histogram.iter().enumerate() {
+                let r_lo = b as f64 * dr;
+                let r_hi = r_lo + dr;
+                let shell = 4.0 / 3.0 * PI * (r_hi.powi(3) - r_lo.powi(3));
+                fingerprint[p_idx * self.n_bins + b] = h / (directions * n_a * (n_b / volume) * shell);
+            }
+        }
+
+        Ok(fingerprint)
+    }
+}
+
+// ============================================================================
+// LOCAL COORDINATION
+// ============================================================================
+
+/// Coordination-environment statistics
+///
+/// A neighbor is counted as coordinating when it lies within
+/// `(1 + tolerance)` of the site's nearest-neighbor distance, which keeps
+/// cation-cation contacts out of ionic first shells.
+#[derive(Debug, Clone)]
+pub struct CoordinationStatistics {
+    pub tolerance: f64,
+}
+
+impl Default for CoordinationStatistics {
+    fn default() -> Self {
+        Self { tolerance: 0.1 }
+    }
+}
+
+impl CoordinationStatistics {
+    fn covalent_radius(symbol: &str) -> f64 {
+        elements::element(symbol).map_or(1.5, |e| e.covalent_radius / 100.0)
+    }
+
+    /// Coordination number of every site
+    pub fn coordination_numbers(&self, structure: &Structure) -> Vec<usize> {
+        self.first_shells(structure).iter().map(|b| b.len()).collect()
+    }
+
+    /// First coordination shell of every site, sorted by distance
+    fn first_shells(&self, structure: &Structure) -> Vec<Vec<Neighbor>> {
+        // Every site has a periodic image within the longest lattice vector
+        let reach = structure.lattice_parameters().0.iter().cloned().fold(0.0, f64::max);
+
+        structure
+            .all_neighbors(reach + 1e-6)
+            .into_iter()
+            .map(|neighbors| {
+                let d_min = neighbors.first().map_or(0.0, |nb| nb.distance);
+                neighbors
+                    .into_iter()
+                    .take_while(|nb| nb.distance <= d_min * (1.0 + self.tolerance))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+fn mean_std(values: &[f64]) -> (f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    (mean, var.sqrt())
+}
+
+impl StructureFeaturizer for CoordinationStatistics {
+    fn name(&self) -> &str {
+        "coordination"
+    }
+
+    fn feature_names(&self) -> Vec<String> {
+        [
+            "mean_cn",
+            "std_cn",
+            "min_cn",
+            "max_cn",
+            "mean_nn_distance",
+            "std_nn_distance",
+            "mean_bond_ratio",
+            "volume_per_atom",
+            "packing_fraction",
+            "density",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+    }
+
+    fn featurize(&self, structure: &Structure) -> Result<Vec<f64>> {
+        check_structure(structure)?;
+
+        let shells = self.first_shells(structure);
+        let cn: Vec<f64> = shells.iter().map(|b| b.len() as f64).collect();
+        let (mean_cn, std_cn) = mean_std(&cn);
+
+        let nn: Vec<f64> = shells.iter().filter_map(|b| b.first().map(|nb| nb.distance)).collect();
+        let (mean_nn, std_nn) = mean_std(&nn);
+
+        // Bond length relative to the sum of covalent radii
+        let ratios: Vec<f64> = shells
+            .iter()
+            .enumerate()
+            .flat_map(|(i, neighbors)| {
+                let ri = Self::covalent_radius(&structure.sites[i].element);
+                neighbors
+                    .iter()
+                    .map(|nb| nb.distance / (ri + Self::covalent_radius(&structure.sites[nb.index].element)))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let (mean_ratio, _) = mean_std(&ratios);
+
+        let volume = structure.volume();
+        let n_atoms: f64 = structure.sites.iter().map(|s| s.occupancy).sum();
+        let atom_volume: f64 = structure
+            .sites
+            .iter()
+            .map(|s| s.occupancy * 4.0 / 3.0 * PI * Self::covalent_radius(&s.element).powi(3))
+            .sum();
+        let mass: f64 = structure
+            .sites
+            .iter()
+            .map(|s| s.occupancy * elements::atomic_mass(&s.element).unwrap_or(0.0))
+            .sum();
+
+        Ok(vec![
+            mean_cn,
+            std_cn,
+            cn.iter().cloned().fold(f64::INFINITY, f64::min),
+            cn.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            mean_nn,
+            std_nn,
+            mean_ratio,
+            volume / n_atoms.max(1e-12),
+            atom_volume / volume,
+            mass / volume * 1.660_539, // g/cm³
+        ])
+    }
+}
+
+// ============================================================================
+// COMBINATION
+// ============================================================================
+
+/// Concatenation of several structure featurizers
+#[derive(Clone, Default)]
+pub struct FeaturizerStack {
+    featurizers: Vec<Arc<dyn StructureFeaturizer>>,
+}
+
+impl FeaturizerStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, featurizer: impl StructureFeaturizer + 'static) -> Self {
+        self.featurizers.push(Arc::new(featurizer));
+        self
+    }
+}
+
+impl std::fmt::Debug for FeaturizerStack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.featurizers.iter().map(|x| x.name())).finish()
+    }
+}
+
+impl StructureFeaturizer for FeaturizerStack {
+    fn name(&self) -> &str {
+        "stack"
+    }
+
+    fn feature_names(&self) -> Vec<String> {
+        self.featurizers.iter().flat_map(|f| f.feature_names()).collect()
+    }
+
+    fn featurize(&self, structure: &Structure) -> Result<Vec<f64>> {
+        let mut features = Vec::new();
+        for featurizer in &self.featurizers {
+            features.extend(featurizer.featurize(structure)?);
+        }
+        Ok(features)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Site;
+
+    fn site(element: &str, coords: [f64; 3]) -> Site {
+        Site { element: element.to_string(), coords, magmom: None, occupancy: 1.0 }
+    }
+
+    fn cubic(a: f64, sites: Vec<Site>) -> Structure {
+        Structure {
+            lattice: [[a, 0.0, 0.0], [0.0, a, 0.0], [0.0, 0.0, a]],
+            sites,
+            space_group: None,
+            crystal_system: None,
+        }
+    }
+
+    /// NaCl in the rock-salt structure (conventional cell, CN = 6)
+    fn rock_salt() -> Structure {
+        let mut sites = Vec::new();
+        for f in [[0.0, 0.0, 0.0], [0.5, 0.5, 0.0], [0.5, 0.0, 0.5], [0.0, 0.5, 0.5]] {
+            sites.push(site("Na", f));
+            sites.push(site("Cl", [(f[0] + 0.5) % 1.0, f[1], f[2]]));
+        }
+        cubic(5.64, sites)
+    }
+
+    /// NaCl in the CsCl structure type (CN = 8)
+    fn cesium_chloride_type() -> Structure {
+        cubic(3.25, vec![site("Na", [0.0, 0.0, 0.0]), site("Cl", [0.5, 0.5, 0.5])])
+    }
+
+    #[test]
+    fn test_sine_coulomb_matrix() {
+        let scm = SineCoulombMatrix::new(10);
+        let features = scm.featurize(&rock_salt()).unwrap();
+
+        assert_eq!(features.len(), 10);
+        assert!(features[0].abs() >= features[1].abs());
+        assert_eq!(features[8], 0.0);
+
+        let m = scm.matrix(&cesium_chloride_type()).unwrap();
+        assert!((m[0][0] - 0.5 * 11f64.powf(2.4)).abs() < 1e-9);
+        assert!((m[0][1] - m[1][0]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_coordination_distinguishes_polymorphs() {
+        let coordination = CoordinationStatistics::default();
+        assert!(coordination.coordination_numbers(&rock_salt()).iter().all(|&cn| cn == 6));
+        assert!(coordination.coordination_numbers(&cesium_chloride_type()).iter().all(|&cn| cn == 8));
+
+        let rs = coordination.featurize(&rock_salt()).unwrap();
+        assert!((rs[4] - 2.82).abs() < 1e-9);
+        assert!((rs[9] - 2.16).abs() < 0.05); // g/cm³
+    }
+
+    #[test]
+    fn test_partial_rdf() {
+        let rdf = PartialRdfFingerprint { cutoff: 4.0, n_bins: 20, species: vec!["Na".into(), "Cl".into()] };
+        let features = rdf.featurize(&rock_salt()).unwrap();
+        assert_eq!(features.len(), rdf.dim());
+
+        // First Na-Cl shell at 2.82 Å; no Na-Na pairs that close
+        let names = rdf.feature_names();
+        let nacl_peak = names.iter().position(|n| n == "rdf_Na-Cl_2.90").unwrap();
+        let nana = names.iter().position(|n| n == "rdf_Na-Na_2.90").unwrap();
+        assert!(features[nacl_peak] > 0.0);
+        assert_eq!(features[nana], 0.0);
+    }
+
+    #[test]
+    fn test_soap_is_rotation_free_and_polymorph_sensitive() {
+        let soap = SoapDescriptor { n_max: 3, l_max: 3, ..Default::default() };
+        let a = soap.featurize(&rock_salt()).unwrap();
+        let b = soap.featurize(&cesium_chloride_type()).unwrap();
+        assert_eq!(a.len(), soap.dim());
+        assert!(a.iter().zip(&b).map(|(x, y)| (x - y).abs()).sum::<f64>() > 1e-3);
+
+        // Relabelling axes leaves the descriptor unchanged
+        let mut permuted = rock_salt();
+        for s in &mut permuted.sites {
+            s.coords = [s.coords[1], s.coords[2], s.coords[0]];
+        }
+        let c = soap.featurize(&permuted).unwrap();
+        assert!(a.iter().zip(&c).all(|(x, y)| (x - y).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_stack() {
+        let stack = FeaturizerStack::new()
+            .with(CoordinationStatistics::default())
+            .with(SineCoulombMatrix::new(4));
+        let features = stack.featurize(&cesium_chloride_type()).unwrap();
+        assert_eq!(features.len(), 14);
+        assert!(stack.featurize(&Structure::default()).is_err());
+    }
+}
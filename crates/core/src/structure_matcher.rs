@@ -0,0 +1,702 @@
+//! Structure Matching
+//!
+//! Decides whether two [`Structure`]s describe the same crystal, as needed
+//! to merge one material reported by several databases. The comparison is
+//! invariant to the choice of lattice basis, site order, origin and the
+//! symmetry setting, following the approach of pymatgen's StructureMatcher:
+//! 1. Compositions must reduce to the same formula. Cells with different
+//!    numbers of sites are replaced by their primitive cells; volumes are
+//!    optionally scaled to agree (DFT vs experiment).
+//! 2. Every triple of lattice vectors of the second structure whose lengths
+//!    and angles fit the first structure's cell within tolerance, and which
+//!    spans a unimodular cell, is a candidate basis.
+//! 3. For each basis, sites of the rarest species fix the candidate origin
+//!    shifts; sites of each element are paired by an optimal assignment
+//!    (least total squared displacement), and every displacement must be
+//!    below the site tolerance, in units of `(V / N)^(1/3)`. Displacements
+//!    are nearest images, found in the Niggli-reduced cell so skewed
+//!    settings are measured correctly.
+//!
+//! Searching bases and origins covers symmetry-equivalent descriptions.
+//! Mirror images (enantiomorphs) match too. A cheap [`StructureFingerprint`]
+//! (formula, cell size and per-element coordination) rules out most
+//! non-matching pairs before the search.
+
+use crate::cell_reduction;
+use crate::composition::Composition;
+use crate::material::Structure;
+use crate::{Error, Result};
+use nalgebra::{Matrix3, RowVector3};
+use serde::{Deserialize, Serialize};
+
+/// Tolerances for structure comparison
+///
+/// Construct with [`StructureMatcher::new`] and the `with_*` methods;
+/// missing fields deserialize to their defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StructureMatcher {
+    /// Fractional tolerance on lattice vector lengths
+    pub length_tolerance: f64,
+    /// Tolerance on lattice angles (degrees)
+    pub angle_tolerance: f64,
+    /// Largest site displacement, as a fraction of `(V / N)^(1/3)`
+    pub site_tolerance: f64,
+    /// Rescale the second structure to the first one's volume
+    pub scale_volume: bool,
+    /// Compare primitive cells when the cells themselves do not match
+    pub primitive_cell: bool,
+}
+
+impl Default for StructureMatcher {
+    fn default() -> Self {
+        Self { length_tolerance: 0.2, angle_tolerance: 5.0, site_tolerance: 0.3, scale_volume: true, primitive_cell: true }
+    }
+}
+
+/// How the second structure maps onto the first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructureMatch {
+    /// `mapping[i]` is the site of the second structure paired with site i
+    /// of the first
+    pub mapping: Vec<usize>,
+    /// Integer rows expressing the aligned basis in the second lattice's
+    /// vectors (determinant ±1)
+    pub transformation: [[i32; 3]; 3],
+    /// Fractional shift applied to the second structure in the aligned basis
+    pub translation: [f64; 3],
+    /// RMS site displacement, in units of `(V / N)^(1/3)`
+    pub rms_displacement: f64,
+    /// Largest site displacement, in the same units
+    pub max_displacement: f64,
+    /// Volume of the second structure over the first, before scaling
+    pub volume_ratio: f64,
+    /// Whether primitive cells were compared; `mapping` then indexes their sites
+    pub primitive: bool,
+}
+
+/// Cheap invariants used to skip hopeless comparisons
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructureFingerprint {
+    pub reduced_formula: String,
+    /// Sites per cell
+    pub num_sites: usize,
+    /// Sites per primitive cell
+    pub primitive_sites: usize,
+    /// Volume per site (Å³)
+    pub volume_per_site: f64,
+    /// Mean first-shell coordination per element, elements in alphabetical order
+    pub coordination: Vec<(String, f64)>,
+}
+
+/// Bonds up to this multiple of a site's shortest distance form its first shell
+const FIRST_SHELL: f64 = 1.3;
+
+/// Distance (Å) within which sites coincide when finding primitive cells;
+/// looser than the crystallographic default to absorb noise between sources
+const PRIMITIVE_SYMPREC: f64 = 0.1;
+
+fn primitive(structure: &Structure) -> Option<Structure> {
+    cell_reduction::primitive_cell(structure, PRIMITIVE_SYMPREC).ok().map(|p| p.structure)
+}
+
+impl StructureFingerprint {
+    pub fn new(structure: &Structure) -> Result<Self> {
+        let composition = site_composition(structure)?;
+        let num_sites = structure.sites.len();
+        let volume_per_site = structure.volume() / num_sites as f64;
+
+        let search = 2.0 * volume_per_site.cbrt();
+        let mut elements = composition.elements();
+        elements.sort_unstable();
+        let mut coordination: Vec<(String, f64)> = elements.into_iter().map(|e| (e.to_string(), 0.0)).collect();
+        for (i, site) in structure.sites.iter().enumerate() {
+            let neighbors = structure.neighbors(i, search);
+            let shell = neighbors.first().map_or(0, |n| {
+                neighbors.iter().filter(|m| m.distance <= n.distance * FIRST_SHELL).count()
+            });
+            if let Some(entry) = coordination.iter_mut().find(|(e, _)| *e == site.element) {
+                entry.1 += shell as f64;
+            }
+        }
+        for (element, total) in &mut coordination {
+            let count = structure.sites.iter().filter(|s| s.element == *element).count().max(1);
+            *total /= count as f64;
+        }
+
+        let primitive_sites = primitive(structure).map_or(num_sites, |p| p.sites.len());
+        Ok(Self { reduced_formula: composition.reduced_formula(), num_sites, primitive_sites, volume_per_site, coordination })
+    }
+}
+
+fn site_composition(structure: &Structure) -> Result<Composition> {
+    if structure.sites.is_empty() {
+        return Err(Error::invalid_input("Structure has no sites"));
+    }
+    if structure.volume() < 1e-8 {
+        return Err(Error::invalid_input("Structure lattice is degenerate"));
+    }
+    Composition::from_amounts(structure.sites.iter().map(|s| (s.element.as_str(), 1.0)))
+}
+
+fn lattice_matrix(lattice: &[[f64; 3]; 3]) -> Matrix3<f64> {
+    Matrix3::from_fn(|i, j| lattice[i][j])
+}
+
+fn norm(v: &[f64; 3]) -> f64 {
+    (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt()
+}
+
+fn angle(u: &[f64; 3], v: &[f64; 3]) -> f64 {
+    let dot = u[0] * v[0] + u[1] * v[1] + u[2] * v[2];
+    (dot / (norm(u) * norm(v))).clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+fn wrap(x: f64) -> f64 {
+    x - x.round()
+}
+
+/// A lattice vector of the second structure: integer coefficients and
+/// Cartesian value
+type LatticeVector = ([i32; 3], [f64; 3]);
+
+/// Nearest images in one lattice
+///
+/// Wrapping fractional coordinates only finds the nearest image when the
+/// cell is close to orthogonal. In the Niggli-reduced basis the nearest
+/// image lies within one cell of the wrapped vector.
+struct MinimumImage {
+    lattice: Matrix3<f64>,
+    lattice_inverse: Matrix3<f64>,
+    reduced: Matrix3<f64>,
+    reduced_inverse: Matrix3<f64>,
+}
+
+impl MinimumImage {
+    fn new(structure: &Structure) -> Option<Self> {
+        let lattice = lattice_matrix(&structure.lattice);
+        let reduced = lattice_matrix(&cell_reduction::niggli_reduce(structure).ok()?.structure.lattice);
+        Some(Self {
+            lattice_inverse: lattice.try_inverse()?,
+            reduced_inverse: reduced.try_inverse()?,
+            lattice,
+            reduced,
+        })
+    }
+
+    /// Shortest Cartesian vector equivalent to a fractional difference
+    fn shortest(&self, fractional: [f64; 3]) -> RowVector3<f64> {
+        let cartesian = RowVector3::new(fractional[0], fractional[1], fractional[2]) * self.lattice;
+        let wrapped = (cartesian * self.reduced_inverse).map(wrap);
+        let mut best = wrapped * self.reduced;
+        for offset in 0..27 {
+            let n = RowVector3::new((offset / 9) as f64 - 1.0, ((offset / 3) % 3) as f64 - 1.0, (offset % 3) as f64 - 1.0);
+            let image = (wrapped + n) * self.reduced;
+            if image.norm_squared() < best.norm_squared() {
+                best = image;
+            }
+        }
+        best
+    }
+
+    /// Fractional coordinates of a Cartesian vector
+    fn fractional(&self, cartesian: RowVector3<f64>) -> [f64; 3] {
+        let f = cartesian * self.lattice_inverse;
+        [f[0], f[1], f[2]]
+    }
+}
+
+/// Minimum-cost perfect matching of a square cost matrix (Hungarian
+/// algorithm, O(n³)); `result[i]` is the column assigned to row i
+fn assignment(cost: &[Vec<f64>]) -> Vec<usize> {
+    let n = cost.len();
+    // Potentials and matching are 1-based; column 0 is a sentinel
+    let mut u = vec![0.0; n + 1];
+    let mut v = vec![0.0; n + 1];
+    let mut owner = vec![0usize; n + 1];
+    let mut way = vec![0usize; n + 1];
+    for row in 1..=n {
+        owner[0] = row;
+        let mut column = 0;
+        let mut slack = vec![f64::INFINITY; n + 1];
+        let mut used = vec![false; n + 1];
+        loop {
+            used[column] = true;
+            let current = owner[column];
+            let mut delta = f64::INFINITY;
+            let mut next = 0;
+            for j in 1..=n {
+                if used[j] {
+                    continue;
+                }
+                let reduced = cost[current - 1][j - 1] - u[current] - v[j];
+                if reduced < slack[j] {
+                    slack[j] = reduced;
+                    way[j] = column;
+                }
+                if slack[j] < delta {
+                    delta = slack[j];
+                    next = j;
+                }
+            }
+            for j in 0..=n {
+                if used[j] {
+                    u[owner[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    slack[j] -= delta;
+                }
+            }
+            column = next;
+            if owner[column] == 0 {
+                break;
+            }
+        }
+        while column != 0 {
+            let previous = way[column];
+            owner[column] = owner[previous];
+            column = previous;
+        }
+    }
+
+    let mut result = vec![0; n];
+    for j in 1..=n {
+        result[owner[j] - 1] = j - 1;
+    }
+    result
+}
+
+impl StructureMatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Same matcher with other length, site and angle tolerances
+    pub fn with_tolerances(mut self, length: f64, site: f64, angle: f64) -> Self {
+        self.length_tolerance = length;
+        self.site_tolerance = site;
+        self.angle_tolerance = angle;
+        self
+    }
+
+    /// Same matcher with or without rescaling to equal volumes
+    pub fn with_volume_scaling(mut self, scale_volume: bool) -> Self {
+        self.scale_volume = scale_volume;
+        self
+    }
+
+    /// Same matcher with or without the fallback to primitive cells
+    pub fn with_primitive_cell(mut self, primitive_cell: bool) -> Self {
+        self.primitive_cell = primitive_cell;
+        self
+    }
+
+    /// Fingerprint for prefiltering
+    pub fn fingerprint(&self, structure: &Structure) -> Result<StructureFingerprint> {
+        StructureFingerprint::new(structure)
+    }
+
+    /// Whether two fingerprints leave a match possible
+    pub fn may_match(&self, a: &StructureFingerprint, b: &StructureFingerprint) -> bool {
+        let sizes = if self.primitive_cell { (a.primitive_sites, b.primitive_sites) } else { (a.num_sites, b.num_sites) };
+        if a.reduced_formula != b.reduced_formula || sizes.0 != sizes.1 {
+            return false;
+        }
+        if !self.scale_volume {
+            let ratio = b.volume_per_site / a.volume_per_site;
+            let limit = (1.0 + self.length_tolerance).powi(3);
+            if ratio > limit || ratio < 1.0 / limit {
+                return false;
+            }
+        }
+        a.coordination
+            .iter()
+            .zip(&b.coordination)
+            .all(|((ea, ca), (eb, cb))| ea == eb && (ca - cb).abs() <= 1.0)
+    }
+
+    /// Whether the two structures are the same crystal
+    pub fn fit(&self, a: &Structure, b: &Structure) -> bool {
+        self.get_match(a, b).is_some_and(|m| m.max_displacement <= self.site_tolerance)
+    }
+
+    /// Best mapping of `b` onto `a`, if one is within tolerance
+    ///
+    /// Cells with the same number of sites are compared directly. When they
+    /// do not match (supercells of different shapes) or their sizes differ,
+    /// the primitive cells are compared instead.
+    pub fn get_match(&self, a: &Structure, b: &Structure) -> Option<StructureMatch> {
+        let same_size = a.sites.len() == b.sites.len();
+        if same_size {
+            if let Some(found) = self.match_cells(a, b) {
+                return Some(found);
+            }
+        }
+        if !self.primitive_cell {
+            return None;
+        }
+        let (pa, pb) = (primitive(a)?, primitive(b)?);
+        // Cells already primitive were compared above
+        if pa.sites.len() != pb.sites.len() || (same_size && pa.sites.len() == a.sites.len()) {
+            return None;
+        }
+        self.match_cells(&pa, &pb).map(|m| StructureMatch { primitive: true, ..m })
+    }
+
+    /// Match cells with the same number of sites
+    fn match_cells(&self, a: &Structure, b: &Structure) -> Option<StructureMatch> {
+        let composition_a = site_composition(a).ok()?;
+        let composition_b = site_composition(b).ok()?;
+        if composition_a.reduced_formula() != composition_b.reduced_formula() {
+            return None;
+        }
+
+        let volume_ratio = b.volume() / a.volume();
+        let scale = if self.scale_volume { (1.0 / volume_ratio).cbrt() } else { 1.0 };
+        let lattice_b = b.lattice.map(|row| row.map(|x| x * scale));
+        let unit = (a.volume() / a.sites.len() as f64).cbrt();
+
+        let bases = self.candidate_bases(&a.lattice, &lattice_b);
+        let image = MinimumImage::new(a)?;
+
+        // Origins come from the rarest species
+        let anchor = composition_a
+            .iter()
+            .min_by(|x, y| x.1.partial_cmp(&y.1).unwrap_or(std::cmp::Ordering::Equal).then(x.0.cmp(y.0)))
+            .map(|(e, _)| e.to_string())?;
+        let anchor_a = a.sites.iter().position(|s| s.element == anchor)?;
+
+        let mut best: Option<StructureMatch> = None;
+        for basis in bases {
+            let transform = Matrix3::from_fn(|i, j| basis[i][j] as f64);
+            let Some(inverse) = transform.try_inverse() else {
+                continue;
+            };
+            // f_b · L_b = f' · (M · L_b), so f' = f_b · M⁻¹
+            let coords_b: Vec<[f64; 3]> = b
+                .sites
+                .iter()
+                .map(|s| {
+                    let f = nalgebra::RowVector3::new(s.coords[0], s.coords[1], s.coords[2]) * inverse;
+                    [f[0], f[1], f[2]]
+                })
+                .collect();
+
+            for (j, site) in b.sites.iter().enumerate() {
+                if site.element != anchor {
+                    continue;
+                }
+                let shift: [f64; 3] = std::array::from_fn(|k| a.sites[anchor_a].coords[k] - coords_b[j][k]);
+                let Some(candidate) = self.pair_sites(a, b, &coords_b, shift, &image, unit) else {
+                    continue;
+                };
+                if best.as_ref().map_or(true, |m| candidate.rms_displacement < m.rms_displacement) {
+                    best = Some(StructureMatch { transformation: basis, volume_ratio, ..candidate });
+                }
+            }
+            if best.as_ref().is_some_and(|m| m.max_displacement < 1e-3 * self.site_tolerance) {
+                break;
+            }
+        }
+        best
+    }
+
+    /// Unimodular integer bases of `lattice_b` whose vectors fit the lengths
+    /// and angles of `lattice_a`
+    fn candidate_bases(&self, lattice_a: &[[f64; 3]; 3], lattice_b: &[[f64; 3]; 3]) -> Vec<[[i32; 3]; 3]> {
+        let lengths: [f64; 3] = std::array::from_fn(|k| norm(&lattice_a[k]));
+        let angles = [
+            angle(&lattice_a[1], &lattice_a[2]),
+            angle(&lattice_a[0], &lattice_a[2]),
+            angle(&lattice_a[0], &lattice_a[1]),
+        ];
+        let max_length = lengths.iter().cloned().fold(0.0, f64::max) * (1.0 + self.length_tolerance);
+
+        // Interplanar spacings bound the coefficients needed
+        let probe = Structure { lattice: *lattice_b, ..Structure::default() };
+        let volume = probe.volume();
+        let cross = |u: &[f64; 3], v: &[f64; 3]| {
+            norm(&[u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]])
+        };
+        let [b0, b1, b2] = lattice_b;
+        let spacings = [volume / cross(b1, b2), volume / cross(b0, b2), volume / cross(b0, b1)];
+        let range = spacings.map(|d| (max_length / d).ceil() as i32);
+
+        let mut candidates: [Vec<LatticeVector>; 3] = Default::default();
+        for n0 in -range[0]..=range[0] {
+            for n1 in -range[1]..=range[1] {
+                for n2 in -range[2]..=range[2] {
+                    if n0 == 0 && n1 == 0 && n2 == 0 {
+                        continue;
+                    }
+                    let v: [f64; 3] = std::array::from_fn(|k| {
+                        n0 as f64 * b0[k] + n1 as f64 * b1[k] + n2 as f64 * b2[k]
+                    });
+                    let length = norm(&v);
+                    for (k, target) in lengths.iter().enumerate() {
+                        if (length - target).abs() <= self.length_tolerance * target {
+                            candidates[k].push(([n0, n1, n2], v));
+                        }
+                    }
+                }
+            }
+        }
+
+        let fits = |u: &[f64; 3], v: &[f64; 3], target: f64| (angle(u, v) - target).abs() <= self.angle_tolerance;
+        let mut bases = Vec::new();
+        for (na, va) in &candidates[0] {
+            for (nb, vb) in &candidates[1] {
+                if !fits(va, vb, angles[2]) {
+                    continue;
+                }
+                for (nc, vc) in &candidates[2] {
+                    if !fits(vb, vc, angles[0]) || !fits(va, vc, angles[1]) {
+                        continue;
+                    }
+                    let det = na[0] * (nb[1] * nc[2] - nb[2] * nc[1]) - na[1] * (nb[0] * nc[2] - nb[2] * nc[0])
+                        + na[2] * (nb[0] * nc[1] - nb[1] * nc[0]);
+                    if det.abs() == 1 {
+                        bases.push([*na, *nb, *nc]);
+                    }
+                }
+            }
+        }
+        bases
+    }
+
+    /// Pair the sites of `a` one-to-one with same-element sites of `b`
+    /// after shifting `b`, minimizing the total squared displacement, then
+    /// refine the shift by the mean displacement
+    fn pair_sites(
+        &self,
+        a: &Structure,
+        b: &Structure,
+        coords_b: &[[f64; 3]],
+        shift: [f64; 3],
+        image: &MinimumImage,
+        unit: f64,
+    ) -> Option<StructureMatch> {
+        let displacement = |i: usize, j: usize, shift: &[f64; 3]| {
+            image.shortest(std::array::from_fn(|k| coords_b[j][k] + shift[k] - a.sites[i].coords[k]))
+        };
+        let limit = self.site_tolerance * unit;
+        // Pairs beyond the tolerance stay assignable but never preferred
+        let forbidden = 1e6 * limit * limit;
+
+        let mut mapping = vec![0; a.sites.len()];
+        let mut elements: Vec<&str> = a.sites.iter().map(|s| s.element.as_str()).collect();
+        elements.sort_unstable();
+        elements.dedup();
+        for element in elements {
+            let rows: Vec<usize> = (0..a.sites.len()).filter(|&i| a.sites[i].element == element).collect();
+            let columns: Vec<usize> = (0..b.sites.len()).filter(|&j| b.sites[j].element == element).collect();
+            if rows.len() != columns.len() {
+                return None;
+            }
+            let mut cost = Vec::with_capacity(rows.len());
+            for &i in &rows {
+                let distances: Vec<f64> = columns.iter().map(|&j| displacement(i, j, &shift).norm()).collect();
+                // A site with no partner in reach rules out this shift early
+                if distances.iter().all(|&d| d > limit) {
+                    return None;
+                }
+                cost.push(distances.into_iter().map(|d| if d > limit { forbidden } else { d * d }).collect::<Vec<_>>());
+            }
+            for (row, column) in assignment(&cost).into_iter().enumerate() {
+                if cost[row][column] >= forbidden {
+                    return None;
+                }
+                mapping[rows[row]] = columns[column];
+            }
+        }
+
+        // Remove the mean offset left by the anchor choice
+        let n = mapping.len() as f64;
+        let mean = mapping
+            .iter()
+            .enumerate()
+            .fold(RowVector3::zeros(), |sum, (i, &j)| sum + displacement(i, j, &shift) / n);
+        let offset = image.fractional(mean);
+        let translation: [f64; 3] = std::array::from_fn(|k| shift[k] - offset[k]);
+
+        let distances: Vec<f64> =
+            mapping.iter().enumerate().map(|(i, &j)| displacement(i, j, &translation).norm() / unit).collect();
+        let rms_displacement = (distances.iter().map(|d| d * d).sum::<f64>() / n).sqrt();
+        let max_displacement = distances.iter().cloned().fold(0.0, f64::max);
+
+        Some(StructureMatch {
+            mapping,
+            transformation: [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+            translation: translation.map(wrap),
+            rms_displacement,
+            max_displacement,
+            volume_ratio: 1.0,
+            primitive: false,
+        })
+    }
+
+    /// Partition structures into groups of the same crystal, in input order
+    ///
+    /// Each structure is compared with the first member of every group
+    /// whose fingerprint allows a match.
+    pub fn group(&self, structures: &[Structure]) -> Vec<Vec<usize>> {
+        let fingerprints: Vec<Option<StructureFingerprint>> =
+            structures.iter().map(|s| self.fingerprint(s).ok()).collect();
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for (i, structure) in structures.iter().enumerate() {
+            let home = groups.iter().position(|group| {
+                let first = group[0];
+                match (&fingerprints[first], &fingerprints[i]) {
+                    (Some(fa), Some(fb)) => self.may_match(fa, fb) && self.fit(&structures[first], structure),
+                    _ => false,
+                }
+            });
+            match home {
+                Some(g) => groups[g].push(i),
+                None => groups.push(vec![i]),
+            }
+        }
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Site;
+
+    fn structure(lattice: [[f64; 3]; 3], sites: &[(&str, [f64; 3])]) -> Structure {
+        Structure {
+            lattice,
+            sites: sites
+                .iter()
+                .map(|(e, c)| Site { element: e.to_string(), coords: *c, magmom: None, occupancy: 1.0 })
+                .collect(),
+            space_group: None,
+            crystal_system: None,
+        }
+    }
+
+    /// Rutile TiO2 with oxygen parameter u
+    fn rutile(a: f64, c: f64, u: f64) -> Structure {
+        structure(
+            [[a, 0.0, 0.0], [0.0, a, 0.0], [0.0, 0.0, c]],
+            &[
+                ("Ti", [0.0, 0.0, 0.0]),
+                ("Ti", [0.5, 0.5, 0.5]),
+                ("O", [u, u, 0.0]),
+                ("O", [1.0 - u, 1.0 - u, 0.0]),
+                ("O", [0.5 + u, 0.5 - u, 0.5]),
+                ("O", [0.5 - u, 0.5 + u, 0.5]),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_matches_up_to_setting_order_and_origin() {
+        let reference = rutile(4.594, 2.959, 0.305);
+        let matcher = StructureMatcher::new();
+        assert!(matcher.fit(&reference, &reference));
+
+        // Another database: axes permuted (c first), sites reordered, origin
+        // moved to a Ti at the body center and a 2% larger cell
+        let s = 1.02;
+        let shifted: Vec<(&str, [f64; 3])> = reference
+            .sites
+            .iter()
+            .rev()
+            .map(|site| {
+                let f = site.coords;
+                (site.element.as_str(), [(f[2] + 0.5) % 1.0, (f[0] + 0.5) % 1.0, (f[1] + 0.5) % 1.0])
+            })
+            .collect();
+        let other = structure([[0.0, 0.0, 2.959 * s], [4.594 * s, 0.0, 0.0], [0.0, 4.594 * s, 0.0]], &shifted);
+
+        let found = matcher.get_match(&reference, &other).unwrap();
+        assert!(found.rms_displacement < 1e-6, "{:?}", found);
+        assert!((found.volume_ratio - s.powi(3)).abs() < 1e-9);
+        let mut mapped = found.mapping.clone();
+        mapped.sort();
+        assert_eq!(mapped, (0..6).collect::<Vec<_>>());
+        for (i, &j) in found.mapping.iter().enumerate() {
+            assert_eq!(reference.sites[i].element, other.sites[j].element);
+        }
+
+        // Without volume scaling the 2% strain is still within tolerance,
+        // but a tight length tolerance rejects it
+        let unscaled = StructureMatcher::new().with_volume_scaling(false);
+        assert!(unscaled.fit(&reference, &other));
+        assert!(!unscaled.clone().with_tolerances(0.01, 0.3, 5.0).fit(&reference, &other));
+    }
+
+    #[test]
+    fn test_distinguishes_polymorphs_and_groups() {
+        let rutile_a = rutile(4.594, 2.959, 0.305);
+        let rutile_b = rutile(4.60, 2.96, 0.30);
+        // Same cell with the oxygens moved far from the rutile positions
+        let distorted = rutile(4.594, 2.959, 0.12);
+        let rock_salt_like = structure(
+            [[4.2, 0.0, 0.0], [0.0, 4.2, 0.0], [0.0, 0.0, 4.2]],
+            &[("Mg", [0.0, 0.0, 0.0]), ("O", [0.5, 0.5, 0.5])],
+        );
+
+        let matcher = StructureMatcher::new();
+        assert!(matcher.fit(&rutile_a, &rutile_b));
+        assert!(!matcher.fit(&rutile_a, &distorted));
+        assert!(!matcher.fit(&rutile_a, &rock_salt_like));
+
+        let fa = matcher.fingerprint(&rutile_a).unwrap();
+        assert_eq!(fa.reduced_formula, "O2Ti");
+        assert_eq!(fa.coordination, vec![("O".to_string(), 3.0), ("Ti".to_string(), 6.0)]);
+        assert!(matcher.may_match(&fa, &matcher.fingerprint(&rutile_b).unwrap()));
+        assert!(!matcher.may_match(&fa, &matcher.fingerprint(&rock_salt_like).unwrap()));
+
+        // A doubled cell reduces back to the same primitive cell
+        let doubled = cell_reduction::make_supercell(&rutile_b, [[1, 0, 0], [0, 1, 0], [0, 0, 2]]).unwrap().structure;
+        let found = matcher.get_match(&rutile_a, &doubled).unwrap();
+        assert!(found.primitive && found.max_displacement <= matcher.site_tolerance);
+        assert!(matcher.may_match(&fa, &matcher.fingerprint(&doubled).unwrap()));
+        let strict = StructureMatcher::new().with_primitive_cell(false);
+        assert!(!strict.fit(&rutile_a, &doubled));
+
+        // Same number of sites, but doubled along different axes: only the
+        // primitive cells line up
+        let doubled_a = cell_reduction::make_supercell(&rutile_a, [[2, 0, 0], [0, 1, 0], [0, 0, 1]]).unwrap().structure;
+        assert!(strict.get_match(&doubled_a, &doubled).is_none());
+        let found = matcher.get_match(&doubled_a, &doubled).unwrap();
+        assert!(found.primitive && found.max_displacement <= matcher.site_tolerance);
+
+        // Configurations written before `primitive_cell` existed still load
+        let old: StructureMatcher =
+            serde_json::from_str(r#"{"length_tolerance":0.1,"angle_tolerance":5.0,"site_tolerance":0.3,"scale_volume":false}"#)
+                .unwrap();
+        assert!(old.primitive_cell && !old.scale_volume);
+
+        let groups = matcher.group(&[rutile_a, rock_salt_like, distorted, rutile_b, doubled]);
+        assert_eq!(groups, vec![vec![0, 3, 4], vec![1], vec![2]]);
+    }
+
+    #[test]
+    fn test_optimal_pairing_and_skewed_cells() {
+        // Nearest-site pairing sends both Cl of `a` to the Cl at z = 0.57;
+        // only the one-to-one assignment finds the match
+        let cubic = [[5.0, 0.0, 0.0], [0.0, 5.0, 0.0], [0.0, 0.0, 5.0]];
+        let a = structure(cubic, &[("Na", [0.0, 0.0, 0.0]), ("Cl", [0.5, 0.5, 0.5]), ("Cl", [0.5, 0.5, 0.62])]);
+        let b = structure(cubic, &[("Na", [0.0, 0.0, 0.0]), ("Cl", [0.5, 0.5, 0.57]), ("Cl", [0.5, 0.5, 0.69])]);
+        let matcher = StructureMatcher::new().with_volume_scaling(false);
+        let found = matcher.get_match(&a, &b).unwrap();
+        assert_eq!(found.mapping, vec![0, 1, 2]);
+        assert!(found.max_displacement <= matcher.site_tolerance);
+
+        assert_eq!(assignment(&[vec![1.0, 2.0, 3.0], vec![2.0, 4.0, 6.0], vec![3.0, 6.0, 9.0]]), vec![2, 1, 0]);
+
+        // The same square lattice in a strongly sheared basis: a 0.42 Å
+        // displacement wraps to a far image without the reduced cell
+        let skewed = [[5.0, 0.0, 0.0], [40.0, 5.0, 0.0], [0.0, 0.0, 5.0]];
+        let to_fractional = |x: f64, y: f64, z: f64| [(x - 8.0 * y) / 5.0, y / 5.0, z / 5.0].map(|f: f64| f.rem_euclid(1.0));
+        let a = structure(skewed, &[("Na", [0.0, 0.0, 0.0]), ("Cl", to_fractional(2.5, 2.5, 2.5))]);
+        let b = structure(skewed, &[("Na", [0.0, 0.0, 0.0]), ("Cl", to_fractional(2.2, 2.8, 2.5))]);
+        let found = matcher.get_match(&a, &b).unwrap();
+        assert!(found.max_displacement <= matcher.site_tolerance, "{:?}", found);
+    }
+}
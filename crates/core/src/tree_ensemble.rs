@@ -0,0 +1,598 @@
+//! Tree Ensemble Regressors
+//!
+//! Random forests and gradient-boosted trees for tabular property prediction.
+//! Both share a histogram-based regression tree: features are bucketed into
+//! quantile bins once per fit, and splits are searched over bin boundaries,
+//! which keeps training linear in the number of samples per node.
+//!
+//! Uncertainty comes from the ensemble itself: the spread of per-tree
+//! predictions for random forests, and out-of-bag residuals for boosting.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Random forest hyperparameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RandomForestConfig {
+    pub n_trees: usize,
+    pub max_depth: usize,
+    pub min_samples_leaf: usize,
+    /// Fraction of features considered at each split
+    pub max_features: f64,
+    pub max_bins: usize,
+    pub seed: u64,
+}
+
+impl Default for RandomForestConfig {
+    fn default() -> Self {
+        Self {
+            n_trees: 100,
+            max_depth: 12,
+            min_samples_leaf: 1,
+            max_features: 0.33,
+            max_bins: 64,
+            seed: 42,
+        }
+    }
+}
+
+/// Gradient boosting hyperparameters (squared loss)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GradientBoostingConfig {
+    pub n_estimators: usize,
+    pub learning_rate: f64,
+    pub max_depth: usize,
+    pub min_samples_leaf: usize,
+    /// Fraction of samples drawn (without replacement) for each tree
+    pub subsample: f64,
+    /// Fraction of samples held out of training to score the model and
+    /// estimate the residual spread
+    #[serde(default = "default_validation_fraction")]
+    pub validation_fraction: f64,
+    pub max_bins: usize,
+    pub seed: u64,
+}
+
+fn default_validation_fraction() -> f64 {
+    0.1
+}
+
+impl Default for GradientBoostingConfig {
+    fn default() -> Self {
+        Self {
+            n_estimators: 200,
+            learning_rate: 0.1,
+            max_depth: 4,
+            min_samples_leaf: 3,
+            subsample: 0.8,
+            validation_fraction: default_validation_fraction(),
+            max_bins: 64,
+            seed: 42,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum EnsembleKind {
+    RandomForest,
+    GradientBoosting,
+}
+
+/// Node of a regression tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TreeNode {
+    Leaf {
+        value: f64,
+    },
+    Split {
+        feature: usize,
+        threshold: f64,
+        left: usize,
+        right: usize,
+    },
+}
+
+/// Binary regression tree stored as a flat node array (root at index 0)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegressionTree {
+    pub nodes: Vec<TreeNode>,
+}
+
+impl RegressionTree {
+    pub fn predict(&self, features: &[f64]) -> f64 {
+        let mut idx = 0;
+        loop {
+            match &self.nodes[idx] {
+                TreeNode::Leaf { value } => return *value,
+                TreeNode::Split { feature, threshold, left, right } => {
+                    let x = features.get(*feature).copied().unwrap_or(0.0);
+                    idx = if x <= *threshold { *left } else { *right };
+                }
+            }
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        fn depth_of(nodes: &[TreeNode], idx: usize) -> usize {
+            match &nodes[idx] {
+                TreeNode::Leaf { .. } => 0,
+                TreeNode::Split { left, right, .. } => {
+                    1 + depth_of(nodes, *left).max(depth_of(nodes, *right))
+                }
+            }
+        }
+        depth_of(&self.nodes, 0)
+    }
+}
+
+/// Per-feature quantile bin edges and the binned training matrix
+struct BinnedData {
+    /// thresholds[f][b] is the upper edge of bin b for feature f
+    thresholds: Vec<Vec<f64>>,
+    /// bins[f][i] is the bin of sample i for feature f
+    bins: Vec<Vec<u16>>,
+}
+
+impl BinnedData {
+    fn new(x: &[Vec<f64>], max_bins: usize) -> Self {
+        let n_features = x.first().map_or(0, |r| r.len());
+        let max_bins = max_bins.clamp(2, u16::MAX as usize);
+
+        let mut thresholds = Vec::with_capacity(n_features);
+        let mut bins = Vec::with_capacity(n_features);
+
+        for f in 0..n_features {
+            let mut values: Vec<f64> = x.iter().map(|r| r[f]).collect();
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            values.dedup();
+
+            let edges: Vec<f64> = if values.len() <= max_bins {
+                values.windows(2).map(|w| 0.5 * (w[0] + w[1])).collect()
+            } else {
+                let mut edges: Vec<f64> = (1..max_bins)
+                    .map(|q| values[q * values.len() / max_bins])
+                    .collect();
+                edges.dedup();
+                edges
+            };
+
+            let column = x
+                .iter()
+                .map(|r| edges.partition_point(|t| *t < r[f]) as u16)
+                .collect();
+
+            thresholds.push(edges);
+            bins.push(column);
+        }
+
+        Self { thresholds, bins }
+    }
+
+    fn n_features(&self) -> usize {
+        self.thresholds.len()
+    }
+}
+
+struct TreeParams {
+    max_depth: usize,
+    min_samples_leaf: usize,
+    features_per_split: usize,
+}
+
+struct TreeBuilder<'a> {
+    data: &'a BinnedData,
+    targets: &'a [f64],
+    params: &'a TreeParams,
+    nodes: Vec<TreeNode>,
+    importance: &'a mut [f64],
+}
+
+impl TreeBuilder<'_> {
+    fn build(&mut self, indices: &[usize], depth: usize, rng: &mut StdRng) -> usize {
+        let n = indices.len() as f64;
+        let sum: f64 = indices.iter().map(|&i| self.targets[i]).sum();
+        let node_idx = self.nodes.len();
+        self.nodes.push(TreeNode::Leaf { value: sum / n });
+
+        if depth >= self.params.max_depth || indices.len() < 2 * self.params.min_samples_leaf {
+            return node_idx;
+        }
+
+        let mut features: Vec<usize> = (0..self.data.n_features()).collect();
+        if self.params.features_per_split < features.len() {
+            features.shuffle(rng);
+            features.truncate(self.params.features_per_split);
+        }
+
+        let parent_score = sum * sum / n;
+        let mut best: Option<(usize, usize, f64)> = None; // (feature, bin, gain)
+
+        for &f in &features {
+            let n_bins = self.data.thresholds[f].len() + 1;
+            if n_bins < 2 {
+                continue;
+            }
+
+            let mut hist_sum = vec![0.0; n_bins];
+            let mut hist_cnt = vec![0usize; n_bins];
+            for &i in indices {
+                let b = self.data.bins[f][i] as usize;
+                hist_sum[b] += self.targets[i];
+                hist_cnt[b] += 1;
+            }
+
+            let (mut left_sum, mut left_cnt) = (0.0, 0usize);
+            for b in 0..n_bins - 1 {
+                left_sum += hist_sum[b];
+                left_cnt += hist_cnt[b];
+                let right_cnt = indices.len() - left_cnt;
+
+                if left_cnt < self.params.min_samples_leaf {
+                    continue;
+                }
+                if right_cnt < self.params.min_samples_leaf {
+                    break;
+                }
+
+                let right_sum = sum - left_sum;
+                let gain = left_sum * left_sum / left_cnt as f64
+                    + right_sum * right_sum / right_cnt as f64
+                    - parent_score;
+
+                if gain > best.map_or(1e-12, |(_, _, g)| g) {
+                    best = Some((f, b, gain));
+                }
+            }
+        }
+
+        let Some((feature, bin, gain)) = best else {
+            return node_idx;
+        };
+
+        self.importance[feature] += gain;
+
+        let (left_idx, right_idx): (Vec<usize>, Vec<usize>) = indices
+            .iter()
+            .partition(|&&i| (self.data.bins[feature][i] as usize) <= bin);
+
+        let left = self.build(&left_idx, depth + 1, rng);
+        let right = self.build(&right_idx, depth + 1, rng);
+
+        self.nodes[node_idx] = TreeNode::Split {
+            feature,
+            threshold: self.data.thresholds[feature][bin],
+            left,
+            right,
+        };
+
+        node_idx
+    }
+}
+
+fn fit_tree(
+    data: &BinnedData,
+    targets: &[f64],
+    indices: &[usize],
+    params: &TreeParams,
+    importance: &mut [f64],
+    rng: &mut StdRng,
+) -> RegressionTree {
+    let mut builder = TreeBuilder {
+        data,
+        targets,
+        params,
+        nodes: Vec::new(),
+        importance,
+    };
+    builder.build(indices, 0, rng);
+    RegressionTree { nodes: builder.nodes }
+}
+
+/// Trained tree ensemble
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeEnsemble {
+    pub kind: EnsembleKind,
+    pub base_prediction: f64,
+    pub learning_rate: f64,
+    pub trees: Vec<RegressionTree>,
+    /// Split-gain importance per feature, normalized to sum to 1
+    pub feature_importance: Vec<f64>,
+    /// Held-out R²: out-of-bag for forests, the validation split for boosting
+    pub oob_score: Option<f64>,
+    /// Standard deviation of held-out (or, without any, training) residuals
+    pub residual_std: f64,
+}
+
+impl TreeEnsemble {
+    /// Fit a random forest on bootstrap samples
+    pub fn fit_random_forest(
+        x: &[Vec<f64>],
+        y: &[f64],
+        config: &RandomForestConfig,
+    ) -> Result<Self, String> {
+        Self::check_input(x, y)?;
+
+        let n = x.len();
+        let n_features = x[0].len();
+        let data = BinnedData::new(x, config.max_bins);
+        let params = TreeParams {
+            max_depth: config.max_depth,
+            min_samples_leaf: config.min_samples_leaf.max(1),
+            features_per_split: ((n_features as f64 * config.max_features).ceil() as usize)
+                .clamp(1, n_features.max(1)),
+        };
+
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let mut importance = vec![0.0; n_features];
+        let mut trees = Vec::with_capacity(config.n_trees);
+        let mut oob_sum = vec![0.0; n];
+        let mut oob_cnt = vec![0usize; n];
+
+        for _ in 0..config.n_trees.max(1) {
+            let sample: Vec<usize> = (0..n).map(|_| rng.gen_range(0..n)).collect();
+            let mut in_bag = vec![false; n];
+            sample.iter().for_each(|&i| in_bag[i] = true);
+
+            let tree = fit_tree(&data, y, &sample, &params, &mut importance, &mut rng);
+
+            for i in (0..n).filter(|&i| !in_bag[i]) {
+                oob_sum[i] += tree.predict(&x[i]);
+                oob_cnt[i] += 1;
+            }
+            trees.push(tree);
+        }
+
+        let oob: Vec<(f64, f64)> = (0..n)
+            .filter(|&i| oob_cnt[i] > 0)
+            .map(|i| (oob_sum[i] / oob_cnt[i] as f64, y[i]))
+            .collect();
+
+        let mut ensemble = Self {
+            kind: EnsembleKind::RandomForest,
+            base_prediction: 0.0,
+            learning_rate: 1.0,
+            trees,
+            feature_importance: Self::normalize_importance(importance),
+            oob_score: None,
+            residual_std: 0.0,
+        };
+        ensemble.set_residual_stats(x, y, &oob);
+
+        Ok(ensemble)
+    }
+
+    /// Fit stochastic gradient boosting on squared loss
+    ///
+    /// Every round sees every training sample through its residual, so
+    /// samples left out of a round's subsample are not unseen; the score
+    /// comes from a validation split that no round trains on.
+    pub fn fit_gradient_boosting(
+        x: &[Vec<f64>],
+        y: &[f64],
+        config: &GradientBoostingConfig,
+    ) -> Result<Self, String> {
+        Self::check_input(x, y)?;
+        if !(config.subsample > 0.0 && config.subsample <= 1.0) {
+            return Err(format!("Subsample must be in (0, 1], got {}", config.subsample));
+        }
+        if !(0.0..1.0).contains(&config.validation_fraction) {
+            return Err(format!(
+                "Validation fraction must be in [0, 1), got {}",
+                config.validation_fraction
+            ));
+        }
+
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let mut shuffled: Vec<usize> = (0..x.len()).collect();
+        shuffled.shuffle(&mut rng);
+        let n_valid = (x.len() as f64 * config.validation_fraction).round() as usize;
+        // Keep at least two training samples
+        let n_valid = if x.len() - n_valid < 2 { 0 } else { n_valid };
+        let (valid_idx, train_idx) = shuffled.split_at(n_valid);
+        let x_train: Vec<Vec<f64>> = train_idx.iter().map(|&i| x[i].clone()).collect();
+        let y_train: Vec<f64> = train_idx.iter().map(|&i| y[i]).collect();
+        let validation: Vec<(&[f64], f64)> = valid_idx.iter().map(|&i| (x[i].as_slice(), y[i])).collect();
+        let (x, y) = (x_train.as_slice(), y_train.as_slice());
+
+        let n = x.len();
+        let n_features = x[0].len();
+        let data = BinnedData::new(x, config.max_bins);
+        let params = TreeParams {
+            max_depth: config.max_depth,
+            min_samples_leaf: config.min_samples_leaf.max(1),
+            features_per_split: n_features,
+        };
+
+        let base_prediction = y.iter().sum::<f64>() / n as f64;
+        let mut current = vec![base_prediction; n];
+        let mut importance = vec![0.0; n_features];
+        let mut trees = Vec::with_capacity(config.n_estimators);
+
+        let sample_size = ((n as f64 * config.subsample).round() as usize).clamp(1, n);
+        let mut order: Vec<usize> = (0..n).collect();
+
+        for _ in 0..config.n_estimators {
+            let residuals: Vec<f64> = y.iter().zip(&current).map(|(t, p)| t - p).collect();
+
+            order.shuffle(&mut rng);
+            let sample = &order[..sample_size];
+
+            let tree = fit_tree(&data, &residuals, sample, &params, &mut importance, &mut rng);
+            for (i, pred) in current.iter_mut().enumerate() {
+                *pred += config.learning_rate * tree.predict(&x[i]);
+            }
+            trees.push(tree);
+        }
+
+        let mut ensemble = Self {
+            kind: EnsembleKind::GradientBoosting,
+            base_prediction,
+            learning_rate: config.learning_rate,
+            trees,
+            feature_importance: Self::normalize_importance(importance),
+            oob_score: None,
+            residual_std: 0.0,
+        };
+
+        let held_out: Vec<(f64, f64)> = validation.iter().map(|(f, t)| (ensemble.predict(f), *t)).collect();
+        ensemble.set_residual_stats(x, y, &held_out);
+
+        Ok(ensemble)
+    }
+
+    pub fn predict(&self, features: &[f64]) -> f64 {
+        match self.kind {
+            EnsembleKind::RandomForest => {
+                self.trees.iter().map(|t| t.predict(features)).sum::<f64>()
+                    / self.trees.len().max(1) as f64
+            }
+            EnsembleKind::GradientBoosting => {
+                self.base_prediction
+                    + self.learning_rate
+                        * self.trees.iter().map(|t| t.predict(features)).sum::<f64>()
+            }
+        }
+    }
+
+    /// Prediction with a one-sigma uncertainty
+    ///
+    /// Random forests combine the spread of per-tree predictions with the
+    /// out-of-bag residual; boosting only has the residual estimate.
+    pub fn predict_with_uncertainty(&self, features: &[f64]) -> (f64, f64) {
+        let prediction = self.predict(features);
+
+        match self.kind {
+            EnsembleKind::RandomForest => {
+                let k = self.trees.len().max(1) as f64;
+                let variance = self
+                    .trees
+                    .iter()
+                    .map(|t| (t.predict(features) - prediction).powi(2))
+                    .sum::<f64>()
+                    / k;
+                (prediction, (variance + self.residual_std.powi(2) / k).sqrt())
+            }
+            EnsembleKind::GradientBoosting => (prediction, self.residual_std),
+        }
+    }
+
+    fn check_input(x: &[Vec<f64>], y: &[f64]) -> Result<(), String> {
+        if x.is_empty() {
+            return Err("No training data available".to_string());
+        }
+        if x.len() != y.len() {
+            return Err(format!("{} samples but {} targets", x.len(), y.len()));
+        }
+        let n_features = x[0].len();
+        if x.iter().any(|r| r.len() != n_features) {
+            return Err("Inconsistent feature vector lengths".to_string());
+        }
+        Ok(())
+    }
+
+    fn normalize_importance(importance: Vec<f64>) -> Vec<f64> {
+        let total: f64 = importance.iter().sum();
+        if total <= 0.0 {
+            return importance;
+        }
+        importance.into_iter().map(|v| v / total).collect()
+    }
+
+    /// Derive `oob_score` and `residual_std` from held-out (prediction, target)
+    /// pairs, falling back to training residuals when nothing was held out.
+    fn set_residual_stats(&mut self, x: &[Vec<f64>], y: &[f64], held_out: &[(f64, f64)]) {
+        let pairs: Vec<(f64, f64)> = if held_out.is_empty() {
+            x.iter().zip(y).map(|(f, t)| (self.predict(f), *t)).collect()
+        } else {
+            held_out.to_vec()
+        };
+
+        let n = pairs.len() as f64;
+        let mean = pairs.iter().map(|(_, t)| t).sum::<f64>() / n;
+        let ss_res: f64 = pairs.iter().map(|(p, t)| (t - p).powi(2)).sum();
+        let ss_tot: f64 = pairs.iter().map(|(_, t)| (t - mean).powi(2)).sum();
+
+        self.residual_std = (ss_res / n).sqrt();
+        if !held_out.is_empty() && ss_tot > 1e-12 {
+            self.oob_score = Some(1.0 - ss_res / ss_tot);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step_data() -> (Vec<Vec<f64>>, Vec<f64>) {
+        let x: Vec<Vec<f64>> = (0..200)
+            .map(|i| vec![(i % 20) as f64, (i / 20) as f64, ((i * 7) % 13) as f64])
+            .collect();
+        let y = x
+            .iter()
+            .map(|r| if r[0] < 10.0 { 1.0 } else { 5.0 } + 0.5 * r[1])
+            .collect();
+        (x, y)
+    }
+
+    #[test]
+    fn test_random_forest_fit() {
+        let (x, y) = step_data();
+        let config = RandomForestConfig { n_trees: 30, max_features: 1.0, ..Default::default() };
+        let forest = TreeEnsemble::fit_random_forest(&x, &y, &config).unwrap();
+
+        assert_eq!(forest.trees.len(), 30);
+        assert!((forest.predict(&[2.0, 4.0, 0.0]) - 3.0).abs() < 0.5);
+        assert!((forest.predict(&[15.0, 4.0, 0.0]) - 7.0).abs() < 0.5);
+        assert!(forest.oob_score.unwrap() > 0.9);
+
+        // The irrelevant third feature should carry the least importance
+        assert!(forest.feature_importance[0] > forest.feature_importance[2]);
+        assert!(forest.feature_importance[1] > forest.feature_importance[2]);
+    }
+
+    #[test]
+    fn test_gradient_boosting_fit() {
+        let (x, y) = step_data();
+        let config = GradientBoostingConfig { n_estimators: 100, ..Default::default() };
+        let model = TreeEnsemble::fit_gradient_boosting(&x, &y, &config).unwrap();
+
+        let (pred, sigma) = model.predict_with_uncertainty(&[15.0, 2.0, 0.0]);
+        assert!((pred - 6.0).abs() < 0.3);
+        assert!(sigma >= 0.0);
+        assert!(model.residual_std < 0.5);
+        assert!(model.feature_importance[0] > 0.5);
+    }
+
+    #[test]
+    fn test_boosting_score_is_held_out() {
+        // Mostly noise: the training fit is optimistic, the held-out one is not
+        let mut rng = StdRng::seed_from_u64(7);
+        let x: Vec<Vec<f64>> = (0..400).map(|_| vec![rng.gen::<f64>(), rng.gen::<f64>()]).collect();
+        let y: Vec<f64> = x.iter().map(|r| r[0] + rng.gen_range(-1.0..1.0)).collect();
+        let config = GradientBoostingConfig { validation_fraction: 0.25, ..Default::default() };
+        let model = TreeEnsemble::fit_gradient_boosting(&x, &y, &config).unwrap();
+
+        let mean = y.iter().sum::<f64>() / y.len() as f64;
+        let ss_res: f64 = x.iter().zip(&y).map(|(f, t)| (t - model.predict(f)).powi(2)).sum();
+        let ss_tot: f64 = y.iter().map(|t| (t - mean).powi(2)).sum();
+        let train_score = 1.0 - ss_res / ss_tot;
+
+        let held_out = model.oob_score.unwrap();
+        assert!(held_out < train_score - 0.1, "held out {} vs train {}", held_out, train_score);
+        assert!(model.residual_std > (ss_res / y.len() as f64).sqrt());
+    }
+
+    #[test]
+    fn test_forest_uncertainty() {
+        let x: Vec<Vec<f64>> = (0..100).map(|i| vec![i as f64 / 10.0]).collect();
+        let y: Vec<f64> = x.iter().map(|r| r[0].sin()).collect();
+        let forest = TreeEnsemble::fit_random_forest(&x, &y, &RandomForestConfig::default()).unwrap();
+
+        let (pred, sigma) = forest.predict_with_uncertainty(&[5.0]);
+        assert!((pred - 5.0f64.sin()).abs() < 0.2);
+        assert!(sigma > 0.0 && sigma < 0.5);
+        assert!(TreeEnsemble::fit_random_forest(&[], &[], &RandomForestConfig::default()).is_err());
+    }
+}
@@ -13,10 +13,13 @@ use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::{RwLock, Semaphore};
+use tokio::sync::{Mutex, RwLock, Semaphore};
 use tracing::{info, warn, error};
 use uuid::Uuid;
 use materials_core::Material;
+use materials_core::Composition;
+use materials_core::material::Structure;
+use materials_core::structure_matcher::{StructureFingerprint, StructureMatcher};
 use crate::MaterialDatabase;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -47,6 +50,9 @@ pub struct IngestionResult {
     pub successful: u64,
     pub failed: u64,
     pub duplicates: u64,
+    /// Records recognized as an already stored crystal and attached to it
+    #[serde(default)]
+    pub linked: u64,
     pub validation_errors: u64,
     pub elapsed_seconds: f64,
 }
@@ -61,12 +67,35 @@ pub struct IngestionProgress {
     pub progress_percent: f64,
 }
 
+/// A stored material as seen by deduplication
+#[derive(Debug, Clone)]
+struct IndexedMaterial {
+    id: Uuid,
+    /// "source:id" references, as in `Metadata::source_ids`
+    references: Vec<String>,
+    structure: Option<Structure>,
+    fingerprint: Option<StructureFingerprint>,
+}
+
+/// Stored materials of one reduced formula, locked on their own so records
+/// of different formulas are matched and written concurrently
+type FormulaShard = Arc<Mutex<Vec<IndexedMaterial>>>;
+
+/// Stored materials keyed by reduced formula
+#[derive(Default)]
+struct DedupIndex {
+    /// Whether materials already in the database have been loaded
+    seeded: bool,
+    formulas: HashMap<String, FormulaShard>,
+}
+
 pub struct ETLPipeline {
     // Database connections
     db: Arc<dyn MaterialDatabase>,
 
-    // Deduplication cache
-    fingerprints: Arc<RwLock<HashMap<String, Uuid>>>,
+    // Deduplication cache and the matcher deciding cross-source duplicates
+    index: Arc<RwLock<DedupIndex>>,
+    matcher: Arc<StructureMatcher>,
 
     // Progress tracking
     progress: Arc<RwLock<HashMap<DataSource, IngestionProgress>>>,
@@ -81,7 +110,8 @@ impl ETLPipeline {
     pub fn new(db: Arc<dyn MaterialDatabase>, max_workers: usize) -> Self {
         Self {
             db,
-            fingerprints: Arc::new(RwLock::new(HashMap::new())),
+            index: Arc::new(RwLock::new(DedupIndex::default())),
+            matcher: Arc::new(StructureMatcher::default()),
             progress: Arc::new(RwLock::new(HashMap::new())),
             semaphore: Arc::new(Semaphore::new(max_workers)),
             batch_size: 1000,
@@ -89,6 +119,12 @@ impl ETLPipeline {
         }
     }
 
+    /// Use other tolerances for recognizing the same crystal across sources
+    pub fn with_matcher(mut self, matcher: StructureMatcher) -> Self {
+        self.matcher = Arc::new(matcher);
+        self
+    }
+
     /// Ingest data from a source
     pub async fn ingest(
         &self,
@@ -100,6 +136,8 @@ impl ETLPipeline {
 
         info!("🌍 Starting ingestion from {:?}: {} records", source, total);
 
+        self.seed_index().await?;
+
         // Initialize progress
         {
             let mut progress = self.progress.write().await;
@@ -116,6 +154,7 @@ impl ETLPipeline {
         let mut successful = 0u64;
         let mut failed = 0u64;
         let mut duplicates = 0u64;
+        let mut linked = 0u64;
         let mut validation_errors = 0u64;
 
         // Process in batches
@@ -125,11 +164,12 @@ impl ETLPipeline {
             for record in chunk {
                 let permit = self.semaphore.clone().acquire_owned().await.unwrap();
                 let db = self.db.clone();
-                let fingerprints = self.fingerprints.clone();
+                let index = self.index.clone();
+                let matcher = self.matcher.clone();
                 let record = record.clone();
 
                 let handle = tokio::spawn(async move {
-                    let result = Self::process_record(db, fingerprints, record).await;
+                    let result = Self::process_record(db, index, matcher, record).await;
                     drop(permit);
                     result
                 });
@@ -142,6 +182,7 @@ impl ETLPipeline {
                 match handle.await {
                     Ok(Ok(ProcessResult::Success)) => successful += 1,
                     Ok(Ok(ProcessResult::Duplicate)) => duplicates += 1,
+                    Ok(Ok(ProcessResult::Linked)) => linked += 1,
                     Ok(Ok(ProcessResult::ValidationError)) => validation_errors += 1,
                     Ok(Err(_)) | Err(_) => failed += 1,
                 }
@@ -172,52 +213,145 @@ impl ETLPipeline {
             successful,
             failed,
             duplicates,
+            linked,
             validation_errors,
             elapsed_seconds: elapsed,
         };
 
         info!("✅ Ingestion complete from {:?}:", source);
-        info!("   Total: {}, Success: {}, Failed: {}, Duplicates: {}, Validation Errors: {}",
-              total, successful, failed, duplicates, validation_errors);
+        info!("   Total: {}, Success: {}, Failed: {}, Duplicates: {}, Linked: {}, Validation Errors: {}",
+              total, successful, failed, duplicates, linked, validation_errors);
         info!("   Time: {:.2}s, Rate: {:.0} records/s",
               elapsed, total as f64 / elapsed);
 
         Ok(result)
     }
 
+    /// Load the materials already in the database into the deduplication
+    /// index, once per pipeline (or after `clear_cache`)
+    async fn seed_index(&self) -> Result<()> {
+        let mut index = self.index.write().await;
+        if index.seeded {
+            return Ok(());
+        }
+
+        let mut offset = 0i64;
+        let mut seeded = 0usize;
+        loop {
+            let page = self
+                .db
+                .list_materials(self.batch_size as i64, offset)
+                .await
+                .map_err(|e| anyhow!("Database error: {}", e))?;
+            let count = page.len();
+
+            for material in page {
+                let Ok(composition) = Composition::parse(&material.formula) else {
+                    continue;
+                };
+                if composition.is_empty() {
+                    continue;
+                }
+                let structure = (!material.structure.sites.is_empty()).then_some(material.structure);
+                let fingerprint = structure.as_ref().and_then(|s| self.matcher.fingerprint(s).ok());
+                let shard = index.formulas.entry(composition.reduced_formula()).or_default();
+                shard.lock().await.push(IndexedMaterial {
+                    id: material.id,
+                    references: material.metadata.source_ids,
+                    structure,
+                    fingerprint,
+                });
+                seeded += 1;
+            }
+
+            if count < self.batch_size {
+                break;
+            }
+            offset += count as i64;
+        }
+
+        index.seeded = true;
+        info!("Deduplication index seeded with {} stored materials", seeded);
+        Ok(())
+    }
+
     /// Process a single record
+    ///
+    /// A record is a duplicate when its source already delivered the same
+    /// ID, or the same formula without structures to tell polymorphs apart.
+    /// A record whose structure matches a stored one of the same reduced
+    /// formula (from any source) is linked to that material instead of
+    /// creating another row.
     async fn process_record(
         db: Arc<dyn MaterialDatabase>,
-        fingerprints: Arc<RwLock<HashMap<String, Uuid>>>,
+        index: Arc<RwLock<DedupIndex>>,
+        matcher: Arc<StructureMatcher>,
         record: RawMaterialData,
     ) -> Result<ProcessResult> {
         // Validate record
-        if let Err(e) = Self::validate_record(&record) {
-            warn!("Validation error for {}: {}", record.source_id, e);
-            return Ok(ProcessResult::ValidationError);
-        }
+        let composition = match Self::validate_record(&record) {
+            Ok(composition) => composition,
+            Err(e) => {
+                warn!("Validation error for {}: {}", record.source_id, e);
+                return Ok(ProcessResult::ValidationError);
+            }
+        };
 
-        // Generate fingerprint for deduplication
-        let fingerprint = Self::generate_fingerprint(&record);
+        let key = composition.reduced_formula();
+        let structure = Self::parse_structure(&record);
+        let fingerprint = structure.as_ref().and_then(|s| matcher.fingerprint(s).ok());
 
-        // Check for duplicates
-        {
-            let fps = fingerprints.read().await;
-            if fps.contains_key(&fingerprint) {
+        // Only this formula stays locked through matching and the database
+        // write, so concurrent records of one crystal cannot both create rows
+        let shard = {
+            let mut index = index.write().await;
+            index.formulas.entry(key).or_default().clone()
+        };
+        let mut candidates = shard.lock().await;
+
+        let reference = Self::source_reference(&record);
+        let source_prefix = format!("{:?}:", record.source);
+        let mut canonical = None;
+        for (position, stored) in candidates.iter().enumerate() {
+            if stored.references.contains(&reference) {
                 return Ok(ProcessResult::Duplicate);
             }
+            match (&stored.structure, &structure, &stored.fingerprint, &fingerprint) {
+                (Some(a), Some(b), Some(fa), Some(fb)) => {
+                    if canonical.is_none() && matcher.may_match(fa, fb) && matcher.fit(a, b) {
+                        canonical = Some(position);
+                    }
+                }
+                (None, None, _, _)
+                    if stored.references.iter().any(|r| r.starts_with(&source_prefix)) =>
+                {
+                    return Ok(ProcessResult::Duplicate);
+                }
+                _ => {}
+            }
         }
 
-        // Transform to Material
-        let material = Self::transform_to_material(record)?;
+        if let Some(position) = canonical {
+            let stored = &mut candidates[position];
+            Self::link_record(db.as_ref(), stored.id, record).await?;
+            stored.references.push(reference);
+            return Ok(ProcessResult::Linked);
+        }
+
+        let mut material = Self::transform_to_material(record)?;
+        if let Some(structure) = &structure {
+            material.structure = structure.clone();
+        }
 
         // Store in database
         match db.create_material(&material).await {
             Ok(id) => {
-                // Store fingerprint
-                let mut fps = fingerprints.write().await;
-                fps.insert(fingerprint, id);
-
+                candidates.push(IndexedMaterial {
+                    id,
+                    references: vec![reference],
+                    structure,
+                    fingerprint,
+                });
                 Ok(ProcessResult::Success)
             }
             Err(e) => {
@@ -227,47 +361,66 @@ impl ETLPipeline {
         }
     }
 
-    /// Validate a record
-    fn validate_record(record: &RawMaterialData) -> Result<()> {
+    /// Attach a record to the stored material it duplicates
+    async fn link_record(db: &dyn MaterialDatabase, id: Uuid, record: RawMaterialData) -> Result<()> {
+        let mut material = db
+            .get_material(id)
+            .await
+            .map_err(|e| anyhow!("Database error: {}", e))?
+            .ok_or_else(|| anyhow!("Linked material {} not found", id))?;
+
+        let reference = Self::source_reference(&record);
+        if !material.metadata.source_ids.contains(&reference) {
+            material.metadata.source_ids.push(reference.clone());
+        }
+
+        // Values already stored win; the new source only fills gaps
+        let linked = Self::transform_to_material(record)?;
+        for (key, value) in linked.metadata.extra {
+            material.metadata.extra.entry(key).or_insert(value);
+        }
+        material.updated_at = chrono::Utc::now();
+
+        info!("🔗 Linked {} to material {}", reference, id);
+        db.update_material(id, &material)
+            .await
+            .map_err(|e| anyhow!("Database error: {}", e))
+    }
+
+    /// Validate a record, returning its composition
+    fn validate_record(record: &RawMaterialData) -> Result<Composition> {
         // Check formula is not empty
         if record.formula.trim().is_empty() {
             return Err(anyhow!("Empty formula"));
         }
 
-        // Basic formula validation
-        if !Self::is_valid_formula(&record.formula) {
-            return Err(anyhow!("Invalid formula format"));
+        let composition = Composition::parse(&record.formula)
+            .map_err(|e| anyhow!("Invalid formula: {}", e))?;
+        if composition.is_empty() {
+            return Err(anyhow!("Formula has no elements"));
         }
 
-        Ok(())
+        Ok(composition)
     }
 
-    /// Check if formula is valid (basic check)
-    fn is_valid_formula(formula: &str) -> bool {
-        // Must contain at least one letter
-        formula.chars().any(|c| c.is_alphabetic())
+    /// Structure of a record, when given in the `materials_core` layout
+    fn parse_structure(record: &RawMaterialData) -> Option<Structure> {
+        let structure: Structure = serde_json::from_value(record.structure.clone()?).ok()?;
+        (!structure.sites.is_empty()).then_some(structure)
     }
 
-    /// Generate fingerprint for deduplication
-    fn generate_fingerprint(record: &RawMaterialData) -> String {
-        // Normalize formula (remove spaces, sort elements)
-        let normalized = Self::normalize_formula(&record.formula);
-
-        // Combine with source for uniqueness
-        format!("{:?}:{}", record.source, normalized)
-    }
-
-    /// Normalize formula for comparison
-    fn normalize_formula(formula: &str) -> String {
-        // Remove whitespace and convert to lowercase
-        formula.replace(" ", "").to_lowercase()
+    /// "source:id" reference kept in `Metadata::source_ids`
+    fn source_reference(record: &RawMaterialData) -> String {
+        format!("{:?}:{}", record.source, record.source_id)
     }
 
     /// Transform raw data to Material
     fn transform_to_material(record: RawMaterialData) -> Result<Material> {
+        let reference = Self::source_reference(&record);
         let mut material = Material::new(record.formula);
 
         // Add source metadata
+        material.metadata.source_ids = vec![reference];
         material.metadata.source = format!("{:?}", record.source);
         material.metadata.source_id = Some(record.source_id);
 
@@ -298,9 +451,9 @@ impl ETLPipeline {
         self.progress.read().await.values().cloned().collect()
     }
 
-    /// Clear deduplication cache
+    /// Clear deduplication cache; the next ingestion reloads it from the database
     pub async fn clear_cache(&self) {
-        self.fingerprints.write().await.clear();
+        *self.index.write().await = DedupIndex::default();
         info!("Deduplication cache cleared");
     }
 
@@ -317,6 +470,7 @@ impl ETLPipeline {
 enum ProcessResult {
     Success,
     Duplicate,
+    Linked,
     ValidationError,
 }
 
@@ -425,3 +579,152 @@ impl SourceAdapter for PubChemAdapter {
         Ok(100_000_000) // PubChem has ~100M compounds
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use materials_core::material::Site;
+
+    /// Materials kept in memory; writes yield first so concurrent
+    /// records interleave
+    #[derive(Default)]
+    struct MemoryDatabase {
+        materials: std::sync::Mutex<HashMap<Uuid, Material>>,
+    }
+
+    impl MemoryDatabase {
+        fn all(&self) -> Vec<Material> {
+            self.materials.lock().unwrap().values().cloned().collect()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl MaterialDatabase for MemoryDatabase {
+        async fn create_material(&self, material: &Material) -> crate::Result<Uuid> {
+            tokio::task::yield_now().await;
+            self.materials.lock().unwrap().insert(material.id, material.clone());
+            Ok(material.id)
+        }
+
+        async fn get_material(&self, id: Uuid) -> crate::Result<Option<Material>> {
+            Ok(self.materials.lock().unwrap().get(&id).cloned())
+        }
+
+        async fn update_material(&self, id: Uuid, material: &Material) -> crate::Result<()> {
+            tokio::task::yield_now().await;
+            self.materials.lock().unwrap().insert(id, material.clone());
+            Ok(())
+        }
+
+        async fn delete_material(&self, id: Uuid) -> crate::Result<()> {
+            self.materials.lock().unwrap().remove(&id);
+            Ok(())
+        }
+
+        async fn list_materials(&self, limit: i64, offset: i64) -> crate::Result<Vec<Material>> {
+            let mut materials = self.all();
+            materials.sort_by_key(|m| m.id);
+            Ok(materials.into_iter().skip(offset as usize).take(limit as usize).collect())
+        }
+    }
+
+    /// Rock-salt MgO primitive cell with lattice constant `a`, origin shifted by `shift`
+    fn magnesia(source: DataSource, source_id: &str, formula: &str, a: f64, shift: f64) -> RawMaterialData {
+        let site = |element: &str, coords| Site { element: element.to_string(), coords, magmom: None, occupancy: 1.0 };
+        let structure = Structure {
+            lattice: [[a, 0.0, 0.0], [0.0, a, 0.0], [0.0, 0.0, a]],
+            sites: vec![site("Mg", [shift, 0.0, 0.0]), site("O", [0.5 + shift, 0.5, 0.5])],
+            space_group: None,
+            crystal_system: None,
+        };
+        RawMaterialData {
+            source,
+            source_id: source_id.to_string(),
+            formula: formula.to_string(),
+            structure: Some(serde_json::to_value(structure).unwrap()),
+            properties: HashMap::from([("band_gap".to_string(), serde_json::json!(7.8))]),
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cross_source_records_link() {
+        let db = Arc::new(MemoryDatabase::default());
+        let pipeline = ETLPipeline::new(db.clone(), 4);
+
+        let result = pipeline
+            .ingest(DataSource::MaterialsProject, vec![magnesia(DataSource::MaterialsProject, "mp-1265", "MgO", 4.21, 0.0)])
+            .await
+            .unwrap();
+        assert_eq!(result.successful, 1);
+
+        // Same crystal, other setting and formula spelling, plus an invalid record
+        let result = pipeline
+            .ingest(DataSource::OQMD, vec![
+                magnesia(DataSource::OQMD, "oqmd-7", "O2Mg2", 4.25, 0.3),
+                magnesia(DataSource::OQMD, "bad", "Xx2", 4.2, 0.0),
+            ])
+            .await
+            .unwrap();
+        assert_eq!((result.successful, result.linked, result.validation_errors), (0, 1, 1));
+
+        let stored = db.all();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(
+            stored[0].metadata.source_ids,
+            vec!["MaterialsProject:mp-1265".to_string(), "OQMD:oqmd-7".to_string()]
+        );
+        assert_eq!(stored[0].metadata.source, "MaterialsProject");
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_same_formula_records() {
+        let db = Arc::new(MemoryDatabase::default());
+        let pipeline = ETLPipeline::new(db.clone(), 4);
+
+        // One batch, processed concurrently: the formula lock lets only one create a row
+        let records = vec![
+            magnesia(DataSource::Custom("lab".to_string()), "a", "MgO", 4.21, 0.0),
+            magnesia(DataSource::Custom("lab".to_string()), "b", "MgO", 4.22, 0.1),
+            magnesia(DataSource::Custom("lab".to_string()), "c", "Mg2O2", 4.20, 0.2),
+        ];
+        let result = pipeline.ingest(DataSource::Custom("lab".to_string()), records).await.unwrap();
+        assert_eq!((result.successful, result.linked, result.failed), (1, 2, 0));
+
+        let stored = db.all();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].metadata.source_ids.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_index_seeded_from_database() {
+        let db = Arc::new(MemoryDatabase::default());
+        ETLPipeline::new(db.clone(), 4)
+            .ingest(DataSource::MaterialsProject, vec![magnesia(DataSource::MaterialsProject, "mp-1265", "MgO", 4.21, 0.0)])
+            .await
+            .unwrap();
+
+        // A fresh pipeline knows what is already stored
+        let pipeline = ETLPipeline::new(db.clone(), 4);
+        let again = vec![magnesia(DataSource::MaterialsProject, "mp-1265", "MgO", 4.21, 0.0)];
+        let result = pipeline.ingest(DataSource::MaterialsProject, again.clone()).await.unwrap();
+        assert_eq!((result.successful, result.duplicates), (0, 1));
+        let result = pipeline
+            .ingest(DataSource::AFLOW, vec![magnesia(DataSource::AFLOW, "aflow-3", "MgO", 4.24, 0.25)])
+            .await
+            .unwrap();
+        assert_eq!(result.linked, 1);
+
+        // Clearing the cache re-seeds, links included
+        pipeline.clear_cache().await;
+        let result = pipeline.ingest(DataSource::MaterialsProject, again).await.unwrap();
+        assert_eq!(result.duplicates, 1);
+        let result = pipeline
+            .ingest(DataSource::AFLOW, vec![magnesia(DataSource::AFLOW, "aflow-3", "MgO", 4.24, 0.25)])
+            .await
+            .unwrap();
+        assert_eq!(result.duplicates, 1);
+        assert_eq!(db.all().len(), 1);
+        assert_eq!(db.all()[0].metadata.source_ids.len(), 2);
+    }
+}
@@ -0,0 +1,202 @@
+//! Recommendation interaction persistence
+//!
+//! `RecommendationEngine` keeps its interaction log in memory; this module
+//! appends it to an [`InteractionStore`] (PostgreSQL in production,
+//! [`InMemoryInteractionStore`] in tests) in batches and restores it on
+//! startup.
+
+use crate::Result;
+use chrono::{DateTime, SubsecRound, Utc};
+use materials_core::recommendations::{InteractionRecord, RecommendationEngine};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::info;
+use uuid::Uuid;
+
+/// Append-only interaction log
+///
+/// Records are keyed by (user, material, interaction type, timestamp);
+/// appending one again is a no-op, so pushes can safely overlap.
+#[async_trait::async_trait]
+pub trait InteractionStore: Send + Sync {
+    /// Returns how many records were new
+    async fn append(&self, records: &[InteractionRecord]) -> Result<usize>;
+
+    /// Records strictly after `since` (all when `None`), oldest first
+    async fn load(&self, since: Option<DateTime<Utc>>) -> Result<Vec<InteractionRecord>>;
+
+    async fn load_user(&self, user_id: Uuid) -> Result<Vec<InteractionRecord>>;
+}
+
+/// Incremental sync between a `RecommendationEngine` and an `InteractionStore`
+pub struct InteractionSync {
+    store: Arc<dyn InteractionStore>,
+    batch_size: usize,
+    /// Latest timestamp known to be stored
+    synced_until: RwLock<Option<DateTime<Utc>>>,
+}
+
+impl InteractionSync {
+    pub fn new(store: Arc<dyn InteractionStore>) -> Self {
+        Self { store, batch_size: 1000, synced_until: RwLock::new(None) }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn store(&self) -> &Arc<dyn InteractionStore> {
+        &self.store
+    }
+
+    /// Append interactions recorded since the last push or restore; returns
+    /// how many were new to the store
+    pub async fn push(&self, engine: &RecommendationEngine) -> Result<usize> {
+        let mut synced_until = self.synced_until.write().await;
+        // Records sharing the last synced timestamp are re-sent; the store skips duplicates
+        let pending: Vec<InteractionRecord> = engine.interactions().await
+            .into_iter()
+            .filter(|r| synced_until.map_or(true, |t| r.timestamp >= t))
+            .collect();
+
+        let mut written = 0;
+        for batch in pending.chunks(self.batch_size) {
+            written += self.store.append(batch).await?;
+        }
+        if let Some(latest) = pending.iter().map(|r| r.timestamp).max() {
+            *synced_until = Some(latest);
+        }
+        info!("Pushed {} new interactions ({} pending)", written, pending.len());
+        Ok(written)
+    }
+
+    /// Replace the engine's log with the stored one
+    pub async fn restore(&self, engine: &RecommendationEngine) -> Result<usize> {
+        let records = self.store.load(None).await?;
+        *self.synced_until.write().await = records.iter().map(|r| r.timestamp).max();
+        Ok(engine.load_interactions(records).await)
+    }
+}
+
+// ============================================================================
+// IN-PROCESS FAKE
+// ============================================================================
+
+/// (user, material, interaction type, timestamp)
+type RecordKey = (Uuid, Uuid, &'static str, DateTime<Utc>);
+
+/// `InteractionStore` held in memory, for tests and single-process use
+///
+/// Timestamps are truncated to microseconds on append, as PostgreSQL
+/// stores them, so round trips behave the same against both.
+#[derive(Default)]
+pub struct InMemoryInteractionStore {
+    records: RwLock<Vec<InteractionRecord>>,
+    keys: RwLock<HashSet<RecordKey>>,
+    /// Number of append calls, to check batching
+    writes: RwLock<usize>,
+}
+
+impl InMemoryInteractionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn len(&self) -> usize {
+        self.records.read().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.records.read().await.is_empty()
+    }
+
+    pub async fn write_calls(&self) -> usize {
+        *self.writes.read().await
+    }
+}
+
+#[async_trait::async_trait]
+impl InteractionStore for InMemoryInteractionStore {
+    async fn append(&self, records: &[InteractionRecord]) -> Result<usize> {
+        let mut stored = self.records.write().await;
+        let mut keys = self.keys.write().await;
+        *self.writes.write().await += 1;
+
+        let mut added = 0;
+        for record in records {
+            let timestamp = record.timestamp.trunc_subsecs(6);
+            if keys.insert((record.user_id, record.material_id, record.interaction_type.name(), timestamp)) {
+                stored.push(InteractionRecord { timestamp, ..record.clone() });
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    async fn load(&self, since: Option<DateTime<Utc>>) -> Result<Vec<InteractionRecord>> {
+        let mut records: Vec<InteractionRecord> = self.records.read().await
+            .iter()
+            .filter(|r| since.map_or(true, |t| r.timestamp > t))
+            .cloned()
+            .collect();
+        records.sort_by_key(|r| r.timestamp);
+        Ok(records)
+    }
+
+    async fn load_user(&self, user_id: Uuid) -> Result<Vec<InteractionRecord>> {
+        let mut records: Vec<InteractionRecord> = self.records.read().await
+            .iter()
+            .filter(|r| r.user_id == user_id)
+            .cloned()
+            .collect();
+        records.sort_by_key(|r| r.timestamp);
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use materials_core::recommendations::InteractionType;
+
+    #[tokio::test]
+    async fn test_push_is_incremental_and_restorable() {
+        let engine = RecommendationEngine::new();
+        let user = Uuid::new_v4();
+        for _ in 0..5 {
+            engine.record_interaction(user, Uuid::new_v4(), "used".to_string(), None).await.unwrap();
+        }
+
+        let store = Arc::new(InMemoryInteractionStore::new());
+        let sync = InteractionSync::new(store.clone()).with_batch_size(2);
+        assert_eq!(sync.push(&engine).await.unwrap(), 5);
+        assert_eq!(store.write_calls().await, 3);
+
+        // Nothing new the second time; one new record after another interaction
+        assert_eq!(sync.push(&engine).await.unwrap(), 0);
+        engine.record_interaction(user, Uuid::new_v4(), "rated".to_string(), Some(4.0)).await.unwrap();
+        assert_eq!(sync.push(&engine).await.unwrap(), 1);
+        assert_eq!(store.len().await, 6);
+
+        // Stored timestamps keep microseconds, like PostgreSQL's
+        let restored = RecommendationEngine::new();
+        assert_eq!(sync.restore(&restored).await.unwrap(), 6);
+        let truncated: Vec<InteractionRecord> = engine.interactions().await
+            .into_iter()
+            .map(|r| InteractionRecord { timestamp: r.timestamp.trunc_subsecs(6), ..r })
+            .collect();
+        assert_eq!(restored.interactions().await, truncated);
+        assert!(restored.interactions().await.iter().all(|r| r.timestamp.timestamp_subsec_nanos() % 1000 == 0));
+
+        // A record differing only below a microsecond is the same row
+        let mut record = truncated[0].clone();
+        record.timestamp += chrono::Duration::nanoseconds(300);
+        assert_eq!(store.append(&[record]).await.unwrap(), 0);
+        assert_eq!(sync.push(&restored).await.unwrap(), 0);
+
+        let history = store.load_user(user).await.unwrap();
+        assert_eq!(history.last().unwrap().interaction_type, InteractionType::Rated(4.0));
+    }
+}
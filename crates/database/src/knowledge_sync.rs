@@ -0,0 +1,427 @@
+//! Knowledge graph persistence
+//!
+//! `KnowledgeGraph` lives in memory; this module syncs it to a [`GraphStore`]
+//! (Neo4j in production, [`InMemoryGraphStore`] in tests) in batches, loads
+//! subgraphs back, and runs path-finding, community detection and
+//! centrality either on a loaded copy or pushed down to the store.
+
+use crate::Result;
+use materials_core::knowledge_graph::{KnowledgeEdge, KnowledgeGraph, KnowledgeNode, RelationType};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::info;
+use uuid::Uuid;
+
+/// Nodes and edges read back from a store
+#[derive(Debug, Clone, Default)]
+pub struct Subgraph {
+    pub nodes: Vec<KnowledgeNode>,
+    pub edges: Vec<KnowledgeEdge>,
+}
+
+impl Subgraph {
+    pub async fn into_graph(self) -> KnowledgeGraph {
+        KnowledgeGraph::from_parts(self.nodes, self.edges).await
+    }
+}
+
+/// Graph persistence with server-side graph queries
+///
+/// Edges are keyed by (from, to, relation type); writing one again updates
+/// its weight, confidence and metadata. Edges whose endpoints are missing
+/// are dropped.
+#[async_trait::async_trait]
+pub trait GraphStore: Send + Sync {
+    async fn upsert_nodes(&self, nodes: &[KnowledgeNode]) -> Result<()>;
+
+    async fn upsert_edges(&self, edges: &[KnowledgeEdge]) -> Result<()>;
+
+    /// Nodes within `depth` hops of `seeds` over `relations` (all when
+    /// `None`), with the edges among them
+    async fn load_subgraph(&self, seeds: &[Uuid], depth: usize, relations: Option<&[RelationType]>) -> Result<Subgraph>;
+
+    async fn load_all(&self) -> Result<Subgraph>;
+
+    /// Fewest-hop path over `relations`, at most `max_hops` long
+    async fn shortest_path(
+        &self,
+        from: Uuid,
+        to: Uuid,
+        max_hops: usize,
+        relations: Option<&[RelationType]>,
+    ) -> Result<Option<Vec<Uuid>>>;
+
+    async fn communities(&self) -> Result<Vec<Vec<Uuid>>>;
+
+    async fn centrality(&self) -> Result<HashMap<Uuid, f64>>;
+}
+
+/// Where graph algorithms run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionMode {
+    /// Load the relevant part of the graph and run `KnowledgeGraph` algorithms
+    InMemory,
+    /// Run in the store (Cypher and Graph Data Science for Neo4j)
+    Pushdown,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub nodes: usize,
+    pub edges: usize,
+    pub batches: usize,
+}
+
+/// Batched sync between a `KnowledgeGraph` and a `GraphStore`
+pub struct KnowledgeGraphSync {
+    store: Arc<dyn GraphStore>,
+    batch_size: usize,
+}
+
+impl KnowledgeGraphSync {
+    pub fn new(store: Arc<dyn GraphStore>) -> Self {
+        Self { store, batch_size: 500 }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn store(&self) -> &Arc<dyn GraphStore> {
+        &self.store
+    }
+
+    /// Write every node, then every edge
+    pub async fn push(&self, graph: &KnowledgeGraph) -> Result<SyncReport> {
+        let mut report = self.push_nodes(&graph.nodes().await).await?;
+        let edges = self.push_edges(&graph.edges().await).await?;
+        report.edges = edges.edges;
+        report.batches += edges.batches;
+        info!("Synced {} nodes and {} edges in {} batches", report.nodes, report.edges, report.batches);
+        Ok(report)
+    }
+
+    pub async fn push_nodes(&self, nodes: &[KnowledgeNode]) -> Result<SyncReport> {
+        let mut report = SyncReport::default();
+        for batch in nodes.chunks(self.batch_size) {
+            self.store.upsert_nodes(batch).await?;
+            report.nodes += batch.len();
+            report.batches += 1;
+        }
+        Ok(report)
+    }
+
+    pub async fn push_edges(&self, edges: &[KnowledgeEdge]) -> Result<SyncReport> {
+        let mut report = SyncReport::default();
+        for batch in edges.chunks(self.batch_size) {
+            self.store.upsert_edges(batch).await?;
+            report.edges += batch.len();
+            report.batches += 1;
+        }
+        Ok(report)
+    }
+
+    pub async fn load_subgraph(
+        &self,
+        seeds: &[Uuid],
+        depth: usize,
+        relations: Option<&[RelationType]>,
+    ) -> Result<KnowledgeGraph> {
+        Ok(self.store.load_subgraph(seeds, depth, relations).await?.into_graph().await)
+    }
+
+    pub async fn load_all(&self) -> Result<KnowledgeGraph> {
+        Ok(self.store.load_all().await?.into_graph().await)
+    }
+
+    pub async fn shortest_path(
+        &self,
+        from: Uuid,
+        to: Uuid,
+        max_hops: usize,
+        relations: Option<&[RelationType]>,
+        mode: ExecutionMode,
+    ) -> Result<Option<Vec<Uuid>>> {
+        match mode {
+            ExecutionMode::Pushdown => self.store.shortest_path(from, to, max_hops, relations).await,
+            ExecutionMode::InMemory => {
+                // Everything within reach of `from` is enough to find the path
+                let graph = self.load_subgraph(&[from], max_hops, relations).await?;
+                Ok(graph.find_path(from, to).await)
+            }
+        }
+    }
+
+    pub async fn communities(&self, mode: ExecutionMode) -> Result<Vec<Vec<Uuid>>> {
+        match mode {
+            ExecutionMode::Pushdown => self.store.communities().await,
+            ExecutionMode::InMemory => Ok(self.load_all().await?.detect_communities().await),
+        }
+    }
+
+    pub async fn centrality(&self, mode: ExecutionMode) -> Result<HashMap<Uuid, f64>> {
+        match mode {
+            ExecutionMode::Pushdown => self.store.centrality().await,
+            ExecutionMode::InMemory => Ok(self.load_all().await?.calculate_centrality().await),
+        }
+    }
+}
+
+pub(crate) fn allows(relations: Option<&[RelationType]>, relation: &RelationType) -> bool {
+    relations.map_or(true, |allowed| allowed.contains(relation))
+}
+
+// ============================================================================
+// IN-PROCESS FAKE
+// ============================================================================
+
+/// `GraphStore` held in memory, for tests and single-process use
+///
+/// Pushed-down queries run the `KnowledgeGraph` algorithms over the stored
+/// data, so results match what `ExecutionMode::InMemory` produces.
+#[derive(Default)]
+pub struct InMemoryGraphStore {
+    nodes: RwLock<HashMap<Uuid, KnowledgeNode>>,
+    edges: RwLock<EdgeTable>,
+    /// Number of upsert calls, to check batching
+    writes: RwLock<usize>,
+}
+
+impl InMemoryGraphStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn node_count(&self) -> usize {
+        self.nodes.read().await.len()
+    }
+
+    pub async fn edge_count(&self) -> usize {
+        self.edges.read().await.edges.len()
+    }
+
+    pub async fn write_calls(&self) -> usize {
+        *self.writes.read().await
+    }
+
+    async fn graph(&self) -> KnowledgeGraph {
+        let nodes = self.nodes.read().await.values().cloned().collect();
+        KnowledgeGraph::from_parts(nodes, self.edges.read().await.edges.clone()).await
+    }
+}
+
+/// Edges in insertion order, indexed by (from, to, relation type)
+#[derive(Default)]
+struct EdgeTable {
+    edges: Vec<KnowledgeEdge>,
+    positions: HashMap<(Uuid, Uuid, RelationType), usize>,
+}
+
+#[async_trait::async_trait]
+impl GraphStore for InMemoryGraphStore {
+    async fn upsert_nodes(&self, nodes: &[KnowledgeNode]) -> Result<()> {
+        let mut stored = self.nodes.write().await;
+        for node in nodes {
+            stored.insert(node.material_id, node.clone());
+        }
+        *self.writes.write().await += 1;
+        Ok(())
+    }
+
+    async fn upsert_edges(&self, edges: &[KnowledgeEdge]) -> Result<()> {
+        let nodes = self.nodes.read().await;
+        let mut guard = self.edges.write().await;
+        let stored = &mut *guard;
+        for edge in edges {
+            if !nodes.contains_key(&edge.from) || !nodes.contains_key(&edge.to) {
+                continue;
+            }
+            let key = (edge.from, edge.to, edge.relation_type.clone());
+            match stored.positions.get(&key) {
+                Some(&i) => stored.edges[i] = edge.clone(),
+                None => {
+                    stored.positions.insert(key, stored.edges.len());
+                    stored.edges.push(edge.clone());
+                }
+            }
+        }
+        *self.writes.write().await += 1;
+        Ok(())
+    }
+
+    async fn load_subgraph(&self, seeds: &[Uuid], depth: usize, relations: Option<&[RelationType]>) -> Result<Subgraph> {
+        let nodes = self.nodes.read().await;
+        let edges = self.edges.read().await;
+        let usable: Vec<&KnowledgeEdge> = edges.edges.iter().filter(|e| allows(relations, &e.relation_type)).collect();
+
+        let mut reached: HashSet<Uuid> = seeds.iter().copied().filter(|id| nodes.contains_key(id)).collect();
+        let mut frontier: HashSet<Uuid> = reached.clone();
+        for _ in 0..depth {
+            let mut next = HashSet::new();
+            for edge in &usable {
+                for (a, b) in [(edge.from, edge.to), (edge.to, edge.from)] {
+                    if frontier.contains(&a) && reached.insert(b) {
+                        next.insert(b);
+                    }
+                }
+            }
+            frontier = next;
+        }
+
+        Ok(Subgraph {
+            nodes: reached.iter().filter_map(|id| nodes.get(id).cloned()).collect(),
+            edges: usable.into_iter()
+                .filter(|e| reached.contains(&e.from) && reached.contains(&e.to))
+                .cloned()
+                .collect(),
+        })
+    }
+
+    async fn load_all(&self) -> Result<Subgraph> {
+        Ok(Subgraph {
+            nodes: self.nodes.read().await.values().cloned().collect(),
+            edges: self.edges.read().await.edges.clone(),
+        })
+    }
+
+    async fn shortest_path(
+        &self,
+        from: Uuid,
+        to: Uuid,
+        max_hops: usize,
+        relations: Option<&[RelationType]>,
+    ) -> Result<Option<Vec<Uuid>>> {
+        let edges = self.edges.read().await;
+        let mut adjacency: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        for edge in edges.edges.iter().filter(|e| allows(relations, &e.relation_type)) {
+            adjacency.entry(edge.from).or_default().push(edge.to);
+            adjacency.entry(edge.to).or_default().push(edge.from);
+        }
+
+        let mut parent: HashMap<Uuid, Uuid> = HashMap::new();
+        let mut queue = VecDeque::from([(from, 0usize)]);
+        let mut seen = HashSet::from([from]);
+        while let Some((current, hops)) = queue.pop_front() {
+            if current == to {
+                let mut path = vec![to];
+                while let Some(&p) = parent.get(path.last().expect("path is non-empty")) {
+                    path.push(p);
+                }
+                path.reverse();
+                return Ok(Some(path));
+            }
+            if hops == max_hops {
+                continue;
+            }
+            for &next in adjacency.get(&current).into_iter().flatten() {
+                if seen.insert(next) {
+                    parent.insert(next, current);
+                    queue.push_back((next, hops + 1));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    async fn communities(&self) -> Result<Vec<Vec<Uuid>>> {
+        Ok(self.graph().await.detect_communities().await)
+    }
+
+    async fn centrality(&self) -> Result<HashMap<Uuid, f64>> {
+        Ok(self.graph().await.calculate_centrality().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(formula: &str) -> KnowledgeNode {
+        KnowledgeNode {
+            material_id: Uuid::new_v4(),
+            formula: formula.to_string(),
+            properties: HashMap::from([("band_gap".to_string(), 1.5)]),
+            tags: vec!["oxide".to_string()],
+            importance_score: 0.5,
+        }
+    }
+
+    fn edge(from: &KnowledgeNode, to: &KnowledgeNode, relation_type: RelationType) -> KnowledgeEdge {
+        KnowledgeEdge {
+            from: from.material_id,
+            to: to.material_id,
+            relation_type,
+            weight: 1.0,
+            confidence: 0.9,
+            metadata: HashMap::new(),
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    /// Chain a - b - c - d plus e, linked to a only by a custom relation
+    async fn chain() -> (KnowledgeGraph, Vec<KnowledgeNode>) {
+        let nodes: Vec<KnowledgeNode> = ["Fe2O3", "Fe3O4", "FeO", "CoO", "NiO"].iter().map(|f| node(f)).collect();
+        let edges = vec![
+            edge(&nodes[0], &nodes[1], RelationType::SimilarComposition),
+            edge(&nodes[1], &nodes[2], RelationType::ContainsElement("Fe".to_string())),
+            edge(&nodes[2], &nodes[3], RelationType::Substitution),
+            edge(&nodes[0], &nodes[4], RelationType::Custom("same_paper".to_string())),
+        ];
+        (KnowledgeGraph::from_parts(nodes.clone(), edges).await, nodes)
+    }
+
+    #[tokio::test]
+    async fn test_batched_push_and_subgraph_load() {
+        let (graph, nodes) = chain().await;
+        let store = Arc::new(InMemoryGraphStore::new());
+        let sync = KnowledgeGraphSync::new(store.clone()).with_batch_size(2);
+
+        let report = sync.push(&graph).await.unwrap();
+        assert_eq!(report, SyncReport { nodes: 5, edges: 4, batches: 5 });
+        assert_eq!(store.write_calls().await, 5);
+
+        // Pushing again updates in place
+        sync.push(&graph).await.unwrap();
+        assert_eq!((store.node_count().await, store.edge_count().await), (5, 4));
+
+        let two_hops = sync.load_subgraph(&[nodes[0].material_id], 2, None).await.unwrap();
+        let mut formulas: Vec<String> = two_hops.nodes().await.into_iter().map(|n| n.formula).collect();
+        formulas.sort();
+        assert_eq!(formulas, vec!["Fe2O3", "Fe3O4", "FeO", "NiO"]);
+        assert_eq!(two_hops.edges().await.len(), 3);
+
+        let restored = two_hops.get_node(nodes[0].material_id).await.unwrap();
+        assert_eq!(restored.properties["band_gap"], 1.5);
+        assert_eq!(restored.tags, vec!["oxide"]);
+
+        let typed = sync.load_subgraph(&[nodes[0].material_id], 3, Some(&[RelationType::SimilarComposition])).await.unwrap();
+        assert_eq!(typed.nodes().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_queries_in_memory_and_pushed_down() {
+        let (graph, nodes) = chain().await;
+        let sync = KnowledgeGraphSync::new(Arc::new(InMemoryGraphStore::new()));
+        sync.push(&graph).await.unwrap();
+        let ids: Vec<Uuid> = nodes.iter().map(|n| n.material_id).collect();
+
+        for mode in [ExecutionMode::InMemory, ExecutionMode::Pushdown] {
+            let path = sync.shortest_path(ids[4], ids[3], 5, None, mode).await.unwrap();
+            assert_eq!(path, Some(vec![ids[4], ids[0], ids[1], ids[2], ids[3]]));
+            assert_eq!(sync.shortest_path(ids[4], ids[3], 3, None, mode).await.unwrap(), None);
+
+            let without_custom = [
+                RelationType::SimilarComposition,
+                RelationType::ContainsElement("Fe".to_string()),
+                RelationType::Substitution,
+            ];
+            assert_eq!(sync.shortest_path(ids[4], ids[3], 5, Some(&without_custom), mode).await.unwrap(), None);
+
+            let communities = sync.communities(mode).await.unwrap();
+            assert_eq!(communities.iter().map(Vec::len).sum::<usize>(), 5);
+            assert_eq!(sync.centrality(mode).await.unwrap().len(), 5);
+        }
+    }
+}
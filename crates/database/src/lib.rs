@@ -1,9 +1,9 @@
 //! Materials-Simulato-R Database Layer
 //!
 //! This crate provides database abstraction and implementations for:
-//! - PostgreSQL (relational data)
+//! - PostgreSQL (relational data, recommendation interactions)
 //! - MongoDB (flexible properties)
-//! - Neo4j (similarity networks)
+//! - Neo4j (similarity networks, knowledge graph persistence)
 //! - Redis (caching, sessions, queues)
 
 #![allow(dead_code, unused_imports)]
@@ -11,6 +11,8 @@
 pub mod postgres;
 pub mod mongo;
 pub mod neo4j_db;
+pub mod knowledge_sync;
+pub mod interaction_store;
 pub mod redis_cache;
 pub mod smart_cache;
 pub mod etl_pipeline;
@@ -2,12 +2,14 @@
 //!
 //! Neo4j is used for storing and querying material similarity networks
 
+use crate::knowledge_sync::{GraphStore, Subgraph};
 use crate::{Error, MaterialDatabase, Result};
+use materials_core::knowledge_graph::{KnowledgeEdge, KnowledgeNode, RelationType};
 use materials_core::Material;
-use neo4rs::{Graph, query, ConfigBuilder, Node};
+use neo4rs::{BoltType, Graph, query, ConfigBuilder, Node, Row};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 /// Neo4j graph database for material similarity networks
 pub struct Neo4jDatabase {
@@ -118,6 +120,323 @@ impl Neo4jDatabase {
     }
 }
 
+// === Knowledge graph persistence ===
+//
+// Knowledge nodes are `:Material` nodes carrying formula, tags, importance
+// and a JSON-encoded property map. Each `RelationType` becomes a
+// relationship type (`RelationType::label`), with the element or custom name
+// in a `detail` property. Relationship types cannot be query parameters, so
+// only those fixed labels are ever formatted into Cypher.
+
+/// Cypher relationship pattern for a relation filter, e.g. `:SUBSTITUTION|SAME_METHOD`;
+/// without a filter every knowledge relationship type, but not `SIMILAR_TO`
+fn relation_pattern(relations: Option<&[RelationType]>) -> String {
+    let mut labels: Vec<&str> = match relations {
+        None => RelationType::LABELS.to_vec(),
+        Some(relations) => relations.iter().map(RelationType::label).collect(),
+    };
+    labels.sort_unstable();
+    labels.dedup();
+    format!(":{}", labels.join("|"))
+}
+
+/// `[label, detail]` pairs a relation filter accepts, matched against
+/// `[type(r), coalesce(r.detail, '')]`
+fn relation_pairs(relations: Option<&[RelationType]>) -> Vec<Vec<String>> {
+    relations.unwrap_or(&[]).iter()
+        .map(|r| vec![r.label().to_string(), r.detail().unwrap_or("").to_string()])
+        .collect()
+}
+
+/// Cypher condition restricting relationship `r` to the filter
+fn edge_condition(relations: Option<&[RelationType]>) -> &'static str {
+    match relations {
+        None => "true",
+        Some(_) => "[type(r), coalesce(r.detail, '')] IN $pairs",
+    }
+}
+
+/// Cypher condition restricting every relationship of path `p` to the filter
+fn relation_condition(relations: Option<&[RelationType]>) -> &'static str {
+    match relations {
+        None => "true",
+        Some(_) => "ALL(r IN relationships(p) WHERE [type(r), coalesce(r.detail, '')] IN $pairs)",
+    }
+}
+
+fn node_row(node: &KnowledgeNode) -> Result<HashMap<String, BoltType>> {
+    // Sorted keys keep the stored JSON stable between syncs
+    let properties: BTreeMap<&String, &f64> = node.properties.iter().collect();
+    Ok(HashMap::from([
+        ("id".to_string(), node.material_id.to_string().into()),
+        ("formula".to_string(), node.formula.clone().into()),
+        ("properties".to_string(), serde_json::to_string(&properties)?.into()),
+        ("tags".to_string(), node.tags.clone().into()),
+        ("importance_score".to_string(), node.importance_score.into()),
+    ]))
+}
+
+fn edge_row(edge: &KnowledgeEdge) -> Result<HashMap<String, BoltType>> {
+    let metadata: BTreeMap<&String, &String> = edge.metadata.iter().collect();
+    Ok(HashMap::from([
+        ("from".to_string(), edge.from.to_string().into()),
+        ("to".to_string(), edge.to.to_string().into()),
+        ("detail".to_string(), edge.relation_type.detail().unwrap_or("").to_string().into()),
+        ("weight".to_string(), edge.weight.into()),
+        ("confidence".to_string(), edge.confidence.into()),
+        ("metadata".to_string(), serde_json::to_string(&metadata)?.into()),
+        ("created_at".to_string(), edge.created_at.to_rfc3339().into()),
+    ]))
+}
+
+fn row_to_node(row: &Row) -> Option<KnowledgeNode> {
+    let id: String = row.get("id").ok()?;
+    let properties: String = row.get("properties").unwrap_or_default();
+    Some(KnowledgeNode {
+        material_id: Uuid::parse_str(&id).ok()?,
+        formula: row.get("formula").unwrap_or_default(),
+        properties: serde_json::from_str(&properties).unwrap_or_default(),
+        tags: row.get("tags").unwrap_or_default(),
+        importance_score: row.get("importance_score").unwrap_or(0.0),
+    })
+}
+
+fn row_to_edge(row: &Row) -> Option<KnowledgeEdge> {
+    let from: String = row.get("from").ok()?;
+    let to: String = row.get("to").ok()?;
+    let label: String = row.get("type").ok()?;
+    let detail: String = row.get("detail").unwrap_or_default();
+    let metadata: String = row.get("metadata").unwrap_or_default();
+    let created_at: String = row.get("created_at").unwrap_or_default();
+    Some(KnowledgeEdge {
+        from: Uuid::parse_str(&from).ok()?,
+        to: Uuid::parse_str(&to).ok()?,
+        relation_type: RelationType::from_label(&label, Some(detail.as_str()).filter(|d| !d.is_empty()))?,
+        weight: row.get("weight").unwrap_or(1.0),
+        confidence: row.get("confidence").unwrap_or(1.0),
+        metadata: serde_json::from_str(&metadata).unwrap_or_default(),
+        created_at: DateTime::parse_from_rfc3339(&created_at).map_or_else(|_| Utc::now(), |t| t.with_timezone(&Utc)),
+    })
+}
+
+const NODE_COLUMNS: &str =
+    "m.id AS id, m.formula AS formula, m.properties AS properties, m.tags AS tags, m.importance_score AS importance_score";
+
+const EDGE_COLUMNS: &str = "a.id AS from, b.id AS to, type(r) AS type, coalesce(r.detail, '') AS detail, \
+     r.weight AS weight, r.confidence AS confidence, r.metadata AS metadata, r.created_at AS created_at";
+
+impl Neo4jDatabase {
+    async fn collect_rows<T>(&self, q: neo4rs::Query, context: &str, parse: impl Fn(&Row) -> Option<T>) -> Result<Vec<T>> {
+        let mut result = self.graph
+            .execute(q)
+            .await
+            .map_err(|e| Error::neo4j(format!("Failed to {}: {}", context, e)))?;
+        let mut items = Vec::new();
+        while let Some(row) = result.next().await.map_err(|e| Error::neo4j(format!("Failed to {}: {}", context, e)))? {
+            items.extend(parse(&row));
+        }
+        Ok(items)
+    }
+
+    /// Edges whose endpoints are both in `ids`
+    async fn edges_among(&self, ids: &[String], relations: Option<&[RelationType]>) -> Result<Vec<KnowledgeEdge>> {
+        let q = query(&format!(
+            "MATCH (a:Material)-[r{}]->(b:Material)
+             WHERE a.id IN $ids AND b.id IN $ids AND {}
+             RETURN {}",
+            relation_pattern(relations), edge_condition(relations), EDGE_COLUMNS
+        ))
+        .param("ids", ids.to_vec())
+        .param("pairs", relation_pairs(relations));
+        self.collect_rows(q, "load knowledge edges", row_to_edge).await
+    }
+
+    /// Run a Graph Data Science algorithm over a temporary undirected,
+    /// weighted projection of the knowledge graph
+    ///
+    /// Only the knowledge relationship types are projected (not
+    /// `SIMILAR_TO`), each with `weight` defaulting to 1. GDS rejects types
+    /// absent from the database, so the projection lists those present.
+    async fn gds_stream<T>(&self, procedure: &str, column: &str, parse: impl Fn(&Row) -> Option<T>) -> Result<Vec<T>> {
+        let present = self.collect_rows(
+            query("CALL db.relationshipTypes() YIELD relationshipType RETURN relationshipType"),
+            "list relationship types",
+            |row| row.get::<String>("relationshipType").ok(),
+        ).await?;
+        let projections: Vec<String> = RelationType::LABELS
+            .iter()
+            .filter(|label| present.iter().any(|p| p == *label))
+            .map(|label| format!(
+                "{0}: {{type: '{0}', orientation: 'UNDIRECTED', properties: {{weight: {{property: 'weight', defaultValue: 1.0}}}}}}",
+                label
+            ))
+            .collect();
+        if projections.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let name = format!("knowledge-{}", Uuid::new_v4());
+        self.graph
+            .run(query(&format!(
+                "CALL gds.graph.project($name, 'Material', {{{}}})",
+                projections.join(", ")
+            )).param("name", name.clone()))
+            .await
+            .map_err(|e| Error::neo4j(format!("Failed to project knowledge graph (is GDS installed?): {}", e)))?;
+
+        let q = query(&format!(
+            "CALL gds.{}.stream($name, {{relationshipWeightProperty: 'weight'}})
+             YIELD nodeId, {}
+             RETURN gds.util.asNode(nodeId).id AS id, {} AS value",
+            procedure, column, column
+        ))
+        .param("name", name.clone());
+        let rows = self.collect_rows(q, procedure, parse).await;
+
+        // Drop the projection even when the algorithm failed
+        let _ = self.graph.run(query("CALL gds.graph.drop($name, false)").param("name", name)).await;
+        rows
+    }
+}
+
+#[async_trait::async_trait]
+impl GraphStore for Neo4jDatabase {
+    async fn upsert_nodes(&self, nodes: &[KnowledgeNode]) -> Result<()> {
+        let rows = nodes.iter().map(node_row).collect::<Result<Vec<_>>>()?;
+        let q = query(
+            "UNWIND $rows AS row
+             MERGE (m:Material {id: row.id})
+             ON CREATE SET m.created_at = datetime()
+             SET m.formula = row.formula,
+                 m.properties = row.properties,
+                 m.tags = row.tags,
+                 m.importance_score = row.importance_score,
+                 m.updated_at = datetime()"
+        )
+        .param("rows", rows);
+
+        self.graph
+            .run(q)
+            .await
+            .map_err(|e| Error::neo4j(format!("Failed to upsert knowledge nodes: {}", e)))
+    }
+
+    async fn upsert_edges(&self, edges: &[KnowledgeEdge]) -> Result<()> {
+        // One statement per relationship type
+        let mut by_label: BTreeMap<&str, Vec<HashMap<String, BoltType>>> = BTreeMap::new();
+        for edge in edges {
+            by_label.entry(edge.relation_type.label()).or_default().push(edge_row(edge)?);
+        }
+
+        for (label, rows) in by_label {
+            let q = query(&format!(
+                "UNWIND $rows AS row
+                 MATCH (a:Material {{id: row.from}}), (b:Material {{id: row.to}})
+                 MERGE (a)-[r:{} {{detail: row.detail}}]->(b)
+                 SET r.weight = row.weight,
+                     r.confidence = row.confidence,
+                     r.metadata = row.metadata,
+                     r.created_at = row.created_at",
+                label
+            ))
+            .param("rows", rows);
+
+            self.graph
+                .run(q)
+                .await
+                .map_err(|e| Error::neo4j(format!("Failed to upsert {} edges: {}", label, e)))?;
+        }
+        Ok(())
+    }
+
+    async fn load_subgraph(&self, seeds: &[Uuid], depth: usize, relations: Option<&[RelationType]>) -> Result<Subgraph> {
+        // Breadth-first over frontiers: one query per hop, each node expanded
+        // once, instead of enumerating every path up to `depth`
+        let mut reached: HashSet<String> = seeds.iter().map(Uuid::to_string).collect();
+        let mut frontier: Vec<String> = reached.iter().cloned().collect();
+        for _ in 0..depth {
+            if frontier.is_empty() {
+                break;
+            }
+            let q = query(&format!(
+                "MATCH (a:Material)-[r{}]-(b:Material)
+                 WHERE a.id IN $frontier AND {}
+                 RETURN DISTINCT b.id AS id",
+                relation_pattern(relations), edge_condition(relations)
+            ))
+            .param("frontier", std::mem::take(&mut frontier))
+            .param("pairs", relation_pairs(relations));
+            let neighbours = self.collect_rows(q, "expand knowledge subgraph", |row| row.get::<String>("id").ok()).await?;
+            frontier = neighbours.into_iter().filter(|id| reached.insert(id.clone())).collect();
+        }
+
+        let q = query(&format!("MATCH (m:Material) WHERE m.id IN $ids RETURN {}", NODE_COLUMNS))
+            .param("ids", reached.into_iter().collect::<Vec<_>>());
+        let nodes = self.collect_rows(q, "load knowledge subgraph", row_to_node).await?;
+
+        let ids: Vec<String> = nodes.iter().map(|n| n.material_id.to_string()).collect();
+        let edges = self.edges_among(&ids, relations).await?;
+        Ok(Subgraph { nodes, edges })
+    }
+
+    async fn load_all(&self) -> Result<Subgraph> {
+        let q = query(&format!("MATCH (m:Material) RETURN {}", NODE_COLUMNS));
+        let nodes = self.collect_rows(q, "load knowledge nodes", row_to_node).await?;
+        let q = query(&format!("MATCH (a:Material)-[r{}]->(b:Material) RETURN {}", relation_pattern(None), EDGE_COLUMNS));
+        let edges = self.collect_rows(q, "load knowledge edges", row_to_edge).await?;
+        Ok(Subgraph { nodes, edges })
+    }
+
+    async fn shortest_path(
+        &self,
+        from: Uuid,
+        to: Uuid,
+        max_hops: usize,
+        relations: Option<&[RelationType]>,
+    ) -> Result<Option<Vec<Uuid>>> {
+        if from == to {
+            return Ok(Some(vec![from]));
+        }
+        let q = query(&format!(
+            "MATCH (a:Material {{id: $from}}), (b:Material {{id: $to}})
+             MATCH p = shortestPath((a)-[{}*..{}]-(b))
+             WHERE {}
+             RETURN [n IN nodes(p) | n.id] AS ids",
+            relation_pattern(relations), max_hops.max(1), relation_condition(relations)
+        ))
+        .param("from", from.to_string())
+        .param("to", to.to_string())
+        .param("pairs", relation_pairs(relations));
+
+        let paths = self.collect_rows(q, "find shortest path", |row| {
+            let ids: Vec<String> = row.get("ids").ok()?;
+            ids.iter().map(|id| Uuid::parse_str(id).ok()).collect::<Option<Vec<Uuid>>>()
+        }).await?;
+        Ok(paths.into_iter().next())
+    }
+
+    async fn communities(&self) -> Result<Vec<Vec<Uuid>>> {
+        let members = self.gds_stream("louvain", "communityId", |row| {
+            let id: String = row.get("id").ok()?;
+            Some((Uuid::parse_str(&id).ok()?, row.get::<i64>("value").ok()?))
+        }).await?;
+
+        let mut communities: BTreeMap<i64, Vec<Uuid>> = BTreeMap::new();
+        for (id, community) in members {
+            communities.entry(community).or_default().push(id);
+        }
+        Ok(communities.into_values().collect())
+    }
+
+    async fn centrality(&self) -> Result<HashMap<Uuid, f64>> {
+        let scores = self.gds_stream("pageRank", "score", |row| {
+            let id: String = row.get("id").ok()?;
+            Some((Uuid::parse_str(&id).ok()?, row.get::<f64>("value").ok()?))
+        }).await?;
+        Ok(scores.into_iter().collect())
+    }
+}
+
 #[async_trait::async_trait]
 impl MaterialDatabase for Neo4jDatabase {
     async fn create_material(&self, material: &Material) -> Result<Uuid> {
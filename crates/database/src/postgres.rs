@@ -1,8 +1,11 @@
 //! PostgreSQL database implementation with SQLx
 
+use crate::interaction_store::InteractionStore;
 use crate::{Error, MaterialDatabase, Result};
+use chrono::{DateTime, Utc};
+use materials_core::recommendations::{InteractionRecord, InteractionType};
 use materials_core::Material;
-use sqlx::{postgres::PgPoolOptions, PgPool, Row};
+use sqlx::{postgres::{PgPoolOptions, PgRow}, PgPool, Row};
 use std::time::Duration;
 use uuid::Uuid;
 
@@ -184,6 +187,92 @@ impl MaterialDatabase for PostgresDatabase {
     }
 }
 
+/// Stored rating: the value of a `Rated` interaction, else the record's own
+fn stored_rating(record: &InteractionRecord) -> Option<f64> {
+    match record.interaction_type {
+        InteractionType::Rated(rating) => Some(rating),
+        _ => record.rating,
+    }
+}
+
+fn row_to_interaction(row: &PgRow) -> Result<InteractionRecord> {
+    let kind: String = row.try_get("interaction_type")?;
+    let rating: Option<f64> = row.try_get("rating")?;
+    Ok(InteractionRecord {
+        user_id: row.try_get("user_id")?,
+        material_id: row.try_get("material_id")?,
+        interaction_type: InteractionType::parse(&kind, rating).map_err(Error::Other)?,
+        timestamp: row.try_get("created_at")?,
+        rating,
+    })
+}
+
+#[async_trait::async_trait]
+impl InteractionStore for PostgresDatabase {
+    async fn append(&self, records: &[InteractionRecord]) -> Result<usize> {
+        if records.is_empty() {
+            return Ok(0);
+        }
+        let user_ids: Vec<Uuid> = records.iter().map(|r| r.user_id).collect();
+        let material_ids: Vec<Uuid> = records.iter().map(|r| r.material_id).collect();
+        let kinds: Vec<String> = records.iter().map(|r| r.interaction_type.name().to_string()).collect();
+        let ratings: Vec<Option<f64>> = records.iter().map(stored_rating).collect();
+        let timestamps: Vec<DateTime<Utc>> = records.iter().map(|r| r.timestamp).collect();
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO material_interactions (user_id, material_id, interaction_type, rating, created_at)
+            SELECT * FROM UNNEST($1::uuid[], $2::uuid[], $3::text[], $4::float8[], $5::timestamptz[])
+            ON CONFLICT (user_id, material_id, interaction_type, created_at) DO NOTHING
+            "#,
+        )
+        .bind(user_ids)
+        .bind(material_ids)
+        .bind(kinds)
+        .bind(ratings)
+        .bind(timestamps)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::postgres(format!("Failed to append interactions: {}", e)))?;
+
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn load(&self, since: Option<DateTime<Utc>>) -> Result<Vec<InteractionRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT user_id, material_id, interaction_type, rating, created_at
+            FROM material_interactions
+            WHERE $1::timestamptz IS NULL OR created_at > $1
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::postgres(format!("Failed to load interactions: {}", e)))?;
+
+        rows.iter().map(row_to_interaction).collect()
+    }
+
+    async fn load_user(&self, user_id: Uuid) -> Result<Vec<InteractionRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT user_id, material_id, interaction_type, rating, created_at
+            FROM material_interactions
+            WHERE user_id = $1
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::postgres(format!("Failed to load user interactions: {}", e)))?;
+
+        rows.iter().map(row_to_interaction).collect()
+    }
+}
+
 impl From<sqlx::Error> for Error {
     fn from(err: sqlx::Error) -> Self {
         Error::Postgres(err.to_string())
@@ -63,6 +63,20 @@ CREATE TABLE IF NOT EXISTS audit_logs (
 CREATE INDEX IF NOT EXISTS idx_audit_logs_user_id ON audit_logs(user_id);
 CREATE INDEX IF NOT EXISTS idx_audit_logs_created_at ON audit_logs(created_at);
 
+-- Recommendation interactions (append-only)
+CREATE TABLE IF NOT EXISTS material_interactions (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL,
+    material_id UUID NOT NULL,
+    interaction_type VARCHAR(20) NOT NULL,
+    rating DOUBLE PRECISION,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    UNIQUE (user_id, material_id, interaction_type, created_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_material_interactions_user_id ON material_interactions(user_id);
+CREATE INDEX IF NOT EXISTS idx_material_interactions_created_at ON material_interactions(created_at);
+
 -- Updated_at trigger function
 CREATE OR REPLACE FUNCTION update_updated_at_column()
 RETURNS TRIGGER AS $$